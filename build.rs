@@ -1,6 +1,105 @@
 fn main() {
     set_git_revision_hash();
     set_windows_exe_options();
+    embed_template_variants();
+}
+
+/// Pack every directory below `templates/` into a gzipped tarball in
+/// `OUT_DIR`, and write the table of variants `src/template.rs` includes as
+/// `template_variants.rs`.
+///
+/// A variant is a template in the format of user templates: a
+/// `template.json` next to the files it lists. Files in `templates/_shared`
+/// are packed into every variant whose `template.json` mentions them and
+/// that has no copy of its own, so variants can share them without each
+/// archive carrying all of them.
+///
+/// `templates` is always printed for `rerun-if-changed`: the Windows
+/// manifest prints its own path, and once any path is printed cargo only
+/// reruns the script when a printed path changes.
+fn embed_template_variants() {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    const TEMPLATES: &str = "templates";
+    const SHARED: &str = "_shared";
+
+    println!("cargo:rerun-if-changed={TEMPLATES}");
+    println!("cargo:rerun-if-changed=build.rs");
+
+    /// Files below `dir`, relative to it and sorted
+    fn files_below(dir: &Path, prefix: &Path, files: &mut Vec<PathBuf>) {
+        let Ok(entries) = fs::read_dir(dir.join(prefix)) else {
+            return;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = prefix.join(entry.file_name());
+            if entry.path().is_dir() {
+                files_below(dir, &path, files);
+            } else {
+                files.push(path);
+            }
+        }
+        files.sort();
+    }
+
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").expect("cargo sets OUT_DIR"));
+    let shared_dir = Path::new(TEMPLATES).join(SHARED);
+    let mut shared = Vec::new();
+    files_below(&shared_dir, Path::new(""), &mut shared);
+
+    let mut variants: Vec<String> = fs::read_dir(TEMPLATES)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| !name.starts_with('_'))
+        .collect();
+    variants.sort();
+
+    let mut table = String::from("&[\n");
+    for variant in &variants {
+        let dir = Path::new(TEMPLATES).join(variant);
+        let manifest = fs::read_to_string(dir.join("template.json")).unwrap_or_default();
+        let mut own = Vec::new();
+        files_below(&dir, Path::new(""), &mut own);
+        let mentioned = |path: &Path| {
+            let path = path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            manifest.contains(&format!("\"{path}\""))
+        };
+        let sources = own.iter().map(|path| (dir.join(path), path)).chain(
+            shared
+                .iter()
+                .filter(|path| !own.contains(path) && mentioned(path))
+                .map(|path| (shared_dir.join(path), path)),
+        );
+
+        let archive = out_dir.join(format!("{variant}.tar.gz"));
+        let file = fs::File::create(&archive).expect("create the template archive");
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::best());
+        let mut builder = tar::Builder::new(encoder);
+        builder.mode(tar::HeaderMode::Deterministic);
+        for (source, path) in sources {
+            builder
+                .append_path_with_name(&source, path)
+                .unwrap_or_else(|e| panic!("pack {}: {e}", source.display()));
+        }
+        builder
+            .into_inner()
+            .and_then(|encoder| encoder.finish())
+            .expect("write the template archive");
+
+        table.push_str(&format!(
+            "    ({variant:?}, include_bytes!(concat!(env!(\"OUT_DIR\"), \"/{variant}.tar.gz\"))),\n"
+        ));
+    }
+    table.push(']');
+    fs::write(out_dir.join("template_variants.rs"), table).expect("write the variant table");
 }
 
 /// Embed a Windows manifest and set some linker options.
@@ -0,0 +1,784 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::CacheConfig;
+use crate::{progress, utils, Result};
+
+/// Default cache directory, relative to the project root
+pub const CACHE_DIR: &str = ".cargonode/cache";
+
+/// Get the cache directory of the project at `project_dir`
+///
+/// This is [`CACHE_DIR`] unless `CARGONODE_HOME` relocates it.
+pub fn cache_dir(project_dir: &Path) -> PathBuf {
+    utils::state_dir(project_dir).join("cache")
+}
+
+/// Result of a tool run, keyed by tool name and input hash
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    /// Name of the tool
+    pub tool: String,
+
+    /// Hash of the tool's cache key
+    pub input_hash: String,
+
+    /// Exit code of the command
+    pub exit_code: i32,
+
+    /// Seconds since the Unix epoch at which the entry was stored
+    pub timestamp: u64,
+
+    /// Output files produced by the run
+    #[serde(default)]
+    pub outputs: Vec<OutputRecord>,
+
+    /// Seconds after which the entry expires, if it expires at all
+    #[serde(default)]
+    pub max_age_seconds: Option<u64>,
+
+    /// What `input_hash` was computed from; missing in older entries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<CacheKey>,
+}
+
+/// Everything a tool's cache key is computed from
+///
+/// A cached run is only reused when all of it is unchanged: the input files,
+/// the command lines, the environment the tool sets, and the versions of
+/// Node.js and of the program the tool runs.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheKey {
+    /// Hash of the content of the tool's input files
+    pub inputs: String,
+
+    /// Each command the tool runs, as the program followed by its arguments
+    pub commands: Vec<Vec<String>>,
+
+    /// `NAME=value` for each variable the tool or its steps set, sorted
+    #[serde(default)]
+    pub env: Vec<String>,
+
+    /// Version of the installed Node.js
+    #[serde(default)]
+    pub node_version: Option<String>,
+
+    /// First line of `<program> --version` for the tool's first command
+    #[serde(default)]
+    pub tool_version: Option<String>,
+}
+
+impl CacheKey {
+    /// Hash all components into the key entries are stored under
+    pub fn hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.inputs.as_bytes());
+        hasher.update(b"\0");
+        for (index, command) in self.commands.iter().enumerate() {
+            if index > 0 {
+                hasher.update(b"\n");
+            }
+            for (position, part) in command.iter().enumerate() {
+                if position > 0 {
+                    hasher.update(b"\0");
+                }
+                hasher.update(part.as_bytes());
+            }
+        }
+        for variable in &self.env {
+            hasher.update(b"\nenv\0");
+            hasher.update(variable.as_bytes());
+        }
+        for (label, version) in [("node", &self.node_version), ("tool", &self.tool_version)] {
+            if let Some(version) = version {
+                hasher.update(format!("\n{}\0", label).as_bytes());
+                hasher.update(version.as_bytes());
+            }
+        }
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+impl CacheEntry {
+    /// Whether the cached run succeeded
+    pub fn is_success(&self) -> bool {
+        self.exit_code == 0
+    }
+
+    /// Explain why the entry can no longer be used, if it cannot
+    ///
+    /// # Arguments
+    ///
+    /// * `project_dir` - Directory the recorded output paths are relative to
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - Why the entry is stale, or `None` if it is valid
+    pub fn stale_reason(&self, project_dir: &Path) -> Option<String> {
+        if let Some(max_age) = self.max_age_seconds {
+            let age = utils::unix_timestamp().saturating_sub(self.timestamp);
+            if age > max_age {
+                return Some(format!("entry is {}s old (max age {}s)", age, max_age));
+            }
+        }
+
+        self.outputs.iter().find_map(|output| {
+            let path = project_dir.join(&output.path);
+            if !path.is_file() {
+                return Some(format!("output '{}' is missing", output.path));
+            }
+            match hash_file(&path) {
+                Ok(hash) if hash == output.hash => None,
+                _ => Some(format!("output '{}' has changed", output.path)),
+            }
+        })
+    }
+}
+
+/// An output file recorded with a cache entry
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OutputRecord {
+    /// Path relative to the project directory
+    pub path: String,
+
+    /// SHA-256 of the file content
+    pub hash: String,
+}
+
+impl OutputRecord {
+    /// Record the current state of an output file
+    ///
+    /// # Arguments
+    ///
+    /// * `project_dir` - Directory the recorded path is made relative to
+    /// * `path` - Path of the output file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The record
+    pub fn capture(project_dir: &Path, path: &Path) -> Result<Self> {
+        let relative = path.strip_prefix(project_dir).unwrap_or(path);
+        Ok(Self {
+            path: relative.to_string_lossy().replace('\\', "/"),
+            hash: hash_file(path)?,
+        })
+    }
+}
+
+/// Outcome of looking up a cache entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lookup {
+    /// A valid entry exists
+    Hit(CacheEntry),
+
+    /// No entry exists
+    Miss,
+
+    /// An entry exists but can no longer be used, for the given reason
+    Stale(String),
+}
+
+/// Entries removed by [`Cache::prune`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneStats {
+    /// Number of entries removed
+    pub removed: usize,
+
+    /// Bytes freed on disk
+    pub freed_bytes: u64,
+}
+
+/// Entries one tool has in the cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ToolCacheStats {
+    /// Number of entries
+    pub entries: usize,
+
+    /// Size of the entries on disk, in bytes
+    pub bytes: u64,
+}
+
+/// What the cache holds, as reported by [`Cache::stats`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    /// Number of entries
+    pub entries: usize,
+
+    /// Size of all entries on disk, in bytes
+    pub total_bytes: u64,
+
+    /// Seconds since the Unix epoch at which the oldest entry was written
+    pub oldest: Option<u64>,
+
+    /// Seconds since the Unix epoch at which the newest entry was written
+    pub newest: Option<u64>,
+
+    /// Entries per tool, keyed by the tool name used in entry file names
+    pub tools: BTreeMap<String, ToolCacheStats>,
+}
+
+/// On-disk cache of tool runs
+pub struct Cache {
+    /// Directory holding one JSON file per entry
+    dir: PathBuf,
+
+    /// Age after which any entry expires
+    ttl: Duration,
+}
+
+impl Cache {
+    /// Create a cache stored in `dir`
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory holding the cache entries
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new Cache instance
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            ttl: CacheConfig::default().ttl(),
+        }
+    }
+
+    /// Expire entries older than `ttl` instead of the default 30 days
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    fn is_expired(&self, entry: &CacheEntry) -> bool {
+        utils::unix_timestamp().saturating_sub(entry.timestamp) > self.ttl.as_secs()
+    }
+
+    fn entry_path(&self, tool: &str, input_hash: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}-{}.json", sanitize(tool), input_hash))
+    }
+
+    /// Look up the entry for a tool and input hash
+    ///
+    /// # Arguments
+    ///
+    /// * `tool` - Name of the tool
+    /// * `input_hash` - Hash of the tool's inputs
+    /// * `project_dir` - Directory the entry's output paths are relative to
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Lookup>` - The entry if it is still valid, or why it is not;
+    ///   an entry that cannot be read or parsed is removed with a warning and
+    ///   reported as a miss
+    pub fn lookup(&self, tool: &str, input_hash: &str, project_dir: &Path) -> Result<Lookup> {
+        let path = self.entry_path(tool, input_hash);
+        if !path.exists() {
+            return Ok(Lookup::Miss);
+        }
+
+        let entry = match read_entry(&path) {
+            Ok(entry) => entry,
+            Err(e) => {
+                // A truncated entry must not fail the run it was meant to speed up
+                progress::warn(&format!(
+                    "removing corrupt cache entry {}: {}",
+                    path.display(),
+                    e
+                ))?;
+                if let Err(e) = fs::remove_file(&path) {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        return Err(e.into());
+                    }
+                }
+                return Ok(Lookup::Miss);
+            }
+        };
+        if self.is_expired(&entry) {
+            // Expired entries can never become valid again
+            fs::remove_file(path)?;
+            return Ok(Lookup::Stale(format!(
+                "entry is older than the cache TTL of {} days",
+                self.ttl.as_secs() / (24 * 60 * 60)
+            )));
+        }
+
+        Ok(match entry.stale_reason(project_dir) {
+            Some(reason) => Lookup::Stale(reason),
+            None => Lookup::Hit(entry),
+        })
+    }
+
+    /// Get the entry for a tool and input hash
+    ///
+    /// Expired entries, corrupt entries and entries whose outputs were
+    /// removed or modified are treated as missing.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<CacheEntry>>` - The entry, if a valid one was stored
+    pub fn get_entry(
+        &self,
+        tool: &str,
+        input_hash: &str,
+        project_dir: &Path,
+    ) -> Result<Option<CacheEntry>> {
+        match self.lookup(tool, input_hash, project_dir)? {
+            Lookup::Hit(entry) => Ok(Some(entry)),
+            Lookup::Miss | Lookup::Stale(_) => Ok(None),
+        }
+    }
+
+    /// Read the entry stored for a tool and input hash as it is, without
+    /// checking whether it is still valid
+    ///
+    /// # Returns
+    ///
+    /// * `Option<CacheEntry>` - The entry, if one can be read
+    pub fn stored_entry(&self, tool: &str, input_hash: &str) -> Option<CacheEntry> {
+        read_entry(&self.entry_path(tool, input_hash)).ok()
+    }
+
+    /// Whether a valid entry exists for a tool and input hash
+    pub fn has_entry(&self, tool: &str, input_hash: &str, project_dir: &Path) -> bool {
+        matches!(
+            self.lookup(tool, input_hash, project_dir),
+            Ok(Lookup::Hit(_))
+        )
+    }
+
+    /// Store an entry, replacing any previous entry for the same key
+    ///
+    /// The entry is written atomically, so an interrupted write never leaves
+    /// a partial entry behind.
+    pub fn store_entry(&self, entry: &CacheEntry) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let content = serde_json::to_string_pretty(entry)?;
+        utils::write_atomic(&self.entry_path(&entry.tool, &entry.input_hash), content)
+    }
+
+    /// Remove cached entries
+    ///
+    /// # Arguments
+    ///
+    /// * `tool` - Only remove entries for this tool, if given
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - Number of entries removed
+    pub fn clear(&self, tool: Option<&str>) -> Result<usize> {
+        if !self.dir.exists() {
+            return Ok(0);
+        }
+
+        let prefix = tool.map(|tool| format!("{}-", sanitize(tool)));
+        let mut removed = 0;
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !file_name.ends_with(".json") {
+                continue;
+            }
+            if let Some(prefix) = &prefix {
+                // Entry names are `<tool>-<hex hash>.json`, so match the full tool name
+                let Some(rest) = file_name.strip_prefix(prefix.as_str()) else {
+                    continue;
+                };
+                if rest.contains('-') {
+                    continue;
+                }
+            }
+
+            fs::remove_file(path)?;
+            removed += 1;
+        }
+
+        Ok(removed)
+    }
+
+    /// Remove expired entries, then the oldest entries until the cache fits the budget
+    ///
+    /// Entry files that cannot be read are removed as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `budget` - Maximum number of entries and total size to keep
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PruneStats>` - How many entries were removed and the space freed
+    pub fn prune(&self, budget: &CacheConfig) -> Result<PruneStats> {
+        let mut stats = PruneStats::default();
+        if !self.dir.exists() {
+            return Ok(stats);
+        }
+
+        let mut remove = |path: &Path, size: u64| -> Result<()> {
+            fs::remove_file(path)?;
+            stats.removed += 1;
+            stats.freed_bytes += size;
+            Ok(())
+        };
+
+        let mut kept = Vec::new();
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            let size = fs::metadata(&path)?.len();
+            let entry = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<CacheEntry>(&content).ok());
+            match entry {
+                Some(entry) if !self.is_expired(&entry) => kept.push((entry.timestamp, size, path)),
+                _ => remove(&path, size)?,
+            }
+        }
+
+        // Newest first, so the oldest entries are popped off the end
+        kept.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| b.2.cmp(&a.2)));
+        let mut total_bytes: u64 = kept.iter().map(|(_, size, _)| size).sum();
+        while let Some((_, size, path)) = kept.last() {
+            let too_many = budget.max_entries.is_some_and(|max| kept.len() > max);
+            let too_big = budget.max_bytes.is_some_and(|max| total_bytes > max);
+            if !too_many && !too_big {
+                break;
+            }
+            remove(path, *size)?;
+            total_bytes -= size;
+            kept.pop();
+        }
+
+        Ok(stats)
+    }
+
+    /// Count the entries per tool, with their size and age
+    ///
+    /// Only file names and metadata are read, so large caches are scanned
+    /// without parsing any entry.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<CacheStats>` - Totals for the whole cache and for each tool
+    pub fn stats(&self) -> Result<CacheStats> {
+        let mut stats = CacheStats::default();
+        if !self.dir.exists() {
+            return Ok(stats);
+        }
+
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let dir_entry = dir_entry?;
+            let file_name = dir_entry.file_name();
+            // Entry names are `<tool>-<hex hash>.json`
+            let Some((tool, _)) = file_name
+                .to_str()
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|name| name.rsplit_once('-'))
+            else {
+                continue;
+            };
+            let metadata = dir_entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            let written = metadata
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            stats.oldest = Some(stats.oldest.map_or(written, |oldest| oldest.min(written)));
+            stats.newest = Some(stats.newest.map_or(written, |newest| newest.max(written)));
+            stats.entries += 1;
+            stats.total_bytes += metadata.len();
+            let tool = stats.tools.entry(tool.to_string()).or_default();
+            tool.entries += 1;
+            tool.bytes += metadata.len();
+        }
+
+        Ok(stats)
+    }
+}
+
+/// Read and parse an entry file
+fn read_entry(path: &Path) -> Result<CacheEntry> {
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// SHA-256 of a file's content
+fn hash_file(path: &Path) -> Result<String> {
+    let content = fs::read(path)?;
+    Ok(format!("{:x}", Sha256::digest(&content)))
+}
+
+/// Make a tool name safe to use in a file name
+pub(crate) fn sanitize(tool: &str) -> String {
+    tool.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::progress::{Level, RecordingSink};
+
+    fn entry(tool: &str, input_hash: &str, exit_code: i32) -> CacheEntry {
+        CacheEntry {
+            tool: tool.to_string(),
+            input_hash: input_hash.to_string(),
+            exit_code,
+            timestamp: utils::unix_timestamp(),
+            outputs: Vec::new(),
+            max_age_seconds: None,
+            key: None,
+        }
+    }
+
+    #[test]
+    fn test_store_and_get_entry() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project_dir = temp_dir.path();
+        let cache = Cache::new(&project_dir.join("cache"));
+
+        assert!(cache.get_entry("build", "abc", project_dir)?.is_none());
+        assert!(!cache.has_entry("build", "abc", project_dir));
+
+        cache.store_entry(&entry("build", "abc", 0))?;
+        let stored = cache.get_entry("build", "abc", project_dir)?.unwrap();
+        assert!(stored.is_success());
+        assert!(cache.has_entry("build", "abc", project_dir));
+        assert!(!cache.has_entry("build", "def", project_dir));
+
+        cache.store_entry(&entry("build", "abc", 2))?;
+        assert!(!cache
+            .get_entry("build", "abc", project_dir)?
+            .unwrap()
+            .is_success());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrupt_entry_is_a_miss() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project_dir = temp_dir.path();
+        let cache_dir = project_dir.join("cache");
+        let cache = Cache::new(&cache_dir);
+        fs::create_dir_all(&cache_dir)?;
+
+        let truncated = cache.entry_path("build", "abc");
+        fs::write(&truncated, r#"{"tool": "build", "input_ha"#)?;
+        let binary = cache.entry_path("test", "abc");
+        fs::write(&binary, [0xff, 0xfe, 0x00])?;
+
+        let sink = Arc::new(RecordingSink::default());
+        progress::with_sink(sink.clone(), || -> Result<()> {
+            assert!(cache.get_entry("build", "abc", project_dir)?.is_none());
+            assert!(!cache.has_entry("test", "abc", project_dir));
+            Ok(())
+        })?;
+        assert!(!truncated.exists());
+        assert!(!binary.exists());
+        let messages = sink.messages();
+        assert_eq!(messages.len(), 2);
+        assert!(messages
+            .iter()
+            .all(|m| m.level == Level::Warn && m.text.contains("removing corrupt cache entry")));
+
+        // The next run stores a fresh entry, leaving no temporary file behind
+        cache.store_entry(&entry("build", "abc", 0))?;
+        assert!(cache.get_entry("build", "abc", project_dir)?.is_some());
+        assert_eq!(fs::read_dir(&cache_dir)?.count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_outputs_invalidate_entry() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project_dir = temp_dir.path();
+        let cache = Cache::new(&project_dir.join("cache"));
+
+        let output = project_dir.join("dist").join("index.js");
+        fs::create_dir_all(output.parent().unwrap())?;
+        fs::write(&output, "built")?;
+
+        let mut stored = entry("build", "abc", 0);
+        stored.outputs = vec![OutputRecord::capture(project_dir, &output)?];
+        assert_eq!(stored.outputs[0].path, "dist/index.js");
+        cache.store_entry(&stored)?;
+        assert!(cache.has_entry("build", "abc", project_dir));
+
+        fs::write(&output, "edited")?;
+        assert_eq!(
+            cache.lookup("build", "abc", project_dir)?,
+            Lookup::Stale("output 'dist/index.js' has changed".to_string())
+        );
+
+        fs::remove_dir_all(project_dir.join("dist"))?;
+        assert_eq!(
+            cache.lookup("build", "abc", project_dir)?,
+            Lookup::Stale("output 'dist/index.js' is missing".to_string())
+        );
+        assert!(cache.get_entry("build", "abc", project_dir)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_age() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project_dir = temp_dir.path();
+        let cache = Cache::new(project_dir);
+
+        let mut stored = entry("build", "abc", 0);
+        stored.max_age_seconds = Some(60);
+        cache.store_entry(&stored)?;
+        assert!(cache.has_entry("build", "abc", project_dir));
+
+        stored.timestamp -= 120;
+        cache.store_entry(&stored)?;
+        assert!(matches!(
+            cache.lookup("build", "abc", project_dir)?,
+            Lookup::Stale(_)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clear() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache = Cache::new(temp_dir.path());
+
+        cache.store_entry(&entry("build", "a1", 0))?;
+        cache.store_entry(&entry("build", "b2", 0))?;
+        cache.store_entry(&entry("build-docs", "c3", 0))?;
+        cache.store_entry(&entry("test", "d4", 0))?;
+
+        assert_eq!(cache.clear(Some("build"))?, 2);
+        assert!(cache.has_entry("build-docs", "c3", temp_dir.path()));
+        assert_eq!(cache.clear(None)?, 2);
+        assert_eq!(cache.clear(None)?, 0);
+
+        Ok(())
+    }
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    #[test]
+    fn test_ttl_expiry() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project_dir = temp_dir.path();
+        let cache = Cache::new(project_dir);
+
+        let mut stored = entry("build", "abc", 0);
+        stored.timestamp -= 29 * DAY;
+        cache.store_entry(&stored)?;
+        assert!(cache.has_entry("build", "abc", project_dir));
+
+        // Expired entries are stale and removed on lookup
+        stored.timestamp -= 2 * DAY;
+        cache.store_entry(&stored)?;
+        assert!(!cache.has_entry("build", "abc", project_dir));
+        assert!(!cache.entry_path("build", "abc").exists());
+
+        // A shorter TTL expires younger entries
+        let cache = cache.with_ttl(Duration::from_secs(DAY));
+        let mut stored = entry("build", "def", 0);
+        stored.timestamp -= 2 * DAY;
+        cache.store_entry(&stored)?;
+        assert!(cache.get_entry("build", "def", project_dir)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project_dir = temp_dir.path();
+        let cache = Cache::new(project_dir);
+
+        // One expired entry and four entries that are a day apart
+        let now = utils::unix_timestamp();
+        for (hash, age) in [("old", 40), ("a", 4), ("b", 3), ("c", 2), ("d", 1)] {
+            let mut stored = entry("build", hash, 0);
+            stored.timestamp = now - age * DAY;
+            cache.store_entry(&stored)?;
+        }
+        fs::write(project_dir.join("broken-1.json"), "{")?;
+
+        let stats = cache.prune(&CacheConfig::default())?;
+        assert_eq!(stats.removed, 2);
+        assert!(stats.freed_bytes > 0);
+
+        let budget = CacheConfig {
+            max_entries: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(cache.prune(&budget)?.removed, 1);
+        assert!(!cache.has_entry("build", "a", project_dir));
+        assert!(cache.has_entry("build", "b", project_dir));
+
+        // Entries are the same size, so a budget of two entries keeps the newest two
+        let size = fs::metadata(cache.entry_path("build", "d"))?.len();
+        let budget = CacheConfig {
+            max_bytes: Some(2 * size),
+            ..Default::default()
+        };
+        assert_eq!(cache.prune(&budget)?.removed, 1);
+        assert!(!cache.has_entry("build", "b", project_dir));
+        assert!(cache.has_entry("build", "c", project_dir));
+        assert!(cache.has_entry("build", "d", project_dir));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache = Cache::new(&temp_dir.path().join("cache"));
+        assert_eq!(cache.stats()?, CacheStats::default());
+
+        for (tool, hash) in [("build", "a1"), ("build", "b2"), ("lint:fix", "c3")] {
+            cache.store_entry(&entry(tool, hash, 0))?;
+        }
+        fs::write(temp_dir.path().join("cache/notes.txt"), "ignored")?;
+
+        let stats = cache.stats()?;
+        assert_eq!(stats.entries, 3);
+        assert_eq!(stats.tools["build"].entries, 2);
+        assert_eq!(stats.tools["lint_fix"].entries, 1);
+        let size = fs::metadata(cache.entry_path("build", "a1"))?.len();
+        assert_eq!(stats.tools["build"].bytes, 2 * size);
+        assert_eq!(
+            stats.total_bytes,
+            stats.tools.values().map(|tool| tool.bytes).sum::<u64>()
+        );
+        assert!(stats.oldest.unwrap() <= stats.newest.unwrap());
+        assert!(stats.newest.unwrap() <= utils::unix_timestamp() + 1);
+
+        Ok(())
+    }
+}
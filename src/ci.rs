@@ -0,0 +1,449 @@
+use serde_json::Value;
+
+use crate::node;
+use crate::package_manager::{PackageManager, PmAction};
+
+/// Path of the workflow generated for GitHub Actions
+pub const GITHUB_WORKFLOW: &str = ".github/workflows/ci.yml";
+
+/// Tools the workflow runs, in order
+const CI_TOOLS: [&str; 3] = ["check", "build", "test"];
+
+/// Script that installs the latest cargonode release
+const CARGONODE_INSTALLER: &str = "curl --proto '=https' --tlsv1.2 -LsSf https://github.com/xosnrdev/cargonode/releases/latest/download/cargonode-installer.sh | sh";
+
+/// CI service a new project gets a workflow for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum CiProvider {
+    /// No workflow (default)
+    #[default]
+    None,
+    /// A GitHub Actions workflow in `.github/workflows/ci.yml`
+    Github,
+}
+
+impl CiProvider {
+    /// Path of the workflow file, relative to the project
+    pub fn workflow_path(&self) -> Option<&'static str> {
+        match self {
+            CiProvider::None => None,
+            CiProvider::Github => Some(GITHUB_WORKFLOW),
+        }
+    }
+}
+
+/// A command the workflow runs after installing dependencies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CiStep {
+    /// `cargonode <tool>`
+    Tool(String),
+    /// A script from package.json, run by the package manager
+    Script(String),
+}
+
+/// Choose what the workflow runs
+///
+/// The check, build and test tools of the cargonode configuration run
+/// through cargonode. A project without them runs its package.json scripts
+/// of the same names instead, and one without either runs `cargonode test`,
+/// whose default is `node --test`.
+///
+/// # Arguments
+///
+/// * `tool_config` - The cargonode configuration, as found under the
+///   `cargonode` key of package.json
+/// * `manifest` - The package.json document
+///
+/// # Returns
+///
+/// * `Vec<CiStep>` - The steps, in order
+pub fn ci_steps(tool_config: Option<&Value>, manifest: &Value) -> Vec<CiStep> {
+    let configured = |section: Option<&Value>, name: &str| {
+        section
+            .and_then(Value::as_object)
+            .is_some_and(|section| section.contains_key(name))
+    };
+    let tools = tool_config.and_then(|config| config.get("tools"));
+    let scripts = manifest.get("scripts");
+
+    let mut steps: Vec<CiStep> = CI_TOOLS
+        .iter()
+        .filter(|tool| configured(tools, tool))
+        .map(|tool| CiStep::Tool(tool.to_string()))
+        .collect();
+    if steps.is_empty() {
+        steps = CI_TOOLS
+            .iter()
+            .filter(|script| configured(scripts, script))
+            .map(|script| CiStep::Script(script.to_string()))
+            .collect();
+    }
+    if steps.is_empty() {
+        steps.push(CiStep::Tool("test".to_string()));
+    }
+    steps
+}
+
+/// One step of a GitHub Actions job
+#[derive(Debug, Default)]
+struct Step {
+    name: Option<String>,
+    uses: Option<&'static str>,
+    with: Vec<(&'static str, String)>,
+    run: Option<String>,
+}
+
+impl Step {
+    fn uses(action: &'static str) -> Self {
+        Self {
+            uses: Some(action),
+            ..Default::default()
+        }
+    }
+
+    fn run(name: &str, command: impl Into<String>) -> Self {
+        Self {
+            name: Some(name.to_string()),
+            run: Some(command.into()),
+            ..Default::default()
+        }
+    }
+
+    fn with(mut self, key: &'static str, value: impl Into<String>) -> Self {
+        self.with.push((key, value.into()));
+        self
+    }
+
+    /// Append the step as an item of a `steps` list indented by `indent`
+    fn render(&self, yaml: &mut String, indent: &str) {
+        let mut fields = Vec::new();
+        fields.extend(self.name.as_ref().map(|name| format!("name: {}", name)));
+        fields.extend(self.uses.map(|uses| format!("uses: {}", uses)));
+        if !self.with.is_empty() {
+            fields.push("with:".to_string());
+            fields.extend(
+                self.with
+                    .iter()
+                    .map(|(key, value)| format!("  {}: {}", key, value)),
+            );
+        }
+        fields.extend(self.run.as_ref().map(|run| format!("run: {}", run)));
+
+        for (i, field) in fields.iter().enumerate() {
+            let marker = if i == 0 { "- " } else { "  " };
+            yaml.push_str(&format!("{}{}{}\n", indent, marker, field));
+        }
+    }
+}
+
+/// A command line from a program and its arguments
+fn command_line((program, args): (&str, Vec<String>)) -> String {
+    std::iter::once(program.to_string())
+        .chain(args)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Generate a GitHub Actions workflow that installs and checks the project
+///
+/// The job sets up the Node.js version of `.nvmrc`, caches the package
+/// manager's store keyed on its lockfile, installs exactly what the lockfile
+/// records, then runs `steps`. Without a lockfile, nothing is cached and a
+/// plain install runs instead, as the clean install would fail.
+///
+/// # Arguments
+///
+/// * `package_manager` - Package manager of the project
+/// * `locked` - Whether the project commits a lockfile
+/// * `steps` - What to run after installing, from [`ci_steps`]
+///
+/// # Returns
+///
+/// * `String` - The workflow, as YAML
+pub fn github_workflow(package_manager: PackageManager, locked: bool, steps: &[CiStep]) -> String {
+    let lockfile = package_manager.lockfiles()[0];
+    let mut job = vec![Step::uses("actions/checkout@v4")];
+    // setup-node needs the package manager to find its cache
+    if matches!(
+        package_manager,
+        PackageManager::Pnpm | PackageManager::YarnBerry
+    ) {
+        job.push(Step::run("Enable corepack", "corepack enable"));
+    }
+    let setup_node =
+        Step::uses("actions/setup-node@v4").with("node-version-file", node::VERSION_FILE);
+    match package_manager {
+        PackageManager::Bun => {
+            job.push(setup_node);
+            job.push(Step::uses("oven-sh/setup-bun@v2"));
+            if locked {
+                job.push(
+                    Step::uses("actions/cache@v4")
+                        .with("path", "~/.bun/install/cache")
+                        .with(
+                            "key",
+                            "${{ runner.os }}-bun-${{ hashFiles('bun.lock', 'bun.lockb') }}",
+                        ),
+                );
+            }
+        }
+        // setup-node fails to cache without the lockfile it keys on
+        _ if !locked => job.push(setup_node),
+        _ => job.push(
+            setup_node
+                .with("cache", package_manager.executable())
+                .with("cache-dependency-path", lockfile),
+        ),
+    }
+    let install = if locked {
+        PmAction::CleanInstall
+    } else {
+        PmAction::Install
+    };
+    job.push(Step::run(
+        "Install dependencies",
+        command_line(package_manager.command(install)),
+    ));
+    if steps.iter().any(|step| matches!(step, CiStep::Tool(_))) {
+        job.push(Step::run("Install cargonode", CARGONODE_INSTALLER));
+    }
+    for step in steps {
+        let (name, command) = match step {
+            CiStep::Tool(tool) => (tool, format!("cargonode {}", tool)),
+            CiStep::Script(script) => (
+                script,
+                command_line(package_manager.command(PmAction::RunScript(script.clone()))),
+            ),
+        };
+        let mut title = name.clone();
+        if let Some(first) = title.get_mut(..1) {
+            first.make_ascii_uppercase();
+        }
+        job.push(Step::run(&title, command));
+    }
+
+    let mut yaml = String::from(
+        "name: CI\n\non:\n  push:\n  pull_request:\n\njobs:\n  ci:\n    runs-on: ubuntu-latest\n    steps:\n",
+    );
+    for step in &job {
+        step.render(&mut yaml, "      ");
+    }
+    yaml
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_ci_steps() {
+        let tool = |name: &str| CiStep::Tool(name.to_string());
+        let script = |name: &str| CiStep::Script(name.to_string());
+        let manifest =
+            json!({ "scripts": { "test": "vitest", "build": "tsup", "dev": "tsup --watch" } });
+
+        // Configured tools win, in check, build, test order
+        let config = json!({ "tools": { "test": {}, "check": {}, "deploy": {} } });
+        assert_eq!(
+            ci_steps(Some(&config), &manifest),
+            vec![tool("check"), tool("test")]
+        );
+
+        // Without cargonode tools, the package.json scripts run
+        assert_eq!(
+            ci_steps(None, &manifest),
+            vec![script("build"), script("test")]
+        );
+        assert_eq!(
+            ci_steps(Some(&json!({ "tools": {} })), &manifest),
+            vec![script("build"), script("test")]
+        );
+
+        assert_eq!(ci_steps(None, &json!({})), vec![tool("test")]);
+    }
+
+    #[test]
+    fn test_github_workflow_npm() {
+        let steps = [
+            CiStep::Tool("check".to_string()),
+            CiStep::Tool("test".to_string()),
+        ];
+        assert_eq!(
+            github_workflow(PackageManager::Npm, true, &steps),
+            r#"name: CI
+
+on:
+  push:
+  pull_request:
+
+jobs:
+  ci:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: actions/setup-node@v4
+        with:
+          node-version-file: .nvmrc
+          cache: npm
+          cache-dependency-path: package-lock.json
+      - name: Install dependencies
+        run: npm ci
+      - name: Install cargonode
+        run: curl --proto '=https' --tlsv1.2 -LsSf https://github.com/xosnrdev/cargonode/releases/latest/download/cargonode-installer.sh | sh
+      - name: Check
+        run: cargonode check
+      - name: Test
+        run: cargonode test
+"#
+        );
+    }
+
+    #[test]
+    fn test_github_workflow_without_lockfile() {
+        let steps = [CiStep::Tool("test".to_string())];
+        let workflow = github_workflow(PackageManager::Pnpm, false, &steps);
+        assert!(workflow.contains(
+            "      - uses: actions/setup-node@v4\n        with:\n          node-version-file: .nvmrc\n      - name: Install dependencies\n        run: pnpm install\n"
+        ));
+        assert!(!workflow.contains("cache"));
+
+        let workflow = github_workflow(PackageManager::Bun, false, &steps);
+        assert!(workflow.contains("oven-sh/setup-bun@v2"));
+        assert!(workflow.contains("run: bun install\n"));
+        assert!(!workflow.contains("actions/cache"));
+    }
+
+    #[test]
+    fn test_github_workflow_yarn() {
+        let steps = [CiStep::Script("test".to_string())];
+        assert_eq!(
+            github_workflow(PackageManager::Yarn, true, &steps),
+            r#"name: CI
+
+on:
+  push:
+  pull_request:
+
+jobs:
+  ci:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: actions/setup-node@v4
+        with:
+          node-version-file: .nvmrc
+          cache: yarn
+          cache-dependency-path: yarn.lock
+      - name: Install dependencies
+        run: yarn install --frozen-lockfile
+      - name: Test
+        run: yarn run test
+"#
+        );
+    }
+
+    #[test]
+    fn test_github_workflow_yarn_berry() {
+        let steps = [CiStep::Tool("build".to_string())];
+        assert_eq!(
+            github_workflow(PackageManager::YarnBerry, true, &steps),
+            r#"name: CI
+
+on:
+  push:
+  pull_request:
+
+jobs:
+  ci:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - name: Enable corepack
+        run: corepack enable
+      - uses: actions/setup-node@v4
+        with:
+          node-version-file: .nvmrc
+          cache: yarn
+          cache-dependency-path: yarn.lock
+      - name: Install dependencies
+        run: yarn install --immutable
+      - name: Install cargonode
+        run: curl --proto '=https' --tlsv1.2 -LsSf https://github.com/xosnrdev/cargonode/releases/latest/download/cargonode-installer.sh | sh
+      - name: Build
+        run: cargonode build
+"#
+        );
+    }
+
+    #[test]
+    fn test_github_workflow_pnpm() {
+        let steps = [
+            CiStep::Script("build".to_string()),
+            CiStep::Script("test".to_string()),
+        ];
+        assert_eq!(
+            github_workflow(PackageManager::Pnpm, true, &steps),
+            r#"name: CI
+
+on:
+  push:
+  pull_request:
+
+jobs:
+  ci:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - name: Enable corepack
+        run: corepack enable
+      - uses: actions/setup-node@v4
+        with:
+          node-version-file: .nvmrc
+          cache: pnpm
+          cache-dependency-path: pnpm-lock.yaml
+      - name: Install dependencies
+        run: pnpm install --frozen-lockfile
+      - name: Build
+        run: pnpm run build
+      - name: Test
+        run: pnpm run test
+"#
+        );
+    }
+
+    #[test]
+    fn test_github_workflow_bun() {
+        let steps = [CiStep::Tool("test".to_string())];
+        assert_eq!(
+            github_workflow(PackageManager::Bun, true, &steps),
+            r#"name: CI
+
+on:
+  push:
+  pull_request:
+
+jobs:
+  ci:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: actions/setup-node@v4
+        with:
+          node-version-file: .nvmrc
+      - uses: oven-sh/setup-bun@v2
+      - uses: actions/cache@v4
+        with:
+          path: ~/.bun/install/cache
+          key: ${{ runner.os }}-bun-${{ hashFiles('bun.lock', 'bun.lockb') }}
+      - name: Install dependencies
+        run: bun install --frozen-lockfile
+      - name: Install cargonode
+        run: curl --proto '=https' --tlsv1.2 -LsSf https://github.com/xosnrdev/cargonode/releases/latest/download/cargonode-installer.sh | sh
+      - name: Test
+        run: cargonode test
+"#
+        );
+    }
+}
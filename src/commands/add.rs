@@ -0,0 +1,328 @@
+use std::fs;
+use std::path::Path;
+
+use crate::dependency::{self, DependencyKind, DependencySpec};
+use crate::error::Error;
+use crate::package_manager::PmAction;
+use crate::registry::RegistryClient;
+use crate::{package_manager, progress, suggest, Result};
+
+/// Options for adding dependencies
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AddOptions {
+    /// Section the dependencies are recorded in
+    pub kind: DependencyKind,
+
+    /// Print what would change without writing package.json or installing
+    pub dry_run: bool,
+
+    /// Whether to install the dependencies with the project's package manager
+    pub install: bool,
+}
+
+/// Add dependencies to the package.json of the project containing `current_dir`
+///
+/// # Arguments
+///
+/// * `packages` - Package specs, optionally with a version (e.g. `express@^4.18.0`)
+/// * `options` - How the dependencies are recorded and installed
+/// * `current_dir` - Directory to start looking for the project from
+/// * `client` - Registry client used to resolve versions
+///
+/// # Returns
+///
+/// * `Result<()>` - Whether the dependencies were added
+pub fn add_dependencies(
+    packages: &[String],
+    options: &AddOptions,
+    current_dir: &Path,
+    client: &(impl RegistryClient + Sync),
+) -> Result<()> {
+    let project_root =
+        package_manager::find_project_root(current_dir).ok_or_else(|| Error::Config {
+            message: format!(
+                "package.json not found in {} or any parent directory",
+                current_dir.display()
+            ),
+            hint: Some(suggest::missing_manifest_hint()),
+        })?;
+
+    let specs = packages
+        .iter()
+        .map(|package| DependencySpec::parse(package, options.kind))
+        .collect::<Result<Vec<_>>>()?;
+
+    for spec in &specs {
+        let requirement = spec.version_req.as_deref().unwrap_or("latest");
+        progress::status("Resolving", &format!("{}@{}", spec.name, requirement))?;
+    }
+
+    let mut resolution = dependency::resolve_dependencies(client, &specs)?;
+
+    // A single failed lookup keeps its own error and suggestion
+    if resolution.conflicts.is_empty() && resolution.unresolved.len() == 1 {
+        return Err(resolution.unresolved.remove(0).error);
+    }
+
+    let failures = resolution
+        .conflicts
+        .iter()
+        .map(|conflict| conflict.describe())
+        .chain(resolution.unresolved.iter().map(|u| u.describe()))
+        .collect::<Vec<_>>();
+    if !failures.is_empty() {
+        return Err(Error::Dependency {
+            message: format!(
+                "Failed to resolve {} dependenc{}",
+                failures.len(),
+                if failures.len() == 1 { "y" } else { "ies" }
+            ),
+            details: failures.join("\n"),
+        });
+    }
+
+    let package_json_path = project_root.join("package.json");
+
+    // Inside a workspace the member's manifest is edited, never the root's
+    if let Some(workspace_root) = package_manager::find_workspace_root(&project_root)? {
+        if workspace_root != project_root {
+            let member = package_json_path
+                .strip_prefix(&workspace_root)
+                .unwrap_or(&package_json_path);
+            progress::status(
+                "Updating",
+                &format!("{} (workspace member)", member.display()),
+            )?;
+        }
+    }
+
+    let original = fs::read_to_string(&package_json_path)?;
+    let mut manifest: serde_json::Value = serde_json::from_str(&original)?;
+
+    for resolved in &resolution.resolved {
+        let range = resolved.range();
+        let section = resolved.spec.kind.section();
+        let previous = dependency::insert_dependency(
+            &mut manifest,
+            &resolved.spec.name,
+            &range,
+            resolved.spec.kind,
+        )?;
+
+        let message = match previous {
+            Some(previous) if previous != range => format!(
+                "{} {} -> {} (v{}) in {}",
+                resolved.spec.name, previous, range, resolved.version, section
+            ),
+            _ => format!(
+                "{} {} (v{}) to {}",
+                resolved.spec.name, range, resolved.version, section
+            ),
+        };
+        progress::status("Adding", &message)?;
+    }
+
+    if options.dry_run {
+        progress::warn("aborting add due to dry run")?;
+        return Ok(());
+    }
+
+    dependency::write_manifest(&package_json_path, &manifest, &original)?;
+
+    let package_manager = package_manager::detect_package_manager(&project_root)?;
+    if !options.install {
+        progress::note(&format!(
+            "Run `{}` to install the added dependencies",
+            package_manager.install_command_line()
+        ))?;
+        return Ok(());
+    }
+
+    progress::status(
+        "Installing",
+        &format!("dependencies with {}", package_manager),
+    )?;
+    // Adding what package.json already records only installs it; exact
+    // versions are added apart, so the package manager keeps them exact
+    let (exact, ranged): (Vec<_>, Vec<_>) = resolution
+        .resolved
+        .iter()
+        .map(|resolved| (resolved.spec.name.as_str(), resolved.range()))
+        .partition(|(_, range)| semver::Version::parse(range).is_ok());
+    for (packages, exact) in [(ranged, false), (exact, true)] {
+        if packages.is_empty() {
+            continue;
+        }
+        let packages: Vec<String> = packages
+            .iter()
+            .map(|(name, range)| format!("{}@{}", name, range))
+            .collect();
+        let action = PmAction::Add {
+            kind: options.kind,
+            exact,
+        };
+        package_manager.run(action, &packages, &project_root)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::dependency::tests::MockClient;
+
+    fn client() -> MockClient {
+        MockClient::new()
+            .with_package("express", &["4.17.0", "4.21.2"], "4.21.2")
+            .with_package("@types/node", &["20.1.0", "20.2.0"], "20.2.0")
+    }
+
+    fn options(kind: DependencyKind) -> AddOptions {
+        AddOptions {
+            kind,
+            ..Default::default()
+        }
+    }
+
+    fn read_manifest(dir: &Path) -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(dir.join("package.json")).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_add_dependencies() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        fs::write(dir_path.join("package.json"), r#"{"name": "app"}"#)?;
+
+        let nested = dir_path.join("src");
+        fs::create_dir(&nested)?;
+
+        add_dependencies(
+            &["express".to_string()],
+            &options(DependencyKind::Normal),
+            &nested,
+            &client(),
+        )?;
+        add_dependencies(
+            &["@types/node@^20.1.0".to_string()],
+            &options(DependencyKind::Dev),
+            dir_path,
+            &client(),
+        )?;
+
+        let manifest = read_manifest(dir_path);
+        assert_eq!(manifest["dependencies"]["express"], "^4.21.2");
+        assert_eq!(manifest["devDependencies"]["@types/node"], "^20.1.0");
+
+        // Re-adding updates the existing entry instead of duplicating it
+        add_dependencies(
+            &["express@~4.17.0".to_string()],
+            &options(DependencyKind::Normal),
+            dir_path,
+            &client(),
+        )?;
+        let manifest = read_manifest(dir_path);
+        assert_eq!(manifest["dependencies"]["express"], "~4.17.0");
+        assert_eq!(manifest["dependencies"].as_object().unwrap().len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_dependencies_in_workspace_member() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        let member = root.join("packages").join("ui");
+        fs::create_dir_all(&member)?;
+        let root_manifest = r#"{"name": "root", "workspaces": ["packages/*"]}"#;
+        fs::write(root.join("package.json"), root_manifest)?;
+        fs::write(member.join("package.json"), r#"{"name": "ui"}"#)?;
+
+        add_dependencies(
+            &["@types/node".to_string()],
+            &options(DependencyKind::Peer),
+            &member,
+            &client(),
+        )?;
+
+        assert_eq!(
+            read_manifest(&member)["peerDependencies"]["@types/node"],
+            "^20.2.0"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("package.json"))?,
+            root_manifest
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_dependencies_dry_run() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        fs::write(dir_path.join("package.json"), r#"{"name": "app"}"#)?;
+
+        let options = AddOptions {
+            dry_run: true,
+            install: true,
+            ..Default::default()
+        };
+        add_dependencies(&["@types/node".to_string()], &options, dir_path, &client())?;
+
+        assert_eq!(
+            fs::read_to_string(dir_path.join("package.json"))?,
+            r#"{"name": "app"}"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_unknown_package() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        fs::write(dir_path.join("package.json"), r#"{"name": "app"}"#)?;
+
+        let result = add_dependencies(
+            &["left-pad".to_string()],
+            &options(DependencyKind::Normal),
+            dir_path,
+            &client(),
+        );
+        assert!(matches!(result, Err(Error::PackageNotFound { .. })));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_dependencies_errors() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+
+        // Outside of a Node.js project
+        let result = add_dependencies(
+            &["express".to_string()],
+            &options(DependencyKind::Normal),
+            dir_path,
+            &client(),
+        );
+        assert!(matches!(result, Err(Error::Config { .. })));
+
+        // Unsatisfiable requirement leaves package.json untouched
+        fs::write(dir_path.join("package.json"), r#"{"name": "app"}"#)?;
+        let result = add_dependencies(
+            &["express@^5.0.0".to_string()],
+            &options(DependencyKind::Normal),
+            dir_path,
+            &client(),
+        );
+        assert!(matches!(result, Err(Error::Dependency { .. })));
+        assert!(read_manifest(dir_path).get("dependencies").is_none());
+
+        Ok(())
+    }
+}
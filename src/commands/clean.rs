@@ -0,0 +1,303 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cache::{self, Cache};
+use crate::config::{self, CargonodeConfig};
+use crate::error::Error;
+use crate::journal::{self, Journal};
+use crate::outputs::OutputVerifier;
+use crate::{progress, Result};
+
+/// Options for removing tool outputs
+#[derive(Debug, Clone, Default)]
+pub struct CleanOptions {
+    /// Only remove the outputs of these tools; all configured tools if empty
+    pub tools: Vec<String>,
+
+    /// List what would be removed without removing anything
+    pub dry_run: bool,
+
+    /// Also clear the project's cache and journal
+    pub cache: bool,
+}
+
+/// What `clean` removed, or would remove on a dry run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanReport {
+    /// Removed files, sorted
+    pub files: Vec<PathBuf>,
+
+    /// Total size of the removed files in bytes
+    pub bytes: u64,
+
+    /// Directories removed because they were left empty
+    pub dirs: Vec<PathBuf>,
+}
+
+/// Output patterns of the selected tools, in tool order
+fn output_patterns(config: &CargonodeConfig, tools: &[String]) -> Result<Vec<String>> {
+    let mut names: Vec<&String> = if tools.is_empty() {
+        config.tools.keys().collect()
+    } else {
+        tools.iter().collect()
+    };
+    names.sort();
+    names.dedup();
+
+    let mut patterns = Vec::new();
+    for name in names {
+        let tool_config = config::get_tool_config(config, name)
+            .ok_or_else(|| config::tool_not_found(config, name))?;
+        for pattern in &tool_config.outputs {
+            if !patterns.contains(pattern) {
+                patterns.push(pattern.clone());
+            }
+        }
+    }
+    Ok(patterns)
+}
+
+/// Resolve `file` to where it lives, failing if that is outside `root`
+///
+/// The parent directory is canonicalized rather than the file itself, so a
+/// symlink inside the project is removed as a link instead of following it.
+fn resolve_inside(root: &Path, file: &Path) -> Result<PathBuf> {
+    let outside = || Error::Config {
+        message: format!(
+            "Refusing to remove '{}': it is outside the project root {}",
+            file.display(),
+            root.display()
+        ),
+        hint: None,
+    };
+    let parent = file.parent().ok_or_else(outside)?;
+    let name = file.file_name().ok_or_else(outside)?;
+    let resolved = fs::canonicalize(parent)?.join(name);
+    if !resolved.starts_with(root) {
+        return Err(outside());
+    }
+    Ok(resolved)
+}
+
+/// Remove the directories above `file` that are left empty, up to `root`
+fn remove_empty_parents(root: &Path, file: &Path, removed: &mut Vec<PathBuf>) -> Result<()> {
+    let mut dir = file.parent();
+    while let Some(current) = dir.filter(|dir| *dir != root && dir.starts_with(root)) {
+        if fs::read_dir(current)?.next().is_some() {
+            break;
+        }
+        fs::remove_dir(current)?;
+        removed.push(current.to_path_buf());
+        dir = current.parent();
+    }
+    Ok(())
+}
+
+/// Remove the files matching the `outputs` patterns of the configured tools
+///
+/// Every match is checked to be inside the project root before anything is
+/// removed; directories left empty are removed too. Nothing matching is not
+/// an error.
+///
+/// # Arguments
+///
+/// * `project_dir` - Root of the project
+/// * `config` - The project's configuration
+/// * `options` - Which tools to clean and how
+///
+/// # Returns
+///
+/// * `Result<CleanReport>` - What was removed, or would be on a dry run
+pub fn clean(
+    project_dir: &Path,
+    config: &CargonodeConfig,
+    options: &CleanOptions,
+) -> Result<CleanReport> {
+    let root = fs::canonicalize(project_dir)?;
+    let patterns = output_patterns(config, &options.tools)?;
+    let matches = OutputVerifier::new(project_dir, patterns).find_outputs()?;
+
+    let mut files = matches
+        .iter()
+        .map(|file| resolve_inside(&root, file))
+        .collect::<Result<Vec<_>>>()?;
+    files.sort();
+    files.dedup();
+
+    let mut report = CleanReport::default();
+    for file in files {
+        let bytes = fs::symlink_metadata(&file)?.len();
+        let display = file.strip_prefix(&root).unwrap_or(&file).display();
+        if options.dry_run {
+            progress::status("Would remove", &display.to_string())?;
+        } else {
+            progress::debug(&format!("removing {}", display))?;
+            fs::remove_file(&file)?;
+            remove_empty_parents(&root, &file, &mut report.dirs)?;
+        }
+        report.bytes += bytes;
+        report.files.push(file);
+    }
+
+    let verb = if options.dry_run {
+        "Would remove"
+    } else {
+        "Removed"
+    };
+    progress::status(
+        verb,
+        &format!(
+            "{} file{}, {} total",
+            report.files.len(),
+            if report.files.len() == 1 { "" } else { "s" },
+            progress::format_bytes(report.bytes)
+        ),
+    )?;
+
+    if options.cache {
+        if options.dry_run {
+            progress::status(verb, "the cache and journal")?;
+        } else {
+            let entries = Cache::new(&cache::cache_dir(project_dir)).clear(None)?;
+            Journal::new(&journal::journal_dir(project_dir)).clear()?;
+            progress::status(
+                verb,
+                &format!(
+                    "{} cache entr{} and the journal",
+                    entries,
+                    if entries == 1 { "y" } else { "ies" }
+                ),
+            )?;
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::config::ToolConfig;
+    use crate::journal::{EntryKind, JournalEntry};
+
+    fn config(tools: &[(&str, &[&str])]) -> CargonodeConfig {
+        let mut config = CargonodeConfig::default();
+        for (name, outputs) in tools {
+            let tool_config = ToolConfig {
+                command: "true".to_string(),
+                outputs: outputs.iter().map(|output| output.to_string()).collect(),
+                ..Default::default()
+            };
+            config.tools.insert(name.to_string(), tool_config);
+        }
+        config
+    }
+
+    #[test]
+    fn test_clean_outputs() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        fs::create_dir_all(dir.join("dist/types"))?;
+        fs::write(dir.join("dist/index.js"), "12345")?;
+        fs::write(dir.join("dist/types/index.d.ts"), "123")?;
+        fs::create_dir(dir.join("coverage"))?;
+        fs::write(dir.join("coverage/lcov.info"), "1")?;
+        fs::write(dir.join("coverage/keep.txt"), "")?;
+        let config = config(&[
+            ("build", &["dist/**/*.js", "dist/**/*.d.ts"]),
+            ("test", &["coverage/*.info"]),
+        ]);
+
+        // A dry run only lists the files
+        let dry_run = CleanOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        let report = clean(dir, &config, &dry_run)?;
+        assert_eq!(report.files.len(), 3);
+        assert_eq!(report.bytes, 9);
+        assert!(dir.join("dist/index.js").exists());
+
+        // Only the named tool's outputs are removed, with the directories
+        // left empty
+        let build = CleanOptions {
+            tools: vec!["build".to_string()],
+            ..Default::default()
+        };
+        let report = clean(dir, &config, &build)?;
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.bytes, 8);
+        assert!(!dir.join("dist").exists());
+        assert!(dir.join("coverage/lcov.info").exists());
+
+        let report = clean(dir, &config, &CleanOptions::default())?;
+        assert_eq!(report.files.len(), 1);
+        assert!(report.dirs.is_empty());
+        assert!(dir.join("coverage/keep.txt").exists());
+
+        // Nothing left to remove is not an error
+        assert_eq!(
+            clean(dir, &config, &CleanOptions::default())?,
+            CleanReport::default()
+        );
+
+        let unknown = CleanOptions {
+            tools: vec!["deploy".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            clean(dir, &config, &unknown),
+            Err(Error::Config { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_refuses_outside_root() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project = temp_dir.path().join("app");
+        fs::create_dir_all(project.join("dist"))?;
+        fs::write(project.join("dist/index.js"), "")?;
+        fs::write(temp_dir.path().join("secret.txt"), "keep me")?;
+        let config = config(&[("build", &["dist/*.js", "../*.txt"])]);
+
+        let result = clean(&project, &config, &CleanOptions::default());
+        let Err(Error::Config { message, .. }) = result else {
+            panic!("expected files outside the project to be refused");
+        };
+        assert!(message.contains("secret.txt"));
+
+        // Nothing is removed, not even the outputs inside the project
+        assert!(temp_dir.path().join("secret.txt").exists());
+        assert!(project.join("dist/index.js").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_cache() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        let journal = Journal::new(&journal::journal_dir(dir));
+        journal.add_entry(JournalEntry {
+            tool: "build".to_string(),
+            kind: EntryKind::Tool,
+            command: "tsc".to_string(),
+            input_hash: "abc".to_string(),
+            exit_code: 0,
+            from_cache: false,
+            timestamp: 0,
+            duration_ms: 0,
+            steps: Vec::new(),
+        })?;
+
+        let options = CleanOptions {
+            cache: true,
+            ..Default::default()
+        };
+        clean(dir, &CargonodeConfig::default(), &options)?;
+        assert!(journal.read_entries()?.is_empty());
+        Ok(())
+    }
+}
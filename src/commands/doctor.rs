@@ -0,0 +1,359 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+
+use semver::Version;
+use serde::Serialize;
+
+use crate::config;
+use crate::dependency;
+use crate::error::Error;
+use crate::node;
+use crate::package_manager;
+use crate::registry::Client;
+use crate::utils::{self, Vcs};
+use crate::{progress, Result};
+
+/// Outcome of one environment check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    /// Everything is in order
+    Pass,
+    /// Something may cause trouble, but cargonode can work
+    Warn,
+    /// Something that cargonode needs is broken
+    Fail,
+}
+
+/// Result of one environment check
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    /// What was checked
+    pub name: &'static str,
+    /// How the check ended
+    pub status: CheckStatus,
+    /// What was found
+    pub message: String,
+}
+
+impl DoctorCheck {
+    fn new(name: &'static str, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn from_result(name: &'static str, failure: CheckStatus, result: Result<String>) -> Self {
+        match result {
+            Ok(message) => Self::new(name, CheckStatus::Pass, message),
+            Err(e) => Self::new(name, failure, summarize(&e)),
+        }
+    }
+}
+
+/// The most specific line of an error, without its title and suggestion
+fn summarize(error: &Error) -> String {
+    let message = error.message();
+    let line = message.lines().last().unwrap_or_default();
+    line.strip_prefix("Error: ").unwrap_or(line).to_string()
+}
+
+/// Check that the installed Node.js satisfies the project's requirement
+fn check_node(project_dir: &Path) -> DoctorCheck {
+    let Some(installed) = utils::node_version() else {
+        return DoctorCheck::new(
+            "node",
+            CheckStatus::Fail,
+            "`node` is not installed or not on PATH",
+        );
+    };
+
+    let required = node::required_version(project_dir);
+    // engines.node is an npm range, which may have `||` alternatives
+    let (Ok(version), Ok(alternatives)) = (
+        Version::parse(&installed),
+        dependency::parse_range(&required),
+    ) else {
+        return DoctorCheck::new(
+            "node",
+            CheckStatus::Warn,
+            format!("cannot tell whether {} satisfies `{}`", installed, required),
+        );
+    };
+
+    if alternatives
+        .iter()
+        .any(|requirement| requirement.matches(&version))
+    {
+        DoctorCheck::new(
+            "node",
+            CheckStatus::Pass,
+            format!("{} satisfies `{}`", installed, required),
+        )
+    } else {
+        DoctorCheck::new(
+            "node",
+            CheckStatus::Fail,
+            format!("{} does not satisfy `{}`", installed, required),
+        )
+    }
+}
+
+/// Check that the package manager the project uses is installed
+fn check_package_manager(project_dir: &Path) -> DoctorCheck {
+    let result = package_manager::detect_package_manager(project_dir).and_then(|detected| {
+        let path = detected.ensure_available()?;
+        Ok(format!("{} at {}", detected, path.display()))
+    });
+    DoctorCheck::from_result("package manager", CheckStatus::Fail, result)
+}
+
+/// Check that git is installed, which is required inside a git repository
+fn check_git(project_dir: &Path) -> DoctorCheck {
+    let in_repository = utils::find_repository_root(project_dir, Vcs::Git).is_some();
+    match utils::git_version() {
+        Some(version) => DoctorCheck::new("git", CheckStatus::Pass, version),
+        None if in_repository => DoctorCheck::new(
+            "git",
+            CheckStatus::Fail,
+            "the project is a git repository but `git` is not installed",
+        ),
+        None => DoctorCheck::new("git", CheckStatus::Warn, "`git` is not installed"),
+    }
+}
+
+/// Check that the registry answers; an unreachable one only matters online
+fn check_registry(client: &Client) -> DoctorCheck {
+    let result = client
+        .ping()
+        .map(|()| format!("{} is reachable", client.base_url()));
+    DoctorCheck::from_result("registry", CheckStatus::Warn, result)
+}
+
+/// Check that cargonode can write its cache and journal next to the project
+fn check_permissions(project_dir: &Path) -> DoctorCheck {
+    let probe = project_dir.join(format!(".cargonode-doctor-{}", process::id()));
+    let _cleanup = crate::process::remove_on_interrupt(&probe);
+    let written = fs::write(&probe, b"");
+    // A write that fails part way may still have created the file
+    let removed = match fs::remove_file(&probe) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound && written.is_err() => Ok(()),
+        removed => removed,
+    };
+    let result = written
+        .and(removed)
+        .map(|()| format!("{} is writable", project_dir.display()))
+        .map_err(Error::from);
+    DoctorCheck::from_result("permissions", CheckStatus::Fail, result)
+}
+
+/// Check that package.json parses and every configured tool is valid
+fn check_config(project_dir: &Path) -> Vec<DoctorCheck> {
+    if !project_dir.join("package.json").is_file() {
+        return vec![DoctorCheck::new(
+            "package.json",
+            CheckStatus::Warn,
+            format!("no package.json in {}", project_dir.display()),
+        )];
+    }
+
+    let config = match config::load_config(project_dir) {
+        Ok(config) => config,
+        Err(e) => {
+            return vec![DoctorCheck::from_result(
+                "package.json",
+                CheckStatus::Fail,
+                Err(e),
+            )]
+        }
+    };
+
+    let mut tools: Vec<_> = config.tools.iter().collect();
+    tools.sort_by_key(|(name, _)| name.as_str());
+    let invalid: Vec<String> = tools
+        .iter()
+        .filter_map(|(name, tool)| config::validate_tool_config(name, tool).err())
+        .map(|e| summarize(&e))
+        .collect();
+
+    let tools_check = if invalid.is_empty() {
+        DoctorCheck::new(
+            "tools",
+            CheckStatus::Pass,
+            format!(
+                "{} tool{} configured",
+                tools.len(),
+                if tools.len() == 1 { "" } else { "s" }
+            ),
+        )
+    } else {
+        DoctorCheck::new("tools", CheckStatus::Fail, invalid.join("; "))
+    };
+    vec![
+        DoctorCheck::new("package.json", CheckStatus::Pass, "parses"),
+        tools_check,
+    ]
+}
+
+/// Run every environment check for the project in `project_dir`
+///
+/// # Arguments
+///
+/// * `project_dir` - Directory the checks run in
+/// * `client` - Registry client used to check that the registry is reachable
+///
+/// # Returns
+///
+/// * `Vec<DoctorCheck>` - One result per check, in a fixed order
+pub fn diagnose(project_dir: &Path, client: &Client) -> Vec<DoctorCheck> {
+    let mut checks = vec![
+        check_node(project_dir),
+        check_package_manager(project_dir),
+        check_git(project_dir),
+        check_registry(client),
+        check_permissions(project_dir),
+    ];
+    checks.extend(check_config(project_dir));
+    checks
+}
+
+/// Print the environment checks, then fail if any of them failed
+///
+/// # Arguments
+///
+/// * `checks` - Results from `diagnose`
+/// * `json` - Print each result as a line of JSON instead of styled text
+///
+/// # Returns
+///
+/// * `Result<()>` - `Error::Doctor` naming the failed checks, if any
+pub fn report_checks(checks: &[DoctorCheck], json: bool) -> Result<()> {
+    for check in checks {
+        if json {
+            progress::write_json("doctor", check)?;
+            continue;
+        }
+        let line = format!("{}: {}", check.name, check.message);
+        match check.status {
+            CheckStatus::Pass => progress::status("ok", &line)?,
+            CheckStatus::Warn => progress::warn(&line)?,
+            CheckStatus::Fail => progress::error(&line)?,
+        }
+    }
+
+    let failed: Vec<&str> = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .map(|check| check.name)
+        .collect();
+    if failed.is_empty() {
+        return Ok(());
+    }
+    Err(Error::Doctor {
+        message: format!(
+            "{} check{} failed: {}",
+            failed.len(),
+            if failed.len() == 1 { "" } else { "s" },
+            failed.join(", ")
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::progress::{Level, RecordingSink};
+    use crate::registry::RetryPolicy;
+
+    fn status_of<'a>(checks: &'a [DoctorCheck], name: &str) -> &'a DoctorCheck {
+        checks.iter().find(|check| check.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::write(
+            dir.join("package.json"),
+            r#"{"name": "app", "engines": {"node": ">=0.1"}, "cargonode": {"tools": {
+                "build": {"command": "tsc", "inputs": ["src/**"]},
+                "lint": {"command": "", "inputs": ["src/**"]}
+            }}}"#,
+        )
+        .unwrap();
+        let client = Client::with_registry("http://127.0.0.1:9")
+            .unwrap()
+            .with_timeout(Duration::from_secs(1))
+            .with_retry(RetryPolicy::never());
+
+        let checks = diagnose(dir, &client);
+        assert_eq!(status_of(&checks, "registry").status, CheckStatus::Warn);
+        assert_eq!(status_of(&checks, "permissions").status, CheckStatus::Pass);
+        assert_eq!(status_of(&checks, "package.json").status, CheckStatus::Pass);
+        let tools = status_of(&checks, "tools");
+        assert_eq!(tools.status, CheckStatus::Fail);
+        assert_eq!(tools.message, "Tool 'lint' has an empty command");
+        if utils::node_version().is_some() {
+            assert_eq!(status_of(&checks, "node").status, CheckStatus::Pass);
+        }
+        // The probe file is cleaned up
+        assert_eq!(fs::read_dir(dir).unwrap().count(), 1);
+
+        let sink = Arc::new(RecordingSink::default());
+        let report = progress::with_sink(sink.clone(), || report_checks(&checks, false));
+        let Err(Error::Doctor { message }) = report else {
+            panic!("expected the failed tools check to fail the report");
+        };
+        assert!(message.ends_with("failed: tools"));
+        let messages = sink.messages();
+        let tools = messages.iter().find(|m| m.text.starts_with("error: tools"));
+        assert_eq!(tools.unwrap().level, Level::Error);
+        assert!(messages
+            .iter()
+            .any(|m| m.level == Level::Warn && m.text.starts_with("warning: registry")));
+    }
+
+    #[test]
+    fn test_check_node_alternatives() {
+        let Some(installed) = utils::node_version() else {
+            return;
+        };
+        let Ok(version) = Version::parse(&installed) else {
+            return;
+        };
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("package.json"),
+            format!(r#"{{"engines": {{"node": "<0.1 || ^{}"}}}}"#, version.major),
+        )
+        .unwrap();
+
+        let check = check_node(temp_dir.path());
+        assert_eq!(check.status, CheckStatus::Pass, "{}", check.message);
+    }
+
+    #[test]
+    fn test_check_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+
+        assert_eq!(check_config(dir)[0].status, CheckStatus::Warn);
+
+        fs::write(dir.join("package.json"), "{ not json").unwrap();
+        assert_eq!(check_config(dir)[0].status, CheckStatus::Fail);
+
+        fs::write(dir.join("package.json"), r#"{"name": "app"}"#).unwrap();
+        let checks = check_config(dir);
+        assert_eq!(checks[1].message, "0 tools configured");
+        assert!(report_checks(&checks, true).is_ok());
+    }
+}
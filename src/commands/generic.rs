@@ -1,8 +1,127 @@
 use std::path::{Path, PathBuf};
 
 use crate::commands::run::{run_tool, RunOptions, RunResult};
-use crate::progress;
-use crate::Result;
+use crate::config::{self, CargonodeConfig, LintTool, ToolConfig};
+use crate::error::Error;
+use crate::{progress, Result};
+
+/// Name of the tool `cargonode fmt` runs
+const FMT_TOOL: &str = "fmt";
+
+/// Tell which linter or formatter cargonode runs for `tool_name` and why
+fn announce_default_tool(
+    tool_name: &str,
+    default: &config::DefaultTool,
+    verbose: bool,
+) -> Result<()> {
+    if verbose {
+        progress::note(&format!(
+            "using `{} {}` as the `{}` tool ({})",
+            default.config.command,
+            default.config.args.join(" "),
+            tool_name,
+            default.reason
+        ))?;
+    }
+    if let (Some(tool), Some(other)) = (default.lint_tool, default.alternatives.first()) {
+        progress::note(&format!(
+            "the project is also set up for {}; running {} ({}), pass `--tool {}` to run {} instead",
+            other.name(),
+            tool.name(),
+            default.reason,
+            other.name(),
+            other.name()
+        ))?;
+    }
+    Ok(())
+}
+
+/// Fail unless `tool` can run as `tool_name`
+fn check_lint_tool(tool_name: &str, tool: Option<LintTool>) -> Result<()> {
+    let candidates = LintTool::candidates(tool_name);
+    match tool {
+        Some(tool) if !candidates.contains(&tool) => Err(Error::Config {
+            message: format!(
+                "{} cannot run as the `{}` tool; use {}",
+                tool.name(),
+                tool_name,
+                candidates
+                    .iter()
+                    .map(|tool| tool.name())
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            ),
+            hint: None,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Load the configuration to run `command_type` with, adding the detected
+/// default when the tool is not configured
+///
+/// `preferred` (`--tool`) replaces the configured or detected linter.
+fn tool_configuration(
+    command_type: &str,
+    project_dir: &Path,
+    verbose: bool,
+    preferred: Option<LintTool>,
+) -> Result<CargonodeConfig> {
+    if cfg!(test) && !project_dir.join("package.json").exists() {
+        // For tests, create a mock configuration
+        let mut config = crate::config::CargonodeConfig::default();
+        let tool_config = crate::config::ToolConfig {
+            command: "echo".to_string(),
+            args: vec![command_type.to_string()],
+            env: std::collections::HashMap::new(),
+            working_dir: None,
+            inputs: vec!["*.txt".to_string()],
+            outputs: vec!["*.out".to_string()],
+            exclude: vec![],
+            max_age_seconds: None,
+            timeout_secs: None,
+            outputs_optional: false,
+            steps: None,
+        };
+        config.tools.insert(command_type.to_string(), tool_config);
+        return Ok(config);
+    }
+
+    check_lint_tool(command_type, preferred)?;
+    let mut config = crate::config::load_config(project_dir)?;
+    if preferred.is_some() || config::get_tool_config(&config, command_type).is_none() {
+        if let Some(default) = config::default_tool_config(command_type, project_dir, preferred) {
+            announce_default_tool(command_type, &default, verbose)?;
+            config
+                .tools
+                .insert(command_type.to_string(), default.config);
+        }
+    }
+    Ok(config)
+}
+
+/// Run a tool of `config` as one of the generic commands
+fn run_configured(
+    command_type: &str,
+    config: &CargonodeConfig,
+    project_dir: &Path,
+    force: bool,
+    verbose: bool,
+    timeout: Option<u64>,
+    log_file: Option<&Path>,
+) -> Result<RunResult> {
+    let options = RunOptions {
+        project_dir: project_dir.to_path_buf(),
+        force,
+        verbose,
+        quiet: progress::verbosity() == progress::Verbosity::Quiet,
+        timeout,
+        args: Vec::new(),
+        log_file: log_file.map(Path::to_path_buf),
+        summarize_tests: command_type == "test",
+    };
+    run_tool(command_type, config, &options)
+}
 
 /// Run a generic command with the given type and arguments
 ///
@@ -13,6 +132,8 @@ use crate::Result;
 /// * `project_dir` - Project directory
 /// * `force` - Whether to force execution even if cached
 /// * `verbose` - Whether to print verbose output
+/// * `timeout` - Seconds after which the command is stopped
+/// * `log_file` - File the command's raw output is copied to
 ///
 /// # Returns
 ///
@@ -23,36 +144,19 @@ pub fn run_generic_command(
     project_dir: &Path,
     force: bool,
     verbose: bool,
+    timeout: Option<u64>,
+    log_file: Option<&Path>,
 ) -> Result<RunResult> {
-    // Load configuration
-    let config = if cfg!(test) && !project_dir.join("package.json").exists() {
-        // For tests, create a mock configuration
-        let mut config = crate::config::CargonodeConfig {
-            tools: std::collections::HashMap::new(),
-        };
-        let tool_config = crate::config::ToolConfig {
-            command: "echo".to_string(),
-            args: vec![command_type.to_string()],
-            env: std::collections::HashMap::new(),
-            working_dir: None,
-            inputs: vec!["*.txt".to_string()],
-            outputs: vec!["*.out".to_string()],
-        };
-        config.tools.insert(command_type.to_string(), tool_config);
-        config
-    } else {
-        crate::config::load_config(project_dir)?
-    };
-
-    // Create run options
-    let options = RunOptions {
-        project_dir: project_dir.to_path_buf(),
+    let config = tool_configuration(command_type, project_dir, verbose, None)?;
+    run_configured(
+        command_type,
+        &config,
+        project_dir,
         force,
         verbose,
-    };
-
-    // Run the tool
-    run_tool(command_type, &config, &options)
+        timeout,
+        log_file,
+    )
 }
 
 /// Run the check command
@@ -60,25 +164,23 @@ pub fn run_generic_command(
 /// # Arguments
 ///
 /// * `paths` - Paths to check
+/// * `tool` - Linter to run instead of the configured or detected one
 /// * `project_dir` - Project directory
 /// * `force` - Whether to force execution even if cached
 /// * `verbose` - Whether to print verbose output
+/// * `timeout` - Seconds after which the command is stopped
 ///
 /// # Returns
 ///
 /// * `Result<RunResult>` - Result of running the check command
 pub fn check(
     paths: &[PathBuf],
+    tool: Option<LintTool>,
     project_dir: &Path,
     force: bool,
     verbose: bool,
+    timeout: Option<u64>,
 ) -> Result<RunResult> {
-    // Convert paths to strings
-    let path_args: Vec<String> = paths
-        .iter()
-        .map(|p| p.to_string_lossy().to_string())
-        .collect();
-
     // Print status message
     if verbose {
         let paths_str = if paths.is_empty() {
@@ -87,11 +189,12 @@ pub fn check(
             format!("{} paths", paths.len())
         };
 
-        progress::write_message(&progress::format_status("Checking", &paths_str))?;
+        progress::status("Checking", &paths_str)?;
     }
 
     // Run the check command
-    run_generic_command("check", &path_args, project_dir, force, verbose)
+    let config = tool_configuration("check", project_dir, verbose, tool)?;
+    run_configured("check", &config, project_dir, force, verbose, timeout, None)
 }
 
 /// Run the build command
@@ -102,11 +205,18 @@ pub fn check(
 /// * `project_dir` - Project directory
 /// * `force` - Whether to force execution even if cached
 /// * `verbose` - Whether to print verbose output
+/// * `timeout` - Seconds after which the command is stopped
 ///
 /// # Returns
 ///
 /// * `Result<RunResult>` - Result of running the build command
-pub fn build(release: bool, project_dir: &Path, force: bool, verbose: bool) -> Result<RunResult> {
+pub fn build(
+    release: bool,
+    project_dir: &Path,
+    force: bool,
+    verbose: bool,
+    timeout: Option<u64>,
+) -> Result<RunResult> {
     // Create arguments
     let mut args = Vec::new();
 
@@ -117,14 +227,11 @@ pub fn build(release: bool, project_dir: &Path, force: bool, verbose: bool) -> R
     // Print status message
     if verbose {
         let mode = if release { "release" } else { "debug" };
-        progress::write_message(&progress::format_status(
-            "Building",
-            &format!("in {} mode", mode),
-        ))?;
+        progress::status("Building", &format!("in {} mode", mode))?;
     }
 
     // Run the build command
-    run_generic_command("build", &args, project_dir, force, verbose)
+    run_generic_command("build", &args, project_dir, force, verbose, timeout, None)
 }
 
 /// Run the test command
@@ -135,11 +242,23 @@ pub fn build(release: bool, project_dir: &Path, force: bool, verbose: bool) -> R
 /// * `project_dir` - Project directory
 /// * `force` - Whether to force execution even if cached
 /// * `verbose` - Whether to print verbose output
+/// * `timeout` - Seconds after which the command is stopped
+/// * `log_file` - File the test runner's raw output is copied to
+///
+/// When the tests fail, the failed tests found in the output of
+/// `node --test` or vitest are summarized after it.
 ///
 /// # Returns
 ///
 /// * `Result<RunResult>` - Result of running the test command
-pub fn test(pattern: &str, project_dir: &Path, force: bool, verbose: bool) -> Result<RunResult> {
+pub fn test(
+    pattern: &str,
+    project_dir: &Path,
+    force: bool,
+    verbose: bool,
+    timeout: Option<u64>,
+    log_file: Option<&Path>,
+) -> Result<RunResult> {
     // Create arguments
     let mut args = Vec::new();
 
@@ -155,11 +274,146 @@ pub fn test(pattern: &str, project_dir: &Path, force: bool, verbose: bool) -> Re
             format!("tests matching '{}'", pattern)
         };
 
-        progress::write_message(&progress::format_status("Running", &pattern_str))?;
+        progress::status("Running", &pattern_str)?;
     }
 
     // Run the test command
-    run_generic_command("test", &args, project_dir, force, verbose)
+    run_generic_command(
+        "test",
+        &args,
+        project_dir,
+        force,
+        verbose,
+        timeout,
+        log_file,
+    )
+}
+
+/// Switch a configured `fmt` tool to only report unformatted files
+///
+/// Only known formatters are switched: prettier gets `--check` in place of
+/// `--write`, and biome drops `--write`, as it reports unformatted files
+/// without it. Any other command might rewrite files, so it is refused.
+fn fmt_check_mode(configured: &mut ToolConfig) -> Result<()> {
+    let program = Path::new(&configured.command)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy());
+    let formatter = LintTool::candidates(FMT_TOOL)
+        .iter()
+        .find(|tool| program.as_deref() == Some(tool.name()));
+    let write_flags: &[&str] =
+        match formatter {
+            Some(LintTool::Prettier) => &["--write", "-w"],
+            Some(_) => &["--write", "--fix", "--apply"],
+            None => return Err(Error::Config {
+                message: format!(
+                    "The `{}` tool runs `{}`, which cargonode cannot run in check mode",
+                    FMT_TOOL, configured.command
+                ),
+                hint: Some(
+                    "Pass `--tool biome` or `--tool prettier` to check with one of those instead."
+                        .to_string(),
+                ),
+            }),
+        };
+    configured
+        .args
+        .retain(|arg| !write_flags.contains(&arg.as_str()));
+    if formatter == Some(&LintTool::Prettier) && !configured.args.iter().any(|arg| arg == "--check")
+    {
+        configured.args.push("--check".to_string());
+    }
+    Ok(())
+}
+
+/// Run the fmt command
+///
+/// Uses the `fmt` tool from package.json when configured, switched to check
+/// mode as described in `fmt_check_mode`; otherwise runs biome or prettier,
+/// whichever the project is set up for, see `config::detect_lint_tool`. Runs
+/// are cached and journaled like any other tool.
+///
+/// # Arguments
+///
+/// * `paths` - Paths to format (defaults to the whole project)
+/// * `check` - Fail if files would change instead of rewriting them
+/// * `tool` - Formatter to run instead of the configured or detected one
+/// * `project_dir` - Project directory
+/// * `force` - Whether to force execution even if cached
+/// * `verbose` - Whether to print verbose output
+/// * `timeout` - Seconds after which the command is stopped
+///
+/// # Returns
+///
+/// * `Result<RunResult>` - Result of running the formatter
+pub fn fmt(
+    paths: &[PathBuf],
+    check: bool,
+    tool: Option<LintTool>,
+    project_dir: &Path,
+    force: bool,
+    verbose: bool,
+    timeout: Option<u64>,
+) -> Result<RunResult> {
+    check_lint_tool(FMT_TOOL, tool)?;
+    let mut config = config::load_config(project_dir)?;
+    match config.tools.get_mut(FMT_TOOL) {
+        Some(configured) if tool.is_none() => {
+            if check {
+                fmt_check_mode(configured)?;
+            }
+        }
+        _ => {
+            let default =
+                config::default_tool_config(FMT_TOOL, project_dir, tool).ok_or_else(|| {
+                    Error::FormatterNotFound {
+                        message: format!(
+                            "The project is set up for neither {}, and no `{}` tool is configured",
+                            LintTool::candidates(FMT_TOOL)
+                                .iter()
+                                .map(|tool| tool.name())
+                                .collect::<Vec<_>>()
+                                .join(" nor "),
+                            FMT_TOOL
+                        ),
+                    }
+                })?;
+            announce_default_tool(FMT_TOOL, &default, verbose)?;
+            let tool_config = match (default.lint_tool, check) {
+                (Some(formatter), true) => formatter.tool_config(FMT_TOOL, project_dir, false),
+                _ => default.config,
+            };
+            config.tools.insert(FMT_TOOL.to_string(), tool_config);
+        }
+    }
+
+    let mut args: Vec<String> = paths
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    if args.is_empty() {
+        args.push(".".to_string());
+    }
+
+    if verbose {
+        if check {
+            progress::status("Checking", &format!("formatting of {}", args.join(" ")))?;
+        } else {
+            progress::status("Formatting", &args.join(" "))?;
+        }
+    }
+
+    let options = RunOptions {
+        project_dir: project_dir.to_path_buf(),
+        force,
+        verbose,
+        quiet: progress::verbosity() == progress::Verbosity::Quiet,
+        timeout,
+        args,
+        log_file: None,
+        summarize_tests: false,
+    };
+    run_tool(FMT_TOOL, &config, &options)
 }
 
 #[cfg(test)]
@@ -191,7 +445,7 @@ mod tests {
         create_test_file(dir_path, "test.out", b"test output")?;
 
         let paths = vec![dir_path.join("test.txt")];
-        let result = check(&paths, dir_path, false, false)?;
+        let result = check(&paths, None, dir_path, false, false, None)?;
 
         // Verify result
         assert!(result.status.success());
@@ -209,7 +463,7 @@ mod tests {
         create_test_file(dir_path, "test.txt", b"test content")?;
         create_test_file(dir_path, "test.out", b"test output")?;
 
-        let result = build(false, dir_path, false, false)?;
+        let result = build(false, dir_path, false, false, None)?;
 
         // Verify result
         assert!(result.status.success());
@@ -217,6 +471,169 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_build_command_caching() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+
+        create_test_file(dir_path, "test.txt", b"test content")?;
+        create_test_file(dir_path, "test.out", b"test output")?;
+
+        // Unchanged inputs are served from the cache unless forced
+        assert!(!build(false, dir_path, false, false, None)?.cached);
+        assert!(build(false, dir_path, false, false, None)?.cached);
+        assert!(!build(false, dir_path, true, false, None)?.cached);
+        assert!(build(false, dir_path, false, false, None)?.cached);
+
+        create_test_file(dir_path, "test.txt", b"changed content")?;
+        assert!(!build(false, dir_path, false, false, None)?.cached);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn create_script(path: &Path, script: &str) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, script)?;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fmt_default_formatter() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        create_test_file(dir_path, "package.json", br#"{"name": "app"}"#)?;
+
+        let result = fmt(&[], false, None, dir_path, false, false, None);
+        let Err(Error::FormatterNotFound { .. }) = result else {
+            panic!("expected a missing formatter error");
+        };
+
+        // A local prettier is picked up, in write or check mode
+        create_script(
+            &dir_path.join("node_modules/.bin/prettier"),
+            "#!/bin/sh\nprintf '%s ' \"$@\" > prettier.out\n",
+        )?;
+        fmt(&[], false, None, dir_path, false, false, None)?;
+        assert_eq!(
+            fs::read_to_string(dir_path.join("prettier.out"))?,
+            "--write . "
+        );
+        fmt(
+            &[PathBuf::from("src")],
+            true,
+            None,
+            dir_path,
+            false,
+            false,
+            None,
+        )?;
+        assert_eq!(
+            fs::read_to_string(dir_path.join("prettier.out"))?,
+            "--check src "
+        );
+
+        // biome is preferred when both are installed
+        create_script(
+            &dir_path.join("node_modules/.bin/biome"),
+            "#!/bin/sh\nprintf '%s ' \"$@\" > biome.out\n",
+        )?;
+        fmt(&[], true, None, dir_path, false, false, None)?;
+        assert_eq!(fs::read_to_string(dir_path.join("biome.out"))?, "format . ");
+
+        // unless a config file says otherwise, or --tool does
+        create_test_file(dir_path, ".prettierrc", b"{}")?;
+        fmt(&[], true, None, dir_path, false, false, None)?;
+        assert_eq!(
+            fs::read_to_string(dir_path.join("prettier.out"))?,
+            "--check . "
+        );
+        fmt(
+            &[],
+            false,
+            Some(LintTool::Biome),
+            dir_path,
+            false,
+            false,
+            None,
+        )?;
+        assert_eq!(
+            fs::read_to_string(dir_path.join("biome.out"))?,
+            "format --write . "
+        );
+        let result = fmt(
+            &[],
+            false,
+            Some(LintTool::Eslint),
+            dir_path,
+            false,
+            false,
+            None,
+        );
+        assert!(matches!(result, Err(Error::Config { .. })));
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fmt_configured_tool() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        create_test_file(
+            dir_path,
+            "package.json",
+            br#"{"name": "app", "cargonode": {"tools": {"fmt": {
+                "command": "node_modules/.bin/prettier",
+                "args": ["--write"],
+                "inputs": ["src/**"]
+            }}}}"#,
+        )?;
+        create_script(
+            &dir_path.join("node_modules/.bin/prettier"),
+            "#!/bin/sh\nprintf '%s ' \"$@\" > fmt.out\n[ \"$1\" != --check ]\n",
+        )?;
+
+        fmt(
+            &[PathBuf::from("a.js")],
+            false,
+            None,
+            dir_path,
+            false,
+            false,
+            None,
+        )?;
+        assert_eq!(
+            fs::read_to_string(dir_path.join("fmt.out"))?,
+            "--write a.js "
+        );
+
+        // Check mode swaps --write for --check, and a failing check is an error
+        let result = fmt(&[], true, None, dir_path, false, false, None);
+        assert_eq!(fs::read_to_string(dir_path.join("fmt.out"))?, "--check . ");
+        assert!(matches!(result, Err(Error::CommandFailed { .. })));
+
+        // An unknown formatter is not trusted to take --check
+        create_test_file(
+            dir_path,
+            "package.json",
+            br#"{"name": "app", "cargonode": {"tools": {"fmt": {
+                "command": "sh",
+                "args": ["-c", "printf '%s ' \"$@\" > fmt.out", "fmt"]
+            }}}}"#,
+        )?;
+        fs::remove_file(dir_path.join("fmt.out"))?;
+        let result = fmt(&[], true, None, dir_path, false, false, None);
+        assert!(matches!(result, Err(Error::Config { .. })));
+        assert!(!dir_path.join("fmt.out").exists());
+
+        Ok(())
+    }
+
     #[test]
     fn test_test_command() -> Result<()> {
         // Create temporary directory
@@ -227,7 +644,7 @@ mod tests {
         create_test_file(dir_path, "test.txt", b"test content")?;
         create_test_file(dir_path, "test.out", b"test output")?;
 
-        let result = test("*", dir_path, false, false)?;
+        let result = test("*", dir_path, false, false, None, None)?;
 
         // Verify result
         assert!(result.status.success());
@@ -0,0 +1,815 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Map, Value};
+
+use crate::config::{self, ConfigFile};
+use crate::dependency::{self, DEPENDENCY_SECTIONS};
+use crate::error::Error;
+use crate::{progress, utils, Result};
+
+/// Scripts each tool is imported from, in order of preference
+const TOOL_SCRIPTS: [(&str, &[&str]); 4] = [
+    ("build", &["build"]),
+    ("test", &["test"]),
+    ("check", &["check", "lint"]),
+    ("fmt", &["fmt", "format"]),
+];
+
+/// Commands whose package has a different name
+const PACKAGE_BINARIES: [(&str, &str); 2] = [("tsc", "typescript"), ("biome", "@biomejs/biome")];
+
+/// Output patterns of a build without a tsconfig `outDir`
+const DEFAULT_OUTPUTS: &str = "dist/**/*";
+
+/// Options for importing the scripts of an existing project
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// Directory of the project, holding its package.json
+    pub project_dir: PathBuf,
+
+    /// Where to write the generated configuration
+    pub config_file: ConfigFile,
+
+    /// Replace the tools of an existing configuration
+    pub force: bool,
+
+    /// Print the generated configuration without writing it
+    pub dry_run: bool,
+}
+
+/// A tool generated from a script
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedTool {
+    /// Name of the tool
+    pub name: String,
+
+    /// Name of the script it was generated from
+    pub script: String,
+
+    /// The tool's configuration, as found under `cargonode.tools`
+    pub config: Value,
+}
+
+/// A script that was not turned into a tool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedScript {
+    /// Name of the script
+    pub name: String,
+
+    /// Why it was skipped
+    pub reason: String,
+}
+
+/// Tool configuration generated from a project's scripts
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptImport {
+    /// Generated tools, in the order build, test, check, fmt
+    pub tools: Vec<ImportedTool>,
+
+    /// Scripts that were left out, in the order of package.json
+    pub skipped: Vec<SkippedScript>,
+}
+
+impl ScriptImport {
+    /// The generated tools as a `tools` table
+    pub fn tools_table(&self) -> Value {
+        let tools: Map<String, Value> = self
+            .tools
+            .iter()
+            .map(|tool| (tool.name.clone(), tool.config.clone()))
+            .collect();
+        Value::Object(tools)
+    }
+}
+
+/// Turn a tsconfig `include` entry into an input pattern
+fn include_pattern(entry: &str) -> String {
+    let entry = entry.trim_start_matches("./").trim_end_matches('/');
+    let is_glob = entry.contains(['*', '?', '[']);
+    if is_glob || Path::new(entry).extension().is_some() {
+        entry.to_string()
+    } else {
+        format!("{}/**/*", entry)
+    }
+}
+
+/// Split a script into commands joined by `&&`, each a list of words
+///
+/// Only what a command line without a shell can express is accepted: plain
+/// words, quotes, backslash escapes and `&&`.
+///
+/// # Returns
+///
+/// * `std::result::Result<Vec<Vec<String>>, String>` - The commands, or why
+///   the script needs a shell
+fn split_script(script: &str) -> std::result::Result<Vec<Vec<String>>, String> {
+    let shell_syntax = |syntax: &str| format!("uses shell syntax (`{}`)", syntax);
+    let mut commands = vec![Vec::new()];
+    let mut word: Option<String> = None;
+    let mut chars = script.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                commands.last_mut().unwrap().extend(word.take());
+            }
+            '&' if chars.peek() == Some(&'&') => {
+                chars.next();
+                commands.last_mut().unwrap().extend(word.take());
+                commands.push(Vec::new());
+            }
+            '\'' => {
+                let quoted = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => quoted.push(c),
+                        None => return Err("has an unclosed quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let quoted = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => quoted.extend(chars.next()),
+                        Some(c @ ('$' | '`')) => return Err(shell_syntax(&c.to_string())),
+                        Some(c) => quoted.push(c),
+                        None => return Err("has an unclosed quote".to_string()),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            '|' | ';' | '<' | '>' | '&' | '$' | '`' | '(' | ')' => {
+                return Err(shell_syntax(&c.to_string()));
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    commands.last_mut().unwrap().extend(word);
+
+    if commands.iter().any(Vec::is_empty) {
+        return Err("is not a simple command".to_string());
+    }
+    Ok(commands)
+}
+
+/// Whether the project declares the package that provides `command`
+fn provides(manifest: &Value, command: &str) -> bool {
+    let package = PACKAGE_BINARIES
+        .iter()
+        .find(|(binary, _)| *binary == command)
+        .map_or(command, |(_, package)| package);
+    DEPENDENCY_SECTIONS
+        .iter()
+        .any(|section| manifest[section].get(package).is_some())
+}
+
+/// The command to configure for an executable a script runs
+///
+/// Scripts find the executables of dependencies in `node_modules/.bin`,
+/// which is not on cargonode's PATH, so those are referenced by a path
+/// relative to the project.
+fn resolve_command(project_dir: &Path, manifest: &Value, command: &str) -> String {
+    if command.contains('/') {
+        return command.to_string();
+    }
+    let installed = utils::find_local_bin(project_dir, command).and_then(|bin| {
+        let root = bin.parent()?.parent()?.parent()?;
+        let depth = project_dir.ancestors().position(|dir| dir == root)?;
+        let file_name = bin.file_name()?.to_string_lossy().to_string();
+        Some(format!(
+            "{}node_modules/.bin/{}",
+            "../".repeat(depth),
+            file_name
+        ))
+    });
+    match installed {
+        Some(path) => path,
+        None if provides(manifest, command) => format!("node_modules/.bin/{}", command),
+        None => command.to_string(),
+    }
+}
+
+/// Configuration of one command of a script, or why it cannot run without a shell
+fn command_config(
+    project_dir: &Path,
+    manifest: &Value,
+    words: &[String],
+) -> std::result::Result<Map<String, Value>, String> {
+    // Leading `NAME=value` words set environment variables
+    let assignments = words
+        .iter()
+        .take_while(|word| {
+            word.split_once('=').is_some_and(|(name, _)| {
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            })
+        })
+        .count();
+    let Some((command, args)) = words[assignments..].split_first() else {
+        return Err("only sets environment variables".to_string());
+    };
+    if args.iter().any(|arg| arg == "--watch" || arg == "watch") || command == "nodemon" {
+        return Err("runs in watch mode and never finishes".to_string());
+    }
+
+    let mut config = Map::new();
+    config.insert(
+        "command".to_string(),
+        json!(resolve_command(project_dir, manifest, command)),
+    );
+    if !args.is_empty() {
+        config.insert("args".to_string(), json!(args));
+    }
+    if assignments > 0 {
+        let env: Map<String, Value> = words[..assignments]
+            .iter()
+            .filter_map(|word| word.split_once('='))
+            .map(|(name, value)| (name.to_string(), json!(value)))
+            .collect();
+        config.insert("env".to_string(), Value::Object(env));
+    }
+    Ok(config)
+}
+
+/// Configuration of a script's commands, or why it cannot be imported
+fn script_config(
+    project_dir: &Path,
+    manifest: &Value,
+    script: &Value,
+) -> std::result::Result<Map<String, Value>, String> {
+    let script = script.as_str().ok_or("is not a string")?;
+    if script.contains("no test specified") {
+        return Err("is npm's placeholder test script".to_string());
+    }
+
+    let mut commands = split_script(script)?
+        .iter()
+        .map(|words| command_config(project_dir, manifest, words))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    if commands.len() == 1 {
+        return Ok(commands.remove(0));
+    }
+    let mut config = Map::new();
+    config.insert("steps".to_string(), json!(commands));
+    Ok(config)
+}
+
+/// Input patterns shared by the generated tools
+fn input_patterns(project_dir: &Path, tsconfig: Option<&Value>) -> Vec<String> {
+    let mut inputs = vec!["src/**/*".to_string()];
+    if let Some(tsconfig) = tsconfig {
+        let includes = tsconfig["include"].as_array().into_iter().flatten();
+        for pattern in includes.filter_map(Value::as_str).map(include_pattern) {
+            if !inputs.contains(&pattern) {
+                inputs.push(pattern);
+            }
+        }
+    }
+    if project_dir.join("tsconfig.json").is_file() {
+        inputs.push("tsconfig.json".to_string());
+    }
+    inputs
+}
+
+/// Generate tool configuration from the scripts of a package.json
+///
+/// `build`, `test`, `check` or `lint`, and `fmt` or `format` become the
+/// tools of the same name. Variants such as `build:watch`, lifecycle hooks
+/// and scripts that need a shell are skipped with a reason.
+///
+/// # Arguments
+///
+/// * `project_dir` - Directory of the project, where tsconfig.json and
+///   `node_modules` are looked up
+/// * `manifest` - The project's package.json
+///
+/// # Returns
+///
+/// * `ScriptImport` - The generated tools and the skipped scripts
+fn map_scripts(project_dir: &Path, manifest: &Value) -> ScriptImport {
+    let tsconfig = fs::read_to_string(project_dir.join("tsconfig.json"))
+        .ok()
+        .and_then(|content| config::parse_tsconfig(&content));
+    let inputs = input_patterns(project_dir, tsconfig.as_ref());
+    let outputs = tsconfig
+        .as_ref()
+        .and_then(|tsconfig| tsconfig["compilerOptions"]["outDir"].as_str())
+        .map(|dir| {
+            format!(
+                "{}/**/*",
+                dir.trim_start_matches("./").trim_end_matches('/')
+            )
+        })
+        .unwrap_or_else(|| DEFAULT_OUTPUTS.to_string());
+
+    let empty = Map::new();
+    let scripts = manifest["scripts"].as_object().unwrap_or(&empty);
+    let mut import = ScriptImport::default();
+
+    // The first script of a tool that converts wins; the others are skipped
+    let mut reasons = HashMap::new();
+    for (tool, names) in TOOL_SCRIPTS {
+        let mut source = None;
+        for &name in names.iter().filter(|name| scripts.contains_key(**name)) {
+            if let Some(source) = source {
+                reasons.insert(
+                    name,
+                    format!("`{}` is already imported from `{}`", tool, source),
+                );
+                continue;
+            }
+            match script_config(project_dir, manifest, &scripts[name]) {
+                Ok(mut config) => {
+                    config.insert("inputs".to_string(), json!(inputs));
+                    if tool == "build" {
+                        config.insert("outputs".to_string(), json!([outputs]));
+                    }
+                    import.tools.push(ImportedTool {
+                        name: tool.to_string(),
+                        script: name.to_string(),
+                        config: Value::Object(config),
+                    });
+                    source = Some(name);
+                }
+                Err(reason) => {
+                    reasons.insert(name, reason);
+                }
+            }
+        }
+    }
+
+    let is_tool_script = |name: &str| TOOL_SCRIPTS.iter().any(|(_, names)| names.contains(&name));
+    for name in scripts.keys() {
+        if import.tools.iter().any(|tool| tool.script == *name) {
+            continue;
+        }
+        let base = name.split(':').next().unwrap_or_default();
+        let hook = ["pre", "post"]
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix))
+            .filter(|hooked| scripts.contains_key(*hooked));
+        let reason = match reasons.remove(name.as_str()) {
+            Some(reason) => reason,
+            None if base != name && is_tool_script(base) => format!(
+                "is a variant of `{}`; only `{}` itself is imported",
+                base, base
+            ),
+            None => match hook {
+                Some(hooked) => format!(
+                    "is a lifecycle hook of `{}`, which cargonode does not run",
+                    hooked
+                ),
+                None => "does not correspond to a cargonode tool".to_string(),
+            },
+        };
+        import.skipped.push(SkippedScript {
+            name: name.clone(),
+            reason,
+        });
+    }
+    import
+}
+
+/// Print the scripts that were skipped
+fn report_skipped(import: &ScriptImport) -> Result<()> {
+    for script in &import.skipped {
+        progress::note(&format!(
+            "skipped script `{}`: it {}",
+            script.name, script.reason
+        ))?;
+    }
+    Ok(())
+}
+
+/// Print what was imported and skipped, and the configuration to write
+fn report_import(import: &ScriptImport, content: &str, path: &Path, dry_run: bool) -> Result<()> {
+    for tool in &import.tools {
+        progress::status(
+            "Importing",
+            &format!("script `{}` as tool `{}`", tool.script, tool.name),
+        )?;
+    }
+    report_skipped(import)?;
+    progress::write_status(&format!("\n{}\n", content.trim_end()))?;
+    if dry_run {
+        progress::warn("aborting import due to dry run")?;
+    } else {
+        progress::status("Writing", &path.display().to_string())?;
+    }
+    Ok(())
+}
+
+/// Generate the tool configuration of an existing npm project from its scripts
+///
+/// Only the chosen configuration file changes: the `cargonode` key of
+/// package.json, or `cargonode.toml`. An existing `.cargonode.toml` is
+/// written in place of `cargonode.toml`, and package.json cannot be chosen
+/// while either exists, as the standalone file would shadow it.
+///
+/// # Arguments
+///
+/// * `options` - The project and where to write its configuration
+///
+/// # Returns
+///
+/// * `Result<ScriptImport>` - The generated tools and the skipped scripts, or
+///   `Error::Config` if the project is already configured or no script maps
+///   to a tool
+pub fn import_scripts(options: &ImportOptions) -> Result<ScriptImport> {
+    let project_dir = &options.project_dir;
+    let package_json_path = project_dir.join("package.json");
+    let original = fs::read_to_string(&package_json_path).map_err(|_| Error::Config {
+        message: format!(
+            "No package.json in {}; `init --import` imports an existing npm project",
+            project_dir.display()
+        ),
+        hint: None,
+    })?;
+    let mut manifest: Value = serde_json::from_str(&original)?;
+
+    // A standalone file takes precedence over package.json, so an existing
+    // one is where imported tools must go to be read
+    let standalone = config::PROJECT_CONFIG_FILES
+        .iter()
+        .map(|file| project_dir.join(file))
+        .find(|path| path.is_file());
+    let toml_path = standalone
+        .clone()
+        .unwrap_or_else(|| project_dir.join(config::PROJECT_CONFIG_FILES[0]));
+    let existing = standalone
+        .clone()
+        .or_else(|| manifest.get("cargonode").map(|_| package_json_path.clone()));
+    if let (Some(path), false) = (existing, options.force) {
+        return Err(Error::Config {
+            message: format!(
+                "The project is already configured in {}; pass --force to replace its tools",
+                path.display()
+            ),
+            hint: None,
+        });
+    }
+    if let (Some(path), ConfigFile::PackageJson) = (&standalone, options.config_file) {
+        return Err(Error::Config {
+            message: format!(
+                "{} takes precedence over package.json, so tools imported into package.json would be ignored",
+                path.display()
+            ),
+            hint: Some(format!(
+                "Import into it with `--config-file toml`, or remove {} first.",
+                path.display()
+            )),
+        });
+    }
+
+    let import = map_scripts(project_dir, &manifest);
+    if import.tools.is_empty() {
+        report_skipped(&import)?;
+        return Err(Error::Config {
+            message: "No script in package.json maps to a cargonode tool".to_string(),
+            hint: None,
+        });
+    }
+
+    // Other keys of an existing configuration are kept
+    match options.config_file {
+        ConfigFile::PackageJson => {
+            let section = manifest
+                .as_object_mut()
+                .ok_or_else(|| Error::Config {
+                    message: "package.json must contain a JSON object".to_string(),
+                    hint: None,
+                })?
+                .entry("cargonode")
+                .or_insert_with(|| json!({}));
+            if !section.is_object() {
+                *section = json!({});
+            }
+            section["tools"] = import.tools_table();
+            let content = serde_json::to_string_pretty(&json!({ "cargonode": section }))?;
+            report_import(&import, &content, &package_json_path, options.dry_run)?;
+            if !options.dry_run {
+                dependency::write_manifest(&package_json_path, &manifest, &original)?;
+            }
+        }
+        ConfigFile::Toml => {
+            let mut config = match fs::read_to_string(&toml_path) {
+                Ok(content) => toml::from_str(&content).map_err(|e| Error::Config {
+                    message: format!("Cannot parse {}: {}", toml_path.display(), e),
+                    hint: None,
+                })?,
+                Err(_) => json!({}),
+            };
+            config["tools"] = import.tools_table();
+            let content = config::tool_config_to_toml(&config)?;
+            report_import(&import, &content, &toml_path, options.dry_run)?;
+            if !options.dry_run {
+                fs::write(&toml_path, content)?;
+            }
+        }
+    }
+
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Map the scripts of a package.json fixture
+    fn import_fixture(package_json: Value, tsconfig: Option<&str>) -> (TempDir, ScriptImport) {
+        let temp_dir = TempDir::new().unwrap();
+        if let Some(tsconfig) = tsconfig {
+            fs::write(temp_dir.path().join("tsconfig.json"), tsconfig).unwrap();
+        }
+        let import = map_scripts(temp_dir.path(), &package_json);
+        (temp_dir, import)
+    }
+
+    fn skipped(import: &ScriptImport) -> Vec<(&str, &str)> {
+        import
+            .skipped
+            .iter()
+            .map(|script| (script.name.as_str(), script.reason.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_map_typescript_library() {
+        let (_dir, import) = import_fixture(
+            json!({
+                "scripts": {
+                    "build": "tsc -p tsconfig.json",
+                    "build:watch": "tsc --watch",
+                    "prebuild": "rimraf lib",
+                    "test": "NODE_ENV=test vitest run",
+                    "lint": "eslint 'src/**/*.ts'"
+                },
+                "devDependencies": {"typescript": "^5.0.0", "vitest": "^1.0.0"}
+            }),
+            Some(
+                r#"{
+                    // Compiled to lib
+                    "compilerOptions": {"outDir": "./lib/", /* no maps */},
+                    "include": ["src", "types/*.d.ts", "./scripts/build.ts",],
+                }"#,
+            ),
+        );
+
+        let names: Vec<_> = import.tools.iter().map(|tool| tool.name.as_str()).collect();
+        assert_eq!(names, vec!["build", "test", "check"]);
+        let inputs = json!([
+            "src/**/*",
+            "types/*.d.ts",
+            "scripts/build.ts",
+            "tsconfig.json"
+        ]);
+        assert_eq!(
+            import.tools[0].config,
+            json!({
+                "command": "node_modules/.bin/tsc",
+                "args": ["-p", "tsconfig.json"],
+                "inputs": inputs,
+                "outputs": ["lib/**/*"]
+            })
+        );
+        assert_eq!(
+            import.tools[1].config,
+            json!({
+                "command": "node_modules/.bin/vitest",
+                "args": ["run"],
+                "env": {"NODE_ENV": "test"},
+                "inputs": inputs
+            })
+        );
+        // eslint is not a dependency, so it is expected on PATH
+        assert_eq!(import.tools[2].config["command"], "eslint");
+        assert_eq!(import.tools[2].config["args"], json!(["src/**/*.ts"]));
+        assert_eq!(import.tools[2].script, "lint");
+
+        assert_eq!(
+            skipped(&import),
+            vec![
+                (
+                    "build:watch",
+                    "is a variant of `build`; only `build` itself is imported"
+                ),
+                (
+                    "prebuild",
+                    "is a lifecycle hook of `build`, which cargonode does not run"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_map_npm_init_defaults() {
+        let (_dir, import) = import_fixture(
+            json!({
+                "scripts": {
+                    "start": "node index.js",
+                    "test": "echo \"Error: no test specified\" && exit 1"
+                }
+            }),
+            None,
+        );
+        assert!(import.tools.is_empty());
+        assert_eq!(
+            skipped(&import),
+            vec![
+                ("start", "does not correspond to a cargonode tool"),
+                ("test", "is npm's placeholder test script"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_map_pipelines_and_shell_scripts() {
+        let (_dir, import) = import_fixture(
+            json!({
+                "scripts": {
+                    "lint": "biome lint src && tsc --noEmit",
+                    "check": "biome check .",
+                    "format": "prettier --write . > /dev/null",
+                    "build": "vite build | tee build.log",
+                    "test": "jest --watch"
+                },
+                "devDependencies": {"@biomejs/biome": "1.9.0"}
+            }),
+            None,
+        );
+
+        // `check` is preferred over `lint`, whatever their order
+        assert_eq!(import.tools.len(), 1);
+        assert_eq!(import.tools[0].script, "check");
+        assert_eq!(
+            import.tools[0].config,
+            json!({
+                "command": "node_modules/.bin/biome",
+                "args": ["check", "."],
+                "inputs": ["src/**/*"]
+            })
+        );
+        assert_eq!(
+            skipped(&import),
+            vec![
+                ("lint", "`check` is already imported from `check`"),
+                ("format", "uses shell syntax (`>`)"),
+                ("build", "uses shell syntax (`|`)"),
+                ("test", "runs in watch mode and never finishes"),
+            ]
+        );
+
+        // Commands joined by `&&` become steps
+        let (_dir, import) = import_fixture(
+            json!({"scripts": {"lint": "biome lint src && tsc --noEmit"}}),
+            None,
+        );
+        assert_eq!(
+            import.tools[0].config["steps"],
+            json!([
+                {"command": "biome", "args": ["lint", "src"]},
+                {"command": "tsc", "args": ["--noEmit"]}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_map_installed_binaries() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join("packages/app");
+        let bin = if cfg!(windows) { "tsup.cmd" } else { "tsup" };
+        fs::create_dir_all(temp_dir.path().join("node_modules/.bin")).unwrap();
+        fs::write(temp_dir.path().join("node_modules/.bin").join(bin), "").unwrap();
+        fs::create_dir_all(&project_dir).unwrap();
+
+        // A binary hoisted to the workspace root is found from a member
+        let import = map_scripts(&project_dir, &json!({"scripts": {"build": "tsup"}}));
+        assert_eq!(
+            import.tools[0].config["command"],
+            format!("../../node_modules/.bin/{}", bin)
+        );
+        assert_eq!(import.tools[0].config["outputs"], json!(["dist/**/*"]));
+    }
+
+    #[test]
+    fn test_split_script() {
+        assert_eq!(
+            split_script(r#"tsc -p "tsconfig build.json" && echo it\'s"#),
+            Ok(vec![
+                vec!["tsc".to_string(), "-p".into(), "tsconfig build.json".into()],
+                vec!["echo".to_string(), "it's".into()],
+            ])
+        );
+        assert_eq!(
+            split_script("echo \"$HOME\""),
+            Err("uses shell syntax (`$`)".to_string())
+        );
+        assert_eq!(
+            split_script("a && && b"),
+            Err("is not a simple command".to_string())
+        );
+        assert!(split_script("echo 'unclosed").is_err());
+    }
+
+    #[test]
+    fn test_import_scripts() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project_dir = temp_dir.path();
+        let original = "{\n\t\"name\": \"app\",\n\t\"scripts\": {\"build\": \"tsup\"}\n}\n";
+        fs::write(project_dir.join("package.json"), original)?;
+        let options = ImportOptions {
+            project_dir: project_dir.to_path_buf(),
+            config_file: ConfigFile::Toml,
+            force: false,
+            dry_run: true,
+        };
+
+        // A dry run changes nothing
+        import_scripts(&options)?;
+        assert_eq!(fs::read_dir(project_dir)?.count(), 1);
+
+        let import = import_scripts(&ImportOptions {
+            dry_run: false,
+            ..options.clone()
+        })?;
+        assert_eq!(import.tools.len(), 1);
+        let config = config::load_config(project_dir)?;
+        assert_eq!(config.tools["build"].command, "tsup");
+        assert_eq!(
+            fs::read_to_string(project_dir.join("package.json"))?,
+            original
+        );
+
+        // An existing configuration is only replaced with --force
+        let options = ImportOptions {
+            config_file: ConfigFile::PackageJson,
+            dry_run: false,
+            ..options
+        };
+        let Err(Error::Config { message, .. }) = import_scripts(&options) else {
+            panic!("expected the existing configuration to be kept");
+        };
+        assert!(message.contains("cargonode.toml"));
+
+        fs::remove_file(project_dir.join("cargonode.toml"))?;
+        import_scripts(&options)?;
+        let content = fs::read_to_string(project_dir.join("package.json"))?;
+        assert!(content.starts_with("{\n\t\"name\": \"app\""));
+        assert!(content.contains("\"cargonode\": {\n\t\t\"tools\": {\n\t\t\t\"build\""));
+        assert!(import_scripts(&options).is_err());
+        import_scripts(&ImportOptions {
+            force: true,
+            ..options
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_scripts_keeps_toml() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project_dir = temp_dir.path();
+        let original = r#"{ "name": "app", "scripts": { "build": "tsup", "test": "vitest run" } }"#;
+        fs::write(project_dir.join("package.json"), original)?;
+        fs::write(
+            project_dir.join(".cargonode.toml"),
+            "[tools.build]\ncommand = \"tsc\"\n",
+        )?;
+        let options = ImportOptions {
+            project_dir: project_dir.to_path_buf(),
+            config_file: ConfigFile::PackageJson,
+            force: true,
+            dry_run: false,
+        };
+
+        // Tools written to package.json would be shadowed by the toml file
+        let Err(Error::Config { message, hint }) = import_scripts(&options) else {
+            panic!("expected package.json to be refused");
+        };
+        assert!(
+            message.contains(".cargonode.toml takes precedence"),
+            "{}",
+            message
+        );
+        assert!(hint.unwrap().contains("--config-file toml"));
+        assert_eq!(
+            fs::read_to_string(project_dir.join("package.json"))?,
+            original
+        );
+
+        // The file the configuration is loaded from is replaced in place
+        import_scripts(&ImportOptions {
+            config_file: ConfigFile::Toml,
+            ..options
+        })?;
+        assert!(!project_dir.join("cargonode.toml").exists());
+        let config = config::load_config(project_dir)?;
+        assert_eq!(config.tools["build"].command, "tsup");
+        assert!(config.tools.contains_key("test"));
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+use serde::Serialize;
+
+use crate::dependency::{self, DependencyKind, DependencySpec};
+use crate::error::Error;
+use crate::registry::{Client, VersionMetadata};
+use crate::{progress, Result};
+
+/// What the registry knows about one published version of a package
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageInfo {
+    /// Metadata of the version, including its tarball hashes and size
+    #[serde(flatten)]
+    pub metadata: VersionMetadata,
+
+    /// Version the `latest` dist-tag points to
+    pub latest: Option<String>,
+
+    /// When the version was published, as an ISO 8601 timestamp
+    pub published: Option<String>,
+}
+
+impl PackageInfo {
+    /// Labeled lines describing the version; missing fields are left out
+    pub fn lines(&self) -> Vec<(&'static str, String)> {
+        let metadata = &self.metadata;
+        let dist = &metadata.dist;
+        let mut lines = vec![("name", metadata.name.clone())];
+        let mut push = |label, value: Option<String>| {
+            if let Some(value) = value {
+                lines.push((label, value));
+            }
+        };
+
+        push("description", metadata.description.clone());
+        push("license", metadata.license().map(str::to_string));
+        push(
+            "version",
+            Some(match &self.latest {
+                Some(latest) if *latest != metadata.version => {
+                    format!("{} (latest is {})", metadata.version, latest)
+                }
+                _ => metadata.version.clone(),
+            }),
+        );
+        push("published", self.published.clone());
+        push("repository", metadata.repository().map(str::to_string));
+        push("tarball", dist.tarball.clone());
+        push(
+            "size",
+            dist.unpacked_size.map(|size| {
+                let files = dist
+                    .file_count
+                    .map(|count| format!(" in {} files", count))
+                    .unwrap_or_default();
+                format!("{} unpacked{}", progress::format_bytes(size), files)
+            }),
+        );
+        push("integrity", dist.integrity.clone());
+        push("shasum", dist.shasum.clone());
+        push(
+            "maintainers",
+            (!metadata.maintainers.is_empty()).then(|| {
+                metadata
+                    .maintainers
+                    .iter()
+                    .map(|person| match &person.email {
+                        Some(email) => format!("{} <{}>", person.name, email),
+                        None => person.name.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }),
+        );
+        lines
+    }
+}
+
+/// Look up a published version of a package on the registry
+///
+/// The version is resolved like `cargonode add` does: a dist-tag or range
+/// picks the highest matching version, and no version picks `latest`.
+///
+/// # Arguments
+///
+/// * `spec` - Package to look up, as `name[@version]`
+/// * `client` - Registry client
+///
+/// # Returns
+///
+/// * `Result<PackageInfo>` - The version's metadata, or `Error::Registry` if
+///   the package or a matching version does not exist
+pub fn package_info(spec: &str, client: &Client) -> Result<PackageInfo> {
+    let spec = DependencySpec::parse(spec, DependencyKind::Normal)?;
+    let document = client.get_package_document(&spec.name)?;
+    let requirements: Vec<&str> = spec.version_req.as_deref().into_iter().collect();
+    let version = dependency::resolve_version(&document, &requirements)?
+        .ok_or_else(|| Error::Registry {
+            message: format!(
+                "No version of '{}' matches '{}'",
+                spec.name,
+                spec.version_req.as_deref().unwrap_or("latest")
+            ),
+        })?
+        .to_string();
+
+    let mut metadata = client.get_version_metadata(&spec.name, &version)?;
+    // Older version documents lack fields the package document has
+    if metadata.description.is_none() {
+        metadata.description = document.description.clone();
+    }
+    if metadata.maintainers.is_empty() {
+        metadata.maintainers = document.maintainers.clone();
+    }
+    if metadata.repository.is_none() {
+        metadata.repository = document.repository.clone();
+    }
+
+    Ok(PackageInfo {
+        latest: document.dist_tags.get("latest").cloned(),
+        published: document.published(&version).map(str::to_string),
+        metadata,
+    })
+}
+
+/// Print a package version's metadata
+///
+/// # Arguments
+///
+/// * `info` - Result of `package_info`
+/// * `json` - Print the metadata as one line of JSON instead of labeled lines
+///
+/// # Returns
+///
+/// * `Result<()>` - Whether the output could be written
+pub fn report_package_info(info: &PackageInfo, json: bool) -> Result<()> {
+    if json {
+        progress::write_json("info", info)?;
+        return Ok(());
+    }
+
+    let lines = info.lines();
+    let width = lines
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0);
+    let text = lines
+        .iter()
+        .map(|(label, value)| format!("{:width$}  {}", label, value, width = width))
+        .collect::<Vec<_>>()
+        .join("\n");
+    progress::write_message(&text)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::progress::{Event, RecordingSink};
+    use crate::test_utils::serve_sequence;
+
+    #[test]
+    fn test_package_info() {
+        let document = r#"{
+            "name": "left-pad",
+            "description": "String left pad",
+            "dist-tags": { "latest": "1.3.0" },
+            "versions": { "1.1.0": {}, "1.3.0": {} },
+            "time": { "1.1.0": "2016-03-23T10:00:00.000Z", "1.3.0": "2018-04-09T10:00:00.000Z" },
+            "maintainers": ["stevemao <maochenyan@gmail.com>"]
+        }"#;
+        // An old version document without dist sizes, maintainers or description
+        let version = r#"{
+            "name": "left-pad",
+            "version": "1.1.0",
+            "license": { "type": "WTFPL" },
+            "repository": { "type": "git", "url": "git+https://github.com/stevemao/left-pad.git" },
+            "dist": {
+                "shasum": "612f61c033f3a9e08e939f1caebeea41b6f6199a",
+                "tarball": "https://registry.npmjs.org/left-pad/-/left-pad-1.1.0.tgz"
+            }
+        }"#;
+        let (base_url, handle) = serve_sequence(vec![
+            (200, String::new(), document.as_bytes().to_vec()),
+            (200, String::new(), version.as_bytes().to_vec()),
+        ]);
+        let client = Client::with_registry(&base_url).unwrap();
+
+        let info = package_info("left-pad@~1.1", &client).unwrap();
+        assert_eq!(info.metadata.version, "1.1.0");
+        assert_eq!(info.published.as_deref(), Some("2016-03-23T10:00:00.000Z"));
+        assert_eq!(
+            info.metadata.description.as_deref(),
+            Some("String left pad")
+        );
+        assert_eq!(info.metadata.maintainers[0].name, "stevemao");
+
+        let lines = info.lines();
+        assert!(lines.contains(&("version", "1.1.0 (latest is 1.3.0)".to_string())));
+        assert!(lines.contains(&("license", "WTFPL".to_string())));
+        assert!(lines.contains(&("maintainers", "stevemao <maochenyan@gmail.com>".to_string())));
+        assert!(!lines.iter().any(|(label, _)| *label == "size"));
+
+        let sink = Arc::new(RecordingSink::default());
+        progress::with_sink(sink.clone(), || report_package_info(&info, true)).unwrap();
+        let events = sink.events();
+        let [Event::Output { command, data }] = events.as_slice() else {
+            panic!("expected one output event, got {:?}", events);
+        };
+        assert_eq!(command, "info");
+        assert_eq!(data["version"], "1.1.0");
+        assert!(sink.messages().is_empty());
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("GET /left-pad HTTP/1.1"));
+        assert!(requests[1].starts_with("GET /left-pad/1.1.0 HTTP/1.1"));
+    }
+
+    #[test]
+    fn test_package_info_without_match() {
+        let document =
+            r#"{"name": "left-pad", "dist-tags": {"latest": "1.3.0"}, "versions": {"1.3.0": {}}}"#;
+        let (base_url, _handle) =
+            serve_sequence(vec![(200, String::new(), document.as_bytes().to_vec())]);
+        let client = Client::with_registry(&base_url).unwrap();
+
+        let Err(Error::Registry { message }) = package_info("left-pad@^2", &client) else {
+            panic!("expected no version to match");
+        };
+        assert_eq!(message, "No version of 'left-pad' matches '^2'");
+    }
+}
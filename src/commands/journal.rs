@@ -0,0 +1,682 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::cache::{self, Cache, CacheKey, CacheStats, ToolCacheStats};
+use crate::config::CacheConfig;
+use crate::journal::{HitRate, Journal, JournalEntry, ToolStats};
+use crate::Result;
+use crate::{progress, utils};
+
+/// Format a Unix timestamp as `YYYY-MM-DD HH:MM:SS` (UTC)
+fn format_timestamp(timestamp: u64) -> String {
+    let (year, month, day) = utils::utc_date(timestamp);
+    let seconds = timestamp % 86_400;
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+/// Format a duration in milliseconds, e.g. `850 ms` or `12.40s`
+fn format_duration_ms(duration_ms: u64) -> String {
+    if duration_ms < 1_000 {
+        return format!("{} ms", duration_ms);
+    }
+    format!("{:.2}s", duration_ms as f64 / 1_000.0)
+}
+
+/// Format a journal entry for display
+///
+/// # Arguments
+///
+/// * `entry` - The entry to format
+/// * `verbose` - Whether to include the command line, input hash and steps
+///
+/// # Returns
+///
+/// * `String` - The formatted entry
+pub fn format_journal_entry(entry: &JournalEntry, verbose: bool) -> String {
+    let outcome = match (entry.exit_code, entry.from_cache) {
+        (0, true) => "ok (cached)".to_string(),
+        (0, false) => "ok".to_string(),
+        (code, _) => format!("failed (exit code {})", code),
+    };
+
+    let mut summary = format!(
+        "{}  {}  {}",
+        format_timestamp(entry.timestamp),
+        entry.tool,
+        outcome
+    );
+    // Cached runs and entries from before durations were recorded have none
+    if entry.duration_ms > 0 && !entry.from_cache {
+        summary.push_str(&format!("  {}", format_duration_ms(entry.duration_ms)));
+    }
+
+    if !verbose {
+        return summary;
+    }
+
+    let mut formatted = format!(
+        "{}\n    command: {}\n    input hash: {}",
+        summary, entry.command, entry.input_hash
+    );
+    for (index, step) in entry.steps.iter().enumerate() {
+        formatted.push_str(&format!(
+            "\n    step {}: {} (exit code {}, {} ms)",
+            index + 1,
+            step.command,
+            step.exit_code,
+            step.duration_ms
+        ));
+    }
+    formatted
+}
+
+/// Format what a cached run was keyed on, as indented lines to follow the
+/// entry's verbose description
+///
+/// # Arguments
+///
+/// * `key` - Components of the cache key
+///
+/// # Returns
+///
+/// * `String` - The formatted components
+pub fn format_cache_key(key: &CacheKey) -> String {
+    let mut formatted = format!("    keyed on:\n      inputs: {}", key.inputs);
+    for command in &key.commands {
+        formatted.push_str(&format!("\n      command: {}", command.join(" ")));
+    }
+    for variable in &key.env {
+        formatted.push_str(&format!("\n      env: {}", variable));
+    }
+    if let Some(version) = &key.node_version {
+        formatted.push_str(&format!("\n      node: {}", version));
+    }
+    if let Some(version) = &key.tool_version {
+        formatted.push_str(&format!("\n      tool version: {}", version));
+    }
+    formatted
+}
+
+/// Read the command history, most recent first
+///
+/// # Arguments
+///
+/// * `journal_dir` - Directory holding the journal
+/// * `tool` - Only return entries for this tool, if given
+/// * `limit` - Maximum number of entries to return
+///
+/// # Returns
+///
+/// * `Result<Vec<JournalEntry>>` - The matching entries
+pub fn history_entries(
+    journal_dir: &Path,
+    tool: Option<&str>,
+    limit: Option<usize>,
+) -> Result<Vec<JournalEntry>> {
+    Ok(Journal::new(journal_dir)
+        .read_entries()?
+        .into_iter()
+        .rev()
+        .filter(|entry| tool.is_none_or(|tool| entry.tool == tool))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect())
+}
+
+/// Print the command history
+///
+/// # Arguments
+///
+/// * `journal_dir` - Directory holding the journal
+/// * `cache_dir` - Directory holding the cache, whose entries tell what each
+///   run was keyed on
+/// * `tool` - Only show entries for this tool, if given
+/// * `limit` - Maximum number of entries to show, most recent first
+/// * `verbose` - Whether to print detailed entries, with their cache keys
+/// * `json` - Print each entry as a line of JSON instead
+///
+/// # Returns
+///
+/// * `Result<()>` - Whether the history was printed
+pub fn show_history(
+    journal_dir: &Path,
+    cache_dir: &Path,
+    tool: Option<&str>,
+    limit: Option<usize>,
+    verbose: bool,
+    json: bool,
+) -> Result<()> {
+    let entries = history_entries(journal_dir, tool, limit)?;
+
+    if json {
+        let mut stdout = io::stdout().lock();
+        for entry in &entries {
+            serde_json::to_writer(&mut stdout, entry)?;
+            writeln!(stdout)?;
+        }
+        stdout.flush()?;
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        progress::note("No command history found")?;
+        return Ok(());
+    }
+
+    let cache = Cache::new(cache_dir);
+    for entry in &entries {
+        let mut formatted = format_journal_entry(entry, verbose);
+        // Entries from before keys were recorded, or since pruned, have none
+        let key = verbose
+            .then(|| cache.stored_entry(&entry.tool, &entry.input_hash))
+            .flatten()
+            .and_then(|cached| cached.key);
+        if let Some(key) = key {
+            formatted.push('\n');
+            formatted.push_str(&format_cache_key(&key));
+        }
+        progress::write_message(&formatted)?;
+    }
+
+    Ok(())
+}
+
+/// Recorded runs of one tool
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct ToolHistoryReport {
+    tool: String,
+    #[serde(flatten)]
+    stats: ToolStats,
+}
+
+/// Format the tool statistics as a table with run count and duration columns
+fn history_table(stats: &BTreeMap<String, ToolStats>) -> String {
+    let width = stats
+        .keys()
+        .map(String::len)
+        .chain(["Tool".len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut lines = vec![format!(
+        "{:<width$}  {:>5}  {:>6}  {:>9}  {:>9}  {:>9}",
+        "Tool", "Runs", "Cached", "Mean", "Median", "Max"
+    )];
+    for (tool, stats) in stats {
+        let duration = |duration_ms| match stats.runs - stats.hits {
+            0 => "-".to_string(),
+            _ => format_duration_ms(duration_ms),
+        };
+        lines.push(format!(
+            "{:<width$}  {:>5}  {:>6}  {:>9}  {:>9}  {:>9}",
+            tool,
+            stats.runs,
+            stats.hits,
+            duration(stats.mean_ms),
+            duration(stats.median_ms),
+            duration(stats.max_ms)
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Print how often each tool ran and how long its runs took
+///
+/// The statistics cover every run still in the journal.
+///
+/// # Arguments
+///
+/// * `journal_dir` - Directory holding the journal
+/// * `tool` - Only show this tool, if given
+/// * `json` - Print the statistics as a JSON object instead
+///
+/// # Returns
+///
+/// * `Result<BTreeMap<String, ToolStats>>` - The statistics, keyed by tool
+pub fn history_stats(
+    journal_dir: &Path,
+    tool: Option<&str>,
+    json: bool,
+) -> Result<BTreeMap<String, ToolStats>> {
+    let mut stats = Journal::new(journal_dir).stats_by_tool()?;
+    stats.retain(|name, _| tool.is_none_or(|tool| name == tool));
+
+    if json {
+        let tools: Vec<ToolHistoryReport> = stats
+            .iter()
+            .map(|(tool, stats)| ToolHistoryReport {
+                tool: tool.clone(),
+                stats: *stats,
+            })
+            .collect();
+        let mut stdout = io::stdout().lock();
+        serde_json::to_writer(&mut stdout, &serde_json::json!({ "tools": tools }))?;
+        writeln!(stdout)?;
+        stdout.flush()?;
+        return Ok(stats);
+    }
+
+    if stats.is_empty() {
+        progress::note("No command history found")?;
+        return Ok(stats);
+    }
+    progress::write_message(&history_table(&stats))?;
+    Ok(stats)
+}
+
+/// Remove cached tool runs
+///
+/// # Arguments
+///
+/// * `cache_dir` - Directory holding the cache
+/// * `tool` - Only remove entries for this tool, if given
+///
+/// # Returns
+///
+/// * `Result<usize>` - Number of entries removed
+pub fn clear_cache(cache_dir: &Path, tool: Option<&str>) -> Result<usize> {
+    let removed = Cache::new(cache_dir).clear(tool)?;
+
+    let scope = tool
+        .map(|tool| format!(" for tool '{}'", tool))
+        .unwrap_or_default();
+    progress::status(
+        "Removed",
+        &format!(
+            "{} cache entr{}{}",
+            removed,
+            if removed == 1 { "y" } else { "ies" },
+            scope
+        ),
+    )?;
+
+    Ok(removed)
+}
+
+/// Remove expired cached tool runs and trim the cache to its configured budget
+///
+/// # Arguments
+///
+/// * `cache_dir` - Directory holding the cache
+/// * `config` - TTL and size budget of the cache
+///
+/// # Returns
+///
+/// * `Result<usize>` - Number of entries removed
+pub fn prune_cache(cache_dir: &Path, config: &CacheConfig) -> Result<usize> {
+    let stats = Cache::new(cache_dir).with_ttl(config.ttl()).prune(config)?;
+
+    progress::status(
+        "Removed",
+        &format!(
+            "{} cache entr{}, {:.1} KiB freed",
+            stats.removed,
+            if stats.removed == 1 { "y" } else { "ies" },
+            stats.freed_bytes as f64 / 1024.0
+        ),
+    )?;
+
+    Ok(stats.removed)
+}
+
+/// Cache usage and hit rate of one tool
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct ToolCacheReport {
+    tool: String,
+    #[serde(flatten)]
+    cache: ToolCacheStats,
+    #[serde(flatten)]
+    history: HitRate,
+}
+
+/// Match the journal's tools to their cache entries, which are keyed by the
+/// sanitized tool name
+fn tool_reports(stats: &CacheStats, rates: &BTreeMap<String, HitRate>) -> Vec<ToolCacheReport> {
+    let mut reports: Vec<ToolCacheReport> = rates
+        .iter()
+        .map(|(tool, history)| ToolCacheReport {
+            tool: tool.clone(),
+            cache: stats
+                .tools
+                .get(&cache::sanitize(tool))
+                .copied()
+                .unwrap_or_default(),
+            history: *history,
+        })
+        .collect();
+    for (tool, cache) in &stats.tools {
+        if !rates.keys().any(|name| cache::sanitize(name) == *tool) {
+            reports.push(ToolCacheReport {
+                tool: tool.clone(),
+                cache: *cache,
+                history: HitRate::default(),
+            });
+        }
+    }
+    reports.sort_by(|a, b| a.tool.cmp(&b.tool));
+    reports
+}
+
+/// Format the tool reports as a table with entries, size and hit rate columns
+fn stats_table(reports: &[ToolCacheReport]) -> String {
+    let width = reports
+        .iter()
+        .map(|report| report.tool.len())
+        .chain(["Tool".len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut lines = vec![format!(
+        "{:<width$}  {:>7}  {:>10}  Hit rate",
+        "Tool", "Entries", "Size"
+    )];
+    for report in reports {
+        let hit_rate = match report.history.runs {
+            0 => "-".to_string(),
+            runs => format!(
+                "{:.0}% ({}/{})",
+                report.history.rate() * 100.0,
+                report.history.hits,
+                runs
+            ),
+        };
+        lines.push(format!(
+            "{:<width$}  {:>7}  {:>10}  {}",
+            report.tool,
+            report.cache.entries,
+            format!("{:.1} KiB", report.cache.bytes as f64 / 1024.0),
+            hit_rate
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Print what the cache holds and how often each tool's runs were cached
+///
+/// Hit rates come from the journal, so they cover the recorded history
+/// rather than only the entries still in the cache.
+///
+/// # Arguments
+///
+/// * `cache_dir` - Directory holding the cache
+/// * `journal_dir` - Directory holding the journal
+/// * `json` - Print the statistics as a JSON object instead
+///
+/// # Returns
+///
+/// * `Result<CacheStats>` - What the cache holds
+pub fn cache_stats(cache_dir: &Path, journal_dir: &Path, json: bool) -> Result<CacheStats> {
+    let stats = Cache::new(cache_dir).stats()?;
+    let rates = Journal::new(journal_dir).hit_rate_by_tool()?;
+    let reports = tool_reports(&stats, &rates);
+
+    if json {
+        let mut stdout = io::stdout().lock();
+        serde_json::to_writer(
+            &mut stdout,
+            &serde_json::json!({
+                "entries": stats.entries,
+                "total_bytes": stats.total_bytes,
+                "oldest": stats.oldest,
+                "newest": stats.newest,
+                "tools": reports,
+            }),
+        )?;
+        writeln!(stdout)?;
+        stdout.flush()?;
+        return Ok(stats);
+    }
+
+    progress::status(
+        "Cache",
+        &format!(
+            "{} entr{}, {:.1} KiB in {}",
+            stats.entries,
+            if stats.entries == 1 { "y" } else { "ies" },
+            stats.total_bytes as f64 / 1024.0,
+            cache_dir.display()
+        ),
+    )?;
+    if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+        progress::note(&format!(
+            "oldest entry written {}, newest {} (UTC)",
+            format_timestamp(oldest),
+            format_timestamp(newest)
+        ))?;
+    }
+    if !reports.is_empty() {
+        progress::write_message(&stats_table(&reports))?;
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::cache::CacheEntry;
+    use crate::journal::EntryKind;
+
+    fn entry(exit_code: i32, from_cache: bool) -> JournalEntry {
+        JournalEntry {
+            tool: "build".to_string(),
+            kind: EntryKind::Tool,
+            command: "tsc --build".to_string(),
+            input_hash: "abc123".to_string(),
+            exit_code,
+            from_cache,
+            timestamp: 1_742_947_200,
+            duration_ms: 0,
+            steps: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00");
+        assert_eq!(format_timestamp(1_742_947_200), "2025-03-26 00:00:00");
+        assert_eq!(format_timestamp(951_782_400 + 3_661), "2000-02-29 01:01:01");
+    }
+
+    #[test]
+    fn test_format_journal_entry() {
+        assert_eq!(
+            format_journal_entry(&entry(0, true), false),
+            "2025-03-26 00:00:00  build  ok (cached)"
+        );
+        assert_eq!(
+            format_journal_entry(&entry(2, false), false),
+            "2025-03-26 00:00:00  build  failed (exit code 2)"
+        );
+
+        let timed = JournalEntry {
+            duration_ms: 12_400,
+            ..entry(0, false)
+        };
+        assert_eq!(
+            format_journal_entry(&timed, false),
+            "2025-03-26 00:00:00  build  ok  12.40s"
+        );
+
+        let verbose = format_journal_entry(&entry(0, false), true);
+        assert!(verbose.contains("command: tsc --build"));
+        assert!(verbose.contains("input hash: abc123"));
+    }
+
+    #[test]
+    fn test_format_cache_key() {
+        let key = CacheKey {
+            inputs: "f00d".to_string(),
+            commands: vec![vec!["tsc".to_string(), "--build".to_string()]],
+            env: vec!["NODE_ENV=production".to_string()],
+            node_version: Some("22.3.0".to_string()),
+            tool_version: Some("Version 5.4.5".to_string()),
+        };
+        assert_eq!(
+            format_cache_key(&key),
+            "    keyed on:\n      inputs: f00d\n      command: tsc --build\n      \
+             env: NODE_ENV=production\n      node: 22.3.0\n      tool version: Version 5.4.5"
+        );
+    }
+
+    #[test]
+    fn test_show_history_without_journal() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        show_history(
+            &temp_dir.path().join("missing"),
+            &temp_dir.path().join("cache"),
+            None,
+            None,
+            false,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_history_entries() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let journal = Journal::new(temp_dir.path());
+        for (tool, timestamp) in [("build", 1), ("test", 2), ("build", 3)] {
+            journal.add_entry(JournalEntry {
+                tool: tool.to_string(),
+                timestamp,
+                ..entry(0, false)
+            })?;
+        }
+
+        let timestamps = |entries: Vec<JournalEntry>| -> Vec<u64> {
+            entries.iter().map(|entry| entry.timestamp).collect()
+        };
+        assert_eq!(
+            timestamps(history_entries(temp_dir.path(), None, None)?),
+            vec![3, 2, 1]
+        );
+        assert_eq!(
+            timestamps(history_entries(temp_dir.path(), Some("build"), Some(1))?),
+            vec![3]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_history_table() {
+        let stats = BTreeMap::from([
+            (
+                "test".to_string(),
+                ToolStats {
+                    runs: 3,
+                    hits: 1,
+                    mean_ms: 47_500,
+                    median_ms: 47_500,
+                    max_ms: 90_000,
+                },
+            ),
+            (
+                "lint".to_string(),
+                ToolStats {
+                    runs: 1,
+                    hits: 1,
+                    ..ToolStats::default()
+                },
+            ),
+        ]);
+        assert_eq!(
+            history_table(&stats),
+            "Tool   Runs  Cached       Mean     Median        Max\n\
+             lint      1       1          -          -          -\n\
+             test      3       1     47.50s     47.50s     90.00s"
+        );
+    }
+
+    #[test]
+    fn test_clear_cache() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache = Cache::new(temp_dir.path());
+        for (tool, hash) in [("build", "a1"), ("test", "b2")] {
+            cache.store_entry(&CacheEntry {
+                tool: tool.to_string(),
+                input_hash: hash.to_string(),
+                exit_code: 0,
+                timestamp: 0,
+                outputs: Vec::new(),
+                max_age_seconds: None,
+                key: None,
+            })?;
+        }
+
+        assert_eq!(clear_cache(temp_dir.path(), Some("test"))?, 1);
+        assert_eq!(clear_cache(temp_dir.path(), None)?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_stats() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache_dir = temp_dir.path().join("cache");
+        let journal_dir = temp_dir.path().join("journal");
+        let cache = Cache::new(&cache_dir);
+        for (tool, hash) in [("lint:fix", "a1"), ("old", "b2")] {
+            cache.store_entry(&CacheEntry {
+                tool: tool.to_string(),
+                input_hash: hash.to_string(),
+                exit_code: 0,
+                timestamp: 0,
+                outputs: Vec::new(),
+                max_age_seconds: None,
+                key: None,
+            })?;
+        }
+        let journal = Journal::new(&journal_dir);
+        for (tool, from_cache) in [("lint:fix", false), ("lint:fix", true), ("test", false)] {
+            journal.add_entry(JournalEntry {
+                tool: tool.to_string(),
+                ..entry(0, from_cache)
+            })?;
+        }
+
+        let stats = cache_stats(&cache_dir, &journal_dir, false)?;
+        assert_eq!(stats.entries, 2);
+
+        // Journal tools find their entries under the sanitized name, and
+        // tools only found in the cache are listed too
+        let reports = tool_reports(&stats, &journal.hit_rate_by_tool()?);
+        let rows: Vec<(&str, usize, usize, usize)> = reports
+            .iter()
+            .map(|report| {
+                (
+                    report.tool.as_str(),
+                    report.cache.entries,
+                    report.history.hits,
+                    report.history.runs,
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![("lint:fix", 1, 1, 2), ("old", 1, 0, 0), ("test", 0, 0, 1)]
+        );
+
+        let table = stats_table(&reports);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "Tool      Entries        Size  Hit rate");
+        assert!(lines[1].ends_with("  50% (1/2)"));
+        assert!(lines[2].ends_with("  -"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,421 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::config::{self, CommandStep, ToolConfig};
+use crate::package_manager::{self, DetectedPackageManager};
+use crate::{cache, journal, Result};
+
+/// Version of the `cargonode metadata` schema
+///
+/// Bumped whenever a field is removed or changes meaning; new fields may be
+/// added without a bump.
+pub const METADATA_VERSION: u32 = 1;
+
+/// Format `cargonode metadata` is printed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum MetadataFormat {
+    /// A single line of JSON (default)
+    #[default]
+    Json,
+}
+
+/// Tools that run without configuration, see `config::default_tool_config`
+const DEFAULT_TOOLS: [&str; 4] = ["check", "fmt", "build", "test"];
+
+/// Everything cargonode knows about a project, for editors and scripts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProjectMetadata {
+    /// Version of the schema, see [`METADATA_VERSION`]
+    pub version: u32,
+
+    /// Directory containing the project's package.json
+    pub project_root: Option<PathBuf>,
+
+    /// `name` field of package.json
+    pub name: Option<String>,
+
+    /// `version` field of package.json
+    pub package_version: Option<String>,
+
+    /// Package manager the project uses
+    pub package_manager: Option<PackageManagerMetadata>,
+
+    /// Workspace the project belongs to, if any
+    pub workspace: Option<WorkspaceMetadata>,
+
+    /// Tools cargonode runs, configured or detected
+    pub tools: BTreeMap<String, ToolMetadata>,
+
+    /// Directory holding the cache of tool runs
+    pub cache_dir: Option<PathBuf>,
+
+    /// Directory holding the journal of tool runs
+    pub journal_dir: Option<PathBuf>,
+}
+
+/// The package manager of a project
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageManagerMetadata {
+    /// Executable of the package manager, e.g. `pnpm`
+    pub name: String,
+
+    /// Version pinned by the `packageManager` field
+    pub version: Option<String>,
+}
+
+impl From<DetectedPackageManager> for PackageManagerMetadata {
+    fn from(detected: DetectedPackageManager) -> Self {
+        Self {
+            name: detected.kind.to_string(),
+            version: detected.version.map(|version| version.to_string()),
+        }
+    }
+}
+
+/// A workspace and its members
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorkspaceMetadata {
+    /// Directory containing the root package.json
+    pub root: PathBuf,
+
+    /// Members, in the order of the `workspaces` patterns
+    pub members: Vec<WorkspaceMember>,
+}
+
+/// A member of a workspace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorkspaceMember {
+    /// Name from the member's package.json, or its directory name
+    pub name: String,
+
+    /// Directory containing the member's package.json
+    pub path: PathBuf,
+}
+
+/// Where a tool's configuration comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolSource {
+    /// The project's cargonode configuration
+    Configured,
+    /// Detected from the project, for tools that need no configuration
+    Default,
+}
+
+/// A resolved tool configuration
+///
+/// Mirrors `ToolConfig` with sorted environment variables, so the output
+/// does not change between runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ToolMetadata {
+    /// Where the configuration comes from
+    pub source: ToolSource,
+    /// Why a default configuration was chosen, e.g. the linter config file
+    /// that was found
+    pub reason: Option<String>,
+    pub command: String,
+    pub steps: Option<Vec<StepMetadata>>,
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub working_dir: Option<String>,
+    pub inputs: Vec<String>,
+    pub exclude: Vec<String>,
+    pub outputs: Vec<String>,
+    pub outputs_optional: bool,
+    pub max_age_seconds: Option<u64>,
+    pub timeout_secs: Option<u64>,
+}
+
+impl ToolMetadata {
+    fn new(config: ToolConfig, source: ToolSource, reason: Option<String>) -> Self {
+        Self {
+            source,
+            reason,
+            command: config.command,
+            steps: config
+                .steps
+                .map(|steps| steps.into_iter().map(StepMetadata::from).collect()),
+            args: config.args,
+            env: config.env.into_iter().collect(),
+            working_dir: config.working_dir,
+            inputs: config.inputs,
+            exclude: config.exclude,
+            outputs: config.outputs,
+            outputs_optional: config.outputs_optional,
+            max_age_seconds: config.max_age_seconds,
+            timeout_secs: config.timeout_secs,
+        }
+    }
+}
+
+/// A step of a tool, see `CommandStep`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StepMetadata {
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub continue_on_error: bool,
+}
+
+impl From<CommandStep> for StepMetadata {
+    fn from(step: CommandStep) -> Self {
+        Self {
+            command: step.command,
+            args: step.args,
+            env: step.env.into_iter().collect(),
+            continue_on_error: step.continue_on_error,
+        }
+    }
+}
+
+/// Collect the metadata of the project containing `current_dir`
+///
+/// Outside of a project every field is null or empty rather than an error.
+///
+/// # Arguments
+///
+/// * `current_dir` - Directory to start looking for the project from
+///
+/// # Returns
+///
+/// * `Result<ProjectMetadata>` - The metadata, or an error if the project's
+///   package.json or configuration cannot be read
+pub fn project_metadata(current_dir: &Path) -> Result<ProjectMetadata> {
+    let mut metadata = ProjectMetadata {
+        version: METADATA_VERSION,
+        project_root: None,
+        name: None,
+        package_version: None,
+        package_manager: None,
+        workspace: None,
+        tools: BTreeMap::new(),
+        cache_dir: None,
+        journal_dir: None,
+    };
+    let Some(root) = package_manager::find_project_root(current_dir) else {
+        return Ok(metadata);
+    };
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(root.join("package.json"))?)?;
+    let field = |key: &str| manifest.get(key)?.as_str().map(str::to_string);
+    metadata.name = field("name");
+    metadata.package_version = field("version");
+    metadata.package_manager = Some(package_manager::detect_package_manager(&root)?.into());
+
+    // Outside of a workspace this is the project root, which lists no members
+    if let Some(workspace_root) = package_manager::find_workspace_root(&root)?
+        .filter(|dir| package_manager::workspace_patterns(dir).is_ok())
+    {
+        let members = package_manager::get_workspace_packages(&workspace_root)?
+            .into_iter()
+            .map(|package| WorkspaceMember {
+                name: package.name,
+                path: package.path,
+            })
+            .collect();
+        metadata.workspace = Some(WorkspaceMetadata {
+            root: workspace_root,
+            members,
+        });
+    }
+
+    let config = config::load_config(&root)?;
+    for (name, tool_config) in config.tools {
+        metadata.tools.insert(
+            name,
+            ToolMetadata::new(tool_config, ToolSource::Configured, None),
+        );
+    }
+    for name in DEFAULT_TOOLS {
+        if metadata.tools.contains_key(name) {
+            continue;
+        }
+        if let Some(default) = config::default_tool_config(name, &root, None) {
+            metadata.tools.insert(
+                name.to_string(),
+                ToolMetadata::new(default.config, ToolSource::Default, Some(default.reason)),
+            );
+        }
+    }
+
+    metadata.cache_dir = Some(cache::cache_dir(&root));
+    metadata.journal_dir = Some(journal::journal_dir(&root));
+    metadata.project_root = Some(root);
+    Ok(metadata)
+}
+
+/// Print the metadata on stdout
+///
+/// # Arguments
+///
+/// * `metadata` - The metadata to print
+/// * `format` - How to print it
+///
+/// # Returns
+///
+/// * `Result<()>` - An error if stdout cannot be written
+pub fn report_metadata(metadata: &ProjectMetadata, format: MetadataFormat) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    match format {
+        MetadataFormat::Json => {
+            serde_json::to_writer(&mut stdout, metadata)?;
+            writeln!(stdout)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_metadata_schema() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("packages/ui/src"))?;
+        fs::write(
+            root.join("package.json"),
+            r#"{
+                "name": "monorepo",
+                "version": "1.2.0",
+                "packageManager": "pnpm@9.1.0",
+                "workspaces": ["packages/*"],
+                "cargonode": {
+                    "tools": {
+                        "lint": {
+                            "command": "eslint",
+                            "args": ["."],
+                            "env": {"B": "2", "A": "1"},
+                            "inputs": ["src/**/*.ts"]
+                        }
+                    }
+                }
+            }"#,
+        )?;
+        fs::write(
+            root.join("packages/ui/package.json"),
+            r#"{"name": "@acme/ui"}"#,
+        )?;
+
+        let metadata = project_metadata(root)?;
+        let value = serde_json::to_value(&metadata)?;
+        let keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                "version",
+                "project_root",
+                "name",
+                "package_version",
+                "package_manager",
+                "workspace",
+                "tools",
+                "cache_dir",
+                "journal_dir"
+            ]
+        );
+        assert_eq!(value["version"], json!(METADATA_VERSION));
+        assert_eq!(value["name"], "monorepo");
+        assert_eq!(value["package_version"], "1.2.0");
+        assert_eq!(
+            value["package_manager"],
+            json!({"name": "pnpm", "version": "9.1.0"})
+        );
+        assert_eq!(
+            value["workspace"],
+            json!({
+                "root": root,
+                "members": [{"name": "@acme/ui", "path": root.join("packages/ui")}]
+            })
+        );
+        assert_eq!(
+            value["tools"]["lint"],
+            json!({
+                "source": "configured",
+                "reason": null,
+                "command": "eslint",
+                "steps": null,
+                "args": ["."],
+                "env": {"A": "1", "B": "2"},
+                "working_dir": null,
+                "inputs": ["src/**/*.ts"],
+                "exclude": [],
+                "outputs": [],
+                "outputs_optional": false,
+                "max_age_seconds": null,
+                "timeout_secs": null
+            })
+        );
+        // Tools that need no configuration are reported as detected
+        assert_eq!(value["tools"]["test"]["source"], "default");
+        assert_eq!(
+            value["tools"]["test"]["reason"],
+            "vitest is not a dependency"
+        );
+        // Without biome or prettier set up, there is no formatter to report
+        assert!(value["tools"].get("fmt").is_none());
+        assert_eq!(value["cache_dir"], json!(cache::cache_dir(root)));
+        assert_eq!(value["journal_dir"], json!(journal::journal_dir(root)));
+
+        // A member reports its own package and the workspace it belongs to
+        let member = project_metadata(&root.join("packages/ui/src"))?;
+        assert_eq!(member.project_root, Some(root.join("packages/ui")));
+        assert_eq!(member.name.as_deref(), Some("@acme/ui"));
+        assert_eq!(member.workspace, metadata.workspace);
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_without_project() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let value = serde_json::to_value(project_metadata(temp_dir.path())?)?;
+        assert_eq!(
+            value,
+            json!({
+                "version": METADATA_VERSION,
+                "project_root": null,
+                "name": null,
+                "package_version": null,
+                "package_manager": null,
+                "workspace": null,
+                "tools": {},
+                "cache_dir": null,
+                "journal_dir": null
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_single_package() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(temp_dir.path().join("package.json"), r#"{"name": "app"}"#)?;
+        fs::write(temp_dir.path().join("package-lock.json"), "{}")?;
+
+        let metadata = project_metadata(temp_dir.path())?;
+        assert_eq!(metadata.package_version, None);
+        assert_eq!(metadata.workspace, None);
+        assert_eq!(
+            metadata.package_manager,
+            Some(PackageManagerMetadata {
+                name: "npm".to_string(),
+                version: None,
+            })
+        );
+        Ok(())
+    }
+}
@@ -1,7 +1,49 @@
+mod add;
+mod clean;
+mod doctor;
 mod generic;
+mod import;
+mod info;
+mod journal;
+mod metadata;
 mod project;
+mod publish;
+mod remove;
 mod run;
+mod template;
+mod upgrade;
+mod version;
+mod watch;
+mod workspace;
 
-pub use generic::{build, check, run_generic_command, test};
-pub use project::{create_new_project, create_project, init_project};
+pub use add::{add_dependencies, AddOptions};
+pub use clean::{clean, CleanOptions, CleanReport};
+pub use doctor::{diagnose, report_checks, CheckStatus, DoctorCheck};
+pub use generic::{build, check, fmt, run_generic_command, test};
+pub use import::{import_scripts, ImportOptions, ImportedTool, ScriptImport, SkippedScript};
+pub use info::{package_info, report_package_info, PackageInfo};
+pub use journal::{
+    cache_stats, clear_cache, format_cache_key, format_journal_entry, history_entries,
+    history_stats, prune_cache, show_history,
+};
+pub use metadata::{
+    project_metadata, report_metadata, MetadataFormat, PackageManagerMetadata, ProjectMetadata,
+    StepMetadata, ToolMetadata, ToolSource, WorkspaceMember, WorkspaceMetadata, METADATA_VERSION,
+};
+pub use project::{
+    create_new_project, create_project, init_project, project_default_license, CreatedProject,
+    ProjectBuilder, ProjectOptions,
+};
+pub use publish::{preflight_checks, publish, report_pack_list, PublishCheck, PublishOptions};
+pub use remove::{remove_dependencies, RemoveOptions};
 pub use run::{run_tool, RunOptions, RunResult};
+pub use template::{
+    find_template, list_templates, report_template, report_templates, report_validation,
+    validate_template, TemplateEntry, TemplateValidation,
+};
+pub use upgrade::{upgrade_dependencies, UpgradeOptions, UpgradeReport, UpgradeRow};
+pub use version::{bump_version, VersionBump, VersionChange, VersionOptions};
+pub use watch::{watch, WatchOptions};
+pub use workspace::{
+    run_in_workspace, PackageOutcome, PackageStatus, WorkspaceOptions, WorkspaceSummary,
+};
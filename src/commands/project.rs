@@ -1,96 +1,1579 @@
-use std::{env, path::Path};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+    time::Duration,
+};
 
 use crate::{
-    config, progress,
+    ci::{self, CiProvider},
+    config,
+    dependency::{self, DependencyKind},
+    error::Error,
+    hooks::{self, HookContext},
+    manifest_merge::{self, MergeReport},
+    node,
+    npmrc::Npmrc,
+    package_manager::{self, DetectedPackageManager, PackageManager, WorkspacePackage},
+    progress,
+    registry::{self, RegistryClient},
     template::{self, ProjectType},
     utils, Result,
 };
 
+/// Git config key holding the license new projects default to
+const LICENSE_GIT_CONFIG: &str = "cargonode.license";
+
+/// Options for creating or initializing a project
+#[derive(Debug, Clone, Default)]
+pub struct ProjectOptions {
+    /// Package name (taken from the directory name when not set)
+    pub name: Option<String>,
+
+    /// Type of package to create
+    pub project_type: ProjectType,
+
+    /// Version control to set up (git when not set)
+    pub vcs_config: Option<utils::VcsConfig>,
+
+    /// Template directory or archive to scaffold from
+    pub template: Option<PathBuf>,
+
+    /// Template variant built into cargonode to scaffold from, e.g.
+    /// `typescript-lib`; ignored when `template` is set
+    pub template_variant: Option<String>,
+
+    /// Package manager used to install dependencies (detected when not set)
+    pub package_manager: Option<PackageManager>,
+
+    /// Install dependencies after scaffolding
+    pub install: bool,
+
+    /// Author recorded in package.json and the LICENSE file
+    pub author: Option<String>,
+
+    /// SPDX identifier of the project's license
+    pub license: Option<String>,
+
+    /// Leave a partially created project in place when scaffolding fails
+    pub keep_on_failure: bool,
+
+    /// Workspace member that gets the new package as a dependency
+    pub dependent: Option<String>,
+
+    /// Where to scaffold a starter tool configuration (none when not set)
+    pub config_file: Option<config::ConfigFile>,
+
+    /// Template features to enable
+    pub features: Vec<String>,
+
+    /// Initialize even if package.json exists, only adding missing files
+    pub force: bool,
+
+    /// Merge the scaffolded package.json into an existing one instead of
+    /// refusing to initialize
+    pub merge: bool,
+
+    /// Accept a name that shadows a Node.js core module or imitates a
+    /// popular package, with a warning
+    pub allow_confusing_name: bool,
+
+    /// Print what would be created without changing anything on disk
+    pub dry_run: bool,
+
+    /// Node.js versions the project supports (the installed LTS line when
+    /// not set)
+    pub node: Option<String>,
+
+    /// Registry a new package's name is looked up on (no lookup when not set)
+    pub registry_url: Option<String>,
+
+    /// CI service to generate a workflow for
+    pub ci: CiProvider,
+
+    /// Scaffolding hooks from the user configuration, for the events the
+    /// project's own configuration has no hook for
+    pub hooks: config::HooksConfig,
+}
+
+/// Time the name availability check may take before it is given up
+const NAME_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Outcome of looking up a new package's name on the registry
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NameCheck {
+    /// Scoped names are not looked up, as private scopes are invisible
+    Skipped,
+    /// Nothing is published under the name
+    Available,
+    /// A package is published under the name
+    Taken {
+        /// Its `latest` dist-tag, if it could be read
+        latest: Option<String>,
+    },
+    /// The registry could not be asked
+    Failed(String),
+}
+
+/// Look up whether `name` is still free on the registry at `registry_url`
+///
+/// # Arguments
+///
+/// * `name` - Validated package name
+/// * `registry_url` - Base URL of the registry
+/// * `timeout` - Time after which each request is given up
+///
+/// # Returns
+///
+/// * `NameCheck` - The outcome; errors are reported in it rather than returned
+fn check_name(name: &str, registry_url: &str, timeout: Duration) -> NameCheck {
+    if name.starts_with('@') {
+        return NameCheck::Skipped;
+    }
+    let summary = |error: Error| match error {
+        Error::Network { message } | Error::Registry { message } => message,
+        other => {
+            let message = other.message();
+            let line = message.lines().last().unwrap_or_default();
+            line.strip_prefix("Error: ").unwrap_or(line).to_string()
+        }
+    };
+    let client = match registry::Client::with_registry(registry_url) {
+        Ok(client) => client
+            .with_timeout(timeout)
+            .with_retry(registry::RetryPolicy::never()),
+        Err(e) => return NameCheck::Failed(summary(e)),
+    };
+    // The package document answers both questions in a single request
+    match client.get_package_info(name) {
+        Ok(info) => NameCheck::Taken {
+            latest: info.dist_tags.get("latest").cloned(),
+        },
+        Err(Error::PackageNotFound { .. }) => NameCheck::Available,
+        Err(e) => NameCheck::Failed(summary(e)),
+    }
+}
+
+/// Print whether `name` is still free on the registry
+///
+/// A taken name or an unreachable registry is only a warning; the project is
+/// created either way.
+fn report_name_check(name: &str, registry_url: &str) -> Result<()> {
+    let registry = registry_host(registry_url);
+    match check_name(name, registry_url, NAME_CHECK_TIMEOUT) {
+        NameCheck::Skipped => {}
+        NameCheck::Available => progress::status(
+            "Available",
+            &format!("`{}` is available on {}", name, registry),
+        )?,
+        NameCheck::Taken { latest } => progress::warn(&format!(
+            "`{}` is already taken on {}{}; pick another name before publishing",
+            name,
+            registry,
+            latest
+                .map(|version| format!(" (latest version {})", version))
+                .unwrap_or_default()
+        ))?,
+        NameCheck::Failed(reason) => progress::warn(&format!(
+            "could not check whether `{}` is available on {}: {}",
+            name, registry, reason
+        ))?,
+    }
+    Ok(())
+}
+
+/// Registry URL without its scheme or trailing slash, for messages
+fn registry_host(registry_url: &str) -> &str {
+    registry_url
+        .split_once("://")
+        .map_or(registry_url, |(_, rest)| rest)
+        .trim_end_matches('/')
+}
+
+/// Find the workspace member named by `--for`
+///
+/// # Arguments
+///
+/// * `workspace_root` - Root of the workspace the project is created in
+/// * `path` - Directory of the new project
+/// * `member` - Name of the member that will depend on the new project
+///
+/// # Returns
+///
+/// * `Result<WorkspacePackage>` - The member, or `Error::Config` if there is
+///   no workspace or no such member
+fn find_dependent(
+    workspace_root: Option<&Path>,
+    path: &Path,
+    member: &str,
+) -> Result<WorkspacePackage> {
+    let Some(workspace_root) = workspace_root else {
+        return Err(Error::Config {
+            message: format!(
+                "`--for {}` needs a workspace, but no package.json above {} declares `workspaces`",
+                member,
+                path.display()
+            ),
+            hint: None,
+        });
+    };
+    package_manager::get_workspace_packages(workspace_root)?
+        .into_iter()
+        .find(|package| package.name == member)
+        .ok_or_else(|| Error::Config {
+            message: format!("Package '{}' not found in the workspace", member),
+            hint: None,
+        })
+}
+
+/// Make the package.json of a new workspace member share the root's settings,
+/// warning if the root's `workspaces` patterns do not include it
+fn join_workspace(
+    workspace_root: &Path,
+    path: &Path,
+    package_json: &mut template::PackageJson,
+) -> Result<()> {
+    let root_manifest = fs::read_to_string(workspace_root.join("package.json"))?;
+    package_json.inherit_workspace(&serde_json::from_str(&root_manifest)?);
+
+    let path = std::path::absolute(path)?;
+    let relative = path
+        .strip_prefix(workspace_root)
+        .unwrap_or(&path)
+        .to_string_lossy()
+        .replace('\\', "/");
+    if package_manager::is_workspace_member_path(workspace_root, &path)? {
+        progress::status(
+            "Adding",
+            &format!(
+                "`{}` to the workspace at {}",
+                relative,
+                workspace_root.display()
+            ),
+        )?;
+    } else {
+        let pattern = match relative.rsplit_once('/') {
+            Some((parent, _)) => format!("{}/*", parent),
+            None => relative.clone(),
+        };
+        progress::warn(&format!(
+            "`{}` is not matched by the `workspaces` patterns in {}; add \"{}\" to include it",
+            relative,
+            workspace_root.join("package.json").display(),
+            pattern
+        ))?;
+    }
+    Ok(())
+}
+
+/// Record the new package as a dependency of an existing workspace member
+fn add_to_dependent(dependent: &WorkspacePackage, name: &str, range: &str) -> Result<()> {
+    let manifest_path = dependent.path.join("package.json");
+    let original = fs::read_to_string(&manifest_path)?;
+    let mut manifest: serde_json::Value = serde_json::from_str(&original)?;
+    dependency::insert_dependency(&mut manifest, name, range, DependencyKind::Normal)?;
+    dependency::write_manifest(&manifest_path, &manifest, &original)?;
+    progress::status(
+        "Adding",
+        &format!(
+            "`{}@{}` to the dependencies of `{}`",
+            name, range, dependent.name
+        ),
+    )
+    .map_err(Error::from)
+}
+
+/// A project created by `ProjectBuilder`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreatedProject {
+    /// Directory the project was created in
+    pub path: PathBuf,
+    /// Name recorded in package.json
+    pub name: String,
+    /// Files written, relative to `path`, not counting version control
+    /// metadata or installed dependencies
+    pub files: Vec<PathBuf>,
+}
+
+/// Creates a Node.js project, for use from other Rust programs
+///
+/// Everything is checked before the first file is written: the package name,
+/// that the directory is empty (or has no package.json when initializing), the
+/// template and an explicitly chosen package manager. If writing fails
+/// partway, the files written so far are removed again.
+///
+/// By default a binary package is created with git, the MIT license (or the
+/// one configured in git or `.npmrc`), and no dependency install.
+///
+/// # Examples
+///
+/// ```
+/// use cargonode::commands::ProjectBuilder;
+/// use cargonode::template::ProjectType;
+/// use cargonode::utils::Vcs;
+///
+/// let dir = tempfile::tempdir()?;
+/// let project = ProjectBuilder::new(dir.path().join("my-lib"))
+///     .project_type(ProjectType::Library)
+///     .vcs(Vcs::None)
+///     .license("ISC")
+///     .author("Jane Doe")
+///     .build()?;
+///
+/// assert_eq!(project.name, "my-lib");
+/// assert!(project.files.iter().any(|file| file.ends_with("src/lib.js")));
+/// assert!(project.path.join("LICENSE").is_file());
+/// # Ok::<(), cargonode::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProjectBuilder {
+    path: PathBuf,
+    is_new: bool,
+    options: ProjectOptions,
+}
+
+impl ProjectBuilder {
+    /// Start a new project in `path`, which must be missing or empty
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            is_new: true,
+            options: ProjectOptions {
+                vcs_config: Some(utils::VcsConfig::default()),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Start a project in the existing directory `path`, which must not have
+    /// a package.json yet; files already there are kept
+    pub fn init(path: impl Into<PathBuf>) -> Self {
+        Self {
+            is_new: false,
+            ..Self::new(path)
+        }
+    }
+
+    /// Replace every option at once
+    pub fn options(mut self, options: ProjectOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Package name, instead of the one taken from the directory name
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.options.name = Some(name.into());
+        self
+    }
+
+    /// Whether to create a binary, a library or a CLI package
+    pub fn project_type(mut self, project_type: ProjectType) -> Self {
+        self.options.project_type = project_type;
+        self
+    }
+
+    /// Version control to set up, or `Vcs::None` for none
+    pub fn vcs(mut self, vcs: utils::Vcs) -> Self {
+        self.options.vcs_config = Some(utils::VcsConfig {
+            vcs,
+            ..Default::default()
+        });
+        self
+    }
+
+    /// Template directory or `.tar.gz` archive to scaffold from
+    pub fn template(mut self, template: impl Into<PathBuf>) -> Self {
+        self.options.template = Some(template.into());
+        self
+    }
+
+    /// Package manager to install with, instead of the detected one
+    pub fn package_manager(mut self, package_manager: PackageManager) -> Self {
+        self.options.package_manager = Some(package_manager);
+        self
+    }
+
+    /// Whether to install dependencies once the project is written
+    pub fn install(mut self, install: bool) -> Self {
+        self.options.install = install;
+        self
+    }
+
+    /// Author recorded in package.json and the LICENSE file
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.options.author = Some(author.into());
+        self
+    }
+
+    /// SPDX identifier of the project's license
+    pub fn license(mut self, license: impl Into<String>) -> Self {
+        self.options.license = Some(license.into());
+        self
+    }
+
+    /// Leave a partially created project in place when writing fails
+    pub fn keep_on_failure(mut self, keep: bool) -> Self {
+        self.options.keep_on_failure = keep;
+        self
+    }
+
+    /// Add the new package to the dependencies of this workspace member
+    pub fn dependent(mut self, member: impl Into<String>) -> Self {
+        self.options.dependent = Some(member.into());
+        self
+    }
+
+    /// Scaffold a starter tool configuration in package.json or
+    /// `cargonode.toml`
+    pub fn config_file(mut self, format: config::ConfigFile) -> Self {
+        self.options.config_file = Some(format);
+        self
+    }
+
+    /// Initialize a directory that already has a package.json, only adding
+    /// the files that are missing
+    pub fn force(mut self, force: bool) -> Self {
+        self.options.force = force;
+        self
+    }
+
+    /// Initialize a directory that already has a package.json, adding the
+    /// scaffolded fields it lacks
+    pub fn merge(mut self, merge: bool) -> Self {
+        self.options.merge = merge;
+        self
+    }
+
+    /// Require these Node.js versions, as a semver range like `>=20`
+    pub fn node(mut self, range: impl Into<String>) -> Self {
+        self.options.node = Some(range.into());
+        self
+    }
+
+    /// Look up a new package's name on the registry at `url` and warn if it
+    /// is taken
+    pub fn registry_check(mut self, url: impl Into<String>) -> Self {
+        self.options.registry_url = Some(url.into());
+        self
+    }
+
+    /// Accept a package name that is easily mistaken for another module
+    pub fn allow_confusing_name(mut self, allow: bool) -> Self {
+        self.options.allow_confusing_name = allow;
+        self
+    }
+
+    /// Only print what would be created, leaving the disk untouched
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.options.dry_run = dry_run;
+        self
+    }
+
+    /// Generate a CI workflow that installs, checks, builds and tests the
+    /// project
+    pub fn ci(mut self, ci: CiProvider) -> Self {
+        self.options.ci = ci;
+        self
+    }
+
+    /// Scaffolding hooks from the user configuration, which run for the
+    /// events the project's own configuration has no hook for
+    pub fn hooks(mut self, hooks: config::HooksConfig) -> Self {
+        self.options.hooks = hooks;
+        self
+    }
+
+    /// Scaffold from a template variant built into cargonode, such as
+    /// `typescript-app`
+    pub fn template_variant(mut self, variant: impl Into<String>) -> Self {
+        self.options.template_variant = Some(variant.into());
+        self
+    }
+
+    /// Enable these features of the template
+    pub fn features(mut self, features: Vec<String>) -> Self {
+        self.options.features = features;
+        self
+    }
+
+    /// Validate the options, then write the project
+    ///
+    /// # Returns
+    ///
+    /// * `Result<CreatedProject>` - Where the project is and what was written
+    pub fn build(self) -> Result<CreatedProject> {
+        let plan = ProjectPlan::new(&self.path, self.is_new, &self.options)?;
+        if self.options.dry_run {
+            return plan.dry_run();
+        }
+        plan.apply()
+    }
+}
+
+/// Paths a failed scaffold must remove or restore
+#[derive(Debug, Default)]
+struct Rollback {
+    /// Files and directories that did not exist before
+    created: Vec<PathBuf>,
+    /// Empty directories that existed before and are emptied again
+    emptied: Vec<PathBuf>,
+    /// Existing files that may be changed, with their original content
+    originals: Vec<(PathBuf, Vec<u8>)>,
+    /// Hidden directory a new project is written to before it is moved
+    staging: Option<PathBuf>,
+}
+
+impl Rollback {
+    /// Remember how to undo writing `path`
+    ///
+    /// An existing file is restored; otherwise the outermost directory that
+    /// is missing on the way to `path`, or the path itself, is removed.
+    fn track(&mut self, path: &Path) -> Result<()> {
+        if path.is_file() {
+            if !self.originals.iter().any(|(original, _)| original == path) {
+                self.originals.push((path.to_path_buf(), fs::read(path)?));
+            }
+            return Ok(());
+        }
+        if path.exists() {
+            return Ok(());
+        }
+
+        let mut outermost = path;
+        while let Some(parent) = outermost.parent() {
+            if parent.as_os_str().is_empty() || parent.exists() {
+                break;
+            }
+            outermost = parent;
+        }
+        if !self.created.iter().any(|created| created == outermost) {
+            self.created.push(outermost.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Remove what was created and put back what was changed
+    fn undo(self) -> Result<()> {
+        for path in self.staging.iter().chain(self.created.iter().rev()) {
+            let removed = if path.is_dir() {
+                fs::remove_dir_all(path)
+            } else {
+                fs::remove_file(path)
+            };
+            match removed {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        for path in &self.emptied {
+            match fs::remove_dir_all(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => fs::create_dir_all(path)?,
+            }
+        }
+        for (path, content) in self.originals {
+            fs::write(path, content)?;
+        }
+        Ok(())
+    }
+
+    /// Leave a failed scaffold for inspection, moving a staged project to
+    /// `path` where it can be found
+    fn keep(self, path: &Path) -> Result<()> {
+        if let Some(staging) = self.staging {
+            if path.is_dir() {
+                fs::remove_dir(path)?;
+            }
+            fs::rename(staging, path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write a new project into a hidden sibling of `path`, then move it into
+/// place once every file has been written
+///
+/// The staging directory is on the same filesystem as `path`, so the final
+/// rename is atomic and an interrupted run never leaves `path` half written.
+///
+/// # Arguments
+///
+/// * `path` - Directory the project ends up in; it is missing or empty
+/// * `rollback` - Records parent directories created here
+/// * `write` - Writes the project below the directory it is given
+///
+/// # Returns
+///
+/// * `Result<T>` - What `write` returned, once the project is in place
+fn create_staged<T>(
+    path: &Path,
+    rollback: &mut Rollback,
+    write: impl FnOnce(&Path) -> Result<T>,
+) -> Result<T> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let staging = parent.join(format!(".{}.cargonode-{}", name, process::id()));
+
+    rollback.track(parent)?;
+    fs::create_dir_all(parent)?;
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    // Registered before it exists so a failure removes it
+    rollback.staging = Some(staging.clone());
+    let _cleanup = crate::process::remove_on_interrupt(&staging);
+    fs::create_dir(&staging)?;
+    let value = write(&staging)?;
+
+    // An empty destination directory may already exist
+    let existed = path.is_dir();
+    if existed {
+        fs::remove_dir(path)?;
+    }
+    if let Err(e) = fs::rename(&staging, path) {
+        if existed {
+            fs::create_dir(path)?;
+        }
+        return Err(e.into());
+    }
+    rollback.staging = None;
+    // An empty directory the user made is kept, emptied, on failure
+    if existed {
+        rollback.emptied.push(path.to_path_buf());
+    } else {
+        rollback.created.push(path.to_path_buf());
+    }
+    Ok(value)
+}
+
+/// Pick the author and license of a new package
+///
+/// Explicit options win. Otherwise the license comes from the
+/// `cargonode.license` git config, then npm's `init-license` setting, then
+/// MIT; the author from npm's `init-author-name`, then git's `user.name`.
+///
+/// # Returns
+///
+/// * `(String, String)` - The author (possibly empty) and license
+fn resolve_metadata(path: &Path, options: &ProjectOptions) -> (String, String) {
+    let npmrc = Npmrc::load(path);
+    let author = options
+        .author
+        .clone()
+        .or_else(|| npmrc.get("init-author-name").map(str::to_string))
+        .or_else(utils::git_user_name)
+        .unwrap_or_default();
+    let license = options
+        .license
+        .clone()
+        .unwrap_or_else(|| default_license(&npmrc));
+
+    (author, template::canonical_license(&license))
+}
+
+/// License used when none is passed: the `cargonode.license` git config,
+/// then npm's `init-license` setting, then MIT
+fn default_license(npmrc: &Npmrc) -> String {
+    utils::git_config(LICENSE_GIT_CONFIG)
+        .or_else(|| npmrc.get("init-license").map(str::to_string))
+        .unwrap_or_else(|| template::DEFAULT_LICENSE.to_string())
+}
+
+/// License a project at `path` gets when no `--license` is passed
+///
+/// # Arguments
+///
+/// * `path` - Directory the project is created in, used to find `.npmrc` files
+///
+/// # Returns
+///
+/// * `String` - The SPDX identifier of the default license
+pub fn project_default_license(path: &Path) -> String {
+    template::canonical_license(&default_license(&Npmrc::load(path)))
+}
+
 fn create_package_config(config: &config::ProjectConfig) -> template::PackageConfig {
     template::PackageConfig {
         name: config.name.to_owned(),
-        project_type: if config.is_binary {
-            ProjectType::Binary
-        } else {
-            ProjectType::Library
-        },
+        project_type: config.project_type,
         version: None,
+        author: None,
+        license: None,
+        node: None,
+    }
+}
+
+/// Summarize what `--merge` added to the existing package.json and what it
+/// left out
+fn report_merge(report: &MergeReport, kept_files: &[(String, String)]) -> Result<()> {
+    progress::status(
+        "Merged",
+        &format!(
+            "{} field{} into the existing package.json, skipped {}",
+            report.added.len(),
+            if report.added.len() == 1 { "" } else { "s" },
+            report.skipped.len()
+        ),
+    )?;
+    if !report.added.is_empty() {
+        progress::note(&format!("added {}", report.added.join(", ")))?;
+    }
+    for skipped in &report.skipped {
+        progress::note(&format!("skipped `{}`: {}", skipped.field, skipped.reason))?;
+    }
+    for (path, existing) in kept_files {
+        progress::note(&format!(
+            "skipped {}: keeping the existing {}",
+            path, existing
+        ))?;
+    }
+    Ok(())
+}
+
+/// The file in `dir` that already plays the part of the generated `path`:
+/// any `LICENSE`, `LICENCE` or `COPYING` file for `LICENSE`, and any
+/// `README` for `README.md`, whatever its case or extension
+fn existing_counterpart(dir: &Path, path: &str) -> Option<String> {
+    let stems: &[&str] = match path {
+        "LICENSE" => &["license", "licence", "copying"],
+        "README.md" => &["readme"],
+        _ => return None,
+    };
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| {
+            let stem = name.split('.').next().unwrap_or_default();
+            stems.contains(&stem.to_ascii_lowercase().as_str())
+        })
+        .collect();
+    names.sort();
+    names.into_iter().next()
+}
+
+/// Print the plan of a dry run, then report it as an event
+fn report_plan(plan: progress::Event) -> Result<()> {
+    if let progress::Event::ScaffoldPlan {
+        files,
+        skipped,
+        substitutions,
+        vcs,
+        install,
+        ..
+    } = &plan
+    {
+        for file in files {
+            progress::status(
+                "Would create",
+                &format!("{} ({} bytes)", file.path, file.size),
+            )?;
+        }
+        for file in skipped {
+            progress::status("Would skip", &format!("{} (already exists)", file))?;
+        }
+        for (placeholder, value) in substitutions {
+            progress::status(
+                "Would substitute",
+                &format!("{} = {:?}", placeholder, value),
+            )?;
+        }
+        if let Some(vcs) = vcs {
+            progress::status("Would initialize", &format!("a {} repository", vcs))?;
+        }
+        if let Some(install) = install {
+            progress::status("Would run", install)?;
+        }
     }
+    progress::emit(&plan)?;
+    Ok(())
 }
 
 fn should_use_vcs(vcs_config: &Option<utils::VcsConfig>) -> bool {
     vcs_config
         .as_ref()
-        .map(|c| c.vcs == utils::Vcs::Git)
+        .map(|c| c.vcs != utils::Vcs::None)
         .unwrap_or(true)
 }
 
-pub fn create_project(
-    path: &Path,
-    lib: bool,
-    vcs_config: Option<utils::VcsConfig>,
+/// Everything a scaffold writes and runs, worked out before the first file
+/// is written
+struct ProjectPlan {
+    /// Whether the project goes into a new directory rather than an existing one
     is_new: bool,
-) -> Result<()> {
-    let has_vcs = should_use_vcs(&vcs_config);
+    /// Options the plan was made from
+    options: ProjectOptions,
+    /// The validated name, type, directory and version control
+    config: config::ProjectConfig,
+    /// Problems a dry run reports in its plan
+    warnings: Vec<String>,
+    /// Whether version control is set up; a workspace member uses the
+    /// workspace's repository
+    has_vcs: bool,
+    /// Workspace member the new package is added to
+    dependent: Option<WorkspacePackage>,
+    /// Values the files are rendered with
+    context: template::TemplateContext,
+    /// Rendered files of the template, when scaffolding from one
+    template_files: Option<Vec<template::TemplateFile>>,
+    /// The license and readme, less those an existing project keeps under
+    /// other names
+    project_files: Vec<template::TemplateFile>,
+    /// `cargonode.toml`, when the configuration goes there
+    tool_config_file: Option<template::TemplateFile>,
+    /// The Node.js version file, outside of a workspace
+    node_version_file: Option<template::TemplateFile>,
+    /// The scaffolded package.json
+    package_json: template::PackageJson,
+    /// The existing package.json with the scaffolded fields merged in, and
+    /// what merging changed
+    merged: Option<(String, MergeReport)>,
+    /// Generated files an existing project keeps under another name, with
+    /// that name
+    kept_files: Vec<(String, String)>,
+    /// Steps of the CI workflow
+    ci_steps: Vec<ci::CiStep>,
+    /// Hooks run around scaffolding
+    hooks: config::HooksConfig,
+    /// Files the scaffold creates, relative to the project
+    files: Vec<PathBuf>,
+    /// Existing files initializing leaves alone
+    skipped: Vec<PathBuf>,
+}
 
-    // Validate configuration first
-    let config = if is_new {
-        // For new projects, ensure directory is empty first
-        utils::ensure_directory_empty(path)?;
-        config::validate_project_config(path, lib, vcs_config)?
-    } else {
-        config::validate_init_config(path, lib, vcs_config)?
-    };
+impl ProjectPlan {
+    /// Validate the options and work out what the scaffold writes
+    ///
+    /// Nothing is written: the package name, the directory, the template and
+    /// an explicitly chosen package manager are all checked here.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Directory the project is created in
+    /// * `is_new` - Whether the directory must be missing or empty
+    /// * `options` - How to create the project
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The plan, or the first problem found
+    fn new(path: &Path, is_new: bool, options: &ProjectOptions) -> Result<Self> {
+        let mut warnings = Vec::new();
 
-    // Create project structure
-    let project_type = if lib { "library" } else { "binary" };
-    let action = if is_new { "Creating" } else { "Initializing" };
-    progress::write_message(&progress::format_status(
-        action,
-        &format!("{} package `{}`", project_type, config.name),
-    ))?;
+        // `--merge` keeps the identity of the existing package: unless passed,
+        // its name and license come from the existing package.json
+        let existing_package = (options.merge && !is_new)
+            .then(|| fs::read_to_string(path.join("package.json")).ok())
+            .flatten()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok());
+        let existing_field = |field: &str| {
+            existing_package
+                .as_ref()
+                .and_then(|package| package.get(field))
+                .and_then(serde_json::Value::as_str)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let name = options.name.clone().or_else(|| existing_field("name"));
+        let config = validate_target(path, is_new, name.as_deref(), options, &mut warnings)?;
 
-    let project_config = utils::create_project_config(&config.path, config.is_binary);
-    utils::create_project_structure(&project_config)?;
+        // Inside a workspace the project becomes a member, and the repository
+        // is the workspace's
+        let workspace_root =
+            package_manager::find_enclosing_workspace(&std::path::absolute(path)?)?;
+        let has_vcs = should_use_vcs(&config.vcs_config) && workspace_root.is_none();
+        let dependent = options
+            .dependent
+            .as_ref()
+            .map(|member| find_dependent(workspace_root.as_deref(), path, member))
+            .transpose()?;
+        if let Some(workflow) = options.ci.workflow_path() {
+            if let Some(root) = &workspace_root {
+                return Err(Error::Config {
+                    message: format!(
+                        "A workflow for a workspace member would not run; generate CI for the workspace at {} instead",
+                        root.display()
+                    ),
+                    hint: None,
+                });
+            }
+            if !is_new && !options.force && path.join(workflow).exists() {
+                return Err(Error::Config {
+                    message: format!("{} already exists; pass --force to replace it", workflow),
+                    hint: None,
+                });
+            }
+        }
 
-    // Generate package.json
-    let package_config = create_package_config(&config);
-    let package_json = template::create_package_json(package_config);
-    template::write_package_json(&package_json, &config.path)?;
+        // An explicitly requested package manager must exist before anything
+        // is written, rather than failing after the project has been scaffolded
+        if let (Some(package_manager), true) = (options.package_manager, options.install) {
+            package_manager.ensure_available()?;
+        }
 
-    // Initialize version control if needed
-    if has_vcs {
-        if let Some(vcs_config) = config.vcs_config.as_ref() {
-            utils::init_vcs(&config.path, vcs_config)?;
+        // Load the template before anything is written
+        let template = match (
+            options.template.as_deref(),
+            options.template_variant.as_deref(),
+        ) {
+            (Some(path), _) => Some(template::TemplateConfig::load(path)?),
+            (None, Some(variant)) => Some(template::TemplateConfig::load_variant(variant)?),
+            (None, None) => None,
+        };
+        if template.is_none() && !options.features.is_empty() {
+            return Err(Error::Template {
+                message: "Features can only be enabled when scaffolding from a template"
+                    .to_string(),
+            });
+        }
+        let (author, mut license) = resolve_metadata(&config.path, options);
+        if let (None, Some(existing)) = (&options.license, existing_field("license")) {
+            license = template::canonical_license(&existing);
         }
+        let mut package_config = create_package_config(&config);
+        package_config.author = (!author.is_empty()).then(|| author.clone());
+        package_config.license = Some(license.clone());
+        let mut context = template::TemplateContext::new(&config.name, package_config.project_type);
+        context.author = author;
+        context.license = license;
+        // The project pins the release line it is created with, in
+        // package.json and for version managers alike
+        let node_range = options
+            .node
+            .clone()
+            .unwrap_or_else(|| node::lts_range(&context.node_version));
+        let node_version = node::lowest_version(&node_range)?;
+        package_config.node = Some(node_range);
+        if let Some(template) = &template {
+            if !template.supports(package_config.project_type) {
+                return Err(Error::Template {
+                    message: format!(
+                        "Template '{}' does not support {} projects",
+                        template.name,
+                        options.project_type.name()
+                    ),
+                });
+            }
+        }
+        let template_files = template
+            .as_ref()
+            .map(|template| template.render(&context, &options.features))
+            .transpose()?;
+
+        let action = if is_new { "Creating" } else { "Initializing" };
+        progress::status(
+            action,
+            &format!("{} package `{}`", options.project_type.name(), config.name),
+        )?;
+
+        if template::license_text(&context.license).is_none() && context.license != "UNLICENSED" {
+            let warning = format!(
+                "no LICENSE file is generated for `{}`; add one yourself",
+                context.license
+            );
+            progress::warn(&warning)?;
+            warnings.push(warning);
+        }
+
+        let mut package_json = template::create_package_json(package_config);
+        if let Some(template) = &template {
+            package_json.merge_template(template, &options.features);
+        }
+        if let Some(root) = &workspace_root {
+            join_workspace(root, path, &mut package_json)?;
+        }
+        let (tool_config_file, toml_tool_config) =
+            place_tool_config(&mut package_json, options.config_file)?;
+
+        // `--merge` adds the scaffolded fields the existing package.json lacks
+        let existing_manifest = config.path.join("package.json");
+        let merged = if options.merge && !is_new && existing_manifest.exists() {
+            let mut manifest: serde_json::Value =
+                serde_json::from_str(&fs::read_to_string(&existing_manifest)?)?;
+            let scaffold = serde_json::from_str(&template::serialize_package_json(&package_json)?)?;
+            let report = manifest_merge::merge_package_json(&mut manifest, &scaffold);
+            Some((serde_json::to_string_pretty(&manifest)?, report))
+        } else {
+            None
+        };
+        // An existing project may keep its license and readme under other
+        // names, e.g. LICENSE.txt or readme.md; merging keeps those rather
+        // than adding a second copy that may disagree with them
+        let kept_files: Vec<(String, String)> = match &merged {
+            Some(_) => context
+                .project_files()
+                .into_iter()
+                .filter_map(|file| {
+                    existing_counterpart(&config.path, &file.path)
+                        .map(|existing| (file.path, existing))
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        let project_files: Vec<template::TemplateFile> = context
+            .project_files()
+            .into_iter()
+            .filter(|file| !kept_files.iter().any(|(path, _)| *path == file.path))
+            .collect();
+
+        // The package.json and configuration that end up on disk
+        let manifest: serde_json::Value = match &merged {
+            Some((manifest, _)) => serde_json::from_str(manifest)?,
+            None if !is_new && existing_manifest.exists() => {
+                serde_json::from_str(&fs::read_to_string(&existing_manifest)?)?
+            }
+            None => serde_json::from_str(&template::serialize_package_json(&package_json)?)?,
+        };
+        let final_tool_config = toml_tool_config.or_else(|| manifest.get("cargonode").cloned());
+
+        // The workflow runs the tools the final configuration declares
+        let ci_steps = match options.ci {
+            CiProvider::None => Vec::new(),
+            CiProvider::Github => ci::ci_steps(final_tool_config.as_ref(), &manifest),
+        };
+        let hooks = scaffold_hooks(
+            &config.path,
+            is_new,
+            final_tool_config.as_ref(),
+            &options.hooks,
+        )?;
+
+        // Workspace members use the Node.js version of the workspace
+        let node_version_file = workspace_root.is_none().then(|| template::TemplateFile {
+            path: node::VERSION_FILE.to_string(),
+            content: Some(format!("{}\n", node_version)),
+            ..Default::default()
+        });
+
+        let mut plan = Self {
+            is_new,
+            options: options.clone(),
+            config,
+            warnings,
+            has_vcs,
+            dependent,
+            context,
+            template_files,
+            project_files,
+            tool_config_file,
+            node_version_file,
+            package_json,
+            merged,
+            kept_files,
+            ci_steps,
+            hooks,
+            files: Vec::new(),
+            skipped: Vec::new(),
+        };
+        (plan.files, plan.skipped) = plan.list_files();
+        Ok(plan)
+    }
+
+    /// Files the scaffold creates and existing files it leaves alone,
+    /// relative to the project; initializing never overwrites existing files
+    fn list_files(&self) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let config = &self.config;
+        let mut files: Vec<PathBuf> = match &self.template_files {
+            Some(template_files) => template_files
+                .iter()
+                .map(|file| file.path.clone().into())
+                .collect(),
+            None => vec![config.project_type.entry_file().into()],
+        };
+        files.extend(
+            self.context
+                .project_files()
+                .into_iter()
+                .map(|file| file.path.into()),
+        );
+        files.push("package.json".into());
+        files.extend(
+            self.tool_config_file
+                .iter()
+                .chain(&self.node_version_file)
+                .map(|file| PathBuf::from(&file.path)),
+        );
+        if let Some(vcs_config) = config.vcs_config.as_ref().filter(|_| self.has_vcs) {
+            match vcs_config.vcs {
+                utils::Vcs::None => {}
+                utils::Vcs::Hg => {
+                    if utils::find_repository_root(&config.path, utils::Vcs::Hg).is_none() {
+                        files.push(".hgignore".into());
+                    }
+                }
+                _ => files.push(".gitignore".into()),
+            }
+        }
+        let (mut files, skipped): (Vec<PathBuf>, Vec<PathBuf>) =
+            files.into_iter().partition(|file| {
+                self.is_new
+                    || (!config.path.join(file).exists()
+                        && !self
+                            .kept_files
+                            .iter()
+                            .any(|(path, _)| file.as_path() == Path::new(path)))
+                    || (self.merged.is_some() && file.as_path() == Path::new("package.json"))
+            });
+        files.extend(self.options.ci.workflow_path().map(PathBuf::from));
+        (files, skipped)
     }
 
-    // Show completion message
-    progress::write_message(&progress::format_note(
-        "See package.json for available scripts and configuration options",
-    ))?;
+    /// The CI workflow for `package_manager`, if one is generated
+    ///
+    /// A clean install needs a lockfile: one the install creates, or one the
+    /// project or template already has in `root`.
+    fn workflow(
+        &self,
+        package_manager: PackageManager,
+        root: &Path,
+    ) -> Option<template::TemplateFile> {
+        let locked = self.options.install
+            || package_manager
+                .lockfiles()
+                .iter()
+                .any(|lockfile| root.join(lockfile).is_file());
+        self.options
+            .ci
+            .workflow_path()
+            .map(|path| template::TemplateFile {
+                path: path.to_string(),
+                content: Some(ci::github_workflow(package_manager, locked, &self.ci_steps)),
+                ..Default::default()
+            })
+    }
 
-    Ok(())
+    /// Write the project below `root`: a staging directory for new projects,
+    /// the project directory itself when initializing
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DetectedPackageManager>` - The package manager the project uses
+    fn write(&self, root: &Path) -> Result<DetectedPackageManager> {
+        match &self.template_files {
+            Some(files) => utils::write_template_files(root, files)?,
+            None => {
+                let project_config = utils::create_project_config(root, self.config.project_type);
+                utils::create_project_structure(&project_config)?;
+            }
+        }
+        utils::write_template_files(root, &self.project_files)?;
+        utils::write_template_files(root, self.tool_config_file.as_slice())?;
+        utils::write_template_files(root, self.node_version_file.as_slice())?;
+        // Re-initializing with `--force` keeps the existing package.json
+        if let Some((manifest, report)) = &self.merged {
+            utils::write_atomic(&root.join("package.json"), manifest)?;
+            report_merge(report, &self.kept_files)?;
+        } else if root.join("package.json").exists() {
+            progress::note("keeping the existing package.json (--force)")?;
+        } else {
+            template::write_package_json(&self.package_json, root)?;
+        }
+
+        let package_manager = match self.options.package_manager {
+            Some(kind) => DetectedPackageManager::from(kind),
+            None => package_manager::detect_package_manager(root)?,
+        };
+        // Replaces an existing workflow, which only `--force` gets this far with
+        if let Some(file) = self.workflow(package_manager.kind, root) {
+            let path = root.join(&file.path);
+            fs::create_dir_all(path.parent().unwrap_or(root))?;
+            fs::write(path, file.content.unwrap_or_default())?;
+        }
+
+        // Initialize version control if needed
+        if self.has_vcs {
+            if let Some(mut vcs_config) = self.config.vcs_config.clone() {
+                vcs_config
+                    .ignore_content
+                    .push_str(&package_manager.kind.gitignore_entries());
+                utils::init_vcs(root, &vcs_config)?;
+            }
+        }
+        Ok(package_manager)
+    }
+
+    /// Report what the scaffold would write and run, leaving the disk
+    /// untouched
+    ///
+    /// # Returns
+    ///
+    /// * `Result<CreatedProject>` - The project as it would be created
+    fn dry_run(self) -> Result<CreatedProject> {
+        let config = &self.config;
+        // Everything the scaffold writes, to look up the size of each file
+        let mut written = self.template_files.clone().unwrap_or_else(|| {
+            let project_config = utils::create_project_config(&config.path, config.project_type);
+            vec![template::TemplateFile {
+                path: config.project_type.entry_file().to_string(),
+                content: Some(project_config.source_content),
+                ..Default::default()
+            }]
+        });
+        written.extend(self.project_files.clone());
+        written.extend(self.tool_config_file.clone());
+        written.extend(self.node_version_file.clone());
+        let manifest = match &self.merged {
+            Some((manifest, report)) => {
+                report_merge(report, &self.kept_files)?;
+                manifest.clone()
+            }
+            None => template::serialize_package_json(&self.package_json)?,
+        };
+        let package_manager = match self.options.package_manager {
+            Some(kind) => DetectedPackageManager::from(kind),
+            None => serde_json::from_str::<serde_json::Value>(&manifest)?
+                .get("packageManager")
+                .and_then(serde_json::Value::as_str)
+                .and_then(DetectedPackageManager::parse)
+                .map_or_else(|| package_manager::detect_package_manager(&config.path), Ok)?,
+        };
+        written.push(template::TemplateFile {
+            path: "package.json".to_string(),
+            content: Some(manifest),
+            ..Default::default()
+        });
+        written.extend(self.workflow(package_manager.kind, &config.path));
+        let mut vcs = None;
+        if let Some(vcs_config) = config.vcs_config.as_ref().filter(|_| self.has_vcs) {
+            let mut ignore = vcs_config.ignore_content.clone();
+            ignore.push_str(&package_manager.kind.gitignore_entries());
+            let (path, content) = match vcs_config.vcs {
+                utils::Vcs::Hg => (".hgignore", utils::hgignore_content(&ignore)),
+                _ => (".gitignore", ignore),
+            };
+            written.push(template::TemplateFile {
+                path: path.to_string(),
+                content: Some(content),
+                ..Default::default()
+            });
+            if utils::find_repository_root(&config.path, vcs_config.vcs).is_none() {
+                vcs = vcs_config.vcs.binary().map(str::to_string);
+            }
+        }
+
+        let planned = self
+            .files
+            .iter()
+            .map(|file| {
+                let path = file.to_string_lossy().replace('\\', "/");
+                let size = written
+                    .iter()
+                    .find(|written| written.path == path)
+                    .map(|written| match &written.bytes {
+                        Some(bytes) => bytes.len(),
+                        None => written.content.as_deref().unwrap_or_default().len(),
+                    })
+                    .unwrap_or_default();
+                progress::PlannedFile {
+                    path,
+                    size: size as u64,
+                }
+            })
+            .collect();
+        report_plan(progress::Event::ScaffoldPlan {
+            path: config.path.display().to_string(),
+            files: planned,
+            skipped: self
+                .skipped
+                .iter()
+                .map(|file| file.to_string_lossy().replace('\\', "/"))
+                .collect(),
+            substitutions: self.context.substitutions(),
+            vcs,
+            install: self
+                .options
+                .install
+                .then(|| package_manager.install_command_line()),
+            warnings: self.warnings,
+        })?;
+        progress::warn(&format!(
+            "aborting {} due to dry run",
+            if self.is_new { "new" } else { "init" }
+        ))?;
+        Ok(CreatedProject {
+            path: self.config.path,
+            name: self.config.name,
+            files: self.files,
+        })
+    }
+
+    /// Write the project, then install its dependencies and run the hooks
+    ///
+    /// If writing fails partway, what was written is removed again, or kept
+    /// with `keep_on_failure`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<CreatedProject>` - Where the project is and what was written
+    fn apply(self) -> Result<CreatedProject> {
+        let config = &self.config;
+        let hook_context = HookContext {
+            operation: if self.is_new { "new" } else { "init" },
+            tool: None,
+            project_dir: &config.path,
+        };
+
+        // A failed pre hook stops scaffolding before anything is written; its
+        // run is journaled once the project exists
+        let pre_hook = hooks::run_pre(&self.hooks, &hook_context)?;
+        if let Some(run) = &pre_hook {
+            if !self.is_new {
+                run.record(&config.path)?;
+            }
+            run.check(&self.hooks)?;
+        }
+
+        // Files the scaffold may create or change outside a staging directory
+        let mut rollback = Rollback::default();
+        if let Some(vcs_config) = config.vcs_config.as_ref().filter(|_| self.has_vcs) {
+            let root = utils::find_repository_root(&config.path, vcs_config.vcs);
+            match (vcs_config.vcs, root) {
+                (utils::Vcs::Hg, Some(root)) => rollback.track(&root.join(".hgignore"))?,
+                (vcs, None) if !self.is_new => {
+                    if let Some(marker) = vcs.marker() {
+                        rollback.track(&config.path.join(marker))?;
+                    }
+                }
+                _ => {}
+            }
+            if !self.is_new {
+                rollback.track(&config.path.join(".gitignore"))?;
+                rollback.track(&config.path.join(".hgignore"))?;
+            }
+        }
+
+        if let Some(dependent) = &self.dependent {
+            rollback.track(&dependent.path.join("package.json"))?;
+        }
+        let result = if self.is_new {
+            create_staged(&config.path, &mut rollback, |root| self.write(root))
+        } else {
+            for file in &self.files {
+                rollback.track(&config.path.join(file))?;
+            }
+            self.write(&config.path)
+        };
+        let result = result.and_then(|package_manager| {
+            if let Some(dependent) = &self.dependent {
+                let range = package_manager::workspace_range(package_manager.kind);
+                add_to_dependent(dependent, &config.name, range)?;
+            }
+            Ok(package_manager)
+        });
+        let package_manager = match result {
+            Ok(package_manager) => package_manager,
+            Err(e) if self.options.keep_on_failure => {
+                rollback.keep(&config.path)?;
+                progress::note(&format!(
+                    "kept the partially created project in {} (--keep-on-failure)",
+                    config.path.display()
+                ))?;
+                return Err(e);
+            }
+            Err(e) => {
+                // Report the original error even if cleaning up fails too
+                if let Err(cleanup) = rollback.undo() {
+                    progress::warn(&format!(
+                        "failed to remove the partially created project: {}",
+                        cleanup
+                    ))?;
+                }
+                return Err(e);
+            }
+        };
+
+        if self.options.install {
+            progress::status(
+                "Installing",
+                &format!("dependencies with {}", package_manager),
+            )?;
+            // The project is usable without node_modules, so a failed install
+            // should not make the whole command fail
+            if let Err(err) = package_manager.install(&config.path) {
+                progress::warn(&format!(
+                    "failed to run `{}`: {}",
+                    package_manager.install_command_line(),
+                    err.to_string().lines().next().unwrap_or_default()
+                ))?;
+            }
+        }
+
+        if let Some(run) = pre_hook.filter(|_| self.is_new) {
+            run.record(&config.path)?;
+        }
+        if let Some(run) = hooks::run_post(&self.hooks, &hook_context, 0)? {
+            run.record(&config.path)?;
+            run.check(&self.hooks)?;
+        }
+
+        // Show completion message
+        progress::note("See package.json for available scripts and configuration options")?;
+
+        Ok(CreatedProject {
+            path: self.config.path.clone(),
+            name: self.config.name.clone(),
+            files: self.files,
+        })
+    }
 }
 
-pub fn create_new_project(
+/// Validate the directory and name of the project
+///
+/// A new project needs a missing or empty directory; a dry run only warns
+/// that the real run would fail. The name must be valid and not easily
+/// mistaken for another module, and a new one is looked up on the registry
+/// when asked.
+///
+/// # Arguments
+///
+/// * `path` - Directory the project is created in
+/// * `is_new` - Whether the directory must be missing or empty
+/// * `name` - Package name, if not taken from the directory
+/// * `options` - How to create the project
+/// * `warnings` - Collects the problems a dry run reports
+///
+/// # Returns
+///
+/// * `Result<config::ProjectConfig>` - The validated configuration
+fn validate_target(
     path: &Path,
-    lib: bool,
-    vcs_config: Option<utils::VcsConfig>,
-) -> Result<()> {
-    create_project(path, lib, vcs_config, true)
+    is_new: bool,
+    name: Option<&str>,
+    options: &ProjectOptions,
+    warnings: &mut Vec<String>,
+) -> Result<config::ProjectConfig> {
+    let vcs_config = options.vcs_config.clone();
+    let config = if is_new {
+        match utils::ensure_directory_empty(path) {
+            Err(Error::DirectoryNotEmpty { path }) if options.dry_run => {
+                let warning = format!(
+                    "{} is not empty, so creating the project there would fail",
+                    path.display()
+                );
+                progress::warn(&warning)?;
+                warnings.push(warning);
+            }
+            result => result?,
+        }
+        config::validate_project_config(path, name, options.project_type, vcs_config)?
+    } else {
+        config::validate_init_config(
+            path,
+            name,
+            options.project_type,
+            vcs_config,
+            options.force || options.merge,
+        )?
+    };
+    utils::check_confusing_package_name(&config.name, options.allow_confusing_name)?;
+    if let (true, Some(registry_url)) = (is_new, &options.registry_url) {
+        report_name_check(&config.name, registry_url)?;
+    }
+    Ok(config)
 }
 
-pub fn init_project(lib: bool, vcs_config: Option<utils::VcsConfig>) -> Result<()> {
+/// Put the tool configuration where `config_file` asks for it
+///
+/// A workspace member keeps the configuration it inherited, in the requested
+/// place; otherwise it gets the starter configuration.
+///
+/// # Returns
+///
+/// * `Result<(Option<template::TemplateFile>, Option<serde_json::Value>)>` -
+///   `cargonode.toml` and the configuration moved into it, for the CI
+///   workflow to read, when the configuration goes there
+fn place_tool_config(
+    package_json: &mut template::PackageJson,
+    config_file: Option<config::ConfigFile>,
+) -> Result<(Option<template::TemplateFile>, Option<serde_json::Value>)> {
+    let Some(format) = config_file else {
+        return Ok((None, None));
+    };
+    let tool_config = package_json
+        .take_tool_config()
+        .unwrap_or_else(config::starter_tool_config);
+    match format {
+        config::ConfigFile::PackageJson => {
+            package_json.set_tool_config(tool_config);
+            Ok((None, None))
+        }
+        config::ConfigFile::Toml => {
+            let file = template::TemplateFile {
+                path: config::PROJECT_CONFIG_FILES[0].to_string(),
+                content: Some(config::tool_config_to_toml(&tool_config)?),
+                ..Default::default()
+            };
+            Ok((Some(file), Some(tool_config)))
+        }
+    }
+}
+
+/// Hooks run around scaffolding
+///
+/// They come from the project's final configuration; an existing project may
+/// keep it in cargonode.toml. The user's hooks run for the events the
+/// project has none for.
+///
+/// # Returns
+///
+/// * `Result<config::HooksConfig>` - The hooks, or an error if the
+///   configured ones are malformed
+fn scaffold_hooks(
+    path: &Path,
+    is_new: bool,
+    tool_config: Option<&serde_json::Value>,
+    user_hooks: &config::HooksConfig,
+) -> Result<config::HooksConfig> {
+    let mut hooks: config::HooksConfig =
+        match tool_config.and_then(|tool_config| tool_config.get("hooks")) {
+            Some(hooks) => serde_json::from_value(hooks.clone())?,
+            None if !is_new => config::read_config(path, false)
+                .map(|project| project.hooks)
+                .unwrap_or_default(),
+            None => config::HooksConfig::default(),
+        };
+    for (event, hook) in &user_hooks.events {
+        hooks
+            .events
+            .entry(event.clone())
+            .or_insert_with(|| hook.clone());
+    }
+    Ok(hooks)
+}
+
+pub fn create_project(
+    path: &Path,
+    is_new: bool,
+    options: &ProjectOptions,
+) -> Result<CreatedProject> {
+    ProjectBuilder {
+        path: path.to_path_buf(),
+        is_new,
+        options: options.clone(),
+    }
+    .build()
+}
+
+pub fn create_new_project(path: &Path, options: &ProjectOptions) -> Result<CreatedProject> {
+    ProjectBuilder::new(path).options(options.clone()).build()
+}
+
+pub fn init_project(options: &ProjectOptions) -> Result<CreatedProject> {
     let current_dir = env::current_dir()?;
-    create_project(&current_dir, lib, vcs_config, false)
+    ProjectBuilder::init(current_dir)
+        .options(options.clone())
+        .build()
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use tempfile::TempDir;
 
     use super::*;
+    use crate::test_utils::{serve_once, serve_sequence};
 
     #[test]
     fn test_should_use_vcs() {
@@ -108,7 +1591,7 @@ mod tests {
         let config = config::ProjectConfig {
             name: "test-pkg".to_string(),
             path: temp_dir.path().to_path_buf(),
-            is_binary: true,
+            project_type: ProjectType::Binary,
             vcs_config: None,
         };
 
@@ -129,10 +1612,55 @@ mod tests {
             ignore_content: String::new(),
         });
 
-        assert!(create_project(&path, false, vcs_config, true).is_ok());
+        let options = ProjectOptions {
+            vcs_config,
+            author: Some("Jane Doe".to_string()),
+            license: Some("isc".to_string()),
+            ..Default::default()
+        };
+        assert!(create_project(&path, true, &options).is_ok());
         assert!(path.exists());
         assert!(path.join("package.json").exists());
         assert!(path.join("src").exists());
+        assert!(path.join(".editorconfig").exists());
+
+        let package_json = std::fs::read_to_string(path.join("package.json")).unwrap();
+        assert!(package_json.contains(r#""author": "Jane Doe""#));
+        assert!(package_json.contains(r#""license": "ISC""#));
+        let license = std::fs::read_to_string(path.join("LICENSE")).unwrap();
+        assert!(license.starts_with("ISC License"));
+        assert!(license.contains("Jane Doe"));
+        let readme = std::fs::read_to_string(path.join("README.md")).unwrap();
+        assert!(readme.contains("the ISC license"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_project_through_symlink() {
+        // Mirrors macOS, where /tmp is a symlink to /private/tmp
+        let temp_dir = TempDir::new().unwrap();
+        let real_dir = temp_dir.path().join("private");
+        std::fs::create_dir(&real_dir).unwrap();
+        let link = temp_dir.path().join("tmp");
+        std::os::unix::fs::symlink(&real_dir, &link).unwrap();
+
+        let vcs_config = Some(utils::VcsConfig {
+            vcs: utils::Vcs::None,
+            ignore_content: String::new(),
+        });
+        let options = ProjectOptions {
+            vcs_config,
+            ..Default::default()
+        };
+        create_project(&link.join("foo"), true, &options).unwrap();
+        assert!(real_dir.join("foo/package.json").exists());
+
+        // A symlinked project directory can be initialized in place
+        let project_link = temp_dir.path().join("linked-project");
+        std::fs::create_dir(real_dir.join("bar")).unwrap();
+        std::os::unix::fs::symlink(real_dir.join("bar"), &project_link).unwrap();
+        create_project(&project_link, false, &options).unwrap();
+        assert!(real_dir.join("bar/package.json").exists());
     }
 
     #[test]
@@ -147,9 +1675,843 @@ mod tests {
             ignore_content: String::new(),
         });
 
-        assert!(create_project(&path, true, vcs_config, false).is_ok());
+        let options = ProjectOptions {
+            project_type: ProjectType::Library,
+            vcs_config,
+            ..Default::default()
+        };
+        assert!(create_project(&path, false, &options).is_ok());
         assert!(path.exists());
         assert!(path.join("package.json").exists());
         assert!(path.join("src").exists());
     }
+
+    #[test]
+    fn test_create_project_from_template() {
+        let temp_dir = TempDir::new().unwrap();
+        let template_dir = temp_dir.path().join("template");
+        std::fs::create_dir(&template_dir).unwrap();
+        std::fs::write(
+            template_dir.join("template.json"),
+            r#"{
+                "name": "cli",
+                "project_types": ["binary"],
+                "files": [{ "path": "index.js", "content": "// {{project-name}}", "executable": true }],
+                "dev_dependencies": { "typescript": "^5.8.2" }
+            }"#,
+        )
+        .unwrap();
+
+        let vcs_config = Some(utils::VcsConfig {
+            vcs: utils::Vcs::None,
+            ignore_content: String::new(),
+        });
+
+        // Unsupported project types fail before anything is written
+        let path = temp_dir.path().join("my-lib");
+        let mut options = ProjectOptions {
+            project_type: ProjectType::Library,
+            vcs_config,
+            template: Some(template_dir),
+            ..Default::default()
+        };
+        let result = create_project(&path, true, &options);
+        assert!(matches!(result, Err(Error::Template { .. })));
+        assert!(!path.exists());
+
+        let path = temp_dir.path().join("my-cli");
+        options.project_type = ProjectType::Binary;
+        create_project(&path, true, &options).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(path.join("index.js")).unwrap(),
+            "// my-cli"
+        );
+        assert!(!path.join("src").exists());
+
+        let package_json = std::fs::read_to_string(path.join("package.json")).unwrap();
+        assert!(package_json.contains(r#""typescript": "^5.8.2""#));
+    }
+
+    #[test]
+    fn test_create_project_from_variant() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("shapes");
+        ProjectBuilder::new(&path)
+            .project_type(ProjectType::Library)
+            .template_variant("typescript-lib")
+            .vcs(utils::Vcs::None)
+            .build()?;
+
+        assert!(path.join("src/lib.ts").is_file());
+        assert!(path.join("tsconfig.json").is_file());
+        assert!(!path.join("src/lib.js").exists());
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path.join("package.json"))?)?;
+        assert_eq!(manifest["main"], "dist/lib.js");
+        assert!(manifest["devDependencies"]["typescript"].is_string());
+
+        let result = ProjectBuilder::new(temp_dir.path().join("other"))
+            .template_variant("typescript")
+            .vcs(utils::Vcs::None)
+            .build();
+        assert!(matches!(result, Err(Error::Template { .. })));
+        Ok(())
+    }
+
+    /// Every path below `dir`, relative to it
+    fn list_tree(dir: &Path) -> Vec<String> {
+        let mut paths: Vec<String> = ignore::WalkBuilder::new(dir)
+            .standard_filters(false)
+            .build()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path().strip_prefix(dir).ok()?;
+                (!path.as_os_str().is_empty()).then(|| path.to_string_lossy().replace('\\', "/"))
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_create_staged_rollback() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let write =
+            |root: &Path| std::fs::write(root.join("package.json"), "{}").map_err(Error::from);
+
+        // A failure after the move removes what was created, parents included
+        let path = temp_dir.path().join("nested/app");
+        let mut rollback = Rollback::default();
+        create_staged(&path, &mut rollback, write)?;
+        assert!(path.join("package.json").is_file());
+        assert_eq!(rollback.created, [temp_dir.path().join("nested"), path]);
+        rollback.undo()?;
+        assert_eq!(list_tree(temp_dir.path()), Vec::<String>::new());
+
+        // but only empties a destination that existed
+        let path = temp_dir.path().join("empty");
+        std::fs::create_dir(&path)?;
+        let mut rollback = Rollback::default();
+        create_staged(&path, &mut rollback, write)?;
+        assert!(rollback.created.is_empty());
+        rollback.undo()?;
+        assert!(path.is_dir());
+        assert_eq!(list_tree(temp_dir.path()), vec!["empty"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_project_rollback() {
+        let temp_dir = TempDir::new().unwrap();
+        let template_dir = temp_dir.path().join("template");
+        std::fs::create_dir(&template_dir).unwrap();
+        // `lib` is written as a file, so writing `lib/index.js` fails halfway
+        std::fs::write(
+            template_dir.join("template.json"),
+            r#"{
+                "name": "poisoned",
+                "files": [
+                    { "path": "src/new.js", "content": "" },
+                    { "path": "lib", "content": "" },
+                    { "path": "lib/index.js", "content": "" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let mut options = ProjectOptions {
+            vcs_config: Some(utils::VcsConfig {
+                vcs: utils::Vcs::None,
+                ..Default::default()
+            }),
+            template: Some(template_dir),
+            ..Default::default()
+        };
+        let before = list_tree(temp_dir.path());
+
+        // A new project, including its missing parent, is removed again
+        let path = temp_dir.path().join("nested").join("my-app");
+        assert!(create_project(&path, true, &options).is_err());
+        assert_eq!(list_tree(temp_dir.path()), before);
+
+        // An existing empty destination is kept
+        std::fs::create_dir(temp_dir.path().join("empty")).unwrap();
+        let before = list_tree(temp_dir.path());
+        assert!(create_project(&temp_dir.path().join("empty"), true, &options).is_err());
+        assert_eq!(list_tree(temp_dir.path()), before);
+
+        // Initializing removes only what it wrote
+        let path = temp_dir.path().join("existing");
+        std::fs::create_dir_all(path.join("src")).unwrap();
+        std::fs::write(path.join("src/app.js"), "").unwrap();
+        std::fs::write(path.join("notes.txt"), "keep me").unwrap();
+        assert!(create_project(&path, false, &options).is_err());
+        assert_eq!(list_tree(&path), vec!["notes.txt", "src", "src/app.js"]);
+        assert_eq!(
+            std::fs::read_to_string(path.join("notes.txt")).unwrap(),
+            "keep me"
+        );
+
+        // So is a workflow, written after the files; a .gitignore directory
+        // makes setting up git fail last
+        let path = temp_dir.path().join("workflow");
+        std::fs::create_dir_all(path.join(".gitignore")).unwrap();
+        let ci_options = ProjectOptions {
+            vcs_config: Some(utils::VcsConfig::default()),
+            template: None,
+            package_manager: Some(PackageManager::Npm),
+            ci: CiProvider::Github,
+            ..options.clone()
+        };
+        assert!(create_project(&path, false, &ci_options).is_err());
+        assert_eq!(list_tree(&path), vec![".gitignore"]);
+
+        // The partial project can be kept for debugging
+        options.keep_on_failure = true;
+        let path = temp_dir.path().join("kept");
+        assert!(create_project(&path, true, &options).is_err());
+        assert!(path.join("src/new.js").is_file());
+        assert!(path.join("lib").is_file());
+        assert!(!list_tree(temp_dir.path())
+            .iter()
+            .any(|path| path.starts_with(".kept")));
+    }
+
+    #[test]
+    fn test_project_builder() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+
+        // Invalid names are rejected before anything is written
+        let path = temp_dir.path().join("app");
+        let result = ProjectBuilder::new(&path)
+            .name("Not Valid")
+            .vcs(utils::Vcs::None)
+            .build();
+        assert!(matches!(result, Err(Error::InvalidPackageName { .. })));
+        assert!(!path.exists());
+
+        let project = ProjectBuilder::new(&path)
+            .name("@acme/app")
+            .vcs(utils::Vcs::None)
+            .license("Apache-2.0")
+            .build()?;
+        assert_eq!(project.path, path);
+        assert_eq!(project.name, "@acme/app");
+        let files: Vec<&str> = project.files.iter().filter_map(|f| f.to_str()).collect();
+        assert_eq!(
+            files,
+            vec![
+                "src/main.js",
+                ".editorconfig",
+                ".gitattributes",
+                "LICENSE",
+                "README.md",
+                "package.json",
+                ".nvmrc"
+            ]
+        );
+
+        // Initializing reports only the files it added
+        let path = temp_dir.path().join("existing");
+        std::fs::create_dir(&path)?;
+        std::fs::write(path.join("README.md"), "# Mine")?;
+        let project = ProjectBuilder::init(&path)
+            .project_type(ProjectType::Library)
+            .vcs(utils::Vcs::None)
+            .build()?;
+        assert!(project.files.contains(&PathBuf::from("src/lib.js")));
+        assert!(!project.files.contains(&PathBuf::from("README.md")));
+        assert_eq!(std::fs::read_to_string(path.join("README.md"))?, "# Mine");
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_name_override() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let manifest_name = |path: &Path| -> Result<String> {
+            let manifest: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(path.join("package.json"))?)?;
+            Ok(manifest["name"].as_str().unwrap_or_default().to_string())
+        };
+
+        // A scoped name in a folder that isn't named after the scope
+        let path = temp_dir.path().join("tools");
+        ProjectBuilder::new(&path)
+            .name("@myorg/tools")
+            .vcs(utils::Vcs::None)
+            .build()?;
+        assert_eq!(manifest_name(&path)?, "@myorg/tools");
+        assert!(std::fs::read_to_string(path.join("README.md"))?.contains("@myorg/tools"));
+
+        // A plain name below directories the name can't be derived from
+        let path = temp_dir.path().join("@one").join("@two").join("app");
+        std::fs::create_dir_all(&path)?;
+        let result = ProjectBuilder::init(&path).vcs(utils::Vcs::None).build();
+        assert!(matches!(result, Err(Error::InvalidPackageName { .. })));
+        ProjectBuilder::init(&path)
+            .name("app-core")
+            .vcs(utils::Vcs::None)
+            .build()?;
+        assert_eq!(manifest_name(&path)?, "app-core");
+
+        // An invalid explicit name is rejected before anything is written
+        let path = temp_dir.path().join("plain");
+        std::fs::create_dir(&path)?;
+        let result = ProjectBuilder::init(&path)
+            .name("@myorg")
+            .vcs(utils::Vcs::None)
+            .build();
+        assert!(matches!(result, Err(Error::InvalidPackageName { .. })));
+        assert_eq!(std::fs::read_dir(&path)?.count(), 0);
+
+        // A name shadowing a core module needs to be allowed explicitly
+        let path = temp_dir.path().join("http");
+        let result = ProjectBuilder::new(&path).vcs(utils::Vcs::None).build();
+        assert!(matches!(result, Err(Error::InvalidPackageName { .. })));
+        assert!(!path.join("package.json").exists());
+        ProjectBuilder::new(&path)
+            .allow_confusing_name(true)
+            .vcs(utils::Vcs::None)
+            .build()?;
+        assert_eq!(manifest_name(&path)?, "http");
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_cli_project() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("greet");
+        ProjectBuilder::new(&path)
+            .name("@acme/greet")
+            .project_type(ProjectType::Cli)
+            .vcs(utils::Vcs::None)
+            .build()?;
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path.join("package.json"))?)?;
+        assert_eq!(
+            manifest["bin"],
+            serde_json::json!({ "greet": "./src/main.js" })
+        );
+        assert_eq!(manifest["files"], serde_json::json!(["src"]));
+        assert_eq!(manifest["private"], false);
+        assert_eq!(manifest["type"], "module");
+
+        let entry = path.join("src/main.js");
+        let content = std::fs::read_to_string(&entry)?;
+        assert!(content.starts_with("#!/usr/bin/env node\n"));
+        assert!(content.contains("parseArgs"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_ne!(std::fs::metadata(&entry)?.permissions().mode() & 0o111, 0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_project_node_version() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+
+        let path = temp_dir.path().join("pinned");
+        ProjectBuilder::new(&path)
+            .vcs(utils::Vcs::None)
+            .node("^20.11")
+            .build()?;
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path.join("package.json"))?)?;
+        assert_eq!(manifest["engines"]["node"], "^20.11");
+        assert_eq!(std::fs::read_to_string(path.join(".nvmrc"))?, "20.11\n");
+
+        // Without a lower bound there is no version to pin
+        let path = temp_dir.path().join("unbounded");
+        let result = ProjectBuilder::new(&path)
+            .vcs(utils::Vcs::None)
+            .node("<23")
+            .build();
+        assert!(matches!(result, Err(Error::Config { .. })));
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_project_force() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("existing");
+        std::fs::create_dir(&path)?;
+        let manifest = r#"{"name": "existing", "version": "2.0.0"}"#;
+        std::fs::write(path.join("package.json"), manifest)?;
+
+        let result = ProjectBuilder::init(&path).vcs(utils::Vcs::None).build();
+        assert!(matches!(result, Err(Error::PackageJsonExists { .. })));
+        assert_eq!(std::fs::read_dir(&path)?.count(), 1);
+
+        let project = ProjectBuilder::init(&path)
+            .vcs(utils::Vcs::None)
+            .force(true)
+            .build()?;
+        assert!(project.files.contains(&PathBuf::from("src/main.js")));
+        assert!(!project.files.contains(&PathBuf::from("package.json")));
+        assert!(path.join("README.md").is_file());
+        assert_eq!(
+            std::fs::read_to_string(path.join("package.json"))?,
+            manifest
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_project_merge() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("existing");
+        std::fs::create_dir(&path)?;
+        std::fs::write(path.join("README.md"), "# Mine\n")?;
+        std::fs::write(path.join("LICENSE.txt"), "All rights reserved\n")?;
+        let manifest = r#"{
+  "name": "mine",
+  "version": "2.0.0",
+  "scripts": { "start": "node index.js" },
+  "dependencies": { "express": "^4.18.0" },
+  "volta": { "node": "20.0.0" }
+}"#;
+        std::fs::write(path.join("package.json"), manifest)?;
+
+        let sink = Arc::new(progress::RecordingSink::default());
+        let project = progress::with_sink(sink.clone(), || {
+            ProjectBuilder::init(&path)
+                .name("scaffolded")
+                .vcs(utils::Vcs::None)
+                .config_file(config::ConfigFile::PackageJson)
+                .merge(true)
+                .build()
+        })?;
+        assert!(project.files.contains(&PathBuf::from("package.json")));
+        assert!(!project.files.contains(&PathBuf::from("README.md")));
+        assert!(!project.files.contains(&PathBuf::from("LICENSE")));
+        assert_eq!(std::fs::read_to_string(path.join("README.md"))?, "# Mine\n");
+        assert!(!path.join("LICENSE").exists());
+
+        let merged: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path.join("package.json"))?)?;
+        assert_eq!(merged["name"], "mine");
+        assert_eq!(merged["version"], "2.0.0");
+        assert_eq!(merged["scripts"]["start"], "node index.js");
+        assert_eq!(merged["dependencies"]["express"], "^4.18.0");
+        assert_eq!(merged["volta"]["node"], "20.0.0");
+        assert!(merged["cargonode"].is_object());
+        assert!(merged["engines"]["node"].is_string());
+
+        let messages = sink.messages();
+        let texts: Vec<&str> = messages.iter().map(|m| m.text.as_str()).collect();
+        assert!(texts
+            .iter()
+            .any(|text| text.contains("into the existing package.json")));
+        assert!(texts
+            .iter()
+            .any(|text| text.contains("skipped `name`: the existing package.json decides")));
+        assert!(texts
+            .iter()
+            .any(|text| text.contains("skipped LICENSE: keeping the existing LICENSE.txt")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_project_merge_identity() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("checkout");
+        std::fs::create_dir(&path)?;
+        std::fs::write(
+            path.join("package.json"),
+            r#"{ "name": "mine", "license": "ISC", "main": "index.js" }"#,
+        )?;
+
+        // Without --name and --license, the scaffold describes the existing
+        // package rather than the directory it is in
+        let sink = Arc::new(progress::RecordingSink::default());
+        progress::with_sink(sink.clone(), || {
+            ProjectBuilder::init(&path)
+                .vcs(utils::Vcs::None)
+                .config_file(config::ConfigFile::PackageJson)
+                .merge(true)
+                .build()
+        })?;
+        let merged: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path.join("package.json"))?)?;
+        assert_eq!(merged["name"], "mine");
+        assert_eq!(merged["license"], "ISC");
+        assert!(merged.get("private").is_none());
+        assert!(merged.get("bin").is_none());
+        assert!(std::fs::read_to_string(path.join("LICENSE"))?.starts_with("ISC License"));
+        assert!(std::fs::read_to_string(path.join("README.md"))?.contains("mine"));
+        assert!(!sink
+            .messages()
+            .iter()
+            .any(|message| message.text.contains("`name`") || message.text.contains("`license`")));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_init_project_hooks() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hooked");
+        std::fs::create_dir(&path)?;
+        let manifest = |pre_init: &str| {
+            serde_json::json!({
+                "name": "hooked",
+                "cargonode": { "hooks": {
+                    "pre-init": { "command": "sh", "args": ["-c", pre_init] },
+                    "post-init": { "command": "sh", "args": ["-c", "echo \"$CN_EVENT $CN_EXIT_CODE\" > post.txt"] }
+                } }
+            })
+            .to_string()
+        };
+        // User hooks only run for events the project has no hook for
+        let sh = |script: &str| config::HookCommand {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+        };
+        let user_hooks = config::HooksConfig {
+            events: [
+                ("post-init", sh("echo user > post.txt")),
+                ("post-new", sh("echo \"$CN_EVENT\" > user.txt")),
+            ]
+            .into_iter()
+            .map(|(event, hook)| (event.to_string(), hook))
+            .collect(),
+            ..config::HooksConfig::default()
+        };
+        let init = || {
+            ProjectBuilder::init(&path)
+                .vcs(utils::Vcs::None)
+                .force(true)
+                .hooks(user_hooks.clone())
+        };
+
+        // A failed pre hook stops before anything is written
+        std::fs::write(path.join("package.json"), manifest("exit 5"))?;
+        let err = init().build().err().unwrap();
+        assert!(matches!(err, Error::Hook { exit_code: 5, .. }));
+        assert!(!path.join("src").exists());
+        assert!(!path.join("post.txt").exists());
+
+        std::fs::write(path.join("package.json"), manifest("true"))?;
+        init().build()?;
+        assert!(path.join("src/main.js").is_file());
+        assert_eq!(
+            std::fs::read_to_string(path.join("post.txt"))?,
+            "post-init 0\n"
+        );
+        let entries =
+            crate::journal::Journal::new(&crate::journal::journal_dir(&path)).read_entries()?;
+        let events: Vec<(&str, i32)> = entries
+            .iter()
+            .map(|entry| (entry.tool.as_str(), entry.exit_code))
+            .collect();
+        assert_eq!(events, [("pre-init", 5), ("pre-init", 0), ("post-init", 0)]);
+
+        let fresh = temp_dir.path().join("fresh");
+        ProjectBuilder::new(&fresh)
+            .vcs(utils::Vcs::None)
+            .install(false)
+            .hooks(user_hooks.clone())
+            .build()?;
+        assert_eq!(
+            std::fs::read_to_string(fresh.join("user.txt"))?,
+            "post-new\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_project_ci() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("checked");
+        let project = ProjectBuilder::new(&path)
+            .vcs(utils::Vcs::None)
+            .package_manager(PackageManager::Pnpm)
+            .install(false)
+            .config_file(config::ConfigFile::Toml)
+            .ci(CiProvider::Github)
+            .build()?;
+        assert!(project.files.contains(&PathBuf::from(ci::GITHUB_WORKFLOW)));
+        let workflow = std::fs::read_to_string(path.join(ci::GITHUB_WORKFLOW))?;
+        // Without an install there is no lockfile to install from
+        assert!(workflow.contains("run: pnpm install\n"));
+        assert!(!workflow.contains("cache-dependency-path"));
+        assert!(workflow.contains("run: cargonode test\n"));
+
+        // init leaves an existing workflow alone unless forced
+        let path = temp_dir.path().join("existing");
+        std::fs::create_dir_all(path.join(".github/workflows"))?;
+        std::fs::write(path.join(ci::GITHUB_WORKFLOW), "name: Mine\n")?;
+        let init = || {
+            ProjectBuilder::init(&path)
+                .vcs(utils::Vcs::None)
+                .package_manager(PackageManager::Npm)
+                .install(false)
+                .ci(CiProvider::Github)
+        };
+        let result = init().build();
+        assert!(matches!(result, Err(Error::Config { .. })));
+        assert!(!path.join("package.json").exists());
+
+        std::fs::write(path.join("package-lock.json"), "{}")?;
+        init().force(true).build()?;
+        let workflow = std::fs::read_to_string(path.join(ci::GITHUB_WORKFLOW))?;
+        assert!(workflow.contains("run: npm ci\n"));
+        assert!(workflow.contains("cache-dependency-path: package-lock.json\n"));
+        Ok(())
+    }
+
+    /// Every path below `dir` with the contents of its files
+    fn snapshot(dir: &Path) -> Vec<(String, Vec<u8>)> {
+        list_tree(dir)
+            .into_iter()
+            .map(|path| {
+                let contents = std::fs::read(dir.join(&path)).unwrap_or_default();
+                (path, contents)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_create_project_dry_run() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+
+        // No directory is created for a new project
+        let path = temp_dir.path().join("planned");
+        let project = ProjectBuilder::new(&path).dry_run(true).build()?;
+        assert!(!path.exists());
+        assert!(project.files.contains(&PathBuf::from("src/main.js")));
+        assert!(project.files.contains(&PathBuf::from(".gitignore")));
+
+        // A non-empty directory is only reported
+        let path = temp_dir.path().join("busy");
+        std::fs::create_dir(&path)?;
+        std::fs::write(path.join("notes.txt"), "keep")?;
+        let before = snapshot(&path);
+        ProjectBuilder::new(&path).dry_run(true).build()?;
+        assert_eq!(snapshot(&path), before);
+        assert!(ProjectBuilder::new(&path).build().is_err());
+
+        // Initializing leaves existing files, and everything else, untouched
+        std::fs::create_dir(path.join("src"))?;
+        std::fs::write(path.join("src/main.js"), "console.log('mine');\n")?;
+        let before = snapshot(&path);
+        let project = ProjectBuilder::init(&path)
+            .vcs(utils::Vcs::None)
+            .dry_run(true)
+            .build()?;
+        assert_eq!(snapshot(&path), before);
+        assert!(!project.files.contains(&PathBuf::from("src/main.js")));
+        assert!(project.files.contains(&PathBuf::from("package.json")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_plan() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("planned");
+        let options = ProjectOptions {
+            vcs_config: Some(utils::VcsConfig {
+                vcs: utils::Vcs::None,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // Planning writes nothing and lists what applying writes
+        let plan = ProjectPlan::new(&path, true, &options)?;
+        assert!(!path.exists());
+        assert_eq!(plan.config.name, "planned");
+        assert!(plan.skipped.is_empty());
+        let planned = plan.files.clone();
+        assert!(planned.contains(&PathBuf::from("src/main.js")));
+        assert!(!planned.contains(&PathBuf::from(".gitignore")));
+
+        let project = plan.apply()?;
+        assert_eq!(project.files, planned);
+        for file in &project.files {
+            assert!(path.join(file).is_file(), "{}", file.display());
+        }
+
+        // Planning an init again skips what is already there
+        let plan = ProjectPlan::new(
+            &path,
+            false,
+            &ProjectOptions {
+                force: true,
+                ..options
+            },
+        )?;
+        assert!(plan.files.is_empty(), "{:?}", plan.files);
+        assert!(plan.skipped.contains(&PathBuf::from("src/main.js")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_name() {
+        let timeout = Duration::from_secs(5);
+        let (base_url, handle) = serve_once(404, "");
+        assert_eq!(
+            check_name("brand-new-name", &base_url, timeout),
+            NameCheck::Available
+        );
+        assert!(handle.join().unwrap().starts_with("GET /brand-new-name "));
+
+        let document = br#"{"name": "express", "dist-tags": {"latest": "4.21.2"}, "versions": {}}"#;
+        let (base_url, handle) = serve_sequence(vec![(200, String::new(), document.to_vec())]);
+        assert_eq!(
+            check_name("express", &base_url, timeout),
+            NameCheck::Taken {
+                latest: Some("4.21.2".to_string())
+            }
+        );
+        // A taken name costs a single request
+        assert_eq!(handle.join().unwrap().len(), 1);
+
+        // A registry that never answers is given up on with a one-line reason
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let start = std::time::Instant::now();
+        let NameCheck::Failed(reason) = check_name("slow", &base_url, Duration::from_millis(200))
+        else {
+            panic!("expected the check to time out");
+        };
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(reason.contains("slow") && !reason.contains('\n'));
+
+        // Scoped names are not looked up at all
+        assert_eq!(
+            check_name("@acme/private", "http://127.0.0.1:9", timeout),
+            NameCheck::Skipped
+        );
+    }
+
+    #[test]
+    fn test_registry_host() {
+        assert_eq!(
+            registry_host("https://registry.npmjs.org/"),
+            "registry.npmjs.org"
+        );
+        assert_eq!(
+            registry_host("https://npm.corp.example/repository/npm"),
+            "npm.corp.example/repository/npm"
+        );
+    }
+
+    #[test]
+    fn test_create_project_registry_check_never_fails() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("offline-app");
+        ProjectBuilder::new(&path)
+            .vcs(utils::Vcs::None)
+            .registry_check("http://127.0.0.1:9")
+            .build()?;
+        assert!(path.join("package.json").is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_project_config_file() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+
+        let project = ProjectBuilder::new(temp_dir.path().join("toml"))
+            .vcs(utils::Vcs::None)
+            .config_file(config::ConfigFile::Toml)
+            .build()?;
+        assert!(project.files.contains(&PathBuf::from("cargonode.toml")));
+        let package_json = std::fs::read_to_string(project.path.join("package.json"))?;
+        assert!(!package_json.contains("cargonode"));
+        let tools = config::load_config(&project.path)?.tools;
+        assert_eq!(tools["test"].command, "node");
+
+        let project = ProjectBuilder::new(temp_dir.path().join("json"))
+            .vcs(utils::Vcs::None)
+            .config_file(config::ConfigFile::PackageJson)
+            .build()?;
+        assert!(!project.path.join("cargonode.toml").exists());
+        let tools = config::load_config(&project.path)?.tools;
+        assert_eq!(tools["test"].args, vec!["--test"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_workspace_member() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("package.json"),
+            r#"{"workspaces": ["packages/*"], "engines": {"node": ">=20"},
+                "cargonode": {"tools": {"build": {"command": "tsc", "inputs": ["src/**"]}},
+                              "hooks": {"post-new": {"command": "true"}},
+                              "upgrade": {"ignore": ["typescript"]}}}"#,
+        )?;
+        std::fs::create_dir_all(root.join("packages/app"))?;
+        std::fs::write(
+            root.join("packages/app/package.json"),
+            "{\n\t\"name\": \"app\"\n}\n",
+        )?;
+
+        // An unknown member fails before anything is written
+        let result = ProjectBuilder::new(root.join("packages/ui"))
+            .dependent("missing")
+            .build();
+        assert!(matches!(result, Err(Error::Config { .. })));
+        assert!(!root.join("packages/ui").exists());
+
+        ProjectBuilder::new(root.join("packages/ui"))
+            .project_type(ProjectType::Library)
+            .package_manager(PackageManager::Pnpm)
+            .dependent("app")
+            .build()?;
+
+        // No repository of its own; the root's settings are shared
+        assert!(!root.join("packages/ui/.git").exists());
+        let ui: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(
+            root.join("packages/ui/package.json"),
+        )?)?;
+        assert_eq!(ui["engines"]["node"], ">=20");
+        assert_eq!(ui["cargonode"]["tools"]["build"]["command"], "tsc");
+        // Settings of the workspace as a whole stay at the root
+        assert_eq!(ui["cargonode"].as_object().unwrap().len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(root.join("packages/app/package.json"))?,
+            "{\n\t\"name\": \"app\",\n\t\"dependencies\": {\n\t\t\"ui\": \"workspace:*\"\n\t}\n}\n"
+        );
+
+        // Outside of a workspace `--for` is an error
+        let outside = TempDir::new().unwrap();
+        let result = ProjectBuilder::new(outside.path().join("lib"))
+            .dependent("app")
+            .build();
+        assert!(matches!(result, Err(Error::Config { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_project_gitignore() {
+        if !std::process::Command::new("git")
+            .arg("--version")
+            .output()
+            .is_ok_and(|output| output.status.success())
+        {
+            return;
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("pnpm-project");
+
+        let options = ProjectOptions {
+            vcs_config: Some(utils::VcsConfig::default()),
+            package_manager: Some(PackageManager::Pnpm),
+            ..Default::default()
+        };
+        create_project(&path, true, &options).unwrap();
+
+        let gitignore = std::fs::read_to_string(path.join(".gitignore")).unwrap();
+        assert!(gitignore.starts_with(template::GITIGNORE_CONTENT));
+        assert!(gitignore.contains("package-lock.json"));
+        assert!(!gitignore.contains("pnpm-lock.yaml"));
+    }
 }
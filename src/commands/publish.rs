@@ -0,0 +1,536 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use ignore::gitignore::GitignoreBuilder;
+use semver::Version;
+
+use crate::error::Error;
+use crate::pack::{self, PackList};
+use crate::package_manager::{self, PackageManager};
+use crate::registry::RegistryClient;
+use crate::{progress, suggest, utils, Result};
+
+/// Fields of package.json that point at files consumers load
+const ENTRY_POINT_FIELDS: [&str; 4] = ["main", "types", "typings", "exports"];
+
+/// Options for publishing a package
+#[derive(Debug, Clone, Default)]
+pub struct PublishOptions {
+    /// Run the checks without invoking `npm publish`
+    pub dry_run: bool,
+
+    /// Registry passed on to `npm publish` with `--registry`; npm picks the
+    /// registry itself when it is not given on the command line
+    pub registry: Option<String>,
+
+    /// List the files npm would pack
+    pub list_files: bool,
+}
+
+/// Result of one pre-flight check
+#[derive(Debug)]
+pub struct PublishCheck {
+    /// What was checked
+    pub label: &'static str,
+    /// What passed, or why the check failed
+    pub result: Result<String>,
+}
+
+/// Check that the package at `project_dir` is ready to be published
+///
+/// # Arguments
+///
+/// * `project_dir` - Directory containing package.json
+/// * `client` - Registry client used to look up published versions
+///
+/// # Returns
+///
+/// * `Result<Vec<PublishCheck>>` - The result of every check, or an error if
+///   package.json cannot be read
+pub fn preflight_checks(
+    project_dir: &Path,
+    client: &impl RegistryClient,
+) -> Result<Vec<PublishCheck>> {
+    let content = fs::read_to_string(project_dir.join("package.json"))?;
+    let manifest: serde_json::Value = serde_json::from_str(&content)?;
+    let entry_points = entry_points(&manifest);
+
+    Ok(vec![
+        PublishCheck {
+            label: "name",
+            result: check_name(&manifest),
+        },
+        PublishCheck {
+            label: "version",
+            result: check_version(&manifest, client),
+        },
+        PublishCheck {
+            label: "entry points",
+            result: check_entry_points_exist(project_dir, &entry_points),
+        },
+        PublishCheck {
+            label: "packed files",
+            result: check_entry_points_packed(project_dir, &manifest, &entry_points),
+        },
+    ])
+}
+
+/// Run the pre-flight checks and publish the package containing `current_dir` with npm
+///
+/// Every check runs and is reported before anything is published. With
+/// `dry_run`, npm is never invoked.
+///
+/// # Arguments
+///
+/// * `options` - Whether to publish and where to
+/// * `current_dir` - Directory to start looking for the project from
+/// * `client` - Registry client used to look up published versions
+///
+/// # Returns
+///
+/// * `Result<()>` - Whether every check passed and the package was published
+pub fn publish(
+    options: &PublishOptions,
+    current_dir: &Path,
+    client: &impl RegistryClient,
+) -> Result<()> {
+    let project_root =
+        package_manager::find_project_root(current_dir).ok_or_else(|| Error::Config {
+            message: format!(
+                "package.json not found in {} or any parent directory",
+                current_dir.display()
+            ),
+            hint: Some(suggest::missing_manifest_hint()),
+        })?;
+
+    let checks = preflight_checks(&project_root, client)?;
+    let mut failures = Vec::new();
+    for check in checks {
+        match check.result {
+            Ok(detail) => progress::status("Passed", &format!("{}: {}", check.label, detail))?,
+            Err(err) => {
+                progress::warn(&format!("{}: {}", check.label, error_summary(&err)))?;
+                failures.push(err);
+            }
+        }
+    }
+
+    if options.list_files {
+        report_pack_list(&pack::pack_list(&project_root)?)?;
+    }
+
+    // A single failure keeps its own error and suggestion
+    if failures.len() == 1 {
+        return Err(failures.remove(0));
+    }
+    if !failures.is_empty() {
+        return Err(Error::Publish {
+            message: format!("{} publish checks failed", failures.len()),
+            suggestion: "Fix the problems listed above, then run `cargonode publish --dry-run` to check again.".to_string(),
+        });
+    }
+
+    if options.dry_run {
+        progress::warn("aborting publish due to dry run")?;
+        return Ok(());
+    }
+
+    PackageManager::Npm.ensure_available()?;
+    let mut command = Command::new(PackageManager::Npm.executable());
+    command.arg("publish").current_dir(&project_root);
+    progress::apply_color_env(&mut command);
+    if let Some(registry) = &options.registry {
+        command.args(["--registry", registry]);
+    }
+
+    progress::status("Publishing", "with npm")?;
+    let status = command.status()?;
+    if !status.success() {
+        return Err(Error::CommandFailed {
+            command: "npm publish".to_string(),
+            status,
+        });
+    }
+
+    Ok(())
+}
+
+/// The `Error:` line of a formatted error, or its first line
+fn error_summary(err: &Error) -> String {
+    let message = err.to_string();
+    message
+        .lines()
+        .find_map(|line| line.strip_prefix("Error: "))
+        .or_else(|| message.lines().next())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn string_field<'a>(manifest: &'a serde_json::Value, field: &str) -> Option<&'a str> {
+    manifest.get(field)?.as_str()
+}
+
+fn check_name(manifest: &serde_json::Value) -> Result<String> {
+    let name = string_field(manifest, "name").ok_or_else(|| Error::Publish {
+        message: "package.json has no `name` field".to_string(),
+        suggestion: "Add the name the package is published under to package.json.".to_string(),
+    })?;
+    utils::validate_package_name(name)?;
+    if let Some(reason) = utils::confusing_package_name(name) {
+        return Err(Error::Publish {
+            message: reason,
+            suggestion: "npm refuses names this close to a core module or an existing package; choose a distinct name or a scope.".to_string(),
+        });
+    }
+    Ok(format!("`{}` is a valid package name", name))
+}
+
+fn check_version(manifest: &serde_json::Value, client: &impl RegistryClient) -> Result<String> {
+    let raw = string_field(manifest, "version").ok_or_else(|| Error::Publish {
+        message: "package.json has no `version` field".to_string(),
+        suggestion: "Add a semver version such as `\"version\": \"1.0.0\"` to package.json."
+            .to_string(),
+    })?;
+    let version = Version::parse(raw).map_err(|e| Error::Publish {
+        message: format!("`{}` is not a valid semver version: {}", raw, e),
+        suggestion: "Use a version of the form MAJOR.MINOR.PATCH, e.g. `1.0.0`.".to_string(),
+    })?;
+
+    // The name check reports a missing name; there is nothing to look up here
+    let Some(name) = string_field(manifest, "name") else {
+        return Ok(format!("{} is a valid version", version));
+    };
+
+    let info = match client.get_package_info(name) {
+        Ok(info) => info,
+        Err(Error::PackageNotFound { .. }) => {
+            return Ok(format!("{} is the first release of `{}`", version, name))
+        }
+        Err(err) => return Err(err),
+    };
+
+    let published = info.version_list();
+    if published.contains(&version) {
+        return Err(Error::Publish {
+            message: format!(
+                "Version {} of `{}` is already published (published versions: {})",
+                version,
+                name,
+                published
+                    .iter()
+                    .map(Version::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            suggestion:
+                "Bump `version` in package.json, e.g. with `npm version patch`, and publish again."
+                    .to_string(),
+        });
+    }
+
+    Ok(format!("{} has not been published yet", version))
+}
+
+/// Entry point paths declared in package.json, as `(field, path)` pairs
+///
+/// Paths are relative to the package root, without a leading `./`. Subpath
+/// patterns in `exports` (e.g. `./lib/*.js`) are skipped.
+fn entry_points(manifest: &serde_json::Value) -> Vec<(&'static str, String)> {
+    fn collect(value: &serde_json::Value, paths: &mut Vec<String>) {
+        match value {
+            serde_json::Value::String(path) if !path.contains('*') => {
+                paths.push(path.trim_start_matches("./").to_string())
+            }
+            serde_json::Value::Array(values) => values.iter().for_each(|v| collect(v, paths)),
+            serde_json::Value::Object(map) => map.values().for_each(|v| collect(v, paths)),
+            _ => {}
+        }
+    }
+
+    let mut entry_points = Vec::new();
+    for field in ENTRY_POINT_FIELDS {
+        let mut paths = Vec::new();
+        if let Some(value) = manifest.get(field) {
+            collect(value, &mut paths);
+        }
+        for path in paths {
+            if !entry_points.contains(&(field, path.clone())) {
+                entry_points.push((field, path));
+            }
+        }
+    }
+    entry_points
+}
+
+fn describe(entry_points: &[&(&str, String)]) -> String {
+    entry_points
+        .iter()
+        .map(|(field, path)| format!("`{}` ({})", field, path))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn check_entry_points_exist(
+    project_dir: &Path,
+    entry_points: &[(&'static str, String)],
+) -> Result<String> {
+    if entry_points.is_empty() {
+        return Ok("no `main`, `exports` or `types` declared".to_string());
+    }
+
+    let missing: Vec<_> = entry_points
+        .iter()
+        .filter(|(_, path)| !project_dir.join(path).exists())
+        .collect();
+    if !missing.is_empty() {
+        return Err(Error::Publish {
+            message: format!("Entry points do not exist: {}", describe(&missing)),
+            suggestion: "Build the package with `cargonode build` before publishing, or fix the paths in package.json.".to_string(),
+        });
+    }
+
+    Ok(format!("{} entry point(s) exist", entry_points.len()))
+}
+
+fn check_entry_points_packed(
+    project_dir: &Path,
+    manifest: &serde_json::Value,
+    entry_points: &[(&'static str, String)],
+) -> Result<String> {
+    // npm always packs the `main` file, whatever `files` and .npmignore say
+    let checked: Vec<_> = entry_points
+        .iter()
+        .filter(|(field, _)| *field != "main")
+        .collect();
+
+    let (excluded, source): (Vec<_>, &str) = match manifest.get("files").and_then(|f| f.as_array())
+    {
+        Some(files) => {
+            let files: Vec<&str> = files.iter().filter_map(|f| f.as_str()).collect();
+            (
+                checked
+                    .into_iter()
+                    .filter(|(_, path)| !pack::listed_in_files(&files, path))
+                    .collect(),
+                "the `files` field",
+            )
+        }
+        None => {
+            // Without .npmignore, npm falls back to .gitignore
+            let Some(ignore_file) = [".npmignore", ".gitignore"]
+                .iter()
+                .map(|name| project_dir.join(name))
+                .find(|path| path.is_file())
+            else {
+                return Ok("no `files` field or .npmignore restricts the package".to_string());
+            };
+
+            let mut builder = GitignoreBuilder::new(project_dir);
+            if let Some(err) = builder.add(&ignore_file) {
+                return Err(Error::Publish {
+                    message: format!("Failed to read {}: {}", ignore_file.display(), err),
+                    suggestion: "Fix the syntax of the ignore file.".to_string(),
+                });
+            }
+            let matcher = builder.build().map_err(|e| Error::Publish {
+                message: format!("Failed to read {}: {}", ignore_file.display(), e),
+                suggestion: "Fix the syntax of the ignore file.".to_string(),
+            })?;
+            (
+                checked
+                    .into_iter()
+                    .filter(|(_, path)| {
+                        matcher
+                            .matched_path_or_any_parents(path, project_dir.join(path).is_dir())
+                            .is_ignore()
+                    })
+                    .collect(),
+                if ignore_file.ends_with(".npmignore") {
+                    ".npmignore"
+                } else {
+                    ".gitignore (used because there is no .npmignore)"
+                },
+            )
+        }
+    };
+
+    if !excluded.is_empty() {
+        return Err(Error::Publish {
+            message: format!(
+                "Entry points are excluded from the package by {}: {}",
+                source,
+                describe(&excluded)
+            ),
+            suggestion:
+                "Add the paths to `files` in package.json, or stop ignoring them in .npmignore."
+                    .to_string(),
+        });
+    }
+
+    Ok("entry points are included in the package".to_string())
+}
+
+/// Print the files npm would pack, with their sizes and a total
+///
+/// Suspicious files are flagged in the listing and warned about after it.
+///
+/// # Arguments
+///
+/// * `list` - The files, as computed by [`pack::pack_list`]
+///
+/// # Returns
+///
+/// * `Result<()>` - Whether the listing could be written
+pub fn report_pack_list(list: &PackList) -> Result<()> {
+    let sizes: Vec<String> = list
+        .files
+        .iter()
+        .map(|file| progress::format_bytes(file.size))
+        .collect();
+    let width = sizes.iter().map(String::len).max().unwrap_or_default();
+    let lines: Vec<String> = list
+        .files
+        .iter()
+        .zip(&sizes)
+        .map(|(file, size)| {
+            let flag = if file.warning.is_some() { "  (!)" } else { "" };
+            format!("{:>width$}  {}{}", size, file.path, flag)
+        })
+        .collect();
+    if !lines.is_empty() {
+        progress::write_message(&lines.join("\n"))?;
+    }
+    progress::status(
+        "Packed",
+        &format!(
+            "{} file{}, {} total",
+            list.files.len(),
+            if list.files.len() == 1 { "" } else { "s" },
+            progress::format_bytes(list.total_bytes)
+        ),
+    )?;
+    for file in list.warnings() {
+        if let Some(warning) = &file.warning {
+            progress::warn(&format!("{} would be published: {}", file.path, warning))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::dependency::tests::MockClient;
+
+    fn create_package(manifest: &str, files: &[&str]) -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("package.json"), manifest).unwrap();
+        for file in files {
+            let path = temp_dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        temp_dir
+    }
+
+    fn dry_run() -> PublishOptions {
+        PublishOptions {
+            dry_run: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_publish_dry_run_passes() -> Result<()> {
+        let temp_dir = create_package(
+            r#"{
+  "name": "my-lib",
+  "version": "1.1.0",
+  "main": "dist/index.js",
+  "types": "dist/index.d.ts",
+  "exports": {".": {"import": "./dist/index.mjs", "require": "./dist/index.js"}, "./utils/*": "./dist/utils/*.js"},
+  "files": ["dist/"]
+}"#,
+            &["dist/index.js", "dist/index.d.ts", "dist/index.mjs"],
+        );
+        let client = MockClient::new().with_package("my-lib", &["1.0.0"], "1.0.0");
+
+        let checks = preflight_checks(temp_dir.path(), &client)?;
+        assert!(checks.iter().all(|check| check.result.is_ok()));
+        publish(&dry_run(), temp_dir.path(), &client)?;
+
+        // A package that was never published passes too
+        publish(&dry_run(), temp_dir.path(), &MockClient::new())?;
+
+        let list_files = PublishOptions {
+            list_files: true,
+            ..dry_run()
+        };
+        publish(&list_files, temp_dir.path(), &client)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_publish_version_already_published() {
+        let temp_dir = create_package(r#"{"name": "my-lib", "version": "1.0.0"}"#, &[]);
+        let client = MockClient::new().with_package("my-lib", &["0.9.0", "1.0.0"], "1.0.0");
+
+        let err = publish(&dry_run(), temp_dir.path(), &client).unwrap_err();
+        assert!(matches!(err, Error::Publish { .. }));
+        assert!(err.to_string().contains("published versions: 0.9.0, 1.0.0"));
+    }
+
+    #[test]
+    fn test_publish_entry_point_checks() -> Result<()> {
+        let temp_dir = create_package(
+            r#"{"name": "my-lib", "version": "1.0.0", "main": "lib/index.js", "types": "types/index.d.ts", "files": ["lib"]}"#,
+            &["types/index.d.ts"],
+        );
+
+        let checks = preflight_checks(temp_dir.path(), &MockClient::new())?;
+        let failed: Vec<_> = checks
+            .iter()
+            .filter_map(|check| Some((check.label, check.result.as_ref().err()?.to_string())))
+            .collect();
+        assert_eq!(failed.len(), 2);
+        assert_eq!(failed[0].0, "entry points");
+        assert!(failed[0].1.contains("`main` (lib/index.js)"));
+        assert_eq!(failed[1].0, "packed files");
+        assert!(failed[1].1.contains("`types` (types/index.d.ts)"));
+
+        let err = publish(&dry_run(), temp_dir.path(), &MockClient::new()).unwrap_err();
+        assert!(err.to_string().contains("2 publish checks failed"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_publish_npmignore() -> Result<()> {
+        let temp_dir = create_package(
+            r#"{"name": "my-lib", "version": "1.0.0", "exports": "./dist/index.js"}"#,
+            &["dist/index.js"],
+        );
+        fs::write(temp_dir.path().join(".npmignore"), "dist/\n")?;
+
+        let err = publish(&dry_run(), temp_dir.path(), &MockClient::new()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("excluded from the package by .npmignore"));
+
+        fs::write(temp_dir.path().join(".npmignore"), "src/\n")?;
+        publish(&dry_run(), temp_dir.path(), &MockClient::new())?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_package_name() {
+        let temp_dir = create_package(r#"{"name": "My_Lib", "version": "1.0.0"}"#, &[]);
+        let err = publish(&dry_run(), temp_dir.path(), &MockClient::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidPackageName { .. }));
+    }
+}
@@ -0,0 +1,190 @@
+use std::fs;
+use std::path::Path;
+
+use crate::dependency;
+use crate::error::Error;
+use crate::package_manager::PmAction;
+use crate::{package_manager, progress, suggest, Result};
+
+/// Options for removing dependencies
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RemoveOptions {
+    /// Print what would change without writing package.json or installing
+    pub dry_run: bool,
+
+    /// Whether to prune node_modules with the project's package manager
+    pub install: bool,
+}
+
+/// Remove dependencies from the package.json of the project containing `current_dir`
+///
+/// Packages are removed from every dependency section. Packages that are not
+/// dependencies are reported individually; the command only fails if none of
+/// the packages were found.
+///
+/// # Arguments
+///
+/// * `packages` - Names of the packages to remove
+/// * `options` - Whether to write and install the changes
+/// * `current_dir` - Directory to start looking for the project from
+///
+/// # Returns
+///
+/// * `Result<()>` - Whether the dependencies were removed
+pub fn remove_dependencies(
+    packages: &[String],
+    options: &RemoveOptions,
+    current_dir: &Path,
+) -> Result<()> {
+    let project_root =
+        package_manager::find_project_root(current_dir).ok_or_else(|| Error::Config {
+            message: format!(
+                "package.json not found in {} or any parent directory",
+                current_dir.display()
+            ),
+            hint: Some(suggest::missing_manifest_hint()),
+        })?;
+
+    let package_json_path = project_root.join("package.json");
+    let original = fs::read_to_string(&package_json_path)?;
+    let mut manifest: serde_json::Value = serde_json::from_str(&original)?;
+
+    let mut found = Vec::new();
+    for package in packages {
+        let removed = dependency::remove_dependency(&mut manifest, package)?;
+        if removed.is_empty() {
+            progress::warn(&format!(
+                "`{}` is not a dependency of this package",
+                package
+            ))?;
+            continue;
+        }
+
+        found.push(package.clone());
+        let sections = removed
+            .iter()
+            .map(|(section, _)| *section)
+            .collect::<Vec<_>>()
+            .join(", ");
+        progress::status("Removing", &format!("{} from {}", package, sections))?;
+    }
+
+    if found.is_empty() {
+        return Err(Error::Config {
+            message: format!(
+                "None of the requested packages ({}) are dependencies in {}",
+                packages.join(", "),
+                package_json_path.display()
+            ),
+            hint: None,
+        });
+    }
+
+    if options.dry_run {
+        progress::warn("aborting remove due to dry run")?;
+        return Ok(());
+    }
+
+    let package_manager = package_manager::detect_package_manager(&project_root)?;
+    if !options.install {
+        dependency::write_manifest(&package_json_path, &manifest, &original)?;
+        progress::note(&format!(
+            "Run `{}` to prune the removed dependencies",
+            package_manager.install_command_line()
+        ))?;
+        return Ok(());
+    }
+
+    // The package manager removes them from package.json, node_modules and
+    // the lockfile at once; some refuse packages package.json no longer lists
+    progress::status("Pruning", &format!("dependencies with {}", package_manager))?;
+    package_manager.run(PmAction::Remove, &found, &project_root)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    const MANIFEST: &str = r#"{
+  "name": "app",
+  "dependencies": {
+    "a": "^1.0.0",
+    "express": "^4.21.2",
+    "z": "^1.0.0"
+  },
+  "devDependencies": {
+    "express": "^4.21.2",
+    "typescript": "^5.8.2"
+  }
+}
+"#;
+
+    fn options() -> RemoveOptions {
+        RemoveOptions {
+            dry_run: false,
+            install: false,
+        }
+    }
+
+    #[test]
+    fn test_remove_dependencies() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        fs::write(dir_path.join("package.json"), MANIFEST)?;
+
+        remove_dependencies(
+            &["express".to_string(), "missing".to_string()],
+            &options(),
+            dir_path,
+        )?;
+
+        assert_eq!(
+            fs::read_to_string(dir_path.join("package.json"))?,
+            r#"{
+  "name": "app",
+  "dependencies": {
+    "a": "^1.0.0",
+    "z": "^1.0.0"
+  },
+  "devDependencies": {
+    "typescript": "^5.8.2"
+  }
+}
+"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_dependencies_none_found() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        fs::write(dir_path.join("package.json"), MANIFEST)?;
+
+        let result = remove_dependencies(&["missing".to_string()], &options(), dir_path);
+        assert!(matches!(result, Err(Error::Config { .. })));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_dependencies_dry_run() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        fs::write(dir_path.join("package.json"), MANIFEST)?;
+
+        let options = RemoveOptions {
+            dry_run: true,
+            install: true,
+        };
+        remove_dependencies(&["typescript".to_string()], &options, dir_path)?;
+        assert_eq!(fs::read_to_string(dir_path.join("package.json"))?, MANIFEST);
+
+        Ok(())
+    }
+}
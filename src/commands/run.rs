@@ -1,10 +1,24 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, ExitStatus};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use crate::cache::{self, Cache, CacheEntry, CacheKey, Lookup, OutputRecord};
 use crate::config::{self};
 use crate::error::Error;
+use crate::hooks::{self, HookContext};
+use crate::inputs::{InputTracker, INPUT_INDEX_FILE};
+use crate::journal::{self, EntryKind, Journal, JournalEntry, StepEntry};
+use crate::node;
 use crate::outputs::OutputVerifier;
+use crate::process::{self, WaitOutcome};
 use crate::progress;
+use crate::test_output::TestOutputParser;
+use crate::utils;
 use crate::Result;
 
 /// Options for running a tool
@@ -17,67 +31,719 @@ pub struct RunOptions {
 
     /// Whether to print verbose output
     pub verbose: bool,
+
+    /// Whether to hide the tool's output unless it fails
+    pub quiet: bool,
+
+    /// Seconds after which the command is stopped, overriding the tool's
+    /// `timeout_secs`
+    pub timeout: Option<u64>,
+
+    /// Extra arguments appended verbatim to the tool's configured `args`
+    pub args: Vec<String>,
+
+    /// File the tool's raw output is copied to when it runs
+    pub log_file: Option<PathBuf>,
+
+    /// Summarize the failed tests of a test runner's output when it fails
+    pub summarize_tests: bool,
 }
 
 /// Result of running a tool
 pub struct RunResult {
     /// Exit status of the command
     pub status: ExitStatus,
+
+    /// Whether execution was skipped because a cached run is still valid
+    pub cached: bool,
+
+    /// Verified output files, relative to the project directory
+    pub outputs: Vec<String>,
+
+    /// Commands that ran, in order; empty when the run was cached
+    pub steps: Vec<StepResult>,
+}
+
+/// Result of one command of a tool's run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepResult {
+    /// Command line of the step
+    pub command: String,
+
+    /// Exit code of the step
+    pub exit_code: i32,
+
+    /// Time the step took
+    pub duration: Duration,
+}
+
+/// Command line of a step, for messages and the journal
+fn command_line(step: &config::CommandStep) -> String {
+    format!("{} {}", step.command, step.args.join(" "))
+}
+
+/// Time a program may take to print its `--version`
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Versions printed by the programs tools run, by executable
+static TOOL_VERSIONS: OnceLock<Mutex<HashMap<PathBuf, Option<String>>>> = OnceLock::new();
+
+/// Tools whose `--version` is part of the cache key, when installed in
+/// `node_modules/.bin`
+const VERSIONED_TOOLS: [&str; 14] = [
+    "biome", "esbuild", "eslint", "jest", "mocha", "prettier", "rollup", "swc", "tsc", "tsup",
+    "tsx", "vite", "vitest", "webpack",
+];
+
+/// Version of the program a tool runs, for its cache key
+///
+/// Only the known tools installed in `node_modules/.bin`, by name or by
+/// path, are asked for their `--version`, once per process. Any other
+/// program could do anything when run with an argument it does not expect.
+fn tool_version(command: &str, working_dir: &Path) -> Option<String> {
+    let path = Path::new(command);
+    let name = path.file_stem()?.to_str()?;
+    if !VERSIONED_TOOLS.contains(&name) {
+        return None;
+    }
+    let program = if path.components().count() == 1 {
+        utils::find_local_bin(working_dir, name)?
+    } else if path.parent()?.ends_with("node_modules/.bin") {
+        working_dir.join(path)
+    } else {
+        return None;
+    };
+
+    let versions = TOOL_VERSIONS.get_or_init(Default::default);
+    if let Some(version) = versions.lock().unwrap().get(&program) {
+        return version.clone();
+    }
+    let version = probe_version(&program);
+    versions.lock().unwrap().insert(program, version.clone());
+    version
+}
+
+/// First line `program --version` prints, if it succeeds within
+/// `VERSION_TIMEOUT`
+fn probe_version(program: &Path) -> Option<String> {
+    let mut child = Command::new(program)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let deadline = Instant::now() + VERSION_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait().ok()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return None;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    if !status.success() {
+        return None;
+    }
+
+    let mut stdout = String::new();
+    child.stdout.take()?.read_to_string(&mut stdout).ok()?;
+    stdout
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Compute the cache key for a tool
+///
+/// The key covers the content of the tool's inputs, its command lines, the
+/// variables it sets, and the versions of Node.js and of the program it
+/// runs, so editing a command, changing its environment or upgrading Node.js
+/// or the tool invalidates cached runs.
+///
+/// # Arguments
+///
+/// * `tool_config` - The tool, with its variables expanded
+/// * `project_dir` - Project directory
+/// * `cache_config` - Variables left out of the key and the size above
+///   which input files are reported
+///
+/// # Returns
+///
+/// * `Result<CacheKey>` - The components of the key
+fn cache_key(
+    tool_config: &config::ToolConfig,
+    project_dir: &Path,
+    cache_config: &config::CacheConfig,
+) -> Result<CacheKey> {
+    // Outputs are never inputs, or every run would invalidate the next one
+    let excludes = tool_config
+        .exclude
+        .iter()
+        .chain(&tool_config.outputs)
+        .cloned()
+        .collect();
+    let inputs = InputTracker::new(project_dir, tool_config.inputs.clone())
+        .with_excludes(excludes)
+        .with_state_dir(&utils::state_dir(project_dir))
+        .with_index(cache::cache_dir(project_dir).join(INPUT_INDEX_FILE))
+        .with_large_file_warning(cache_config.large_input_bytes)
+        .calculate_hash()?;
+
+    let commands = tool_config.commands();
+    let env: BTreeSet<String> = commands
+        .iter()
+        .flat_map(|step| &step.env)
+        .filter(|(name, _)| !cache_config.exclude_env.contains(name))
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect();
+    let working_dir = match &tool_config.working_dir {
+        Some(dir) => project_dir.join(dir),
+        None => project_dir.to_path_buf(),
+    };
+
+    Ok(CacheKey {
+        inputs,
+        commands: commands
+            .iter()
+            .map(|step| {
+                std::iter::once(step.command.clone())
+                    .chain(step.args.iter().cloned())
+                    .collect()
+            })
+            .collect(),
+        env: env.into_iter().collect(),
+        node_version: node::installed_version().map(str::to_string),
+        tool_version: commands
+            .first()
+            .and_then(|step| tool_version(&step.command, &working_dir)),
+    })
+}
+
+/// Expand `${VAR}` and `${VAR:-default}` references in `text`
+///
+/// A default is used when the variable is unset or empty. `$${` stands for a
+/// literal `${`, and a `$` not followed by `{` is kept as is.
+///
+/// # Arguments
+///
+/// * `text` - Configured value
+/// * `tool_name` - Name of the tool, used in error messages
+/// * `lookup` - Value of a variable, if it is defined
+///
+/// # Returns
+///
+/// * `Result<String>` - The expanded value, or `Error::Config` naming an
+///   undefined variable
+fn interpolate(
+    text: &str,
+    tool_name: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<String> {
+    let invalid = |message: String| Error::Config {
+        message: format!("{} in the configuration of tool '{}'", message, tool_name),
+        hint: None,
+    };
+
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("$${") {
+            expanded.push_str("${");
+            rest = after;
+            continue;
+        }
+        let Some(after) = rest.strip_prefix("${") else {
+            expanded.push('$');
+            rest = &rest[1..];
+            continue;
+        };
+        let Some(end) = after.find('}') else {
+            return Err(invalid(format!("Unclosed '${{' in '{}'", text)));
+        };
+        let (name, default) = match after[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&after[..end], None),
+        };
+        let valid_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(invalid(format!("Invalid variable name '{}'", name)));
+        }
+
+        match (lookup(name).filter(|value| !value.is_empty()), default) {
+            (Some(value), _) => expanded.push_str(&value),
+            (None, Some(default)) => expanded.push_str(default),
+            (None, None) => {
+                return Err(invalid(format!(
+                    "Environment variable '{}' is not defined",
+                    name
+                )))
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Expand variable references in a tool's `args`, `env` values and
+/// `working_dir`, and in the `args` and `env` values of its steps
+///
+/// `PROJECT_DIR`, `TOOL_NAME` and `CACHE_DIR` are always defined; other
+/// references are looked up in cargonode's own environment.
+fn expand_variables(
+    tool_name: &str,
+    tool_config: &config::ToolConfig,
+    project_dir: &Path,
+) -> Result<config::ToolConfig> {
+    let builtin = |name: &str| match name {
+        "PROJECT_DIR" => Some(project_dir.display().to_string()),
+        "TOOL_NAME" => Some(tool_name.to_string()),
+        "CACHE_DIR" => Some(cache::cache_dir(project_dir).display().to_string()),
+        _ => None,
+    };
+    let lookup = |name: &str| builtin(name).or_else(|| std::env::var(name).ok());
+    let expand = |text: &String| interpolate(text, tool_name, &lookup);
+    let expand_args = |args: &[String]| args.iter().map(expand).collect::<Result<_>>();
+    let expand_env = |env: &HashMap<String, String>| {
+        env.iter()
+            .map(|(key, value)| Ok((key.clone(), expand(value)?)))
+            .collect::<Result<_>>()
+    };
+
+    let mut expanded = tool_config.clone();
+    expanded.args = expand_args(&tool_config.args)?;
+    expanded.env = expand_env(&tool_config.env)?;
+    if let Some(steps) = &mut expanded.steps {
+        for step in steps {
+            step.args = expand_args(&step.args)?;
+            step.env = expand_env(&step.env)?;
+        }
+    }
+    expanded.working_dir = tool_config.working_dir.as_ref().map(expand).transpose()?;
+    Ok(expanded)
 }
 
 /// Run a tool with the given options
+///
+/// `tool-started` and `tool-finished` events are reported around the run,
+/// and the tool's `pre-<tool>` and `post-<tool>` hooks around those. A
+/// failed `pre-<tool>` hook keeps the tool from running.
 pub fn run_tool(
     tool_name: &str,
     config: &config::CargonodeConfig,
     options: &RunOptions,
 ) -> Result<RunResult> {
-    let tool_config = config::get_tool_config(config, tool_name).ok_or_else(|| Error::Config {
-        message: format!("Tool '{}' not found in configuration", tool_name),
+    let context = HookContext {
+        operation: tool_name,
+        tool: Some(tool_name),
+        project_dir: &options.project_dir,
+    };
+    if let Some(run) = hooks::run_pre(&config.hooks, &context)? {
+        run.record(&options.project_dir)?;
+        run.check(&config.hooks)?;
+    }
+
+    progress::emit(&progress::Event::ToolStarted {
+        tool: tool_name.to_string(),
+    })?;
+    let start = Instant::now();
+
+    let result = run_tool_inner(tool_name, config, options);
+    let (exit_code, cached, outputs) = match &result {
+        Ok(result) => (
+            utils::exit_code(&result.status),
+            result.cached,
+            result.outputs.clone(),
+        ),
+        Err(Error::CommandFailed { status, .. }) => (utils::exit_code(status), false, Vec::new()),
+        Err(e) => (e.exit_code(), false, Vec::new()),
+    };
+    progress::emit(&progress::Event::ToolFinished {
+        tool: tool_name.to_string(),
+        duration_ms: start.elapsed().as_millis() as u64,
+        exit_code,
+        cached,
+        outputs,
     })?;
 
+    if let Some(run) = hooks::run_post(&config.hooks, &context, exit_code)? {
+        run.record(&options.project_dir)?;
+        // The tool's own failure takes precedence over the hook's
+        let checked = run.check(&config.hooks);
+        if result.is_ok() {
+            checked?;
+        }
+    }
+
+    result
+}
+
+fn run_tool_inner(
+    tool_name: &str,
+    config: &config::CargonodeConfig,
+    options: &RunOptions,
+) -> Result<RunResult> {
+    let tool_config = config::get_tool_config(config, tool_name)
+        .ok_or_else(|| config::tool_not_found(config, tool_name))?;
+
     config::validate_tool_config(tool_name, tool_config)?;
 
-    let status = execute_command(
-        tool_name,
-        tool_config,
-        &options.project_dir,
-        options.verbose,
-    )?;
+    // Extra arguments are part of the command line, and so of the cache key;
+    // unlike the configured ones they are passed verbatim. A pipeline passes
+    // them to its last step.
+    let mut tool_config = expand_variables(tool_name, tool_config, &options.project_dir)?;
+    match tool_config
+        .steps
+        .as_mut()
+        .and_then(|steps| steps.last_mut())
+    {
+        Some(step) => step.args.extend(options.args.iter().cloned()),
+        None => tool_config.args.extend(options.args.iter().cloned()),
+    }
+    let tool_config = &tool_config;
+
+    let key = cache_key(tool_config, &options.project_dir, &config.cache)?;
+    let input_hash = key.hash();
+    progress::debug(&format!("cache key of '{}': {}", tool_name, input_hash))?;
+    let commands = tool_config.commands();
+    let pipeline = tool_config.steps.is_some();
+    let command_str = commands
+        .iter()
+        .map(command_line)
+        .collect::<Vec<_>>()
+        .join(" && ");
+    let cache = Cache::new(&cache::cache_dir(&options.project_dir)).with_ttl(config.cache.ttl());
+    let journal = Journal::new(&journal::journal_dir(&options.project_dir));
+
+    let run_start = Instant::now();
+    let record = |exit_code: i32, from_cache: bool, steps: &[StepResult]| {
+        // A single command is described by the entry itself
+        let steps = if pipeline {
+            steps
+                .iter()
+                .map(|step| StepEntry {
+                    command: step.command.clone(),
+                    exit_code: step.exit_code,
+                    duration_ms: step.duration.as_millis() as u64,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        journal.add_entry(JournalEntry {
+            tool: tool_name.to_string(),
+            kind: EntryKind::Tool,
+            command: command_str.clone(),
+            input_hash: input_hash.clone(),
+            exit_code,
+            from_cache,
+            timestamp: utils::unix_timestamp(),
+            duration_ms: run_start.elapsed().as_millis() as u64,
+            steps,
+        })
+    };
+
+    if !options.force {
+        match cache.lookup(tool_name, &input_hash, &options.project_dir)? {
+            Lookup::Hit(entry) if entry.is_success() => {
+                progress::status("Fresh", &format!("{} (cached)", tool_name))?;
+                record(entry.exit_code, true, &[])?;
+                return Ok(RunResult {
+                    status: ExitStatus::default(),
+                    cached: true,
+                    outputs: entry
+                        .outputs
+                        .into_iter()
+                        .map(|output| output.path)
+                        .collect(),
+                    steps: Vec::new(),
+                });
+            }
+            Lookup::Stale(reason) if options.verbose => {
+                progress::note(&format!(
+                    "Cache bypassed for tool '{}': {}",
+                    tool_name, reason
+                ))?;
+            }
+            Lookup::Stale(reason) => {
+                progress::debug(&format!("cache of '{}' is stale: {}", tool_name, reason))?;
+            }
+            _ => {}
+        }
+    }
+
+    // The log covers every step of the run
+    let log = options
+        .log_file
+        .as_ref()
+        .map(File::create)
+        .transpose()?
+        .map(Mutex::new);
+
+    // Tools can write their outputs without creating directories first
+    let verifier = OutputVerifier::new(&options.project_dir, tool_config.outputs.clone());
+    verifier.prepare_output_dirs()?;
+
+    // Steps run in order; the first failure skips the rest unless the
+    // failed step allows it
+    let mut status = ExitStatus::default();
+    let mut steps = Vec::new();
+    for (index, step) in commands.iter().enumerate() {
+        let label = format!("{}/{} `{}`", index + 1, commands.len(), command_line(step));
+        if pipeline {
+            progress::status("Step", &label)?;
+        }
+
+        let step_config = config::ToolConfig {
+            command: step.command.clone(),
+            args: step.args.clone(),
+            env: step.env.clone(),
+            steps: None,
+            ..tool_config.clone()
+        };
+        let capture = OutputCapture {
+            log: log.as_ref(),
+            tests: options
+                .summarize_tests
+                .then(|| Mutex::new(TestOutputParser::default())),
+        };
+        let start = Instant::now();
+        let result = execute_command(
+            tool_name,
+            &step_config,
+            &options.project_dir,
+            options.verbose,
+            options.quiet,
+            options.timeout.or(tool_config.timeout_secs),
+            &capture,
+        );
+        let exit_code = match &result {
+            Ok(status) | Err(Error::CommandFailed { status, .. }) => utils::exit_code(status),
+            Err(e) => e.exit_code(),
+        };
+        steps.push(StepResult {
+            command: command_line(step),
+            exit_code,
+            duration: start.elapsed(),
+        });
+        if pipeline {
+            progress::emit(&progress::Event::StepFinished {
+                tool: tool_name.to_string(),
+                step: index + 1,
+                command: command_line(step),
+                duration_ms: start.elapsed().as_millis() as u64,
+                exit_code,
+            })?;
+        }
+
+        match result {
+            Ok(step_status) => status = step_status,
+            Err(Error::CommandFailed { .. }) if step.continue_on_error => {
+                progress::warn(&format!(
+                    "step {} failed with exit code {}; continuing",
+                    label, exit_code
+                ))?;
+            }
+            Err(Error::CommandFailed { command, status }) => {
+                let skipped = commands.len() - index - 1;
+                if skipped > 0 {
+                    progress::note(&format!(
+                        "skipped {} remaining step{} of tool '{}'",
+                        skipped,
+                        if skipped == 1 { "" } else { "s" },
+                        tool_name
+                    ))?;
+                }
+                cache.store_entry(&CacheEntry {
+                    tool: tool_name.to_string(),
+                    input_hash: input_hash.clone(),
+                    exit_code,
+                    timestamp: utils::unix_timestamp(),
+                    outputs: Vec::new(),
+                    max_age_seconds: tool_config.max_age_seconds,
+                    key: Some(key.clone()),
+                })?;
+                record(exit_code, false, &steps)?;
+                return Err(Error::CommandFailed { command, status });
+            }
+            // Signals and timeouts are usually transient (e.g. the OOM killer
+            // or a slow machine), so they are journaled but never cached
+            Err(e @ (Error::Terminated { .. } | Error::Timeout { .. })) => {
+                record(exit_code, false, &steps)?;
+                return Err(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    let mut outputs = Vec::new();
 
     // Only verify outputs if the command succeeded and has output patterns defined
     if status.success() && !tool_config.outputs.is_empty() {
         if options.verbose {
-            progress::write_message(&progress::format_note(&format!(
-                "Verifying outputs for tool '{}'",
+            progress::note(&format!("Verifying outputs for tool '{}'", tool_name))?;
+        }
+
+        let files = if tool_config.outputs_optional {
+            verifier.find_outputs()?
+        } else {
+            verifier.verify_outputs(tool_name)?
+        };
+        if options.verbose {
+            progress::note(&format!(
+                "Found {} output files for tool '{}'",
+                files.len(),
                 tool_name
-            )))?;
+            ))?;
         }
 
-        let verifier = OutputVerifier::new(&options.project_dir, tool_config.outputs.clone());
+        // Record what was produced so deleting or editing it invalidates the entry
+        for path in files {
+            outputs.push(OutputRecord::capture(&options.project_dir, &path)?);
+        }
+    }
 
-        match verifier.verify_outputs() {
-            Ok(outputs) => {
-                if options.verbose {
-                    progress::write_message(&progress::format_note(&format!(
-                        "Found {} output files for tool '{}'",
-                        outputs.len(),
-                        tool_name
-                    )))?;
+    let output_paths = outputs.iter().map(|output| output.path.clone()).collect();
+    let exit_code = utils::exit_code(&status);
+    cache.store_entry(&CacheEntry {
+        tool: tool_name.to_string(),
+        input_hash: input_hash.clone(),
+        exit_code,
+        timestamp: utils::unix_timestamp(),
+        outputs,
+        max_age_seconds: tool_config.max_age_seconds,
+        key: Some(key),
+    })?;
+    record(exit_code, false, &steps)?;
+
+    Ok(RunResult {
+        status,
+        cached: false,
+        outputs: output_paths,
+        steps,
+    })
+}
+
+/// Number of trailing output lines kept for the failure summary
+const TAIL_LINES: usize = 5;
+
+/// Where a command's output goes besides the terminal
+#[derive(Default)]
+struct OutputCapture<'a> {
+    /// File the raw output is copied to
+    log: Option<&'a Mutex<File>>,
+    /// Reads the output of a test runner, to summarize failed tests
+    tests: Option<Mutex<TestOutputParser>>,
+}
+
+/// Copy a child's output stream to `writer` as it arrives
+///
+/// Output is forwarded in chunks rather than whole lines so prompts without a
+/// trailing newline are shown immediately. When `prefix` is set it is written
+/// at the start of every line. The output is also copied, unprefixed, to
+/// the capture's log and fed to its test output parser.
+///
+/// # Arguments
+///
+/// * `reader` - The child's stdout or stderr
+/// * `writer` - Where to forward the output
+/// * `prefix` - Text written before every line
+/// * `capture` - Where else the output goes
+///
+/// # Returns
+///
+/// * `io::Result<Vec<String>>` - The last [`TAIL_LINES`] lines of output
+fn stream_output(
+    mut reader: impl Read,
+    writer: &mut dyn Write,
+    prefix: Option<&str>,
+    capture: &OutputCapture,
+) -> io::Result<Vec<String>> {
+    let mut tail = VecDeque::with_capacity(TAIL_LINES + 1);
+    let mut push_line = |line: &[u8]| {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim_end_matches(['\n', '\r']);
+        if let Some(tests) = &capture.tests {
+            tests.lock().unwrap().feed(line);
+        }
+        tail.push_back(line.to_string());
+        if tail.len() > TAIL_LINES {
+            tail.pop_front();
+        }
+    };
+
+    let mut buf = [0; 8192];
+    let mut line = Vec::new();
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if let Some(log) = capture.log {
+            log.lock().unwrap().write_all(&buf[..n])?;
+        }
+
+        for chunk in buf[..n].split_inclusive(|&b| b == b'\n') {
+            if line.is_empty() {
+                if let Some(prefix) = prefix {
+                    writer.write_all(prefix.as_bytes())?;
                 }
             }
-            Err(e) => return Err(e),
+            writer.write_all(chunk)?;
+            line.extend_from_slice(chunk);
+            if chunk.ends_with(b"\n") {
+                push_line(&line);
+                line.clear();
+            }
         }
+        writer.flush()?;
     }
 
-    Ok(RunResult { status })
+    if !line.is_empty() {
+        push_line(&line);
+    }
+    Ok(tail.into())
+}
+
+/// Print the last lines of a failed command's output
+fn print_tail(stdout: &[String], stderr: &[String]) -> Result<()> {
+    if !stdout.is_empty() {
+        progress::note("Command output:")?;
+        progress::write_message(&stdout.join("\n"))?;
+    }
+    if !stderr.is_empty() {
+        progress::error("Command error output:")?;
+        progress::write_error(&stderr.join("\n"))?;
+    }
+    Ok(())
 }
 
 /// Execute a command
+///
+/// The command's output is streamed to the terminal while it runs, prefixed
+/// with the tool name when verbose. In quiet mode nothing is shown unless the
+/// command fails, in which case the last lines of its output are printed.
+/// The command and everything it spawns are stopped once `timeout` seconds
+/// have passed or Ctrl-C is pressed. When it fails and its output is a
+/// test runner's, the failed tests are summarized after it.
 fn execute_command(
     tool_name: &str,
     config: &config::ToolConfig,
     project_dir: &Path,
     verbose: bool,
+    quiet: bool,
+    timeout: Option<u64>,
+    capture: &OutputCapture,
 ) -> Result<ExitStatus> {
     let working_dir = if let Some(dir) = &config.working_dir {
         project_dir.join(dir)
@@ -92,6 +758,7 @@ fn execute_command(
                 working_dir.display(),
                 tool_name
             ),
+            hint: None,
         });
     }
     if !working_dir.is_dir() {
@@ -101,12 +768,18 @@ fn execute_command(
                 working_dir.display(),
                 tool_name
             ),
+            hint: None,
         });
     }
 
+    if node::needs_node(&config.command) {
+        node::ensure_node(project_dir)?;
+    }
+
     let mut command = Command::new(&config.command);
     command.current_dir(&working_dir);
     command.args(&config.args);
+    progress::apply_color_env(&mut command);
 
     for (key, value) in &config.env {
         command.env(key, value);
@@ -116,73 +789,136 @@ fn execute_command(
     let command_str = format!("{} {}", config.command, config.args.join(" "));
 
     if verbose {
-        progress::write_message(&progress::format_status("Running", &command_str))?;
+        progress::status("Running", &command_str)?;
     }
 
-    let output = command.output()?;
+    process::isolate(&mut command);
+    let mut child = command
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let prefix = verbose.then(|| format!("[{}] ", tool_name));
 
-    // Handle command output
-    if verbose || !output.status.success() {
-        if !output.stdout.is_empty() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let formatted = if verbose {
-                format!("\n{}", stdout)
+    let (status, stdout_tail, stderr_tail) = thread::scope(|scope| {
+        let prefix = prefix.as_deref();
+        let writer = |terminal: Box<dyn Write>| -> Box<dyn Write> {
+            if quiet {
+                Box::new(io::sink())
             } else {
-                // When not verbose, only show last few lines
-                stdout
-                    .lines()
-                    .rev()
-                    .take(5)
-                    .collect::<Vec<_>>()
-                    .into_iter()
-                    .rev()
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            };
-            progress::write_message(&progress::format_note("Command output:"))?;
-            println!("{}", formatted);
-        }
+                terminal
+            }
+        };
 
-        if !output.stderr.is_empty() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let formatted = if verbose {
-                format!("\n{}", stderr)
-            } else {
-                // When not verbose, only show last few lines
-                stderr
-                    .lines()
-                    .rev()
-                    .take(5)
-                    .collect::<Vec<_>>()
-                    .into_iter()
-                    .rev()
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            };
-            progress::write_message(&progress::format_error("Command error output:"))?;
-            eprintln!("{}", formatted);
+        // JSON events own stdout, so the tool's output moves to stderr
+        let json = progress::message_format() == progress::MessageFormat::Json;
+        let stdout_thread = scope.spawn(move || {
+            stdout.map_or(Ok(Vec::new()), |out| {
+                let terminal: Box<dyn Write> = if json {
+                    Box::new(io::stderr())
+                } else {
+                    Box::new(io::stdout())
+                };
+                stream_output(out, writer(terminal).as_mut(), prefix, capture)
+            })
+        });
+        let stderr_thread = scope.spawn(move || {
+            stderr.map_or(Ok(Vec::new()), |err| {
+                stream_output(
+                    err,
+                    writer(Box::new(io::stderr())).as_mut(),
+                    prefix,
+                    capture,
+                )
+            })
+        });
+
+        let status = process::wait_with_timeout(&mut child, timeout.map(Duration::from_secs));
+        let join = |handle: thread::ScopedJoinHandle<'_, io::Result<Vec<String>>>| {
+            handle.join().unwrap_or_else(|_| Ok(Vec::new()))
+        };
+        (status, join(stdout_thread), join(stderr_thread))
+    });
+    let status = match status? {
+        WaitOutcome::Exited(status) => status,
+        WaitOutcome::TimedOut => {
+            return Err(Error::Timeout {
+                command: command_str,
+                secs: timeout.unwrap_or_default(),
+            })
         }
+        WaitOutcome::Interrupted => {
+            return Err(Error::Terminated {
+                message: format!("tool '{}' was interrupted", tool_name),
+                signal: process::SIGINT,
+            })
+        }
+    };
+
+    // Streamed output has already been shown in full
+    if quiet && !status.success() {
+        print_tail(&stdout_tail?, &stderr_tail?)?;
+    }
+    if let Some(log) = capture.log {
+        log.lock().unwrap().flush()?;
     }
 
-    if !output.status.success() {
+    // The summary stays visible however long the output was
+    let summary = capture
+        .tests
+        .as_ref()
+        .and_then(|tests| std::mem::take(&mut *tests.lock().unwrap()).finish());
+    if let Some(summary) = summary.filter(|summary| !status.success() && summary.failed > 0) {
+        progress::error(&format!("test summary: {}", summary.render()))?;
+    }
+
+    if let Some(signal) = utils::termination_signal(&status) {
+        return Err(Error::Terminated {
+            message: format!(
+                "tool '{}' was terminated by {}",
+                tool_name,
+                utils::describe_signal(signal)
+            ),
+            signal,
+        });
+    }
+
+    if !status.success() {
         return Err(Error::CommandFailed {
             command: command_str,
-            status: output.status,
+            status,
         });
     }
 
-    Ok(output.status)
+    Ok(status)
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
-    use std::fs::File;
+    use std::fs::{self, File};
     use std::io::Write;
 
     use tempfile::tempdir;
 
     use super::*;
+    use crate::cache::CACHE_DIR;
+    use crate::journal::JOURNAL_DIR;
+
+    /// Number of cache entries stored for the project
+    fn cached_entries(dir_path: &Path) -> usize {
+        fs::read_dir(dir_path.join(CACHE_DIR)).map_or(0, |entries| {
+            entries
+                .filter(|entry| {
+                    entry
+                        .as_ref()
+                        .is_ok_and(|entry| entry.path().extension().is_some_and(|e| e == "json"))
+                })
+                .count()
+        })
+    }
 
     #[test]
     fn test_run_tool() -> Result<()> {
@@ -208,18 +944,31 @@ mod tests {
             working_dir: None,
             inputs: vec!["*.txt".to_string()],
             outputs: vec!["*.out".to_string()],
+            exclude: vec![],
+            max_age_seconds: None,
+            timeout_secs: None,
+            outputs_optional: false,
+            steps: None,
         };
 
         // Create a test configuration
         let mut tools = HashMap::new();
         tools.insert("test-tool".to_string(), tool_config);
-        let config = config::CargonodeConfig { tools };
+        let config = config::CargonodeConfig {
+            tools,
+            ..Default::default()
+        };
 
         // Create run options
         let options = RunOptions {
             project_dir: dir_path.to_path_buf(),
             force: false,
             verbose: false,
+            quiet: false,
+            timeout: None,
+            args: Vec::new(),
+            log_file: None,
+            summarize_tests: false,
         };
 
         // Run the tool
@@ -238,34 +987,846 @@ mod tests {
         let temp_dir = tempdir()?;
         let dir_path = temp_dir.path();
 
-        // Create a test tool configuration with output verification
+        // The output directory exists before the tool runs
         let tool_config = config::ToolConfig {
-            command: "echo".to_string(),
-            args: vec!["test".to_string()],
-            env: HashMap::new(),
-            working_dir: None,
+            command: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                "echo test > subdir/test-output.txt".to_string(),
+            ],
             inputs: vec!["*.txt".to_string()],
             outputs: vec!["subdir/test-output.txt".to_string()],
+            ..Default::default()
+        };
+        let missing = config::ToolConfig {
+            command: "echo".to_string(),
+            inputs: vec!["*.txt".to_string()],
+            outputs: vec![
+                "dist/*.js".to_string(),
+                "subdir/test-output.txt".to_string(),
+            ],
+            ..Default::default()
         };
 
         // Create a test configuration
         let mut tools = HashMap::new();
         tools.insert("test-tool".to_string(), tool_config);
-        let config = config::CargonodeConfig { tools };
+        tools.insert("missing".to_string(), missing.clone());
+        tools.insert(
+            "optional".to_string(),
+            config::ToolConfig {
+                outputs_optional: true,
+                ..missing
+            },
+        );
+        let config = config::CargonodeConfig {
+            tools,
+            ..Default::default()
+        };
 
         // Create run options
         let options = RunOptions {
             project_dir: dir_path.to_path_buf(),
             force: false,
             verbose: false,
+            quiet: false,
+            timeout: None,
+            args: Vec::new(),
+            log_file: None,
+            summarize_tests: false,
         };
 
-        // Run the tool (should succeed and create directory)
         let result = run_tool("test-tool", &config, &options)?;
         assert!(result.status.success());
+        assert_eq!(result.outputs, vec!["subdir/test-output.txt"]);
+
+        // Only the pattern nothing matched is reported
+        let Err(Error::MissingOutput { tool, patterns }) = run_tool("missing", &config, &options)
+        else {
+            panic!("expected the missing output to fail the run");
+        };
+        assert_eq!(tool, "missing");
+        assert_eq!(patterns, vec!["dist/*.js"]);
+
+        let result = run_tool("optional", &config, &options)?;
+        assert_eq!(result.outputs, vec!["subdir/test-output.txt"]);
+
+        Ok(())
+    }
+
+    /// Test that unchanged runs are served from the cache
+    #[test]
+    fn test_run_tool_caching() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let dir_path = temp_dir.path();
+        File::create(dir_path.join("input.txt"))?.write_all(b"v1")?;
+
+        let tool_config = config::ToolConfig {
+            command: "echo".to_string(),
+            args: vec!["test".to_string()],
+            env: HashMap::new(),
+            working_dir: None,
+            inputs: vec!["*.txt".to_string()],
+            outputs: vec![],
+            exclude: vec![],
+            max_age_seconds: None,
+            timeout_secs: None,
+            outputs_optional: false,
+            steps: None,
+        };
+        let mut tools = HashMap::new();
+        tools.insert("test-tool".to_string(), tool_config);
+        let mut config = config::CargonodeConfig {
+            tools,
+            ..Default::default()
+        };
+
+        let mut options = RunOptions {
+            project_dir: dir_path.to_path_buf(),
+            force: false,
+            verbose: false,
+            quiet: false,
+            timeout: None,
+            args: Vec::new(),
+            log_file: None,
+            summarize_tests: false,
+        };
+
+        // First run executes, second run is cached
+        assert!(!run_tool("test-tool", &config, &options)?.cached);
+        assert!(run_tool("test-tool", &config, &options)?.cached);
+
+        // --force always executes
+        options.force = true;
+        assert!(!run_tool("test-tool", &config, &options)?.cached);
+        options.force = false;
+
+        // Changed inputs rerun
+        File::create(dir_path.join("input.txt"))?.write_all(b"v2")?;
+        assert!(!run_tool("test-tool", &config, &options)?.cached);
+        assert!(run_tool("test-tool", &config, &options)?.cached);
+
+        // Changed arguments rerun even with identical inputs
+        config.tools.get_mut("test-tool").unwrap().args = vec!["other".to_string()];
+        assert!(!run_tool("test-tool", &config, &options)?.cached);
+
+        let entries = Journal::new(&dir_path.join(JOURNAL_DIR)).read_entries()?;
+        let from_cache: Vec<bool> = entries.iter().map(|e| e.from_cache).collect();
+        assert_eq!(from_cache, vec![false, true, false, false, true, false]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_tool_cache_key() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let dir_path = temp_dir.path();
+        File::create(dir_path.join("input.txt"))?.write_all(b"v1")?;
+
+        let tool_config = config::ToolConfig {
+            command: "echo".to_string(),
+            env: HashMap::from([
+                ("NODE_ENV".to_string(), "production".to_string()),
+                ("BUILD_NUMBER".to_string(), "1".to_string()),
+            ]),
+            inputs: vec!["*.txt".to_string()],
+            ..Default::default()
+        };
+        let mut config = config::CargonodeConfig {
+            tools: HashMap::from([("test-tool".to_string(), tool_config)]),
+            ..Default::default()
+        };
+        config.cache.exclude_env = vec!["BUILD_NUMBER".to_string()];
+        let options = RunOptions {
+            project_dir: dir_path.to_path_buf(),
+            force: false,
+            verbose: false,
+            quiet: false,
+            timeout: None,
+            args: Vec::new(),
+            log_file: None,
+            summarize_tests: false,
+        };
+        let set_env = |config: &mut config::CargonodeConfig, name: &str, value: &str| {
+            let tool = config.tools.get_mut("test-tool").unwrap();
+            tool.env.insert(name.to_string(), value.to_string());
+        };
+
+        assert!(!run_tool("test-tool", &config, &options)?.cached);
+
+        // Excluded variables can change without invalidating the run
+        set_env(&mut config, "BUILD_NUMBER", "2");
+        assert!(run_tool("test-tool", &config, &options)?.cached);
+
+        // Other variables are part of the key
+        set_env(&mut config, "NODE_ENV", "development");
+        assert!(!run_tool("test-tool", &config, &options)?.cached);
+
+        // The entry records what it was keyed on
+        let journal = Journal::new(&dir_path.join(JOURNAL_DIR)).read_entries()?;
+        let last = journal.last().unwrap();
+        let entry = Cache::new(&dir_path.join(CACHE_DIR))
+            .stored_entry("test-tool", &last.input_hash)
+            .unwrap();
+        let key = entry.key.unwrap();
+        assert_eq!(key.hash(), last.input_hash);
+        assert_eq!(key.commands, vec![vec!["echo".to_string()]]);
+        assert_eq!(key.env, vec!["NODE_ENV=development".to_string()]);
+        assert_eq!(key.node_version.as_deref(), node::installed_version());
+        assert_eq!(key.tool_version, tool_version("echo", dir_path));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_print_tail() -> Result<()> {
+        let sink = std::sync::Arc::new(progress::RecordingSink::default());
+        progress::with_sink(sink.clone(), || {
+            print_tail(&["built".to_string()], &["oops".to_string()])
+        })?;
+        let messages: Vec<_> = sink
+            .messages()
+            .into_iter()
+            .map(|message| (message.level, message.text))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (progress::Level::Note, "note: Command output:".to_string()),
+                (progress::Level::Output, "built".to_string()),
+                (
+                    progress::Level::Error,
+                    "error: Command error output:".to_string()
+                ),
+                (progress::Level::Error, "oops".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tool_version() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempdir()?;
+        let dir_path = temp_dir.path();
+        let bin = dir_path.join("node_modules/.bin");
+        fs::create_dir_all(&bin)?;
+        for name in ["tsc", "deploy"] {
+            let path = bin.join(name);
+            fs::write(&path, "#!/bin/sh\ntouch \"$0.ran\"\necho 'Version 5.4.5'\n")?;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        }
+
+        // Known tools in node_modules/.bin, by name or by path
+        assert_eq!(
+            tool_version("tsc", dir_path).as_deref(),
+            Some("Version 5.4.5")
+        );
+        assert_eq!(
+            tool_version("node_modules/.bin/tsc", dir_path).as_deref(),
+            Some("Version 5.4.5")
+        );
+        // Anything else is never run
+        assert_eq!(tool_version("deploy", dir_path), None);
+        assert_eq!(tool_version("node_modules/.bin/deploy", dir_path), None);
+        assert_eq!(tool_version("echo", dir_path), None);
+        assert!(!bin.join("deploy.ran").exists());
+        Ok(())
+    }
+
+    /// Test that removing a recorded output invalidates the cached run
+    #[cfg(unix)]
+    #[test]
+    fn test_run_tool_missing_output() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let dir_path = temp_dir.path();
+        File::create(dir_path.join("input.txt"))?.write_all(b"v1")?;
+
+        let tool_config = config::ToolConfig {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), "echo built > dist.out".to_string()],
+            env: HashMap::new(),
+            working_dir: None,
+            inputs: vec!["*.txt".to_string()],
+            outputs: vec!["*.out".to_string()],
+            exclude: vec![],
+            max_age_seconds: None,
+            timeout_secs: None,
+            outputs_optional: false,
+            steps: None,
+        };
+        let mut tools = HashMap::new();
+        tools.insert("build".to_string(), tool_config);
+        let config = config::CargonodeConfig {
+            tools,
+            ..Default::default()
+        };
+
+        let options = RunOptions {
+            project_dir: dir_path.to_path_buf(),
+            force: false,
+            verbose: true,
+            quiet: false,
+            timeout: None,
+            args: Vec::new(),
+            log_file: None,
+            summarize_tests: false,
+        };
+
+        assert!(!run_tool("build", &config, &options)?.cached);
+        assert!(run_tool("build", &config, &options)?.cached);
+
+        fs::remove_file(dir_path.join("dist.out"))?;
+        assert!(!run_tool("build", &config, &options)?.cached);
+        assert!(dir_path.join("dist.out").exists());
+
+        Ok(())
+    }
+
+    /// Test that a tool killed by a signal is reported and not cached
+    #[cfg(unix)]
+    #[test]
+    fn test_run_tool_terminated_by_signal() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let dir_path = temp_dir.path();
+        File::create(dir_path.join("input.txt"))?.write_all(b"v1")?;
+
+        let tool_config = config::ToolConfig {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), "kill -9 $$".to_string()],
+            env: HashMap::new(),
+            working_dir: None,
+            inputs: vec!["*.txt".to_string()],
+            outputs: vec![],
+            exclude: vec![],
+            max_age_seconds: None,
+            timeout_secs: None,
+            outputs_optional: false,
+            steps: None,
+        };
+        let mut tools = HashMap::new();
+        tools.insert("build".to_string(), tool_config);
+        let config = config::CargonodeConfig {
+            tools,
+            ..Default::default()
+        };
+
+        let options = RunOptions {
+            project_dir: dir_path.to_path_buf(),
+            force: false,
+            verbose: false,
+            quiet: false,
+            timeout: None,
+            args: Vec::new(),
+            log_file: None,
+            summarize_tests: false,
+        };
+
+        let err = run_tool("build", &config, &options).err().unwrap();
+        assert!(matches!(err, Error::Terminated { signal: 9, .. }));
+        assert_eq!(err.exit_code(), 137);
+        assert!(err
+            .to_string()
+            .contains("tool 'build' was terminated by signal 9 (SIGKILL)"));
+
+        let entries = Journal::new(&dir_path.join(JOURNAL_DIR)).read_entries()?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].exit_code, 137);
+        assert_eq!(cached_entries(dir_path), 0);
+
+        Ok(())
+    }
+
+    /// Test that extra arguments reach the tool verbatim and change the cache key
+    #[cfg(unix)]
+    #[test]
+    fn test_run_tool_forwards_args() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let dir_path = temp_dir.path();
+        File::create(dir_path.join("input.txt"))?.write_all(b"input")?;
+
+        let tool_config = config::ToolConfig {
+            command: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                r#"printf '%s|' "$@" > args.out"#.to_string(),
+                "sh".to_string(),
+                "--config".to_string(),
+            ],
+            env: HashMap::new(),
+            working_dir: None,
+            inputs: vec!["*.txt".to_string()],
+            outputs: vec![],
+            exclude: vec![],
+            max_age_seconds: None,
+            timeout_secs: None,
+            outputs_optional: false,
+            steps: None,
+        };
+        let mut tools = HashMap::new();
+        tools.insert("test".to_string(), tool_config);
+        let config = config::CargonodeConfig {
+            tools,
+            ..Default::default()
+        };
+
+        let mut options = RunOptions {
+            project_dir: dir_path.to_path_buf(),
+            force: false,
+            verbose: false,
+            quiet: false,
+            timeout: None,
+            args: Vec::new(),
+            log_file: None,
+            summarize_tests: false,
+        };
+
+        assert!(!run_tool("test", &config, &options)?.cached);
+        assert_eq!(fs::read_to_string(dir_path.join("args.out"))?, "--config|");
+
+        // A cached plain run must not mask a run with extra arguments
+        options.args = vec!["--coverage".to_string(), "a b; echo *".to_string()];
+        assert!(!run_tool("test", &config, &options)?.cached);
+        assert_eq!(
+            fs::read_to_string(dir_path.join("args.out"))?,
+            "--config|--coverage|a b; echo *|"
+        );
+        assert!(run_tool("test", &config, &options)?.cached);
+
+        options.args.clear();
+        assert!(run_tool("test", &config, &options)?.cached);
+
+        let entries = Journal::new(&dir_path.join(JOURNAL_DIR)).read_entries()?;
+        assert_ne!(entries[0].input_hash, entries[1].input_hash);
+        assert!(entries[1].command.ends_with("--coverage a b; echo *"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let lookup = |name: &str| match name {
+            "HOME" => Some("/home/jane".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+        let expand = |text: &str| interpolate(text, "build", &lookup);
+
+        assert_eq!(expand("${HOME}/.cache").unwrap(), "/home/jane/.cache");
+        assert_eq!(
+            expand("--max-old-space-size=${NODE_MEM:-4096}").unwrap(),
+            "--max-old-space-size=4096"
+        );
+        assert_eq!(expand("${EMPTY:-fallback}").unwrap(), "fallback");
+        assert_eq!(expand("${HOME:-}").unwrap(), "/home/jane");
+        assert_eq!(expand("$${literal} $HOME $").unwrap(), "${literal} $HOME $");
+
+        let Err(Error::Config { message, .. }) = expand("${NODE_MEM}") else {
+            panic!("expected an undefined variable to be an error");
+        };
+        assert_eq!(
+            message,
+            "Environment variable 'NODE_MEM' is not defined in the configuration of tool 'build'"
+        );
+        assert!(expand("${HOME").is_err());
+        assert!(expand("${1HOME}").is_err());
+    }
+
+    #[test]
+    fn test_run_tool_expands_variables() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let dir_path = temp_dir.path();
+        fs::create_dir(dir_path.join("sub"))?;
+        File::create(dir_path.join("input.txt"))?.write_all(b"input")?;
+
+        let tool_config = config::ToolConfig {
+            command: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                r#"printf '%s|%s|%s' "$1" "$OUT" "$PWD" > "$PROJECT/vars.out""#.to_string(),
+                "sh".to_string(),
+                "${TOOL_NAME}:${CARGONODE_TEST_UNSET:-none}".to_string(),
+            ],
+            env: HashMap::from([
+                ("OUT".to_string(), "${CACHE_DIR}".to_string()),
+                ("PROJECT".to_string(), "${PROJECT_DIR}".to_string()),
+            ]),
+            working_dir: Some("${PROJECT_DIR}/sub".to_string()),
+            inputs: vec!["*.txt".to_string()],
+            ..Default::default()
+        };
+        let mut tools = HashMap::new();
+        tools.insert("vars".to_string(), tool_config);
+        let config = config::CargonodeConfig {
+            tools,
+            ..Default::default()
+        };
+        let options = RunOptions {
+            project_dir: dir_path.to_path_buf(),
+            force: false,
+            verbose: false,
+            quiet: false,
+            timeout: None,
+            args: vec!["${TOOL_NAME}".to_string()],
+            log_file: None,
+            summarize_tests: false,
+        };
+
+        run_tool("vars", &config, &options)?;
+        let output = fs::read_to_string(dir_path.join("vars.out"))?;
+        let parts: Vec<&str> = output.split('|').collect();
+        assert_eq!(parts[0], "vars:none");
+        assert_eq!(Path::new(parts[1]), dir_path.join(CACHE_DIR));
+        assert!(parts[2].ends_with("sub"));
+
+        // Extra arguments are not expanded
+        let entries = Journal::new(&dir_path.join(JOURNAL_DIR)).read_entries()?;
+        assert!(entries[0].command.ends_with("vars:none ${TOOL_NAME}"));
+
+        Ok(())
+    }
+
+    /// Test that a tool running past its timeout is stopped with everything it spawned
+    #[cfg(unix)]
+    #[test]
+    fn test_run_tool_timeout() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let dir_path = temp_dir.path();
+        File::create(dir_path.join("input.txt"))?.write_all(b"input")?;
+
+        // The shell's `sleep` child holds the output pipe open until it is killed
+        let tool_config = config::ToolConfig {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), "sleep 30; echo done".to_string()],
+            env: HashMap::new(),
+            working_dir: None,
+            inputs: vec!["*.txt".to_string()],
+            outputs: vec![],
+            exclude: vec![],
+            max_age_seconds: None,
+            timeout_secs: Some(1),
+            outputs_optional: false,
+            steps: None,
+        };
+        let mut tools = HashMap::new();
+        tools.insert("slow".to_string(), tool_config);
+        let config = config::CargonodeConfig {
+            tools,
+            ..Default::default()
+        };
+
+        let mut options = RunOptions {
+            project_dir: dir_path.to_path_buf(),
+            force: false,
+            verbose: false,
+            quiet: false,
+            timeout: None,
+            args: Vec::new(),
+            log_file: None,
+            summarize_tests: false,
+        };
+
+        let start = std::time::Instant::now();
+        let err = run_tool("slow", &config, &options).err().unwrap();
+        assert!(matches!(err, Error::Timeout { secs: 1, .. }));
+        assert_eq!(err.exit_code(), 124);
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+
+        // The command line limit overrides the tool's
+        options.timeout = Some(2);
+        let err = run_tool("slow", &config, &options).err().unwrap();
+        assert!(matches!(err, Error::Timeout { secs: 2, .. }));
+
+        // Timed out runs are journaled but never cached
+        let entries = Journal::new(&dir_path.join(JOURNAL_DIR)).read_entries()?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(cached_entries(dir_path), 0);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_tool_test_summary() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let dir_path = temp_dir.path();
+        let tap = "TAP version 13\nok 1 - adds\nnot ok 2 - divides\n# pass 1\n# fail 1\n";
+        fs::write(dir_path.join("tap.txt"), tap)?;
+
+        let tool = |script: &str| config::ToolConfig {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            inputs: vec!["*.txt".to_string()],
+            ..Default::default()
+        };
+        let mut tools = HashMap::new();
+        tools.insert("test".to_string(), tool("cat tap.txt; exit 1"));
+        tools.insert("passing".to_string(), tool("cat tap.txt"));
+        let config = config::CargonodeConfig {
+            tools,
+            ..Default::default()
+        };
+        let log_file = dir_path.join("logs/test.log");
+        fs::create_dir(dir_path.join("logs"))?;
+        let options = RunOptions {
+            project_dir: dir_path.to_path_buf(),
+            force: true,
+            verbose: false,
+            quiet: true,
+            timeout: None,
+            args: Vec::new(),
+            log_file: Some(log_file.clone()),
+            summarize_tests: true,
+        };
+
+        let sink = std::sync::Arc::new(progress::RecordingSink::default());
+        let result = progress::with_sink(sink.clone(), || run_tool("test", &config, &options));
+        assert!(matches!(result, Err(Error::CommandFailed { .. })));
+        assert_eq!(fs::read_to_string(&log_file)?, tap);
+        let errors: Vec<String> = sink
+            .messages()
+            .into_iter()
+            .filter(|message| message.level == progress::Level::Error)
+            .map(|message| message.text)
+            .collect();
+        assert!(errors
+            .iter()
+            .any(|text| text.ends_with("test summary: 1 failed, 1 passed\n  ✖ divides")));
+
+        // Passing runs and runs without summarizing print no summary
+        let sink = std::sync::Arc::new(progress::RecordingSink::default());
+        progress::with_sink(sink.clone(), || run_tool("passing", &config, &options))?;
+        let options = RunOptions {
+            summarize_tests: false,
+            ..options
+        };
+        let result = progress::with_sink(sink.clone(), || run_tool("test", &config, &options));
+        assert!(result.is_err());
+        assert!(!sink
+            .messages()
+            .iter()
+            .any(|message| message.text.contains("test summary")));
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_tool_hooks() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let dir_path = temp_dir.path();
+        fs::write(dir_path.join("input.txt"), "input")?;
+
+        let script = |script: &str| config::HookCommand {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+        };
+        let tool_config = config::ToolConfig {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), "echo ran >> tool.log".to_string()],
+            inputs: vec!["*.txt".to_string()],
+            ..Default::default()
+        };
+        let mut config = config::CargonodeConfig::default();
+        config.tools.insert("build".to_string(), tool_config);
+        config.hooks.events.insert(
+            "pre-build".to_string(),
+            script("echo \"$CN_EVENT\" >> hooks.log"),
+        );
+        config.hooks.events.insert(
+            "post-build".to_string(),
+            script("echo \"$CN_EVENT $CN_EXIT_CODE\" >> hooks.log; exit 3"),
+        );
+        let options = RunOptions {
+            project_dir: dir_path.to_path_buf(),
+            force: true,
+            verbose: false,
+            quiet: true,
+            timeout: None,
+            args: Vec::new(),
+            log_file: None,
+            summarize_tests: false,
+        };
+
+        // A failed post hook only warns
+        assert!(run_tool("build", &config, &options)?.status.success());
+        assert_eq!(
+            fs::read_to_string(dir_path.join("hooks.log"))?,
+            "pre-build\npost-build 0\n"
+        );
+
+        // ...unless post hook failures are errors
+        config.hooks.fail_on_post_hook_error = true;
+        let err = run_tool("build", &config, &options).err().unwrap();
+        assert!(matches!(err, Error::Hook { exit_code: 3, .. }));
+        assert_eq!(fs::read_to_string(dir_path.join("tool.log"))?, "ran\nran\n");
+
+        // A failed pre hook keeps the tool from running
+        config
+            .hooks
+            .events
+            .insert("pre-build".to_string(), script("exit 9"));
+        let err = run_tool("build", &config, &options).err().unwrap();
+        assert_eq!(err.exit_code(), 9);
+        assert_eq!(fs::read_to_string(dir_path.join("tool.log"))?, "ran\nran\n");
+
+        // Every hook run is journaled
+        let entries = Journal::new(&dir_path.join(JOURNAL_DIR)).read_entries()?;
+        let events: Vec<(&str, i32)> = entries
+            .iter()
+            .map(|entry| (entry.tool.as_str(), entry.exit_code))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                ("pre-build", 0),
+                ("build", 0),
+                ("post-build", 3),
+                ("pre-build", 0),
+                ("build", 0),
+                ("post-build", 3),
+                ("pre-build", 9),
+            ]
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_tool_steps() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let dir_path = temp_dir.path();
+        fs::write(dir_path.join("input.txt"), "input")?;
+
+        let step = |script: &str, continue_on_error: bool| config::CommandStep {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            continue_on_error,
+            ..Default::default()
+        };
+        let tool_config = config::ToolConfig {
+            steps: Some(vec![
+                step("echo lint >> steps.log; exit 3", true),
+                step("echo test >> steps.log", false),
+            ]),
+            inputs: vec!["*.txt".to_string()],
+            ..Default::default()
+        };
+        let mut tools = HashMap::new();
+        tools.insert("ci".to_string(), tool_config.clone());
+        tools.insert(
+            "strict".to_string(),
+            config::ToolConfig {
+                steps: Some(vec![
+                    step("exit 2", false),
+                    step("echo never >> steps.log", false),
+                ]),
+                ..tool_config
+            },
+        );
+        let config = config::CargonodeConfig {
+            tools,
+            ..Default::default()
+        };
+        let options = RunOptions {
+            project_dir: dir_path.to_path_buf(),
+            force: false,
+            verbose: false,
+            quiet: true,
+            timeout: None,
+            args: Vec::new(),
+            log_file: None,
+            summarize_tests: false,
+        };
+
+        // A step allowed to fail does not stop the pipeline or fail the tool
+        let result = run_tool("ci", &config, &options)?;
+        assert!(result.status.success());
+        let exit_codes: Vec<_> = result.steps.iter().map(|step| step.exit_code).collect();
+        assert_eq!(exit_codes, vec![3, 0]);
+        assert_eq!(
+            fs::read_to_string(dir_path.join("steps.log"))?,
+            "lint\ntest\n"
+        );
+
+        // The pipeline shares one cache entry
+        let result = run_tool("ci", &config, &options)?;
+        assert!(result.cached);
+        assert!(result.steps.is_empty());
+
+        // A failed step skips the rest
+        assert!(matches!(
+            run_tool("strict", &config, &options),
+            Err(Error::CommandFailed { .. })
+        ));
+        assert!(!fs::read_to_string(dir_path.join("steps.log"))?.contains("never"));
+
+        // Each run is one journal entry carrying its steps
+        let entries = Journal::new(&dir_path.join(JOURNAL_DIR)).read_entries()?;
+        assert_eq!(entries.len(), 3);
+        let steps: Vec<_> = entries.iter().map(|entry| entry.steps.len()).collect();
+        assert_eq!(steps, vec![2, 0, 1]);
+        assert_eq!(entries[0].steps[0].exit_code, 3);
+        assert_eq!(entries[2].exit_code, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_output_prefix_and_tail() -> Result<()> {
+        let mut written = Vec::new();
+        let tail = stream_output(
+            &b"one\ntwo\r\nthree\nfour\nfive\nsix\nprompt> "[..],
+            &mut written,
+            Some("[lint] "),
+            &OutputCapture::default(),
+        )?;
+
+        assert!(String::from_utf8_lossy(&written).starts_with("[lint] one\n[lint] two\r\n"));
+        assert!(String::from_utf8_lossy(&written).ends_with("[lint] six\n[lint] prompt> "));
+        assert_eq!(tail, vec!["three", "four", "five", "six", "prompt> "]);
+
+        Ok(())
+    }
+
+    /// Test that output is forwarded before the command exits
+    #[cfg(unix)]
+    #[test]
+    fn test_stream_output_is_incremental() -> Result<()> {
+        /// Records when each write happens
+        struct Recorder(Vec<(std::time::Instant, Vec<u8>)>);
+
+        impl Write for Recorder {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.push((std::time::Instant::now(), buf.to_vec()));
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let start = std::time::Instant::now();
+        let mut child = Command::new("sh")
+            .args(["-c", "echo first; sleep 1; echo second"])
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let mut recorder = Recorder(Vec::new());
+        let tail = stream_output(
+            child.stdout.take().unwrap(),
+            &mut recorder,
+            None,
+            &OutputCapture::default(),
+        )?;
+        child.wait()?;
 
-        // Verify directory was created
-        assert!(dir_path.join("subdir").exists());
+        assert_eq!(tail, vec!["first", "second"]);
+        let (first_at, first) = &recorder.0[0];
+        assert_eq!(first, b"first\n");
+        assert!(first_at.duration_since(start) < std::time::Duration::from_millis(900));
 
         Ok(())
     }
@@ -0,0 +1,488 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::config::UserConfig;
+use crate::error::Error;
+use crate::template::{self, TemplateConfig, TemplateProblem};
+use crate::{progress, template_cache, Result};
+
+/// A template known to `cargonode template`
+#[derive(Debug, Clone)]
+pub struct TemplateEntry {
+    /// Name the template is registered under
+    pub name: String,
+    /// Path or URL the template is registered with; `None` for the built-in
+    /// template
+    pub source: Option<String>,
+    /// The loaded template, or why it could not be loaded
+    pub template: std::result::Result<TemplateConfig, String>,
+}
+
+/// Load a template from a path or URL, fetching remote templates unless
+/// `offline`
+fn load_template(source: &Path, offline: bool) -> Result<TemplateConfig> {
+    let path = template_cache::resolve_template(source, offline, None)?;
+    TemplateConfig::load(&path)
+}
+
+/// The built-in template and every template registered in the user config
+///
+/// Remote templates are only read from the template cache, so listing never
+/// downloads anything; one that was never fetched is listed with the error.
+///
+/// # Arguments
+///
+/// * `user` - The user configuration
+///
+/// # Returns
+///
+/// * `Vec<TemplateEntry>` - The built-in template first, unless a registered
+///   template replaces it, then the registered ones by name
+pub fn list_templates(user: &UserConfig) -> Vec<TemplateEntry> {
+    let builtin = user
+        .template_source(template::BUILTIN_TEMPLATE)
+        .is_none()
+        .then(|| TemplateEntry {
+            name: template::BUILTIN_TEMPLATE.to_string(),
+            source: None,
+            template: Ok(TemplateConfig::builtin()),
+        });
+    let registered = user.templates().map(|(name, source)| TemplateEntry {
+        name: name.to_string(),
+        source: Some(source.to_string()),
+        template: load_template(Path::new(source), true).map_err(|e| {
+            // The last line of the message says what went wrong
+            let message = e.message();
+            let last = message.lines().last().unwrap_or_default();
+            last.trim_start_matches("Error: ").to_string()
+        }),
+    });
+    builtin.into_iter().chain(registered).collect()
+}
+
+/// Find a template by its registered name, or by path or URL
+///
+/// # Arguments
+///
+/// * `user` - The user configuration
+/// * `name` - `default`, a built-in variant, a name from `[templates]`, or a
+///   template path or URL
+/// * `offline` - Use only a cached copy of remote templates
+///
+/// # Returns
+///
+/// * `Result<TemplateConfig>` - The loaded template, or `Error::Template`
+pub fn find_template(user: &UserConfig, name: &str, offline: bool) -> Result<TemplateConfig> {
+    // A template registered as `default` replaces the built-in one
+    if name == template::BUILTIN_TEMPLATE && user.template_source(name).is_none() {
+        return Ok(TemplateConfig::builtin());
+    }
+    if template::template_variants().contains(&name) {
+        return TemplateConfig::load_variant(name);
+    }
+    let source = user.resolve_template_name(Path::new(name));
+    load_template(&source, offline)
+}
+
+/// Print one line per template: name, version, project types and description
+///
+/// # Arguments
+///
+/// * `entries` - Result of `list_templates`
+///
+/// # Returns
+///
+/// * `Result<()>` - Whether the output could be written
+pub fn report_templates(entries: &[TemplateEntry]) -> Result<()> {
+    let rows: Vec<[String; 4]> = entries
+        .iter()
+        .map(|entry| match &entry.template {
+            Ok(template) => {
+                let mut description = template.description.clone().unwrap_or_default();
+                if let Some(source) = &entry.source {
+                    description = format!("{} ({})", description, source)
+                        .trim_start()
+                        .to_string();
+                }
+                [
+                    entry.name.clone(),
+                    template.version.clone().unwrap_or_else(|| "-".to_string()),
+                    template
+                        .project_types
+                        .iter()
+                        .map(|project_type| project_type.name())
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    description,
+                ]
+            }
+            Err(e) => [
+                entry.name.clone(),
+                "-".to_string(),
+                "-".to_string(),
+                format!("unavailable: {}", e),
+            ],
+        })
+        .collect();
+
+    let width = |column: usize| rows.iter().map(|row| row[column].len()).max().unwrap_or(0);
+    let (name, version, types) = (width(0), width(1), width(2));
+    let text = rows
+        .iter()
+        .map(|[n, v, t, d]| {
+            format!("{:name$}  {:version$}  {:types$}  {}", n, v, t, d)
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    progress::write_message(&text)?;
+    Ok(())
+}
+
+/// Labeled lines describing the whole manifest of a template
+fn manifest_lines(template: &TemplateConfig) -> Vec<String> {
+    let mut lines = vec![format!("name: {}", template.name)];
+    if let Some(description) = &template.description {
+        lines.push(format!("description: {}", description));
+    }
+    if let Some(version) = &template.version {
+        lines.push(format!("version: {}", version));
+    }
+    lines.push(format!(
+        "project types: {}",
+        template
+            .project_types
+            .iter()
+            .map(|project_type| project_type.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+    if let Some(main) = &template.main {
+        lines.push(format!("main: {}", main));
+    }
+    if let Some(required) = &template.min_cargonode_version {
+        lines.push(format!("min cargonode version: {}", required));
+    }
+
+    let mut section = |title: &str, items: Vec<String>| {
+        if !items.is_empty() {
+            lines.push(format!("{}:", title));
+            lines.extend(items.into_iter().map(|item| format!("  {}", item)));
+        }
+    };
+    section(
+        "files",
+        template
+            .files
+            .iter()
+            .map(|file| {
+                let mut notes: Vec<String> = template
+                    .features
+                    .iter()
+                    .filter(|(_, feature)| feature.files.contains(&file.path))
+                    .map(|(name, _)| format!("feature {}", name))
+                    .collect();
+                if file.executable {
+                    notes.insert(0, "executable".to_string());
+                }
+                if notes.is_empty() {
+                    file.path.clone()
+                } else {
+                    format!("{} ({})", file.path, notes.join(", "))
+                }
+            })
+            .collect(),
+    );
+    let versions = |dependencies: &BTreeMap<String, String>| {
+        dependencies
+            .iter()
+            .map(|(name, range)| format!("{}@{}", name, range))
+            .collect()
+    };
+    section("dependencies", versions(&template.dependencies));
+    section("dev dependencies", versions(&template.dev_dependencies));
+    section(
+        "scripts",
+        template
+            .scripts
+            .iter()
+            .map(|(name, command)| format!("{}: {}", name, command))
+            .collect(),
+    );
+    section(
+        "features",
+        template
+            .features
+            .iter()
+            .map(|(name, feature)| {
+                let mut parts: Vec<String> = feature.description.iter().cloned().collect();
+                parts.extend(
+                    feature
+                        .dependencies
+                        .iter()
+                        .chain(&feature.dev_dependencies)
+                        .map(|(name, range)| format!("{}@{}", name, range)),
+                );
+                if parts.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}: {}", name, parts.join(", "))
+                }
+            })
+            .collect(),
+    );
+    lines
+}
+
+/// Print the manifest of a template: its metadata, files, dependencies,
+/// scripts and features
+///
+/// # Arguments
+///
+/// * `template` - Result of `find_template`
+///
+/// # Returns
+///
+/// * `Result<()>` - Whether the output could be written
+pub fn report_template(template: &TemplateConfig) -> Result<()> {
+    progress::write_message(&manifest_lines(template).join("\n"))?;
+    Ok(())
+}
+
+/// Result of `cargonode template validate`, as printed with `--json`
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateValidation {
+    /// Path of the checked template
+    pub path: String,
+    /// Whether the template has no problems
+    pub valid: bool,
+    /// Every problem found, with the template file it is in
+    pub errors: Vec<TemplateProblem>,
+}
+
+/// Check a template, collecting every problem instead of stopping at the
+/// first
+///
+/// # Arguments
+///
+/// * `path` - Template directory or `.tar.gz` archive
+///
+/// # Returns
+///
+/// * `TemplateValidation` - The problems found
+pub fn validate_template(path: &Path) -> TemplateValidation {
+    let errors = TemplateConfig::check(path);
+    TemplateValidation {
+        path: path.display().to_string(),
+        valid: errors.is_empty(),
+        errors,
+    }
+}
+
+/// Print the problems of a template
+///
+/// # Arguments
+///
+/// * `validation` - Result of `validate_template`
+/// * `json` - Print the result as one line of JSON instead of messages
+///
+/// # Returns
+///
+/// * `Result<()>` - `Error::Template` counting the problems, if there are any
+pub fn report_validation(validation: &TemplateValidation, json: bool) -> Result<()> {
+    if json {
+        progress::write_json("template", validation)?;
+    } else {
+        for problem in &validation.errors {
+            match &problem.file {
+                Some(file) => progress::error(&format!("{}: {}", file, problem.message))?,
+                None => progress::error(&problem.message)?,
+            }
+        }
+    }
+
+    match validation.errors.len() {
+        0 => {
+            if !json {
+                progress::status("Valid", &validation.path)?;
+            }
+            Ok(())
+        }
+        count => Err(Error::Template {
+            message: format!(
+                "Template '{}' has {} problem{}",
+                validation.path,
+                count,
+                if count == 1 { "" } else { "s" }
+            ),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::Arc;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::progress::{with_sink, Level, RecordingSink};
+    use crate::template::TEMPLATE_MANIFEST;
+
+    fn write_template(dir: &Path, manifest: &str) {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join(TEMPLATE_MANIFEST), manifest).unwrap();
+        fs::write(dir.join("src/main.js"), "main();\n").unwrap();
+    }
+
+    #[test]
+    fn test_list_and_find_templates() {
+        let temp_dir = TempDir::new().unwrap();
+        let web = temp_dir.path().join("web");
+        write_template(
+            &web,
+            r#"{
+                "name": "web",
+                "description": "Express server",
+                "version": "1.2.0",
+                "project_types": ["binary"],
+                "files": [{"path": "src/main.js"}],
+                "dependencies": {"express": "^4.18.0"},
+                "features": {"eslint": {"description": "Linting", "dev_dependencies": {"eslint": "^9.0.0"}}}
+            }"#,
+        );
+        let content = format!(
+            "[templates]\nweb = \"{}\"\ngone = \"{}\"\n",
+            web.display(),
+            temp_dir.path().join("gone").display()
+        );
+        let user = UserConfig::parse(&content, Path::new("config.toml")).unwrap();
+
+        let entries = list_templates(&user);
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, vec!["default", "gone", "web"]);
+        let builtin = entries[0].template.as_ref().unwrap();
+        assert_eq!(builtin.version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+        let cli = builtin
+            .files
+            .iter()
+            .find(|file| file.path == "src/main.js__if_cli")
+            .unwrap();
+        assert!(cli
+            .content
+            .as_deref()
+            .unwrap()
+            .starts_with("#!/usr/bin/env node"));
+        assert!(entries[1]
+            .template
+            .as_ref()
+            .unwrap_err()
+            .contains("does not exist"));
+        assert_eq!(
+            entries[2].template.as_ref().unwrap().version.as_deref(),
+            Some("1.2.0")
+        );
+
+        let sink = Arc::new(RecordingSink::default());
+        with_sink(sink.clone(), || report_templates(&entries)).unwrap();
+        let text = &sink.messages()[0].text;
+        assert!(text.contains("web      1.2.0  binary"), "{text}");
+        assert!(text.contains(&format!("Express server ({})", web.display())));
+
+        let template = find_template(&user, "web", true).unwrap();
+        assert_eq!(
+            manifest_lines(&template),
+            vec![
+                "name: web",
+                "description: Express server",
+                "version: 1.2.0",
+                "project types: binary",
+                "files:",
+                "  src/main.js",
+                "dependencies:",
+                "  express@^4.18.0",
+                "features:",
+                "  eslint: Linting, eslint@^9.0.0",
+            ]
+        );
+        assert_eq!(
+            find_template(&user, "default", true).unwrap().name,
+            "default"
+        );
+        assert_eq!(
+            find_template(&user, "typescript-lib", true)
+                .unwrap()
+                .main
+                .as_deref(),
+            Some("dist/lib.js")
+        );
+        assert!(find_template(&user, "missing", true).is_err());
+
+        // A registered `default` replaces the built-in template
+        let content = format!("[templates]\ndefault = \"{}\"\n", web.display());
+        let user = UserConfig::parse(&content, Path::new("config.toml")).unwrap();
+        let entries = list_templates(&user);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].source.as_deref(), Some(&*web.to_string_lossy()));
+        assert_eq!(find_template(&user, "default", true).unwrap().name, "web");
+    }
+
+    #[test]
+    fn test_validate_template() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        write_template(
+            dir,
+            r#"{
+                "name": "broken",
+                "version": "one",
+                "files": [
+                    {"path": "src/main.js"},
+                    {"path": "../escape.js", "content": ""},
+                    {"path": "src/missing.js"}
+                ],
+                "features": {"lint": {"files": [".eslintrc"]}}
+            }"#,
+        );
+
+        let validation = validate_template(dir);
+        assert!(!validation.valid);
+        let problems: Vec<(Option<&str>, &str)> = validation
+            .errors
+            .iter()
+            .map(|problem| (problem.file.as_deref(), problem.message.as_str()))
+            .collect();
+        assert_eq!(problems.len(), 4, "{problems:?}");
+        assert_eq!(problems[0].0, Some(TEMPLATE_MANIFEST));
+        assert!(problems[0].1.contains("invalid version 'one'"));
+        assert_eq!(problems[1].0, Some("../escape.js"));
+        assert!(problems[2].1.contains("lists '.eslintrc'"));
+        assert_eq!(problems[3].0, Some("src/missing.js"));
+
+        let json = serde_json::to_value(&validation).unwrap();
+        assert_eq!(json["valid"], false);
+        assert_eq!(json["errors"][3]["file"], "src/missing.js");
+
+        let sink = Arc::new(RecordingSink::default());
+        let err = with_sink(sink.clone(), || report_validation(&validation, false)).unwrap_err();
+        assert!(err.to_string().contains("has 4 problems"));
+        let messages = sink.messages();
+        assert_eq!(messages.len(), 4);
+        assert!(messages.iter().all(|message| message.level == Level::Error));
+        assert!(messages[3]
+            .text
+            .starts_with("error: src/missing.js: Cannot read"));
+
+        // A directory without a manifest is a single problem
+        let validation = validate_template(&dir.join("src"));
+        assert_eq!(validation.errors.len(), 1);
+        assert_eq!(
+            validation.errors[0].file.as_deref(),
+            Some(TEMPLATE_MANIFEST)
+        );
+    }
+}
@@ -0,0 +1,556 @@
+use std::fs;
+use std::path::Path;
+
+use semver::{Op, Version};
+
+use crate::dependency::{self, DEFAULT_CONCURRENCY};
+use crate::error::Error;
+use crate::registry::RegistryClient;
+use crate::{config, package_manager, progress, suggest, Result};
+
+/// Sections of package.json whose dependencies are upgraded
+const UPGRADE_SECTIONS: [&str; 2] = ["dependencies", "devDependencies"];
+
+/// Options for upgrading dependencies
+#[derive(Debug, Clone, Default)]
+pub struct UpgradeOptions {
+    /// Only upgrade these packages (every dependency when empty)
+    pub packages: Vec<String>,
+
+    /// Move to the latest stable version even if it is outside the range
+    pub latest: bool,
+
+    /// Rewrite package.json with the new ranges
+    pub write: bool,
+
+    /// Fail when upgrades are available, without writing anything
+    pub check: bool,
+}
+
+/// Upgrade status of one dependency
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpgradeRow {
+    /// Package name
+    pub name: String,
+    /// Section of package.json the dependency is recorded in
+    pub section: &'static str,
+    /// Range currently recorded
+    pub current: String,
+    /// Newest version the upgrade moves to
+    pub wanted: Option<Version>,
+    /// Version the `latest` dist-tag points to
+    pub latest: Option<Version>,
+    /// Range recorded after the upgrade
+    pub range: String,
+    /// Why the package could not be looked up
+    pub error: Option<String>,
+}
+
+impl UpgradeRow {
+    /// Whether the upgrade changes the recorded range
+    pub fn is_upgrade(&self) -> bool {
+        self.error.is_none() && self.range != self.current
+    }
+}
+
+/// Upgrade status of the dependencies of a project
+#[derive(Debug, Clone, Default)]
+pub struct UpgradeReport {
+    /// One row per looked up dependency, in package.json order
+    pub rows: Vec<UpgradeRow>,
+}
+
+impl UpgradeReport {
+    /// Rows whose range changes
+    pub fn upgrades(&self) -> Vec<&UpgradeRow> {
+        self.rows.iter().filter(|row| row.is_upgrade()).collect()
+    }
+
+    /// Format the upgrades and failed lookups as a table with package,
+    /// current range, new version and latest version columns
+    pub fn table(&self) -> String {
+        let show = |version: &Option<Version>| {
+            version
+                .as_ref()
+                .map_or_else(|| "-".to_string(), Version::to_string)
+        };
+        let rows: Vec<[String; 4]> = self
+            .rows
+            .iter()
+            .filter(|row| row.is_upgrade() || row.error.is_some())
+            .map(|row| {
+                let wanted = match &row.error {
+                    Some(_) => "error".to_string(),
+                    None => show(&row.wanted),
+                };
+                [
+                    row.name.clone(),
+                    row.current.clone(),
+                    wanted,
+                    show(&row.latest),
+                ]
+            })
+            .collect();
+
+        let header = ["Package", "Current", "New", "Latest"].map(str::to_string);
+        let mut widths = header.clone().map(|column| column.len());
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        std::iter::once(&header)
+            .chain(&rows)
+            .map(|row| {
+                format!(
+                    "{:<w0$}  {:<w1$}  {:<w2$}  {}",
+                    row[0],
+                    row[1],
+                    row[2],
+                    row[3],
+                    w0 = widths[0],
+                    w1 = widths[1],
+                    w2 = widths[2]
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Range recording `version` in the style of `current`
+///
+/// Caret and tilde ranges keep their operator and exact versions stay exact;
+/// any other range becomes a caret range.
+fn restyle_range(current: &str, version: &Version) -> String {
+    let current = current.trim();
+    if current.starts_with('~') {
+        format!("~{}", version)
+    } else if Version::parse(current.trim_start_matches('=')).is_ok() {
+        version.to_string()
+    } else {
+        format!("^{}", version)
+    }
+}
+
+/// Lowest version `range` allows, or `None` when it has no lower bound
+fn range_minimum(range: &str) -> Option<Version> {
+    let alternatives = dependency::parse_range(range).ok()?;
+    alternatives
+        .iter()
+        .map(|requirement| {
+            requirement
+                .comparators
+                .iter()
+                .filter(|comparator| !matches!(comparator.op, Op::Less | Op::LessEq))
+                .map(|comparator| Version {
+                    major: comparator.major,
+                    minor: comparator.minor.unwrap_or(0),
+                    patch: comparator.patch.unwrap_or(0),
+                    pre: comparator.pre.clone(),
+                    build: Default::default(),
+                })
+                .max()
+        })
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .min()
+}
+
+/// Work out the new range of one dependency
+///
+/// Without `latest`, caret and tilde ranges move to the newest version they
+/// already allow; other ranges are left as they are. With `latest`, a
+/// dependency whose `latest` dist-tag is older than its range allows, e.g. a
+/// prerelease line, keeps its range with a warning rather than being
+/// downgraded.
+fn plan_upgrade(
+    info: &crate::registry::RegistryResponse,
+    current: &str,
+    latest_only: bool,
+) -> Result<(Option<Version>, Option<Version>, String)> {
+    let latest = info
+        .dist_tags
+        .get("latest")
+        .and_then(|version| Version::parse(version).ok());
+    let bumpable = current.starts_with(['^', '~']);
+
+    let wanted = if latest_only {
+        match (&latest, range_minimum(current)) {
+            (Some(version), Some(minimum)) if *version < minimum => {
+                progress::warn(&format!(
+                    "keeping `{}` at {}: its latest version {} is older than the range allows",
+                    info.name, current, version
+                ))?;
+                return Ok((None, latest, current.to_string()));
+            }
+            _ => latest.clone(),
+        }
+    } else {
+        dependency::resolve_version(info, &[current])?
+    };
+    let range = match &wanted {
+        Some(version) if latest_only || bumpable => restyle_range(current, version),
+        _ => current.to_string(),
+    };
+    Ok((wanted, latest, range))
+}
+
+/// Look up newer versions of the dependencies of the project containing
+/// `current_dir`, print them as a table and optionally record them
+///
+/// Registry lookups run concurrently; a package that cannot be looked up is
+/// reported in the table without stopping the others. Dependencies that are
+/// not registry ranges (e.g. `workspace:*` or git URLs) and packages listed
+/// in `upgrade.ignore` are skipped.
+///
+/// # Arguments
+///
+/// * `options` - Which packages to upgrade and what to do with the result
+/// * `current_dir` - Directory to start looking for the project from
+/// * `client` - Registry client used to look up versions
+///
+/// # Returns
+///
+/// * `Result<UpgradeReport>` - The status of every dependency looked up, or
+///   `Error::Outdated` under `check` when upgrades are available
+pub fn upgrade_dependencies(
+    options: &UpgradeOptions,
+    current_dir: &Path,
+    client: &(impl RegistryClient + Sync),
+) -> Result<UpgradeReport> {
+    let project_root =
+        package_manager::find_project_root(current_dir).ok_or_else(|| Error::Config {
+            message: format!(
+                "package.json not found in {} or any parent directory",
+                current_dir.display()
+            ),
+            hint: Some(suggest::missing_manifest_hint()),
+        })?;
+    let ignored = config::load_config(&project_root)?.upgrade.ignore;
+
+    let package_json_path = project_root.join("package.json");
+    let original = fs::read_to_string(&package_json_path)?;
+    let mut manifest: serde_json::Value = serde_json::from_str(&original)?;
+
+    let mut dependencies = Vec::new();
+    for section in UPGRADE_SECTIONS {
+        let Some(entries) = manifest[section].as_object() else {
+            continue;
+        };
+        for (name, range) in entries {
+            let Some(range) = range.as_str() else {
+                continue;
+            };
+            if dependency::parse_range(range).is_ok() {
+                dependencies.push((name.clone(), section, range.to_string()));
+            }
+        }
+    }
+    if let Some(unknown) = options.packages.iter().find(|name| {
+        !dependencies
+            .iter()
+            .any(|(dependency, ..)| dependency == *name)
+    }) {
+        return Err(Error::Config {
+            message: format!(
+                "'{}' is not a registry dependency in {}",
+                unknown,
+                package_json_path.display()
+            ),
+            hint: None,
+        });
+    }
+    if !options.packages.is_empty() {
+        dependencies.retain(|(name, ..)| options.packages.contains(name));
+    }
+    for name in ignored.iter().filter(|name| {
+        dependencies
+            .iter()
+            .any(|(dependency, ..)| dependency == *name)
+    }) {
+        progress::note(&format!(
+            "Keeping `{}` at its current range (upgrade.ignore)",
+            name
+        ))?;
+    }
+    dependencies.retain(|(name, ..)| !ignored.contains(name));
+
+    progress::status(
+        "Checking",
+        &format!(
+            "{} dependenc{} for newer versions",
+            dependencies.len(),
+            if dependencies.len() == 1 { "y" } else { "ies" }
+        ),
+    )?;
+    // A package can be listed in both sections but is looked up once
+    let mut unique: Vec<String> = dependencies.iter().map(|(name, ..)| name.clone()).collect();
+    unique.sort();
+    unique.dedup();
+    let names: Vec<&str> = unique.iter().map(String::as_str).collect();
+    let infos = dependency::fetch_package_infos(client, &names, DEFAULT_CONCURRENCY);
+
+    let mut report = UpgradeReport::default();
+    for (name, section, current) in dependencies {
+        let planned = match &infos[name.as_str()] {
+            Ok(info) => plan_upgrade(info, &current, options.latest),
+            Err(e) => Err(Error::Registry {
+                message: e.message(),
+            }),
+        };
+        let row = match planned {
+            Ok((wanted, latest, range)) => UpgradeRow {
+                name,
+                section,
+                current,
+                wanted,
+                latest,
+                range,
+                error: None,
+            },
+            Err(e) => UpgradeRow {
+                name,
+                section,
+                range: current.clone(),
+                current,
+                wanted: None,
+                latest: None,
+                error: Some(
+                    e.message()
+                        .lines()
+                        .find_map(|line| line.strip_prefix("Error: "))
+                        .unwrap_or_default()
+                        .to_string(),
+                ),
+            },
+        };
+        report.rows.push(row);
+    }
+
+    for row in &report.rows {
+        if let Some(error) = &row.error {
+            progress::warn(&format!("cannot look up `{}`: {}", row.name, error))?;
+        }
+    }
+    let upgrades = report.upgrades();
+    if upgrades.is_empty() {
+        progress::status("Finished", "all dependencies are up to date")?;
+        return Ok(report);
+    }
+    progress::write_message(&report.table())?;
+
+    if options.check {
+        return Err(Error::Outdated {
+            message: format!(
+                "{} dependenc{} can be upgraded: {}",
+                upgrades.len(),
+                if upgrades.len() == 1 { "y" } else { "ies" },
+                upgrades
+                    .iter()
+                    .map(|row| row.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        });
+    }
+    if !options.write {
+        progress::note("Run with `--write` to record the new ranges in package.json")?;
+        return Ok(report);
+    }
+
+    for row in &upgrades {
+        manifest[row.section][&row.name] = serde_json::Value::String(row.range.clone());
+        progress::status(
+            "Upgrading",
+            &format!(
+                "{} {} -> {} in {}",
+                row.name, row.current, row.range, row.section
+            ),
+        )?;
+    }
+    dependency::write_manifest(&package_json_path, &manifest, &original)?;
+    let package_manager = package_manager::detect_package_manager(&project_root)?;
+    progress::note(&format!(
+        "Run `{}` to install the upgraded dependencies",
+        package_manager.install_command_line()
+    ))?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::dependency::tests::MockClient;
+
+    fn client() -> MockClient {
+        MockClient::new()
+            .with_package("express", &["4.17.0", "4.21.2", "5.0.1"], "5.0.1")
+            .with_package("lodash", &["4.17.20", "4.17.21"], "4.17.21")
+            .with_package("typescript", &["5.4.5", "5.8.2", "6.0.0-beta"], "5.8.2")
+            .with_package("left-pad", &["1.0.0", "1.3.0"], "1.3.0")
+    }
+
+    const MANIFEST: &str = r#"{
+  "name": "app",
+  "dependencies": {
+    "express": "^4.17.0",
+    "lodash": "~4.17.20",
+    "ui": "workspace:*",
+    "missing": "^1.0.0"
+  },
+  "devDependencies": {
+    "typescript": "5.4.5",
+    "left-pad": ">=1.0.0 <2"
+  },
+  "cargonode": { "upgrade": { "ignore": ["left-pad"] } }
+}
+"#;
+
+    fn create_project() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("package.json"), MANIFEST).unwrap();
+        temp_dir
+    }
+
+    fn row<'a>(report: &'a UpgradeReport, name: &str) -> &'a UpgradeRow {
+        report.rows.iter().find(|row| row.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_upgrade_within_ranges() -> Result<()> {
+        let temp_dir = create_project();
+        let dir = temp_dir.path();
+
+        let report = upgrade_dependencies(&UpgradeOptions::default(), dir, &client())?;
+        // Non-registry ranges and ignored packages are not looked up
+        let names: Vec<&str> = report.rows.iter().map(|row| row.name.as_str()).collect();
+        assert_eq!(names, vec!["express", "lodash", "missing", "typescript"]);
+
+        assert_eq!(row(&report, "express").range, "^4.21.2");
+        assert_eq!(row(&report, "express").latest, Version::parse("5.0.1").ok());
+        assert_eq!(row(&report, "lodash").range, "~4.17.21");
+        // Exact versions only move with `--latest`
+        assert!(!row(&report, "typescript").is_upgrade());
+        // A failed lookup is reported without stopping the others
+        assert!(row(&report, "missing").error.is_some());
+        assert_eq!(report.upgrades().len(), 2);
+
+        let table = report.table();
+        assert!(table.starts_with("Package  Current   New      Latest\n"));
+        assert!(table.contains("express  ^4.17.0   4.21.2   5.0.1"));
+        assert!(table.contains("missing  ^1.0.0    error    -"));
+
+        // Nothing is written without `--write`
+        assert_eq!(fs::read_to_string(dir.join("package.json"))?, MANIFEST);
+        Ok(())
+    }
+
+    #[test]
+    fn test_upgrade_latest_and_write() -> Result<()> {
+        let temp_dir = create_project();
+        let dir = temp_dir.path();
+
+        let options = UpgradeOptions {
+            packages: vec!["express".to_string(), "typescript".to_string()],
+            latest: true,
+            write: true,
+            ..Default::default()
+        };
+        let report = upgrade_dependencies(&options, dir, &client())?;
+        assert_eq!(report.rows.len(), 2);
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("package.json"))?)?;
+        assert_eq!(manifest["dependencies"]["express"], "^5.0.1");
+        assert_eq!(manifest["dependencies"]["lodash"], "~4.17.20");
+        // Prereleases are never picked by `--latest`
+        assert_eq!(manifest["devDependencies"]["typescript"], "5.8.2");
+
+        let options = UpgradeOptions {
+            packages: vec!["ui".to_string()],
+            ..Default::default()
+        };
+        let result = upgrade_dependencies(&options, dir, &client());
+        assert!(matches!(result, Err(Error::Config { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_upgrade_check() -> Result<()> {
+        let temp_dir = create_project();
+        let dir = temp_dir.path();
+
+        let options = UpgradeOptions {
+            check: true,
+            ..Default::default()
+        };
+        let Err(Error::Outdated { message }) = upgrade_dependencies(&options, dir, &client())
+        else {
+            panic!("expected available upgrades to fail the check");
+        };
+        assert_eq!(message, "2 dependencies can be upgraded: express, lodash");
+        assert_eq!(fs::read_to_string(dir.join("package.json"))?, MANIFEST);
+
+        // Once up to date, the check passes
+        let options = UpgradeOptions {
+            packages: vec!["typescript".to_string()],
+            check: true,
+            ..Default::default()
+        };
+        assert!(upgrade_dependencies(&options, dir, &client()).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_upgrade_latest_older_than_range() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        let manifest = r#"{ "dependencies": { "express": "^5.1.0-rc.1", "lodash": "^4.0.0" } }"#;
+        fs::write(dir.join("package.json"), manifest)?;
+
+        let options = UpgradeOptions {
+            latest: true,
+            write: true,
+            ..Default::default()
+        };
+        let sink = std::sync::Arc::new(progress::RecordingSink::default());
+        let report = progress::with_sink(sink.clone(), || {
+            upgrade_dependencies(&options, dir, &client())
+        })?;
+        // express 5.0.1 would be a downgrade
+        assert!(!row(&report, "express").is_upgrade());
+        assert_eq!(row(&report, "lodash").range, "^4.17.21");
+        assert!(sink.messages().iter().any(|message| message.text
+            == "warning: keeping `express` at ^5.1.0-rc.1: its latest version 5.0.1 is older than the range allows"));
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("package.json"))?)?;
+        assert_eq!(manifest["dependencies"]["express"], "^5.1.0-rc.1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_minimum() {
+        let minimum = |range: &str| range_minimum(range).map(|version| version.to_string());
+        assert_eq!(minimum("^1.2.3").as_deref(), Some("1.2.3"));
+        assert_eq!(minimum(">= 2 <3").as_deref(), Some("2.0.0"));
+        assert_eq!(minimum("^2.0.0 || ^1.4").as_deref(), Some("1.4.0"));
+        assert_eq!(minimum("^6.0.0-beta.1").as_deref(), Some("6.0.0-beta.1"));
+        assert_eq!(minimum("<2"), None);
+        assert_eq!(minimum("^1 || <0.5"), None);
+    }
+
+    #[test]
+    fn test_restyle_range() {
+        let version = Version::parse("2.1.0").unwrap();
+        assert_eq!(restyle_range("^1.0.0", &version), "^2.1.0");
+        assert_eq!(restyle_range("~1.0.0", &version), "~2.1.0");
+        assert_eq!(restyle_range("1.0.0", &version), "2.1.0");
+        assert_eq!(restyle_range("=1.0.0", &version), "2.1.0");
+        assert_eq!(restyle_range(">=1 <2", &version), "^2.1.0");
+    }
+}
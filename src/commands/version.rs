@@ -0,0 +1,779 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use semver::{Prerelease, Version};
+
+use crate::commands::run::{run_tool, RunOptions};
+use crate::dependency::{self, DEPENDENCY_SECTIONS};
+use crate::error::Error;
+use crate::{config, package_manager, progress, suggest, utils, Result};
+
+/// Lockfile written by npm that records package versions
+const NPM_LOCKFILE: &str = "package-lock.json";
+
+/// Lockfile written by pnpm that records dependency specifiers
+const PNPM_LOCKFILE: &str = "pnpm-lock.yaml";
+
+/// How to compute the next version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionBump {
+    /// Next major version, e.g. 1.2.3 -> 2.0.0
+    Major,
+    /// Next minor version, e.g. 1.2.3 -> 1.3.0
+    Minor,
+    /// Next patch version, e.g. 1.2.3 -> 1.2.4
+    Patch,
+    /// Next prerelease, e.g. 1.2.3 -> 1.2.4-0 and 1.2.4-beta.0 -> 1.2.4-beta.1
+    Prerelease,
+    /// An explicit version
+    Exact(Version),
+}
+
+impl FromStr for VersionBump {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "major" => Ok(VersionBump::Major),
+            "minor" => Ok(VersionBump::Minor),
+            "patch" => Ok(VersionBump::Patch),
+            "prerelease" => Ok(VersionBump::Prerelease),
+            _ => Version::parse(s.trim_start_matches('v'))
+                .map(VersionBump::Exact)
+                .map_err(|_| Error::Config {
+                    message: format!(
+                        "Invalid version '{}': expected major, minor, patch, prerelease or a semver version such as 1.2.3",
+                        s
+                    ),
+                    hint: None,
+                }),
+        }
+    }
+}
+
+impl VersionBump {
+    /// Compute the version that follows `current`
+    ///
+    /// Like `npm version`, bumping a prerelease releases it when it already
+    /// has the requested level, e.g. a major bump of 2.0.0-rc.1 gives 2.0.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The current version
+    ///
+    /// # Returns
+    ///
+    /// * `Version` - The next version
+    pub fn apply(&self, current: &Version) -> Version {
+        let is_pre = !current.pre.is_empty();
+        let release = |major, minor, patch| Version::new(major, minor, patch);
+        match self {
+            VersionBump::Major if is_pre && current.minor == 0 && current.patch == 0 => {
+                release(current.major, 0, 0)
+            }
+            VersionBump::Major => release(current.major + 1, 0, 0),
+            VersionBump::Minor if is_pre && current.patch == 0 => {
+                release(current.major, current.minor, 0)
+            }
+            VersionBump::Minor => release(current.major, current.minor + 1, 0),
+            VersionBump::Patch if is_pre => release(current.major, current.minor, current.patch),
+            VersionBump::Patch => release(current.major, current.minor, current.patch + 1),
+            VersionBump::Prerelease if !is_pre => {
+                let mut next = release(current.major, current.minor, current.patch + 1);
+                next.pre = Prerelease::new("0").expect("0 is a valid prerelease");
+                next
+            }
+            VersionBump::Prerelease => {
+                let mut next = current.clone();
+                next.build = semver::BuildMetadata::EMPTY;
+                next.pre = next_prerelease(&current.pre);
+                next
+            }
+            VersionBump::Exact(version) => version.clone(),
+        }
+    }
+}
+
+/// Increment the last numeric identifier of a prerelease, or append `.0`
+fn next_prerelease(pre: &Prerelease) -> Prerelease {
+    let mut identifiers: Vec<String> = pre.as_str().split('.').map(str::to_string).collect();
+    match identifiers
+        .iter_mut()
+        .rev()
+        .find_map(|identifier| Some((identifier.parse::<u64>().ok()?, identifier)))
+    {
+        Some((number, identifier)) => *identifier = (number + 1).to_string(),
+        None => identifiers.push("0".to_string()),
+    }
+    Prerelease::new(&identifiers.join(".")).expect("incremented prerelease is valid")
+}
+
+/// Options for bumping versions
+#[derive(Debug, Clone)]
+pub struct VersionOptions {
+    /// How to compute the next version
+    pub bump: VersionBump,
+
+    /// Commit the change and tag it
+    pub git: bool,
+
+    /// Bump even if the git working tree has uncommitted changes
+    pub allow_dirty: bool,
+
+    /// Bump every package of the workspace instead of the current package
+    pub workspace: bool,
+}
+
+/// A version change made to one package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionChange {
+    /// Package name
+    pub name: String,
+    /// Directory containing the package's package.json
+    pub path: PathBuf,
+    /// Version before the bump
+    pub from: Version,
+    /// Version after the bump
+    pub to: Version,
+}
+
+/// Bump the version of the package containing `current_dir`, or of every workspace package
+///
+/// package.json files are rewritten in their existing style, package-lock.json
+/// and pnpm-lock.yaml are kept in sync, and the change is committed and tagged
+/// unless `git` is unset. The `preversion` and `postversion` tools from the
+/// cargonode configuration run before the bump and after the tag.
+///
+/// Bumping one member of a workspace updates its entry in the workspace's
+/// package-lock.json; the other members keep the ranges they depend on it
+/// with.
+///
+/// # Arguments
+///
+/// * `options` - Which packages to bump, how, and whether to commit
+/// * `current_dir` - Directory to start looking for the project from
+///
+/// # Returns
+///
+/// * `Result<Vec<VersionChange>>` - The version changes that were made
+pub fn bump_version(options: &VersionOptions, current_dir: &Path) -> Result<Vec<VersionChange>> {
+    let not_found = || Error::Config {
+        message: format!(
+            "package.json not found in {} or any parent directory",
+            current_dir.display()
+        ),
+        hint: Some(suggest::missing_manifest_hint()),
+    };
+    let root = if options.workspace {
+        package_manager::find_workspace_root(current_dir)?
+    } else {
+        package_manager::find_project_root(current_dir)
+    }
+    .ok_or_else(not_found)?;
+
+    let packages = if options.workspace {
+        package_manager::get_workspace_packages(&root)?
+            .into_iter()
+            .map(|package| package.path)
+            .collect()
+    } else {
+        vec![root.clone()]
+    };
+    let changes = packages
+        .into_iter()
+        .map(|path| plan_change(&path, &options.bump))
+        .collect::<Result<Vec<_>>>()?;
+
+    let use_git = options.git && is_inside_git_repo(&root);
+    if use_git {
+        if !options.allow_dirty {
+            ensure_clean(&root)?;
+        }
+        for tag in tags(&changes, options.workspace) {
+            let tag_ref = format!("refs/tags/{}", tag);
+            if git(&root, &["rev-parse", "--quiet", "--verify", &tag_ref]).is_ok() {
+                return Err(Error::Git {
+                    message: format!("Tag '{}' already exists", tag),
+                    details: "Pick a different version or delete the tag first".to_string(),
+                });
+            }
+        }
+    } else if options.git {
+        progress::note("Not in a git repository; skipping the commit and tag")?;
+    }
+
+    run_script(&root, "preversion")?;
+
+    let mut written = Vec::new();
+    for change in &changes {
+        progress::status(
+            "Bumping",
+            &format!("{} v{} -> v{}", change.name, change.from, change.to),
+        )?;
+        written.push(write_package_json(change, &changes)?);
+    }
+    // A member's lockfile is the workspace's; the other members keep the
+    // ranges they depend on it with, in package.json and lockfile alike
+    let lockfile_root = match options.workspace {
+        true => root.clone(),
+        false => package_manager::find_workspace_root(&root)?.unwrap_or_else(|| root.clone()),
+    };
+    written.extend(update_npm_lockfile(
+        &lockfile_root,
+        &changes,
+        options.workspace,
+    )?);
+    if options.workspace {
+        written.extend(update_pnpm_lockfile(&lockfile_root, &changes)?);
+    }
+
+    if use_git {
+        commit_and_tag(&root, &written, &changes, options.workspace)?;
+    }
+
+    run_script(&root, "postversion")?;
+
+    Ok(changes)
+}
+
+fn plan_change(path: &Path, bump: &VersionBump) -> Result<VersionChange> {
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(path.join("package.json"))?)?;
+    let field = |name: &str| manifest.get(name).and_then(|value| value.as_str());
+    let name = field("name").map(str::to_string).unwrap_or_else(|| {
+        path.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    });
+
+    let raw = field("version").ok_or_else(|| Error::Config {
+        message: format!(
+            "{} has no version field",
+            path.join("package.json").display()
+        ),
+        hint: None,
+    })?;
+    let from = Version::parse(raw).map_err(|e| Error::Config {
+        message: format!("Invalid version '{}' in {}: {}", raw, name, e),
+        hint: None,
+    })?;
+
+    let to = bump.apply(&from);
+    if to == from {
+        return Err(Error::Config {
+            message: format!("{} is already at version {}", name, from),
+            hint: None,
+        });
+    }
+
+    Ok(VersionChange {
+        name,
+        path: path.to_path_buf(),
+        from,
+        to,
+    })
+}
+
+/// Rewrite a dependency range to point at `version`, keeping its operator
+///
+/// `workspace:` prefixes are kept. Ranges other than an exact version or a
+/// single `^`/`~` version, like `workspace:*` or `>=1 <2`, are left alone.
+fn rewrite_range(range: &str, version: &Version) -> Option<String> {
+    let (prefix, rest) = match range.strip_prefix("workspace:") {
+        Some(rest) => ("workspace:", rest),
+        None => ("", range),
+    };
+    let (operator, raw) = match rest.strip_prefix(['^', '~']) {
+        Some(raw) => (&rest[..1], raw),
+        None => ("", rest),
+    };
+    Version::parse(raw).ok()?;
+
+    let rewritten = format!("{}{}{}", prefix, operator, version);
+    (rewritten != range).then_some(rewritten)
+}
+
+/// Point the dependencies in `deps` on bumped packages at their new versions
+fn update_dependency_ranges(
+    deps: &mut serde_json::Map<String, serde_json::Value>,
+    changes: &[VersionChange],
+) {
+    for change in changes {
+        let Some(range) = deps.get(&change.name).and_then(|range| range.as_str()) else {
+            continue;
+        };
+        if let Some(rewritten) = rewrite_range(range, &change.to) {
+            deps.insert(change.name.clone(), rewritten.into());
+        }
+    }
+}
+
+fn write_package_json(change: &VersionChange, changes: &[VersionChange]) -> Result<PathBuf> {
+    let path = change.path.join("package.json");
+    let original = fs::read_to_string(&path)?;
+    let mut manifest: serde_json::Value = serde_json::from_str(&original)?;
+
+    manifest["version"] = change.to.to_string().into();
+    for section in DEPENDENCY_SECTIONS {
+        if let Some(deps) = manifest
+            .get_mut(section)
+            .and_then(|deps| deps.as_object_mut())
+        {
+            update_dependency_ranges(deps, changes);
+        }
+    }
+
+    dependency::write_manifest(&path, &manifest, &original)?;
+    Ok(path)
+}
+
+/// Update package versions and workspace dependency ranges in package-lock.json
+///
+/// # Arguments
+///
+/// * `root` - Directory of the lockfile: the workspace root for members
+/// * `changes` - The version changes
+/// * `ranges` - Whether the packages depending on the changed ones were
+///   rewritten too, so their ranges in the lockfile follow
+///
+/// # Returns
+///
+/// * `Result<Option<PathBuf>>` - The lockfile, if there is one
+fn update_npm_lockfile(
+    root: &Path,
+    changes: &[VersionChange],
+    ranges: bool,
+) -> Result<Option<PathBuf>> {
+    let path = root.join(NPM_LOCKFILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let original = fs::read_to_string(&path)?;
+    let mut lockfile: serde_json::Value = serde_json::from_str(&original)?;
+
+    // The top-level version is the root package's
+    if let Some(change) = changes.iter().find(|change| change.path == root) {
+        lockfile["version"] = change.to.to_string().into();
+    }
+
+    if let Some(packages) = lockfile
+        .get_mut("packages")
+        .and_then(|packages| packages.as_object_mut())
+    {
+        for change in changes {
+            let key = change
+                .path
+                .strip_prefix(root)
+                .unwrap_or(&change.path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            if let Some(entry) = packages.get_mut(&key) {
+                entry["version"] = change.to.to_string().into();
+            }
+        }
+        for entry in packages.values_mut().filter(|_| ranges) {
+            for section in DEPENDENCY_SECTIONS {
+                if let Some(deps) = entry.get_mut(section).and_then(|deps| deps.as_object_mut()) {
+                    update_dependency_ranges(deps, changes);
+                }
+            }
+        }
+    }
+
+    dependency::write_manifest(&path, &lockfile, &original)?;
+    Ok(Some(path))
+}
+
+/// Update the specifiers of workspace dependencies in pnpm-lock.yaml
+///
+/// pnpm does not record the versions of the packages themselves, only the
+/// ranges other packages depend on them with, so only those are rewritten.
+fn update_pnpm_lockfile(root: &Path, changes: &[VersionChange]) -> Result<Option<PathBuf>> {
+    let path = root.join(PNPM_LOCKFILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let original = fs::read_to_string(&path)?;
+
+    let mut dependency: Option<&VersionChange> = None;
+    let mut updated = String::with_capacity(original.len());
+    for line in original.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if let Some(specifier) = trimmed.strip_prefix("specifier:") {
+            let specifier = specifier.trim().trim_matches(['\'', '"']);
+            if let Some(rewritten) =
+                dependency.and_then(|change| rewrite_range(specifier, &change.to))
+            {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                let newline = &line[line.trim_end().len()..];
+                updated.push_str(&format!("{}specifier: {}{}", indent, rewritten, newline));
+                continue;
+            }
+        } else if let Some(name) = trimmed.strip_suffix(':') {
+            let name = name.trim_matches(['\'', '"']);
+            dependency = changes.iter().find(|change| change.name == name);
+        }
+        updated.push_str(line);
+    }
+
+    if updated == original {
+        return Ok(None);
+    }
+    utils::write_atomic(&path, updated)?;
+    Ok(Some(path))
+}
+
+/// Run the tool named `script` if the cargonode configuration defines it
+fn run_script(root: &Path, script: &str) -> Result<()> {
+    let Ok(config) = config::load_config(root) else {
+        return Ok(());
+    };
+    if config::get_tool_config(&config, script).is_none() {
+        return Ok(());
+    }
+
+    progress::status("Running", script)?;
+    let options = RunOptions {
+        project_dir: root.to_path_buf(),
+        force: true,
+        verbose: false,
+        quiet: progress::verbosity() == progress::Verbosity::Quiet,
+        timeout: None,
+        args: Vec::new(),
+        log_file: None,
+        summarize_tests: false,
+    };
+    let result = run_tool(script, &config, &options)?;
+    if !result.status.success() {
+        return Err(Error::CommandFailed {
+            command: script.to_string(),
+            status: result.status,
+        });
+    }
+    Ok(())
+}
+
+/// Tags created for `changes`: `vX.Y.Z` for a single package, `name@X.Y.Z` per workspace package
+fn tags(changes: &[VersionChange], workspace: bool) -> Vec<String> {
+    if workspace {
+        changes
+            .iter()
+            .map(|change| format!("{}@{}", change.name, change.to))
+            .collect()
+    } else {
+        changes
+            .iter()
+            .map(|change| format!("v{}", change.to))
+            .collect()
+    }
+}
+
+fn commit_and_tag(
+    root: &Path,
+    written: &[PathBuf],
+    changes: &[VersionChange],
+    workspace: bool,
+) -> Result<()> {
+    let tags = tags(changes, workspace);
+    let message = if workspace {
+        format!("Release {}", tags.join(", "))
+    } else {
+        tags.join(", ")
+    };
+
+    let mut add = vec!["add".to_string(), "--".to_string()];
+    add.extend(
+        written
+            .iter()
+            .map(|path| path.to_string_lossy().to_string()),
+    );
+    git(root, &add.iter().map(String::as_str).collect::<Vec<_>>())?;
+    git(root, &["commit", "--quiet", "-m", &message])?;
+    for tag in &tags {
+        git(root, &["tag", "-a", tag, "-m", tag])?;
+        progress::status("Tagged", tag)?;
+    }
+    Ok(())
+}
+
+fn is_inside_git_repo(dir: &Path) -> bool {
+    git(dir, &["rev-parse", "--is-inside-work-tree"]).is_ok_and(|output| output == "true")
+}
+
+/// Fail if the git working tree has uncommitted changes
+fn ensure_clean(dir: &Path) -> Result<()> {
+    let status = git(dir, &["status", "--porcelain"])?;
+    if status.is_empty() {
+        return Ok(());
+    }
+
+    // Porcelain lines are a two-letter status, a space and the path
+    let files: Vec<&str> = status.lines().filter_map(|line| line.get(3..)).collect();
+    Err(Error::DirtyWorkingTree {
+        message: format!(
+            "{} uncommitted file{}: {}",
+            files.len(),
+            if files.len() == 1 { "" } else { "s" },
+            files.join(", ")
+        ),
+    })
+}
+
+/// Run git in `dir` and return its standard output without trailing whitespace
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| Error::Git {
+            message: "Git is not installed".to_string(),
+            details: e.to_string(),
+        })?;
+
+    if !output.status.success() {
+        return Err(Error::Git {
+            message: format!("`git {}` failed", args.join(" ")),
+            details: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    // Leading whitespace is significant, e.g. in `git status --porcelain`
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn options(bump: &str) -> VersionOptions {
+        VersionOptions {
+            bump: bump.parse().unwrap(),
+            git: false,
+            allow_dirty: false,
+            workspace: false,
+        }
+    }
+
+    fn git_available() -> bool {
+        Command::new("git")
+            .arg("--version")
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    #[test]
+    fn test_version_bump_apply() {
+        let cases = [
+            ("major", "1.2.3", "2.0.0"),
+            ("minor", "1.2.3", "1.3.0"),
+            ("patch", "1.2.3", "1.2.4"),
+            ("prerelease", "1.2.3", "1.2.4-0"),
+            ("prerelease", "1.2.4-0", "1.2.4-1"),
+            ("prerelease", "1.2.4-beta", "1.2.4-beta.0"),
+            ("prerelease", "1.2.4-beta.1", "1.2.4-beta.2"),
+            ("major", "2.0.0-rc.1", "2.0.0"),
+            ("major", "2.1.0-rc.1", "3.0.0"),
+            ("minor", "1.3.0-rc.1", "1.3.0"),
+            ("patch", "1.2.4-rc.1", "1.2.4"),
+            ("3.0.0", "1.2.3", "3.0.0"),
+            ("v3.0.0-alpha.1", "1.2.3", "3.0.0-alpha.1"),
+        ];
+
+        for (bump, current, expected) in cases {
+            let bump: VersionBump = bump.parse().unwrap();
+            let next = bump.apply(&Version::parse(current).unwrap());
+            assert_eq!(next.to_string(), expected, "{:?} of {}", bump, current);
+        }
+
+        assert!(matches!(
+            "huge".parse::<VersionBump>(),
+            Err(Error::Config { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rewrite_range() {
+        let version = Version::new(2, 0, 0);
+        assert_eq!(rewrite_range("^1.0.0", &version).unwrap(), "^2.0.0");
+        assert_eq!(rewrite_range("~1.0.0", &version).unwrap(), "~2.0.0");
+        assert_eq!(rewrite_range("1.0.0", &version).unwrap(), "2.0.0");
+        assert_eq!(
+            rewrite_range("workspace:^1.0.0", &version).unwrap(),
+            "workspace:^2.0.0"
+        );
+        assert_eq!(rewrite_range("workspace:*", &version), None);
+        assert_eq!(rewrite_range(">=1.0.0 <2.0.0", &version), None);
+        assert_eq!(rewrite_range("^2.0.0", &version), None);
+    }
+
+    #[test]
+    fn test_bump_version_single_package() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(
+            root.join("package.json"),
+            "{\n\t\"name\": \"app\",\n\t\"version\": \"1.2.3\"\n}\n",
+        )?;
+        fs::write(
+            root.join(NPM_LOCKFILE),
+            r#"{"name": "app", "version": "1.2.3", "lockfileVersion": 3, "packages": {"": {"name": "app", "version": "1.2.3"}}}"#,
+        )?;
+
+        let changes = bump_version(&options("minor"), root)?;
+        assert_eq!(changes[0].to, Version::new(1, 3, 0));
+        assert_eq!(
+            fs::read_to_string(root.join("package.json"))?,
+            "{\n\t\"name\": \"app\",\n\t\"version\": \"1.3.0\"\n}\n"
+        );
+
+        let lockfile: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(root.join(NPM_LOCKFILE))?)?;
+        assert_eq!(lockfile["version"], "1.3.0");
+        assert_eq!(lockfile["packages"][""]["version"], "1.3.0");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bump_version_workspace() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        let write = |dir: &str, manifest: &str| {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("package.json"), manifest).unwrap();
+        };
+        write(
+            ".",
+            r#"{"name": "root", "version": "0.0.0", "workspaces": ["packages/*"]}"#,
+        );
+        write("packages/core", r#"{"name": "core", "version": "1.0.0"}"#);
+        write(
+            "packages/app",
+            r#"{"name": "app", "version": "0.1.0", "dependencies": {"core": "^1.0.0", "left-pad": "^1.0.0"}, "devDependencies": {"core": "workspace:*"}}"#,
+        );
+        fs::write(
+            root.join(PNPM_LOCKFILE),
+            "importers:\n  packages/app:\n    dependencies:\n      core:\n        specifier: ^1.0.0\n        version: link:../core\n      left-pad:\n        specifier: ^1.0.0\n        version: 1.3.0\n",
+        )?;
+
+        let options = VersionOptions {
+            workspace: true,
+            ..options("major")
+        };
+        let changes = bump_version(&options, &root.join("packages/app"))?;
+        let versions: Vec<String> = changes
+            .iter()
+            .map(|change| format!("{}@{}", change.name, change.to))
+            .collect();
+        assert_eq!(versions, vec!["app@1.0.0", "core@2.0.0"]);
+
+        let app: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(root.join("packages/app/package.json"))?)?;
+        assert_eq!(app["dependencies"]["core"], "^2.0.0");
+        assert_eq!(app["dependencies"]["left-pad"], "^1.0.0");
+        assert_eq!(app["devDependencies"]["core"], "workspace:*");
+
+        // The root package is not a workspace member and keeps its version
+        let root_manifest = fs::read_to_string(root.join("package.json"))?;
+        assert!(root_manifest.contains(r#""version": "0.0.0""#));
+
+        let lockfile = fs::read_to_string(root.join(PNPM_LOCKFILE))?;
+        assert!(lockfile.contains("      core:\n        specifier: ^2.0.0\n"));
+        assert!(lockfile.contains("      left-pad:\n        specifier: ^1.0.0\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bump_version_workspace_member() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        let write = |dir: &str, manifest: &str| {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("package.json"), manifest).unwrap();
+        };
+        write(
+            ".",
+            r#"{"name": "root", "version": "0.0.0", "workspaces": ["packages/*"]}"#,
+        );
+        write("packages/core", r#"{"name": "core", "version": "1.0.0"}"#);
+        write(
+            "packages/app",
+            r#"{"name": "app", "version": "0.1.0", "dependencies": {"core": "^1.0.0"}}"#,
+        );
+        fs::write(
+            root.join(NPM_LOCKFILE),
+            r#"{"name": "root", "version": "0.0.0", "lockfileVersion": 3, "packages": {"": {"name": "root", "version": "0.0.0"}, "packages/core": {"name": "core", "version": "1.0.0"}, "packages/app": {"name": "app", "version": "0.1.0", "dependencies": {"core": "^1.0.0"}}}}"#,
+        )?;
+
+        // Without --workspace only the member is bumped, in the workspace's
+        // lockfile too
+        bump_version(&options("major"), &root.join("packages/core"))?;
+        let lockfile: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(root.join(NPM_LOCKFILE))?)?;
+        assert_eq!(lockfile["packages"]["packages/core"]["version"], "2.0.0");
+        assert_eq!(lockfile["version"], "0.0.0");
+        assert_eq!(lockfile["packages"][""]["version"], "0.0.0");
+        assert_eq!(
+            lockfile["packages"]["packages/app"]["dependencies"]["core"],
+            "^1.0.0"
+        );
+        assert!(!root.join("packages/core").join(NPM_LOCKFILE).exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bump_version_git() -> Result<()> {
+        if !git_available() {
+            return Ok(());
+        }
+
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(
+            root.join("package.json"),
+            r#"{"name": "app", "version": "1.0.0"}"#,
+        )?;
+        for args in [
+            &["init", "--quiet"][..],
+            &["config", "user.name", "Test"],
+            &["config", "user.email", "test@example.com"],
+            &["config", "commit.gpgsign", "false"],
+            &["config", "tag.gpgsign", "false"],
+            &["add", "."],
+            &["commit", "--quiet", "-m", "init"],
+        ] {
+            git(root, args)?;
+        }
+        let options = VersionOptions {
+            git: true,
+            ..options("patch")
+        };
+
+        fs::write(
+            root.join("package.json"),
+            r#"{"name": "app", "version": "1.0.0" }"#,
+        )?;
+        let err = bump_version(&options, root).unwrap_err();
+        assert!(matches!(err, Error::DirtyWorkingTree { .. }));
+        assert!(err.to_string().contains("1 uncommitted file: package.json"));
+        assert!(fs::read_to_string(root.join("package.json"))?.contains("1.0.0"));
+
+        git(root, &["checkout", "--quiet", "--", "package.json"])?;
+        bump_version(&options, root)?;
+        assert_eq!(git(root, &["log", "-1", "--format=%s"])?, "v1.0.1");
+        assert_eq!(git(root, &["tag", "--list"])?, "v1.0.1");
+        assert_eq!(git(root, &["cat-file", "-t", "v1.0.1"])?, "tag");
+        assert!(git(root, &["status", "--porcelain"])?.is_empty());
+
+        Ok(())
+    }
+}
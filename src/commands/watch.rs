@@ -0,0 +1,340 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::commands::run::RunResult;
+use crate::config::{self, ToolConfig};
+use crate::error::Error;
+use crate::inputs::{InputMatcher, InputTracker, EXCLUDED_DIRS};
+use crate::{progress, utils, Result};
+
+/// Time without further changes after which a burst of changes runs the tool
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// ANSI sequence clearing the terminal and moving the cursor home
+const CLEAR_SCREEN: &str = "\x1B[2J\x1B[H";
+
+/// Options for watching a project
+pub struct WatchOptions {
+    /// Project directory
+    pub project_dir: PathBuf,
+
+    /// Whether to clear the terminal before each run
+    pub clear_screen: bool,
+
+    /// Stop after this many runs, counting the first one
+    pub max_runs: Option<usize>,
+}
+
+/// Files whose changes reload the configuration
+fn is_config_file(project_dir: &Path, path: &Path) -> bool {
+    path.parent() == Some(project_dir)
+        && path.file_name().is_some_and(|name| {
+            name == "package.json"
+                || config::PROJECT_CONFIG_FILES
+                    .iter()
+                    .any(|file| name == *file)
+        })
+}
+
+/// Resolve the configuration of `tool` the way `run_generic_command` does
+fn watched_tool(tool: &str, project_dir: &Path) -> Result<ToolConfig> {
+    let config = config::load_config(project_dir)?;
+    config::get_tool_config(&config, tool)
+        .cloned()
+        .or_else(|| {
+            config::default_tool_config(tool, project_dir, None).map(|default| default.config)
+        })
+        .ok_or_else(|| config::tool_not_found(&config, tool))
+}
+
+/// Matcher for the inputs of `tool_config`, never matching its outputs
+fn input_matcher(tool_config: &ToolConfig, project_dir: &Path) -> Result<InputMatcher> {
+    let excludes = tool_config
+        .exclude
+        .iter()
+        .chain(&tool_config.outputs)
+        .cloned()
+        .collect();
+    InputTracker::new(project_dir, tool_config.inputs.clone())
+        .with_excludes(excludes)
+        .with_state_dir(&utils::state_dir(project_dir))
+        .matcher()
+}
+
+/// Watches the project directory and its top-level directories
+///
+/// [`EXCLUDED_DIRS`] are never watched, so installing packages does not
+/// flood the watcher. Directories created later are picked up by
+/// [`ProjectWatcher::watch_new_dirs`].
+struct ProjectWatcher {
+    watcher: RecommendedWatcher,
+    project_dir: PathBuf,
+    watched: HashSet<PathBuf>,
+}
+
+impl ProjectWatcher {
+    fn new(project_dir: &Path) -> Result<(Self, Receiver<notify::Result<Event>>)> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(watch_error)?;
+        watcher
+            .watch(project_dir, RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+        let mut project = Self {
+            watcher,
+            project_dir: project_dir.to_path_buf(),
+            watched: HashSet::new(),
+        };
+        project.watch_new_dirs()?;
+        Ok((project, events))
+    }
+
+    /// Watch top-level directories that are not watched yet
+    fn watch_new_dirs(&mut self) -> Result<()> {
+        for entry in fs::read_dir(&self.project_dir)? {
+            let path = entry?.path();
+            let excluded = path
+                .file_name()
+                .is_some_and(|name| EXCLUDED_DIRS.iter().any(|dir| name == *dir));
+            if excluded || !path.is_dir() || self.watched.contains(&path) {
+                continue;
+            }
+            self.watcher
+                .watch(&path, RecursiveMode::Recursive)
+                .map_err(watch_error)?;
+            self.watched.insert(path);
+        }
+        Ok(())
+    }
+}
+
+fn watch_error(error: notify::Error) -> Error {
+    Error::Input {
+        message: format!("Failed to watch the project: {}", error),
+        hint: None,
+    }
+}
+
+/// What a burst of file events asks for
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Changes {
+    /// An input changed
+    inputs: bool,
+    /// package.json or the cargonode config changed
+    config: bool,
+    /// Something was created directly in the project directory
+    created: bool,
+}
+
+impl Changes {
+    fn record(&mut self, event: &Event, project_dir: &Path, matcher: &InputMatcher) {
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        for path in &event.paths {
+            if is_config_file(project_dir, path) {
+                self.config = true;
+            } else if matcher.matches(path) {
+                self.inputs = true;
+            }
+            if matches!(event.kind, EventKind::Create(_)) && path.parent() == Some(project_dir) {
+                self.created = true;
+            }
+        }
+    }
+}
+
+/// Block until inputs or the configuration change, then until they settle
+fn wait_for_changes(
+    events: &Receiver<notify::Result<Event>>,
+    project: &mut ProjectWatcher,
+    matcher: &InputMatcher,
+) -> Result<Changes> {
+    let mut changes = Changes::default();
+    loop {
+        let received = if changes.inputs || changes.config {
+            match events.recv_timeout(DEBOUNCE) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => return Ok(changes),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        } else {
+            match events.recv() {
+                Ok(event) => event,
+                Err(_) => break,
+            }
+        };
+        match received {
+            Ok(event) => changes.record(&event, &project.project_dir, matcher),
+            Err(e) => progress::warn(&format!("file watching: {}", e))?,
+        }
+        if std::mem::take(&mut changes.created) {
+            project.watch_new_dirs()?;
+        }
+    }
+    Err(Error::Input {
+        message: "The file watcher stopped unexpectedly".to_string(),
+        hint: None,
+    })
+}
+
+/// Run a tool, then run it again whenever its inputs change
+///
+/// Changes are debounced so that saving several files runs the tool once.
+/// A change to package.json or the cargonode config reloads the tool's
+/// configuration before the next run. The tool's outputs and the
+/// directories in [`EXCLUDED_DIRS`] never trigger a run. A failing run is
+/// reported and watching continues; Ctrl-C stops the run in flight and the
+/// watch with it.
+///
+/// # Arguments
+///
+/// * `tool` - Name of the tool whose inputs are watched
+/// * `options` - Watch options
+/// * `run` - Runs the tool once in the given project directory
+///
+/// # Returns
+///
+/// * `Result<usize>` - Number of runs, once `max_runs` is reached
+pub fn watch(
+    tool: &str,
+    options: &WatchOptions,
+    mut run: impl FnMut(&Path) -> Result<RunResult>,
+) -> Result<usize> {
+    let project_dir = options.project_dir.as_path();
+    let mut matcher = input_matcher(&watched_tool(tool, project_dir)?, project_dir)?;
+    let (mut project, events) = ProjectWatcher::new(project_dir)?;
+
+    let mut runs = 0;
+    loop {
+        if options.clear_screen {
+            let mut stderr = io::stderr();
+            write!(stderr, "{}", CLEAR_SCREEN)?;
+            stderr.flush()?;
+        }
+
+        runs += 1;
+        let start = Instant::now();
+        let outcome = match run(project_dir) {
+            Ok(result) if result.status.success() => "finished".to_string(),
+            Ok(result) => format!("failed ({})", result.status),
+            Err(e @ Error::Terminated { .. }) => return Err(e),
+            Err(e) => {
+                progress::error(&e.to_string())?;
+                "failed".to_string()
+            }
+        };
+        progress::status(
+            "Watching",
+            &format!(
+                "run {} {} in {:.1}s; waiting for changes",
+                runs,
+                outcome,
+                start.elapsed().as_secs_f64()
+            ),
+        )?;
+        if options.max_runs.is_some_and(|max_runs| runs >= max_runs) {
+            return Ok(runs);
+        }
+
+        let changes = wait_for_changes(&events, &mut project, &matcher)?;
+        if changes.config {
+            let reloaded = watched_tool(tool, project_dir)
+                .and_then(|tool_config| input_matcher(&tool_config, project_dir));
+            match reloaded {
+                Ok(reloaded) => {
+                    matcher = reloaded;
+                    progress::status("Reloaded", &format!("configuration of `{}`", tool))?;
+                }
+                Err(e) => progress::warn(&format!(
+                    "keeping the previous configuration of `{}`: {}",
+                    tool, e
+                ))?,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::commands::run::{run_tool, RunOptions};
+
+    #[test]
+    fn test_input_matcher() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        let tool_config = ToolConfig {
+            command: "tsc".to_string(),
+            inputs: vec!["src/**/*".to_string()],
+            outputs: vec!["src/generated/**/*".to_string()],
+            ..Default::default()
+        };
+
+        let matcher = input_matcher(&tool_config, dir)?;
+        assert!(matcher.matches(&dir.join("src/index.ts")));
+        assert!(!matcher.matches(&dir.join("src/generated/types.ts")));
+        assert!(!matcher.matches(&dir.join("src/node_modules/a/index.js")));
+        assert!(!matcher.matches(&dir.join("README.md")));
+
+        assert!(is_config_file(dir, &dir.join("package.json")));
+        assert!(is_config_file(dir, &dir.join(".cargonode.toml")));
+        assert!(!is_config_file(dir, &dir.join("src/package.json")));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_watch_reruns_on_change() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        fs::create_dir(dir.join("src"))?;
+        fs::write(dir.join("src/index.js"), "one")?;
+        fs::write(
+            dir.join("package.json"),
+            r#"{"name": "app", "cargonode": {"tools": {"build": {
+                "command": "sh",
+                "args": ["-c", "echo run >> runs.log"],
+                "inputs": ["src/**/*"]
+            }}}}"#,
+        )?;
+
+        let options = WatchOptions {
+            project_dir: dir.to_path_buf(),
+            clear_screen: false,
+            max_runs: Some(2),
+        };
+        let mut calls = 0;
+        let runs = watch("build", &options, |project_dir| {
+            let config = config::load_config(project_dir)?;
+            let run_options = RunOptions {
+                project_dir: project_dir.to_path_buf(),
+                force: false,
+                verbose: false,
+                quiet: true,
+                timeout: None,
+                args: Vec::new(),
+                log_file: None,
+                summarize_tests: false,
+            };
+            let result = run_tool("build", &config, &run_options)?;
+            calls += 1;
+            if calls == 1 {
+                // Written after the watch started, so it is seen as a change
+                fs::write(project_dir.join("src/index.js"), "two")?;
+            }
+            Ok(result)
+        })?;
+
+        assert_eq!(runs, 2);
+        assert_eq!(fs::read_to_string(dir.join("runs.log"))?, "run\nrun\n");
+        Ok(())
+    }
+}
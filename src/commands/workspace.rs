@@ -0,0 +1,366 @@
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::commands::run::RunResult;
+use crate::error::Error;
+use crate::package_manager;
+use crate::{config, progress, suggest, Result};
+
+/// Options for running a tool across a workspace
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceOptions {
+    /// Only run in these packages (all members when empty)
+    pub packages: Vec<String>,
+
+    /// Stop at the first package that fails
+    pub fail_fast: bool,
+}
+
+/// Outcome of running a tool in one workspace package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageStatus {
+    /// The tool ran and succeeded
+    Ok,
+    /// The tool was skipped because a cached run is still valid
+    Cached,
+    /// The tool failed
+    Failed,
+    /// The package does not configure the tool
+    Skipped,
+}
+
+impl fmt::Display for PackageStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            PackageStatus::Ok => "ok",
+            PackageStatus::Cached => "cached",
+            PackageStatus::Failed => "failed",
+            PackageStatus::Skipped => "skipped",
+        };
+        f.pad(status)
+    }
+}
+
+/// Result of running a tool in one workspace package
+#[derive(Debug, Clone)]
+pub struct PackageOutcome {
+    /// Package name
+    pub name: String,
+    /// How the run ended
+    pub status: PackageStatus,
+    /// Time spent running the tool
+    pub duration: Duration,
+}
+
+/// Results of running a tool across a workspace, in run order
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceSummary {
+    /// One outcome per package that was visited
+    pub outcomes: Vec<PackageOutcome>,
+}
+
+impl WorkspaceSummary {
+    /// Names of the packages whose run failed
+    pub fn failed(&self) -> Vec<&str> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.status == PackageStatus::Failed)
+            .map(|outcome| outcome.name.as_str())
+            .collect()
+    }
+
+    /// Format the outcomes as a table with package, status and duration columns
+    pub fn table(&self) -> String {
+        let width = self
+            .outcomes
+            .iter()
+            .map(|outcome| outcome.name.len())
+            .chain(["Package".len()])
+            .max()
+            .unwrap_or_default();
+
+        let mut lines = vec![format!("{:<width$}  {:<7}  Duration", "Package", "Status")];
+        for outcome in &self.outcomes {
+            lines.push(format!(
+                "{:<width$}  {:<7}  {:.2}s",
+                outcome.name,
+                outcome.status,
+                outcome.duration.as_secs_f64()
+            ));
+        }
+        lines.join("\n")
+    }
+
+    /// Turn failed packages into an error
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error naming the failed packages, if any
+    pub fn ensure_success(&self) -> Result<()> {
+        let failed = self.failed();
+        if failed.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::Workspace {
+            message: format!(
+                "{} of {} workspace package{} failed: {}",
+                failed.len(),
+                self.outcomes.len(),
+                if self.outcomes.len() == 1 { "" } else { "s" },
+                failed.join(", ")
+            ),
+        })
+    }
+}
+
+/// Run a tool in every package of the workspace containing `current_dir`
+///
+/// Packages run after the workspace members they depend on. Packages that do
+/// not configure `tool` are skipped. A failure does not stop the remaining
+/// packages unless `fail_fast` is set. A summary table is printed at the end.
+///
+/// # Arguments
+///
+/// * `tool` - Name of the tool, used to skip packages that don't configure it
+/// * `current_dir` - Directory inside the workspace
+/// * `options` - Package filter and failure handling
+/// * `run` - Runs the tool in a package directory
+///
+/// # Returns
+///
+/// * `Result<WorkspaceSummary>` - The outcome for each visited package
+pub fn run_in_workspace(
+    tool: &str,
+    current_dir: &Path,
+    options: &WorkspaceOptions,
+    mut run: impl FnMut(&Path) -> Result<RunResult>,
+) -> Result<WorkspaceSummary> {
+    let workspace_root =
+        package_manager::find_workspace_root(current_dir)?.ok_or_else(|| Error::Config {
+            message: format!(
+                "package.json not found in {} or any parent directory",
+                current_dir.display()
+            ),
+            hint: Some(suggest::missing_manifest_hint()),
+        })?;
+
+    // Ordering before filtering keeps indirect dependencies in order too
+    let graph = package_manager::workspace_dependency_graph(&workspace_root)?;
+    let mut packages = graph.topological_order()?;
+    if let Some(unknown) = options
+        .packages
+        .iter()
+        .find(|name| !packages.iter().any(|package| package.name == **name))
+    {
+        return Err(Error::Config {
+            message: format!("Package '{}' not found in the workspace", unknown),
+            hint: None,
+        });
+    }
+    if !options.packages.is_empty() {
+        packages.retain(|package| options.packages.contains(&package.name));
+    }
+
+    let mut summary = WorkspaceSummary::default();
+    for package in packages {
+        let configured = config::load_config(&package.path)
+            .map(|config| config::get_tool_config(&config, tool).is_some())
+            .unwrap_or(false);
+        if !configured {
+            summary.outcomes.push(PackageOutcome {
+                name: package.name.clone(),
+                status: PackageStatus::Skipped,
+                duration: Duration::ZERO,
+            });
+            continue;
+        }
+
+        progress::status("Running", &format!("{} in {}", tool, package.name))?;
+
+        let start = Instant::now();
+        let status = match run(&package.path) {
+            Ok(result) if result.cached => PackageStatus::Cached,
+            Ok(result) if result.status.success() => PackageStatus::Ok,
+            Ok(_) => PackageStatus::Failed,
+            Err(e) => {
+                progress::error(&e.to_string())?;
+                PackageStatus::Failed
+            }
+        };
+        let failed = status == PackageStatus::Failed;
+        summary.outcomes.push(PackageOutcome {
+            name: package.name.clone(),
+            status,
+            duration: start.elapsed(),
+        });
+
+        if failed && options.fail_fast {
+            progress::note("Stopping after the first failure (--fail-fast)")?;
+            break;
+        }
+    }
+
+    progress::write_status(&summary.table())?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process::ExitStatus;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Create a workspace where `app` depends on `ui`, which depends on `core`
+    fn create_workspace() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let tools = r#""cargonode": {"tools": {"build": {"command": "echo", "inputs": ["*"]}}}"#;
+
+        let write = |dir: &str, manifest: String| {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("package.json"), manifest).unwrap();
+        };
+        write(
+            ".",
+            r#"{"name": "root", "workspaces": ["packages/*"]}"#.to_string(),
+        );
+        write(
+            "packages/app",
+            format!(
+                r#"{{"name": "app", "dependencies": {{"ui": "*"}}, {}}}"#,
+                tools
+            ),
+        );
+        write("packages/core", format!(r#"{{"name": "core", {}}}"#, tools));
+        write("packages/docs", r#"{"name": "docs"}"#.to_string());
+        write(
+            "packages/ui",
+            format!(
+                r#"{{"name": "ui", "devDependencies": {{"core": "*"}}, {}}}"#,
+                tools
+            ),
+        );
+
+        temp_dir
+    }
+
+    fn name_of(path: &Path) -> String {
+        path.file_name().unwrap().to_string_lossy().to_string()
+    }
+
+    fn success() -> Result<RunResult> {
+        Ok(RunResult {
+            status: ExitStatus::default(),
+            cached: false,
+            outputs: Vec::new(),
+            steps: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_run_in_workspace_order() -> Result<()> {
+        let temp_dir = create_workspace();
+        let mut ran = Vec::new();
+
+        let summary = run_in_workspace(
+            "build",
+            &temp_dir.path().join("packages/app"),
+            &WorkspaceOptions::default(),
+            |dir| {
+                ran.push(name_of(dir));
+                success()
+            },
+        )?;
+
+        // Dependencies run first; `docs` has no build tool and is skipped
+        assert_eq!(ran, vec!["core", "ui", "app"]);
+        let statuses: Vec<(&str, &PackageStatus)> = summary
+            .outcomes
+            .iter()
+            .map(|outcome| (outcome.name.as_str(), &outcome.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("core", &PackageStatus::Ok),
+                ("docs", &PackageStatus::Skipped),
+                ("ui", &PackageStatus::Ok),
+                ("app", &PackageStatus::Ok),
+            ]
+        );
+        assert!(summary.ensure_success().is_ok());
+        assert!(summary
+            .table()
+            .starts_with("Package  Status   Duration\ncore     ok       "));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_in_workspace_failures() -> Result<()> {
+        let temp_dir = create_workspace();
+        let fail_core = |dir: &Path| {
+            if name_of(dir) == "core" {
+                Err(Error::Config {
+                    message: "broken".to_string(),
+                    hint: None,
+                })
+            } else {
+                success()
+            }
+        };
+
+        // A failure does not stop the other packages
+        let summary = run_in_workspace(
+            "build",
+            temp_dir.path(),
+            &WorkspaceOptions::default(),
+            fail_core,
+        )?;
+        assert_eq!(summary.failed(), vec!["core"]);
+        assert_eq!(summary.outcomes.len(), 4);
+        assert!(matches!(
+            summary.ensure_success(),
+            Err(Error::Workspace { .. })
+        ));
+
+        let options = WorkspaceOptions {
+            fail_fast: true,
+            ..Default::default()
+        };
+        let summary = run_in_workspace("build", temp_dir.path(), &options, fail_core)?;
+        assert_eq!(summary.outcomes.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_in_workspace_package_filter() -> Result<()> {
+        let temp_dir = create_workspace();
+        let options = WorkspaceOptions {
+            packages: vec!["app".to_string(), "core".to_string()],
+            fail_fast: false,
+        };
+
+        let mut ran = Vec::new();
+        run_in_workspace("build", temp_dir.path(), &options, |dir| {
+            ran.push(name_of(dir));
+            success()
+        })?;
+        assert_eq!(ran, vec!["core", "app"]);
+
+        let options = WorkspaceOptions {
+            packages: vec!["missing".to_string()],
+            fail_fast: false,
+        };
+        let result = run_in_workspace("build", temp_dir.path(), &options, |_| success());
+        assert!(matches!(result, Err(Error::Config { .. })));
+
+        Ok(())
+    }
+}
@@ -1,11 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
 
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
 use crate::error::Error;
-use crate::{utils, Result};
+use crate::package_manager::PackageManager;
+use crate::template::ProjectType;
+use crate::{progress, suggest, utils, Result};
 
 #[derive(Debug, Clone)]
 pub struct ProjectConfig {
@@ -13,57 +18,84 @@ pub struct ProjectConfig {
     pub name: String,
     /// Project root path
     pub path: PathBuf,
-    /// Whether this is a binary project
-    pub is_binary: bool,
+    /// Type of the project
+    pub project_type: ProjectType,
     /// Version control configuration
     pub vcs_config: Option<utils::VcsConfig>,
 }
 
 pub fn validate_init_config(
     path: &Path,
-    lib: bool,
+    name: Option<&str>,
+    project_type: ProjectType,
     vcs_config: Option<utils::VcsConfig>,
+    force: bool,
 ) -> Result<ProjectConfig> {
-    // Check if package.json already exists
-    if path.join("package.json").exists() {
-        return Err(Error::PackageJsonExists);
+    // Check if package.json already exists, unless re-initializing
+    if path.join("package.json").exists() && !force {
+        return Err(Error::PackageJsonExists {
+            path: path.to_path_buf(),
+        });
     }
 
-    // Extract and validate package name
-    let package_name = utils::extract_package_name(path)?;
-    utils::validate_package_name(&package_name)?;
+    let package_name = utils::resolve_package_name(path, name)?;
 
     Ok(ProjectConfig {
         name: package_name,
         path: path.to_path_buf(),
-        is_binary: !lib,
+        project_type,
         vcs_config,
     })
 }
 
 pub fn validate_project_config(
     path: &Path,
-    lib: bool,
+    name: Option<&str>,
+    project_type: ProjectType,
     vcs_config: Option<utils::VcsConfig>,
 ) -> Result<ProjectConfig> {
-    // Extract and validate package name
-    let package_name = utils::extract_package_name(path)?;
-    utils::validate_package_name(&package_name)?;
+    let package_name = utils::resolve_package_name(path, name)?;
 
     Ok(ProjectConfig {
         name: package_name,
         path: path.to_path_buf(),
-        is_binary: !lib,
+        project_type,
         vcs_config,
     })
 }
 
+/// One command of a tool that runs several commands in sequence
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandStep {
+    /// Command to run
+    pub command: String,
+
+    /// Arguments to pass to the command
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Environment variables to set, in addition to the tool's own
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    /// Whether the following steps still run if this one fails (optional)
+    #[serde(default)]
+    pub continue_on_error: bool,
+}
+
 /// Configuration for a tool
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ToolConfig {
     /// Command to run
+    /// Empty when the tool runs `steps` instead
+    #[serde(default)]
     pub command: String,
 
+    /// Commands to run in sequence instead of `command` (optional)
+    /// All steps share the tool's inputs, outputs and cache entry
+    #[serde(default)]
+    pub steps: Option<Vec<CommandStep>>,
+
     /// Arguments to pass to the command
     #[serde(default)]
     pub args: Vec<String>,
@@ -80,21 +112,395 @@ pub struct ToolConfig {
     #[serde(default)]
     pub inputs: Vec<String>,
 
+    /// Patterns for files excluded from the inputs (optional)
+    #[serde(default)]
+    pub exclude: Vec<String>,
+
     /// Output file patterns (optional)
     /// Only required for commands that generate files (e.g., build)
     #[serde(default)]
     pub outputs: Vec<String>,
+
+    /// Whether the outputs may be missing after a successful run (optional)
+    /// For tools that only write files under some conditions
+    #[serde(default)]
+    pub outputs_optional: bool,
+
+    /// Seconds after which a cached run expires (optional)
+    #[serde(default)]
+    pub max_age_seconds: Option<u64>,
+
+    /// Seconds after which a running command is stopped (optional)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+impl ToolConfig {
+    /// Commands the tool runs, in order
+    ///
+    /// A tool without `steps` runs its `command` as a single step. Every
+    /// step gets the tool's `env`, overridden by its own.
+    pub fn commands(&self) -> Vec<CommandStep> {
+        let Some(steps) = &self.steps else {
+            return vec![CommandStep {
+                command: self.command.clone(),
+                args: self.args.clone(),
+                env: self.env.clone(),
+                continue_on_error: false,
+            }];
+        };
+        steps
+            .iter()
+            .map(|step| {
+                let mut env = self.env.clone();
+                env.extend(step.env.clone());
+                CommandStep {
+                    env,
+                    ..step.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Default number of days after which cached tool runs expire
+pub const DEFAULT_CACHE_TTL_DAYS: u64 = 30;
+
+/// Configuration for the cache of tool runs
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Days after which a cached run expires
+    pub ttl_days: u64,
+
+    /// Maximum number of cached runs kept by `cargonode cache prune`
+    pub max_entries: Option<usize>,
+
+    /// Maximum total size in bytes of the cached runs kept by `cargonode cache prune`
+    pub max_bytes: Option<u64>,
+
+    /// Variables of a tool's `env` left out of its cache key, for values
+    /// that change on every run such as CI build numbers
+    pub exclude_env: Vec<String>,
+
+    /// Size in bytes above which an input file is reported when it is
+    /// hashed; `null` or `0` reports none
+    pub large_input_bytes: Option<u64>,
+}
+
+/// Default size above which input files are reported when hashed
+pub const DEFAULT_LARGE_INPUT_BYTES: u64 = 10 * 1024 * 1024;
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_days: DEFAULT_CACHE_TTL_DAYS,
+            max_entries: None,
+            max_bytes: None,
+            exclude_env: Vec::new(),
+            large_input_bytes: Some(DEFAULT_LARGE_INPUT_BYTES),
+        }
+    }
+}
+
+impl CacheConfig {
+    /// How long a cached run stays valid
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_days.saturating_mul(24 * 60 * 60))
+    }
 }
 
 /// Configuration for cargonode
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct CargonodeConfig {
     /// Tool configurations
     #[serde(default)]
     pub tools: HashMap<String, ToolConfig>,
+
+    /// Cache settings
+    #[serde(default)]
+    pub cache: CacheConfig,
+
+    /// `cargonode upgrade` settings
+    #[serde(default)]
+    pub upgrade: UpgradeConfig,
+
+    /// Node.js versions the tools need, in the syntax of `engines.node`
+    #[serde(default)]
+    pub node: Option<String>,
+
+    /// Commands run before and after scaffolding and tools
+    #[serde(default)]
+    pub hooks: HooksConfig,
+}
+
+/// A command run before or after a cargonode operation
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct HookCommand {
+    /// Program to run
+    pub command: String,
+
+    /// Arguments passed to the program
+    pub args: Vec<String>,
+}
+
+/// Hooks of a project, under `hooks` in the configuration
+///
+/// Every other key names the event a hook runs on: `pre-new` and
+/// `post-new` around scaffolding, `pre-<tool>` and `post-<tool>` around a
+/// tool.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct HooksConfig {
+    /// Fail the operation when a `post-*` hook fails instead of warning
+    #[serde(default)]
+    pub fail_on_post_hook_error: bool,
+
+    /// Hooks by event
+    #[serde(flatten)]
+    pub events: BTreeMap<String, HookCommand>,
+}
+
+impl HooksConfig {
+    /// The hook that runs on `event`, e.g. `pre-build`
+    pub fn get(&self, event: &str) -> Option<&HookCommand> {
+        self.events.get(event)
+    }
+}
+
+/// Configuration for `cargonode upgrade`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct UpgradeConfig {
+    /// Packages that stay at their current range
+    pub ignore: Vec<String>,
+}
+
+/// Standalone project configuration files, in order of precedence
+pub const PROJECT_CONFIG_FILES: [&str; 2] = ["cargonode.toml", ".cargonode.toml"];
+
+/// Where new projects keep their tool configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConfigFile {
+    /// Under the `cargonode` key in package.json
+    PackageJson,
+    /// In a standalone `cargonode.toml`
+    Toml,
+}
+
+/// Tool configuration new projects start with when a config file is requested
+pub fn starter_tool_config() -> serde_json::Value {
+    serde_json::json!({
+        "tools": {
+            "test": {
+                "command": "node",
+                "args": ["--test"],
+                "inputs": ["src/**/*", "test/**/*"]
+            }
+        }
+    })
+}
+
+/// Serialize a cargonode configuration as the content of `cargonode.toml`
+///
+/// # Arguments
+///
+/// * `config` - The configuration, as found under the `cargonode` key
+///
+/// # Returns
+///
+/// * `Result<String>` - The TOML document
+pub fn tool_config_to_toml(config: &serde_json::Value) -> Result<String> {
+    toml::to_string_pretty(config).map_err(|e| Error::Config {
+        message: format!("Cannot write the configuration as TOML: {}", e),
+        hint: None,
+    })
+}
+
+static STRICT_CONFIG: OnceLock<bool> = OnceLock::new();
+
+/// Choose whether unknown configuration keys are errors for the rest of the
+/// process
+///
+/// Only the first call has an effect; unknown keys are warnings until then.
+pub fn set_strict_config(strict: bool) {
+    let _ = STRICT_CONFIG.set(strict);
+}
+
+/// Whether unknown configuration keys are errors
+pub fn strict_config() -> bool {
+    STRICT_CONFIG.get().copied().unwrap_or_default()
+}
+
+/// Field names of a configuration section, taken from its serialized default
+fn known_fields<T: Default + Serialize>() -> Vec<String> {
+    match serde_json::to_value(T::default()) {
+        Ok(serde_json::Value::Object(fields)) => fields.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The known field closest to `key`, if it is close enough to be a typo
+fn did_you_mean<'a>(key: &str, known: &'a [String]) -> Option<&'a str> {
+    suggest::closest_match(key, known.iter().map(String::as_str))
+}
+
+/// Describe an unknown key, suggesting the known field it may be a typo of
+fn describe_unknown(key: &str, known: &[String], location: &str) -> String {
+    match did_you_mean(key, known) {
+        Some(field) => format!(
+            "unknown key `{}` {}; did you mean `{}`?",
+            key, location, field
+        ),
+        None => format!("unknown key `{}` {}", key, location),
+    }
+}
+
+/// Describe every key of `section` that is not in `known`
+fn unknown_fields(section: &serde_json::Value, known: &[String], location: &str) -> Vec<String> {
+    let Some(fields) = section.as_object() else {
+        return Vec::new();
+    };
+    fields
+        .keys()
+        .filter(|key| !known.contains(key))
+        .map(|key| describe_unknown(key, known, location))
+        .collect()
+}
+
+/// Describe the keys of a `cargonode` configuration that no setting reads
+///
+/// Keys are checked in every tool, in every hook and in the `cache` and
+/// `upgrade` sections; hooks must be named `pre-<event>` or `post-<event>`.
+/// A top-level key holding a `command` is reported as a tool that belongs
+/// under `tools`.
+///
+/// # Arguments
+///
+/// * `config` - The configuration, as found under the `cargonode` key
+///
+/// # Returns
+///
+/// * `Vec<String>` - One description per unknown key
+pub fn unknown_keys(config: &serde_json::Value) -> Vec<String> {
+    let Some(sections) = config.as_object() else {
+        return Vec::new();
+    };
+    let known = known_fields::<CargonodeConfig>();
+    let mut unknown = Vec::new();
+    for (key, value) in sections {
+        if known.contains(key) {
+            continue;
+        }
+        if value.get("command").is_some() {
+            unknown.push(format!(
+                "unknown key `{}` in the configuration; tools belong under `tools.{}`",
+                key, key
+            ));
+        } else {
+            unknown.push(describe_unknown(key, &known, "in the configuration"));
+        }
+    }
+
+    let tool_fields = known_fields::<ToolConfig>();
+    if let Some(tools) = config["tools"].as_object() {
+        let mut tools: Vec<_> = tools.iter().collect();
+        tools.sort_by_key(|(name, _)| name.as_str());
+        for (name, tool) in tools {
+            unknown.extend(unknown_fields(
+                tool,
+                &tool_fields,
+                &format!("in tool `{}`", name),
+            ));
+        }
+    }
+    unknown.extend(unknown_fields(
+        &config["cache"],
+        &known_fields::<CacheConfig>(),
+        "in `cache`",
+    ));
+    unknown.extend(unknown_fields(
+        &config["upgrade"],
+        &known_fields::<UpgradeConfig>(),
+        "in `upgrade`",
+    ));
+    if let Some(hooks) = config["hooks"].as_object() {
+        let known = known_fields::<HooksConfig>();
+        let hook_fields = known_fields::<HookCommand>();
+        for (event, hook) in hooks {
+            if known.contains(event) {
+                continue;
+            }
+            if !event.starts_with("pre-") && !event.starts_with("post-") {
+                unknown.push(describe_unknown(event, &known, "in `hooks`"));
+                continue;
+            }
+            unknown.extend(unknown_fields(
+                hook,
+                &hook_fields,
+                &format!("in hook `{}`", event),
+            ));
+        }
+    }
+    unknown
+}
+
+/// Warn about the unknown keys of a configuration, or fail when `strict`
+///
+/// # Arguments
+///
+/// * `config` - The configuration, as found under the `cargonode` key
+/// * `source` - File the configuration was read from
+/// * `strict` - Whether unknown keys are an error
+///
+/// # Returns
+///
+/// * `Result<()>` - `Error::Config` listing the unknown keys under `strict`
+pub fn check_unknown_keys(config: &serde_json::Value, source: &Path, strict: bool) -> Result<()> {
+    let unknown = unknown_keys(config);
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    if strict {
+        return Err(Error::Config {
+            message: format!(
+                "Unknown keys in {} (--strict-config):\n{}",
+                source.display(),
+                unknown.join("\n")
+            ),
+            hint: None,
+        });
+    }
+    for message in unknown {
+        progress::warn(&format!("{} in {}", message, source.display()))?;
+    }
+    Ok(())
+}
+
+/// Parse a standalone configuration file, warning about unknown keys if `warn`
+fn load_toml_config(path: &Path, warn: bool) -> Result<CargonodeConfig> {
+    let content = fs::read_to_string(path)?;
+    let invalid = |e: toml::de::Error| Error::Config {
+        message: format!("Invalid {}\n{}", path.display(), e.to_string().trim_end()),
+        hint: None,
+    };
+    let config = toml::from_str(&content).map_err(invalid)?;
+    if warn {
+        check_unknown_keys(
+            &toml::from_str(&content).map_err(invalid)?,
+            path,
+            strict_config(),
+        )?;
+    }
+    Ok(config)
 }
 
-/// Load the cargonode configuration from package.json
+/// Load the cargonode configuration of a project
+///
+/// A `cargonode.toml` or `.cargonode.toml` next to package.json takes
+/// precedence over the `cargonode` key in package.json; a warning names the
+/// configuration that is ignored when there is more than one, once per
+/// process.
 ///
 /// # Arguments
 ///
@@ -104,12 +510,39 @@ pub struct CargonodeConfig {
 ///
 /// * `Result<CargonodeConfig>` - The loaded configuration
 pub fn load_config(project_dir: &Path) -> Result<CargonodeConfig> {
+    read_config(project_dir, true)
+}
+
+/// Names of the tools configured for a project, sorted
+///
+/// Nothing is printed, so the names can be used for shell completion; a
+/// project without a readable configuration has no tools.
+///
+/// # Arguments
+///
+/// * `project_dir` - Path to the project directory
+///
+/// # Returns
+///
+/// * `Vec<String>` - The tool names
+pub fn tool_names(project_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = read_config(project_dir, false)
+        .map(|config| config.tools.into_keys().collect())
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// Load the cargonode configuration of a project, reporting ignored files
+/// and unknown keys if `warn`
+pub(crate) fn read_config(project_dir: &Path, warn: bool) -> Result<CargonodeConfig> {
     let package_json_path = project_dir.join("package.json");
 
     // Check if package.json exists
     if !package_json_path.exists() {
         return Err(Error::Config {
             message: format!("package.json not found in {}", project_dir.display()),
+            hint: Some(suggest::missing_manifest_hint()),
         });
     }
 
@@ -117,29 +550,910 @@ pub fn load_config(project_dir: &Path) -> Result<CargonodeConfig> {
     if !project_dir.is_dir() {
         return Err(Error::Config {
             message: format!("{} is not a directory", project_dir.display()),
+            hint: None,
         });
     }
 
     // Read package.json
-    let package_json_content = fs::read_to_string(package_json_path)?;
+    let package_json_content = fs::read_to_string(&package_json_path)?;
 
     // Parse package.json
     let package_json: serde_json::Value = serde_json::from_str(&package_json_content)?;
 
+    let standalone: Vec<PathBuf> = PROJECT_CONFIG_FILES
+        .iter()
+        .map(|name| project_dir.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+    if let Some((path, ignored)) = standalone.split_first() {
+        let ignored = ignored
+            .iter()
+            .map(|ignored| ignored.display().to_string())
+            .chain(
+                package_json
+                    .get("cargonode")
+                    .map(|_| "the `cargonode` key in package.json".to_string()),
+            );
+        for ignored in ignored.filter(|_| warn) {
+            progress::warn_once(&format!(
+                "ignoring {}; {} takes precedence",
+                ignored,
+                path.display()
+            ))?;
+        }
+        if warn {
+            progress::debug(&format!(
+                "reading the configuration from {}",
+                path.display()
+            ))?;
+        }
+        return load_toml_config(path, warn);
+    }
+
     // Extract cargonode configuration
     let config = if let Some(cargonode_config) = package_json.get("cargonode") {
         // Parse cargonode configuration
+        if warn {
+            check_unknown_keys(cargonode_config, &package_json_path, strict_config())?;
+            progress::debug(&format!(
+                "reading the configuration from {}",
+                package_json_path.display()
+            ))?;
+        }
         serde_json::from_value(cargonode_config.clone())?
     } else {
         // No cargonode configuration found, use default
-        CargonodeConfig {
-            tools: HashMap::new(),
-        }
+        CargonodeConfig::default()
     };
 
     Ok(config)
 }
 
+/// Name of the user configuration file
+pub const USER_CONFIG_FILE: &str = "config.toml";
+
+/// Keys accepted in the user configuration
+pub const USER_CONFIG_KEYS: [&str; 6] = [
+    "type",
+    "vcs",
+    "package-manager",
+    "author",
+    "license",
+    "template",
+];
+
+/// Prefix of user configuration keys naming a template, the entries of the
+/// `[templates]` table
+pub const USER_TEMPLATE_PREFIX: &str = "templates.";
+
+/// Prefix of user configuration keys naming a hook event, the entries of the
+/// `[hooks]` table
+pub const USER_HOOK_PREFIX: &str = "hooks.";
+
+/// Hook events the user configuration can set, the ones that run around
+/// scaffolding
+pub const USER_HOOK_EVENTS: [&str; 4] = ["pre-new", "post-new", "pre-init", "post-init"];
+
+/// User-level defaults for `cargonode new` and `cargonode init`
+///
+/// Read from a flat list of `key = "value"` pairs, a subset of TOML, followed
+/// by optional `[templates]` and `[hooks]` tables of `name = "path or URL"`
+/// and `event = "command line"` pairs. Flags passed on the command line take
+/// precedence over every value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserConfig {
+    path: PathBuf,
+    entries: BTreeMap<String, String>,
+}
+
+impl UserConfig {
+    /// Parse the content of a user configuration file
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Content of the file
+    /// * `path` - Path of the file, used in error messages
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The configuration, or `Error::UserConfig` naming the
+    ///   first invalid line
+    pub fn parse(content: &str, path: &Path) -> Result<Self> {
+        let invalid = |line: usize, message: String| Error::UserConfig {
+            path: path.to_path_buf(),
+            message: format!("line {}: {}", line + 1, message),
+        };
+
+        let mut config = Self {
+            path: path.to_path_buf(),
+            ..Self::default()
+        };
+        let mut table = "";
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                let header = line.split('#').next().unwrap_or_default().trim();
+                table = match header {
+                    "[templates]" => USER_TEMPLATE_PREFIX,
+                    "[hooks]" => USER_HOOK_PREFIX,
+                    _ => {
+                        return Err(invalid(
+                            number,
+                            format!(
+                                "unknown table `{}`; only `[templates]` and `[hooks]` are allowed",
+                                header
+                            ),
+                        ))
+                    }
+                };
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(invalid(
+                    number,
+                    format!("expected `key = \"value\"`, found `{}`", line),
+                ));
+            };
+            let value = parse_toml_string(value.trim()).ok_or_else(|| {
+                invalid(number, format!("`{}` must be a quoted string", key.trim()))
+            })?;
+            let key = format!("{}{}", table, key.trim());
+            config.set(&key, &value).map_err(|e| match e {
+                Error::UserConfig { message, .. } | Error::Config { message, .. } => {
+                    invalid(number, message)
+                }
+                e => e,
+            })?;
+        }
+        Ok(config)
+    }
+
+    /// Get the value of `key`
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<&str>>` - The value if set, or `Error::UserConfig` for
+    ///   an unknown key
+    pub fn get(&self, key: &str) -> Result<Option<&str>> {
+        self.check_key(key)?;
+        Ok(self.entries.get(key).map(String::as_str))
+    }
+
+    /// Set `key` to `value` after checking that both are valid
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        self.check_key(key)?;
+        let valid = match key {
+            "type" => ProjectType::from_str(value, false).is_ok(),
+            "vcs" => utils::Vcs::from_str(value, true).is_ok(),
+            "package-manager" => PackageManager::from_str(value, true).is_ok(),
+            _ => !value.trim().is_empty(),
+        };
+        if !valid {
+            let expected = match key {
+                "type" => "`binary`, `library` or `cli`".to_string(),
+                "vcs" => possible_values::<utils::Vcs>(),
+                "package-manager" => possible_values::<PackageManager>(),
+                _ => "a non-empty value".to_string(),
+            };
+            return Err(Error::Config {
+                message: format!(
+                    "Invalid value '{}' for `{}`; expected {}",
+                    value, key, expected
+                ),
+                hint: None,
+            });
+        }
+
+        self.entries.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    /// Path of the file the configuration was loaded from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn check_key(&self, key: &str) -> Result<()> {
+        if USER_CONFIG_KEYS.contains(&key) {
+            return Ok(());
+        }
+        let message = match (
+            key.strip_prefix(USER_TEMPLATE_PREFIX),
+            key.strip_prefix(USER_HOOK_PREFIX),
+        ) {
+            (Some(name), _) if is_template_name(name) => return Ok(()),
+            (Some(name), _) => format!(
+                "Invalid template name '{}'; use letters, digits, `-` and `_`",
+                name
+            ),
+            (_, Some(event)) if USER_HOOK_EVENTS.contains(&event) => return Ok(()),
+            (_, Some(event)) => format!(
+                "Invalid hook event '{}'; the user configuration can set {}",
+                event,
+                USER_HOOK_EVENTS.join(", ")
+            ),
+            (None, None) => format!(
+                "Unknown config key '{}'; known keys are: {}, {}<name>, {}<event>",
+                key,
+                USER_CONFIG_KEYS.join(", "),
+                USER_TEMPLATE_PREFIX,
+                USER_HOOK_PREFIX
+            ),
+        };
+        Err(Error::UserConfig {
+            path: self.path.clone(),
+            message,
+        })
+    }
+
+    /// Serialize the configuration as TOML, keys in their documented order,
+    /// then named templates and hooks in trailing `[templates]` and `[hooks]`
+    /// tables
+    pub fn to_toml(&self) -> String {
+        let mut toml: String = USER_CONFIG_KEYS
+            .iter()
+            .filter_map(|key| Some((key, self.entries.get(*key)?)))
+            .map(|(key, value)| format!("{} = {}\n", key, toml_string(value)))
+            .collect();
+        for (header, prefix) in [
+            ("[templates]", USER_TEMPLATE_PREFIX),
+            ("[hooks]", USER_HOOK_PREFIX),
+        ] {
+            let entries: String = self
+                .entries
+                .iter()
+                .filter_map(|(key, value)| Some((key.strip_prefix(prefix)?, value)))
+                .map(|(name, value)| format!("{} = {}\n", name, toml_string(value)))
+                .collect();
+            if entries.is_empty() {
+                continue;
+            }
+            if !toml.is_empty() {
+                toml.push('\n');
+            }
+            toml.push_str(header);
+            toml.push('\n');
+            toml.push_str(&entries);
+        }
+        toml
+    }
+
+    /// Type of new projects, from `type`
+    pub fn project_type(&self) -> Option<ProjectType> {
+        ProjectType::from_str(self.entries.get("type")?, false).ok()
+    }
+
+    /// Version control for new projects, from `vcs`
+    pub fn vcs(&self) -> Option<utils::Vcs> {
+        utils::Vcs::from_str(self.entries.get("vcs")?, true).ok()
+    }
+
+    /// Package manager for new projects, from `package-manager`
+    pub fn package_manager(&self) -> Option<PackageManager> {
+        PackageManager::from_str(self.entries.get("package-manager")?, true).ok()
+    }
+
+    /// Author of new projects, from `author`
+    pub fn author(&self) -> Option<&str> {
+        self.entries.get("author").map(String::as_str)
+    }
+
+    /// License of new projects, from `license`
+    pub fn license(&self) -> Option<&str> {
+        self.entries.get("license").map(String::as_str)
+    }
+
+    /// Template new projects are scaffolded from, from `template`
+    pub fn template(&self) -> Option<PathBuf> {
+        self.entries.get("template").map(PathBuf::from)
+    }
+
+    /// Templates registered by name, with their sources, from `[templates]`
+    pub fn templates(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().filter_map(|(key, source)| {
+            Some((key.strip_prefix(USER_TEMPLATE_PREFIX)?, source.as_str()))
+        })
+    }
+
+    /// Scaffolding hooks, from `[hooks]`
+    ///
+    /// Each command line is split on whitespace into the program and its
+    /// arguments.
+    pub fn hooks(&self) -> HooksConfig {
+        let events = self
+            .entries
+            .iter()
+            .filter_map(|(key, command_line)| {
+                let event = key.strip_prefix(USER_HOOK_PREFIX)?;
+                let mut words = command_line.split_whitespace().map(str::to_string);
+                let hook = HookCommand {
+                    command: words.next()?,
+                    args: words.collect(),
+                };
+                Some((event.to_string(), hook))
+            })
+            .collect();
+        HooksConfig {
+            events,
+            ..HooksConfig::default()
+        }
+    }
+
+    /// Source of the template registered as `name`
+    pub fn template_source(&self, name: &str) -> Option<&str> {
+        self.entries
+            .get(&format!("{}{}", USER_TEMPLATE_PREFIX, name))
+            .map(String::as_str)
+    }
+
+    /// Resolve a `--template` value that may name a registered template
+    ///
+    /// A registered name stands for its source unless a file or directory of
+    /// that name exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - Template name, path or URL
+    ///
+    /// # Returns
+    ///
+    /// * `PathBuf` - The source of the template
+    pub fn resolve_template_name(&self, template: &Path) -> PathBuf {
+        match template
+            .to_str()
+            .and_then(|name| self.template_source(name))
+        {
+            Some(source) if !template.exists() => PathBuf::from(source),
+            _ => template.to_path_buf(),
+        }
+    }
+}
+
+/// Whether `name` can name a template in the user configuration: a TOML bare
+/// key
+///
+/// Registering the built-in template's name replaces the built-in template.
+fn is_template_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Possible values of a command line enum, for error messages
+fn possible_values<T: ValueEnum>() -> String {
+    T::value_variants()
+        .iter()
+        .filter_map(|variant| variant.to_possible_value())
+        .map(|value| format!("`{}`", value.get_name()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parse a TOML basic (`"..."`) or literal (`'...'`) string, allowing a
+/// trailing comment
+fn parse_toml_string(value: &str) -> Option<String> {
+    let mut chars = value.chars();
+    let quote = chars.next().filter(|c| *c == '"' || *c == '\'')?;
+    let mut parsed = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c == quote => {
+                let rest = chars.as_str().trim();
+                return (rest.is_empty() || rest.starts_with('#')).then_some(parsed);
+            }
+            '\\' if quote == '"' => parsed.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                c @ ('"' | '\\') => c,
+                _ => return None,
+            }),
+            c => parsed.push(c),
+        }
+    }
+    None
+}
+
+/// Quote `value` as a TOML basic string
+fn toml_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+/// Path of the user configuration file
+///
+/// # Returns
+///
+/// * `Result<PathBuf>` - The path, or `Error::Config` when there is no
+///   user configuration directory
+pub fn user_config_path() -> Result<PathBuf> {
+    Ok(utils::user_config_dir()?.join(USER_CONFIG_FILE))
+}
+
+/// Load the user configuration
+///
+/// A missing file is an empty configuration.
+///
+/// # Arguments
+///
+/// * `path` - Path of the configuration file, usually `user_config_path()?`
+///
+/// # Returns
+///
+/// * `Result<UserConfig>` - The configuration
+pub fn load_user_config(path: &Path) -> Result<UserConfig> {
+    match fs::read_to_string(path) {
+        Ok(content) => UserConfig::parse(&content, path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => UserConfig::parse("", path),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Write the user configuration back to the file it was loaded from,
+/// creating its directory
+pub fn save_user_config(config: &UserConfig) -> Result<()> {
+    if let Some(parent) = config.path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&config.path, config.to_toml())?;
+    Ok(())
+}
+
+/// Parse a tsconfig.json, which may contain comments and trailing commas
+pub fn parse_tsconfig(content: &str) -> Option<serde_json::Value> {
+    // Comments go first, as one may sit between a trailing comma and its bracket
+    let mut uncommented = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            (c, _) if in_string => {
+                uncommented.push(c);
+                match c {
+                    '\\' => uncommented.extend(chars.next()),
+                    '"' => in_string = false,
+                    _ => {}
+                }
+            }
+            ('/', Some('/')) => while chars.next_if(|&c| c != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            _ => {
+                in_string = c == '"';
+                uncommented.push(c);
+            }
+        }
+    }
+
+    let mut json = String::with_capacity(uncommented.len());
+    let mut chars = uncommented.chars();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_string => json.extend([c].into_iter().chain(chars.next())),
+            '"' => {
+                in_string = !in_string;
+                json.push(c);
+            }
+            ',' if !in_string => {
+                let next = chars.clone().find(|c| !c.is_whitespace());
+                if !matches!(next, Some('}' | ']')) {
+                    json.push(c);
+                }
+            }
+            c => json.push(c),
+        }
+    }
+    serde_json::from_str(&json).ok()
+}
+
+/// Tool configuration cargonode falls back to when package.json has none
+#[derive(Debug, Clone)]
+pub struct DefaultTool {
+    /// The detected configuration
+    pub config: ToolConfig,
+
+    /// Why this command was chosen, for `--verbose` output
+    pub reason: String,
+
+    /// Linter or formatter the configuration runs, for `check` and `fmt`
+    pub lint_tool: Option<LintTool>,
+
+    /// Other linters or formatters the project is set up for, which were
+    /// not chosen
+    pub alternatives: Vec<LintTool>,
+}
+
+/// Linters and formatters the `check` and `fmt` tools run without
+/// configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LintTool {
+    /// Biome, which both lints and formats
+    Biome,
+    /// ESLint, for `check`
+    Eslint,
+    /// Prettier, for `fmt`
+    Prettier,
+}
+
+impl LintTool {
+    /// Name of the tool's package and executable
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintTool::Biome => "biome",
+            LintTool::Eslint => "eslint",
+            LintTool::Prettier => "prettier",
+        }
+    }
+
+    /// Tools that can run as the cargonode tool `tool_name`, in order of
+    /// preference
+    pub fn candidates(tool_name: &str) -> &'static [LintTool] {
+        match tool_name {
+            "check" => &[LintTool::Biome, LintTool::Eslint],
+            "fmt" => &[LintTool::Biome, LintTool::Prettier],
+            _ => &[],
+        }
+    }
+
+    /// Whether `file`, at the project root, configures the tool
+    fn is_config_file(&self, file: &str) -> bool {
+        let (rc, config) = match self {
+            LintTool::Biome => return ["biome.json", "biome.jsonc"].contains(&file),
+            LintTool::Eslint => (".eslintrc", "eslint.config."),
+            LintTool::Prettier => (".prettierrc", "prettier.config."),
+        };
+        file.strip_prefix(rc)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+            || file.starts_with(config)
+    }
+
+    /// Arguments running the tool as `tool_name`
+    ///
+    /// # Arguments
+    ///
+    /// * `tool_name` - `check` or `fmt`
+    /// * `write` - Fix or rewrite files instead of only reporting problems
+    fn args(&self, tool_name: &str, write: bool) -> Vec<String> {
+        let args: &[&str] = match (self, tool_name, write) {
+            (LintTool::Biome, "check", false) => &["check"],
+            (LintTool::Biome, "check", true) => &["check", "--write"],
+            // Without --write, biome reports unformatted files and fails
+            (LintTool::Biome, _, false) => &["format"],
+            (LintTool::Biome, _, true) => &["format", "--write"],
+            (LintTool::Eslint, _, false) => &["."],
+            (LintTool::Eslint, _, true) => &[".", "--fix"],
+            (LintTool::Prettier, _, false) => &["--check"],
+            (LintTool::Prettier, _, true) => &["--write"],
+        };
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    /// Configuration running the tool as `tool_name`
+    ///
+    /// # Arguments
+    ///
+    /// * `tool_name` - `check` or `fmt`
+    /// * `project_dir` - Path to the project directory
+    /// * `write` - Fix or rewrite files instead of only reporting problems
+    pub fn tool_config(&self, tool_name: &str, project_dir: &Path, write: bool) -> ToolConfig {
+        let command = utils::find_local_bin(project_dir, self.name()).map_or_else(
+            || self.name().to_string(),
+            |path| path.to_string_lossy().to_string(),
+        );
+        // Every tool runs on the whole project, and its config file decides
+        // what it reports
+        let mut inputs = vec!["**/*".to_string()];
+        inputs.extend(
+            ProjectSnapshot::read(project_dir)
+                .config_file(*self)
+                .map(str::to_string),
+        );
+        ToolConfig {
+            command,
+            args: self.args(tool_name, write),
+            inputs,
+            ..Default::default()
+        }
+    }
+}
+
+/// What linter and formatter detection looks at in a project directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectSnapshot {
+    /// Names of the files at the project root
+    pub files: BTreeSet<String>,
+
+    /// Packages package.json depends on, in any dependency group
+    pub dependencies: BTreeSet<String>,
+
+    /// Executables installed in `node_modules/.bin`
+    pub bins: BTreeSet<String>,
+}
+
+impl ProjectSnapshot {
+    /// Take a snapshot of `project_dir`
+    ///
+    /// Unreadable files and directories are left out rather than reported.
+    pub fn read(project_dir: &Path) -> Self {
+        let mut snapshot = Self::default();
+        if let Ok(entries) = fs::read_dir(project_dir) {
+            snapshot.files = entries
+                .filter_map(|entry| Some(entry.ok()?.file_name().to_str()?.to_string()))
+                .collect();
+        }
+        if let Some(manifest) = fs::read_to_string(project_dir.join("package.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        {
+            for group in ["dependencies", "devDependencies"] {
+                if let Some(dependencies) = manifest[group].as_object() {
+                    snapshot.dependencies.extend(dependencies.keys().cloned());
+                }
+            }
+        }
+        // Only the nearest node_modules/.bin with any of the tools counts, so
+        // a project's own install wins over a workspace root's
+        let tools = [LintTool::Biome, LintTool::Eslint, LintTool::Prettier].map(|tool| tool.name());
+        snapshot.bins = utils::find_nearest_local_bins(project_dir, &tools)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        snapshot
+    }
+
+    /// The root file configuring `tool`, if any
+    fn config_file(&self, tool: LintTool) -> Option<&str> {
+        self.files
+            .iter()
+            .map(String::as_str)
+            .find(|file| tool.is_config_file(file))
+    }
+
+    /// Whether `tool` is a dependency or installed
+    fn is_installed(&self, tool: LintTool) -> bool {
+        let package = match tool {
+            LintTool::Biome => "@biomejs/biome",
+            tool => tool.name(),
+        };
+        self.dependencies.contains(package) || self.bins.contains(tool.name())
+    }
+}
+
+/// A linter or formatter detected for a tool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintChoice {
+    /// The chosen tool
+    pub tool: LintTool,
+
+    /// Why it was chosen
+    pub reason: String,
+
+    /// Other tools the project is set up for
+    pub alternatives: Vec<LintTool>,
+}
+
+/// Choose the linter or formatter to run as `tool_name` in a project
+///
+/// A tool with a config file at the project root wins over one that is only
+/// a dependency or installed; ties go to the first of
+/// [`LintTool::candidates`].
+///
+/// # Arguments
+///
+/// * `tool_name` - `check` or `fmt`
+/// * `snapshot` - The project directory
+///
+/// # Returns
+///
+/// * `Option<LintChoice>` - The chosen tool, or `None` if the project is not
+///   set up for any
+pub fn detect_lint_tool(tool_name: &str, snapshot: &ProjectSnapshot) -> Option<LintChoice> {
+    let candidates = LintTool::candidates(tool_name);
+    let configured = candidates
+        .iter()
+        .find_map(|tool| Some((*tool, snapshot.config_file(*tool)?)));
+    let (tool, reason) = match configured {
+        Some((tool, file)) => (tool, format!("{} found", file)),
+        None => {
+            let tool = *candidates
+                .iter()
+                .find(|tool| snapshot.is_installed(**tool))?;
+            (tool, format!("{} is installed", tool.name()))
+        }
+    };
+
+    let alternatives = candidates
+        .iter()
+        .filter(|other| **other != tool)
+        .filter(|other| snapshot.config_file(**other).is_some() || snapshot.is_installed(**other))
+        .copied()
+        .collect();
+    Some(LintChoice {
+        tool,
+        reason,
+        alternatives,
+    })
+}
+
+/// Detect a sensible configuration for a built-in tool
+///
+/// * `check` runs `biome check` or `eslint .`, see [`detect_lint_tool`];
+///   biome when the project is set up for neither
+/// * `fmt` runs `biome format --write` or `prettier --write`, when the
+///   project is set up for either
+/// * `build` runs `tsc` when a tsconfig.json exists and `tsup` otherwise,
+///   writing to `dist`
+/// * `test` runs `vitest run` when vitest is a dependency and `node --test`
+///   otherwise
+///
+/// Commands installed in `node_modules/.bin` are preferred over ones on PATH.
+///
+/// # Arguments
+///
+/// * `tool_name` - Name of the tool
+/// * `project_dir` - Path to the project directory
+/// * `preferred` - Linter or formatter to run instead of detecting one
+///   (`--tool`)
+///
+/// # Returns
+///
+/// * `Option<DefaultTool>` - The detected configuration, or `None` for tools
+///   without a default
+pub fn default_tool_config(
+    tool_name: &str,
+    project_dir: &Path,
+    preferred: Option<LintTool>,
+) -> Option<DefaultTool> {
+    let local = |name: &str| {
+        utils::find_local_bin(project_dir, name).map_or_else(
+            || name.to_string(),
+            |path| path.to_string_lossy().to_string(),
+        )
+    };
+    let inputs = vec!["src/**/*".to_string()];
+
+    let mut lint_tool = None;
+    let (config, reason, alternatives) = match tool_name {
+        "check" | "fmt" => {
+            let write = tool_name == "fmt";
+            let choice = match preferred {
+                Some(tool) => Some(LintChoice {
+                    tool,
+                    reason: "chosen with --tool".to_string(),
+                    alternatives: Vec::new(),
+                }),
+                None => detect_lint_tool(tool_name, &ProjectSnapshot::read(project_dir)),
+            };
+            let choice = match choice {
+                Some(choice) => choice,
+                None if tool_name == "check" => LintChoice {
+                    tool: LintTool::Biome,
+                    reason: "no linter detected; biome is the default checker".to_string(),
+                    alternatives: Vec::new(),
+                },
+                None => return None,
+            };
+            lint_tool = Some(choice.tool);
+            (
+                choice.tool.tool_config(tool_name, project_dir, write),
+                choice.reason,
+                choice.alternatives,
+            )
+        }
+        "build" => {
+            let outputs = vec!["dist/**/*".to_string()];
+            if project_dir.join("tsconfig.json").is_file() {
+                // An outDir in tsconfig.json is left to tsc rather than overridden
+                let out_dir = fs::read_to_string(project_dir.join("tsconfig.json"))
+                    .ok()
+                    .and_then(|content| parse_tsconfig(&content))
+                    .and_then(|tsconfig| {
+                        let dir = tsconfig["compilerOptions"]["outDir"].as_str()?;
+                        Some(
+                            dir.trim_start_matches("./")
+                                .trim_end_matches('/')
+                                .to_string(),
+                        )
+                    });
+                let (args, outputs) = match out_dir {
+                    Some(dir) => (Vec::new(), vec![format!("{}/**/*", dir)]),
+                    None => (vec!["--outDir".to_string(), "dist".to_string()], outputs),
+                };
+                (
+                    ToolConfig {
+                        command: local("tsc"),
+                        args,
+                        inputs: [inputs, vec!["tsconfig.json".to_string()]].concat(),
+                        outputs,
+                        ..Default::default()
+                    },
+                    "tsconfig.json found".to_string(),
+                    Vec::new(),
+                )
+            } else {
+                (
+                    ToolConfig {
+                        command: local("tsup"),
+                        inputs,
+                        outputs,
+                        ..Default::default()
+                    },
+                    "no tsconfig.json found".to_string(),
+                    Vec::new(),
+                )
+            }
+        }
+        "test" => {
+            // Tests usually live next to the sources or in their own directory
+            let inputs = [
+                inputs,
+                vec!["test/**/*".to_string(), "tests/**/*".to_string()],
+            ]
+            .concat();
+            if has_dependency(project_dir, "vitest") {
+                (
+                    ToolConfig {
+                        command: local("vitest"),
+                        args: vec!["run".to_string()],
+                        inputs,
+                        ..Default::default()
+                    },
+                    "vitest is a dependency".to_string(),
+                    Vec::new(),
+                )
+            } else {
+                (
+                    ToolConfig {
+                        command: "node".to_string(),
+                        args: vec!["--test".to_string()],
+                        inputs,
+                        ..Default::default()
+                    },
+                    "vitest is not a dependency".to_string(),
+                    Vec::new(),
+                )
+            }
+        }
+        _ => return None,
+    };
+
+    Some(DefaultTool {
+        config,
+        reason,
+        lint_tool,
+        alternatives,
+    })
+}
+
+/// Whether package.json in `project_dir` depends on `name` in any dependency group
+fn has_dependency(project_dir: &Path, name: &str) -> bool {
+    let Ok(content) = fs::read_to_string(project_dir.join("package.json")) else {
+        return false;
+    };
+    let Ok(manifest) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return false;
+    };
+    ["dependencies", "devDependencies"]
+        .iter()
+        .any(|group| manifest[group].get(name).is_some())
+}
+
 /// Get a tool configuration by name
 ///
 /// # Arguments
@@ -154,6 +1468,29 @@ pub fn get_tool_config<'a>(config: &'a CargonodeConfig, tool_name: &str) -> Opti
     config.tools.get(tool_name)
 }
 
+/// Error for a tool that is not in the configuration
+///
+/// The hint names the configured tool `tool_name` is closest to, if it looks
+/// like a typo of one.
+///
+/// # Arguments
+///
+/// * `config` - The cargonode configuration
+/// * `tool_name` - Name of the missing tool
+///
+/// # Returns
+///
+/// * `Error` - `Error::Config` with a hint
+pub fn tool_not_found(config: &CargonodeConfig, tool_name: &str) -> Error {
+    Error::Config {
+        message: format!("Tool '{}' not found in configuration", tool_name),
+        hint: Some(suggest::unknown_tool_hint(
+            tool_name,
+            config.tools.keys().map(String::as_str),
+        )),
+    }
+}
+
 /// Validate a tool configuration
 ///
 /// # Arguments
@@ -165,17 +1502,59 @@ pub fn get_tool_config<'a>(config: &'a CargonodeConfig, tool_name: &str) -> Opti
 ///
 /// * `Result<()>` - Whether the configuration is valid
 pub fn validate_tool_config(tool_name: &str, config: &ToolConfig) -> Result<()> {
-    // Check if command is empty
-    if config.command.is_empty() {
-        return Err(Error::Config {
-            message: format!("Tool '{}' has an empty command", tool_name),
-        });
+    match &config.steps {
+        Some(_) if !config.command.is_empty() => {
+            return Err(Error::Config {
+                message: format!(
+                    "Tool '{}' sets both `command` and `steps`; use one or the other",
+                    tool_name
+                ),
+                hint: None,
+            });
+        }
+        // Arguments belong to the step they are passed to
+        Some(_) if !config.args.is_empty() => {
+            return Err(Error::Config {
+                message: format!(
+                    "Tool '{}' sets `args` next to `steps`; move them into a step",
+                    tool_name
+                ),
+                hint: None,
+            });
+        }
+        Some(steps) if steps.is_empty() => {
+            return Err(Error::Config {
+                message: format!("Tool '{}' has an empty steps array", tool_name),
+                hint: None,
+            });
+        }
+        Some(steps) => {
+            if let Some(index) = steps.iter().position(|step| step.command.is_empty()) {
+                return Err(Error::Config {
+                    message: format!(
+                        "Step {} of tool '{}' has an empty command",
+                        index + 1,
+                        tool_name
+                    ),
+                    hint: None,
+                });
+            }
+        }
+        // Check if command is empty
+        None if config.command.is_empty() => {
+            return Err(Error::Config {
+                message: format!("Tool '{}' has an empty command", tool_name),
+                hint: None,
+            });
+        }
+        None => {}
     }
 
     // Check if inputs is empty
     if config.inputs.is_empty() {
         return Err(Error::Config {
             message: format!("Tool '{}' has no input patterns", tool_name),
+            hint: None,
         });
     }
 
@@ -213,6 +1592,7 @@ mod tests {
                         "command": "npm",
                         "args": ["test"],
                         "inputs": ["src/**/*.js"],
+                        "exclude": ["src/generated/**"],
                         "outputs": ["coverage/**/*"]
                     }
                 }
@@ -233,11 +1613,162 @@ mod tests {
         assert_eq!(test_tool.command, "npm");
         assert_eq!(test_tool.args, vec!["test"]);
         assert_eq!(test_tool.inputs, vec!["src/**/*.js"]);
+        assert_eq!(test_tool.exclude, vec!["src/generated/**"]);
         assert_eq!(test_tool.outputs, vec!["coverage/**/*"]);
 
         Ok(())
     }
 
+    #[test]
+    fn test_load_config_from_toml() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        create_package_json(
+            dir_path,
+            r#"{"name": "app", "cargonode": {"tools": {"build": {"command": "tsc", "inputs": ["src/**"]}}}}"#,
+        )?;
+
+        // The hidden file is used on its own
+        fs::write(
+            dir_path.join(".cargonode.toml"),
+            "[tools.lint]\ncommand = \"eslint\"\ninputs = [\"src/**\"]\n",
+        )?;
+        assert!(load_config(dir_path)?.tools.contains_key("lint"));
+
+        // `cargonode.toml` wins over both others
+        fs::write(
+            dir_path.join("cargonode.toml"),
+            r#"
+[cache]
+ttl_days = 7
+
+[tools.test]
+command = "node"
+args = ["--test"]
+inputs = ["src/**/*", "test/**/*"]
+env = { NODE_ENV = "test" }
+"#,
+        )?;
+        let sink = std::sync::Arc::new(progress::RecordingSink::default());
+        let config = progress::with_sink(sink.clone(), || {
+            load_config(dir_path)?;
+            load_config(dir_path)
+        })?;
+        assert_eq!(config.tools.keys().collect::<Vec<_>>(), vec!["test"]);
+        assert_eq!(config.tools["test"].env["NODE_ENV"], "test");
+        assert_eq!(config.cache.ttl_days, 7);
+        // Each ignored configuration is warned about once per process
+        let warnings: Vec<_> = sink
+            .messages()
+            .into_iter()
+            .filter(|message| message.level == progress::Level::Warn)
+            .map(|message| message.text)
+            .collect();
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings[1].contains("the `cargonode` key in package.json"));
+
+        // Syntax errors point at the line
+        fs::write(
+            dir_path.join("cargonode.toml"),
+            "[tools.test]\ncommand = node\n",
+        )?;
+        let Err(Error::Config { message, .. }) = load_config(dir_path) else {
+            panic!("expected the invalid TOML to be rejected");
+        };
+        assert!(message.contains("cargonode.toml"));
+        assert!(message.contains("line 2"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let config = serde_json::json!({
+            "tools": {
+                "test": {"command": "node", "input": ["src/**"], "colour": true},
+                "build": {"comand": "tsc", "timeout_sec": 60}
+            },
+            "lint": {"command": "eslint"},
+            "cahce": {"ttl_days": 7},
+            "cache": {"max_entry": 10},
+            "upgrade": {"ignored": ["typescript"]},
+            "hooks": {
+                "pre-build": {"command": "node", "arg": ["gen.js"]},
+                "post-new": {"command": "catalog"},
+                "fail_on_post_hook_errors": true
+            }
+        });
+
+        assert_eq!(
+            unknown_keys(&config),
+            vec![
+                "unknown key `lint` in the configuration; tools belong under `tools.lint`",
+                "unknown key `cahce` in the configuration; did you mean `cache`?",
+                "unknown key `comand` in tool `build`; did you mean `command`?",
+                "unknown key `timeout_sec` in tool `build`; did you mean `timeout_secs`?",
+                "unknown key `input` in tool `test`; did you mean `inputs`?",
+                "unknown key `colour` in tool `test`",
+                "unknown key `max_entry` in `cache`; did you mean `max_entries`?",
+                "unknown key `ignored` in `upgrade`; did you mean `ignore`?",
+                "unknown key `arg` in hook `pre-build`; did you mean `args`?",
+                "unknown key `fail_on_post_hook_errors` in `hooks`; did you mean `fail_on_post_hook_error`?",
+            ]
+        );
+        assert!(unknown_keys(&starter_tool_config()).is_empty());
+    }
+
+    #[test]
+    fn test_check_unknown_keys() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        let source = dir_path.join("package.json");
+
+        let config = serde_json::json!({"tools": {"build": {"command": "tsc", "outputz": []}}});
+        assert!(check_unknown_keys(&config, &source, false).is_ok());
+        let Err(Error::Config { message, .. }) = check_unknown_keys(&config, &source, true) else {
+            panic!("expected unknown keys to fail under --strict-config");
+        };
+        assert!(message.ends_with("unknown key `outputz` in tool `build`; did you mean `outputs`?"));
+
+        // Unknown keys only warn when loading, from either config file
+        create_package_json(dir_path, r#"{"name": "app", "cargonode": {"tols": {}}}"#)?;
+        assert!(load_config(dir_path)?.tools.is_empty());
+        fs::write(
+            dir_path.join("cargonode.toml"),
+            "[tools.build]\ncommand = \"tsc\"\nargz = []\n",
+        )?;
+        assert_eq!(load_config(dir_path)?.tools["build"].command, "tsc");
+        Ok(())
+    }
+
+    #[test]
+    fn test_tool_names() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        assert!(tool_names(dir_path).is_empty());
+
+        create_package_json(
+            dir_path,
+            r#"{"cargonode": {"tools": {
+                "test": {"command": "node"},
+                "build": {"command": "tsc", "typo": true}
+            }}}"#,
+        )?;
+        assert_eq!(tool_names(dir_path), vec!["build", "test"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tool_config_to_toml() -> Result<()> {
+        let content = tool_config_to_toml(&starter_tool_config())?;
+        assert!(content.starts_with("[tools.test]\ncommand = \"node\"\n"));
+
+        let config: CargonodeConfig = toml::from_str(&content).unwrap();
+        assert_eq!(config.tools["test"].args, vec!["--test"]);
+        assert!(validate_tool_config("test", &config.tools["test"]).is_ok());
+        Ok(())
+    }
+
     #[test]
     fn test_get_tool_config() -> Result<()> {
         // Create a configuration
@@ -251,10 +1782,18 @@ mod tests {
                 working_dir: None,
                 inputs: vec!["src/**/*.js".to_string()],
                 outputs: vec!["coverage/**/*".to_string()],
+                exclude: vec![],
+                max_age_seconds: None,
+                timeout_secs: None,
+                outputs_optional: false,
+                steps: None,
             },
         );
 
-        let config = CargonodeConfig { tools };
+        let config = CargonodeConfig {
+            tools,
+            ..Default::default()
+        };
 
         // Get existing tool
         let test_tool = get_tool_config(&config, "test").unwrap();
@@ -264,9 +1803,24 @@ mod tests {
         let result = get_tool_config(&config, "build");
         assert!(result.is_none());
 
+        // A typo of a configured tool is pointed out
+        let err = tool_not_found(&config, "tset");
+        assert_eq!(err.suggestion().as_deref(), Some("Did you mean `test`?"));
+        assert!(err.to_string().contains("Tool 'tset' not found"));
+        let err = tool_not_found(&config, "deploy");
+        assert_eq!(err.suggestion().as_deref(), Some("Configured tools: test."));
+
         Ok(())
     }
 
+    #[test]
+    fn test_missing_package_json_hint() {
+        let temp_dir = TempDir::new().unwrap();
+        let err = load_config(temp_dir.path()).unwrap_err();
+        assert!(matches!(err, Error::Config { .. }));
+        assert!(err.suggestion().unwrap().contains("`cargonode init`"));
+    }
+
     #[test]
     fn test_validate_tool_config() -> Result<()> {
         // Valid configuration with outputs
@@ -277,6 +1831,11 @@ mod tests {
             working_dir: None,
             inputs: vec!["src/**/*.js".to_string()],
             outputs: vec!["coverage/**/*".to_string()],
+            exclude: vec![],
+            max_age_seconds: None,
+            timeout_secs: None,
+            outputs_optional: false,
+            steps: None,
         };
         assert!(validate_tool_config("test", &valid_config).is_ok());
 
@@ -288,6 +1847,11 @@ mod tests {
             working_dir: None,
             inputs: vec!["src/**/*.js".to_string()],
             outputs: vec![],
+            exclude: vec![],
+            max_age_seconds: None,
+            timeout_secs: None,
+            outputs_optional: false,
+            steps: None,
         };
         assert!(validate_tool_config("start", &valid_no_outputs).is_ok());
 
@@ -299,6 +1863,11 @@ mod tests {
             working_dir: None,
             inputs: vec!["src/**/*.js".to_string()],
             outputs: vec!["coverage/**/*".to_string()],
+            exclude: vec![],
+            max_age_seconds: None,
+            timeout_secs: None,
+            outputs_optional: false,
+            steps: None,
         };
         assert!(validate_tool_config("test", &invalid_command).is_err());
 
@@ -310,12 +1879,376 @@ mod tests {
             working_dir: None,
             inputs: vec![],
             outputs: vec!["coverage/**/*".to_string()],
+            exclude: vec![],
+            max_age_seconds: None,
+            timeout_secs: None,
+            outputs_optional: false,
+            steps: None,
         };
         assert!(validate_tool_config("test", &invalid_inputs).is_err());
 
         Ok(())
     }
 
+    #[test]
+    fn test_validate_tool_steps() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        create_package_json(
+            dir_path,
+            r#"{"cargonode": {"tools": {"ci": {
+                "env": {"CI": "1", "NODE_ENV": "test"},
+                "steps": [
+                    {"command": "eslint", "args": ["src"], "continue_on_error": true},
+                    {"command": "vitest", "env": {"NODE_ENV": "ci"}}
+                ],
+                "inputs": ["src/**"]
+            }}}}"#,
+        )?;
+        let config = load_config(dir_path)?;
+        let ci = &config.tools["ci"];
+        assert!(validate_tool_config("ci", ci).is_ok());
+
+        // Every step gets the tool's environment, overridden by its own
+        let commands = ci.commands();
+        assert_eq!(commands.len(), 2);
+        assert!(commands[0].continue_on_error);
+        assert_eq!(commands[0].env["NODE_ENV"], "test");
+        assert_eq!(commands[1].env["NODE_ENV"], "ci");
+        assert_eq!(commands[1].env["CI"], "1");
+
+        let message = |tool: &ToolConfig| validate_tool_config("ci", tool).unwrap_err().message();
+        let both = ToolConfig {
+            command: "npm".to_string(),
+            ..ci.clone()
+        };
+        assert!(message(&both).contains("sets both `command` and `steps`"));
+        let empty = ToolConfig {
+            steps: Some(Vec::new()),
+            ..ci.clone()
+        };
+        assert!(message(&empty).contains("has an empty steps array"));
+        let blank = ToolConfig {
+            steps: Some(vec![CommandStep::default()]),
+            ..ci.clone()
+        };
+        assert!(message(&blank).contains("Step 1 of tool 'ci' has an empty command"));
+        let args = ToolConfig {
+            args: vec!["--fix".to_string()],
+            ..ci.clone()
+        };
+        assert!(message(&args).contains("move them into a step"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_config() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("cargonode").join(USER_CONFIG_FILE);
+
+        // A missing file is an empty configuration
+        let mut config = load_user_config(&path)?;
+        assert_eq!(config.to_toml(), "");
+        assert_eq!(config.path(), path);
+
+        config.set("type", "library")?;
+        config.set("vcs", "hg")?;
+        config.set("package-manager", "pnpm")?;
+        config.set("author", "Jane \"JD\" Doe")?;
+        config.set("template", "~/templates/web")?;
+        save_user_config(&config)?;
+
+        let loaded = load_user_config(&path)?;
+        assert_eq!(loaded, config);
+        assert_eq!(loaded.project_type(), Some(ProjectType::Library));
+        assert_eq!(loaded.vcs(), Some(utils::Vcs::Hg));
+        assert_eq!(loaded.package_manager(), Some(PackageManager::Pnpm));
+        assert_eq!(loaded.author(), Some("Jane \"JD\" Doe"));
+        assert_eq!(loaded.get("license")?, None);
+        assert_eq!(
+            fs::read_to_string(&path)?,
+            "type = \"library\"\nvcs = \"hg\"\npackage-manager = \"pnpm\"\n\
+             author = \"Jane \\\"JD\\\" Doe\"\ntemplate = \"~/templates/web\"\n"
+        );
+
+        // Named templates live in a trailing [templates] table
+        let mut config = UserConfig::parse(
+            "template = \"web\"\n\n[templates] # by name\nweb = \"~/templates/web\"\napi = \"gh:acme/api-template\"\n",
+            &path,
+        )?;
+        assert_eq!(
+            config.templates().collect::<Vec<_>>(),
+            vec![("api", "gh:acme/api-template"), ("web", "~/templates/web")]
+        );
+        assert_eq!(config.get("templates.web")?, Some("~/templates/web"));
+        assert_eq!(
+            config.resolve_template_name(Path::new("api")),
+            PathBuf::from("gh:acme/api-template")
+        );
+        assert_eq!(
+            config.resolve_template_name(Path::new("./other")),
+            PathBuf::from("./other")
+        );
+        config.set("templates.cli", "/opt/templates/cli")?;
+        assert_eq!(
+            config.to_toml(),
+            "template = \"web\"\n\n[templates]\napi = \"gh:acme/api-template\"\n\
+             cli = \"/opt/templates/cli\"\nweb = \"~/templates/web\"\n"
+        );
+        assert_eq!(UserConfig::parse(&config.to_toml(), &path)?, config);
+
+        // Scaffolding hooks live in a [hooks] table
+        config.set("hooks.post-new", "catalog register --team web")?;
+        assert!(config.to_toml().ends_with(
+            "web = \"~/templates/web\"\n\n[hooks]\npost-new = \"catalog register --team web\"\n"
+        ));
+        let loaded = UserConfig::parse(&config.to_toml(), &path)?;
+        assert_eq!(loaded, config);
+        assert_eq!(
+            loaded.hooks().get("post-new"),
+            Some(&HookCommand {
+                command: "catalog".to_string(),
+                args: vec![
+                    "register".to_string(),
+                    "--team".to_string(),
+                    "web".to_string()
+                ],
+            })
+        );
+        assert_eq!(loaded.templates().count(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_config_errors() {
+        let path = Path::new("config.toml");
+        let mut config = UserConfig::default();
+
+        let Err(Error::UserConfig { message, .. }) = config.set("colour", "blue") else {
+            panic!("expected an unknown key error");
+        };
+        assert!(message.contains("known keys are: type, vcs, package-manager"));
+        assert!(config.get("colour").is_err());
+        assert!(config.set("vcs", "svn").is_err());
+        assert!(config.set("type", "app").is_err());
+        assert!(config.set("type", "cli").is_ok());
+
+        let config = UserConfig::parse(
+            "# defaults\nlicense = 'Apache-2.0' # for work\n\nvcs = \"jj\"\n",
+            path,
+        )
+        .unwrap();
+        assert_eq!(config.license(), Some("Apache-2.0"));
+        assert_eq!(config.vcs(), Some(utils::Vcs::Jujutsu));
+
+        for (content, expected) in [
+            ("lib = true", "line 1: `lib` must be a quoted string"),
+            ("vcs = \"git\"\n[new]", "line 2: unknown table `[new]`"),
+            (
+                "[templates]\n\"my web\" = \"./web\"",
+                "line 2: Invalid template name",
+            ),
+            ("\ncolour = \"blue\"", "line 2: Unknown config key 'colour'"),
+            (
+                "[hooks]\npre-build = \"make\"",
+                "line 2: Invalid hook event 'pre-build'",
+            ),
+            // Invalid values keep the file and line too
+            ("\n\nvcs = \"svn\"", "line 3: Invalid value 'svn' for `vcs`"),
+        ] {
+            let Err(Error::UserConfig {
+                path: error_path,
+                message,
+            }) = UserConfig::parse(content, path)
+            else {
+                panic!("expected {content:?} to be rejected");
+            };
+            assert_eq!(error_path, path);
+            assert!(message.starts_with(expected), "{message}");
+        }
+    }
+
+    #[test]
+    fn test_default_tool_config() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        create_package_json(dir, r#"{"name": "app"}"#)?;
+
+        let check = default_tool_config("check", dir, None).unwrap();
+        assert_eq!(check.config.command, "biome");
+        assert_eq!(check.config.args, vec!["check"]);
+        assert_eq!(check.config.inputs, vec!["**/*"]);
+        assert!(default_tool_config("fmt", dir, None).is_none());
+
+        let build = default_tool_config("build", dir, None).unwrap();
+        assert_eq!(build.config.command, "tsup");
+        assert_eq!(build.config.outputs, vec!["dist/**/*"]);
+        fs::write(dir.join("tsconfig.json"), "{}")?;
+        let build = default_tool_config("build", dir, None).unwrap();
+        assert_eq!(build.config.command, "tsc");
+        assert_eq!(build.config.args, vec!["--outDir", "dist"]);
+        assert_eq!(build.reason, "tsconfig.json found");
+
+        // An outDir set in tsconfig.json is not overridden
+        fs::write(
+            dir.join("tsconfig.json"),
+            r#"{"compilerOptions": {"outDir": "./lib/", /* emitted */}}"#,
+        )?;
+        let build = default_tool_config("build", dir, None).unwrap();
+        assert!(build.config.args.is_empty());
+        assert_eq!(build.config.outputs, vec!["lib/**/*"]);
+
+        let test = default_tool_config("test", dir, None).unwrap();
+        assert_eq!(test.config.command, "node");
+        assert_eq!(test.config.args, vec!["--test"]);
+
+        // Local binaries win over the ones on PATH
+        create_package_json(
+            dir,
+            r#"{"name": "app", "devDependencies": {"vitest": "^3.0.0"}}"#,
+        )?;
+        fs::create_dir_all(dir.join("node_modules/.bin"))?;
+        let vitest = dir.join("node_modules/.bin/vitest");
+        fs::write(&vitest, "")?;
+        let test = default_tool_config("test", dir, None).unwrap();
+        assert_eq!(test.config.command, vitest.to_string_lossy());
+        assert_eq!(test.config.args, vec!["run"]);
+
+        assert!(default_tool_config("deploy", dir, None).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_lint_tool() {
+        let snapshot = |files: &[&str], dependencies: &[&str]| ProjectSnapshot {
+            files: files.iter().map(|file| file.to_string()).collect(),
+            dependencies: dependencies.iter().map(|dep| dep.to_string()).collect(),
+            bins: BTreeSet::new(),
+        };
+        let detect = |tool_name, files: &[&str], dependencies: &[&str]| {
+            detect_lint_tool(tool_name, &snapshot(files, dependencies))
+                .map(|choice| (choice.tool, choice.reason, choice.alternatives))
+        };
+
+        assert_eq!(detect("check", &["package.json"], &[]), None);
+        assert_eq!(detect("fmt", &["prettier.out"], &[]), None);
+
+        // ESLint and Prettier projects run them, by config file or dependency
+        for file in [".eslintrc", ".eslintrc.json", "eslint.config.mjs"] {
+            assert_eq!(
+                detect("check", &[file], &[]),
+                Some((LintTool::Eslint, format!("{} found", file), Vec::new()))
+            );
+        }
+        assert_eq!(
+            detect("fmt", &[".prettierrc.yaml"], &["eslint"]),
+            Some((
+                LintTool::Prettier,
+                ".prettierrc.yaml found".to_string(),
+                Vec::new()
+            ))
+        );
+        assert_eq!(
+            detect("check", &[], &["eslint"]),
+            Some((
+                LintTool::Eslint,
+                "eslint is installed".to_string(),
+                Vec::new()
+            ))
+        );
+
+        // A config file at the root wins over a dependency
+        assert_eq!(
+            detect(
+                "check",
+                &["eslint.config.js"],
+                &["@biomejs/biome", "eslint"]
+            ),
+            Some((
+                LintTool::Eslint,
+                "eslint.config.js found".to_string(),
+                vec![LintTool::Biome]
+            ))
+        );
+        assert_eq!(
+            detect("fmt", &["biome.json", "prettier.config.js"], &[]),
+            Some((
+                LintTool::Biome,
+                "biome.json found".to_string(),
+                vec![LintTool::Prettier]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_project_snapshot_nearest_bins() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        let project = root.join("packages/app");
+        fs::create_dir_all(project.join("node_modules/.bin"))?;
+        fs::create_dir_all(root.join("node_modules/.bin"))?;
+        let bin = |dir: &Path, name: &str| {
+            fs::write(
+                dir.join("node_modules/.bin").join(if cfg!(windows) {
+                    format!("{}.cmd", name)
+                } else {
+                    name.to_string()
+                }),
+                "",
+            )
+        };
+
+        // The workspace root's biome loses to the project's own prettier
+        bin(root, "biome")?;
+        bin(&project, "prettier")?;
+        let snapshot = ProjectSnapshot::read(&project);
+        assert_eq!(snapshot.bins, BTreeSet::from(["prettier".to_string()]));
+        assert_eq!(
+            detect_lint_tool("fmt", &snapshot).map(|choice| choice.tool),
+            Some(LintTool::Prettier)
+        );
+
+        // Within one directory, the order of preference decides
+        bin(&project, "biome")?;
+        let snapshot = ProjectSnapshot::read(&project);
+        assert_eq!(
+            detect_lint_tool("fmt", &snapshot).map(|choice| choice.tool),
+            Some(LintTool::Biome)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lint_tool_args() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        let args = |tool: LintTool, tool_name, write| tool.tool_config(tool_name, dir, write).args;
+
+        assert_eq!(args(LintTool::Biome, "check", false), ["check"]);
+        assert_eq!(args(LintTool::Biome, "check", true), ["check", "--write"]);
+        assert_eq!(args(LintTool::Eslint, "check", false), ["."]);
+        assert_eq!(args(LintTool::Eslint, "check", true), [".", "--fix"]);
+        assert_eq!(args(LintTool::Biome, "fmt", false), ["format"]);
+        assert_eq!(args(LintTool::Prettier, "fmt", false), ["--check"]);
+        assert_eq!(args(LintTool::Prettier, "fmt", true), ["--write"]);
+
+        // The choice made by default_tool_config, and --tool overriding it
+        create_package_json(
+            dir,
+            r#"{"name": "app", "devDependencies": {"eslint": "^9.0.0"}}"#,
+        )?;
+        fs::write(dir.join("eslint.config.js"), "export default [];")?;
+        let check = default_tool_config("check", dir, None).unwrap();
+        assert_eq!(check.config.command, "eslint");
+        assert_eq!(check.lint_tool, Some(LintTool::Eslint));
+        assert_eq!(check.config.inputs, ["**/*", "eslint.config.js"]);
+        let check = default_tool_config("check", dir, Some(LintTool::Biome)).unwrap();
+        assert_eq!(check.config.args, ["check"]);
+        assert_eq!(check.reason, "chosen with --tool");
+        Ok(())
+    }
+
     #[test]
     fn test_validate_init_config() {
         let temp_dir = TempDir::new().unwrap();
@@ -323,20 +2256,26 @@ mod tests {
         std::fs::create_dir(&path).unwrap();
 
         // Test valid configuration (library)
-        let config = validate_init_config(&path, true, None).unwrap();
-        assert!(!config.is_binary);
+        let config = validate_init_config(&path, None, ProjectType::Library, None, false).unwrap();
+        assert_eq!(config.project_type, ProjectType::Library);
         assert_eq!(config.path, path);
         assert_eq!(config.name, "valid-package");
 
         // Test valid configuration (binary)
-        let config = validate_init_config(&path, false, None).unwrap();
-        assert!(config.is_binary);
+        let config = validate_init_config(&path, None, ProjectType::Binary, None, false).unwrap();
+        assert_eq!(config.project_type, ProjectType::Binary);
         assert_eq!(config.path, path);
         assert_eq!(config.name, "valid-package");
 
         // Test package.json exists
         fs::write(path.join("package.json"), "{}").unwrap();
-        assert!(validate_init_config(&path, false, None).is_err());
+        let Err(Error::PackageJsonExists { path: existing }) =
+            validate_init_config(&path, None, ProjectType::Binary, None, false)
+        else {
+            panic!("expected an existing package.json to be rejected");
+        };
+        assert_eq!(existing, path);
+        assert!(validate_init_config(&path, None, ProjectType::Binary, None, true).is_ok());
     }
 
     #[test]
@@ -344,9 +2283,19 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let path = temp_dir.path().join("@scope").join("my-pkg");
 
-        let config = validate_project_config(&path, true, None).unwrap();
-        assert!(!config.is_binary);
+        let config = validate_project_config(&path, None, ProjectType::Library, None).unwrap();
+        assert_eq!(config.project_type, ProjectType::Library);
         assert_eq!(config.path, path);
         assert_eq!(config.name, "@scope/my-pkg");
+
+        // An explicit name replaces the one taken from the path
+        let path = temp_dir.path().join("MyApp");
+        let config =
+            validate_project_config(&path, Some("my-app"), ProjectType::Library, None).unwrap();
+        assert_eq!(config.name, "my-app");
+        assert!(validate_project_config(&path, None, ProjectType::Library, None).is_err());
+        assert!(
+            validate_project_config(&path, Some("My-App"), ProjectType::Library, None).is_err()
+        );
     }
 }
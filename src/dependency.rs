@@ -0,0 +1,829 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use semver::{Version, VersionReq};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+use crate::registry::{RegistryClient, RegistryResponse};
+use crate::{utils, Result};
+
+/// package.json sections that can hold a dependency
+pub const DEPENDENCY_SECTIONS: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// Kind of dependency, mapping to a package.json section
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DependencyKind {
+    /// Regular runtime dependency
+    #[default]
+    Normal,
+    /// Development-only dependency
+    Dev,
+    /// Peer dependency
+    Peer,
+    /// Optional dependency
+    Optional,
+}
+
+impl DependencyKind {
+    /// Name of the package.json section for this kind
+    pub const fn section(self) -> &'static str {
+        match self {
+            DependencyKind::Normal => "dependencies",
+            DependencyKind::Dev => "devDependencies",
+            DependencyKind::Peer => "peerDependencies",
+            DependencyKind::Optional => "optionalDependencies",
+        }
+    }
+}
+
+/// A requested dependency, e.g. `express@^4.18.0`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencySpec {
+    /// Package name (can be scoped)
+    pub name: String,
+    /// Version requirement, if one was given
+    pub version_req: Option<String>,
+    /// Section the dependency is recorded in
+    pub kind: DependencyKind,
+}
+
+impl DependencySpec {
+    /// Parse a `name[@version-req]` spec
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The spec as typed by the user
+    /// * `kind` - The kind of dependency
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DependencySpec>` - The parsed spec
+    pub fn parse(spec: &str, kind: DependencyKind) -> Result<Self> {
+        let spec = spec.trim();
+
+        // The version separator is the first `@` that does not start a scope
+        let split_at = if let Some(rest) = spec.strip_prefix('@') {
+            rest.find('@').map(|i| i + 1)
+        } else {
+            spec.find('@')
+        };
+
+        let (name, version_req) = match split_at {
+            Some(i) => (&spec[..i], Some(&spec[i + 1..])),
+            None => (spec, None),
+        };
+
+        utils::validate_existing_package_name(name)?;
+
+        let version_req = match version_req {
+            Some("") => {
+                return Err(Error::InvalidPackageName {
+                    name: spec.to_string(),
+                    reason: "Missing version requirement after '@'".to_string(),
+                })
+            }
+            Some(req) => Some(req.to_string()),
+            None => None,
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            version_req,
+            kind,
+        })
+    }
+
+    /// Range to record in package.json once `version` has been resolved
+    ///
+    /// Explicit ranges are kept verbatim, otherwise (no requirement or a
+    /// dist-tag like `next`) the resolved version is recorded as a caret range
+    /// like npm does.
+    pub fn range_for(&self, version: &Version) -> String {
+        match &self.version_req {
+            Some(req) if parse_range(req).is_ok() => req.clone(),
+            _ => format!("^{}", version),
+        }
+    }
+}
+
+/// Parse an npm-style range (`^1.2.0`, `>=1 <2`, `1.x || 2.x`)
+///
+/// Returns one requirement per `||` alternative.
+pub fn parse_range(range: &str) -> Result<Vec<VersionReq>> {
+    range
+        .split("||")
+        .map(|alternative| {
+            let mut comparators: Vec<String> = Vec::new();
+            let mut pending_op = String::new();
+
+            for token in alternative.split_whitespace() {
+                // Allow a space between the operator and the version (`>= 1.0`)
+                if token
+                    .chars()
+                    .all(|c| matches!(c, '<' | '>' | '=' | '~' | '^'))
+                {
+                    pending_op.push_str(token);
+                } else {
+                    comparators.push(format!("{}{}", pending_op, token));
+                    pending_op.clear();
+                }
+            }
+
+            if comparators.is_empty() {
+                return Ok(VersionReq::STAR);
+            }
+
+            VersionReq::parse(&comparators.join(", ")).map_err(|e| Error::Dependency {
+                message: format!("Invalid version requirement '{}'", range),
+                details: e.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parse a requirement that is either a dist-tag name or a range
+///
+/// A dist-tag such as `latest` or `next` requires exactly the version it
+/// points to; anything else is parsed as a range.
+fn parse_requirement(info: &RegistryResponse, requirement: &str) -> Result<Vec<VersionReq>> {
+    match info.dist_tags.get(requirement) {
+        // A tag pointing at an invalid version matches nothing
+        Some(tagged) => Ok(parse_range(&format!("={}", tagged)).unwrap_or_default()),
+        None => parse_range(requirement),
+    }
+}
+
+/// Pick the highest version satisfying every requirement
+///
+/// Without any requirement the `latest` dist-tag is used. As with npm, a
+/// prerelease only satisfies a requirement that itself names a prerelease of
+/// the same `major.minor.patch`, e.g. `^1.0.0-beta.1` matches `1.0.0-beta.2`
+/// but not `1.1.0-beta.1`.
+pub fn resolve_version(info: &RegistryResponse, requirements: &[&str]) -> Result<Option<Version>> {
+    if requirements.is_empty() {
+        if let Some(latest) = info
+            .dist_tags
+            .get("latest")
+            .and_then(|v| Version::parse(v).ok())
+        {
+            return Ok(Some(latest));
+        }
+    }
+
+    let ranges = requirements
+        .iter()
+        .map(|req| parse_requirement(info, req))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(info
+        .version_list()
+        .into_iter()
+        .rev()
+        .filter(|v| !ranges.is_empty() || v.pre.is_empty())
+        .find(|v| {
+            ranges
+                .iter()
+                .all(|alternatives| alternatives.iter().any(|req| req.matches(v)))
+        }))
+}
+
+/// A dependency resolved to a concrete version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedDependency {
+    /// The requested spec
+    pub spec: DependencySpec,
+    /// The selected version
+    pub version: Version,
+}
+
+impl ResolvedDependency {
+    /// Range to record in package.json
+    pub fn range(&self) -> String {
+        self.spec.range_for(&self.version)
+    }
+}
+
+/// Requirements on a package that no published version satisfies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// Package name
+    pub name: String,
+    /// Requirements that could not be satisfied together
+    pub requirements: Vec<String>,
+    /// Most recent published versions
+    pub available: Vec<String>,
+}
+
+impl Conflict {
+    /// Human-readable description of the conflict
+    pub fn describe(&self) -> String {
+        format!(
+            "{}: no version satisfies {} (available: {})",
+            self.name,
+            self.requirements.join(" and "),
+            if self.available.is_empty() {
+                "none".to_string()
+            } else {
+                self.available.join(", ")
+            }
+        )
+    }
+}
+
+/// Package whose metadata could not be fetched from the registry
+#[derive(Debug)]
+pub struct Unresolved {
+    /// Package name
+    pub name: String,
+    /// Requirements requested for the package
+    pub requirements: Vec<String>,
+    /// Error returned by the registry client
+    pub error: Error,
+}
+
+impl Unresolved {
+    /// Human-readable description of the failure
+    pub fn describe(&self) -> String {
+        let error = self.error.to_string();
+        let reason = error
+            .lines()
+            .find_map(|line| line.strip_prefix("Error: "))
+            .unwrap_or(&error);
+        format!("{}: {}", self.name, reason)
+    }
+}
+
+/// Outcome of resolving a set of dependency specs
+#[derive(Debug, Default)]
+pub struct ResolutionResult {
+    /// Specs resolved to a concrete version
+    pub resolved: Vec<ResolvedDependency>,
+    /// Packages whose requirements could not be satisfied
+    pub conflicts: Vec<Conflict>,
+    /// Packages whose metadata could not be fetched
+    pub unresolved: Vec<Unresolved>,
+}
+
+/// Number of recent versions listed in a conflict
+const CONFLICT_VERSION_COUNT: usize = 5;
+
+/// Default number of registry requests made at the same time
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Fetch the metadata of every package, `concurrency` requests at a time
+pub(crate) fn fetch_package_infos<'a>(
+    client: &(impl RegistryClient + Sync),
+    names: &[&'a str],
+    concurrency: usize,
+) -> HashMap<&'a str, Result<RegistryResponse>> {
+    let next = AtomicUsize::new(0);
+    let infos = Mutex::new(HashMap::new());
+
+    thread::scope(|scope| {
+        for _ in 0..concurrency.clamp(1, names.len().max(1)) {
+            scope.spawn(|| {
+                while let Some(name) = names.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let info = client.get_package_info(name);
+                    infos
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(*name, info);
+                }
+            });
+        }
+    });
+
+    infos.into_inner().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Resolve dependency specs against the registry
+///
+/// Uses up to [`DEFAULT_CONCURRENCY`] registry requests at a time; see
+/// [`resolve_dependencies_with_concurrency`].
+///
+/// # Arguments
+///
+/// * `client` - Registry client used to fetch package metadata
+/// * `specs` - Specs to resolve
+///
+/// # Returns
+///
+/// * `Result<ResolutionResult>` - Resolved, conflicting and unresolved dependencies
+pub fn resolve_dependencies(
+    client: &(impl RegistryClient + Sync),
+    specs: &[DependencySpec],
+) -> Result<ResolutionResult> {
+    resolve_dependencies_with_concurrency(client, specs, DEFAULT_CONCURRENCY)
+}
+
+/// Resolve dependency specs against the registry
+///
+/// The metadata of each package is fetched once, with up to `concurrency`
+/// requests in flight. Packages that cannot be fetched are reported as
+/// unresolved rather than aborting the resolution. Specs naming the same
+/// package must be satisfiable by a single version, otherwise they are
+/// reported as a conflict. The last spec for a package determines the section
+/// and range that get recorded.
+///
+/// # Arguments
+///
+/// * `client` - Registry client used to fetch package metadata
+/// * `specs` - Specs to resolve
+/// * `concurrency` - Maximum number of registry requests made at the same time
+///
+/// # Returns
+///
+/// * `Result<ResolutionResult>` - Resolved, conflicting and unresolved dependencies
+pub fn resolve_dependencies_with_concurrency(
+    client: &(impl RegistryClient + Sync),
+    specs: &[DependencySpec],
+    concurrency: usize,
+) -> Result<ResolutionResult> {
+    let mut result = ResolutionResult::default();
+
+    let mut names: Vec<&str> = Vec::new();
+    for spec in specs {
+        if !names.contains(&spec.name.as_str()) {
+            names.push(&spec.name);
+        }
+    }
+    let mut version_cache = fetch_package_infos(client, &names, concurrency);
+
+    for name in names {
+        let same_name: Vec<&DependencySpec> = specs.iter().filter(|s| s.name == name).collect();
+        let requirements: Vec<&str> = same_name
+            .iter()
+            .filter_map(|s| s.version_req.as_deref())
+            .collect();
+
+        let info = match version_cache.remove(name) {
+            Some(Ok(info)) => info,
+            Some(Err(error)) => {
+                result.unresolved.push(Unresolved {
+                    name: name.to_string(),
+                    requirements: requirements.iter().map(|r| r.to_string()).collect(),
+                    error,
+                });
+                continue;
+            }
+            None => continue,
+        };
+
+        match resolve_version(&info, &requirements)? {
+            Some(version) => result.resolved.push(ResolvedDependency {
+                spec: same_name[same_name.len() - 1].clone(),
+                version,
+            }),
+            None => result.conflicts.push(Conflict {
+                name: name.to_string(),
+                requirements: requirements.iter().map(|r| r.to_string()).collect(),
+                available: info
+                    .version_list()
+                    .iter()
+                    .rev()
+                    .take(CONFLICT_VERSION_COUNT)
+                    .rev()
+                    .map(Version::to_string)
+                    .collect(),
+            }),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Record a dependency in a package.json document
+///
+/// The package is removed from every other dependency section so it is never
+/// listed twice.
+///
+/// # Returns
+///
+/// * `Result<Option<String>>` - The range previously recorded, if any
+pub fn insert_dependency(
+    manifest: &mut Value,
+    name: &str,
+    range: &str,
+    kind: DependencyKind,
+) -> Result<Option<String>> {
+    let previous = remove_dependency(manifest, name)?
+        .pop()
+        .map(|(_, range)| range);
+
+    let manifest = manifest.as_object_mut().ok_or_else(|| Error::Config {
+        message: "package.json must contain a JSON object".to_string(),
+        hint: None,
+    })?;
+    let section = manifest
+        .entry(kind.section())
+        .or_insert_with(|| Value::Object(Map::new()));
+    let deps = section.as_object_mut().ok_or_else(|| Error::Config {
+        message: format!("'{}' in package.json must be an object", kind.section()),
+        hint: None,
+    })?;
+    deps.insert(name.to_string(), Value::String(range.to_string()));
+
+    Ok(previous)
+}
+
+/// Remove a dependency from every section of a package.json document
+///
+/// The order of the remaining entries is preserved.
+///
+/// # Returns
+///
+/// * `Result<Vec<(&'static str, String)>>` - The sections the package was
+///   removed from, with the range recorded in each
+pub fn remove_dependency(manifest: &mut Value, name: &str) -> Result<Vec<(&'static str, String)>> {
+    let manifest = manifest.as_object_mut().ok_or_else(|| Error::Config {
+        message: "package.json must contain a JSON object".to_string(),
+        hint: None,
+    })?;
+
+    let mut removed = Vec::new();
+    for section in DEPENDENCY_SECTIONS {
+        if let Some(Value::Object(deps)) = manifest.get_mut(section) {
+            if let Some(old) = deps.shift_remove(name) {
+                removed.push((section, old.as_str().unwrap_or_default().to_string()));
+            }
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Write a package.json document in the style of its previous content
+///
+/// The indentation of `original` (tabs or any number of spaces) is kept, as
+/// is the presence of a trailing newline. The file is replaced atomically.
+///
+/// # Arguments
+///
+/// * `path` - Path of the package.json file
+/// * `manifest` - Document to write
+/// * `original` - Previous content of the file
+///
+/// # Returns
+///
+/// * `Result<()>` - Whether the file was written
+pub fn write_manifest(path: &Path, manifest: &Value, original: &str) -> Result<()> {
+    let indent = original
+        .lines()
+        .skip(1)
+        .find(|line| !line.trim().is_empty())
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .filter(|indent| !indent.is_empty())
+        .unwrap_or("  ");
+
+    let mut content = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut content, formatter);
+    manifest.serialize(&mut serializer)?;
+    if original.is_empty() || original.ends_with('\n') {
+        content.push(b'\n');
+    }
+
+    utils::write_atomic(path, content)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::fs;
+
+    use serde_json::json;
+
+    use super::*;
+
+    /// Registry client serving canned package documents
+    pub(crate) struct MockClient {
+        packages: HashMap<String, RegistryResponse>,
+        /// Names requested from the client, in order
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl MockClient {
+        pub(crate) fn new() -> Self {
+            Self {
+                packages: HashMap::new(),
+                calls: Mutex::new(Vec::new()),
+            }
+        }
+
+        pub(crate) fn with_package(mut self, name: &str, versions: &[&str], latest: &str) -> Self {
+            self.packages.insert(
+                name.to_string(),
+                RegistryResponse {
+                    name: name.to_string(),
+                    dist_tags: HashMap::from([("latest".to_string(), latest.to_string())]),
+                    versions: versions
+                        .iter()
+                        .map(|v| (v.to_string(), json!({ "version": v })))
+                        .collect(),
+                    ..Default::default()
+                },
+            );
+            self
+        }
+    }
+
+    impl RegistryClient for MockClient {
+        fn get_package_info(&self, name: &str) -> Result<RegistryResponse> {
+            self.calls.lock().unwrap().push(name.to_string());
+            self.packages
+                .get(name)
+                .cloned()
+                .ok_or_else(|| Error::PackageNotFound {
+                    name: name.to_string(),
+                    version: None,
+                })
+        }
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        let spec = DependencySpec::parse("express", DependencyKind::Normal).unwrap();
+        assert_eq!(spec.name, "express");
+        assert_eq!(spec.version_req, None);
+
+        let spec = DependencySpec::parse("express@^4.18.0", DependencyKind::Dev).unwrap();
+        assert_eq!(spec.name, "express");
+        assert_eq!(spec.version_req.as_deref(), Some("^4.18.0"));
+        assert_eq!(spec.kind, DependencyKind::Dev);
+
+        let spec = DependencySpec::parse("@types/node", DependencyKind::Dev).unwrap();
+        assert_eq!(spec.name, "@types/node");
+        assert_eq!(spec.version_req, None);
+
+        let spec = DependencySpec::parse("@types/node@20", DependencyKind::Dev).unwrap();
+        assert_eq!(spec.name, "@types/node");
+        assert_eq!(spec.version_req.as_deref(), Some("20"));
+
+        for invalid in ["", "express@", "@types", "@/node", "foo/bar", "two words"] {
+            assert!(
+                DependencySpec::parse(invalid, DependencyKind::Normal).is_err(),
+                "Should reject {}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_range() {
+        let v = |s: &str| Version::parse(s).unwrap();
+        let matches = |range: &str, version: &str| {
+            parse_range(range)
+                .unwrap()
+                .iter()
+                .any(|req| req.matches(&v(version)))
+        };
+
+        assert!(matches("^4.18.0", "4.21.2"));
+        assert!(!matches("^4.18.0", "5.0.0"));
+        assert!(matches(">=1.0.0 <2.0.0", "1.5.0"));
+        assert!(matches(">= 1.0.0", "1.5.0"));
+        assert!(matches("1.x || 3.x", "3.1.0"));
+        assert!(!matches("1.x || 3.x", "2.0.0"));
+        assert!(matches("*", "2.0.0"));
+        assert!(parse_range("not-a-range").is_err());
+    }
+
+    #[test]
+    fn test_resolve_dependencies() {
+        let client = MockClient::new()
+            .with_package("express", &["4.17.0", "4.21.2", "5.0.0-beta.1"], "4.21.2")
+            .with_package("@types/node", &["18.0.0", "20.1.0", "20.2.0"], "20.2.0");
+
+        let specs = vec![
+            DependencySpec::parse("express@^4.17.0", DependencyKind::Normal).unwrap(),
+            DependencySpec::parse("@types/node", DependencyKind::Dev).unwrap(),
+        ];
+
+        let result = resolve_dependencies(&client, &specs).unwrap();
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.resolved.len(), 2);
+        assert_eq!(
+            result.resolved[0].version,
+            Version::parse("4.21.2").unwrap()
+        );
+        assert_eq!(result.resolved[0].range(), "^4.17.0");
+        assert_eq!(
+            result.resolved[1].version,
+            Version::parse("20.2.0").unwrap()
+        );
+        assert_eq!(result.resolved[1].range(), "^20.2.0");
+    }
+
+    #[test]
+    fn test_resolve_dependencies_conflict() {
+        let client = MockClient::new().with_package("express", &["4.17.0", "4.21.2"], "4.21.2");
+
+        let specs = vec![
+            DependencySpec::parse("express@^4.0.0", DependencyKind::Normal).unwrap(),
+            DependencySpec::parse("express@^5.0.0", DependencyKind::Normal).unwrap(),
+        ];
+
+        let result = resolve_dependencies(&client, &specs).unwrap();
+        assert!(result.resolved.is_empty());
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].requirements, vec!["^4.0.0", "^5.0.0"]);
+        assert_eq!(result.conflicts[0].available, vec!["4.17.0", "4.21.2"]);
+    }
+
+    #[test]
+    fn test_resolve_dependencies_fetches_once() {
+        let client = MockClient::new()
+            .with_package("express", &["4.21.2"], "4.21.2")
+            .with_package("react", &["18.3.1"], "18.3.1");
+
+        let specs = ["express@^4.0.0", "missing", "react", "express@4.21.2"]
+            .iter()
+            .map(|spec| DependencySpec::parse(spec, DependencyKind::Normal).unwrap())
+            .collect::<Vec<_>>();
+
+        // With a single request at a time packages are fetched in spec order
+        let result = resolve_dependencies_with_concurrency(&client, &specs, 1).unwrap();
+        assert_eq!(
+            *client.calls.lock().unwrap(),
+            vec!["express", "missing", "react"]
+        );
+        assert_eq!(result.resolved.len(), 2);
+        assert_eq!(result.unresolved.len(), 1);
+        assert_eq!(result.unresolved[0].name, "missing");
+        assert!(matches!(
+            result.unresolved[0].error,
+            Error::PackageNotFound { .. }
+        ));
+        assert_eq!(
+            result.unresolved[0].describe(),
+            "missing: Package 'missing' not found in the registry"
+        );
+
+        client.calls.lock().unwrap().clear();
+        let result = resolve_dependencies(&client, &specs).unwrap();
+        let mut calls = client.calls.lock().unwrap().clone();
+        calls.sort();
+        assert_eq!(calls, vec!["express", "missing", "react"]);
+        assert_eq!(result.resolved.len(), 2);
+        assert_eq!(result.unresolved.len(), 1);
+    }
+
+    #[test]
+    fn test_resolve_dependencies_dist_tags() {
+        let mut client =
+            MockClient::new().with_package("react", &["18.2.0", "18.3.1", "19.0.0-rc.1"], "18.3.1");
+        client
+            .packages
+            .get_mut("react")
+            .unwrap()
+            .dist_tags
+            .insert("next".to_string(), "19.0.0-rc.1".to_string());
+
+        let resolve = |spec: &str| {
+            let specs = vec![DependencySpec::parse(spec, DependencyKind::Normal).unwrap()];
+            resolve_dependencies(&client, &specs).unwrap()
+        };
+
+        let result = resolve("react@latest");
+        assert_eq!(result.resolved[0].version.to_string(), "18.3.1");
+        assert_eq!(result.resolved[0].range(), "^18.3.1");
+
+        let result = resolve("react@next");
+        assert_eq!(result.resolved[0].version.to_string(), "19.0.0-rc.1");
+        assert_eq!(result.resolved[0].range(), "^19.0.0-rc.1");
+
+        // Names that are not tags are still parsed as ranges
+        let result = resolve("react@~18.2.0");
+        assert_eq!(result.resolved[0].version.to_string(), "18.2.0");
+        assert!(resolve_dependencies(
+            &client,
+            &[DependencySpec::parse("react@beta", DependencyKind::Normal).unwrap()]
+        )
+        .is_err());
+
+        // A tag must also satisfy the other requirements on the package
+        let specs = vec![
+            DependencySpec::parse("react@next", DependencyKind::Normal).unwrap(),
+            DependencySpec::parse("react@^18.0.0", DependencyKind::Normal).unwrap(),
+        ];
+        let result = resolve_dependencies(&client, &specs).unwrap();
+        assert_eq!(result.conflicts.len(), 1);
+    }
+
+    #[test]
+    fn test_resolve_dependencies_prerelease() {
+        let client = MockClient::new().with_package(
+            "vite",
+            &["1.0.0-beta.1", "1.0.0-beta.2", "1.1.0-beta.1", "2.0.0"],
+            "2.0.0",
+        );
+
+        let resolve = |req: &str| {
+            let specs =
+                vec![
+                    DependencySpec::parse(&format!("vite@{}", req), DependencyKind::Normal)
+                        .unwrap(),
+                ];
+            resolve_dependencies(&client, &specs).unwrap()
+        };
+
+        let result = resolve("^1.0.0-beta.1");
+        assert_eq!(result.resolved[0].version.to_string(), "1.0.0-beta.2");
+
+        // Prereleases of other versions are never picked for a plain range
+        let result = resolve("^1.0.0");
+        assert!(result.resolved.is_empty());
+        assert_eq!(result.conflicts.len(), 1);
+
+        let result = resolve(">=1.0.0");
+        assert_eq!(result.resolved[0].version.to_string(), "2.0.0");
+    }
+
+    #[test]
+    fn test_insert_dependency() {
+        let mut manifest = json!({
+            "name": "app",
+            "dependencies": { "express": "^4.0.0" }
+        });
+
+        let previous =
+            insert_dependency(&mut manifest, "express", "^4.21.2", DependencyKind::Normal).unwrap();
+        assert_eq!(previous.as_deref(), Some("^4.0.0"));
+        assert_eq!(manifest["dependencies"]["express"], "^4.21.2");
+
+        // Moving to another section must not leave a duplicate behind
+        insert_dependency(&mut manifest, "express", "^4.21.2", DependencyKind::Peer).unwrap();
+        assert!(manifest["dependencies"].get("express").is_none());
+        assert_eq!(manifest["peerDependencies"]["express"], "^4.21.2");
+
+        let previous =
+            insert_dependency(&mut manifest, "@types/node", "^20.2.0", DependencyKind::Dev)
+                .unwrap();
+        assert!(previous.is_none());
+        assert_eq!(manifest["devDependencies"]["@types/node"], "^20.2.0");
+    }
+
+    #[test]
+    fn test_remove_dependency() {
+        let mut manifest = json!({
+            "name": "app",
+            "dependencies": { "a": "^1.0.0", "express": "^4.0.0", "z": "^1.0.0" },
+            "devDependencies": { "express": "^4.0.0" }
+        });
+
+        let removed = remove_dependency(&mut manifest, "express").unwrap();
+        assert_eq!(
+            removed,
+            vec![
+                ("dependencies", "^4.0.0".to_string()),
+                ("devDependencies", "^4.0.0".to_string())
+            ]
+        );
+        // Remaining entries keep their order
+        let names: Vec<_> = manifest["dependencies"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        assert_eq!(names, vec!["a", "z"]);
+
+        assert!(remove_dependency(&mut manifest, "express")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_write_manifest_keeps_style() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("package.json");
+        let manifest = json!({ "name": "app" });
+
+        write_manifest(&path, &manifest, "{\n\t\"name\": \"old\"\n}").unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "{\n\t\"name\": \"app\"\n}"
+        );
+
+        write_manifest(&path, &manifest, "{\n    \"name\": \"old\"\n}\n").unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "{\n    \"name\": \"app\"\n}\n"
+        );
+
+        write_manifest(&path, &manifest, r#"{"name": "old"}"#).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "{\n  \"name\": \"app\"\n}"
+        );
+    }
+}
@@ -1,6 +1,266 @@
 use std::{io, path::PathBuf, process::ExitStatus};
 use thiserror::Error;
 
+/// Separates the suggestion from the rest of an error message
+const SUGGESTION_SEPARATOR: &str = "\n\nSuggestion: ";
+
+/// A stable code naming a kind of error, with the exit code it causes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode {
+    /// Machine-readable name, e.g. `CN_CONFIG`
+    pub code: &'static str,
+    /// Exit code cargonode terminates with
+    pub exit_code: i32,
+    /// What went wrong
+    pub description: &'static str,
+    /// How it is usually fixed
+    pub suggestion: &'static str,
+}
+
+/// Exit code for a failure without a more specific category
+const EXIT_OTHER: i32 = 1;
+/// Exit code for a missing or invalid configuration
+const EXIT_CONFIG: i32 = 2;
+/// Exit code for invalid input or a failed check
+const EXIT_INPUT: i32 = 3;
+/// Exit code for an unreachable registry or repository
+const EXIT_NETWORK: i32 = 4;
+/// Exit code for a missing or unsuitable program in the environment
+const EXIT_ENVIRONMENT: i32 = 5;
+/// Exit code for a tool that failed
+const EXIT_TOOL: i32 = 101;
+
+/// Every error code cargonode reports
+pub const ERROR_CODES: [ErrorCode; 35] = [
+    ErrorCode {
+        code: "CN_DIRECTORY_NOT_EMPTY",
+        exit_code: EXIT_INPUT,
+        description: "`new` was asked to create a project in a directory that already contains files.",
+        suggestion: "Run `cargonode init` in that directory instead, or choose an empty or new directory.",
+    },
+    ErrorCode {
+        code: "CN_NOT_A_DIRECTORY",
+        exit_code: EXIT_INPUT,
+        description: "The project path exists, but it is a file rather than a directory.",
+        suggestion: "Choose a different path for the project.",
+    },
+    ErrorCode {
+        code: "CN_INVALID_PACKAGE_NAME",
+        exit_code: EXIT_INPUT,
+        description: "A package name breaks one of npm's naming rules.",
+        suggestion: "Use a lowercase, URL-safe name of at most 214 characters, optionally scoped as `@scope/name`.",
+    },
+    ErrorCode {
+        code: "CN_PACKAGE_JSON_EXISTS",
+        exit_code: EXIT_INPUT,
+        description: "`init` found a package.json in the directory it was asked to initialize.",
+        suggestion: "Pass `--force` to only add the missing files, or create the project elsewhere with `cargonode new`.",
+    },
+    ErrorCode {
+        code: "CN_GIT",
+        exit_code: EXIT_ENVIRONMENT,
+        description: "A git command run by cargonode failed.",
+        suggestion: "Check that git is installed and can access the repository.",
+    },
+    ErrorCode {
+        code: "CN_VCS",
+        exit_code: EXIT_ENVIRONMENT,
+        description: "Setting up version control for a new project failed.",
+        suggestion: "Install the version control system, or pass `--vcs none` to skip it.",
+    },
+    ErrorCode {
+        code: "CN_DIRTY_WORKING_TREE",
+        exit_code: EXIT_INPUT,
+        description: "`version` does not bump the version while the working tree has uncommitted changes.",
+        suggestion: "Commit or stash the changes first, or pass `--allow-dirty`.",
+    },
+    ErrorCode {
+        code: "CN_IO",
+        exit_code: EXIT_OTHER,
+        description: "Reading or writing a file or directory failed.",
+        suggestion: "Check file permissions and free disk space.",
+    },
+    ErrorCode {
+        code: "CN_INVALID_JSON",
+        exit_code: EXIT_CONFIG,
+        description: "A JSON file, usually package.json, is not valid JSON.",
+        suggestion: "Fix the syntax at the line and column named in the error.",
+    },
+    ErrorCode {
+        code: "CN_PACKAGE_JSON_WRITE",
+        exit_code: EXIT_OTHER,
+        description: "The generated package.json could not be written.",
+        suggestion: "Check that the directory is writable and that no other process holds the file.",
+    },
+    ErrorCode {
+        code: "CN_CONFIG",
+        exit_code: EXIT_CONFIG,
+        description: "The project's package.json or cargonode configuration is missing, invalid, or refers to something that does not exist.",
+        suggestion: "Check the cargonode section of package.json or cargonode.toml, e.g. with `cargonode doctor`.",
+    },
+    ErrorCode {
+        code: "CN_USER_CONFIG",
+        exit_code: EXIT_CONFIG,
+        description: "The user config holding the defaults for new projects is invalid.",
+        suggestion: "Fix the file, or change values with `cargonode config set <key> <value>`.",
+    },
+    ErrorCode {
+        code: "CN_INPUT",
+        exit_code: EXIT_INPUT,
+        description: "A path, pattern or value given to cargonode could not be used.",
+        suggestion: "Check that the files exist and that the patterns match them.",
+    },
+    ErrorCode {
+        code: "CN_COMMAND_FAILED",
+        exit_code: EXIT_TOOL,
+        description: "A tool or command run by cargonode exited unsuccessfully.",
+        suggestion: "Read the tool's output above, or run it with `--verbose`.",
+    },
+    ErrorCode {
+        code: "CN_TERMINATED",
+        exit_code: 130,
+        description: "A command was killed by a signal. cargonode exits with 128 plus the signal number, 130 for Ctrl-C.",
+        suggestion: "If nobody stopped it, the system may have run out of memory.",
+    },
+    ErrorCode {
+        code: "CN_PACKAGE_MANAGER_NOT_FOUND",
+        exit_code: EXIT_ENVIRONMENT,
+        description: "The project's package manager is not installed or not on PATH.",
+        suggestion: "Install it, or pass `--package-manager npm`.",
+    },
+    ErrorCode {
+        code: "CN_NODE_MISSING",
+        exit_code: EXIT_ENVIRONMENT,
+        description: "Node.js is not installed or not on PATH.",
+        suggestion: "Install Node.js, e.g. with a version manager such as fnm or nvm.",
+    },
+    ErrorCode {
+        code: "CN_NODE_TOO_OLD",
+        exit_code: EXIT_ENVIRONMENT,
+        description: "The installed Node.js does not satisfy the project's `engines.node`.",
+        suggestion: "Install a matching Node.js, or relax `engines.node` in package.json.",
+    },
+    ErrorCode {
+        code: "CN_FORMATTER_NOT_FOUND",
+        exit_code: EXIT_ENVIRONMENT,
+        description: "`fmt` found neither a configured `fmt` tool nor an installed formatter.",
+        suggestion: "Add Biome or Prettier as a devDependency, or configure a `fmt` tool.",
+    },
+    ErrorCode {
+        code: "CN_DOCTOR",
+        exit_code: EXIT_ENVIRONMENT,
+        description: "At least one `doctor` check failed.",
+        suggestion: "Fix the failed checks and run `cargonode doctor` again.",
+    },
+    ErrorCode {
+        code: "CN_WORKSPACE",
+        exit_code: EXIT_TOOL,
+        description: "A tool failed in at least one workspace package.",
+        suggestion: "Read the output for each failed package, and rerun one with `--package <name>`.",
+    },
+    ErrorCode {
+        code: "CN_TIMEOUT",
+        exit_code: 124,
+        description: "A tool ran longer than its time limit and was stopped.",
+        suggestion: "Raise `timeout_secs` in the tool configuration, or pass a larger `--timeout`.",
+    },
+    ErrorCode {
+        code: "CN_OUTPUT",
+        exit_code: EXIT_OTHER,
+        description: "Writing output failed.",
+        suggestion: "Check permissions and free disk space in the output directory.",
+    },
+    ErrorCode {
+        code: "CN_TEMPLATE",
+        exit_code: EXIT_INPUT,
+        description: "A project template is invalid, or cannot be used for the requested project.",
+        suggestion: "Check the template's template.json and that every file it lists exists.",
+    },
+    ErrorCode {
+        code: "CN_TEMPLATE_UNREACHABLE",
+        exit_code: EXIT_NETWORK,
+        description: "A template repository could not be cloned.",
+        suggestion: "Check the URL, the branch or tag, your network connection and your git credentials.",
+    },
+    ErrorCode {
+        code: "CN_CHECKSUM_MISMATCH",
+        exit_code: EXIT_INPUT,
+        description: "A downloaded template archive does not match its pinned SHA-256 digest.",
+        suggestion: "Check that the URL still serves the archive you pinned; if it changed on purpose, pin the new digest.",
+    },
+    ErrorCode {
+        code: "CN_REGISTRY",
+        exit_code: EXIT_NETWORK,
+        description: "The package registry returned an error, e.g. for a package that does not exist.",
+        suggestion: "Check the package name and the configured registry.",
+    },
+    ErrorCode {
+        code: "CN_NETWORK",
+        exit_code: EXIT_NETWORK,
+        description: "The network or the registry could not be reached.",
+        suggestion: "Check your network connection, or pass `--offline` to use cached data.",
+    },
+    ErrorCode {
+        code: "CN_DEPENDENCY",
+        exit_code: EXIT_INPUT,
+        description: "No published version satisfies a dependency's version requirement.",
+        suggestion: "Relax the requirement, or pick one of the available versions.",
+    },
+    ErrorCode {
+        code: "CN_OUTDATED",
+        exit_code: EXIT_INPUT,
+        description: "`upgrade --check` found dependencies with newer versions.",
+        suggestion: "Run `cargonode upgrade --write`, or add packages that must stay pinned to `upgrade.ignore`.",
+    },
+    ErrorCode {
+        code: "CN_PUBLISH",
+        exit_code: EXIT_INPUT,
+        description: "A check before publishing failed.",
+        suggestion: "Follow the suggestion printed with the failed check.",
+    },
+    ErrorCode {
+        code: "CN_MISSING_OUTPUT",
+        exit_code: EXIT_TOOL,
+        description: "A tool succeeded but produced none of the files its `outputs` point at.",
+        suggestion: "Check where the tool writes, or set `outputs_optional` for conditional outputs.",
+    },
+    ErrorCode {
+        code: "CN_OUTPUT_VERIFICATION",
+        exit_code: EXIT_TOOL,
+        description: "A tool's outputs failed verification.",
+        suggestion: "Follow the suggestion printed with the failed verification.",
+    },
+    ErrorCode {
+        code: "CN_PROJECT_LOCKED",
+        exit_code: EXIT_ENVIRONMENT,
+        description: "Another cargonode process held the project lock for longer than cargonode waited.",
+        suggestion: "Wait for the other process, or pass a larger `--lock-timeout`.",
+    },
+    ErrorCode {
+        code: "CN_HOOK",
+        exit_code: EXIT_TOOL,
+        description: "A `pre-*` hook failed, or a `post-*` hook with `fail_on_post_hook_error` set. cargonode exits with the hook's exit code.",
+        suggestion: "Fix the hook command in `cargonode.hooks`, or pass `--no-hooks` to skip hooks.",
+    },
+];
+
+/// Look up an error code, ignoring case and an omitted `CN_` prefix
+///
+/// # Arguments
+///
+/// * `code` - The code, e.g. `CN_CONFIG` or `config`
+///
+/// # Returns
+///
+/// * `Option<&'static ErrorCode>` - The code, if it exists
+pub fn explain(code: &str) -> Option<&'static ErrorCode> {
+    let code = code.trim().to_uppercase().replace('-', "_");
+    let code = code.strip_prefix("CN_").unwrap_or(&code);
+    ERROR_CODES
+        .iter()
+        .find(|entry| entry.code.strip_prefix("CN_") == Some(code))
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Cannot create directory: {path}\n\nThe directory already contains files.\n\nSuggestion: Use `cargonode init` to initialize the project in an existing directory, or choose a different empty directory.")]
@@ -12,12 +272,22 @@ pub enum Error {
     #[error("Invalid package name: {name}\n\nError: {reason}\n\nSuggestion: Package names must be lowercase, can contain hyphens and underscores, and may be scoped (e.g., @scope/name).")]
     InvalidPackageName { name: String, reason: String },
 
-    #[error("Package already exists\n\nA package.json file already exists in {}\n\nSuggestion: To create a new project, either:\n1. Use a different directory\n2. Remove the existing package.json\n3. Use `cargonode new` to create a new project in a different directory", std::env::current_dir().unwrap_or_default().display())]
-    PackageJsonExists,
+    #[error("Package already exists\n\nA package.json file already exists in {}\n\nSuggestion: To create a new project, either:\n1. Use a different directory\n2. Remove the existing package.json\n3. Use `cargonode new` to create a new project in a different directory\n4. Use `cargonode init --force` to only add the files that are missing", path.display())]
+    PackageJsonExists { path: PathBuf },
 
     #[error("Git operation failed\n\nError: {message}\n\nDetails: {details}\n\nSuggestion: Ensure you have git installed and have appropriate permissions.")]
     Git { message: String, details: String },
 
+    #[error("Version control operation failed\n\nError: {message}\n\nDetails: {details}\n\nSuggestion: Ensure you have {binary} installed, or pass `--vcs none` to skip version control.")]
+    Vcs {
+        binary: String,
+        message: String,
+        details: String,
+    },
+
+    #[error("Working tree has uncommitted changes\n\nError: {message}\n\nSuggestion: Commit or stash your changes first, or pass `--allow-dirty` to bump the version anyway.")]
+    DirtyWorkingTree { message: String },
+
     #[error("File system error: {0}\n\nSuggestion: Check file permissions and ensure you have write access to the directory.")]
     Io(#[from] io::Error),
 
@@ -27,18 +297,251 @@ pub enum Error {
     #[error("Failed to create package.json\n\nError: {0}\n\nSuggestion: Ensure you have write permissions in the current directory and that no other process is using the file.")]
     PackageJsonCreation(String),
 
-    #[error("Configuration error\n\nError: {message}\n\nSuggestion: Check your package.json configuration and ensure all required fields are properly set.")]
-    Config { message: String },
+    #[error("Configuration error\n\nError: {message}{}", suggestion_line(hint))]
+    Config {
+        message: String,
+        hint: Option<String>,
+    },
 
-    #[error("Input error\n\nError: {message}\n\nSuggestion: Verify that all required input files exist and match the specified patterns.")]
-    Input { message: String },
+    #[error("Invalid user configuration: {}\n\nError: {message}\n\nSuggestion: Fix the file, or change a value with `cargonode config set <key> <value>`.", path.display())]
+    UserConfig { path: PathBuf, message: String },
+
+    #[error("Input error\n\nError: {message}{}", suggestion_line(hint))]
+    Input {
+        message: String,
+        hint: Option<String>,
+    },
 
     #[error("Command failed: {command}\n\nStatus: {status}\n\nSuggestion: Try the following:\n1. Run the command manually to see detailed output\n2. Check if all required dependencies are installed\n3. Verify the command arguments are correct")]
     CommandFailed { command: String, status: ExitStatus },
 
+    #[error("Command terminated\n\nError: {message}\n\nSuggestion: The process was killed from outside, often because the system ran out of memory. Check available resources and try again.")]
+    Terminated { message: String, signal: i32 },
+
+    #[error("Package manager not found\n\nError: `{binary}` is not installed or not on PATH\n\nSuggestion: Install {binary}, or pass `--package-manager npm` to install with npm instead.")]
+    PackageManagerNotFound { binary: String },
+
+    #[error(
+        "Node.js not found\n\nError: `node` is not installed or not on PATH\n\nSuggestion: {}.",
+        crate::node::install_hint()
+    )]
+    NodeMissing,
+
+    #[error("Node.js is too old\n\nError: Node.js {found} is installed, but the project requires `{required}`\n\nSuggestion: {}, or relax `engines.node` in package.json.", crate::node::install_hint())]
+    NodeTooOld { found: String, required: String },
+
+    #[error("Formatter not found\n\nError: {message}\n\nSuggestion: Add one as a devDependency with `cargonode add -D @biomejs/biome` or `cargonode add -D prettier`, or configure a `fmt` tool in the cargonode section of package.json.")]
+    FormatterNotFound { message: String },
+
+    #[error("Environment check failed\n\nError: {message}\n\nSuggestion: Fix the failed checks listed above and run `cargonode doctor` again.")]
+    Doctor { message: String },
+
+    #[error("Workspace command failed\n\nError: {message}\n\nSuggestion: Check the output above for each failed package, and rerun one with `--package <name>`.")]
+    Workspace { message: String },
+
+    #[error("Command timed out\n\nError: '{command}' did not finish within {secs} seconds and was stopped\n\nSuggestion: Raise the limit with `timeout_secs` in the tool configuration or pass a larger `--timeout`.")]
+    Timeout { command: String, secs: u64 },
+
+    #[error("Project is locked\n\nError: another cargonode process{} is running in this project\n\nSuggestion: Wait for it to finish, or pass a larger `--lock-timeout`. The lock is {}.", pid.map(|pid| format!(" (pid {})", pid)).unwrap_or_default(), path.display())]
+    ProjectLocked { path: PathBuf, pid: Option<u32> },
+
+    #[error("Hook failed: {event}\n\nError: `{command}` exited with code {exit_code}\n\nSuggestion: Fix the hook in `cargonode.hooks`, or pass `--no-hooks` to skip hooks.")]
+    Hook {
+        event: String,
+        command: String,
+        exit_code: i32,
+    },
+
     #[error("Output error\n\nError: {message}\n\nSuggestion: Check if you have write permissions and sufficient disk space in the output directory.")]
     Output { message: String },
 
+    #[error("Template error\n\nError: {message}\n\nSuggestion: Check that the template contains a valid template.json manifest and that every listed file exists.")]
+    Template { message: String },
+
+    #[error("Template repository unreachable\n\nError: cannot clone '{url}'\n\nDetails: {details}\n\nSuggestion: Check the repository URL and branch or tag, your network connection, and that git can authenticate to the repository.")]
+    TemplateUnreachable { url: String, details: String },
+
+    #[error("Checksum mismatch\n\nError: the template archive '{url}' does not match its pinned digest\n\nDetails: expected sha256 {expected}, got {actual}\n\nSuggestion: Check that the URL still serves the archive you pinned. If it changed on purpose, pass `--sha256 {actual}`.")]
+    ChecksumMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("Registry error\n\nError: {message}\n\nSuggestion: Check your network connection and verify that the package name is spelled correctly.")]
+    Registry { message: String },
+
+    #[error("Registry error\n\nError: {}\n\nSuggestion: Check that the package name is spelled correctly and published to the configured registry.", match version {
+        Some(version) => format!("Version '{}' of '{}' not found in the registry", version, name),
+        None => format!("Package '{}' not found in the registry", name),
+    })]
+    PackageNotFound {
+        name: String,
+        version: Option<String>,
+    },
+
+    #[error("Network error\n\nError: {message}\n\nSuggestion: Check your network connection, or use `--offline` to work from cached data.")]
+    Network { message: String },
+
+    #[error("Dependency resolution failed\n\nError: {message}\n\nDetails: {details}\n\nSuggestion: Relax the version requirements or pick one of the available versions.")]
+    Dependency { message: String, details: String },
+
+    #[error("Dependencies are out of date\n\nError: {message}\n\nSuggestion: Run `cargonode upgrade --write` to update package.json, or add packages that must stay pinned to `upgrade.ignore` in the cargonode configuration.")]
+    Outdated { message: String },
+
+    #[error("Publish check failed\n\nError: {message}\n\nSuggestion: {suggestion}")]
+    Publish { message: String, suggestion: String },
+
+    #[error("Missing outputs\n\nError: tool '{tool}' produced no files matching: {}\n\nSuggestion: Check that the tool writes where its `outputs` point, or set `outputs_optional` for tools whose outputs are conditional.", patterns.join(", "))]
+    MissingOutput { tool: String, patterns: Vec<String> },
+
     #[error("Output verification failed\n\nError: {message}\n\nSuggestion: {suggestion}")]
     OutputVerificationFailed { message: String, suggestion: String },
 }
+
+/// The suggestion line of an error with a context-specific hint, or nothing
+fn suggestion_line(hint: &Option<String>) -> String {
+    hint.as_ref()
+        .map(|hint| format!("{}{}", SUGGESTION_SEPARATOR, hint))
+        .unwrap_or_default()
+}
+
+impl Error {
+    /// The error message without its suggestion
+    pub fn message(&self) -> String {
+        let text = self.to_string();
+        match text.split_once(SUGGESTION_SEPARATOR) {
+            Some((message, _)) => message.to_string(),
+            None => text,
+        }
+    }
+
+    /// How to fix the error, if the message includes a suggestion
+    pub fn suggestion(&self) -> Option<String> {
+        self.to_string()
+            .split_once(SUGGESTION_SEPARATOR)
+            .map(|(_, suggestion)| suggestion.to_string())
+    }
+
+    /// Stable, machine-readable code of the error, e.g. `CN_CONFIG`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::DirectoryNotEmpty { .. } => "CN_DIRECTORY_NOT_EMPTY",
+            Error::DirectoryExists { .. } => "CN_NOT_A_DIRECTORY",
+            Error::InvalidPackageName { .. } => "CN_INVALID_PACKAGE_NAME",
+            Error::PackageJsonExists { .. } => "CN_PACKAGE_JSON_EXISTS",
+            Error::Git { .. } => "CN_GIT",
+            Error::Vcs { .. } => "CN_VCS",
+            Error::DirtyWorkingTree { .. } => "CN_DIRTY_WORKING_TREE",
+            Error::Io(_) => "CN_IO",
+            Error::SerdeJson(_) => "CN_INVALID_JSON",
+            Error::PackageJsonCreation(_) => "CN_PACKAGE_JSON_WRITE",
+            Error::Config { .. } => "CN_CONFIG",
+            Error::UserConfig { .. } => "CN_USER_CONFIG",
+            Error::Input { .. } => "CN_INPUT",
+            Error::CommandFailed { .. } => "CN_COMMAND_FAILED",
+            Error::Terminated { .. } => "CN_TERMINATED",
+            Error::PackageManagerNotFound { .. } => "CN_PACKAGE_MANAGER_NOT_FOUND",
+            Error::NodeMissing => "CN_NODE_MISSING",
+            Error::NodeTooOld { .. } => "CN_NODE_TOO_OLD",
+            Error::FormatterNotFound { .. } => "CN_FORMATTER_NOT_FOUND",
+            Error::Doctor { .. } => "CN_DOCTOR",
+            Error::Workspace { .. } => "CN_WORKSPACE",
+            Error::Timeout { .. } => "CN_TIMEOUT",
+            Error::Output { .. } => "CN_OUTPUT",
+            Error::Template { .. } => "CN_TEMPLATE",
+            Error::TemplateUnreachable { .. } => "CN_TEMPLATE_UNREACHABLE",
+            Error::ChecksumMismatch { .. } => "CN_CHECKSUM_MISMATCH",
+            Error::Registry { .. } | Error::PackageNotFound { .. } => "CN_REGISTRY",
+            Error::Network { .. } => "CN_NETWORK",
+            Error::Dependency { .. } => "CN_DEPENDENCY",
+            Error::Outdated { .. } => "CN_OUTDATED",
+            Error::Publish { .. } => "CN_PUBLISH",
+            Error::MissingOutput { .. } => "CN_MISSING_OUTPUT",
+            Error::OutputVerificationFailed { .. } => "CN_OUTPUT_VERIFICATION",
+            Error::ProjectLocked { .. } => "CN_PROJECT_LOCKED",
+            Error::Hook { .. } => "CN_HOOK",
+        }
+    }
+
+    /// Exit code cargonode should terminate with for this error
+    ///
+    /// Each error code belongs to a category with its own exit code, listed
+    /// in [`ERROR_CODES`]. Commands killed by a signal map to `128 + signal`,
+    /// as in shells, timeouts to 124, as with `timeout(1)`, and failed hooks
+    /// to the hook's exit code.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Terminated { signal, .. } => 128 + signal,
+            Error::Hook { exit_code, .. } => *exit_code,
+            _ => explain(self.code()).map_or(EXIT_OTHER, |entry| entry.exit_code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        let config = Error::Config {
+            message: "Tool 'build' not found in configuration".to_string(),
+            hint: None,
+        };
+        assert_eq!((config.code(), config.exit_code()), ("CN_CONFIG", 2));
+
+        let name = Error::InvalidPackageName {
+            name: "My-App".to_string(),
+            reason: "uppercase".to_string(),
+        };
+        assert_eq!(
+            (name.code(), name.exit_code()),
+            ("CN_INVALID_PACKAGE_NAME", 3)
+        );
+
+        let network = Error::Network {
+            message: "connection refused".to_string(),
+        };
+        assert_eq!((network.code(), network.exit_code()), ("CN_NETWORK", 4));
+        assert_eq!(Error::NodeMissing.exit_code(), 5);
+
+        let failed = Error::Workspace {
+            message: "1 of 3 workspace packages failed: ui".to_string(),
+        };
+        assert_eq!(failed.exit_code(), 101);
+
+        let killed = Error::Terminated {
+            message: "killed".to_string(),
+            signal: 9,
+        };
+        assert_eq!(killed.exit_code(), 137);
+        let timeout = Error::Timeout {
+            command: "tsc".to_string(),
+            secs: 1,
+        };
+        assert_eq!(timeout.exit_code(), 124);
+        let hook = Error::Hook {
+            event: "pre-build".to_string(),
+            command: "node gen.js".to_string(),
+            exit_code: 7,
+        };
+        assert_eq!((hook.code(), hook.exit_code()), ("CN_HOOK", 7));
+        let io = Error::Io(io::Error::other("disk full"));
+        assert_eq!((io.code(), io.exit_code()), ("CN_IO", 1));
+    }
+
+    #[test]
+    fn test_explain() {
+        let codes: HashSet<&str> = ERROR_CODES.iter().map(|entry| entry.code).collect();
+        assert_eq!(codes.len(), ERROR_CODES.len());
+        assert!(codes.iter().all(|code| code.starts_with("CN_")));
+
+        assert_eq!(explain("CN_CONFIG").unwrap().exit_code, 2);
+        assert_eq!(explain("node-too-old").unwrap().code, "CN_NODE_TOO_OLD");
+        assert!(explain("CN_NOPE").is_none());
+        assert!(explain("").is_none());
+    }
+}
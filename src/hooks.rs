@@ -0,0 +1,357 @@
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::config::HooksConfig;
+use crate::error::Error;
+use crate::journal::{self, EntryKind, Journal, JournalEntry};
+use crate::{process, progress, utils, Result};
+
+/// Exit code recorded for a hook whose program could not be started, as in
+/// shells
+const EXIT_NOT_STARTED: i32 = 127;
+
+static DISABLED: OnceLock<bool> = OnceLock::new();
+
+/// Choose whether hooks are skipped for the rest of the process
+/// (`--no-hooks`)
+///
+/// Only the first call has an effect; hooks run until then.
+pub fn set_disabled(disabled: bool) {
+    let _ = DISABLED.set(disabled);
+}
+
+/// Whether hooks are skipped
+pub fn disabled() -> bool {
+    DISABLED.get().copied().unwrap_or_default()
+}
+
+/// What a hook runs around
+#[derive(Debug, Clone, Copy)]
+pub struct HookContext<'a> {
+    /// Operation the event is named after: `new`, `init` or a tool name
+    pub operation: &'a str,
+    /// Tool that runs, for tool hooks
+    pub tool: Option<&'a str>,
+    /// Directory of the project
+    pub project_dir: &'a Path,
+}
+
+/// A hook that ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookRun {
+    /// Event the hook ran on, e.g. `pre-build`
+    pub event: String,
+    /// Command line of the hook
+    pub command: String,
+    /// Exit code of the hook
+    pub exit_code: i32,
+    /// Time the hook took
+    pub duration: Duration,
+}
+
+impl HookRun {
+    /// Record the run in the journal of `project_dir`
+    pub fn record(&self, project_dir: &Path) -> Result<()> {
+        Journal::new(&journal::journal_dir(project_dir)).add_entry(JournalEntry {
+            tool: self.event.clone(),
+            kind: EntryKind::Hook,
+            command: self.command.clone(),
+            input_hash: String::new(),
+            exit_code: self.exit_code,
+            from_cache: false,
+            timestamp: utils::unix_timestamp(),
+            duration_ms: self.duration.as_millis() as u64,
+            steps: Vec::new(),
+        })
+    }
+
+    /// Whether the operation goes on after the hook
+    ///
+    /// A failed `pre-*` hook stops it. A failed `post-*` hook is a warning,
+    /// and an error only with `fail_on_post_hook_error`.
+    ///
+    /// # Arguments
+    ///
+    /// * `hooks` - The hooks the run comes from
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - `Error::Hook` with the hook's exit code when the
+    ///   failure is an error
+    pub fn check(&self, hooks: &HooksConfig) -> Result<()> {
+        if self.exit_code == 0 {
+            return Ok(());
+        }
+        let error = Error::Hook {
+            event: self.event.clone(),
+            command: self.command.clone(),
+            exit_code: self.exit_code,
+        };
+        if self.event.starts_with("pre-") {
+            return Err(error);
+        }
+        progress::warn(&format!(
+            "{} hook `{}` failed with exit code {}",
+            self.event, self.command, self.exit_code
+        ))?;
+        if hooks.fail_on_post_hook_error {
+            return Err(error);
+        }
+        Ok(())
+    }
+}
+
+/// Run the `pre-<operation>` hook, if one is configured and hooks are not
+/// disabled
+///
+/// # Arguments
+///
+/// * `hooks` - The project's hooks
+/// * `context` - What the hook runs before
+///
+/// # Returns
+///
+/// * `Result<Option<HookRun>>` - The hook that ran, successful or not
+pub fn run_pre(hooks: &HooksConfig, context: &HookContext) -> Result<Option<HookRun>> {
+    run_hook(hooks, &format!("pre-{}", context.operation), context, None)
+}
+
+/// Run the `post-<operation>` hook, if one is configured and hooks are not
+/// disabled
+///
+/// # Arguments
+///
+/// * `hooks` - The project's hooks
+/// * `context` - What the hook runs after
+/// * `exit_code` - Exit code of the operation, passed as `CN_EXIT_CODE`
+///
+/// # Returns
+///
+/// * `Result<Option<HookRun>>` - The hook that ran, successful or not
+pub fn run_post(
+    hooks: &HooksConfig,
+    context: &HookContext,
+    exit_code: i32,
+) -> Result<Option<HookRun>> {
+    run_hook(
+        hooks,
+        &format!("post-{}", context.operation),
+        context,
+        Some(exit_code),
+    )
+}
+
+/// Run the hook of `event` with the variables describing `context`
+///
+/// The hook runs in the project directory, or in its parent while the
+/// project does not exist yet. Its output goes to the terminal, or to
+/// stderr when stdout carries JSON events.
+fn run_hook(
+    hooks: &HooksConfig,
+    event: &str,
+    context: &HookContext,
+    exit_code: Option<i32>,
+) -> Result<Option<HookRun>> {
+    let Some(hook) = hooks.get(event).filter(|_| !disabled()) else {
+        return Ok(None);
+    };
+    let command_line = std::iter::once(hook.command.as_str())
+        .chain(hook.args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    progress::status("Hook", &format!("{} `{}`", event, command_line))?;
+
+    let project_dir = std::path::absolute(context.project_dir)?;
+    let working_dir = if project_dir.is_dir() {
+        project_dir.as_path()
+    } else {
+        project_dir.parent().unwrap_or(&project_dir)
+    };
+    let mut command = Command::new(&hook.command);
+    command
+        .args(&hook.args)
+        .current_dir(working_dir)
+        .env("CN_EVENT", event)
+        .env("CN_PROJECT_DIR", &project_dir)
+        .stdin(Stdio::inherit());
+    if let Some(tool) = context.tool {
+        command.env("CN_TOOL", tool);
+    }
+    if let Some(exit_code) = exit_code {
+        command.env("CN_EXIT_CODE", exit_code.to_string());
+    }
+    if progress::message_format() == progress::MessageFormat::Json {
+        command.stdout(io::stderr());
+    }
+
+    let start = Instant::now();
+    let exit_code = match process::status_streamed(&mut command) {
+        Ok(status) => utils::exit_code(&status),
+        Err(e) => {
+            progress::error(&format!(
+                "cannot run {} hook `{}`: {}",
+                event, command_line, e
+            ))?;
+            EXIT_NOT_STARTED
+        }
+    };
+    Ok(Some(HookRun {
+        event: event.to_string(),
+        command: command_line,
+        exit_code,
+        duration: start.elapsed(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::config::HookCommand;
+
+    fn hooks(events: &[(&str, &str)], fail_on_post_hook_error: bool) -> HooksConfig {
+        HooksConfig {
+            fail_on_post_hook_error,
+            events: events
+                .iter()
+                .map(|(event, script)| {
+                    let hook = HookCommand {
+                        command: "sh".to_string(),
+                        args: vec!["-c".to_string(), script.to_string()],
+                    };
+                    (event.to_string(), hook)
+                })
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hook_environment() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        let hooks = hooks(
+            &[
+                (
+                    "pre-build",
+                    "echo \"$CN_EVENT $CN_TOOL $CN_EXIT_CODE\" > pre.txt; pwd >> pre.txt",
+                ),
+                (
+                    "post-build",
+                    "echo \"$CN_EVENT $CN_TOOL $CN_EXIT_CODE\" > post.txt",
+                ),
+            ],
+            false,
+        );
+        let context = HookContext {
+            operation: "build",
+            tool: Some("build"),
+            project_dir: dir,
+        };
+
+        let run = run_pre(&hooks, &context)?.unwrap();
+        assert_eq!(run.event, "pre-build");
+        assert_eq!(run.exit_code, 0);
+        let pre = std::fs::read_to_string(dir.join("pre.txt"))?;
+        let mut lines = pre.lines();
+        assert_eq!(lines.next(), Some("pre-build build "));
+        assert_eq!(
+            std::fs::canonicalize(lines.next().unwrap())?,
+            std::fs::canonicalize(dir)?
+        );
+
+        run_post(&hooks, &context, 3)?;
+        assert_eq!(
+            std::fs::read_to_string(dir.join("post.txt"))?,
+            "post-build build 3\n"
+        );
+
+        // Events without a hook run nothing
+        let context = HookContext {
+            operation: "test",
+            ..context
+        };
+        assert_eq!(run_pre(&hooks, &context)?, None);
+        assert_eq!(run_post(&hooks, &context, 0)?, None);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hook_failures() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let context = HookContext {
+            operation: "build",
+            tool: Some("build"),
+            project_dir: temp_dir.path(),
+        };
+
+        // A failed pre hook aborts with its exit code
+        let failing = hooks(&[("pre-build", "exit 7"), ("post-build", "exit 4")], false);
+        let run = run_pre(&failing, &context)?.unwrap();
+        let Err(err) = run.check(&failing) else {
+            panic!("expected the pre hook to fail");
+        };
+        assert!(matches!(&err, Error::Hook { event, .. } if event == "pre-build"));
+        assert_eq!(err.exit_code(), 7);
+
+        // A failed post hook only warns, unless failures are errors
+        let run = run_post(&failing, &context, 0)?.unwrap();
+        assert_eq!(run.exit_code, 4);
+        assert!(run.check(&failing).is_ok());
+        let strict = HooksConfig {
+            fail_on_post_hook_error: true,
+            ..failing
+        };
+        let Err(err) = run.check(&strict) else {
+            panic!("expected the post hook to fail");
+        };
+        assert_eq!(err.exit_code(), 4);
+
+        // A program that cannot be started fails like a missing command
+        let missing = HooksConfig {
+            events: BTreeMap::from([(
+                "pre-build".to_string(),
+                HookCommand {
+                    command: "cargonode-no-such-hook".to_string(),
+                    args: Vec::new(),
+                },
+            )]),
+            ..HooksConfig::default()
+        };
+        let run = run_pre(&missing, &context)?.unwrap();
+        assert_eq!(run.exit_code, EXIT_NOT_STARTED);
+        assert!(run.check(&missing).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hook_record() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let run = HookRun {
+            event: "post-new".to_string(),
+            command: "catalog register".to_string(),
+            exit_code: 0,
+            duration: Duration::from_millis(1_500),
+        };
+        run.record(temp_dir.path())?;
+        let journal = Journal::new(&journal::journal_dir(temp_dir.path()));
+        let entries = journal.read_entries()?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].tool, "post-new");
+        assert_eq!(entries[0].kind, EntryKind::Hook);
+        assert_eq!(entries[0].command, "catalog register");
+        assert_eq!(entries[0].duration_ms, 1_500);
+
+        // Hooks are history, not tool runs
+        assert!(journal.stats_by_tool()?.is_empty());
+        assert!(journal.hit_rate_by_tool()?.is_empty());
+        Ok(())
+    }
+}
@@ -1,12 +1,93 @@
-use std::collections::HashSet;
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::{Path, PathBuf};
-
-use glob::glob;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use glob::{MatchOptions, Pattern};
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 use crate::error::Error;
-use crate::Result;
+use crate::{progress, Result};
+
+/// Directories whose files are never inputs
+///
+/// Installed packages and VCS metadata are not project sources, and
+/// cargonode's own cache and journal change on every run and would otherwise
+/// invalidate the cache for broad patterns like `**/*`.
+pub(crate) const EXCLUDED_DIRS: [&str; 3] = [".cargonode", ".git", "node_modules"];
+
+/// Name of the file in the cache directory that remembers input file hashes
+///
+/// It is not a `.json` file so that it is never mistaken for a cache entry.
+pub const INPUT_INDEX_FILE: &str = "inputs.index";
+
+/// Version of the input hashing scheme, hashed into every input hash so that
+/// cache entries from an older scheme no longer match
+const HASH_VERSION: &str = "cargonode-inputs-v2";
+
+/// Size of the chunks file contents are hashed in, so memory stays bounded
+/// whatever the size of the file
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Number of changed files above which contents are hashed on several threads
+const PARALLEL_THRESHOLD: usize = 64;
+
+/// How long after a modification a file's fingerprint is trusted
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// Options used to match input and exclude patterns
+///
+/// `*` does not cross directory boundaries, matching how `glob` expands
+/// patterns.
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Tells whether single paths are inputs, without walking the project
+pub struct InputMatcher {
+    /// Base path the patterns were resolved against
+    base_path: PathBuf,
+
+    /// Compiled input patterns
+    includes: Vec<Pattern>,
+
+    /// Compiled exclude patterns
+    excludes: Vec<Pattern>,
+}
+
+impl InputMatcher {
+    /// Whether `path` is, or was before it was deleted, an input file
+    ///
+    /// Like [`InputTracker::get_input_files`], paths inside [`EXCLUDED_DIRS`]
+    /// or an excluded directory are never inputs.
+    pub fn matches(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.base_path) else {
+            return false;
+        };
+        let excluded_dir = relative.components().any(|component| {
+            EXCLUDED_DIRS
+                .iter()
+                .any(|dir| component.as_os_str() == *dir)
+        });
+        let excluded = path.ancestors().any(|ancestor| {
+            self.excludes
+                .iter()
+                .any(|pattern| pattern.matches_path_with(ancestor, MATCH_OPTIONS))
+        });
+        !excluded_dir
+            && !excluded
+            && self
+                .includes
+                .iter()
+                .any(|pattern| pattern.matches_path_with(path, MATCH_OPTIONS))
+    }
+}
 
 /// Tracks input files for idempotency
 pub struct InputTracker {
@@ -15,6 +96,15 @@ pub struct InputTracker {
 
     /// Glob patterns for input files
     patterns: Vec<String>,
+
+    /// Glob patterns for files and directories that are never inputs
+    excludes: Vec<String>,
+
+    /// File remembering content hashes between runs
+    index_path: Option<PathBuf>,
+
+    /// Size in bytes above which a file is reported when it is hashed
+    large_file_bytes: Option<u64>,
 }
 
 impl InputTracker {
@@ -32,70 +122,231 @@ impl InputTracker {
         Self {
             base_path: base_path.to_path_buf(),
             patterns,
+            excludes: Vec::new(),
+            index_path: None,
+            large_file_bytes: None,
+        }
+    }
+
+    /// Exclude files and directories matching `excludes` from the inputs
+    ///
+    /// # Arguments
+    ///
+    /// * `excludes` - Glob patterns, relative to the base path
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The tracker with the exclusions added
+    pub fn with_excludes(mut self, excludes: Vec<String>) -> Self {
+        self.excludes.extend(excludes);
+        self
+    }
+
+    /// Exclude cargonode's state directory when it lies inside the base path
+    ///
+    /// `.cargonode` is one of [`EXCLUDED_DIRS`], but `CARGONODE_HOME` can move
+    /// the cache, journal and lock file anywhere in the project, where they
+    /// would change the hash on every run.
+    ///
+    /// # Arguments
+    ///
+    /// * `state_dir` - The project's state directory, from [`crate::utils::state_dir`]
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The tracker with the state directory excluded
+    pub fn with_state_dir(mut self, state_dir: &Path) -> Self {
+        if let Ok(relative) = state_dir.strip_prefix(&self.base_path) {
+            if !relative.as_os_str().is_empty() {
+                self.excludes
+                    .push(Pattern::escape(&relative.to_string_lossy()));
+            }
         }
+        self
+    }
+
+    /// Compile glob patterns, resolving relative ones against the base path
+    fn compile(&self, patterns: &[String]) -> Result<Vec<Pattern>> {
+        patterns
+            .iter()
+            .map(|pattern| {
+                let absolute = if Path::new(pattern).is_absolute() {
+                    pattern.clone()
+                } else {
+                    format!(
+                        "{}/{}",
+                        Pattern::escape(&self.base_path.to_string_lossy()),
+                        pattern
+                    )
+                };
+                Pattern::new(&absolute).map_err(|err| Error::Input {
+                    message: format!("Invalid glob pattern '{}': {}", pattern, err),
+                    hint: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Compile the patterns once for repeated [`InputMatcher::matches`] calls
+    ///
+    /// # Returns
+    ///
+    /// * `Result<InputMatcher>` - The matcher, or `Error::Input` if a pattern
+    ///   is invalid
+    pub fn matcher(&self) -> Result<InputMatcher> {
+        Ok(InputMatcher {
+            base_path: self.base_path.clone(),
+            includes: self.compile(&self.patterns)?,
+            excludes: self.compile(&self.excludes)?,
+        })
     }
 
     /// Get all input files matching the patterns
     ///
+    /// The project is walked once, skipping [`EXCLUDED_DIRS`], excluded
+    /// patterns and anything ignored by `.gitignore` files. Patterns reaching
+    /// outside the project, such as `../shared/**` or an absolute path, are
+    /// walked from their literal prefix in the same way.
+    ///
     /// # Returns
     ///
     /// * `Result<Vec<PathBuf>>` - List of matching file paths
     pub fn get_input_files(&self) -> Result<Vec<PathBuf>> {
-        let mut files = Vec::new();
-        let mut seen_paths = HashSet::new();
-
         // Maximum number of files to process
         const MAX_FILES: usize = 10000;
 
-        for pattern in &self.patterns {
-            // Construct absolute pattern
-            let abs_pattern = if Path::new(pattern).is_absolute() {
-                pattern.clone()
-            } else {
-                self.base_path.join(pattern).to_string_lossy().to_string()
-            };
+        if self.patterns.is_empty() {
+            return Ok(Vec::new());
+        }
 
-            // Use glob to find matching files
-            let glob_result = glob(&abs_pattern);
-
-            match glob_result {
-                Ok(entries) => {
-                    for entry_result in entries {
-                        // Check if we've reached the maximum file limit
-                        if files.len() >= MAX_FILES {
-                            return Err(Error::Input {
-                                message: format!("Too many input files (limit: {})", MAX_FILES),
-                            });
-                        }
-
-                        match entry_result {
-                            Ok(path) => {
-                                if path.is_file() && !seen_paths.contains(&path) {
-                                    seen_paths.insert(path.clone());
-                                    files.push(path);
-                                }
-                            }
-                            Err(err) => {
-                                return Err(Error::Input {
-                                    message: format!("Failed to process glob entry: {}", err),
-                                });
-                            }
-                        }
-                    }
+        let includes = self.compile(&self.patterns)?;
+        let excludes = self.compile(&self.excludes)?;
+
+        let mut files = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        for root in self.walk_roots() {
+            let excludes = excludes.clone();
+            let walker = WalkBuilder::new(&root)
+                .hidden(false)
+                .git_global(false)
+                .require_git(false)
+                .filter_entry(move |entry| {
+                    let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                    let excluded_dir = is_dir
+                        && entry.depth() > 0
+                        && EXCLUDED_DIRS.iter().any(|dir| entry.file_name() == *dir);
+
+                    !excluded_dir
+                        && !excludes
+                            .iter()
+                            .any(|pattern| pattern.matches_path_with(entry.path(), MATCH_OPTIONS))
+                })
+                .build();
+
+            for entry in walker {
+                let entry = entry.map_err(|err| Error::Input {
+                    message: format!("Failed to walk input files: {}", err),
+                    hint: None,
+                })?;
+                let path = entry.path();
+
+                if !path.is_file()
+                    || !includes
+                        .iter()
+                        .any(|pattern| pattern.matches_path_with(path, MATCH_OPTIONS))
+                    || !seen.insert(normalize(path))
+                {
+                    continue;
                 }
-                Err(err) => {
+
+                // Check if we've reached the maximum file limit
+                if files.len() >= MAX_FILES {
                     return Err(Error::Input {
-                        message: format!("Invalid glob pattern '{}': {}", pattern, err),
+                        message: format!("Too many input files (limit: {})", MAX_FILES),
+                        hint: None,
                     });
                 }
+                files.push(entry.into_path());
             }
         }
 
         Ok(files)
     }
 
+    /// Paths to walk for the patterns: the base path, then the literal
+    /// prefix of each pattern outside it, e.g. `<base>/../shared` for
+    /// `../shared/**`
+    ///
+    /// Prefixes are kept as written, so the paths found below them match the
+    /// compiled patterns.
+    fn walk_roots(&self) -> Vec<PathBuf> {
+        let base = normalize(&self.base_path);
+        let mut roots = vec![self.base_path.clone()];
+        for pattern in &self.patterns {
+            let mut root = if Path::new(pattern).is_absolute() {
+                PathBuf::new()
+            } else {
+                self.base_path.clone()
+            };
+            for component in Path::new(pattern).components() {
+                if component
+                    .as_os_str()
+                    .to_string_lossy()
+                    .contains(['*', '?', '['])
+                {
+                    break;
+                }
+                root.push(component);
+            }
+            if !normalize(&root).starts_with(&base) && !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+        roots
+    }
+
+    /// Remember file hashes in `index_path` between runs
+    ///
+    /// Files whose size and modification time match the index are not read
+    /// again; their stored content hash is used instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `index_path` - File the index is kept in, usually
+    ///   [`INPUT_INDEX_FILE`] in the cache directory
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The tracker using the index
+    pub fn with_index(mut self, index_path: PathBuf) -> Self {
+        self.index_path = Some(index_path);
+        self
+    }
+
+    /// Report files larger than `bytes` when their content is hashed
+    ///
+    /// Large files are hashed like any other; the warning only points out
+    /// inputs that slow down every run that has to read them.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - Size above which files are reported, or `None` or `0` for
+    ///   none
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The tracker reporting large files
+    pub fn with_large_file_warning(mut self, bytes: Option<u64>) -> Self {
+        self.large_file_bytes = bytes.filter(|&bytes| bytes > 0);
+        self
+    }
+
     /// Calculate a hash of all input files
     ///
+    /// The hash covers each file's path and the hash of its content. Contents
+    /// are only read for files that changed since the index was written, and
+    /// on several threads when there are many of them.
+    ///
     /// # Returns
     ///
     /// * `Result<String>` - Hex string of the hash
@@ -105,52 +356,234 @@ impl InputTracker {
         // Sort files for deterministic ordering
         files.sort();
 
-        // Create hasher
-        let mut hasher = Sha256::new();
-
-        // Maximum file size to hash (10MB)
-        const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
-
-        for file in files {
-            // Get file metadata
-            let metadata = fs::metadata(&file)?;
-
-            // Skip files that are too large
-            if metadata.len() > MAX_FILE_SIZE {
-                return Err(Error::Input {
-                    message: format!(
-                        "File too large to hash: {} ({} bytes, limit: {} bytes)",
-                        file.display(),
-                        metadata.len(),
-                        MAX_FILE_SIZE
-                    ),
-                });
+        let mut index = self
+            .index_path
+            .as_deref()
+            .map(FileIndex::load)
+            .unwrap_or_default();
+
+        // Cheap fingerprints first; only changed files are read
+        let mut hashes = Vec::with_capacity(files.len());
+        let mut changed = Vec::new();
+        for file in &files {
+            let metadata = fs::metadata(file)?;
+            let fingerprint = Fingerprint::of(&metadata);
+            match index.get(file, &fingerprint) {
+                Some(hash) => hashes.push(hash.to_string()),
+                None => {
+                    if self
+                        .large_file_bytes
+                        .is_some_and(|limit| metadata.len() > limit)
+                    {
+                        progress::warn(&format!(
+                            "input {} is {}; hashing it slows down every run that changes it",
+                            file.strip_prefix(&self.base_path).unwrap_or(file).display(),
+                            progress::format_bytes(metadata.len())
+                        ))?;
+                    }
+                    hashes.push(String::new());
+                    changed.push((hashes.len() - 1, fingerprint));
+                }
             }
+        }
 
-            // Read file content
-            let content = fs::read(&file)?;
+        let paths: Vec<&Path> = changed.iter().map(|(i, _)| files[*i].as_path()).collect();
+        for ((i, fingerprint), hash) in changed.into_iter().zip(hash_contents(&paths)?) {
+            index.insert(&files[i], fingerprint, &hash);
+            hashes[i] = hash;
+        }
+        if let Some(index_path) = &self.index_path {
+            index.save(index_path);
+        }
 
-            // Update hash with file path and content
+        let mut hasher = Sha256::new();
+        hasher.update(HASH_VERSION.as_bytes());
+        hasher.update(b"\n");
+        for (file, hash) in files.iter().zip(&hashes) {
             hasher.update(file.to_string_lossy().as_bytes());
             hasher.update(b":");
-            hasher.update(&content);
+            hasher.update(hash.as_bytes());
             hasher.update(b"\n");
         }
 
-        // Finalize hash
-        let hash = hasher.finalize();
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+}
+
+/// `path` with `.` and `..` components resolved without touching the disk
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Hash the contents of `files`, spreading the work across threads when
+/// there are more than [`PARALLEL_THRESHOLD`] of them
+///
+/// # Returns
+///
+/// * `Result<Vec<String>>` - One hex hash per file, in the same order
+fn hash_contents(files: &[&Path]) -> Result<Vec<String>> {
+    if files.len() <= PARALLEL_THRESHOLD {
+        return files.iter().map(|file| hash_file(file)).collect();
+    }
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = files.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let workers: Vec<_> = files
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|file| hash_file(file))
+                        .collect::<Result<Vec<_>>>()
+                })
+            })
+            .collect();
+        let mut hashes = Vec::with_capacity(files.len());
+        for worker in workers {
+            let chunk = worker.join().map_err(|_| Error::Input {
+                message: "A thread hashing input files panicked".to_string(),
+                hint: None,
+            })?;
+            hashes.extend(chunk?);
+        }
+        Ok(hashes)
+    })
+}
 
-        Ok(format!("{:x}", hash))
+/// Hash the content of `file`, read in chunks of [`HASH_CHUNK_SIZE`]
+///
+/// # Returns
+///
+/// * `Result<String>` - Hex SHA-256 of the content
+fn hash_file(file: &Path) -> Result<String> {
+    let mut reader = fs::File::open(file)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; HASH_CHUNK_SIZE];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => hasher.update(&buffer[..read]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Size and modification time of a file, which change whenever its content does
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+struct Fingerprint {
+    size: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+}
+
+impl Fingerprint {
+    fn of(metadata: &fs::Metadata) -> Self {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        Self {
+            size: metadata.len(),
+            modified_secs: modified.as_secs(),
+            modified_nanos: modified.subsec_nanos(),
+        }
+    }
+
+    /// Whether the file was modified long enough ago that a change within
+    /// the same timestamp tick can be ruled out
+    fn is_settled(&self) -> bool {
+        let modified = Duration::new(self.modified_secs, self.modified_nanos);
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .is_ok_and(|now| now.saturating_sub(modified) > SETTLE_TIME)
+    }
+}
+
+/// Content hash of a file, valid while its fingerprint is unchanged
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct IndexEntry {
+    #[serde(flatten)]
+    fingerprint: Fingerprint,
+    hash: String,
+}
+
+/// Content hashes of input files from earlier runs, keyed by path
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct FileIndex {
+    version: String,
+    files: BTreeMap<String, IndexEntry>,
+}
+
+impl FileIndex {
+    /// Load the index; a missing, unreadable or outdated one is empty
+    fn load(path: &Path) -> Self {
+        fs::read(path)
+            .ok()
+            .and_then(|content| serde_json::from_slice::<Self>(&content).ok())
+            .filter(|index| index.version == HASH_VERSION)
+            .unwrap_or_default()
+    }
+
+    /// Write the index, forgetting files that no longer exist
+    ///
+    /// The index only saves work, so failing to write it is not an error.
+    fn save(mut self, path: &Path) {
+        self.version = HASH_VERSION.to_string();
+        self.files.retain(|file, _| Path::new(file).is_file());
+        if let (Some(parent), Ok(content)) = (path.parent(), serde_json::to_vec(&self)) {
+            let _ = fs::create_dir_all(parent).and_then(|()| fs::write(path, content));
+        }
+    }
+
+    fn get(&self, file: &Path, fingerprint: &Fingerprint) -> Option<&str> {
+        self.files
+            .get(&*file.to_string_lossy())
+            .filter(|entry| entry.fingerprint == *fingerprint)
+            .map(|entry| entry.hash.as_str())
+    }
+
+    /// Remember the hash of a file, unless it was modified so recently that
+    /// another change could keep the same fingerprint
+    fn insert(&mut self, file: &Path, fingerprint: Fingerprint, hash: &str) {
+        let key = file.to_string_lossy().to_string();
+        if fingerprint.is_settled() {
+            self.files.insert(
+                key,
+                IndexEntry {
+                    fingerprint,
+                    hash: hash.to_string(),
+                },
+            );
+        } else {
+            self.files.remove(&key);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use std::io::Write;
+    use std::sync::Arc;
 
     use tempfile::TempDir;
 
     use super::*;
+    use crate::progress::{Level, RecordingSink};
 
     fn create_test_file(dir: &Path, name: &str, content: &[u8]) -> Result<PathBuf> {
         let file_path = dir.join(name);
@@ -221,6 +654,289 @@ mod tests {
         Ok(())
     }
 
+    /// Pretend `path` was last modified an hour ago
+    fn age(path: &Path) -> Result<()> {
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(an_hour_ago)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_calculate_hash_with_index() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        let index_path = dir_path.join(".cargonode/cache").join(INPUT_INDEX_FILE);
+        let settled = create_test_file(dir_path, "settled.txt", b"content1")?;
+        age(&settled)?;
+        create_test_file(dir_path, "fresh.txt", b"content2")?;
+
+        let plain = InputTracker::new(dir_path, vec!["*.txt".to_string()]);
+        let indexed =
+            InputTracker::new(dir_path, vec!["*.txt".to_string()]).with_index(index_path.clone());
+        let hash = plain.calculate_hash()?;
+        assert_eq!(indexed.calculate_hash()?, hash);
+
+        // Only files modified a while ago are remembered
+        let index = FileIndex::load(&index_path);
+        assert_eq!(index.files.len(), 1);
+        assert!(index.files.contains_key(&*settled.to_string_lossy()));
+
+        // An unchanged fingerprint means the content is not read again
+        let mut index = FileIndex::load(&index_path);
+        index.files.values_mut().next().unwrap().hash = "remembered".to_string();
+        index.save(&index_path);
+        assert_ne!(indexed.calculate_hash()?, hash);
+
+        // A changed size or modification time means it is
+        create_test_file(dir_path, "settled.txt", b"content1")?;
+        assert_eq!(indexed.calculate_hash()?, hash);
+        create_test_file(dir_path, "fresh.txt", b"changed")?;
+        assert_ne!(indexed.calculate_hash()?, hash);
+        assert_eq!(indexed.calculate_hash()?, plain.calculate_hash()?);
+
+        // Deleted files are forgotten, and an index from another scheme is ignored
+        age(&settled)?;
+        indexed.calculate_hash()?;
+        fs::remove_file(&settled)?;
+        indexed.calculate_hash()?;
+        assert!(FileIndex::load(&index_path).files.is_empty());
+        fs::write(&index_path, r#"{"version": "v1", "files": {}}"#)?;
+        assert_eq!(FileIndex::load(&index_path).version, "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_calculate_hash_in_parallel() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        for i in 0..PARALLEL_THRESHOLD * 2 {
+            create_test_file(
+                dir_path,
+                &format!("file{}.txt", i),
+                i.to_string().as_bytes(),
+            )?;
+        }
+
+        let tracker = InputTracker::new(dir_path, vec!["*.txt".to_string()]);
+        let mut files = tracker.get_input_files()?;
+        files.sort();
+        let paths: Vec<&Path> = files.iter().map(PathBuf::as_path).collect();
+
+        // Threads return the hashes in file order
+        let parallel = hash_contents(&paths)?;
+        let serial: Vec<String> = paths
+            .chunks(PARALLEL_THRESHOLD)
+            .map(hash_contents)
+            .collect::<Result<Vec<_>>>()?
+            .concat();
+        assert_eq!(parallel, serial);
+        assert_eq!(parallel[0], format!("{:x}", Sha256::digest(b"0")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_calculate_hash_of_large_files() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        let content: Vec<u8> = (0..12 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let large = create_test_file(dir_path, "large.bin", &content)?;
+        let small = create_test_file(dir_path, "small.bin", b"content")?;
+
+        // Files of any size are hashed, in chunks, to the digest of their
+        // whole content
+        assert_eq!(
+            hash_file(&large)?,
+            format!("{:x}", Sha256::digest(&content))
+        );
+
+        // Keys are the same as when contents were read whole
+        let mut expected = Sha256::new();
+        expected.update(format!("{}\n", HASH_VERSION));
+        for (file, content) in [(&large, content.as_slice()), (&small, b"content")] {
+            expected.update(format!(
+                "{}:{:x}\n",
+                file.to_string_lossy(),
+                Sha256::digest(content)
+            ));
+        }
+        let tracker = InputTracker::new(dir_path, vec!["*.bin".to_string()])
+            .with_large_file_warning(Some(10 * 1024 * 1024));
+        let sink = Arc::new(RecordingSink::default());
+        let hash = progress::with_sink(sink.clone(), || tracker.calculate_hash())?;
+        assert_eq!(hash, format!("{:x}", expected.finalize()));
+
+        // Only the file over the threshold is reported
+        let messages = sink.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].level, Level::Warn);
+        assert!(
+            messages[0].text.contains("input large.bin is 12.0 MiB"),
+            "{}",
+            messages[0].text
+        );
+
+        // A threshold of 0 turns the warning off
+        let tracker =
+            InputTracker::new(dir_path, vec!["*.bin".to_string()]).with_large_file_warning(Some(0));
+        let sink = Arc::new(RecordingSink::default());
+        assert_eq!(
+            progress::with_sink(sink.clone(), || tracker.calculate_hash())?,
+            hash
+        );
+        assert!(sink.messages().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_excluded_dirs() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+
+        create_test_file(dir_path, "file1.txt", b"content1")?;
+        fs::create_dir_all(dir_path.join(".cargonode/cache"))?;
+        create_test_file(&dir_path.join(".cargonode/cache"), "entry.txt", b"cached")?;
+
+        let tracker = InputTracker::new(dir_path, vec!["**/*".to_string()]);
+        let files = tracker.get_input_files()?;
+        assert_eq!(files, vec![dir_path.join("file1.txt")]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_exclusions() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+
+        create_test_file(dir_path, "index.js", b"main")?;
+        for dir in ["node_modules/express", "packages/a/node_modules/b", ".git"] {
+            fs::create_dir_all(dir_path.join(dir))?;
+            create_test_file(&dir_path.join(dir), "index.js", b"dependency")?;
+        }
+
+        let tracker = InputTracker::new(dir_path, vec!["**/*.js".to_string()]);
+        assert_eq!(tracker.get_input_files()?, vec![dir_path.join("index.js")]);
+
+        // Installing packages must not change the hash
+        let hash = tracker.calculate_hash()?;
+        create_test_file(&dir_path.join("node_modules/express"), "new.js", b"new")?;
+        assert_eq!(tracker.calculate_hash()?, hash);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_relative_state_dir() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+        create_test_file(dir_path, "index.js", b"main")?;
+
+        // As with `CARGONODE_HOME=cn-home`
+        let state_dir = crate::utils::state_dir_with(dir_path, Some("cn-home".into()));
+        fs::create_dir_all(state_dir.join("cache"))?;
+        create_test_file(&state_dir.join("cache"), "entry.json", b"{}")?;
+
+        let tracker = InputTracker::new(dir_path, vec!["**/*".to_string()])
+            .with_state_dir(&state_dir)
+            .with_index(state_dir.join("cache").join(INPUT_INDEX_FILE));
+        assert_eq!(tracker.get_input_files()?, vec![dir_path.join("index.js")]);
+
+        // Writing the cache, journal or lock file does not change the hash
+        let hash = tracker.calculate_hash()?;
+        create_test_file(&state_dir, "lock", b"1234")?;
+        create_test_file(&state_dir.join("cache"), "other.json", b"{}")?;
+        assert_eq!(tracker.calculate_hash()?, hash);
+
+        let matcher = tracker.matcher()?;
+        assert!(matcher.matches(&dir_path.join("index.js")));
+        assert!(!matcher.matches(&state_dir.join("lock")));
+
+        // A state directory outside the project is left alone
+        let elsewhere = TempDir::new()?;
+        let outside =
+            InputTracker::new(dir_path, vec!["**/*".to_string()]).with_state_dir(elsewhere.path());
+        assert!(outside.excludes.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_patterns_outside_project() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project = temp_dir.path().join("app");
+        let shared = temp_dir.path().join("shared");
+        let assets = TempDir::new()?;
+        fs::create_dir_all(project.join("src"))?;
+        fs::create_dir_all(shared.join("node_modules"))?;
+        create_test_file(&project.join("src"), "main.js", b"main")?;
+        create_test_file(&shared, "util.js", b"util")?;
+        create_test_file(&shared.join("node_modules"), "dep.js", b"dep")?;
+        create_test_file(assets.path(), "logo.svg", b"<svg/>")?;
+
+        let tracker = InputTracker::new(
+            &project,
+            vec![
+                "src/**/*".to_string(),
+                "../shared/**/*.js".to_string(),
+                format!("{}/*.svg", assets.path().display()),
+            ],
+        );
+        let mut files: Vec<PathBuf> = tracker
+            .get_input_files()?
+            .iter()
+            .map(|file| normalize(file))
+            .collect();
+        files.sort();
+        let mut expected = vec![
+            normalize(&project.join("src/main.js")),
+            normalize(&shared.join("util.js")),
+            normalize(&assets.path().join("logo.svg")),
+        ];
+        expected.sort();
+        assert_eq!(files, expected);
+
+        // Changing a file outside the project changes the hash
+        let hash = tracker.calculate_hash()?;
+        create_test_file(&shared, "util.js", b"changed")?;
+        assert_ne!(tracker.calculate_hash()?, hash);
+        Ok(())
+    }
+
+    #[test]
+    fn test_excludes_and_gitignore() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir_path = temp_dir.path();
+
+        for dir in ["src/generated", "dist", "coverage"] {
+            fs::create_dir_all(dir_path.join(dir))?;
+            create_test_file(&dir_path.join(dir), "index.js", b"content")?;
+        }
+        create_test_file(&dir_path.join("src"), "main.js", b"content")?;
+        create_test_file(dir_path, ".gitignore", b"/coverage\n")?;
+
+        let tracker = InputTracker::new(dir_path, vec!["**/*.js".to_string()])
+            .with_excludes(vec!["dist/**/*".to_string(), "src/generated".to_string()]);
+        assert_eq!(
+            tracker.get_input_files()?,
+            vec![dir_path.join("src/main.js")]
+        );
+
+        let tracker = InputTracker::new(dir_path, vec!["**/*.js".to_string()])
+            .with_excludes(vec!["[".to_string()]);
+        assert!(matches!(
+            tracker.get_input_files(),
+            Err(Error::Input { .. })
+        ));
+
+        Ok(())
+    }
+
     #[test]
     fn test_empty_patterns() -> Result<()> {
         let temp_dir = TempDir::new()?;
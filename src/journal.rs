@@ -0,0 +1,527 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{progress, utils, Result};
+
+/// Default journal directory, relative to the project root
+pub const JOURNAL_DIR: &str = ".cargonode/journal";
+
+/// Get the journal directory of the project at `project_dir`
+///
+/// This is [`JOURNAL_DIR`] unless `CARGONODE_HOME` relocates it.
+pub fn journal_dir(project_dir: &Path) -> PathBuf {
+    utils::state_dir(project_dir).join("journal")
+}
+
+/// Name of the file holding the journal entries
+const JOURNAL_FILE: &str = "journal.json";
+
+/// Name of the file locked while the journal is read or written
+const LOCK_FILE: &str = "journal.lock";
+
+/// Name a corrupt journal is moved to before starting a new one
+const BACKUP_FILE: &str = "journal.json.bak";
+
+/// Maximum number of entries kept; older entries are dropped first
+const MAX_ENTRIES: usize = 1000;
+
+/// What a journal entry records
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EntryKind {
+    /// A tool run
+    #[default]
+    Tool,
+    /// A hook run, named after its event
+    Hook,
+}
+
+impl EntryKind {
+    fn is_tool(&self) -> bool {
+        *self == EntryKind::Tool
+    }
+}
+
+/// A record of a single tool invocation
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// Name of the tool, or event of the hook
+    pub tool: String,
+
+    /// Whether a tool or a hook ran; entries recorded before hooks were
+    /// are tool runs
+    #[serde(default, skip_serializing_if = "EntryKind::is_tool")]
+    pub kind: EntryKind,
+
+    /// Command line that was (or would have been) executed
+    pub command: String,
+
+    /// Hash of the tool's inputs and command line
+    pub input_hash: String,
+
+    /// Exit code of the command
+    pub exit_code: i32,
+
+    /// Whether the result was served from the cache
+    pub from_cache: bool,
+
+    /// Seconds since the Unix epoch at which the entry was recorded
+    pub timestamp: u64,
+
+    /// Time the invocation took, in milliseconds; 0 in entries recorded
+    /// before durations were
+    #[serde(default)]
+    pub duration_ms: u64,
+
+    /// Steps that ran, for tools configured with `steps`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<StepEntry>,
+}
+
+/// A record of one step of a tool invocation
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StepEntry {
+    /// Command line of the step
+    pub command: String,
+
+    /// Exit code of the step
+    pub exit_code: i32,
+
+    /// Time the step took, in milliseconds
+    pub duration_ms: u64,
+}
+
+/// How often a tool's runs were served from the cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct HitRate {
+    /// Runs served from the cache
+    pub hits: usize,
+
+    /// All recorded runs
+    pub runs: usize,
+}
+
+impl HitRate {
+    /// Fraction of the runs served from the cache, between 0 and 1
+    pub fn rate(&self) -> f64 {
+        if self.runs == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / self.runs as f64
+    }
+}
+
+/// How often a tool ran and how long it took
+///
+/// Durations only cover the runs that executed, as a run served from the
+/// cache takes no time worth reporting. Entries recorded before durations
+/// were have none and are left out of them too.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ToolStats {
+    /// All recorded runs
+    pub runs: usize,
+
+    /// Runs served from the cache
+    pub hits: usize,
+
+    /// Mean duration of the executed runs, in milliseconds
+    pub mean_ms: u64,
+
+    /// Median duration of the executed runs, in milliseconds
+    pub median_ms: u64,
+
+    /// Longest duration of the executed runs, in milliseconds
+    pub max_ms: u64,
+}
+
+impl ToolStats {
+    /// Aggregate the runs of one tool
+    fn from_entries<'a>(entries: impl IntoIterator<Item = &'a JournalEntry>) -> Self {
+        let mut stats = Self::default();
+        let mut durations = Vec::new();
+        for entry in entries {
+            stats.runs += 1;
+            if entry.from_cache {
+                stats.hits += 1;
+            } else if entry.duration_ms > 0 {
+                durations.push(entry.duration_ms);
+            }
+        }
+        if durations.is_empty() {
+            return stats;
+        }
+
+        durations.sort_unstable();
+        let middle = durations.len() / 2;
+        stats.median_ms = if durations.len() % 2 == 0 {
+            (durations[middle - 1] + durations[middle]) / 2
+        } else {
+            durations[middle]
+        };
+        stats.mean_ms = durations.iter().sum::<u64>() / durations.len() as u64;
+        stats.max_ms = durations[durations.len() - 1];
+        stats
+    }
+}
+
+/// History of tool invocations
+pub struct Journal {
+    /// Directory holding the journal file
+    dir: PathBuf,
+}
+
+impl Journal {
+    /// Create a journal stored in `dir`
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory holding the journal file
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new Journal instance
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.dir.join(JOURNAL_FILE)
+    }
+
+    /// Lock the journal against other processes until the returned file is dropped
+    fn lock(&self) -> Result<File> {
+        fs::create_dir_all(&self.dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.dir.join(LOCK_FILE))?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    /// Read all entries, oldest first
+    ///
+    /// A corrupt journal is moved to `journal.json.bak` with a warning and
+    /// treated as empty, so one bad write cannot break every later run.
+    pub fn read_entries(&self) -> Result<Vec<JournalEntry>> {
+        if !self.journal_path().exists() {
+            return Ok(Vec::new());
+        }
+
+        let _lock = self.lock()?;
+        self.read_locked()
+    }
+
+    fn read_locked(&self) -> Result<Vec<JournalEntry>> {
+        let path = self.journal_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&path)?;
+        match serde_json::from_str(&content) {
+            Ok(entries) => Ok(entries),
+            Err(e) => {
+                let backup = self.dir.join(BACKUP_FILE);
+                fs::rename(&path, &backup)?;
+                progress::warn(&format!(
+                    "journal {} is corrupt ({}); moved it to {} and started a new one",
+                    path.display(),
+                    e,
+                    backup.display()
+                ))?;
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Replace all entries
+    pub fn write_entries(&self, entries: &[JournalEntry]) -> Result<()> {
+        let _lock = self.lock()?;
+        self.write_locked(entries)
+    }
+
+    fn write_locked(&self, entries: &[JournalEntry]) -> Result<()> {
+        // Readers never see a partially written journal
+        utils::write_atomic(&self.journal_path(), serde_json::to_string_pretty(entries)?)
+    }
+
+    /// Append an entry
+    ///
+    /// The journal stays locked from reading to writing, so concurrent
+    /// cargonode processes never lose each other's entries.
+    pub fn add_entry(&self, entry: JournalEntry) -> Result<()> {
+        let _lock = self.lock()?;
+        let mut entries = self.read_locked()?;
+        entries.push(entry);
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+        }
+        self.write_locked(&entries)
+    }
+
+    /// Count the runs of each tool and how many were served from the cache
+    ///
+    /// Hook runs are left out.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<BTreeMap<String, HitRate>>` - Hits and runs, keyed by tool
+    pub fn hit_rate_by_tool(&self) -> Result<BTreeMap<String, HitRate>> {
+        let mut rates: BTreeMap<String, HitRate> = BTreeMap::new();
+        for entry in self
+            .read_entries()?
+            .into_iter()
+            .filter(|entry| entry.kind.is_tool())
+        {
+            let rate = rates.entry(entry.tool).or_default();
+            rate.runs += 1;
+            rate.hits += usize::from(entry.from_cache);
+        }
+        Ok(rates)
+    }
+
+    /// Aggregate the recorded runs of each tool
+    ///
+    /// Hook runs are left out.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<BTreeMap<String, ToolStats>>` - Run counts and durations,
+    ///   keyed by tool
+    pub fn stats_by_tool(&self) -> Result<BTreeMap<String, ToolStats>> {
+        let mut by_tool: BTreeMap<String, Vec<JournalEntry>> = BTreeMap::new();
+        for entry in self
+            .read_entries()?
+            .into_iter()
+            .filter(|entry| entry.kind.is_tool())
+        {
+            by_tool.entry(entry.tool.clone()).or_default().push(entry);
+        }
+        Ok(by_tool
+            .into_iter()
+            .map(|(tool, entries)| (tool, ToolStats::from_entries(&entries)))
+            .collect())
+    }
+
+    /// Remove all entries
+    pub fn clear(&self) -> Result<()> {
+        let path = self.journal_path();
+        if path.exists() {
+            let _lock = self.lock()?;
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn entry(tool: &str, from_cache: bool) -> JournalEntry {
+        JournalEntry {
+            tool: tool.to_string(),
+            kind: EntryKind::Tool,
+            command: "echo test".to_string(),
+            input_hash: "abc".to_string(),
+            exit_code: 0,
+            from_cache,
+            timestamp: 0,
+            duration_ms: 0,
+            steps: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_add_and_read_entries() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let journal = Journal::new(&temp_dir.path().join("journal"));
+
+        assert!(journal.read_entries()?.is_empty());
+
+        journal.add_entry(entry("build", false))?;
+        journal.add_entry(entry("build", true))?;
+
+        let entries = journal.read_entries()?;
+        assert_eq!(entries.len(), 2);
+        assert!(!entries[0].from_cache);
+        assert!(entries[1].from_cache);
+
+        journal.clear()?;
+        assert!(journal.read_entries()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hit_rate_by_tool() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let journal = Journal::new(temp_dir.path());
+        assert!(journal.hit_rate_by_tool()?.is_empty());
+
+        for (tool, from_cache) in [
+            ("build", false),
+            ("build", true),
+            ("build", true),
+            ("test", false),
+        ] {
+            journal.add_entry(entry(tool, from_cache))?;
+        }
+
+        let rates = journal.hit_rate_by_tool()?;
+        assert_eq!(rates["build"], HitRate { hits: 2, runs: 3 });
+        assert_eq!(rates["test"].rate(), 0.0);
+        assert!((rates["build"].rate() - 2.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!(HitRate::default().rate(), 0.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats_by_tool() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let journal = Journal::new(temp_dir.path());
+        assert!(journal.stats_by_tool()?.is_empty());
+
+        for (tool, from_cache, duration_ms) in [
+            ("test", false, 5_000),
+            ("test", false, 90_000),
+            ("test", true, 3),
+            ("test", false, 6_000),
+            ("build", false, 1_000),
+            ("build", false, 3_000),
+            // Recorded before durations were
+            ("build", false, 0),
+            ("lint", true, 2),
+        ] {
+            journal.add_entry(JournalEntry {
+                duration_ms,
+                ..entry(tool, from_cache)
+            })?;
+        }
+
+        let stats = journal.stats_by_tool()?;
+        assert_eq!(
+            stats["test"],
+            ToolStats {
+                runs: 4,
+                hits: 1,
+                mean_ms: 33_666,
+                median_ms: 6_000,
+                max_ms: 90_000,
+            }
+        );
+        // An even number of runs has the mean of the middle two as median,
+        // and runs without a duration count only as runs
+        assert_eq!(
+            stats["build"],
+            ToolStats {
+                runs: 3,
+                hits: 0,
+                mean_ms: 2_000,
+                median_ms: 2_000,
+                max_ms: 3_000,
+            }
+        );
+        // Cached runs count as runs but not towards durations
+        assert_eq!(
+            stats["lint"],
+            ToolStats {
+                runs: 1,
+                hits: 1,
+                ..ToolStats::default()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_entries_without_duration() {
+        let entries: Vec<JournalEntry> = serde_json::from_str(
+            r#"[{"tool": "build", "command": "tsc", "input_hash": "abc",
+                 "exit_code": 0, "from_cache": false, "timestamp": 0}]"#,
+        )
+        .unwrap();
+        assert_eq!(entries[0].duration_ms, 0);
+    }
+
+    #[test]
+    fn test_entries_are_bounded() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let journal = Journal::new(temp_dir.path());
+
+        let entries: Vec<_> = (0..MAX_ENTRIES).map(|_| entry("old", false)).collect();
+        journal.write_entries(&entries)?;
+        journal.add_entry(entry("new", false))?;
+
+        let entries = journal.read_entries()?;
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries.last().unwrap().tool, "new");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_add_entry() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path().join("journal");
+
+        std::thread::scope(|scope| {
+            for thread in 0..8 {
+                let dir = &dir;
+                scope.spawn(move || {
+                    // Each thread opens the journal itself, like separate processes
+                    let journal = Journal::new(dir);
+                    for _ in 0..25 {
+                        journal
+                            .add_entry(entry(&format!("tool-{}", thread), false))
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let entries = Journal::new(&dir).read_entries()?;
+        assert_eq!(entries.len(), 200);
+        for thread in 0..8 {
+            let tool = format!("tool-{}", thread);
+            assert_eq!(entries.iter().filter(|e| e.tool == tool).count(), 25);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrupt_journal_is_backed_up() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let journal = Journal::new(temp_dir.path());
+        fs::write(temp_dir.path().join(JOURNAL_FILE), r#"[{"tool": "bu"#)?;
+
+        assert!(journal.read_entries()?.is_empty());
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(BACKUP_FILE))?,
+            r#"[{"tool": "bu"#
+        );
+
+        journal.add_entry(entry("build", false))?;
+        assert_eq!(journal.read_entries()?.len(), 1);
+
+        // Only the journal, its backup and the lock file; no temporary file
+        let mut names: Vec<_> = fs::read_dir(temp_dir.path())?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<Result<_>>()?;
+        names.sort();
+        assert_eq!(names, [JOURNAL_FILE, BACKUP_FILE, LOCK_FILE]);
+
+        Ok(())
+    }
+}
@@ -1,12 +1,32 @@
+pub mod cache;
+pub mod ci;
 pub mod commands;
 pub mod config;
+pub mod dependency;
 pub mod error;
+pub mod hooks;
 pub mod inputs;
+pub mod journal;
+pub mod lock;
+pub mod manifest_merge;
+pub mod node;
+pub mod npmrc;
 pub mod outputs;
+pub mod pack;
+pub mod package_manager;
+pub mod process;
 pub mod progress;
+pub mod prompt;
+pub mod registry;
+pub mod suggest;
 pub mod template;
+pub mod template_cache;
+pub mod test_output;
 pub mod utils;
 
+#[cfg(test)]
+mod test_utils;
+
 pub use error::Error;
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -25,18 +45,22 @@ mod tests {
 
         let config_err = error::Error::Config {
             message: "invalid config".to_string(),
+            hint: None,
         };
         let config_err_str = format!("{}", config_err);
         assert!(config_err_str.contains("Configuration error"));
         assert!(config_err_str.contains("Error: invalid config"));
-        assert!(config_err_str.contains("Suggestion:"));
+        assert!(!config_err_str.contains("Suggestion:"));
+        assert_eq!(config_err.suggestion(), None);
 
+        // The suggestion line only appears with a hint
         let input_err = error::Error::Input {
             message: "invalid input".to_string(),
+            hint: Some("Quote the pattern.".to_string()),
         };
         let input_err_str = format!("{}", input_err);
         assert!(input_err_str.contains("Input error"));
         assert!(input_err_str.contains("Error: invalid input"));
-        assert!(input_err_str.contains("Suggestion:"));
+        assert!(input_err_str.ends_with("\n\nSuggestion: Quote the pattern."));
     }
 }
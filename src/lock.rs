@@ -0,0 +1,248 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use crate::{progress, utils, Result};
+
+/// Name of the lock file in the state directory
+const LOCK_FILE: &str = "lock";
+
+/// Time waited for another cargonode process unless configured otherwise
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Interval at which a held lock is tried again
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Get the lock file of the project at `project_dir`
+///
+/// A state directory outside the project, such as an absolute
+/// `CARGONODE_HOME`, may be shared by several projects; each then gets a
+/// lock file named after a hash of its path, so they don't block each other.
+pub fn lock_path(project_dir: &Path) -> PathBuf {
+    lock_path_with(project_dir, env::var_os(utils::HOME_ENV))
+}
+
+fn lock_path_with(project_dir: &Path, home: Option<OsString>) -> PathBuf {
+    let state_dir = utils::state_dir_with(project_dir, home);
+    if state_dir.starts_with(project_dir) {
+        return state_dir.join(LOCK_FILE);
+    }
+    let project_dir = std::path::absolute(project_dir).unwrap_or_else(|_| project_dir.into());
+    let hash = format!(
+        "{:x}",
+        Sha256::digest(project_dir.as_os_str().as_encoded_bytes())
+    );
+    state_dir.join(format!("{}-{}", LOCK_FILE, &hash[..16]))
+}
+
+/// Exclusive hold on a project's cache, journal and tool outputs
+///
+/// The lock is released when the guard is dropped, including while
+/// unwinding from a panic. The operating system releases it when the
+/// process exits, so a process that was killed or interrupted never leaves
+/// a lock behind; only its PID stays in the file.
+#[derive(Debug)]
+pub struct ProjectLock {
+    file: File,
+}
+
+impl Drop for ProjectLock {
+    fn drop(&mut self) {
+        // Closing the file releases the lock as well; unlocking first lets a
+        // waiting process in without waiting for the close
+        let _ = self.file.unlock();
+    }
+}
+
+/// PID recorded by the process that holds, or last held, the lock
+fn recorded_pid(file: &mut File) -> Option<u32> {
+    let mut content = String::new();
+    file.seek(SeekFrom::Start(0)).ok()?;
+    file.read_to_string(&mut content).ok()?;
+    content.trim().parse().ok()
+}
+
+/// Whether a process with this PID is running
+fn is_running(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        // SAFETY: signal 0 only checks that the process exists
+        let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+        result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        true
+    }
+}
+
+/// Lock the project at `project_dir` against other cargonode processes
+///
+/// While another process holds the lock, a message naming it is printed once
+/// and the lock is tried again until `timeout` has passed. A PID left in the
+/// lock file by a process that is no longer running does not count as a
+/// holder.
+///
+/// # Arguments
+///
+/// * `project_dir` - Directory of the project to lock
+/// * `timeout` - How long to wait for another process; zero fails at once
+///
+/// # Returns
+///
+/// * `Result<ProjectLock>` - The held lock, or `Error::ProjectLocked` if the
+///   project is still locked once `timeout` has passed
+pub fn acquire(project_dir: &Path, timeout: Duration) -> Result<ProjectLock> {
+    let path = lock_path(project_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(&path)?;
+
+    let start = Instant::now();
+    let mut announced = false;
+    loop {
+        match file.try_lock() {
+            Ok(()) => {
+                file.set_len(0)?;
+                file.seek(SeekFrom::Start(0))?;
+                write!(file, "{}", process::id())?;
+                file.flush()?;
+                return Ok(ProjectLock { file });
+            }
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        // The holder may not have recorded its PID yet, leaving a dead one
+        let pid = recorded_pid(&mut file).filter(|pid| is_running(*pid));
+        if start.elapsed() >= timeout {
+            return Err(Error::ProjectLocked { path, pid });
+        }
+        if !announced {
+            announced = true;
+            let holder = pid.map(|pid| format!(" (pid {})", pid)).unwrap_or_default();
+            progress::status(
+                "Blocking",
+                &format!("waiting for other cargonode process{}", holder),
+            )?;
+        }
+        thread::sleep(RETRY_INTERVAL.min(timeout.saturating_sub(start.elapsed())));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_acquire_waits_for_holder() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project_dir = temp_dir.path();
+        let (locked, wait_for_lock) = mpsc::channel();
+
+        thread::scope(|scope| -> Result<()> {
+            scope.spawn(move || {
+                let _lock = acquire(project_dir, Duration::ZERO).unwrap();
+                locked.send(()).unwrap();
+                thread::sleep(Duration::from_millis(300));
+            });
+            wait_for_lock.recv().unwrap();
+
+            // Failing fast names the holder
+            match acquire(project_dir, Duration::ZERO) {
+                Err(Error::ProjectLocked { pid, .. }) => assert_eq!(pid, Some(process::id())),
+                other => panic!("expected the project to be locked, got {:?}", other),
+            }
+
+            let start = Instant::now();
+            let _lock = acquire(project_dir, Duration::from_secs(10))?;
+            assert!(start.elapsed() >= Duration::from_millis(100));
+            Ok(())
+        })?;
+
+        assert_eq!(
+            fs::read_to_string(lock_path(project_dir))?,
+            process::id().to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lock_released_on_panic() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project_dir = temp_dir.path();
+
+        let result = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _lock = acquire(project_dir, Duration::ZERO).unwrap();
+                    panic!("tool crashed");
+                })
+                .join()
+        });
+        assert!(result.is_err());
+        acquire(project_dir, Duration::ZERO)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_lock_path_shared_home() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let home = TempDir::new()?;
+        let (a, b) = (temp_dir.path().join("a"), temp_dir.path().join("b"));
+
+        // A state directory inside the project has a plain lock file
+        assert_eq!(
+            lock_path_with(&a, Some("cn-home".into())),
+            a.join("cn-home").join(LOCK_FILE)
+        );
+
+        // A shared one has a lock file per project
+        let shared = Some(home.path().as_os_str().to_owned());
+        let lock_a = lock_path_with(&a, shared.clone());
+        let lock_b = lock_path_with(&b, shared.clone());
+        assert_ne!(lock_a, lock_b);
+        assert_eq!(lock_a, lock_path_with(&a, shared));
+        assert_eq!(lock_a.parent(), Some(home.path()));
+        assert!(lock_a
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("lock-") && name.len() == 21));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dead_pid_does_not_block() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let project_dir = temp_dir.path();
+        let mut child = process::Command::new("true").spawn()?;
+        child.wait()?;
+        assert!(!is_running(child.id()));
+
+        fs::create_dir_all(lock_path(project_dir).parent().unwrap())?;
+        fs::write(lock_path(project_dir), child.id().to_string())?;
+        let _lock = acquire(project_dir, Duration::ZERO)?;
+        assert!(is_running(process::id()));
+        Ok(())
+    }
+}
@@ -1,15 +1,432 @@
-use std::{env, path::PathBuf, process};
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process,
+    time::Duration,
+};
 
-use clap::{Parser, Subcommand};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use clap_cargo::style::CLAP_STYLING;
+use clap_complete::Shell;
 
-use cargonode::{commands, config, progress, utils};
+use cargonode::{
+    cache, ci, commands, config, dependency, hooks, journal, lock, npmrc, package_manager,
+    progress, prompt, registry,
+    template::{self, ProjectType},
+    template_cache, utils,
+};
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None, styles = CLAP_STYLING)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    styles = CLAP_STYLING,
+    after_help = "Exit codes: 1 other failure, 2 invalid configuration, 3 invalid input or failed check, 4 network, 5 missing or unsuitable program, 101 tool failed, 124 tool timed out, 128+N tool killed by signal N.\nRun `cargonode explain <CODE>` for the code printed with an error."
+)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Print more details; pass twice for debug messages
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Only print errors and warnings, and tool output when a tool fails
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Stop tools that run longer than SECS seconds
+    #[arg(long, global = true, value_name = "SECS")]
+    timeout: Option<u64>,
+
+    /// How to report progress: styled messages, or JSON events on stdout
+    #[arg(long, global = true, value_enum, default_value_t, value_name = "FMT")]
+    message_format: progress::MessageFormat,
+
+    /// When to draw progress bars [env: CARGONODE_PROGRESS] [default: auto]
+    #[arg(long, global = true, value_enum, value_name = "WHEN")]
+    progress: Option<progress::ProgressMode>,
+
+    /// When to use colors, also in tool output; NO_COLOR and FORCE_COLOR apply unless passed [default: auto]
+    #[arg(long, global = true, value_enum, value_name = "WHEN")]
+    color: Option<progress::ColorChoice>,
+
+    /// Fail instead of warning when the cargonode configuration has unknown keys
+    #[arg(long, global = true)]
+    strict_config: bool,
+
+    /// Skip the `pre-*` and `post-*` hooks of the configuration
+    #[arg(long, global = true)]
+    no_hooks: bool,
+
+    /// Wait up to SECS seconds for another cargonode process running in the project [default: 300]
+    #[arg(long, global = true, value_name = "SECS")]
+    lock_timeout: Option<u64>,
+
+    /// Fail at once if another cargonode process is running in the project
+    #[arg(long, global = true, conflicts_with = "lock_timeout")]
+    no_wait: bool,
+}
+
+#[derive(Args, Default)]
+struct WorkspaceArgs {
+    /// Run in every package of the workspace
+    #[arg(long)]
+    workspace: bool,
+    /// Only run in this workspace package (can be repeated)
+    #[arg(short, long = "package", value_name = "NAME")]
+    packages: Vec<String>,
+    /// Stop at the first workspace package that fails
+    #[arg(long)]
+    fail_fast: bool,
+}
+
+/// Options shared by `new` and `init`; unset ones come from the user config
+#[derive(Args)]
+struct ProjectArgs {
+    /// Package name, scoped like @acme/tools or not (defaults to the directory name)
+    #[arg(long)]
+    name: Option<String>,
+    /// Create a library package
+    #[arg(long)]
+    lib: bool,
+    /// Create a binary package, even if the user config defaults to libraries
+    #[arg(long, conflicts_with = "lib")]
+    bin: bool,
+    /// Create a command-line tool: an executable `src/main.js` mapped in `bin`
+    #[arg(long, conflicts_with_all = ["lib", "bin"])]
+    cli: bool,
+    /// Initialize a new repository of the given type [default: git]
+    #[arg(long, value_enum)]
+    vcs: Option<utils::Vcs>,
+    /// Scaffold from a template directory, .tar.gz archive, archive URL, git repository (gh:user/repo, URL ending in .git, optional #ref) or a name from `[templates]` in the user config
+    #[arg(long, value_name = "PATH|URL|NAME")]
+    template: Option<PathBuf>,
+    /// Scaffold from a template variant built into cargonode, e.g. typescript-app or javascript-lib
+    #[arg(long, value_name = "NAME", conflicts_with = "template")]
+    template_variant: Option<String>,
+    /// Scaffold TypeScript sources compiled with tsc (the typescript-app or typescript-lib variant)
+    #[arg(long, conflicts_with_all = ["template", "template_variant"])]
+    ts: bool,
+    /// SHA-256 digest the template archive URL must match
+    #[arg(long, value_name = "HEX", requires = "template")]
+    sha256: Option<String>,
+    /// Use only a cached copy of a template URL or repository, and skip the registry name check
+    #[arg(long)]
+    offline: bool,
+    /// Don't look up whether the package name is already taken on the registry
+    #[arg(long)]
+    no_registry_check: bool,
+    /// Template features to enable, separated by commas
+    #[arg(long, value_delimiter = ',', value_name = "FEATURES")]
+    features: Vec<String>,
+    /// Package manager used to install dependencies (detected by default)
+    #[arg(long, value_enum)]
+    package_manager: Option<package_manager::PackageManager>,
+    /// Skip installing dependencies
+    #[arg(long)]
+    no_install: bool,
+    /// SPDX license identifier (defaults to git config `cargonode.license`, npm's `init-license`, then MIT)
+    #[arg(long, value_name = "SPDX")]
+    license: Option<String>,
+    /// Author recorded in package.json and LICENSE (defaults to git's `user.name`)
+    #[arg(long, value_name = "NAME")]
+    author: Option<String>,
+    /// Node.js versions the project supports, e.g. `>=20` (defaults to the installed LTS line)
+    #[arg(long, value_name = "RANGE")]
+    node: Option<String>,
+    /// Scaffold a starter tool configuration in package.json or cargonode.toml
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    config_file: Option<config::ConfigFile>,
+    /// Generate a CI workflow that installs, builds and tests the project [default: none]
+    #[arg(long, value_enum, value_name = "PROVIDER")]
+    ci: Option<ci::CiProvider>,
+    /// Inside a workspace, add the new package to this member's dependencies
+    #[arg(long = "for", value_name = "MEMBER")]
+    dependent: Option<String>,
+    /// Accept a name that shadows a Node.js core module or imitates a popular package
+    #[arg(long)]
+    allow_confusing_name: bool,
+    /// Leave the partially created project in place if scaffolding fails
+    #[arg(long)]
+    keep_on_failure: bool,
+    /// Print the files, substitutions and commands without changing anything
+    #[arg(long)]
+    dry_run: bool,
+    /// Don't ask for options that were not passed; use their defaults
+    #[arg(short, long)]
+    yes: bool,
+}
+
+impl ProjectArgs {
+    /// Combine the flags with the user config, flags taking precedence
+    fn into_options(
+        self,
+        user: &config::UserConfig,
+    ) -> Result<commands::ProjectOptions, Box<dyn std::error::Error>> {
+        let mut project_type = match (self.lib, self.bin, self.cli) {
+            (true, _, _) => ProjectType::Library,
+            (_, true, _) => ProjectType::Binary,
+            (_, _, true) => ProjectType::Cli,
+            _ => user.project_type().unwrap_or_default(),
+        };
+        let template_variant = match (self.template_variant, self.ts) {
+            (Some(variant), _) => {
+                // Without a type flag, the variant decides what it scaffolds
+                let types = template::TemplateConfig::load_variant(&variant)?.project_types;
+                let flagged = self.lib || self.bin || self.cli;
+                if !flagged && !types.contains(&project_type) {
+                    project_type = types.first().copied().unwrap_or_default();
+                }
+                Some(variant)
+            }
+            (None, true) => Some(match project_type {
+                ProjectType::Library => "typescript-lib".to_string(),
+                _ => "typescript-app".to_string(),
+            }),
+            (None, false) => None,
+        };
+        // A variant replaces the default template of the user config
+        let template = self
+            .template
+            .or_else(|| {
+                template_variant
+                    .is_none()
+                    .then(|| user.template())
+                    .flatten()
+            })
+            .map(|template| user.resolve_template_name(&template))
+            .map(|template| {
+                template_cache::resolve_template(&template, self.offline, self.sha256.as_deref())
+            })
+            .transpose()?;
+
+        // A registry that cannot be resolved only skips the name check
+        let registry_url = if self.no_registry_check || self.offline {
+            None
+        } else {
+            match registry::resolve_registry_url(None) {
+                Ok(url) => Some(url),
+                Err(e) => {
+                    progress::warn(&format!(
+                        "skipping the registry name check: {}",
+                        e.message().lines().last().unwrap_or_default()
+                    ))?;
+                    None
+                }
+            }
+        };
+
+        Ok(commands::ProjectOptions {
+            name: self.name,
+            project_type,
+            vcs_config: Some(utils::VcsConfig {
+                vcs: self.vcs.or_else(|| user.vcs()).unwrap_or_default(),
+                ..Default::default()
+            }),
+            template,
+            template_variant,
+            package_manager: self.package_manager.or_else(|| user.package_manager()),
+            install: !self.no_install,
+            author: self.author.or_else(|| user.author().map(str::to_string)),
+            license: self.license.or_else(|| user.license().map(str::to_string)),
+            keep_on_failure: self.keep_on_failure,
+            dependent: self.dependent,
+            config_file: self.config_file,
+            ci: self.ci.unwrap_or_default(),
+            features: self.features,
+            force: false,
+            merge: false,
+            allow_confusing_name: self.allow_confusing_name,
+            dry_run: self.dry_run,
+            node: self.node,
+            registry_url,
+            hooks: user.hooks(),
+        })
+    }
+
+    /// Ask for the options that were not passed, unless `--yes` was given or
+    /// there is no terminal to ask on
+    ///
+    /// The answers are echoed as the equivalent command line, which creates
+    /// the same project without asking.
+    fn prompt(
+        &mut self,
+        command: &str,
+        path: &Path,
+        user: &config::UserConfig,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.yes || !prompt::is_interactive() {
+            return Ok(());
+        }
+        let mut prompter = prompt::Prompter::stdio();
+
+        if self.name.is_none() {
+            let default = utils::extract_package_name(path).unwrap_or_default();
+            let name = prompter.input("Package name", &default, utils::validate_package_name)?;
+            self.name = Some(name);
+        }
+        if !self.lib && !self.bin && !self.cli {
+            let kinds = ProjectType::value_variants();
+            let names: Vec<String> = kinds.iter().map(value_name).collect();
+            let default = user.project_type().unwrap_or_default();
+            let default = kinds.iter().position(|kind| *kind == default).unwrap_or(0);
+            let name = prompter.select("Project type", &names, default)?;
+            let kind = kinds.iter().copied().find(|kind| value_name(kind) == name);
+            self.lib = kind == Some(ProjectType::Library);
+            self.cli = kind == Some(ProjectType::Cli);
+            self.bin = !self.lib && !self.cli;
+        }
+        if self.package_manager.is_none() {
+            let kinds = package_manager::PackageManager::value_variants();
+            let names: Vec<String> = kinds.iter().map(value_name).collect();
+            // The one the project would get without an answer: the configured
+            // one, else the one its lockfiles or the launching command use
+            let default = match user.package_manager() {
+                Some(kind) => kind,
+                None => package_manager::detect_package_manager(path)?.kind,
+            };
+            let default = kinds.iter().position(|kind| *kind == default).unwrap_or(0);
+            let name = prompter.select("Package manager", &names, default)?;
+            self.package_manager = kinds.iter().copied().find(|kind| value_name(kind) == name);
+        }
+        if self.license.is_none() {
+            let default = user
+                .license()
+                .map(str::to_string)
+                .unwrap_or_else(|| commands::project_default_license(path));
+            self.license = Some(prompter.input("License", &default, |_| Ok(()))?);
+        }
+
+        progress::note(&format!(
+            "to create the same project without questions, run `{}`",
+            self.command_line(command, path)
+        ))?;
+        Ok(())
+    }
+
+    /// The command line that passes every option set on `self`
+    fn command_line(&self, command: &str, path: &Path) -> String {
+        let mut args = vec!["cargonode".to_string(), command.to_string()];
+        if command == "new" {
+            args.push(prompt::shell_quote(&path.display().to_string()));
+        }
+        let mut option = |flag: &str, value: Option<String>| {
+            if let Some(value) = value {
+                args.push(format!("--{}", flag));
+                args.push(prompt::shell_quote(&value));
+            }
+        };
+        option("name", self.name.clone());
+        option("vcs", self.vcs.as_ref().map(value_name));
+        option(
+            "template",
+            self.template.as_ref().map(|t| t.display().to_string()),
+        );
+        option("template-variant", self.template_variant.clone());
+        option("sha256", self.sha256.clone());
+        option(
+            "package-manager",
+            self.package_manager.as_ref().map(value_name),
+        );
+        option("license", self.license.clone());
+        option("author", self.author.clone());
+        option("node", self.node.clone());
+        option("for", self.dependent.clone());
+        option("config-file", self.config_file.as_ref().map(value_name));
+        option("ci", self.ci.as_ref().map(value_name));
+        option(
+            "features",
+            (!self.features.is_empty()).then(|| self.features.join(",")),
+        );
+        for (flag, set) in [
+            ("--lib", self.lib),
+            ("--bin", self.bin),
+            ("--cli", self.cli),
+            ("--ts", self.ts),
+            ("--offline", self.offline),
+            ("--no-registry-check", self.no_registry_check),
+            ("--no-install", self.no_install),
+            ("--allow-confusing-name", self.allow_confusing_name),
+            ("--keep-on-failure", self.keep_on_failure),
+            ("--dry-run", self.dry_run),
+        ] {
+            if set {
+                args.push(flag.to_string());
+            }
+        }
+        args.push("--yes".to_string());
+        args.join(" ")
+    }
+}
+
+/// The name clap accepts for a value of `T`
+fn value_name<T: ValueEnum>(value: &T) -> String {
+    value
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print a value from the user config
+    Get {
+        /// One of: type, vcs, package-manager, author, license, template, templates.<name>
+        key: String,
+    },
+    /// Store a value in the user config
+    Set {
+        /// One of: type, vcs, package-manager, author, license, template, templates.<name>
+        key: String,
+        /// The new value
+        value: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateCommands {
+    /// List the built-in template and the templates named in the user config
+    List,
+    /// Print the manifest of a template: metadata, files, dependencies and features
+    Show {
+        /// `default`, a built-in variant such as `typescript-app`, a name from `[templates]` in the user config, or a template path or URL
+        name: String,
+        /// Use only a cached copy of a template URL or repository
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Check a template directory or archive, reporting every problem
+    Validate {
+        /// Template directory or .tar.gz archive
+        path: PathBuf,
+        /// Print the result as one line of JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheCommands {
+    /// Remove cached tool runs
+    Clear {
+        /// Only remove entries for this tool
+        #[arg(long)]
+        tool: Option<String>,
+    },
+    /// Remove expired cached runs and trim the cache to its size budget
+    Prune,
+    /// Show entries, size and age of the cache, and each tool's hit rate
+    Stats {
+        /// Print the statistics as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -18,34 +435,130 @@ enum Commands {
     New {
         /// The path to create the project in
         path: PathBuf,
-        /// Create a library package
-        #[arg(long)]
-        lib: bool,
-        /// Initialize a new repository of the given type
-        #[arg(long, value_enum, default_value_t = utils::Vcs::default())]
-        vcs: utils::Vcs,
+        #[command(flatten)]
+        project: ProjectArgs,
     },
     /// Create a new Node.js project in an existing directory
     Init {
-        /// Create a library package
+        #[command(flatten)]
+        project: ProjectArgs,
+        /// Initialize even if package.json exists, only adding missing files
+        #[arg(long)]
+        force: bool,
+        /// Merge the scaffolded fields into an existing package.json, keeping its name, version, dependencies and scripts
+        #[arg(long, conflicts_with = "import")]
+        merge: bool,
+        /// Generate the tool configuration from the scripts of the existing package.json instead of scaffolding
+        #[arg(long)]
+        import: bool,
+    },
+    /// Add dependencies to package.json
+    Add {
+        /// Packages to add, optionally with a version requirement (e.g. express@^4.18.0)
+        #[arg(required = true)]
+        packages: Vec<String>,
+        /// Add as a development dependency
+        #[arg(short = 'D', long, group = "kind")]
+        dev: bool,
+        /// Add as a peer dependency
+        #[arg(long = "save-peer", visible_alias = "peer", group = "kind")]
+        peer: bool,
+        /// Add as an optional dependency
+        #[arg(long = "save-optional", visible_alias = "optional", group = "kind")]
+        optional: bool,
+        /// Resolve versions from cached registry data only
+        #[arg(long)]
+        offline: bool,
+        /// Registry to resolve packages from (defaults to NPM_CONFIG_REGISTRY or npmjs.org)
+        #[arg(long, value_name = "URL")]
+        registry: Option<String>,
+        /// Print what would change without modifying package.json
+        #[arg(long)]
+        dry_run: bool,
+        /// Update package.json without running the package manager
+        #[arg(long)]
+        no_install: bool,
+    },
+    /// Remove dependencies from package.json
+    Remove {
+        /// Packages to remove
+        #[arg(required = true)]
+        packages: Vec<String>,
+        /// Print what would change without modifying package.json
+        #[arg(long)]
+        dry_run: bool,
+        /// Update package.json without running the package manager
+        #[arg(long)]
+        no_install: bool,
+    },
+    /// Upgrade dependencies in package.json to newer versions
+    Upgrade {
+        /// Only upgrade these packages (defaults to every dependency)
+        packages: Vec<String>,
+        /// Move to the latest version even when it is outside the current range
+        #[arg(long)]
+        latest: bool,
+        /// Record the new ranges in package.json
+        #[arg(long, conflicts_with = "check")]
+        write: bool,
+        /// Exit with an error when upgrades are available, without changing anything
+        #[arg(long)]
+        check: bool,
+        /// Resolve versions from cached registry data only
+        #[arg(long)]
+        offline: bool,
+        /// Registry to resolve packages from (defaults to NPM_CONFIG_REGISTRY or npmjs.org)
+        #[arg(long, value_name = "URL")]
+        registry: Option<String>,
+    },
+    /// Show what the registry knows about a package version
+    Info {
+        /// Package to look up, as NAME[@VERSION]; VERSION can be a range or dist-tag
+        package: String,
+        /// Print the metadata as a line of JSON
+        #[arg(long)]
+        json: bool,
+        /// Registry to look the package up in (defaults to NPM_CONFIG_REGISTRY or npmjs.org)
+        #[arg(long, value_name = "URL")]
+        registry: Option<String>,
+    },
+    /// Check the package and publish it with npm
+    Publish {
+        /// Run the checks without publishing
+        #[arg(long)]
+        dry_run: bool,
+        /// With --dry-run, list the files npm would pack, with their sizes
+        #[arg(long, requires = "dry_run")]
+        list_files: bool,
+        /// Registry to check and publish to (defaults to NPM_CONFIG_REGISTRY or npmjs.org)
+        #[arg(long, value_name = "URL")]
+        registry: Option<String>,
+    },
+    /// Bump the package version, then commit and tag it
+    Version {
+        /// New version, or the part of the current version to increment
+        #[arg(value_name = "major|minor|patch|prerelease|X.Y.Z")]
+        bump: commands::VersionBump,
+        /// Bump every package of the workspace
+        #[arg(long)]
+        workspace: bool,
+        /// Don't create a git commit and tag
         #[arg(long)]
-        lib: bool,
-        /// Initialize a new repository of the given type
-        #[arg(long, value_enum, default_value_t = utils::Vcs::default())]
-        vcs: utils::Vcs,
+        no_git: bool,
+        /// Bump even if the working tree has uncommitted changes
+        #[arg(long)]
+        allow_dirty: bool,
     },
     /// Run a specific tool
     Run {
         /// The tool to run
         tool: String,
-        /// Arguments to pass to the tool
-        _args: Vec<String>,
+        /// Arguments to pass to the tool (use `--` before flags meant for the tool)
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
         /// Force execution even if cached
         #[arg(long)]
         force: bool,
-        /// Print verbose output
-        #[arg(short, long)]
-        verbose: bool,
     },
     /// Check files for errors
     Check {
@@ -54,9 +567,29 @@ enum Commands {
         /// Force execution even if cached
         #[arg(long)]
         force: bool,
-        /// Print verbose output
-        #[arg(short, long)]
-        verbose: bool,
+        /// Run again whenever the tool's inputs change
+        #[arg(long, conflicts_with_all = ["workspace", "packages"])]
+        watch: bool,
+        /// Linter to run instead of the configured or detected one
+        #[arg(long, value_enum, value_name = "NAME")]
+        tool: Option<config::LintTool>,
+        #[command(flatten)]
+        selection: WorkspaceArgs,
+    },
+    /// Format files with the configured formatter, or the biome or prettier
+    /// the project is set up for
+    Fmt {
+        /// Paths to format (defaults to the whole project)
+        paths: Vec<PathBuf>,
+        /// Fail if files are not formatted instead of rewriting them
+        #[arg(long)]
+        check: bool,
+        /// Formatter to run instead of the configured or detected one
+        #[arg(long, value_enum, value_name = "NAME")]
+        tool: Option<config::LintTool>,
+        /// Force execution even if cached
+        #[arg(long)]
+        force: bool,
     },
     /// Build the project
     Build {
@@ -66,9 +599,11 @@ enum Commands {
         /// Force execution even if cached
         #[arg(long)]
         force: bool,
-        /// Print verbose output
-        #[arg(short, long)]
-        verbose: bool,
+        /// Run again whenever the tool's inputs change
+        #[arg(long, conflicts_with_all = ["workspace", "packages"])]
+        watch: bool,
+        #[command(flatten)]
+        selection: WorkspaceArgs,
     },
     /// Run tests
     Test {
@@ -78,51 +613,419 @@ enum Commands {
         /// Force execution even if cached
         #[arg(long)]
         force: bool,
-        /// Print verbose output
-        #[arg(short, long)]
-        verbose: bool,
+        /// Run again whenever the tool's inputs change
+        #[arg(long, conflicts_with_all = ["workspace", "packages"])]
+        watch: bool,
+        /// Also write the test runner's raw output to this file
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["workspace", "packages"])]
+        log_file: Option<PathBuf>,
+        #[command(flatten)]
+        selection: WorkspaceArgs,
+    },
+    /// Remove the files matching the configured tools' outputs
+    Clean {
+        /// Only remove the outputs of these tools (defaults to all)
+        tools: Vec<String>,
+        /// List what would be removed without removing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Also clear the cache and journal of tool runs
+        #[arg(long)]
+        cache: bool,
+    },
+    /// Print what cargonode knows about the project, for editors and scripts
+    Metadata {
+        /// Output format
+        #[arg(long, value_enum, default_value_t, value_name = "FMT")]
+        format: commands::MetadataFormat,
+    },
+    /// Show the history of tool runs
+    History {
+        /// Only show runs of this tool
+        #[arg(long)]
+        tool: Option<String>,
+        /// Maximum number of runs to show
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Print each run as a line of JSON
+        #[arg(long, conflicts_with = "verbose")]
+        json: bool,
+        /// Show run counts and durations per tool instead of the runs
+        #[arg(long, conflicts_with = "limit")]
+        stats: bool,
+    },
+    /// Check the environment for problems that would break cargonode
+    Doctor {
+        /// Print each check as a line of JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Manage the cache of tool runs
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
     },
+    /// List, inspect and check project templates
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommands,
+    },
+    /// Read or change defaults for new projects in the user config
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Explain an error code, or list every code with its exit code
+    Explain {
+        /// Error code printed with an error, e.g. CN_CONFIG
+        code: Option<String>,
+    },
+    /// Print a shell completion script
+    Completions {
+        /// Shell to complete for
+        shell: Shell,
+    },
+    /// Print the tools of the current project, for completion scripts
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        /// Only print tools starting with this prefix
+        #[arg(default_value = "")]
+        prefix: String,
+    },
+}
+
+/// Completion script for `shell`, completing `run` with the project's tools
+///
+/// The scripts complete tool names by calling `cargonode __complete`; the
+/// PowerShell and Elvish scripts only complete the static command line.
+fn completion_script(shell: Shell) -> String {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut Cli::command(), "cargonode", &mut script);
+    let script = String::from_utf8_lossy(&script).into_owned();
+
+    match shell {
+        Shell::Bash => script + BASH_TOOL_COMPLETION,
+        Shell::Zsh => {
+            script.replace(
+                "':tool -- The tool to run:_default'",
+                "':tool -- The tool to run:_cargonode_tools'",
+            ) + ZSH_TOOL_COMPLETION
+        }
+        Shell::Fish => script + FISH_TOOL_COMPLETION,
+        _ => script,
+    }
+}
+
+const BASH_TOOL_COMPLETION: &str = r#"
+_cargonode_with_tools() {
+    if [[ ${COMP_CWORD} -eq 2 && ${COMP_WORDS[1]} == run ]]; then
+        COMPREPLY=($(compgen -W "$(cargonode __complete 2>/dev/null)" -- "${COMP_WORDS[2]}"))
+        return 0
+    fi
+    _cargonode "$@"
+}
+complete -F _cargonode_with_tools -o bashdefault -o default cargonode
+"#;
+
+const ZSH_TOOL_COMPLETION: &str = r#"
+_cargonode_tools() {
+    local -a tools
+    tools=(${(f)"$(cargonode __complete 2>/dev/null)"})
+    _describe -t tools 'tool' tools
+}
+"#;
+
+const FISH_TOOL_COMPLETION: &str = r#"
+complete -c cargonode -n "__fish_seen_subcommand_from run" -f -a "(cargonode __complete (commandline -ct))"
+"#;
+
+/// Value of the last `--color` in `args`, read before clap parses them so that
+/// its help and errors are styled accordingly
+fn color_flag(args: impl IntoIterator<Item = OsString>) -> Option<progress::ColorChoice> {
+    let mut args = args
+        .into_iter()
+        .skip(1)
+        .filter_map(|arg| arg.into_string().ok())
+        .take_while(|arg| arg != "--");
+    let mut choice = None;
+    while let Some(arg) = args.next() {
+        let value = match arg.strip_prefix("--color") {
+            Some("") => args.next(),
+            Some(value) => value.strip_prefix('=').map(str::to_string),
+            None => continue,
+        };
+        if let Some(value) = value {
+            choice = progress::ColorChoice::from_str(&value, true)
+                .ok()
+                .or(choice);
+        }
+    }
+    choice
 }
 
 fn main() {
-    let cli = Cli::parse();
+    let color = progress::ColorChoice::from_env(color_flag(env::args_os()));
+    let matches = Cli::command()
+        .color(match color {
+            progress::ColorChoice::Auto => clap::ColorChoice::Auto,
+            progress::ColorChoice::Always => clap::ColorChoice::Always,
+            progress::ColorChoice::Never => clap::ColorChoice::Never,
+        })
+        .get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    progress::set_color_choice(color);
+    progress::set_message_format(cli.message_format);
+    progress::set_verbosity(progress::Verbosity::from_flags(cli.verbose, cli.quiet));
+    let progress_mode = cli.progress.or_else(|| {
+        let mode = env::var("CARGONODE_PROGRESS").ok()?;
+        progress::ProgressMode::from_str(&mode, true).ok()
+    });
+    progress::set_progress_mode(progress_mode.unwrap_or_default());
+    config::set_strict_config(cli.strict_config);
+    hooks::set_disabled(cli.no_hooks);
 
     if let Err(err) = run(cli) {
-        progress::write_message(&progress::format_error(&err.to_string())).unwrap();
-        process::exit(1);
+        let event = match err.downcast_ref::<cargonode::Error>() {
+            Some(err) => progress::Event::Error {
+                code: Some(err.code().to_string()),
+                message: err.message(),
+                suggestion: err.suggestion(),
+            },
+            None => progress::Event::Error {
+                code: None,
+                message: err.to_string(),
+                suggestion: None,
+            },
+        };
+        // The exit code still says what failed when the error cannot be
+        // written, as when the output is piped to a closed reader
+        let _ = progress::emit(&event);
+        let code = err
+            .downcast_ref::<cargonode::Error>()
+            .map_or(1, cargonode::Error::exit_code);
+        process::exit(code);
     }
 }
 
 fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    // Commands print their `--verbose` details from `-v` on
+    let verbose = cli.verbose > 0;
+    // Commands that run tools hold the project lock while they do
+    let lock_timeout = match (cli.no_wait, cli.lock_timeout) {
+        (true, _) => Duration::ZERO,
+        (false, Some(secs)) => Duration::from_secs(secs),
+        (false, None) => lock::DEFAULT_LOCK_TIMEOUT,
+    };
     match cli.command {
-        Commands::New { path, lib, vcs } => {
-            let config = utils::VcsConfig {
-                vcs,
-                ..Default::default()
-            };
-            commands::create_new_project(&path, lib, Some(config))?;
+        Commands::New { path, mut project } => {
+            let user = config::load_user_config(&config::user_config_path()?)?;
+            project.prompt("new", &path, &user)?;
+            commands::ProjectBuilder::new(path)
+                .options(project.into_options(&user)?)
+                .build()?;
         }
-        Commands::Init { lib, vcs } => {
-            let config = utils::VcsConfig {
-                vcs,
-                ..Default::default()
+        Commands::Init {
+            project,
+            force,
+            import: true,
+            ..
+        } => {
+            let project_dir = env::current_dir()?;
+            let config_file = match project.config_file {
+                Some(config_file) => config_file,
+                // Tools go to the standalone file that takes precedence
+                None if config::PROJECT_CONFIG_FILES
+                    .iter()
+                    .any(|file| project_dir.join(file).is_file()) =>
+                {
+                    config::ConfigFile::Toml
+                }
+                None if !project.yes && prompt::is_interactive() => {
+                    let choices = ["package.json", config::PROJECT_CONFIG_FILES[0]];
+                    let mut prompter = prompt::Prompter::stdio();
+                    match prompter.select("Write the configuration to", &choices, 0)? {
+                        "package.json" => config::ConfigFile::PackageJson,
+                        _ => config::ConfigFile::Toml,
+                    }
+                }
+                None => config::ConfigFile::PackageJson,
             };
-            commands::init_project(lib, Some(config))?;
+            commands::import_scripts(&commands::ImportOptions {
+                project_dir,
+                config_file,
+                force,
+                dry_run: project.dry_run,
+            })?;
         }
-        Commands::Run {
-            tool,
-            _args,
+        Commands::Init {
+            mut project,
             force,
-            verbose,
+            merge,
+            ..
+        } => {
+            let user = config::load_user_config(&config::user_config_path()?)?;
+            let current_dir = env::current_dir()?;
+            project.prompt("init", &current_dir, &user)?;
+            commands::ProjectBuilder::init(current_dir)
+                .options(project.into_options(&user)?)
+                .force(force)
+                .merge(merge)
+                .build()?;
+        }
+        Commands::Add {
+            packages,
+            dev,
+            peer,
+            optional,
+            offline,
+            registry,
+            dry_run,
+            no_install,
+        } => {
+            let kind = if dev {
+                dependency::DependencyKind::Dev
+            } else if peer {
+                dependency::DependencyKind::Peer
+            } else if optional {
+                dependency::DependencyKind::Optional
+            } else {
+                dependency::DependencyKind::Normal
+            };
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            let registry_url = registry::resolve_registry_url(registry.as_deref())?;
+            let mut client = registry::Client::with_registry(&registry_url)?.with_cache(
+                registry::CacheOptions {
+                    offline,
+                    ..Default::default()
+                },
+            );
+            let project_dir =
+                package_manager::find_project_root(&current_dir).unwrap_or(current_dir.clone());
+            if let Some(token) = npmrc::Npmrc::load(&project_dir).auth_token(&registry_url) {
+                client = client.with_token(token);
+            }
+            let options = commands::AddOptions {
+                kind,
+                dry_run,
+                install: !no_install,
+            };
+            commands::add_dependencies(&packages, &options, &current_dir, &client)?;
+        }
+        Commands::Remove {
+            packages,
+            dry_run,
+            no_install,
+        } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            let options = commands::RemoveOptions {
+                dry_run,
+                install: !no_install,
+            };
+            commands::remove_dependencies(&packages, &options, &current_dir)?;
+        }
+        Commands::Upgrade {
+            packages,
+            latest,
+            write,
+            check,
+            offline,
+            registry,
+        } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            let registry_url = registry::resolve_registry_url(registry.as_deref())?;
+            let mut client = registry::Client::with_registry(&registry_url)?.with_cache(
+                registry::CacheOptions {
+                    offline,
+                    ..Default::default()
+                },
+            );
+            let project_dir =
+                package_manager::find_project_root(&current_dir).unwrap_or(current_dir.clone());
+            if let Some(token) = npmrc::Npmrc::load(&project_dir).auth_token(&registry_url) {
+                client = client.with_token(token);
+            }
+            let options = commands::UpgradeOptions {
+                packages,
+                latest,
+                write,
+                check,
+            };
+            commands::upgrade_dependencies(&options, &current_dir, &client)?;
+        }
+        Commands::Info {
+            package,
+            json,
+            registry,
+        } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            let registry_url = registry::resolve_registry_url(registry.as_deref())?;
+            let mut client = registry::Client::with_registry(&registry_url)?;
+            let project_dir =
+                package_manager::find_project_root(&current_dir).unwrap_or(current_dir.clone());
+            if let Some(token) = npmrc::Npmrc::load(&project_dir).auth_token(&registry_url) {
+                client = client.with_token(token);
+            }
+            let info = commands::package_info(&package, &client)?;
+            commands::report_package_info(&info, json)?;
+        }
+        Commands::Publish {
+            dry_run,
+            list_files,
+            registry,
+        } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            let project_dir =
+                package_manager::find_project_root(&current_dir).unwrap_or(current_dir.clone());
+            // A missing or invalid package.json is reported by the checks
+            let manifest = fs::read_to_string(project_dir.join("package.json"))
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default();
+            let npmrc = npmrc::Npmrc::load(&project_dir);
+            let registry_url =
+                registry::resolve_package_registry(registry.as_deref(), &manifest, &npmrc)?;
+            let mut client = registry::Client::with_registry(&registry_url)?;
+            if let Some(token) = npmrc.auth_token(&registry_url) {
+                client = client.with_token(token);
+            }
+            let options = commands::PublishOptions {
+                dry_run,
+                registry,
+                list_files,
+            };
+            commands::publish(&options, &current_dir, &client)?;
+        }
+        Commands::Version {
+            bump,
+            workspace,
+            no_git,
+            allow_dirty,
         } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            let options = commands::VersionOptions {
+                bump,
+                git: !no_git,
+                allow_dirty,
+                workspace,
+            };
+            commands::bump_version(&options, &current_dir)?;
+        }
+        Commands::Run { tool, args, force } => {
             let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
             let options = commands::RunOptions {
                 project_dir: current_dir.clone(),
                 force,
                 verbose,
+                // The tool's output is hidden unless it fails
+                quiet: cli.quiet,
+                timeout: cli.timeout,
+                args,
+                log_file: None,
+                summarize_tests: false,
             };
 
             let config = config::load_config(&current_dir)?;
+            let _lock = lock::acquire(&current_dir, lock_timeout)?;
             let result = commands::run_tool(&tool, &config, &options)?;
             if !result.status.success() {
                 return Err(Box::new(cargonode::Error::CommandFailed {
@@ -134,46 +1037,362 @@ fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
         Commands::Check {
             paths,
             force,
-            verbose,
+            watch,
+            tool,
+            selection,
         } => {
             let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
-            let result = commands::check(&paths, &current_dir, force, verbose)?;
-            if !result.status.success() {
-                return Err(Box::new(cargonode::Error::CommandFailed {
-                    command: "check".to_string(),
-                    status: result.status,
-                }));
+            let run = |dir: &Path| commands::check(&paths, tool, dir, force, verbose, cli.timeout);
+            if watch {
+                watch_tool("check", &current_dir, lock_timeout, run)?;
+            } else {
+                run_in_selection("check", &current_dir, selection, lock_timeout, run)?;
             }
         }
+        Commands::Fmt {
+            paths,
+            check,
+            tool,
+            force,
+        } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            run_in_selection(
+                "fmt",
+                &current_dir,
+                WorkspaceArgs::default(),
+                lock_timeout,
+                |dir| commands::fmt(&paths, check, tool, dir, force, verbose, cli.timeout),
+            )?;
+        }
         Commands::Build {
             release,
             force,
-            verbose,
+            watch,
+            selection,
         } => {
             let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
-            let result = commands::build(release, &current_dir, force, verbose)?;
-            if !result.status.success() {
-                return Err(Box::new(cargonode::Error::CommandFailed {
-                    command: "build".to_string(),
-                    status: result.status,
-                }));
+            let run = |dir: &Path| commands::build(release, dir, force, verbose, cli.timeout);
+            if watch {
+                watch_tool("build", &current_dir, lock_timeout, run)?;
+            } else {
+                run_in_selection("build", &current_dir, selection, lock_timeout, run)?;
             }
         }
         Commands::Test {
             pattern,
             force,
-            verbose,
+            watch,
+            log_file,
+            selection,
         } => {
             let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
-            let result = commands::test(&pattern, &current_dir, force, verbose)?;
-            if !result.status.success() {
-                return Err(Box::new(cargonode::Error::CommandFailed {
-                    command: "test".to_string(),
-                    status: result.status,
-                }));
+            let run = |dir: &Path| {
+                commands::test(
+                    &pattern,
+                    dir,
+                    force,
+                    verbose,
+                    cli.timeout,
+                    log_file.as_deref(),
+                )
+            };
+            if watch {
+                watch_tool("test", &current_dir, lock_timeout, run)?;
+            } else {
+                run_in_selection("test", &current_dir, selection, lock_timeout, run)?;
+            }
+        }
+        Commands::Clean {
+            tools,
+            dry_run,
+            cache,
+        } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            let config = config::load_config(&current_dir)?;
+            let _lock = lock::acquire(&current_dir, lock_timeout)?;
+            let options = commands::CleanOptions {
+                tools,
+                dry_run,
+                cache,
+            };
+            commands::clean(&current_dir, &config, &options)?;
+        }
+        Commands::Metadata { format } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            commands::report_metadata(&commands::project_metadata(&current_dir)?, format)?;
+        }
+        Commands::History {
+            tool,
+            limit,
+            json,
+            stats,
+        } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            if stats {
+                commands::history_stats(
+                    &journal::journal_dir(&current_dir),
+                    tool.as_deref(),
+                    json,
+                )?;
+            } else {
+                commands::show_history(
+                    &journal::journal_dir(&current_dir),
+                    &cache::cache_dir(&current_dir),
+                    tool.as_deref(),
+                    limit,
+                    verbose,
+                    json,
+                )?;
+            }
+        }
+        Commands::Template {
+            command: TemplateCommands::List,
+        } => {
+            let user = config::load_user_config(&config::user_config_path()?)?;
+            commands::report_templates(&commands::list_templates(&user))?;
+        }
+        Commands::Template {
+            command: TemplateCommands::Show { name, offline },
+        } => {
+            let user = config::load_user_config(&config::user_config_path()?)?;
+            commands::report_template(&commands::find_template(&user, &name, offline)?)?;
+        }
+        Commands::Template {
+            command: TemplateCommands::Validate { path, json },
+        } => {
+            let validation = commands::validate_template(&path);
+            if let Err(err) = commands::report_validation(&validation, json) {
+                // The JSON already lists the problems; only the exit code is left
+                if json {
+                    process::exit(err.exit_code());
+                }
+                return Err(err.into());
+            }
+        }
+        Commands::Cache {
+            command: CacheCommands::Clear { tool },
+        } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            commands::clear_cache(&cache::cache_dir(&current_dir), tool.as_deref())?;
+        }
+        Commands::Cache {
+            command: CacheCommands::Prune,
+        } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            let config = config::load_config(&current_dir).unwrap_or_default();
+            commands::prune_cache(&cache::cache_dir(&current_dir), &config.cache)?;
+        }
+        Commands::Cache {
+            command: CacheCommands::Stats { json },
+        } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            commands::cache_stats(
+                &cache::cache_dir(&current_dir),
+                &journal::journal_dir(&current_dir),
+                json,
+            )?;
+        }
+        Commands::Doctor { json } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            let client = registry::Client::with_registry(&registry::resolve_registry_url(None)?)?
+                .with_timeout(Duration::from_secs(5))
+                .with_retry(registry::RetryPolicy::never());
+            let checks = commands::diagnose(&current_dir, &client);
+            if let Err(err) = commands::report_checks(&checks, json) {
+                // The JSON lines already say what failed; only the exit code is left
+                if json {
+                    process::exit(err.exit_code());
+                }
+                return Err(err.into());
+            }
+        }
+        Commands::Explain { code: None } => {
+            let list: String = cargonode::error::ERROR_CODES
+                .iter()
+                .map(|entry| {
+                    format!(
+                        "{:<30} {:>3}  {}\n",
+                        entry.code, entry.exit_code, entry.description
+                    )
+                })
+                .collect();
+            progress::write_message(list.trim_end())?;
+        }
+        Commands::Explain { code: Some(code) } => {
+            let entry =
+                cargonode::error::explain(&code).ok_or_else(|| cargonode::Error::Input {
+                    message: format!(
+                        "'{}' is not a cargonode error code; run `cargonode explain` to list them",
+                        code
+                    ),
+                    hint: None,
+                })?;
+            progress::write_message(&format!(
+                "{} (exit code {})\n\n{}\n\nSuggestion: {}",
+                entry.code, entry.exit_code, entry.description, entry.suggestion
+            ))?;
+        }
+        Commands::Completions { shell } => {
+            io::Write::write_all(&mut io::stdout(), completion_script(shell).as_bytes())?;
+        }
+        Commands::Complete { prefix } => {
+            let current_dir = env::current_dir().map_err(cargonode::Error::Io)?;
+            for tool in config::tool_names(&current_dir) {
+                if tool.starts_with(&prefix) {
+                    progress::write_message(&tool)?;
+                }
+            }
+        }
+        Commands::Config {
+            command: ConfigCommands::Get { key },
+        } => {
+            let user = config::load_user_config(&config::user_config_path()?)?;
+            match user.get(&key)? {
+                Some(value) => progress::write_message(value)?,
+                None => progress::note(&format!(
+                    "`{}` is not set in {}",
+                    key,
+                    user.path().display()
+                ))?,
             }
         }
+        Commands::Config {
+            command: ConfigCommands::Set { key, value },
+        } => {
+            let mut user = config::load_user_config(&config::user_config_path()?)?;
+            user.set(&key, &value)?;
+            config::save_user_config(&user)?;
+        }
     }
 
     Ok(())
 }
+
+/// Run a tool in the current project, or across the selected workspace packages
+fn run_in_selection(
+    tool: &str,
+    current_dir: &Path,
+    selection: WorkspaceArgs,
+    lock_timeout: Duration,
+    mut run: impl FnMut(&Path) -> cargonode::Result<commands::RunResult>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Each package is locked only while its tool runs
+    let mut run = |dir: &Path| {
+        let _lock = lock::acquire(dir, lock_timeout)?;
+        run(dir)
+    };
+    if selection.workspace || !selection.packages.is_empty() {
+        let options = commands::WorkspaceOptions {
+            packages: selection.packages,
+            fail_fast: selection.fail_fast,
+        };
+        commands::run_in_workspace(tool, current_dir, &options, run)?.ensure_success()?;
+        return Ok(());
+    }
+
+    let result = run(current_dir)?;
+    if !result.status.success() {
+        return Err(Box::new(cargonode::Error::CommandFailed {
+            command: tool.to_string(),
+            status: result.status,
+        }));
+    }
+    Ok(())
+}
+
+/// Run a tool in the current project again whenever its inputs change
+fn watch_tool(
+    tool: &str,
+    current_dir: &Path,
+    lock_timeout: Duration,
+    mut run: impl FnMut(&Path) -> cargonode::Result<commands::RunResult>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let options = commands::WatchOptions {
+        project_dir: current_dir.to_path_buf(),
+        clear_screen: progress::message_format() == progress::MessageFormat::Human
+            && io::stderr().is_terminal(),
+        max_runs: None,
+    };
+    // The project is locked only while the tool runs, not while waiting
+    commands::watch(tool, &options, |dir| {
+        let _lock = lock::acquire(dir, lock_timeout)?;
+        run(dir)
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_completion_script() {
+        for shell in [
+            Shell::Bash,
+            Shell::Zsh,
+            Shell::Fish,
+            Shell::PowerShell,
+            Shell::Elvish,
+        ] {
+            let script = completion_script(shell);
+            for subcommand in ["new", "run", "upgrade", "completions"] {
+                assert!(
+                    script.contains(subcommand),
+                    "{} lacks {}",
+                    shell,
+                    subcommand
+                );
+            }
+        }
+
+        // Tool names are completed by calling back into cargonode
+        assert!(completion_script(Shell::Bash).contains("$(cargonode __complete"));
+        assert!(completion_script(Shell::Zsh).contains(":_cargonode_tools'"));
+        assert!(completion_script(Shell::Fish).contains("(cargonode __complete"));
+    }
+
+    #[test]
+    fn test_known_commands() {
+        let commands: Vec<String> = Cli::command()
+            .get_subcommands()
+            .filter(|command| !command.is_hide_set())
+            .map(|command| command.get_name().to_string())
+            .collect();
+        assert_eq!(commands, cargonode::suggest::KNOWN_COMMANDS);
+    }
+
+    #[test]
+    fn test_color_flag() {
+        let args = |args: &str| args.split(' ').map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(color_flag(args("cargonode build")), None);
+        assert_eq!(
+            color_flag(args("cargonode --color never build")),
+            Some(progress::ColorChoice::Never)
+        );
+        assert_eq!(
+            color_flag(args("cargonode build --color=always --color=auto")),
+            Some(progress::ColorChoice::Auto)
+        );
+        // Arguments after `--` belong to the tool
+        assert_eq!(
+            color_flag(args("cargonode run lint -- --color always")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_complete_tools() {
+        let cli = Cli::try_parse_from(["cargonode", "__complete", "b"]).unwrap();
+        assert!(matches!(cli.command, Commands::Complete { prefix } if prefix == "b"));
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("package.json"),
+            r#"{"cargonode": {"tools": {"lint": {"command": "eslint"}, "build": {"command": "tsc"}}}}"#,
+        )
+        .unwrap();
+        assert_eq!(config::tool_names(temp_dir.path()), vec!["build", "lint"]);
+    }
+}
@@ -0,0 +1,442 @@
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::dependency::DEPENDENCY_SECTIONS;
+
+/// Fields whose existing value is kept even when the scaffold differs
+const PROTECTED_FIELDS: [&str; 5] = ["name", "version", "private", "bin", "type"];
+
+/// Protected fields whose absence means something, so they are not added
+/// either: a package without them is CommonJS, publishable and installs no
+/// executables
+const UNSET_PROTECTED_FIELDS: [&str; 3] = ["private", "bin", "type"];
+
+/// Section holding the cargonode configuration
+const TOOL_CONFIG_FIELD: &str = "cargonode";
+
+/// Why a scaffolded field was left out of the existing package.json
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The field identifies the package or decides how it is loaded and
+    /// published, and is never changed
+    Protected,
+    /// The package.json already has a different value
+    Conflict,
+    /// The package.json already has a cargonode configuration
+    Configured,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SkipReason::Protected => "the existing package.json decides this field",
+            SkipReason::Conflict => "already defined with a different value",
+            SkipReason::Configured => "the existing cargonode configuration is kept",
+        })
+    }
+}
+
+/// A scaffolded field the merge left out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedField {
+    /// Dotted path of the field, e.g. `scripts.test`
+    pub field: String,
+    /// Why it was left out
+    pub reason: SkipReason,
+}
+
+/// What merging a scaffolded package.json changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Dotted paths of the fields that were added, in order
+    pub added: Vec<String>,
+    /// Fields that were left out
+    pub skipped: Vec<SkippedField>,
+}
+
+impl MergeReport {
+    fn skip(&mut self, field: String, reason: SkipReason) {
+        self.skipped.push(SkippedField { field, reason });
+    }
+}
+
+/// Merge a scaffolded package.json into an existing one
+///
+/// Fields the existing package.json lacks are added, and fields it has are
+/// kept:
+///
+/// - `name` and `version` are never overwritten, and only added when missing
+/// - `private`, `bin` and `type` are never overwritten or added, as adding
+///   them changes how the existing package is loaded and published
+/// - `scripts` and the dependency sections gain the entries they lack;
+///   same-named entries keep their existing command or requirement
+/// - `cargonode` is only added when the package.json has no configuration
+/// - other objects are merged key by key, and other values are kept
+///
+/// Fields only the existing package.json has are left untouched. Values
+/// equal on both sides are neither added nor skipped.
+///
+/// # Arguments
+///
+/// * `existing` - The existing package.json document, updated in place
+/// * `scaffold` - The package.json document the scaffold would write
+///
+/// # Returns
+///
+/// * `MergeReport` - The fields that were added and skipped
+pub fn merge_package_json(existing: &mut Value, scaffold: &Value) -> MergeReport {
+    let mut report = MergeReport::default();
+    let (Some(existing), Some(scaffold)) = (existing.as_object_mut(), scaffold.as_object()) else {
+        return report;
+    };
+
+    for (key, value) in scaffold {
+        let Some(current) = existing.get_mut(key) else {
+            if UNSET_PROTECTED_FIELDS.contains(&key.as_str()) {
+                report.skip(key.clone(), SkipReason::Protected);
+                continue;
+            }
+            existing.insert(key.clone(), value.clone());
+            report.added.push(key.clone());
+            continue;
+        };
+        if current == value {
+            continue;
+        }
+        if PROTECTED_FIELDS.contains(&key.as_str()) {
+            report.skip(key.clone(), SkipReason::Protected);
+        } else if key == TOOL_CONFIG_FIELD {
+            report.skip(key.clone(), SkipReason::Configured);
+        } else if key == "scripts" || DEPENDENCY_SECTIONS.contains(&key.as_str()) {
+            merge_entries(key, current, value, &mut report);
+        } else {
+            merge_value(key, current, value, &mut report);
+        }
+    }
+    report
+}
+
+/// Add the entries of a `scripts` or dependency section that are missing,
+/// without descending into them
+fn merge_entries(field: &str, current: &mut Value, scaffold: &Value, report: &mut MergeReport) {
+    let (Some(current), Some(scaffold)) = (current.as_object_mut(), scaffold.as_object()) else {
+        report.skip(field.to_string(), SkipReason::Conflict);
+        return;
+    };
+    for (name, value) in scaffold {
+        let path = format!("{}.{}", field, name);
+        match current.get(name) {
+            None => {
+                current.insert(name.clone(), value.clone());
+                report.added.push(path);
+            }
+            Some(existing) if existing != value => report.skip(path, SkipReason::Conflict),
+            Some(_) => {}
+        }
+    }
+}
+
+/// Deep-merge `scaffold` into `current`, keeping every existing value
+fn merge_value(field: &str, current: &mut Value, scaffold: &Value, report: &mut MergeReport) {
+    match (current, scaffold) {
+        (Value::Object(current), Value::Object(scaffold)) => {
+            merge_object(field, current, scaffold, report)
+        }
+        (current, scaffold) if current != scaffold => {
+            report.skip(field.to_string(), SkipReason::Conflict)
+        }
+        _ => {}
+    }
+}
+
+fn merge_object(
+    field: &str,
+    current: &mut Map<String, Value>,
+    scaffold: &Map<String, Value>,
+    report: &mut MergeReport,
+) {
+    for (key, value) in scaffold {
+        let path = format!("{}.{}", field, key);
+        match current.get_mut(key) {
+            None => {
+                current.insert(key.clone(), value.clone());
+                report.added.push(path);
+            }
+            Some(existing) => merge_value(&path, existing, value, report),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn skipped(field: &str, reason: SkipReason) -> SkippedField {
+        SkippedField {
+            field: field.to_string(),
+            reason,
+        }
+    }
+
+    fn scaffold() -> Value {
+        json!({
+            "name": "scaffolded",
+            "version": "0.1.0",
+            "description": "A new package",
+            "type": "module",
+            "main": "src/main.js",
+            "scripts": { "start": "node src/main.js", "test": "node --test" },
+            "engines": { "node": ">=22" },
+            "devDependencies": { "typescript": "^5.0.0" },
+            "cargonode": { "tools": { "test": { "command": "node" } } }
+        })
+    }
+
+    #[test]
+    fn test_merge_into_empty() {
+        let mut existing = json!({});
+        let report = merge_package_json(&mut existing, &scaffold());
+        let mut expected = scaffold();
+        expected.as_object_mut().unwrap().remove("type");
+        assert_eq!(existing, expected);
+        assert_eq!(
+            report.added,
+            [
+                "name",
+                "version",
+                "description",
+                "main",
+                "scripts",
+                "engines",
+                "devDependencies",
+                "cargonode"
+            ]
+        );
+        assert_eq!(report.skipped, [skipped("type", SkipReason::Protected)]);
+    }
+
+    #[test]
+    fn test_merge_after_npm_init() {
+        let mut existing = json!({
+            "name": "mine",
+            "version": "1.0.0",
+            "description": "",
+            "main": "index.js",
+            "scripts": { "test": "echo \"Error: no test specified\" && exit 1" },
+            "keywords": [],
+            "author": "",
+            "license": "ISC"
+        });
+        let report = merge_package_json(&mut existing, &scaffold());
+        assert_eq!(
+            existing,
+            json!({
+                "name": "mine",
+                "version": "1.0.0",
+                "description": "",
+                "main": "index.js",
+                "scripts": {
+                    "test": "echo \"Error: no test specified\" && exit 1",
+                    "start": "node src/main.js"
+                },
+                "keywords": [],
+                "author": "",
+                "license": "ISC",
+                "engines": { "node": ">=22" },
+                "devDependencies": { "typescript": "^5.0.0" },
+                "cargonode": { "tools": { "test": { "command": "node" } } }
+            })
+        );
+        assert_eq!(
+            report.added,
+            ["scripts.start", "engines", "devDependencies", "cargonode"]
+        );
+        assert_eq!(
+            report.skipped,
+            [
+                skipped("name", SkipReason::Protected),
+                skipped("version", SkipReason::Protected),
+                skipped("description", SkipReason::Conflict),
+                skipped("type", SkipReason::Protected),
+                skipped("main", SkipReason::Conflict),
+                skipped("scripts.test", SkipReason::Conflict),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_keeps_identity() {
+        // name and version are only added when missing
+        let mut existing = json!({ "version": "2.0.0" });
+        let report = merge_package_json(&mut existing, &json!({ "name": "a", "version": "0.1.0" }));
+        assert_eq!(existing, json!({ "version": "2.0.0", "name": "a" }));
+        assert_eq!(report.added, ["name"]);
+        assert_eq!(report.skipped, [skipped("version", SkipReason::Protected)]);
+
+        // Equal values are not reported
+        let mut existing = json!({ "name": "a", "version": "0.1.0" });
+        let report = merge_package_json(&mut existing, &json!({ "name": "a", "version": "0.1.0" }));
+        assert_eq!(report, MergeReport::default());
+    }
+
+    #[test]
+    fn test_merge_keeps_module_and_publishing() {
+        // Neither added nor overwritten: the existing package stays CommonJS,
+        // publishable and without executables
+        let scaffold = json!({
+            "private": true,
+            "type": "module",
+            "bin": { "scaffolded": "src/main.js" }
+        });
+        let mut existing = json!({ "name": "mine" });
+        let report = merge_package_json(&mut existing, &scaffold);
+        assert_eq!(existing, json!({ "name": "mine" }));
+        assert!(report.added.is_empty());
+        assert_eq!(
+            report.skipped,
+            [
+                skipped("private", SkipReason::Protected),
+                skipped("type", SkipReason::Protected),
+                skipped("bin", SkipReason::Protected),
+            ]
+        );
+
+        let mut existing = json!({
+            "private": false,
+            "type": "commonjs",
+            "bin": { "mine": "cli.js" }
+        });
+        let original = existing.clone();
+        let report = merge_package_json(&mut existing, &scaffold);
+        assert_eq!(existing, original);
+        assert_eq!(report.skipped.len(), 3);
+        assert!(report
+            .skipped
+            .iter()
+            .all(|field| field.reason == SkipReason::Protected));
+    }
+
+    #[test]
+    fn test_merge_dependencies() {
+        let mut existing = json!({
+            "dependencies": { "express": "^4.18.0" },
+            "devDependencies": { "typescript": "~5.4.0", "vitest": "^1.0.0" }
+        });
+        let scaffold = json!({
+            "dependencies": { "express": "^5.0.0", "zod": "^3.0.0" },
+            "devDependencies": { "typescript": "~5.4.0" },
+            "peerDependencies": { "react": ">=18" }
+        });
+        let report = merge_package_json(&mut existing, &scaffold);
+        assert_eq!(
+            existing,
+            json!({
+                "dependencies": { "express": "^4.18.0", "zod": "^3.0.0" },
+                "devDependencies": { "typescript": "~5.4.0", "vitest": "^1.0.0" },
+                "peerDependencies": { "react": ">=18" }
+            })
+        );
+        assert_eq!(report.added, ["dependencies.zod", "peerDependencies"]);
+        assert_eq!(
+            report.skipped,
+            [skipped("dependencies.express", SkipReason::Conflict)]
+        );
+    }
+
+    #[test]
+    fn test_merge_scripts() {
+        let mut existing = json!({ "scripts": { "build": "tsc", "test": "node --test" } });
+        let scaffold =
+            json!({ "scripts": { "test": "node --test", "build": "tsup", "dev": "tsup --watch" } });
+        let report = merge_package_json(&mut existing, &scaffold);
+        assert_eq!(
+            existing,
+            json!({ "scripts": { "build": "tsc", "test": "node --test", "dev": "tsup --watch" } })
+        );
+        assert_eq!(report.added, ["scripts.dev"]);
+        assert_eq!(
+            report.skipped,
+            [skipped("scripts.build", SkipReason::Conflict)]
+        );
+
+        // A malformed section is kept as it is
+        let mut existing = json!({ "scripts": "tsc" });
+        let report = merge_package_json(&mut existing, &scaffold);
+        assert_eq!(existing, json!({ "scripts": "tsc" }));
+        assert_eq!(report.skipped, [skipped("scripts", SkipReason::Conflict)]);
+    }
+
+    #[test]
+    fn test_merge_keeps_tool_config() {
+        let mut existing = json!({ "cargonode": { "tools": { "build": { "command": "tsc" } } } });
+        let report = merge_package_json(&mut existing, &scaffold());
+        assert_eq!(
+            existing["cargonode"],
+            json!({ "tools": { "build": { "command": "tsc" } } })
+        );
+        assert!(report
+            .skipped
+            .contains(&skipped("cargonode", SkipReason::Configured)));
+        assert!(!report
+            .added
+            .iter()
+            .any(|field| field.starts_with("cargonode")));
+    }
+
+    #[test]
+    fn test_merge_nested_objects() {
+        let mut existing = json!({
+            "engines": { "node": ">=18" },
+            "exports": { ".": "./index.js" },
+            "files": ["dist"],
+            "private": true
+        });
+        let scaffold = json!({
+            "engines": { "node": ">=22", "npm": ">=10" },
+            "exports": { ".": { "import": "./src/main.js" }, "./package.json": "./package.json" },
+            "files": ["src"],
+            "private": true
+        });
+        let report = merge_package_json(&mut existing, &scaffold);
+        assert_eq!(
+            existing,
+            json!({
+                "engines": { "node": ">=18", "npm": ">=10" },
+                "exports": { ".": "./index.js", "./package.json": "./package.json" },
+                "files": ["dist"],
+                "private": true
+            })
+        );
+        assert_eq!(report.added, ["engines.npm", "exports../package.json"]);
+        assert_eq!(
+            report.skipped,
+            [
+                skipped("engines.node", SkipReason::Conflict),
+                skipped("exports..", SkipReason::Conflict),
+                skipped("files", SkipReason::Conflict),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_leaves_unknown_fields() {
+        let mut existing = json!({
+            "name": "mine",
+            "volta": { "node": "20.0.0" },
+            "custom": [1, 2, 3]
+        });
+        let original = existing.clone();
+        let report = merge_package_json(&mut existing, &json!({ "name": "mine" }));
+        assert_eq!(existing, original);
+        assert_eq!(report, MergeReport::default());
+
+        // A document that is not an object is left alone
+        let mut existing = json!([]);
+        let report = merge_package_json(&mut existing, &scaffold());
+        assert_eq!(existing, json!([]));
+        assert_eq!(report, MergeReport::default());
+    }
+}
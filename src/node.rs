@@ -0,0 +1,263 @@
+use std::fs;
+use std::path::{Component, Path};
+use std::sync::OnceLock;
+
+use semver::{Op, Version, VersionReq};
+
+use crate::error::Error;
+use crate::package_manager::find_executable;
+use crate::{config, dependency, utils, Result};
+
+/// Oldest Node.js release line required when nothing else is configured
+pub const MIN_NODE_VERSION: &str = ">=18";
+
+/// File pinning the Node.js version for nvm, fnm and other version managers
+pub const VERSION_FILE: &str = ".nvmrc";
+
+/// Commands that are Node.js programs, or launch one
+const NODE_COMMANDS: [&str; 6] = ["node", "npm", "npx", "pnpm", "yarn", "corepack"];
+
+static INSTALLED_VERSION: OnceLock<Option<String>> = OnceLock::new();
+
+/// Version of the installed Node.js, looked up once per process
+pub fn installed_version() -> Option<&'static str> {
+    INSTALLED_VERSION
+        .get_or_init(|| find_executable("node").and_then(|_| utils::node_version()))
+        .as_deref()
+}
+
+/// Whether running `command` needs Node.js
+///
+/// This holds for node itself, the package managers that run on it, and
+/// executables installed under `node_modules`.
+pub fn needs_node(command: &str) -> bool {
+    let path = Path::new(command);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    NODE_COMMANDS.contains(&stem.as_str())
+        || path
+            .components()
+            .any(|component| component == Component::Normal("node_modules".as_ref()))
+}
+
+/// Node.js versions the project in `project_dir` requires
+///
+/// The `node` setting of the cargonode configuration wins over `engines.node`
+/// in package.json, which wins over `MIN_NODE_VERSION`.
+pub fn required_version(project_dir: &Path) -> String {
+    if let Some(required) = config::read_config(project_dir, false)
+        .ok()
+        .and_then(|config| config.node)
+    {
+        return required;
+    }
+    fs::read_to_string(project_dir.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|manifest| manifest["engines"]["node"].as_str().map(str::to_string))
+        .unwrap_or_else(|| MIN_NODE_VERSION.to_string())
+}
+
+/// Check that an installed Node.js version satisfies a requirement
+///
+/// The requirement is an npm range, so `>= 18` and `^18 || >=20` work as in
+/// `engines.node`. One that is not, such as `lts/*`, is reported rather than
+/// silently accepted.
+///
+/// # Arguments
+///
+/// * `found` - Installed version, without the leading `v`
+/// * `required` - Requirement in the syntax of `engines.node`
+///
+/// # Returns
+///
+/// * `Result<()>` - `Error::NodeTooOld` if the version does not satisfy it,
+///   or `Error::Config` if the requirement is not a range
+pub fn check_version(found: &str, required: &str) -> Result<()> {
+    let alternatives = dependency::parse_range(required).map_err(|_| Error::Config {
+        message: format!("'{}' is not a Node.js version range", required),
+        hint: Some(
+            "Require a semver range such as `>=20` in `engines.node` or the cargonode `node` setting."
+                .to_string(),
+        ),
+    })?;
+    match Version::parse(found) {
+        Ok(version)
+            if !alternatives
+                .iter()
+                .any(|requirement| requirement.matches(&version)) =>
+        {
+            Err(Error::NodeTooOld {
+                found: found.to_string(),
+                required: required.to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Requirement a new project gets when created with Node.js `version`
+///
+/// The version is rounded down to a release line with long-term support,
+/// which are the even majors, e.g. `23.1.0` gives `>=22`.
+pub fn lts_range(version: &str) -> String {
+    match version
+        .split('.')
+        .next()
+        .and_then(|major| major.parse::<u64>().ok())
+    {
+        Some(major) => format!(">={}", major - major % 2),
+        None => MIN_NODE_VERSION.to_string(),
+    }
+}
+
+/// Lowest Node.js version a requirement allows, as written to `.nvmrc`
+///
+/// The version is as precise as the requirement, e.g. `>=20` gives `20` and
+/// `^20.11` gives `20.11`.
+///
+/// # Arguments
+///
+/// * `required` - Requirement in the syntax of `engines.node`
+///
+/// # Returns
+///
+/// * `Result<String>` - The version, or `Error::Config` if the requirement
+///   is not a semver range or has no lower bound
+pub fn lowest_version(required: &str) -> Result<String> {
+    let invalid = |reason: String| Error::Config {
+        message: format!(
+            "'{}' is not a usable Node.js version range: {}",
+            required, reason
+        ),
+        hint: None,
+    };
+    let requirement =
+        VersionReq::parse(&required.replace(' ', ", ")).map_err(|e| invalid(e.to_string()))?;
+
+    requirement
+        .comparators
+        .iter()
+        .filter(|comparator| {
+            matches!(
+                comparator.op,
+                Op::Exact | Op::GreaterEq | Op::Tilde | Op::Caret | Op::Wildcard
+            )
+        })
+        .find(|comparator| {
+            requirement.matches(&Version::new(
+                comparator.major,
+                comparator.minor.unwrap_or(0),
+                comparator.patch.unwrap_or(0),
+            ))
+        })
+        .map(|comparator| match (comparator.minor, comparator.patch) {
+            (Some(minor), Some(patch)) => format!("{}.{}.{}", comparator.major, minor, patch),
+            (Some(minor), None) => format!("{}.{}", comparator.major, minor),
+            _ => comparator.major.to_string(),
+        })
+        .ok_or_else(|| invalid("it needs a lower bound such as `>=20`".to_string()))
+}
+
+/// Make sure Node.js is installed and new enough for the project
+///
+/// `node --version` is only run on the first call.
+///
+/// # Arguments
+///
+/// * `project_dir` - Directory of the project whose requirement applies
+///
+/// # Returns
+///
+/// * `Result<()>` - `Error::NodeMissing` or `Error::NodeTooOld` on failure
+pub fn ensure_node(project_dir: &Path) -> Result<()> {
+    let found = installed_version().ok_or(Error::NodeMissing)?;
+    check_version(found, &required_version(project_dir))
+}
+
+/// How to install Node.js on this platform
+pub fn install_hint() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "Install Node.js with `brew install node`, a version manager such as fnm or nvm, or from https://nodejs.org"
+    } else if cfg!(windows) {
+        "Install Node.js with `winget install OpenJS.NodeJS.LTS`, a version manager such as fnm or nvm-windows, or from https://nodejs.org"
+    } else {
+        "Install Node.js with your distribution's package manager, a version manager such as fnm or nvm, or from https://nodejs.org"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_needs_node() {
+        assert!(needs_node("node"));
+        assert!(needs_node("npx"));
+        assert!(needs_node("/usr/local/bin/npm"));
+        assert!(needs_node("/app/node_modules/.bin/tsc"));
+        assert!(!needs_node("cargo"));
+        assert!(!needs_node("bun"));
+        assert!(!needs_node("nodemon-like"));
+    }
+
+    #[test]
+    fn test_lts_range() {
+        assert_eq!(lts_range("22.14.0"), ">=22");
+        assert_eq!(lts_range("23.1.0"), ">=22");
+        assert_eq!(lts_range("unknown"), MIN_NODE_VERSION);
+    }
+
+    #[test]
+    fn test_lowest_version() -> Result<()> {
+        assert_eq!(lowest_version(">=20")?, "20");
+        assert_eq!(lowest_version("^20.11")?, "20.11");
+        assert_eq!(lowest_version(">=18.17.0 <23")?, "18.17.0");
+        assert_eq!(lowest_version("22.x")?, "22");
+        assert!(lowest_version("<23").is_err());
+        assert!(lowest_version("lts/*").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_version() {
+        assert!(check_version("20.11.1", ">=18").is_ok());
+        assert!(check_version("20.11.1", "^20.10.0").is_ok());
+        assert!(check_version("18.0.0", ">=18 <21").is_ok());
+        let Err(Error::NodeTooOld { found, required }) = check_version("16.20.2", ">=18") else {
+            panic!("expected Node.js 16 to be too old");
+        };
+        assert_eq!((found.as_str(), required.as_str()), ("16.20.2", ">=18"));
+        // npm range syntax, as in engines.node
+        assert!(check_version("20.11.1", ">= 18").is_ok());
+        assert!(check_version("20.11.1", "^18.17 || >=20").is_ok());
+        assert!(check_version("19.0.0", "^18.17 || >=20").is_err());
+
+        let Err(Error::Config { message, hint }) = check_version("16.20.2", "lts/*") else {
+            panic!("expected an unparseable range to be reported");
+        };
+        assert!(message.contains("'lts/*'"));
+        assert!(hint.is_some());
+    }
+
+    #[test]
+    fn test_required_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        assert_eq!(required_version(dir), MIN_NODE_VERSION);
+
+        fs::write(dir.join("package.json"), r#"{"engines": {"node": ">=20"}}"#).unwrap();
+        assert_eq!(required_version(dir), ">=20");
+
+        fs::write(
+            dir.join("package.json"),
+            r#"{"engines": {"node": ">=20"}, "cargonode": {"node": ">=22"}}"#,
+        )
+        .unwrap();
+        assert_eq!(required_version(dir), ">=22");
+    }
+}
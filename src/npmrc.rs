@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of npm's configuration file
+pub const NPMRC_FILE: &str = ".npmrc";
+
+/// Settings read from `.npmrc` files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Npmrc {
+    /// Settings keyed by name, with environment variables expanded
+    entries: HashMap<String, String>,
+}
+
+impl Npmrc {
+    /// Parse the content of an `.npmrc` file
+    ///
+    /// Lines are `key=value` pairs; blank lines and lines starting with `#`
+    /// or `;` are ignored. `${VAR}` references are replaced with the value of
+    /// the environment variable, or left empty if it is unset.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Content of the file
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The parsed settings
+    pub fn parse(content: &str) -> Self {
+        let entries = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with(';'))
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| {
+                let value = value.trim().trim_matches('"');
+                (key.trim().to_string(), expand_env(value))
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    /// Load the user's `~/.npmrc` overlaid with the project's `.npmrc`
+    ///
+    /// Missing or unreadable files are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_dir` - Directory containing the project's `.npmrc`
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The merged settings
+    pub fn load(project_dir: &Path) -> Self {
+        let user = env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(NPMRC_FILE));
+        let files = [user, Some(project_dir.join(NPMRC_FILE))];
+
+        let mut npmrc = Self::default();
+        for path in files.iter().flatten() {
+            if let Ok(content) = fs::read_to_string(path) {
+                npmrc.entries.extend(Self::parse(&content).entries);
+            }
+        }
+        npmrc
+    }
+
+    /// Get a setting by key
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+
+    /// Get the auth token configured for the registry at `base_url`
+    ///
+    /// Tokens are keyed by the registry URL without its scheme, e.g.
+    /// `//registry.npmjs.org/:_authToken`.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - Base URL of the registry
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&str>` - The token, if one is configured
+    pub fn auth_token(&self, base_url: &str) -> Option<&str> {
+        let without_scheme = base_url
+            .split_once("://")
+            .map_or(base_url, |(_, rest)| rest)
+            .trim_end_matches('/');
+
+        [
+            format!("//{}/:_authToken", without_scheme),
+            format!("//{}:_authToken", without_scheme),
+        ]
+        .iter()
+        .find_map(|key| self.get(key))
+        .filter(|token| !token.is_empty())
+    }
+}
+
+/// Replace `${VAR}` references with environment variable values
+fn expand_env(value: &str) -> String {
+    let mut expanded = String::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        expanded.push_str(&rest[..start]);
+        let name = &rest[start + 2..start + end];
+        expanded.push_str(&env::var(name).unwrap_or_default());
+        rest = &rest[start + end + 1..];
+    }
+
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let npmrc = Npmrc::parse(
+            "# comment\n; another\nregistry = https://npm.example.com/\n\nsave-exact=\"true\"\n",
+        );
+        assert_eq!(npmrc.get("registry"), Some("https://npm.example.com/"));
+        assert_eq!(npmrc.get("save-exact"), Some("true"));
+        assert_eq!(npmrc.get("# comment"), None);
+    }
+
+    #[test]
+    fn test_expand_env() {
+        env::set_var("CARGONODE_NPMRC_TEST_TOKEN", "secret");
+        assert_eq!(expand_env("${CARGONODE_NPMRC_TEST_TOKEN}"), "secret");
+        assert_eq!(
+            expand_env("a-${CARGONODE_NPMRC_TEST_TOKEN}-${CARGONODE_NPMRC_UNSET}-b"),
+            "a-secret--b"
+        );
+        assert_eq!(expand_env("${unterminated"), "${unterminated");
+    }
+
+    #[test]
+    fn test_auth_token() {
+        let npmrc = Npmrc::parse(
+            "//registry.npmjs.org/:_authToken=public-token\n\
+             //npm.example.com/repository/npm/:_authToken=mirror-token\n\
+             //empty.example.com/:_authToken=\n",
+        );
+
+        assert_eq!(
+            npmrc.auth_token("https://registry.npmjs.org"),
+            Some("public-token")
+        );
+        assert_eq!(
+            npmrc.auth_token("https://npm.example.com/repository/npm"),
+            Some("mirror-token")
+        );
+        assert_eq!(npmrc.auth_token("https://npm.example.com"), None);
+        assert_eq!(npmrc.auth_token("https://empty.example.com"), None);
+    }
+
+    #[test]
+    fn test_load_project_overrides_user() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(NPMRC_FILE),
+            "//registry.npmjs.org/:_authToken=project-token\n",
+        )
+        .unwrap();
+
+        let npmrc = Npmrc::load(temp_dir.path());
+        assert_eq!(
+            npmrc.auth_token("https://registry.npmjs.org"),
+            Some("project-token")
+        );
+    }
+}
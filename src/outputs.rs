@@ -1,8 +1,15 @@
 use std::path::{Path, PathBuf};
 
+use glob::{glob, Pattern};
+
 use crate::error::Error;
 use crate::Result;
 
+/// Whether `pattern` contains glob metacharacters
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
 /// Output verifier for checking expected output files
 pub struct OutputVerifier {
     /// Base path for resolving output patterns
@@ -30,42 +37,121 @@ impl OutputVerifier {
         }
     }
 
-    /// Verify that all expected output directories exist and create them if needed
+    /// Create the directories outputs are written to, before the tool runs
+    ///
+    /// For literal paths this is the parent directory; for glob patterns it
+    /// is the directory before the first component with a wildcard.
     ///
     /// # Returns
     ///
-    /// * `Result<Vec<PathBuf>>` - List of output file paths that were verified
-    pub fn verify_outputs(&self) -> Result<Vec<PathBuf>> {
-        // Check if patterns is empty
-        if self.patterns.is_empty() {
-            return Ok(Vec::new());
+    /// * `Result<()>` - An error if a directory cannot be created
+    pub fn prepare_output_dirs(&self) -> Result<()> {
+        for pattern in &self.patterns {
+            let path = Path::new(pattern);
+            let mut components: Vec<_> = path.components().collect();
+            match components
+                .iter()
+                .position(|c| is_glob(&c.as_os_str().to_string_lossy()))
+            {
+                Some(wildcard) => components.truncate(wildcard),
+                None => {
+                    components.pop();
+                }
+            }
+            if components.is_empty() {
+                continue;
+            }
+
+            let dir = self.base_path.join(components.iter().collect::<PathBuf>());
+            std::fs::create_dir_all(&dir).map_err(|e| Error::Output {
+                message: format!("Failed to create directory '{}': {}", dir.display(), e),
+            })?;
         }
+        Ok(())
+    }
 
-        let mut output_paths = Vec::new();
+    /// Verify that the tool produced every expected output
+    ///
+    /// Glob patterns must match at least one file, literal paths must exist
+    /// as files. Nothing is written.
+    ///
+    /// # Arguments
+    ///
+    /// * `tool` - Name of the tool, used in the error
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<PathBuf>>` - Sorted list of the output files, or
+    ///   `Error::MissingOutput` listing every pattern that matched nothing
+    pub fn verify_outputs(&self, tool: &str) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut missing = Vec::new();
 
-        // Process each pattern
         for pattern in &self.patterns {
-            let pattern_path = self.base_path.join(pattern);
-
-            // Get the parent directory of the pattern
-            if let Some(parent) = pattern_path.parent() {
-                // Create parent directories if they don't exist
-                if !parent.exists() {
-                    std::fs::create_dir_all(parent).map_err(|e| Error::Output {
-                        message: format!(
-                            "Failed to create directory '{}': {}",
-                            parent.display(),
-                            e
-                        ),
-                    })?;
+            let matched = if is_glob(pattern) {
+                self.expand(pattern)?
+            } else {
+                let path = self.base_path.join(pattern);
+                if path.is_file() {
+                    vec![path]
+                } else {
+                    Vec::new()
                 }
+            };
+            if matched.is_empty() {
+                missing.push(pattern.clone());
             }
+            files.extend(matched);
+        }
+
+        if !missing.is_empty() {
+            return Err(Error::MissingOutput {
+                tool: tool.to_string(),
+                patterns: missing,
+            });
+        }
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
 
-            // Add the expected output path
-            output_paths.push(pattern_path);
+    /// Files matching one glob pattern
+    fn expand(&self, pattern: &str) -> Result<Vec<PathBuf>> {
+        let abs_pattern = format!(
+            "{}/{}",
+            Pattern::escape(&self.base_path.to_string_lossy()),
+            pattern
+        );
+        let entries = glob(&abs_pattern).map_err(|e| Error::Output {
+            message: format!("Invalid output pattern '{}': {}", pattern, e),
+        })?;
+
+        let mut files = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| Error::Output {
+                message: format!("Failed to process output entry: {}", e),
+            })?;
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+
+    /// Find the files currently matching the output patterns
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<PathBuf>>` - Sorted list of matching files
+    pub fn find_outputs(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for pattern in &self.patterns {
+            files.extend(self.expand(pattern)?);
         }
 
-        Ok(output_paths)
+        files.sort();
+        files.dedup();
+        Ok(files)
     }
 
     /// Get a list of expected output files
@@ -105,7 +191,7 @@ mod tests {
         );
 
         // Verify outputs
-        let outputs = verifier.verify_outputs()?;
+        let outputs = verifier.verify_outputs("build")?;
 
         // Check that both file paths were returned
         assert_eq!(outputs.len(), 2);
@@ -115,25 +201,32 @@ mod tests {
         Ok(())
     }
 
-    /// Test output verification with non-existent files (should succeed and create directories)
+    /// Test that every pattern without a matching file is reported, and
+    /// nothing is created
     #[test]
     fn test_verify_outputs_missing() -> Result<()> {
-        // Create temporary directory
         let temp_dir = tempdir()?;
         let temp_path = temp_dir.path();
+        fs::create_dir(temp_path.join("dist"))?;
+        File::create(temp_path.join("dist/index.js"))?;
 
-        // Create output verifier with non-existent pattern
-        let verifier = OutputVerifier::new(temp_path, vec!["subdir/missing.out".to_string()]);
-
-        // Verify outputs (should succeed and create directory)
-        let outputs = verifier.verify_outputs()?;
-
-        // Check that the directory was created
-        assert!(temp_path.join("subdir").exists());
+        let verifier = OutputVerifier::new(
+            temp_path,
+            vec![
+                "subdir/missing.out".to_string(),
+                "dist/*.js".to_string(),
+                "dist/*.d.ts".to_string(),
+                // A directory is not an output file
+                "dist".to_string(),
+            ],
+        );
 
-        // Check that the expected path was returned
-        assert_eq!(outputs.len(), 1);
-        assert_eq!(outputs[0], temp_path.join("subdir/missing.out"));
+        let Err(Error::MissingOutput { tool, patterns }) = verifier.verify_outputs("build") else {
+            panic!("expected missing outputs to be reported");
+        };
+        assert_eq!(tool, "build");
+        assert_eq!(patterns, vec!["subdir/missing.out", "dist/*.d.ts", "dist"]);
+        assert!(!temp_path.join("subdir").exists());
 
         Ok(())
     }
@@ -143,90 +236,62 @@ mod tests {
     fn test_verify_outputs_empty() -> Result<()> {
         let temp_dir = tempdir()?;
         let verifier = OutputVerifier::new(temp_dir.path(), vec![]);
-        let outputs = verifier.verify_outputs()?;
+        let outputs = verifier.verify_outputs("build")?;
         assert!(outputs.is_empty());
         Ok(())
     }
 
-    /// Test output verification with subdirectories
+    /// Test output verification with subdirectories and globs
     #[test]
     fn test_verify_outputs_subdirectories() -> Result<()> {
         let temp_dir = tempdir()?;
         let temp_path = temp_dir.path();
 
         // Create subdirectory and file
-        let subdir_path = temp_path.join("subdir");
-        fs::create_dir(&subdir_path)?;
+        let subdir_path = temp_path.join("subdir/nested");
+        fs::create_dir_all(&subdir_path)?;
         let file_path = subdir_path.join("test.out");
         let _file = File::create(&file_path)?;
 
-        // Create output verifier
-        let verifier = OutputVerifier::new(temp_path, vec!["subdir/test.out".to_string()]);
-
-        // Verify outputs
-        let outputs = verifier.verify_outputs()?;
+        let verifier = OutputVerifier::new(
+            temp_path,
+            vec![
+                "subdir/nested/test.out".to_string(),
+                "subdir/**/*.out".to_string(),
+            ],
+        );
 
-        // Check that the file path was returned
-        assert_eq!(outputs.len(), 1);
-        assert_eq!(outputs[0], file_path);
+        // Files matched by several patterns are listed once
+        let outputs = verifier.verify_outputs("build")?;
+        assert_eq!(outputs, vec![file_path]);
 
         Ok(())
     }
 
     #[test]
-    fn test_verify_outputs_creates_directories() -> Result<()> {
+    fn test_prepare_output_dirs() -> Result<()> {
         let temp_dir = tempdir()?;
         let base_path = temp_dir.path();
 
-        // Create patterns with nested directories
         let patterns = vec![
             "output/dir1/file1.txt".to_string(),
             "output/dir2/subdir/file2.txt".to_string(),
+            "dist/**/*.js".to_string(),
+            "build-*/index.js".to_string(),
+            "top-level.txt".to_string(),
         ];
-
-        let verifier = OutputVerifier::new(base_path, patterns.clone());
-        let output_paths = verifier.verify_outputs()?;
-
-        // Verify directories were created
-        assert!(base_path.join("output/dir1").exists());
-        assert!(base_path.join("output/dir2/subdir").exists());
-
-        // Verify returned paths match expected
-        assert_eq!(output_paths.len(), 2);
-        assert_eq!(output_paths[0], base_path.join("output/dir1/file1.txt"));
-        assert_eq!(
-            output_paths[1],
-            base_path.join("output/dir2/subdir/file2.txt")
-        );
-
-        Ok(())
-    }
-
-    #[test]
-    fn test_verify_outputs_existing_directories() -> Result<()> {
-        let temp_dir = tempdir()?;
-        let base_path = temp_dir.path();
-
-        // Create directories first
-        fs::create_dir_all(base_path.join("existing/dir1"))?;
-        fs::create_dir_all(base_path.join("existing/dir2"))?;
-
-        let patterns = vec![
-            "existing/dir1/file1.txt".to_string(),
-            "existing/dir2/file2.txt".to_string(),
-        ];
-
-        let verifier = OutputVerifier::new(base_path, patterns);
-        let output_paths = verifier.verify_outputs()?;
-
-        // Verify directories still exist
-        assert!(base_path.join("existing/dir1").exists());
-        assert!(base_path.join("existing/dir2").exists());
-
-        // Verify returned paths match expected
-        assert_eq!(output_paths.len(), 2);
-        assert_eq!(output_paths[0], base_path.join("existing/dir1/file1.txt"));
-        assert_eq!(output_paths[1], base_path.join("existing/dir2/file2.txt"));
+        OutputVerifier::new(base_path, patterns).prepare_output_dirs()?;
+
+        // Directories are created up to the file name or the first wildcard
+        assert!(base_path.join("output/dir1").is_dir());
+        assert!(base_path.join("output/dir2/subdir").is_dir());
+        assert!(base_path.join("dist").is_dir());
+        assert!(!base_path.join("dist/**").exists());
+        let mut entries: Vec<_> = fs::read_dir(base_path)?
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, vec!["dist", "output"]);
 
         Ok(())
     }
@@ -0,0 +1,574 @@
+use std::fs;
+use std::path::Path;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+use crate::error::Error;
+use crate::Result;
+
+/// Paths npm never packs, in `.gitignore` syntax
+///
+/// Patterns without a leading `/` apply at any depth, as in npm-packlist.
+const DEFAULT_EXCLUDES: [&str; 21] = [
+    ".npmignore",
+    ".gitignore",
+    ".git",
+    ".svn",
+    ".hg",
+    "CVS",
+    "node_modules",
+    ".npmrc",
+    "npm-debug.log",
+    ".DS_Store",
+    "._*",
+    ".*.swp",
+    "*.orig",
+    "/.lock-wscript",
+    "/.wafpickle-*",
+    "/build/config.gypi",
+    "/archived-packages/",
+    "/package-lock.json",
+    "/yarn.lock",
+    "/pnpm-lock.yaml",
+    "/bun.lockb",
+];
+
+/// Names of root files npm always packs, whatever their extension
+const ALWAYS_INCLUDED: [&str; 4] = ["readme", "copying", "license", "licence"];
+
+/// Directories that hold build output, for flagging sources packed beside it
+const BUILD_DIRS: [&str; 3] = ["dist", "lib", "build"];
+
+/// A file npm would put in the package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedFile {
+    /// Path relative to the package root, with `/` separators
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Why the file looks like it should not be published, if it does
+    pub warning: Option<String>,
+}
+
+/// The files npm would pack, sorted by path
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackList {
+    /// Packed files
+    pub files: Vec<PackedFile>,
+    /// Total size of the files in bytes
+    pub total_bytes: u64,
+}
+
+impl PackList {
+    /// Files flagged as suspicious
+    pub fn warnings(&self) -> impl Iterator<Item = &PackedFile> {
+        self.files.iter().filter(|file| file.warning.is_some())
+    }
+}
+
+/// Compute the files npm would pack for the package at `project_dir`
+///
+/// The rules follow npm's documented precedence:
+///
+/// 1. [`DEFAULT_EXCLUDES`], such as `node_modules` and VCS metadata, are
+///    never packed.
+/// 2. `package.json`, the README and LICENSE files at the root, the `main`
+///    file and the `bin` files are always packed.
+/// 3. With a `files` field, only paths it lists are packed, and the root
+///    `.npmignore` or `.gitignore` does not apply. Without one, every file
+///    is packed unless the root ignore file excludes it.
+/// 4. Ignore files in subdirectories exclude files in both cases.
+///
+/// In each directory, `.npmignore` is used when present and `.gitignore`
+/// otherwise, never both.
+///
+/// # Arguments
+///
+/// * `project_dir` - Directory containing package.json
+///
+/// # Returns
+///
+/// * `Result<PackList>` - The packed files, or an error if package.json or
+///   an ignore file cannot be read
+pub fn pack_list(project_dir: &Path) -> Result<PackList> {
+    let content = fs::read_to_string(project_dir.join("package.json"))?;
+    let manifest: serde_json::Value = serde_json::from_str(&content)?;
+    let files: Option<Vec<String>> = manifest.get("files").and_then(|files| {
+        Some(
+            files
+                .as_array()?
+                .iter()
+                .filter_map(|entry| entry.as_str())
+                .map(normalize)
+                .collect(),
+        )
+    });
+
+    let mut packer = Packer {
+        defaults: default_excludes(project_dir)?,
+        always: always_packed(&manifest),
+        files: files.as_deref(),
+        list: PackList::default(),
+    };
+    packer.walk(project_dir, "", &mut Vec::new())?;
+
+    let mut list = packer.list;
+    list.files.sort_by(|a, b| a.path.cmp(&b.path));
+    for file in &mut list.files {
+        file.warning = warning(&file.path, files.as_deref());
+    }
+    Ok(list)
+}
+
+/// Whether `path` is packed according to the `files` field of package.json
+///
+/// Entries name a file, a directory whose whole content is packed, or a glob
+/// matched against the path relative to the package root.
+///
+/// # Arguments
+///
+/// * `files` - Entries of the `files` field, without a leading `./`
+/// * `path` - Path relative to the package root, with `/` separators
+///
+/// # Returns
+///
+/// * `bool` - Whether the path or one of its directories is listed
+pub fn listed_in_files(files: &[impl AsRef<str>], path: &str) -> bool {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    let mut ancestors = std::iter::successors(Some(path), |path| Some(path.rsplit_once('/')?.0));
+    ancestors.any(|candidate| {
+        files.iter().any(|entry| {
+            let entry = normalize(entry.as_ref());
+            candidate == entry
+                || glob::Pattern::new(&entry)
+                    .is_ok_and(|pattern| pattern.matches_with(candidate, options))
+        })
+    })
+}
+
+/// Strip the `./` and trailing `/` npm accepts around paths
+fn normalize(path: &str) -> String {
+    path.trim_start_matches("./")
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Walks the package, deciding for each file whether npm packs it
+struct Packer<'a> {
+    /// Matcher for [`DEFAULT_EXCLUDES`]
+    defaults: Gitignore,
+    /// Files packed whatever the other rules say, relative to the root
+    always: Vec<String>,
+    /// Entries of the `files` field, if there is one
+    files: Option<&'a [String]>,
+    /// Files packed so far
+    list: PackList,
+}
+
+impl Packer<'_> {
+    /// Add the packed files below `dir`, whose path relative to the root is
+    /// `relative`
+    ///
+    /// `ignores` holds the ignore files of the enclosing directories, the
+    /// innermost last.
+    fn walk(&mut self, dir: &Path, relative: &str, ignores: &mut Vec<Gitignore>) -> Result<()> {
+        // The root ignore file does not override the `files` field
+        let ignore = if relative.is_empty() && self.files.is_some() {
+            None
+        } else {
+            ignore_file(dir)?
+        };
+        let pushed = ignore.is_some();
+        ignores.extend(ignore);
+
+        let mut entries = fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let relative = if relative.is_empty() {
+                name
+            } else {
+                format!("{}/{}", relative, name)
+            };
+            // Broken symlinks and symlinked directories are skipped, as npm
+            // does; following the latter could loop forever
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let is_dir = metadata.is_dir();
+            if is_dir && entry.file_type()?.is_symlink() {
+                continue;
+            }
+
+            if self.defaults.matched(&path, is_dir).is_ignore() {
+                continue;
+            }
+            if !is_dir && (self.always.contains(&relative) || is_always_included(&relative)) {
+                self.add(relative, metadata.len());
+                continue;
+            }
+            let ignored = ignores
+                .iter()
+                .rev()
+                .find_map(|ignore| match ignore.matched(&path, is_dir) {
+                    Match::None => None,
+                    matched => Some(matched.is_ignore()),
+                })
+                .unwrap_or(false);
+            if ignored {
+                // An ignored directory may still hold the main or bin files
+                if is_dir {
+                    self.add_always_below(&path, &relative);
+                }
+                continue;
+            }
+
+            if is_dir {
+                self.walk(&path, &relative, ignores)?;
+            } else if self
+                .files
+                .is_none_or(|files| listed_in_files(files, &relative))
+            {
+                self.add(relative, metadata.len());
+            }
+        }
+
+        if pushed {
+            ignores.pop();
+        }
+        Ok(())
+    }
+
+    /// Add the files packed whatever the other rules say from below the
+    /// ignored directory `dir`, whose path relative to the root is `relative`
+    fn add_always_below(&mut self, dir: &Path, relative: &str) {
+        let prefix = format!("{}/", relative);
+        let mut below: Vec<String> = self
+            .always
+            .iter()
+            .filter(|path| path.starts_with(&prefix))
+            .cloned()
+            .collect();
+        // `main` is often a `bin` file too
+        below.sort();
+        below.dedup();
+        for path in below {
+            match fs::metadata(dir.join(&path[prefix.len()..])) {
+                Ok(metadata) if metadata.is_file() => self.add(path, metadata.len()),
+                _ => {}
+            }
+        }
+    }
+
+    fn add(&mut self, path: String, size: u64) {
+        self.list.total_bytes += size;
+        self.list.files.push(PackedFile {
+            path,
+            size,
+            warning: None,
+        });
+    }
+}
+
+/// Build the matcher for [`DEFAULT_EXCLUDES`], rooted at the package
+fn default_excludes(root: &Path) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root);
+    for line in DEFAULT_EXCLUDES {
+        builder.add_line(None, line).map_err(ignore_error(root))?;
+    }
+    builder.build().map_err(ignore_error(root))
+}
+
+/// The ignore file npm reads in `dir`: `.npmignore`, or `.gitignore` when
+/// there is none
+fn ignore_file(dir: &Path) -> Result<Option<Gitignore>> {
+    let Some(path) = [".npmignore", ".gitignore"]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+    else {
+        return Ok(None);
+    };
+
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(err) = builder.add(&path) {
+        return Err(ignore_error(&path)(err));
+    }
+    Ok(Some(builder.build().map_err(ignore_error(&path))?))
+}
+
+fn ignore_error(path: &Path) -> impl Fn(ignore::Error) -> Error + '_ {
+    move |err| Error::Publish {
+        message: format!("Failed to read {}: {}", path.display(), err),
+        suggestion: "Fix the syntax of the ignore file.".to_string(),
+    }
+}
+
+/// Root-relative paths npm packs whatever `files` and the ignore files say
+fn always_packed(manifest: &serde_json::Value) -> Vec<String> {
+    let mut paths = vec!["package.json".to_string()];
+    if let Some(main) = manifest.get("main").and_then(|main| main.as_str()) {
+        paths.push(normalize(main));
+    }
+    match manifest.get("bin") {
+        Some(serde_json::Value::String(bin)) => paths.push(normalize(bin)),
+        Some(serde_json::Value::Object(bins)) => {
+            paths.extend(bins.values().filter_map(|bin| bin.as_str()).map(normalize))
+        }
+        _ => {}
+    }
+    paths
+}
+
+/// Why `path` looks like it should not be published, if it does
+fn warning(path: &str, files: Option<&[String]>) -> Option<String> {
+    if path.split('/').any(|component| component.starts_with('.')) {
+        return Some("dotfiles often hold secrets or local settings".to_string());
+    }
+
+    let top = |entry: &String| entry.split('/').next().unwrap_or_default().to_string();
+    let files = files?;
+    let build_dir = files
+        .iter()
+        .map(top)
+        .find(|dir| BUILD_DIRS.contains(&dir.as_str()))?;
+    if path.starts_with("src/") && !files.iter().any(|entry| top(entry) == "src") {
+        return Some(format!(
+            "`files` points at {}/, but this source file is packed too",
+            build_dir
+        ));
+    }
+    None
+}
+
+/// Whether `path` is a README or LICENSE file npm always packs
+fn is_always_included(path: &str) -> bool {
+    let name = path.to_ascii_lowercase();
+    let stem = name.split_once('.').map_or(name.as_str(), |(stem, _)| stem);
+    !path.contains('/') && ALWAYS_INCLUDED.contains(&stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn create_package(manifest: &str, files: &[(&str, &str)]) -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("package.json"), manifest).unwrap();
+        for (file, content) in files {
+            let path = temp_dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        temp_dir
+    }
+
+    fn paths(list: &PackList) -> Vec<&str> {
+        list.files.iter().map(|file| file.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_pack_list_without_files() -> Result<()> {
+        let temp_dir = create_package(
+            "{}",
+            &[
+                ("index.js", "12345"),
+                ("README.md", "123"),
+                (".env", "SECRET=1"),
+                (".npmrc", ""),
+                ("package-lock.json", "{}"),
+                ("node_modules/dep/index.js", ""),
+                (".git/HEAD", ""),
+                ("lib/.DS_Store", ""),
+                ("lib/util.js.orig", ""),
+                ("lib/node_modules/dep.js", ""),
+            ],
+        );
+
+        let list = pack_list(temp_dir.path())?;
+        assert_eq!(
+            paths(&list),
+            [".env", "README.md", "index.js", "package.json"]
+        );
+        assert_eq!(list.total_bytes, 5 + 3 + 8 + 2);
+
+        // Dotfiles are packed, but flagged
+        let flagged: Vec<_> = list.warnings().map(|file| file.path.as_str()).collect();
+        assert_eq!(flagged, [".env"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pack_list_ignore_files() -> Result<()> {
+        let temp_dir = create_package(
+            "{}",
+            &[
+                ("index.js", ""),
+                ("test/index.test.js", ""),
+                ("dist/index.js", ""),
+                ("docs/guide.md", ""),
+                ("docs/draft.md", ""),
+                ("docs/.npmignore", "draft.md\n"),
+                (".gitignore", "dist/\n"),
+            ],
+        );
+
+        // Without .npmignore, .gitignore applies
+        let list = pack_list(temp_dir.path())?;
+        assert_eq!(
+            paths(&list),
+            [
+                "docs/guide.md",
+                "index.js",
+                "package.json",
+                "test/index.test.js"
+            ]
+        );
+
+        // With one, .gitignore is not read at all
+        fs::write(temp_dir.path().join(".npmignore"), "test/\n")?;
+        let list = pack_list(temp_dir.path())?;
+        assert_eq!(
+            paths(&list),
+            ["dist/index.js", "docs/guide.md", "index.js", "package.json"]
+        );
+
+        // A negation cannot bring back a file inside an ignored directory
+        fs::write(
+            temp_dir.path().join(".npmignore"),
+            "test/\n!test/index.test.js\n*.md\n!guide.md\n",
+        )?;
+        let list = pack_list(temp_dir.path())?;
+        assert_eq!(
+            paths(&list),
+            ["dist/index.js", "docs/guide.md", "index.js", "package.json"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_pack_list_main_in_ignored_dir() -> Result<()> {
+        let temp_dir = create_package(
+            r#"{"main": "dist/index.js", "bin": {"tool": "./dist/cli/tool.js", "index": "dist/index.js"}}"#,
+            &[
+                ("dist/index.js", "123"),
+                ("dist/cli/tool.js", "12"),
+                ("dist/other.js", ""),
+                (".npmignore", "dist\n"),
+            ],
+        );
+
+        // The main and bin files are packed although `dist` is ignored, and
+        // nothing else from it
+        let list = pack_list(temp_dir.path())?;
+        assert_eq!(
+            paths(&list),
+            ["dist/cli/tool.js", "dist/index.js", "package.json"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_pack_list_files_field() -> Result<()> {
+        let temp_dir = create_package(
+            r#"{"files": ["dist/", "./types/*.d.ts"], "main": "src/index.js", "bin": {"tool": "./bin/cli.js"}}"#,
+            &[
+                ("dist/index.js", ""),
+                ("dist/index.js.map", ""),
+                ("dist/internal/.npmignore", "*.map\n"),
+                ("dist/internal/util.js", ""),
+                ("dist/internal/util.js.map", ""),
+                ("types/index.d.ts", ""),
+                ("types/nested/extra.d.ts", ""),
+                ("src/index.js", ""),
+                ("src/util.js", ""),
+                ("bin/cli.js", ""),
+                ("LICENSE", ""),
+                ("Readme.markdown", ""),
+                ("CHANGELOG.md", ""),
+                (".npmignore", "dist/\nREADME*\n"),
+                ("dist/node_modules/dep.js", ""),
+            ],
+        );
+
+        // The root .npmignore does not override `files`, ignore files in
+        // subdirectories do, and package.json, README, LICENSE, `main` and
+        // `bin` are always packed
+        let list = pack_list(temp_dir.path())?;
+        assert_eq!(
+            paths(&list),
+            [
+                "LICENSE",
+                "Readme.markdown",
+                "bin/cli.js",
+                "dist/index.js",
+                "dist/index.js.map",
+                "dist/internal/util.js",
+                "package.json",
+                "src/index.js",
+                "types/index.d.ts",
+            ]
+        );
+
+        // Sources packed beside the build output are flagged
+        let flagged: Vec<_> = list.warnings().collect();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].path, "src/index.js");
+        assert!(flagged[0].warning.as_ref().unwrap().contains("dist/"));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pack_list_symlinks() -> Result<()> {
+        let temp_dir = create_package(
+            r#"{"name": "pkg", "version": "1.0.0"}"#,
+            &[("src/index.js", ""), ("shared/util.js", "")],
+        );
+        let root = temp_dir.path();
+        std::os::unix::fs::symlink(root, root.join("src/loop"))?;
+        std::os::unix::fs::symlink(root.join("shared"), root.join("src/shared"))?;
+        std::os::unix::fs::symlink(root.join("shared/util.js"), root.join("src/util.js"))?;
+
+        let list = pack_list(root)?;
+        assert_eq!(
+            paths(&list),
+            vec![
+                "package.json",
+                "shared/util.js",
+                "src/index.js",
+                "src/util.js"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_listed_in_files() {
+        let files = ["dist", "lib/*.js", "index.d.ts"];
+        assert!(listed_in_files(&files, "dist/index.js"));
+        assert!(listed_in_files(&files, "dist/nested/index.js"));
+        assert!(listed_in_files(&files, "lib/util.js"));
+        assert!(!listed_in_files(&files, "lib/nested/util.js"));
+        assert!(listed_in_files(&files, "index.d.ts"));
+        assert!(!listed_in_files(&files, "distribution/index.js"));
+        assert!(!listed_in_files(&files, "types/index.d.ts"));
+        assert!(listed_in_files(&["dist*"], "dist-esm/index.js"));
+    }
+
+    #[test]
+    fn test_always_included_names() {
+        assert!(is_always_included("README.md"));
+        assert!(is_always_included("licence"));
+        assert!(is_always_included("COPYING.txt"));
+        assert!(!is_always_included("docs/README.md"));
+        assert!(!is_always_included("READMEFIRST.md"));
+    }
+}
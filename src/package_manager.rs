@@ -0,0 +1,1621 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::sync::{Mutex, OnceLock};
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::dependency::{DependencyKind, DependencySpec, DEPENDENCY_SECTIONS};
+use crate::error::Error;
+use crate::{node, process, progress, Result};
+
+/// Node.js package managers cargonode can drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum PackageManager {
+    /// npm (default)
+    #[default]
+    Npm,
+    /// Yarn Classic (1.x)
+    Yarn,
+    /// Yarn Berry (2.x and later)
+    YarnBerry,
+    /// pnpm
+    Pnpm,
+    /// Bun
+    Bun,
+}
+
+/// Every package manager, in lockfile detection order
+const ALL: [PackageManager; 5] = [
+    PackageManager::Pnpm,
+    PackageManager::YarnBerry,
+    PackageManager::Yarn,
+    PackageManager::Bun,
+    PackageManager::Npm,
+];
+
+/// Something a package manager is asked to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PmAction {
+    /// Install the dependencies declared in package.json
+    Install,
+    /// Install exactly what the lockfile records, failing if it is out of
+    /// date, as CI does
+    CleanInstall,
+    /// Add packages, given after the returned arguments
+    Add {
+        /// Section of package.json they are saved in
+        kind: DependencyKind,
+        /// Save their exact versions instead of caret ranges
+        exact: bool,
+    },
+    /// Remove packages, given after the returned arguments
+    Remove,
+    /// Run a script from package.json
+    RunScript(String),
+    /// Run a binary installed in the project
+    Exec(String),
+}
+
+/// Entries Yarn Berry needs in .gitignore to keep its install state untracked
+const YARN_BERRY_IGNORE: &str = r#".yarn/*
+!.yarn/patches
+!.yarn/plugins
+!.yarn/releases
+!.yarn/sdks
+!.yarn/versions
+.pnp.*
+"#;
+
+impl PackageManager {
+    /// Executable used to invoke the package manager
+    pub fn executable(&self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm",
+            PackageManager::Yarn | PackageManager::YarnBerry => "yarn",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Bun => "bun",
+        }
+    }
+
+    /// The program and arguments that perform `action`
+    ///
+    /// Yarn Berry takes `-D` and `-E` where the others spell out the flag.
+    /// Installed binaries run with `npx`, `yarn exec`, `pnpm exec` or `bunx`,
+    /// never with the `dlx` commands that download a package.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - What to do
+    ///
+    /// # Returns
+    ///
+    /// * `(&'static str, Vec<String>)` - The program to run and its arguments
+    pub fn command(&self, action: PmAction) -> (&'static str, Vec<String>) {
+        use PackageManager::*;
+
+        let program = self.executable();
+        let (program, args): (&str, Vec<&str>) = match action {
+            PmAction::Install => match self {
+                Yarn | YarnBerry => (program, vec![]),
+                _ => (program, vec!["install"]),
+            },
+            PmAction::CleanInstall => match self {
+                Npm => (program, vec!["ci"]),
+                YarnBerry => (program, vec!["install", "--immutable"]),
+                Yarn | Pnpm | Bun => (program, vec!["install", "--frozen-lockfile"]),
+            },
+            PmAction::Add { kind, exact } => {
+                let (verb, [dev, peer, optional], exact_flag) = match self {
+                    Npm => (
+                        "install",
+                        ["--save-dev", "--save-peer", "--save-optional"],
+                        "--save-exact",
+                    ),
+                    Yarn | Bun => ("add", ["--dev", "--peer", "--optional"], "--exact"),
+                    YarnBerry => ("add", ["-D", "-P", "-O"], "-E"),
+                    Pnpm => ("add", ["-D", "--save-peer", "-O"], "-E"),
+                };
+                let mut args = vec![verb];
+                args.extend(match kind {
+                    DependencyKind::Normal => None,
+                    DependencyKind::Dev => Some(dev),
+                    DependencyKind::Peer => Some(peer),
+                    DependencyKind::Optional => Some(optional),
+                });
+                args.extend(exact.then_some(exact_flag));
+                (program, args)
+            }
+            PmAction::Remove => match self {
+                Npm => (program, vec!["uninstall"]),
+                _ => (program, vec!["remove"]),
+            },
+            PmAction::RunScript(script) => {
+                return (program, vec!["run".to_string(), script]);
+            }
+            PmAction::Exec(binary) => {
+                let (program, args) = match self {
+                    Npm => ("npx", vec![]),
+                    Yarn | YarnBerry | Pnpm => (program, vec!["exec"]),
+                    Bun => ("bunx", vec![]),
+                };
+                let mut args: Vec<String> = args.into_iter().map(str::to_string).collect();
+                args.push(binary);
+                return (program, args);
+            }
+        };
+        (program, args.into_iter().map(str::to_string).collect())
+    }
+
+    /// Lockfiles written by the package manager
+    pub(crate) fn lockfiles(&self) -> &'static [&'static str] {
+        match self {
+            PackageManager::Npm => &["package-lock.json", "npm-shrinkwrap.json"],
+            PackageManager::Yarn | PackageManager::YarnBerry => &["yarn.lock"],
+            PackageManager::Pnpm => &["pnpm-lock.yaml"],
+            PackageManager::Bun => &["bun.lock", "bun.lockb"],
+        }
+    }
+
+    /// Whether the lockfiles in `dir` were written by this package manager
+    fn owns_lockfile_in(&self, dir: &Path) -> bool {
+        let has_lockfile = self
+            .lockfiles()
+            .iter()
+            .any(|lockfile| dir.join(lockfile).is_file());
+
+        // Both Yarn generations write yarn.lock; Berry also writes .yarnrc.yml
+        match self {
+            PackageManager::Yarn => has_lockfile && !dir.join(".yarnrc.yml").is_file(),
+            PackageManager::YarnBerry => has_lockfile && dir.join(".yarnrc.yml").is_file(),
+            _ => has_lockfile,
+        }
+    }
+
+    /// Parse `<name>` or `<name>@<version>`, as used by the `packageManager`
+    /// field and the npm user agent
+    fn from_spec(spec: &str) -> Option<Self> {
+        let (name, version) = spec.split_once(['@', '/']).unwrap_or((spec, ""));
+        match name {
+            "npm" => Some(PackageManager::Npm),
+            "yarn" => {
+                let major = version.split('.').next()?.parse::<u64>().unwrap_or(1);
+                Some(if major >= 2 {
+                    PackageManager::YarnBerry
+                } else {
+                    PackageManager::Yarn
+                })
+            }
+            "pnpm" => Some(PackageManager::Pnpm),
+            "bun" => Some(PackageManager::Bun),
+            _ => None,
+        }
+    }
+
+    /// Detect the package manager that launched cargonode
+    ///
+    /// Package managers set `npm_config_user_agent` (e.g. `pnpm/9.1.0 npm/? node/v20.11.0`)
+    /// for the scripts and binaries they run.
+    fn from_user_agent(user_agent: &str) -> Option<Self> {
+        Self::from_spec(user_agent.split_whitespace().next()?)
+    }
+
+    /// Make sure the package manager's executable can be found on PATH
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PathBuf>` - Path to the executable
+    pub fn ensure_available(&self) -> Result<PathBuf> {
+        find_executable(self.executable()).ok_or_else(|| Error::PackageManagerNotFound {
+            binary: self.executable().to_string(),
+        })
+    }
+
+    /// Build the command that installs the dependencies declared in package.json
+    ///
+    /// # Arguments
+    ///
+    /// * `project_dir` - Directory containing package.json
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - The install command, ready to spawn
+    pub fn install_command(&self, project_dir: &Path) -> Command {
+        let (program, args) = self.command(PmAction::Install);
+        let mut command = Command::new(program);
+        command.args(args).current_dir(project_dir);
+        progress::apply_color_env(&mut command);
+        command
+    }
+
+    /// Install the dependencies declared in package.json
+    ///
+    /// # Arguments
+    ///
+    /// * `project_dir` - Directory containing package.json
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ExitStatus>` - Exit status of a successful install
+    pub fn install(&self, project_dir: &Path) -> Result<ExitStatus> {
+        if node::needs_node(self.executable()) {
+            node::ensure_node(project_dir)?;
+        }
+        self.ensure_available()?;
+        let spinner = progress::ProgressBar::spinner("Installing");
+        let status = process::status_streamed(&mut self.install_command(project_dir))?;
+        drop(spinner);
+
+        if !status.success() {
+            return Err(Error::CommandFailed {
+                command: self.install_command_line(),
+                status,
+            });
+        }
+
+        Ok(status)
+    }
+
+    /// The install command as the user would type it
+    pub fn install_command_line(&self) -> String {
+        let (program, args) = self.command(PmAction::Install);
+        std::iter::once(program.to_string())
+            .chain(args)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Entries to append to .gitignore for projects using this package manager
+    ///
+    /// Lockfiles of other package managers are ignored so that running the
+    /// wrong one by accident does not end up committed.
+    pub fn gitignore_entries(&self) -> String {
+        let own = self.lockfiles();
+        let mut entries: Vec<&str> = ALL
+            .iter()
+            .flat_map(|package_manager| package_manager.lockfiles().iter().copied())
+            .filter(|lockfile| !own.contains(lockfile))
+            .collect();
+        entries.sort_unstable();
+        entries.dedup();
+
+        let mut content = entries.join("\n");
+        content.push('\n');
+        if *self == PackageManager::YarnBerry {
+            content.push_str(YARN_BERRY_IGNORE);
+        }
+        content
+    }
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.executable())
+    }
+}
+
+/// The package manager of a project, with the version pinned by the
+/// `packageManager` field of package.json if there is one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedPackageManager {
+    /// Which package manager to use
+    pub kind: PackageManager,
+    /// Exact version to use, when pinned
+    pub version: Option<Version>,
+}
+
+impl From<PackageManager> for DetectedPackageManager {
+    fn from(kind: PackageManager) -> Self {
+        Self {
+            kind,
+            version: None,
+        }
+    }
+}
+
+impl DetectedPackageManager {
+    /// Parse a `packageManager` field such as `pnpm@9.1.0` or `yarn@4.1.0+sha224.abc`
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - Value of the field
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Self>` - The package manager, or `None` if the name is unknown
+    ///   or the version is not a valid semver version
+    pub fn parse(spec: &str) -> Option<Self> {
+        let (name, version) = match spec.split_once('@') {
+            Some((name, version)) => (name, Some(Version::parse(version).ok()?)),
+            None => (spec, None),
+        };
+        let kind = match name {
+            "npm" => PackageManager::Npm,
+            "yarn" if version.as_ref().is_some_and(|version| version.major >= 2) => {
+                PackageManager::YarnBerry
+            }
+            "yarn" => PackageManager::Yarn,
+            "pnpm" => PackageManager::Pnpm,
+            "bun" => PackageManager::Bun,
+            _ => return None,
+        };
+        Some(Self { kind, version })
+    }
+
+    /// Whether installs go through corepack, which fetches the pinned version
+    fn uses_corepack(&self) -> bool {
+        self.version.is_some() && find_executable("corepack").is_some()
+    }
+
+    /// Make sure the package manager, or corepack for a pinned version, is on PATH
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PathBuf>` - Path to the executable
+    pub fn ensure_available(&self) -> Result<PathBuf> {
+        if self.uses_corepack() {
+            return find_executable("corepack").ok_or_else(|| Error::PackageManagerNotFound {
+                binary: "corepack".to_string(),
+            });
+        }
+        self.kind.ensure_available()
+    }
+
+    /// Build the command that installs the dependencies declared in package.json
+    ///
+    /// # Arguments
+    ///
+    /// * `project_dir` - Directory containing package.json
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - The install command, ready to spawn
+    pub fn install_command(&self, project_dir: &Path) -> Command {
+        self.action_command(PmAction::Install, &[], project_dir, self.uses_corepack())
+    }
+
+    /// Build the command that performs `action`, through corepack when it
+    /// fetches the pinned version
+    ///
+    /// Actions run by another program, such as `npx`, never go through
+    /// corepack.
+    fn action_command(
+        &self,
+        action: PmAction,
+        packages: &[String],
+        project_dir: &Path,
+        corepack: bool,
+    ) -> Command {
+        let (program, args) = self.kind.command(action);
+        let mut command = match (&self.version, corepack) {
+            (Some(_), true) if program == self.kind.executable() => {
+                let mut command = Command::new("corepack");
+                command.arg(self.to_string());
+                command
+            }
+            _ => Command::new(program),
+        };
+        command.args(args).args(packages).current_dir(project_dir);
+        progress::apply_color_env(&mut command);
+        command
+    }
+
+    /// Install the dependencies declared in package.json
+    ///
+    /// # Arguments
+    ///
+    /// * `project_dir` - Directory containing package.json
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ExitStatus>` - Exit status of a successful install
+    pub fn install(&self, project_dir: &Path) -> Result<ExitStatus> {
+        self.run(PmAction::Install, &[], project_dir)
+    }
+
+    /// Perform `action` on `packages`, e.g. add or remove them
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - What to do
+    /// * `packages` - Packages given after the action's arguments
+    /// * `project_dir` - Directory containing package.json
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ExitStatus>` - Exit status of a successful run, or
+    ///   `Error::CommandFailed` with the command line
+    pub fn run(
+        &self,
+        action: PmAction,
+        packages: &[String],
+        project_dir: &Path,
+    ) -> Result<ExitStatus> {
+        if node::needs_node(self.kind.executable()) {
+            node::ensure_node(project_dir)?;
+        }
+        self.ensure_available()?;
+        let label = match action {
+            PmAction::Remove => "Removing",
+            _ => "Installing",
+        };
+        let mut command = self.action_command(action, packages, project_dir, self.uses_corepack());
+        let spinner = progress::ProgressBar::spinner(label);
+        let status = process::status_streamed(&mut command)?;
+        drop(spinner);
+
+        if !status.success() {
+            return Err(Error::CommandFailed {
+                command: command_line(&command),
+                status,
+            });
+        }
+
+        Ok(status)
+    }
+
+    /// The install command as the user would type it
+    pub fn install_command_line(&self) -> String {
+        command_line(&self.install_command(Path::new(".")))
+    }
+}
+
+/// A command as the user would type it
+fn command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl fmt::Display for DetectedPackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{}@{}", self.kind, version),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+/// Detect the package manager used by the project at `project_root`
+///
+/// The `packageManager` field of package.json takes precedence, followed by
+/// the lockfiles present in the project (or its workspace root) and then the
+/// package manager that launched cargonode. Otherwise npm is used. A malformed
+/// `packageManager` field is reported as a warning and otherwise ignored.
+///
+/// # Arguments
+///
+/// * `project_root` - Directory containing package.json
+///
+/// # Returns
+///
+/// * `Result<DetectedPackageManager>` - The package manager and pinned version
+pub fn detect_package_manager(project_root: &Path) -> Result<DetectedPackageManager> {
+    let declared = fs::read_to_string(project_root.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|manifest| manifest.get("packageManager")?.as_str().map(str::to_string));
+    if let Some(spec) = declared {
+        match DetectedPackageManager::parse(&spec) {
+            Some(detected) => return Ok(detected),
+            None => progress::warn(&format!(
+                "ignoring malformed packageManager field `{}`, expected `<name>@<version>`",
+                spec
+            ))?,
+        }
+    }
+
+    let workspace_root = find_workspace_root(project_root)?;
+    let candidates = [Some(project_root.to_path_buf()), workspace_root];
+
+    let kind = candidates
+        .iter()
+        .flatten()
+        .find_map(|dir| {
+            ALL.into_iter()
+                .find(|package_manager| package_manager.owns_lockfile_in(dir))
+        })
+        .or_else(|| {
+            env::var("npm_config_user_agent")
+                .ok()
+                .and_then(|user_agent| PackageManager::from_user_agent(&user_agent))
+        })
+        .unwrap_or_default();
+    Ok(kind.into())
+}
+
+/// Find `program` in the directories listed in PATH
+///
+/// On Windows the extensions in PATHEXT are tried as well, since package
+/// managers are installed as `.cmd` shims there.
+pub(crate) fn find_executable(program: &str) -> Option<PathBuf> {
+    let extensions: Vec<String> = if cfg!(windows) {
+        env::var("PATHEXT")
+            .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
+            .split(';')
+            .map(str::to_string)
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+
+    env::split_paths(&env::var_os("PATH")?).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|extension| dir.join(format!("{}{}", program, extension)))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Find the root of the Node.js project containing `start`
+///
+/// This is the nearest directory, starting at `start` itself, that contains
+/// a package.json file.
+pub fn find_project_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join("package.json").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Variable overriding [`WORKSPACE_SEARCH_DEPTH`]
+pub const WORKSPACE_DEPTH_ENV: &str = "CARGONODE_WORKSPACE_MAX_DEPTH";
+
+/// Number of directories the search for a workspace goes up by default
+pub const WORKSPACE_SEARCH_DEPTH: usize = 32;
+
+/// Workspace roots found by `find_workspace_root`, by project root
+static WORKSPACE_ROOTS: OnceLock<Mutex<HashMap<PathBuf, PathBuf>>> = OnceLock::new();
+
+/// How far up the search for a workspace goes
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SearchBounds {
+    /// Directories above the start that are looked at
+    max_depth: usize,
+
+    /// The user's home directory, above which a search starting inside it
+    /// does not go
+    home: Option<PathBuf>,
+}
+
+impl SearchBounds {
+    /// The default bounds, with any override from [`WORKSPACE_DEPTH_ENV`]
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The bounds, or `Error::Config` if the variable is
+    ///   not a non-negative integer
+    fn from_env() -> Result<Self> {
+        let max_depth = parse_max_depth(env::var(WORKSPACE_DEPTH_ENV).ok().as_deref())?;
+        let home = env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" })
+            .map(PathBuf::from)
+            .filter(|home| home.is_absolute());
+        Ok(Self { max_depth, home })
+    }
+
+    /// `start` and the directories above it the search looks at, nearest
+    /// first
+    ///
+    /// The search stops after `max_depth` directories, at the home directory
+    /// when `start` is inside it, and before crossing into another
+    /// filesystem.
+    fn ancestors<'a>(&self, start: &'a Path) -> Vec<&'a Path> {
+        let home = self.home.as_deref().filter(|home| start.starts_with(home));
+        let mut device = None;
+        let mut dirs = Vec::new();
+        for dir in start.ancestors().take(self.max_depth.saturating_add(1)) {
+            // Directories that do not exist yet are on no filesystem
+            if let Some(id) = device_id(dir) {
+                if device.is_some_and(|device| device != id) {
+                    break;
+                }
+                device = Some(id);
+            }
+            dirs.push(dir);
+            if Some(dir) == home {
+                break;
+            }
+        }
+        dirs
+    }
+}
+
+/// Depth of the workspace search given the value of [`WORKSPACE_DEPTH_ENV`]
+///
+/// # Arguments
+///
+/// * `value` - The variable's value, if it is set
+///
+/// # Returns
+///
+/// * `Result<usize>` - The depth, [`WORKSPACE_SEARCH_DEPTH`] when unset, or
+///   `Error::Config` if the value is not a non-negative integer
+fn parse_max_depth(value: Option<&str>) -> Result<usize> {
+    let Some(value) = value else {
+        return Ok(WORKSPACE_SEARCH_DEPTH);
+    };
+    value.trim().parse().map_err(|_| Error::Config {
+        message: format!(
+            "Invalid {}='{}'; expected a non-negative integer",
+            WORKSPACE_DEPTH_ENV, value
+        ),
+        hint: None,
+    })
+}
+
+/// Identifier of the filesystem `path` is on, if it exists
+#[cfg(unix)]
+fn device_id(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    fs::metadata(path).ok().map(|metadata| metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device_id(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Find the root of the workspace containing `start`
+///
+/// Walks up from `start` looking for a package.json declaring `workspaces`,
+/// within the bounds described by `SearchBounds::ancestors`. Outside of a
+/// workspace this falls back to the project root.
+///
+/// The result is remembered for the rest of the process, keyed by the
+/// project root rather than `start` so that a package.json written in the
+/// meantime is not missed, and commands can ask repeatedly without reading
+/// every package.json above the project again.
+///
+/// # Returns
+///
+/// * `Result<Option<PathBuf>>` - The root, `None` outside of any project, or
+///   `Error::Config` if [`WORKSPACE_DEPTH_ENV`] is invalid
+pub fn find_workspace_root(start: &Path) -> Result<Option<PathBuf>> {
+    let Some(project_root) = find_project_root(start) else {
+        return Ok(None);
+    };
+    let roots = WORKSPACE_ROOTS.get_or_init(Default::default);
+    if let Some(root) = roots.lock().unwrap().get(&project_root) {
+        return Ok(Some(root.clone()));
+    }
+    let root = search_workspace_root(&project_root, &SearchBounds::from_env()?);
+    roots.lock().unwrap().insert(project_root, root.clone());
+    Ok(Some(root))
+}
+
+/// Search for the workspace containing `project_root` without the cache
+fn search_workspace_root(project_root: &Path, bounds: &SearchBounds) -> PathBuf {
+    bounds
+        .ancestors(project_root)
+        .into_iter()
+        .find(|dir| declares_workspaces(&dir.join("package.json")))
+        .unwrap_or(project_root)
+        .to_path_buf()
+}
+
+/// A package belonging to a workspace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspacePackage {
+    /// Name from the package's package.json, or its directory name
+    pub name: String,
+
+    /// Directory containing the package's package.json
+    pub path: PathBuf,
+
+    /// Names of every package it depends on, from all dependency sections
+    pub dependencies: Vec<String>,
+}
+
+/// Read the `workspaces` patterns of the root package.json
+///
+/// Both the array form and Yarn's `{ "packages": [...] }` form are supported.
+///
+/// # Arguments
+///
+/// * `workspace_root` - Directory containing the root package.json
+///
+/// # Returns
+///
+/// * `Result<Vec<String>>` - The patterns, including `!` exclusions
+pub fn workspace_patterns(workspace_root: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(workspace_root.join("package.json"))?;
+    let manifest: serde_json::Value = serde_json::from_str(&content)?;
+
+    let workspaces = manifest.get("workspaces");
+    let patterns = workspaces
+        .and_then(|w| w.get("packages"))
+        .or(workspaces)
+        .and_then(|w| w.as_array())
+        .ok_or_else(|| Error::Config {
+            message: format!(
+                "{} does not declare any `workspaces`",
+                workspace_root.join("package.json").display()
+            ),
+            hint: None,
+        })?;
+    Ok(patterns
+        .iter()
+        .filter_map(|p| p.as_str())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Whether `dir` is matched by the `workspaces` patterns of the workspace
+/// rooted at `workspace_root`, whether or not it exists yet
+///
+/// # Arguments
+///
+/// * `workspace_root` - Directory containing the root package.json
+/// * `dir` - Absolute path of the package directory
+///
+/// # Returns
+///
+/// * `Result<bool>` - Whether an including pattern matches and no `!`
+///   pattern excludes it
+pub fn is_workspace_member_path(workspace_root: &Path, dir: &Path) -> Result<bool> {
+    let Ok(relative) = dir.strip_prefix(workspace_root) else {
+        return Ok(false);
+    };
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    let matches = |pattern: &str| -> Result<bool> {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        let pattern = glob::Pattern::new(pattern).map_err(|e| Error::Config {
+            message: format!("Invalid workspace pattern '{}': {}", pattern, e),
+            hint: None,
+        })?;
+        Ok(pattern.matches_with(&relative, options))
+    };
+
+    let mut included = false;
+    for pattern in workspace_patterns(workspace_root)? {
+        match pattern.strip_prefix('!') {
+            Some(excluded) if matches(excluded)? => return Ok(false),
+            Some(_) => {}
+            None => included |= matches(&pattern)?,
+        }
+    }
+    Ok(included)
+}
+
+/// Find the workspace a package at `dir` would belong to
+///
+/// Unlike `find_workspace_root`, `dir` itself is not considered and there is
+/// no fallback to a plain project root.
+///
+/// # Arguments
+///
+/// * `dir` - Absolute path of the package directory, which may not exist yet
+///
+/// # Returns
+///
+/// * `Result<Option<PathBuf>>` - The nearest ancestor whose package.json
+///   declares `workspaces`, or `Error::Config` if [`WORKSPACE_DEPTH_ENV`] is
+///   invalid
+pub fn find_enclosing_workspace(dir: &Path) -> Result<Option<PathBuf>> {
+    Ok(SearchBounds::from_env()?
+        .ancestors(dir)
+        .into_iter()
+        .skip(1)
+        .find(|ancestor| declares_workspaces(&ancestor.join("package.json")))
+        .map(Path::to_path_buf))
+}
+
+/// Dependency range that refers to another member of the same workspace
+///
+/// pnpm, Yarn Berry and Bun understand the `workspace:` protocol; npm and
+/// Yarn Classic link any member whose version satisfies `*`.
+pub fn workspace_range(kind: PackageManager) -> &'static str {
+    match kind {
+        PackageManager::Pnpm | PackageManager::YarnBerry | PackageManager::Bun => "workspace:*",
+        PackageManager::Npm | PackageManager::Yarn => "*",
+    }
+}
+
+/// List the members of the workspace rooted at `workspace_root`
+///
+/// Members are the directories matched by the `workspaces` patterns of the
+/// root package.json that contain a package.json, in pattern order. Both the
+/// array form and Yarn's `{ "packages": [...] }` form are supported, and
+/// patterns starting with `!` exclude directories.
+///
+/// # Arguments
+///
+/// * `workspace_root` - Directory containing the root package.json
+///
+/// # Returns
+///
+/// * `Result<Vec<WorkspacePackage>>` - The workspace members
+pub fn get_workspace_packages(workspace_root: &Path) -> Result<Vec<WorkspacePackage>> {
+    let patterns = workspace_patterns(workspace_root)?;
+    let patterns: Vec<&str> = patterns.iter().map(String::as_str).collect();
+
+    let resolve = |pattern: &str| -> Result<Vec<PathBuf>> {
+        let absolute = format!(
+            "{}/{}",
+            glob::Pattern::escape(&workspace_root.to_string_lossy()),
+            pattern.trim_end_matches('/')
+        );
+        let paths = glob::glob(&absolute).map_err(|e| Error::Config {
+            message: format!("Invalid workspace pattern '{}': {}", pattern, e),
+            hint: None,
+        })?;
+        Ok(paths
+            .filter_map(|path| path.ok())
+            .filter(|path| path.join("package.json").is_file())
+            .collect())
+    };
+
+    let mut excluded = Vec::new();
+    for pattern in patterns.iter().filter_map(|p| p.strip_prefix('!')) {
+        excluded.extend(resolve(pattern)?);
+    }
+
+    let mut packages: Vec<WorkspacePackage> = Vec::new();
+    for pattern in patterns.iter().filter(|p| !p.starts_with('!')) {
+        for path in resolve(pattern)? {
+            if excluded.contains(&path) || packages.iter().any(|p| p.path == path) {
+                continue;
+            }
+            packages.push(read_workspace_package(path)?);
+        }
+    }
+
+    Ok(packages)
+}
+
+fn read_workspace_package(path: PathBuf) -> Result<WorkspacePackage> {
+    let content = fs::read_to_string(path.join("package.json"))?;
+    let manifest: serde_json::Value = serde_json::from_str(&content)?;
+
+    let name = manifest
+        .get("name")
+        .and_then(|name| name.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+
+    let mut dependencies: Vec<String> = Vec::new();
+    for section in DEPENDENCY_SECTIONS {
+        if let Some(deps) = manifest.get(section).and_then(|deps| deps.as_object()) {
+            for (dep, spec) in deps {
+                let dep = spec
+                    .as_str()
+                    .and_then(workspace_alias)
+                    .unwrap_or_else(|| dep.clone());
+                if !dependencies.contains(&dep) {
+                    dependencies.push(dep);
+                }
+            }
+        }
+    }
+
+    Ok(WorkspacePackage {
+        name,
+        path,
+        dependencies,
+    })
+}
+
+/// Name of the member an aliased `workspace:` specifier points to
+///
+/// `workspace:@repo/ui@*` installs the member `@repo/ui` under another name,
+/// while plain specifiers like `workspace:*` or `workspace:^1.0.0` refer to
+/// the member named by the dependency key.
+fn workspace_alias(spec: &str) -> Option<String> {
+    let target = spec.strip_prefix("workspace:")?;
+    if !target.starts_with(|c: char| c == '@' || c.is_ascii_alphabetic()) {
+        return None;
+    }
+    DependencySpec::parse(target, DependencyKind::Normal)
+        .ok()
+        .map(|spec| spec.name)
+}
+
+/// Dependency graph between the members of a workspace
+///
+/// Only dependencies on other members are kept, so every edge points at a
+/// package in the graph.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceGraph {
+    /// Workspace members in workspace order, with local dependencies only
+    pub packages: Vec<WorkspacePackage>,
+}
+
+impl WorkspaceGraph {
+    /// Build the graph, dropping dependencies on packages outside `packages`
+    ///
+    /// # Arguments
+    ///
+    /// * `packages` - The workspace members
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The dependency graph
+    pub fn new(mut packages: Vec<WorkspacePackage>) -> Self {
+        let names: Vec<String> = packages.iter().map(|p| p.name.clone()).collect();
+        for package in &mut packages {
+            let name = package.name.clone();
+            package
+                .dependencies
+                .retain(|dep| *dep != name && names.contains(dep));
+        }
+        Self { packages }
+    }
+
+    fn get(&self, name: &str) -> Option<&WorkspacePackage> {
+        self.packages.iter().find(|package| package.name == name)
+    }
+
+    /// Find a dependency cycle between members
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Vec<String>>` - The packages forming a cycle, in dependency
+    ///   order, e.g. `["a", "b"]` when `a` depends on `b` and `b` on `a`
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        fn visit<'a>(
+            graph: &'a WorkspaceGraph,
+            package: &'a WorkspacePackage,
+            path: &mut Vec<&'a str>,
+            done: &mut Vec<&'a str>,
+        ) -> Option<Vec<String>> {
+            if let Some(start) = path.iter().position(|name| *name == package.name) {
+                return Some(path[start..].iter().map(|name| name.to_string()).collect());
+            }
+            if done.contains(&package.name.as_str()) {
+                return None;
+            }
+
+            path.push(&package.name);
+            for dep in package.dependencies.iter().filter_map(|dep| graph.get(dep)) {
+                if let Some(cycle) = visit(graph, dep, path, done) {
+                    return Some(cycle);
+                }
+            }
+            path.pop();
+            done.push(&package.name);
+            None
+        }
+
+        let mut done = Vec::new();
+        self.packages
+            .iter()
+            .find_map(|package| visit(self, package, &mut Vec::new(), &mut done))
+    }
+
+    /// Order members so every package comes after the members it depends on
+    ///
+    /// Packages that do not depend on each other keep their workspace order.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<&WorkspacePackage>>` - The ordered packages, or an error
+    ///   naming the packages of a dependency cycle
+    pub fn topological_order(&self) -> Result<Vec<&WorkspacePackage>> {
+        if let Some(cycle) = self.find_cycle() {
+            return Err(Error::Config {
+                message: format!(
+                    "Workspace packages depend on each other in a cycle: {} -> {}",
+                    cycle.join(" -> "),
+                    cycle[0]
+                ),
+                hint: None,
+            });
+        }
+
+        let mut ordered: Vec<&WorkspacePackage> = Vec::with_capacity(self.packages.len());
+        while ordered.len() < self.packages.len() {
+            let is_ordered = |name: &str| ordered.iter().any(|p| p.name == name);
+            let ready = self.packages.iter().find(|package| {
+                !is_ordered(&package.name) && package.dependencies.iter().all(|dep| is_ordered(dep))
+            });
+            // Without a cycle some package is always ready
+            ordered.extend(ready);
+        }
+
+        Ok(ordered)
+    }
+}
+
+/// Build the dependency graph of the workspace rooted at `workspace_root`
+///
+/// # Arguments
+///
+/// * `workspace_root` - Directory containing the root package.json
+///
+/// # Returns
+///
+/// * `Result<WorkspaceGraph>` - Members and their dependencies on each other
+pub fn workspace_dependency_graph(workspace_root: &Path) -> Result<WorkspaceGraph> {
+    Ok(WorkspaceGraph::new(get_workspace_packages(workspace_root)?))
+}
+
+/// Whether `package_json` declares workspaces
+///
+/// An empty `workspaces` list declares none.
+fn declares_workspaces(package_json: &Path) -> bool {
+    let Some(manifest) = fs::read_to_string(package_json)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+    else {
+        return false;
+    };
+    let Some(workspaces) = manifest.get("workspaces") else {
+        return false;
+    };
+    match workspaces.get("packages").unwrap_or(workspaces) {
+        serde_json::Value::Array(patterns) => !patterns.is_empty(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_find_project_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let nested = root.join("src").join("deep");
+        fs::create_dir_all(&nested).unwrap();
+
+        assert!(find_project_root(&nested).is_none());
+
+        fs::write(root.join("package.json"), "{}").unwrap();
+        assert_eq!(find_project_root(&nested).unwrap(), root);
+        assert_eq!(find_project_root(root).unwrap(), root);
+    }
+
+    #[test]
+    fn test_is_workspace_member_path() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join("package.json"),
+            r#"{"workspaces": ["packages/*", "./apps/web/", "!packages/legacy"]}"#,
+        )?;
+        fs::create_dir_all(root.join("packages/ui/src"))?;
+
+        assert_eq!(
+            find_enclosing_workspace(&root.join("packages/ui"))?,
+            Some(root.to_path_buf())
+        );
+        assert_eq!(find_enclosing_workspace(root)?, None);
+
+        for (dir, member) in [
+            ("packages/new", true),
+            ("apps/web", true),
+            ("apps/docs", false),
+            ("packages/ui/src", false),
+            ("packages/legacy", false),
+            ("tools", false),
+        ] {
+            assert_eq!(
+                is_workspace_member_path(root, &root.join(dir))?,
+                member,
+                "{}",
+                dir
+            );
+        }
+        assert!(!is_workspace_member_path(root, Path::new("/elsewhere"))?);
+
+        assert_eq!(workspace_range(PackageManager::Pnpm), "workspace:*");
+        assert_eq!(workspace_range(PackageManager::Npm), "*");
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_workspace_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let member = root.join("packages").join("ui");
+        fs::create_dir_all(&member).unwrap();
+        fs::write(member.join("package.json"), r#"{"name": "ui"}"#).unwrap();
+
+        // Without a workspace the member is its own root
+        assert_eq!(find_workspace_root(&member).unwrap(), Some(member.clone()));
+
+        // An empty list of workspaces declares none
+        fs::write(
+            root.join("package.json"),
+            r#"{"name": "root", "workspaces": []}"#,
+        )
+        .unwrap();
+        let bounds = SearchBounds::from_env().unwrap();
+        assert_eq!(search_workspace_root(&member, &bounds), member);
+
+        fs::write(
+            root.join("package.json"),
+            r#"{"name": "root", "workspaces": ["packages/*"]}"#,
+        )
+        .unwrap();
+        assert_eq!(search_workspace_root(&member, &bounds), root);
+        assert_eq!(find_workspace_root(root).unwrap(), Some(root.to_path_buf()));
+
+        // The first answer for a project holds for the rest of the process
+        assert_eq!(
+            find_workspace_root(&member.join("src")).unwrap(),
+            Some(member)
+        );
+    }
+
+    #[test]
+    fn test_workspace_search_bounds() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let member = root.join("a/b/c/d");
+        fs::create_dir_all(&member).unwrap();
+        fs::write(member.join("package.json"), r#"{"name": "d"}"#).unwrap();
+        fs::write(
+            root.join("package.json"),
+            r#"{"name": "root", "workspaces": {"packages": ["a/b/c/*"]}}"#,
+        )
+        .unwrap();
+        let bounds = |max_depth, home: Option<&Path>| SearchBounds {
+            max_depth,
+            home: home.map(Path::to_path_buf),
+        };
+
+        assert_eq!(search_workspace_root(&member, &bounds(4, None)), root);
+        // The root is four directories up, one more than allowed
+        assert_eq!(search_workspace_root(&member, &bounds(3, None)), member);
+        assert_eq!(bounds(0, None).ancestors(&member), vec![member.as_path()]);
+
+        assert_eq!(parse_max_depth(None).unwrap(), WORKSPACE_SEARCH_DEPTH);
+        assert_eq!(parse_max_depth(Some(" 4 ")).unwrap(), 4);
+        assert_eq!(parse_max_depth(Some("0")).unwrap(), 0);
+        for value in ["-1", "deep", ""] {
+            let message = parse_max_depth(Some(value)).unwrap_err().to_string();
+            assert!(message.contains(WORKSPACE_DEPTH_ENV), "{}", message);
+        }
+
+        // A search starting inside the home directory stops there
+        let home = root.join("a/b");
+        assert_eq!(
+            search_workspace_root(&member, &bounds(32, Some(&home))),
+            member
+        );
+        assert_eq!(
+            bounds(32, Some(&home)).ancestors(&member).last(),
+            Some(&home.as_path())
+        );
+        // unless it starts outside of it
+        let elsewhere = root.join("x/y");
+        assert_eq!(
+            search_workspace_root(&member, &bounds(32, Some(&elsewhere))),
+            root
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_workspace_search_stops_at_filesystem_boundary() {
+        // /proc is its own filesystem on Linux, so the search from inside it
+        // never reaches /
+        let ancestors = SearchBounds::from_env()
+            .unwrap()
+            .ancestors(Path::new("/proc/self"));
+        assert!(!ancestors.contains(&Path::new("/")), "{:?}", ancestors);
+        assert!(ancestors.contains(&Path::new("/proc")));
+    }
+
+    #[test]
+    fn test_detect_package_manager() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let member = root.join("packages").join("ui");
+        fs::create_dir_all(&member).unwrap();
+        fs::write(member.join("package.json"), r#"{"name": "ui"}"#).unwrap();
+        fs::write(
+            root.join("package.json"),
+            r#"{"name": "root", "workspaces": ["packages/*"]}"#,
+        )
+        .unwrap();
+        let detect = |dir: &Path| detect_package_manager(dir).map(|detected| detected.kind);
+
+        assert_eq!(detect(&member)?, PackageManager::Npm);
+
+        // Lockfiles in the workspace root apply to its members
+        fs::write(root.join("pnpm-lock.yaml"), "").unwrap();
+        assert_eq!(detect(&member)?, PackageManager::Pnpm);
+
+        fs::write(member.join("yarn.lock"), "").unwrap();
+        assert_eq!(detect(&member)?, PackageManager::Yarn);
+
+        fs::write(member.join(".yarnrc.yml"), "").unwrap();
+        assert_eq!(detect(&member)?, PackageManager::YarnBerry);
+
+        // The packageManager field wins over every lockfile, in the project
+        // and in the workspace root, and pins the version
+        fs::write(root.join("pnpm-lock.yaml"), "").unwrap();
+        fs::write(member.join("yarn.lock"), "").unwrap();
+        fs::write(
+            member.join("package.json"),
+            r#"{"name": "ui", "packageManager": "bun@1.1.0"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            detect_package_manager(&member)?,
+            DetectedPackageManager {
+                kind: PackageManager::Bun,
+                version: Some(Version::new(1, 1, 0)),
+            }
+        );
+
+        fs::remove_file(member.join("yarn.lock")).unwrap();
+        fs::remove_file(member.join(".yarnrc.yml")).unwrap();
+        fs::remove_file(root.join("pnpm-lock.yaml")).unwrap();
+        fs::write(member.join("bun.lockb"), "").unwrap();
+        fs::write(
+            member.join("package.json"),
+            r#"{"name": "ui", "packageManager": "pnpm@9.1.0"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            detect_package_manager(&member)?,
+            DetectedPackageManager {
+                kind: PackageManager::Pnpm,
+                version: Some(Version::new(9, 1, 0)),
+            }
+        );
+
+        // A malformed field falls back to the lockfiles
+        fs::write(
+            member.join("package.json"),
+            r#"{"name": "ui", "packageManager": "weird"}"#,
+        )
+        .unwrap();
+        assert_eq!(detect(&member)?, PackageManager::Bun);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_package_manager_field() {
+        let parse = |spec: &str| {
+            DetectedPackageManager::parse(spec)
+                .map(|detected| (detected.kind, detected.version.map(|v| v.to_string())))
+        };
+
+        assert_eq!(parse("npm"), Some((PackageManager::Npm, None)));
+        assert_eq!(
+            parse("npm@10.8.1"),
+            Some((PackageManager::Npm, Some("10.8.1".to_string())))
+        );
+        assert_eq!(parse("yarn"), Some((PackageManager::Yarn, None)));
+        assert_eq!(
+            parse("yarn@1.22.19"),
+            Some((PackageManager::Yarn, Some("1.22.19".to_string())))
+        );
+        assert_eq!(
+            parse("yarn@4.1.0+sha224.abc"),
+            Some((
+                PackageManager::YarnBerry,
+                Some("4.1.0+sha224.abc".to_string())
+            ))
+        );
+        assert_eq!(parse("pnpm"), Some((PackageManager::Pnpm, None)));
+        assert_eq!(
+            parse("pnpm@9.1.0"),
+            Some((PackageManager::Pnpm, Some("9.1.0".to_string())))
+        );
+        assert_eq!(parse("weird"), None);
+        assert_eq!(parse("pnpm@latest"), None);
+        assert_eq!(parse("deno@2.0.0"), None);
+    }
+
+    #[test]
+    fn test_install_command_through_corepack() {
+        let dir = Path::new("/tmp/app");
+        let pinned = DetectedPackageManager::parse("pnpm@9.1.0").unwrap();
+
+        let command = pinned.action_command(PmAction::Install, &[], dir, true);
+        assert_eq!(command.get_program(), "corepack");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            vec!["pnpm@9.1.0", "install"]
+        );
+        assert_eq!(command.get_current_dir(), Some(dir));
+
+        // Without corepack, or without a pinned version, the binary runs directly
+        let command = pinned.action_command(PmAction::Install, &[], dir, false);
+        assert_eq!(command.get_program(), "pnpm");
+        let command = DetectedPackageManager::from(PackageManager::Yarn).action_command(
+            PmAction::Install,
+            &[],
+            dir,
+            true,
+        );
+        assert_eq!(command.get_program(), "yarn");
+
+        // Packages follow the action's arguments
+        let command = pinned.action_command(PmAction::Remove, &["lodash".to_string()], dir, true);
+        assert_eq!(command_line(&command), "corepack pnpm@9.1.0 remove lodash");
+        let npm = DetectedPackageManager::parse("npm@10.8.0").unwrap();
+        let command = npm.action_command(PmAction::Exec("tsc".to_string()), &[], dir, true);
+        assert_eq!(command_line(&command), "npx tsc");
+    }
+
+    #[test]
+    fn test_find_executable() {
+        #[cfg(unix)]
+        assert!(find_executable("sh").is_some_and(|path| path.is_absolute()));
+        assert!(find_executable("cargonode-missing-binary").is_none());
+    }
+
+    #[test]
+    fn test_from_spec() {
+        assert_eq!(
+            PackageManager::from_spec("yarn@1.22.19"),
+            Some(PackageManager::Yarn)
+        );
+        assert_eq!(
+            PackageManager::from_spec("yarn@4.1.0+sha224.abc"),
+            Some(PackageManager::YarnBerry)
+        );
+        assert_eq!(
+            PackageManager::from_spec("yarn"),
+            Some(PackageManager::Yarn)
+        );
+        assert_eq!(PackageManager::from_spec("deno@2.0.0"), None);
+        assert_eq!(
+            PackageManager::from_user_agent("pnpm/9.1.0 npm/? node/v20.11.0 linux x64"),
+            Some(PackageManager::Pnpm)
+        );
+    }
+
+    #[test]
+    fn test_install_command() {
+        let dir = Path::new("/tmp/app");
+        let expected = [
+            (PackageManager::Npm, "npm", vec!["install"]),
+            (PackageManager::Yarn, "yarn", vec![]),
+            (PackageManager::YarnBerry, "yarn", vec![]),
+            (PackageManager::Pnpm, "pnpm", vec!["install"]),
+            (PackageManager::Bun, "bun", vec!["install"]),
+        ];
+
+        for (package_manager, program, args) in expected {
+            let command = package_manager.install_command(dir);
+            assert_eq!(command.get_program(), program);
+            assert_eq!(command.get_args().collect::<Vec<_>>(), args);
+            assert_eq!(command.get_current_dir(), Some(dir));
+        }
+
+        assert_eq!(PackageManager::Pnpm.install_command_line(), "pnpm install");
+        assert_eq!(PackageManager::Yarn.install_command_line(), "yarn");
+    }
+
+    #[test]
+    fn test_command() {
+        use PackageManager::*;
+
+        let add = |dev: bool, exact| PmAction::Add {
+            kind: if dev {
+                DependencyKind::Dev
+            } else {
+                DependencyKind::Normal
+            },
+            exact,
+        };
+        let add_kind = |kind| PmAction::Add { kind, exact: false };
+        let expected = [
+            (Npm, PmAction::Install, "npm install"),
+            (Yarn, PmAction::Install, "yarn"),
+            (YarnBerry, PmAction::Install, "yarn"),
+            (Pnpm, PmAction::Install, "pnpm install"),
+            (Bun, PmAction::Install, "bun install"),
+            (Npm, PmAction::CleanInstall, "npm ci"),
+            (
+                Yarn,
+                PmAction::CleanInstall,
+                "yarn install --frozen-lockfile",
+            ),
+            (
+                YarnBerry,
+                PmAction::CleanInstall,
+                "yarn install --immutable",
+            ),
+            (
+                Pnpm,
+                PmAction::CleanInstall,
+                "pnpm install --frozen-lockfile",
+            ),
+            (Bun, PmAction::CleanInstall, "bun install --frozen-lockfile"),
+            (Npm, add(false, false), "npm install"),
+            (Npm, add(true, true), "npm install --save-dev --save-exact"),
+            (Yarn, add(true, false), "yarn add --dev"),
+            (Yarn, add(false, true), "yarn add --exact"),
+            (YarnBerry, add(true, true), "yarn add -D -E"),
+            (Pnpm, add(true, false), "pnpm add -D"),
+            (Pnpm, add(false, true), "pnpm add -E"),
+            (Bun, add(true, true), "bun add --dev --exact"),
+            (
+                Npm,
+                add_kind(DependencyKind::Peer),
+                "npm install --save-peer",
+            ),
+            (
+                Npm,
+                add_kind(DependencyKind::Optional),
+                "npm install --save-optional",
+            ),
+            (Yarn, add_kind(DependencyKind::Peer), "yarn add --peer"),
+            (YarnBerry, add_kind(DependencyKind::Optional), "yarn add -O"),
+            (Pnpm, add_kind(DependencyKind::Peer), "pnpm add --save-peer"),
+            (
+                Bun,
+                add_kind(DependencyKind::Optional),
+                "bun add --optional",
+            ),
+            (Npm, PmAction::Remove, "npm uninstall"),
+            (Yarn, PmAction::Remove, "yarn remove"),
+            (YarnBerry, PmAction::Remove, "yarn remove"),
+            (Pnpm, PmAction::Remove, "pnpm remove"),
+            (Bun, PmAction::Remove, "bun remove"),
+            (Npm, PmAction::RunScript("build".into()), "npm run build"),
+            (Yarn, PmAction::RunScript("build".into()), "yarn run build"),
+            (
+                YarnBerry,
+                PmAction::RunScript("build".into()),
+                "yarn run build",
+            ),
+            (Pnpm, PmAction::RunScript("build".into()), "pnpm run build"),
+            (Bun, PmAction::RunScript("build".into()), "bun run build"),
+            (Npm, PmAction::Exec("tsc".into()), "npx tsc"),
+            (Yarn, PmAction::Exec("tsc".into()), "yarn exec tsc"),
+            (YarnBerry, PmAction::Exec("tsc".into()), "yarn exec tsc"),
+            (Pnpm, PmAction::Exec("tsc".into()), "pnpm exec tsc"),
+            (Bun, PmAction::Exec("tsc".into()), "bunx tsc"),
+        ];
+
+        for (package_manager, action, command_line) in expected {
+            let (program, args) = package_manager.command(action.clone());
+            let argv: Vec<&str> = std::iter::once(program)
+                .chain(args.iter().map(String::as_str))
+                .collect();
+            assert_eq!(
+                argv.join(" "),
+                command_line,
+                "{:?} {:?}",
+                package_manager,
+                action
+            );
+        }
+    }
+
+    #[test]
+    fn test_gitignore_entries() {
+        let entries = PackageManager::Pnpm.gitignore_entries();
+        assert!(entries.contains("package-lock.json\n"));
+        assert!(entries.contains("yarn.lock\n"));
+        assert!(!entries.contains("pnpm-lock.yaml"));
+
+        let entries = PackageManager::YarnBerry.gitignore_entries();
+        assert!(!entries.contains("yarn.lock"));
+        assert!(entries.contains(".pnp.*"));
+    }
+
+    #[test]
+    fn test_get_workspace_packages() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let write = |dir: &str, manifest: &str| {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("package.json"), manifest).unwrap();
+        };
+
+        write(
+            ".",
+            r#"{"name": "root", "workspaces": ["packages/*", "!packages/old", "tools/cli"]}"#,
+        );
+        write(
+            "packages/app",
+            r#"{"name": "@repo/app", "dependencies": {"@repo/ui": "*", "react": "^18"}, "devDependencies": {"@repo/ui": "*"}}"#,
+        );
+        write("packages/ui", r#"{"name": "@repo/ui"}"#);
+        write("packages/old", r#"{"name": "@repo/old"}"#);
+        write("tools/cli", "{}");
+        fs::create_dir_all(root.join("packages/docs")).unwrap();
+
+        let packages = get_workspace_packages(root).unwrap();
+        let names: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["@repo/app", "@repo/ui", "cli"]);
+        assert_eq!(packages[0].path, root.join("packages/app"));
+        assert_eq!(packages[0].dependencies, vec!["@repo/ui", "react"]);
+
+        // Yarn's object form
+        write(
+            ".",
+            r#"{"name": "root", "workspaces": {"packages": ["tools/*"]}}"#,
+        );
+        let packages = get_workspace_packages(root).unwrap();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].name, "cli");
+
+        write(".", r#"{"name": "root"}"#);
+        assert!(matches!(
+            get_workspace_packages(root),
+            Err(Error::Config { .. })
+        ));
+    }
+
+    #[test]
+    fn test_workspace_dependency_graph() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let write = |dir: &str, manifest: &str| {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("package.json"), manifest).unwrap();
+        };
+
+        write(".", r#"{"workspaces": ["packages/*"]}"#);
+        write(
+            "packages/app",
+            r#"{"name": "app", "dependencies": {"ui-kit": "workspace:ui@*", "react": "^18"}}"#,
+        );
+        write(
+            "packages/ui",
+            r#"{"name": "ui", "dependencies": {"core": "workspace:*", "ui": "workspace:*"}}"#,
+        );
+        write("packages/core", r#"{"name": "core"}"#);
+
+        let graph = workspace_dependency_graph(root).unwrap();
+        assert_eq!(graph.get("app").unwrap().dependencies, vec!["ui"]);
+        assert_eq!(graph.get("ui").unwrap().dependencies, vec!["core"]);
+        assert_eq!(graph.find_cycle(), None);
+
+        let order: Vec<&str> = graph
+            .topological_order()
+            .unwrap()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(order, vec!["core", "ui", "app"]);
+
+        let json = serde_json::to_value(&graph).unwrap();
+        assert_eq!(json["packages"][0]["name"], "app");
+        assert_eq!(json["packages"][0]["dependencies"][0], "ui");
+    }
+
+    #[test]
+    fn test_workspace_graph_cycle() {
+        let package = |name: &str, deps: &[&str]| WorkspacePackage {
+            name: name.to_string(),
+            path: PathBuf::from(name),
+            dependencies: deps.iter().map(|d| d.to_string()).collect(),
+        };
+
+        let graph = WorkspaceGraph::new(vec![
+            package("app", &["a"]),
+            package("a", &["b"]),
+            package("b", &["c", "react"]),
+            package("c", &["a"]),
+        ]);
+        assert_eq!(
+            graph.find_cycle(),
+            Some(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+        );
+        assert!(matches!(
+            graph.topological_order(),
+            Err(Error::Config { message, .. }) if message.ends_with("a -> b -> c -> a")
+        ));
+    }
+}
@@ -0,0 +1,493 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Signal number reported for runs stopped by Ctrl-C
+pub const SIGINT: i32 = 2;
+
+/// Interval at which a running child is checked for exit, timeout and Ctrl-C
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Time a child gets to exit after SIGTERM before it is killed
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Number of children currently waited on by [`wait_with_timeout`]
+static RUNNING_CHILDREN: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether Ctrl-C was pressed while a child was running
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Temporary paths removed when Ctrl-C exits cargonode
+static INTERRUPT_CLEANUP: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// How a child started by cargonode finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitOutcome {
+    /// The child exited on its own
+    Exited(ExitStatus),
+    /// The child ran past its time limit and was killed
+    TimedOut,
+    /// The child was killed because of Ctrl-C
+    Interrupted,
+}
+
+/// Run `command` to completion while a progress bar may be shown
+///
+/// When progress is drawn, the command's output is read line by line and
+/// printed around the bar instead of over it. Otherwise the command writes
+/// to the terminal directly.
+///
+/// # Arguments
+///
+/// * `command` - Command to run
+///
+/// # Returns
+///
+/// * `io::Result<ExitStatus>` - How the command exited
+pub fn status_streamed(command: &mut Command) -> io::Result<ExitStatus> {
+    if !crate::progress::progress_enabled() {
+        return command.status();
+    }
+
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let forward = |stream: Option<Box<dyn Read + Send>>, to_stderr: bool| {
+        thread::spawn(move || {
+            let Some(stream) = stream else {
+                return;
+            };
+            for line in BufReader::new(stream).lines().map_while(|line| line.ok()) {
+                crate::progress::suspend(|| {
+                    if to_stderr {
+                        let _ = writeln!(io::stderr(), "{}", line);
+                    } else {
+                        let _ = writeln!(io::stdout(), "{}", line);
+                        let _ = io::stdout().flush();
+                    }
+                });
+            }
+        })
+    };
+    let stdout = forward(child.stdout.take().map(|s| Box::new(s) as _), false);
+    let stderr = forward(child.stderr.take().map(|s| Box::new(s) as _), true);
+    let status = child.wait()?;
+    let _ = stdout.join();
+    let _ = stderr.join();
+    Ok(status)
+}
+
+/// Configure `command` to start in its own process group
+///
+/// This lets [`wait_with_timeout`] kill everything the command spawned, e.g.
+/// the node processes started by `npm run`. On Unix the group is made the
+/// terminal's foreground group so interactive prompts keep working. On
+/// Windows nothing changes here; the child is put in a job object once
+/// [`wait_with_timeout`] waits for it.
+pub fn isolate(command: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        command.process_group(0);
+        // The child also claims the terminal itself, as shells do, so it can
+        // never read from it while still in the background
+        // SAFETY: only async-signal-safe libc calls are made between fork and exec
+        unsafe {
+            command.pre_exec(|| {
+                unix::take_terminal(libc::getpid());
+                Ok(())
+            });
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+/// Wait for a child started with [`isolate`]
+///
+/// The child's process group is killed once `timeout` elapses or Ctrl-C is
+/// pressed. On Windows the child is put in a job object instead, and the
+/// whole job is terminated.
+///
+/// # Arguments
+///
+/// * `child` - The running child
+/// * `timeout` - Maximum time the child may run
+///
+/// # Returns
+///
+/// * `io::Result<WaitOutcome>` - How the child finished
+pub fn wait_with_timeout(child: &mut Child, timeout: Option<Duration>) -> io::Result<WaitOutcome> {
+    let guard = RunningGuard::new(child);
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(WaitOutcome::Exited(status));
+        }
+
+        let outcome = if INTERRUPTED.load(Ordering::SeqCst) {
+            WaitOutcome::Interrupted
+        } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            WaitOutcome::TimedOut
+        } else {
+            thread::sleep(POLL_INTERVAL);
+            continue;
+        };
+
+        guard.kill(child)?;
+        return Ok(outcome);
+    }
+}
+
+/// Removes a temporary path if Ctrl-C exits cargonode while it is held
+///
+/// Dropping the guard only forgets the path; removing it otherwise is up to
+/// the caller.
+#[derive(Debug)]
+pub struct InterruptCleanup {
+    path: PathBuf,
+}
+
+impl Drop for InterruptCleanup {
+    fn drop(&mut self) {
+        let mut paths = cleanup_paths();
+        if let Some(index) = paths.iter().rposition(|path| *path == self.path) {
+            paths.remove(index);
+        }
+    }
+}
+
+/// Remove `path` if Ctrl-C exits cargonode before the guard is dropped
+///
+/// Ctrl-C outside a child run exits cargonode right away, so files being
+/// written and directories being staged are registered here to not be
+/// left behind. Locks need no cleanup, as the system releases them.
+///
+/// # Arguments
+///
+/// * `path` - Temporary file or directory
+///
+/// # Returns
+///
+/// * `InterruptCleanup` - Keeps `path` registered until dropped
+pub fn remove_on_interrupt(path: &Path) -> InterruptCleanup {
+    install_interrupt_handler();
+    cleanup_paths().push(path.to_path_buf());
+    InterruptCleanup {
+        path: path.to_path_buf(),
+    }
+}
+
+fn cleanup_paths() -> MutexGuard<'static, Vec<PathBuf>> {
+    INTERRUPT_CLEANUP
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Remove the registered temporary paths and exit as Ctrl-C would
+fn exit_interrupted() -> ! {
+    for path in cleanup_paths().iter().rev() {
+        let _ = match path.is_dir() {
+            true => fs::remove_dir_all(path),
+            false => fs::remove_file(path),
+        };
+    }
+    std::process::exit(128 + SIGINT)
+}
+
+/// Marks a child as running and hands it the terminal for its lifetime
+struct RunningGuard {
+    /// Whether the terminal was given to the child and must be taken back
+    #[cfg(unix)]
+    took_terminal: bool,
+    /// Job holding the child and the processes it starts
+    #[cfg(windows)]
+    job: Option<windows::Job>,
+}
+
+impl RunningGuard {
+    fn new(child: &Child) -> Self {
+        install_interrupt_handler();
+        if RUNNING_CHILDREN.fetch_add(1, Ordering::SeqCst) == 0 {
+            INTERRUPTED.store(false, Ordering::SeqCst);
+        }
+
+        #[cfg(unix)]
+        {
+            Self {
+                took_terminal: unix::take_terminal(child.id() as libc::pid_t),
+            }
+        }
+        #[cfg(windows)]
+        {
+            Self {
+                job: windows::Job::assign(child),
+            }
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            let _ = child;
+            Self {}
+        }
+    }
+
+    /// Terminate the child and every process it started
+    fn kill(&self, child: &mut Child) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            unix::signal_group(child, libc::SIGTERM);
+            let deadline = Instant::now() + KILL_GRACE_PERIOD;
+            while child.try_wait()?.is_none() && Instant::now() < deadline {
+                thread::sleep(POLL_INTERVAL);
+            }
+            // Grandchildren may outlive the child, so the group is always killed
+            unix::signal_group(child, libc::SIGKILL);
+        }
+        #[cfg(windows)]
+        {
+            let _ = KILL_GRACE_PERIOD;
+            match &self.job {
+                Some(job) if job.terminate() => {}
+                _ => child.kill()?,
+            }
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            let _ = KILL_GRACE_PERIOD;
+            child.kill()?;
+        }
+
+        child.wait()?;
+        Ok(())
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if self.took_terminal {
+            // SAFETY: getpgrp has no preconditions
+            unix::take_terminal(unsafe { libc::getpgrp() });
+        }
+        RUNNING_CHILDREN.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Route Ctrl-C to [`wait_with_timeout`] while a child is running
+///
+/// Outside of a child run Ctrl-C still exits cargonode immediately, after
+/// removing the paths registered with [`remove_on_interrupt`].
+fn install_interrupt_handler() {
+    static INSTALL: std::sync::Once = std::sync::Once::new();
+    #[cfg(unix)]
+    INSTALL.call_once(unix::install_interrupt_handler);
+    #[cfg(windows)]
+    INSTALL.call_once(windows::install_interrupt_handler);
+    #[cfg(not(any(unix, windows)))]
+    let _ = &INSTALL;
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::process::Child;
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::thread;
+
+    use super::{INTERRUPTED, RUNNING_CHILDREN, SIGINT};
+
+    /// Write end of the pipe that wakes the thread exiting on Ctrl-C
+    static WAKE_FD: AtomicI32 = AtomicI32::new(-1);
+
+    extern "C" fn on_interrupt(_: libc::c_int) {
+        if RUNNING_CHILDREN.load(Ordering::SeqCst) > 0 {
+            INTERRUPTED.store(true, Ordering::SeqCst);
+            return;
+        }
+        // Cleanup is not async-signal-safe, so it is left to the thread
+        let byte = 1u8;
+        let fd = WAKE_FD.load(Ordering::SeqCst);
+        // SAFETY: write and _exit are async-signal-safe
+        unsafe {
+            if fd < 0 || libc::write(fd, (&byte as *const u8).cast(), 1) != 1 {
+                libc::_exit(128 + SIGINT);
+            }
+        }
+    }
+
+    pub(super) fn install_interrupt_handler() {
+        let mut fds = [0; 2];
+        // SAFETY: pipe fills in the two descriptors of a new pipe
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } == 0 {
+            let [read_fd, write_fd] = fds;
+            WAKE_FD.store(write_fd, Ordering::SeqCst);
+            thread::spawn(move || {
+                let mut byte = 0u8;
+                // SAFETY: one byte is read into a local buffer; reads
+                // interrupted by a signal are retried
+                while unsafe { libc::read(read_fd, (&mut byte as *mut u8).cast(), 1) } != 1 {}
+                super::exit_interrupted();
+            });
+        }
+        // SAFETY: the handler only touches atomics, writes to a pipe and calls _exit
+        unsafe {
+            libc::signal(
+                libc::SIGINT,
+                on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            );
+        }
+    }
+
+    /// Make `pgid` the foreground process group of the terminal on stdin
+    ///
+    /// Returns whether stdin is a terminal whose foreground group was changed.
+    pub(super) fn take_terminal(pgid: libc::pid_t) -> bool {
+        // SAFETY: these calls are async-signal-safe and only affect stdin's
+        // terminal; SIGTTOU is ignored so a background group may call tcsetpgrp
+        unsafe {
+            if libc::isatty(libc::STDIN_FILENO) != 1 {
+                return false;
+            }
+            let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+            let changed = libc::tcsetpgrp(libc::STDIN_FILENO, pgid) == 0;
+            libc::signal(libc::SIGTTOU, previous);
+            changed
+        }
+    }
+
+    /// Send `signal` to the process group led by `child`
+    pub(super) fn signal_group(child: &Child, signal: libc::c_int) {
+        // SAFETY: killpg has no memory-safety preconditions; a group that is
+        // already gone is not an error here
+        unsafe {
+            libc::killpg(child.id() as libc::pid_t, signal);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::os::windows::io::AsRawHandle;
+    use std::process::Child;
+    use std::ptr;
+    use std::sync::atomic::Ordering;
+
+    use windows_sys::Win32::Foundation::{CloseHandle, BOOL, FALSE, HANDLE, TRUE};
+    use windows_sys::Win32::System::Console::{SetConsoleCtrlHandler, CTRL_C_EVENT};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, TerminateJobObject,
+    };
+
+    use super::{INTERRUPTED, RUNNING_CHILDREN};
+
+    /// Job object holding a child and every process it starts
+    ///
+    /// Processes the child started before it was added to the job are not
+    /// part of it.
+    pub(super) struct Job(HANDLE);
+
+    impl Job {
+        /// Put `child` in a new job, if the system allows it
+        pub(super) fn assign(child: &Child) -> Option<Self> {
+            // SAFETY: null attributes and name create an anonymous job
+            let handle = unsafe { CreateJobObjectW(ptr::null(), ptr::null()) };
+            if handle.is_null() {
+                return None;
+            }
+            let job = Self(handle);
+            // SAFETY: both handles stay open for the duration of the call
+            let assigned =
+                unsafe { AssignProcessToJobObject(job.0, child.as_raw_handle() as HANDLE) };
+            (assigned != 0).then_some(job)
+        }
+
+        /// Terminate every process in the job
+        pub(super) fn terminate(&self) -> bool {
+            // SAFETY: the handle is owned by this job
+            unsafe { TerminateJobObject(self.0, 1) != 0 }
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            // SAFETY: the handle is owned by this job and closed only here
+            unsafe { CloseHandle(self.0) };
+        }
+    }
+
+    unsafe extern "system" fn on_control(event: u32) -> BOOL {
+        if event != CTRL_C_EVENT {
+            return FALSE;
+        }
+        if RUNNING_CHILDREN.load(Ordering::SeqCst) == 0 {
+            super::exit_interrupted();
+        }
+        INTERRUPTED.store(true, Ordering::SeqCst);
+        TRUE
+    }
+
+    pub(super) fn install_interrupt_handler() {
+        // SAFETY: the handler runs on a thread of its own, so it may clean up
+        // and exit there
+        unsafe { SetConsoleCtrlHandler(Some(on_control), TRUE) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wait_with_timeout_kills_group() {
+        // The grandchild `sleep` must be killed along with the shell
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 30; echo done"]);
+        isolate(&mut command);
+        let mut child = command.spawn().unwrap();
+
+        let start = Instant::now();
+        let outcome = wait_with_timeout(&mut child, Some(Duration::from_millis(200))).unwrap();
+        assert_eq!(outcome, WaitOutcome::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        let mut command = Command::new("true");
+        isolate(&mut command);
+        let mut child = command.spawn().unwrap();
+        assert!(matches!(
+            wait_with_timeout(&mut child, Some(Duration::from_secs(10))).unwrap(),
+            WaitOutcome::Exited(status) if status.success()
+        ));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_wait_with_timeout_kills_job() {
+        // The grandchild `ping` is in the job along with cmd
+        let mut command = Command::new("cmd");
+        command.args(["/C", "ping -n 30 127.0.0.1 > NUL"]);
+        isolate(&mut command);
+        let mut child = command.spawn().unwrap();
+
+        let start = Instant::now();
+        let outcome = wait_with_timeout(&mut child, Some(Duration::from_millis(200))).unwrap();
+        assert_eq!(outcome, WaitOutcome::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_remove_on_interrupt() {
+        let path = std::env::temp_dir().join(format!("cargonode-interrupt-{}", std::process::id()));
+        let guard = remove_on_interrupt(&path);
+        assert!(cleanup_paths().contains(&path));
+        drop(guard);
+        assert!(!cleanup_paths().contains(&path));
+    }
+}
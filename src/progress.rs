@@ -1,8 +1,19 @@
 use std::{
-    env,
-    io::{self, Write},
+    cell::RefCell,
+    collections::{BTreeMap, HashSet},
+    env, fmt,
+    io::{self, IsTerminal, Write},
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
+use serde::Serialize;
+
 /// Terminal colors as ANSI escape codes
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Color {
@@ -27,15 +38,112 @@ impl Color {
     }
 }
 
-fn should_use_colors() -> bool {
-    if cfg!(test) {
-        return false;
+/// When output is styled with colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Only when messages are written to a terminal that is not dumb (default)
+    #[default]
+    Auto,
+    /// Always, even when output is redirected
+    Always,
+    /// Never
+    Never,
+}
+
+impl ColorChoice {
+    /// Resolve the color setting from `--color` and the environment
+    ///
+    /// `--color` wins. Otherwise a non-empty `NO_COLOR` turns colors off and
+    /// a `FORCE_COLOR` other than empty, `0` or `false` turns them on;
+    /// `FORCE_COLOR=0` turns them off.
+    ///
+    /// # Arguments
+    ///
+    /// * `flag` - Value of `--color`, if passed
+    /// * `no_color` - Value of `NO_COLOR`, if set
+    /// * `force_color` - Value of `FORCE_COLOR`, if set
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The color setting
+    pub fn resolve(
+        flag: Option<ColorChoice>,
+        no_color: Option<&str>,
+        force_color: Option<&str>,
+    ) -> Self {
+        if let Some(choice) = flag {
+            return choice;
+        }
+        if no_color.is_some_and(|value| !value.is_empty()) {
+            return ColorChoice::Never;
+        }
+        match force_color {
+            Some("0" | "false") => ColorChoice::Never,
+            Some(value) if !value.is_empty() => ColorChoice::Always,
+            _ => ColorChoice::Auto,
+        }
+    }
+
+    /// Resolve the color setting from `--color` and the process environment
+    pub fn from_env(flag: Option<ColorChoice>) -> Self {
+        let var = |name| env::var(name).ok();
+        Self::resolve(
+            flag,
+            var("NO_COLOR").as_deref(),
+            var("FORCE_COLOR").as_deref(),
+        )
+    }
+}
+
+static COLOR_CHOICE: OnceLock<ColorChoice> = OnceLock::new();
+
+/// Choose when output is styled for the rest of the process
+///
+/// Only the first call has an effect; `auto` is used until then.
+pub fn set_color_choice(choice: ColorChoice) {
+    let _ = COLOR_CHOICE.set(choice);
+}
+
+/// When output is styled
+pub fn color_choice() -> ColorChoice {
+    COLOR_CHOICE.get().copied().unwrap_or_default()
+}
+
+/// Whether messages are styled with colors
+///
+/// With `auto`, colors are left out when the terminal is dumb or the stream
+/// messages are written to is not a terminal. Labels such as `error:` are
+/// printed either way.
+pub fn colors_enabled() -> bool {
+    match color_choice() {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            let terminal = match message_format() {
+                MessageFormat::Human => io::stdout().is_terminal(),
+                MessageFormat::Json => io::stderr().is_terminal(),
+            };
+            !cfg!(test) && terminal && env::var("TERM").is_ok_and(|term| term != "dumb")
+        }
+    }
+}
+
+/// Make a child process color its output the way cargonode does
+///
+/// Tools such as biome and vitest see `FORCE_COLOR` when cargonode uses
+/// colors and `NO_COLOR` when it does not, so their output matches even
+/// though it is read through a pipe. Variables set afterwards, such as a
+/// tool's configured `env`, still win.
+pub fn apply_color_env(command: &mut Command) {
+    if colors_enabled() {
+        command.env("FORCE_COLOR", "1").env_remove("NO_COLOR");
+    } else {
+        command.env("NO_COLOR", "1").env_remove("FORCE_COLOR");
     }
-    env::var("NO_COLOR").is_err() && env::var("TERM").is_ok()
 }
 
 pub fn style_text(text: &str, color: Color, is_bold: bool) -> String {
-    if !should_use_colors() {
+    if !colors_enabled() {
         return text.to_string();
     }
 
@@ -51,12 +159,21 @@ pub fn style_text(text: &str, color: Color, is_bold: bool) -> String {
 
 /// Format an error message with consistent styling
 pub fn format_error(message: &str) -> String {
+    format_labeled_error("error", message)
+}
+
+/// Format an error message with its code, as `error[CODE]: ...`
+pub fn format_coded_error(code: &str, message: &str) -> String {
+    format_labeled_error(&format!("error[{}]", code), message)
+}
+
+fn format_labeled_error(label: &str, message: &str) -> String {
     let parts: Vec<&str> = message.split("\n\n").collect();
     let main_message = parts[0];
 
     let mut formatted = vec![format!(
         "{}: {}",
-        style_text("error", Color::Red, true),
+        style_text(label, Color::Red, true),
         main_message
     )];
 
@@ -76,29 +193,792 @@ pub fn format_error(message: &str) -> String {
     formatted.join("\n\n")
 }
 
+fn format_label(label: &str, color: Color, message: &str) -> String {
+    format!("{}: {}", style_text(label, color, true), message)
+}
+
 /// Format a warning message with consistent styling
 pub fn format_warning(message: &str) -> String {
-    format!(
-        "{}: {}",
-        style_text("warning", Color::Yellow, true),
-        message
-    )
+    format_label("warning", Color::Yellow, message)
 }
 
 /// Format an informational note with consistent styling
 pub fn format_note(message: &str) -> String {
-    format!("{}: {}", style_text("note", Color::Blue, true), message)
+    format_label("note", Color::Blue, message)
 }
 
 /// Format a status message with consistent styling
 pub fn format_status(status: &str, message: &str) -> String {
-    format!("{}: {}", style_text(status, Color::Green, true), message)
+    format_label(status, Color::Green, message)
+}
+
+/// How progress is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum MessageFormat {
+    /// Styled messages for people (default)
+    #[default]
+    Human,
+    /// Newline-delimited JSON events on stdout, messages on stderr
+    Json,
+}
+
+/// A file a dry run would write
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlannedFile {
+    /// Path relative to the project directory
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+}
+
+/// Something that happened while running a command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    /// A tool is about to run, or to be looked up in the cache
+    ToolStarted {
+        /// Name of the tool
+        tool: String,
+    },
+    /// One step of a tool configured with `steps` finished
+    StepFinished {
+        /// Name of the tool
+        tool: String,
+        /// Position of the step, starting at 1
+        step: usize,
+        /// Command line of the step
+        command: String,
+        /// Time spent, in milliseconds
+        duration_ms: u64,
+        /// Exit code of the step
+        exit_code: i32,
+    },
+    /// A tool finished, successfully or not
+    ToolFinished {
+        /// Name of the tool
+        tool: String,
+        /// Time spent, in milliseconds
+        duration_ms: u64,
+        /// Exit code of the command, or the one cargonode exits with
+        exit_code: i32,
+        /// Whether a cached run was reused
+        cached: bool,
+        /// Verified output files, relative to the project directory
+        outputs: Vec<String>,
+    },
+    /// What `new` or `init` would do, reported instead of doing it
+    ScaffoldPlan {
+        /// Project directory
+        path: String,
+        /// Files that would be written
+        files: Vec<PlannedFile>,
+        /// Existing files that would be left untouched
+        skipped: Vec<String>,
+        /// Value of every template placeholder
+        substitutions: BTreeMap<String, String>,
+        /// Version control repository that would be created, if any
+        vcs: Option<String>,
+        /// Install command that would run, if any
+        install: Option<String>,
+        /// Problems found while planning
+        warnings: Vec<String>,
+    },
+    /// Result of a command asked for JSON output, such as `info --json`
+    Output {
+        /// Command that produced the result
+        command: String,
+        /// The result
+        data: serde_json::Value,
+    },
+    /// The command failed
+    Error {
+        /// Stable code of the error, e.g. `CN_CONFIG`, if it has one
+        code: Option<String>,
+        /// What went wrong
+        message: String,
+        /// How to fix it, if known
+        suggestion: Option<String>,
+    },
+}
+
+/// Importance of a progress message, from most to least important
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// Something failed
+    Error,
+    /// Something may be wrong
+    Warn,
+    /// What the user asked for, such as the history table or the files a
+    /// package would contain
+    Output,
+    /// What cargonode is doing, and plain output that accompanies it
+    Status,
+    /// Extra information about what cargonode is doing
+    Note,
+    /// Details for troubleshooting cargonode itself
+    Debug,
+}
+
+/// How many progress messages are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Verbosity {
+    /// Only errors, warnings and the output asked for (`--quiet`)
+    Quiet,
+    /// Errors, warnings, status lines and notes (default)
+    #[default]
+    Normal,
+    /// Also what commands print with `--verbose` (`-v`)
+    Verbose,
+    /// Also debug messages (`-vv`)
+    Debug,
+}
+
+impl Verbosity {
+    /// Verbosity for the number of `-v` flags and `--quiet`
+    ///
+    /// # Arguments
+    ///
+    /// * `verbose` - Number of times `-v` was passed
+    /// * `quiet` - Whether `--quiet` was passed, which wins
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The verbosity
+    pub fn from_flags(verbose: u8, quiet: bool) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::Debug,
+        }
+    }
+
+    /// Whether messages at `level` are shown
+    pub fn shows(self, level: Level) -> bool {
+        match level {
+            Level::Error | Level::Warn | Level::Output => true,
+            Level::Status | Level::Note => self >= Verbosity::Normal,
+            Level::Debug => self >= Verbosity::Debug,
+        }
+    }
+}
+
+/// A progress message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Message<'a> {
+    /// Importance of the message
+    pub level: Level,
+    /// Label printed before the text, e.g. `warning` or `Compiling`; plain
+    /// output such as tables has none
+    pub label: Option<&'a str>,
+    /// The message itself
+    pub text: &'a str,
+}
+
+impl Message<'_> {
+    /// The message as printed, styled when colors are enabled
+    pub fn styled(&self) -> String {
+        let Some(label) = self.label else {
+            return self.text.to_string();
+        };
+        match self.level {
+            Level::Error => format_labeled_error(label, self.text),
+            Level::Warn => format_label(label, Color::Yellow, self.text),
+            Level::Output | Level::Status => format_label(label, Color::Green, self.text),
+            Level::Note => format_label(label, Color::Blue, self.text),
+            Level::Debug => format_label(label, Color::Gray, self.text),
+        }
+    }
+}
+
+impl fmt::Display for Message<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.label {
+            Some(label) => write!(f, "{}: {}", label, self.text),
+            None => f.write_str(self.text),
+        }
+    }
+}
+
+/// Destination for progress messages and events
+///
+/// Library users can replace the default sink with [`set_sink`], for
+/// example to forward messages to their own logging. Messages hidden by the
+/// current [`Verbosity`] never reach the sink.
+pub trait Sink: Send + Sync {
+    /// Write a human-readable progress message
+    fn message(&self, message: &Message) -> io::Result<()>;
+
+    /// Report an event
+    fn event(&self, event: &Event) -> io::Result<()>;
+}
+
+/// Prints messages to stdout; only errors are shown of all events
+pub struct HumanSink;
+
+impl Sink for HumanSink {
+    fn message(&self, message: &Message) -> io::Result<()> {
+        suspend(|| {
+            let mut stdout = io::stdout().lock();
+            writeln!(stdout, "{}", message.styled())?;
+            stdout.flush()
+        })
+    }
+
+    fn event(&self, event: &Event) -> io::Result<()> {
+        if let Event::Output { data, .. } = event {
+            return suspend(|| {
+                let mut stdout = io::stdout().lock();
+                serde_json::to_writer(&mut stdout, data)?;
+                writeln!(stdout)?;
+                stdout.flush()
+            });
+        }
+        let Event::Error {
+            code,
+            message,
+            suggestion,
+        } = event
+        else {
+            return Ok(());
+        };
+        let text = match suggestion {
+            Some(suggestion) => format!("{}\n\nSuggestion: {}", message, suggestion),
+            None => message.clone(),
+        };
+        let label = match code {
+            Some(code) => format!("error[{}]", code),
+            None => "error".to_string(),
+        };
+        self.message(&Message {
+            level: Level::Error,
+            label: Some(&label),
+            text: &text,
+        })
+    }
+}
+
+/// Writes events as JSON lines to stdout and moves messages to stderr
+pub struct JsonSink;
+
+impl Sink for JsonSink {
+    fn message(&self, message: &Message) -> io::Result<()> {
+        let mut stderr = io::stderr().lock();
+        writeln!(stderr, "{}", message.styled())?;
+        stderr.flush()
+    }
+
+    fn event(&self, event: &Event) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        serde_json::to_writer(&mut stdout, event)?;
+        writeln!(stdout)?;
+        stdout.flush()
+    }
+}
+
+/// A message kept by [`RecordingSink`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedMessage {
+    /// Importance of the message
+    pub level: Level,
+    /// The message without styling, label included
+    pub text: String,
+}
+
+/// Keeps messages and events instead of printing them, for assertions
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use cargonode::progress::{self, Level, RecordingSink};
+///
+/// let sink = Arc::new(RecordingSink::default());
+/// progress::with_sink(sink.clone(), || progress::warn("disk almost full")).unwrap();
+/// assert_eq!(sink.messages()[0].level, Level::Warn);
+/// assert_eq!(sink.messages()[0].text, "warning: disk almost full");
+/// ```
+#[derive(Debug, Default)]
+pub struct RecordingSink {
+    messages: Mutex<Vec<RecordedMessage>>,
+    events: Mutex<Vec<Event>>,
+}
+
+impl RecordingSink {
+    /// Messages received so far, oldest first
+    pub fn messages(&self) -> Vec<RecordedMessage> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    /// Events received so far, oldest first
+    pub fn events(&self) -> Vec<Event> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl Sink for RecordingSink {
+    fn message(&self, message: &Message) -> io::Result<()> {
+        self.messages.lock().unwrap().push(RecordedMessage {
+            level: message.level,
+            text: message.to_string(),
+        });
+        Ok(())
+    }
+
+    fn event(&self, event: &Event) -> io::Result<()> {
+        self.events.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+static MESSAGE_FORMAT: OnceLock<MessageFormat> = OnceLock::new();
+
+/// Choose how progress is reported for the rest of the process
+///
+/// Only the first call has an effect; human output is used until then.
+pub fn set_message_format(format: MessageFormat) {
+    let _ = MESSAGE_FORMAT.set(format);
+}
+
+/// How progress is reported
+pub fn message_format() -> MessageFormat {
+    MESSAGE_FORMAT.get().copied().unwrap_or_default()
+}
+
+static VERBOSITY: OnceLock<Verbosity> = OnceLock::new();
+
+/// Choose how many messages are shown for the rest of the process
+///
+/// Only the first call has an effect; `normal` is used until then.
+pub fn set_verbosity(verbosity: Verbosity) {
+    let _ = VERBOSITY.set(verbosity);
+}
+
+/// How many messages are shown
+pub fn verbosity() -> Verbosity {
+    VERBOSITY.get().copied().unwrap_or_default()
+}
+
+/// Sink installed with [`set_sink`]
+static CUSTOM_SINK: RwLock<Option<Arc<dyn Sink>>> = RwLock::new(None);
+
+thread_local! {
+    /// Sink installed with [`with_sink`] on this thread
+    static SCOPED_SINK: RefCell<Option<Arc<dyn Sink>>> = const { RefCell::new(None) };
+}
+
+/// Send all messages and events to `sink` instead of the terminal
+pub fn set_sink(sink: Arc<dyn Sink>) {
+    *CUSTOM_SINK.write().unwrap_or_else(|e| e.into_inner()) = Some(sink);
+}
+
+/// Send the messages and events of `report` on this thread to `sink`
+///
+/// Threads spawned by `report` still use the process-wide sink.
+///
+/// # Arguments
+///
+/// * `sink` - Sink to use while `report` runs
+/// * `report` - Code whose messages are captured
+///
+/// # Returns
+///
+/// * `T` - What `report` returned
+pub fn with_sink<T>(sink: Arc<dyn Sink>, report: impl FnOnce() -> T) -> T {
+    struct Restore(Option<Arc<dyn Sink>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCOPED_SINK.with(|scoped| *scoped.borrow_mut() = self.0.take());
+        }
+    }
+
+    let _restore = Restore(SCOPED_SINK.with(|scoped| scoped.borrow_mut().replace(sink)));
+    report()
+}
+
+/// The sink messages and events currently go to
+///
+/// That is the one installed with [`with_sink`] on this thread, then the one
+/// installed with [`set_sink`], then the default for the message format.
+pub fn sink() -> Arc<dyn Sink> {
+    if let Some(sink) = SCOPED_SINK.with(|scoped| scoped.borrow().clone()) {
+        return sink;
+    }
+    if let Some(sink) = CUSTOM_SINK
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+    {
+        return sink;
+    }
+    match message_format() {
+        MessageFormat::Human => Arc::new(HumanSink),
+        MessageFormat::Json => Arc::new(JsonSink),
+    }
+}
+
+/// Send a message to the current sink, unless the verbosity hides it
+pub fn report(message: &Message) -> io::Result<()> {
+    if !verbosity().shows(message.level) {
+        return Ok(());
+    }
+    sink().message(message)
+}
+
+/// Report an error
+pub fn error(message: &str) -> io::Result<()> {
+    report(&Message {
+        level: Level::Error,
+        label: Some("error"),
+        text: message,
+    })
+}
+
+/// Report a warning
+pub fn warn(message: &str) -> io::Result<()> {
+    report(&Message {
+        level: Level::Warn,
+        label: Some("warning"),
+        text: message,
+    })
+}
+
+/// Warnings already reported by [`warn_once`]
+static WARNED: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// Report a warning unless the same one was already reported by this process
+///
+/// For warnings about state that does not change while cargonode runs, such
+/// as an ignored configuration file, which would otherwise repeat every time
+/// it is read.
+pub fn warn_once(message: &str) -> io::Result<()> {
+    let first = WARNED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(HashSet::new)
+        .insert(message.to_string());
+    match first {
+        true => warn(message),
+        false => Ok(()),
+    }
+}
+
+/// Report what cargonode is doing, e.g. `status("Running", "build")`
+pub fn status(status: &str, message: &str) -> io::Result<()> {
+    report(&Message {
+        level: Level::Status,
+        label: Some(status),
+        text: message,
+    })
+}
+
+/// Report extra information
+pub fn note(message: &str) -> io::Result<()> {
+    report(&Message {
+        level: Level::Note,
+        label: Some("note"),
+        text: message,
+    })
+}
+
+/// Report a detail that only matters when troubleshooting, shown with `-vv`
+pub fn debug(message: &str) -> io::Result<()> {
+    report(&Message {
+        level: Level::Debug,
+        label: Some("debug"),
+        text: message,
+    })
 }
 
-/// Write a message to stdout with proper formatting
+/// Write plain output the command was asked for, such as a table
+///
+/// Unlike status lines it is shown with `--quiet` too. Output goes to
+/// stdout, or to stderr when stdout carries JSON events.
 pub fn write_message(message: &str) -> io::Result<()> {
-    println!("{}", message);
-    io::stdout().flush()
+    report(&Message {
+        level: Level::Output,
+        label: None,
+        text: message,
+    })
+}
+
+/// Write plain output that accompanies status lines, such as a summary of
+/// what ran, at the status level
+pub fn write_status(message: &str) -> io::Result<()> {
+    report(&Message {
+        level: Level::Status,
+        label: None,
+        text: message,
+    })
+}
+
+/// Write plain output that accompanies an error, such as what a failed
+/// command printed to stderr, at the error level
+pub fn write_error(message: &str) -> io::Result<()> {
+    report(&Message {
+        level: Level::Error,
+        label: None,
+        text: message,
+    })
+}
+
+/// Write the result of a command asked for JSON output
+///
+/// Human output prints `value` as one line of JSON on stdout; JSON output
+/// wraps it in an `output` event.
+///
+/// # Arguments
+///
+/// * `command` - Command that produced the result, e.g. `info`
+/// * `value` - The result
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Whether the result could be written
+pub fn write_json(command: &str, value: &impl Serialize) -> io::Result<()> {
+    emit(&Event::Output {
+        command: command.to_string(),
+        data: serde_json::to_value(value)?,
+    })
+}
+
+/// Report an event to the current sink
+pub fn emit(event: &Event) -> io::Result<()> {
+    sink().event(event)
+}
+
+/// When progress bars and spinners are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ProgressMode {
+    /// Only when stderr is a terminal and messages are human-readable (default)
+    #[default]
+    Auto,
+    /// Never; long steps are only announced with a status line
+    Never,
+    /// Always, even when stderr is not a terminal
+    Always,
+}
+
+static PROGRESS_MODE: OnceLock<ProgressMode> = OnceLock::new();
+
+/// Choose when progress bars are drawn for the rest of the process
+///
+/// Only the first call has an effect; `auto` is used until then.
+pub fn set_progress_mode(mode: ProgressMode) {
+    let _ = PROGRESS_MODE.set(mode);
+}
+
+/// Whether progress bars and spinners are drawn
+///
+/// `--quiet` hides them whatever the progress mode.
+pub fn progress_enabled() -> bool {
+    draws_progress(
+        PROGRESS_MODE.get().copied().unwrap_or_default(),
+        verbosity(),
+    )
+}
+
+fn draws_progress(mode: ProgressMode, verbosity: Verbosity) -> bool {
+    match (mode, verbosity) {
+        (_, Verbosity::Quiet) | (ProgressMode::Never, _) => false,
+        (ProgressMode::Always, _) => true,
+        (ProgressMode::Auto, _) => {
+            !cfg!(test)
+                && message_format() == MessageFormat::Human
+                && io::stderr().is_terminal()
+                && env::var("TERM").is_ok_and(|term| term != "dumb")
+        }
+    }
+}
+
+/// How often spinners move and elapsed times are updated
+const TICK: Duration = Duration::from_millis(100);
+
+/// Frames of the spinner animation
+const SPINNER_FRAMES: [char; 4] = ['-', '\\', '|', '/'];
+
+/// Width of a progress bar, in characters
+const BAR_WIDTH: usize = 24;
+
+/// State shared between a progress bar, its ticker thread and the sink
+#[derive(Debug)]
+struct BarState {
+    message: String,
+    total: Option<u64>,
+    position: AtomicU64,
+    started: Instant,
+    finished: AtomicBool,
+}
+
+impl BarState {
+    /// The line drawn for the bar at its current position
+    fn line(&self) -> String {
+        let elapsed = self.started.elapsed();
+        let position = self.position.load(Ordering::Relaxed);
+        let status = style_text(&self.message, Color::Green, true);
+        match self.total {
+            Some(total) if total > 0 => {
+                let filled = (position.min(total) * BAR_WIDTH as u64 / total) as usize;
+                format!(
+                    "{} [{}{}] {}/{}",
+                    status,
+                    "=".repeat(filled),
+                    " ".repeat(BAR_WIDTH - filled),
+                    format_bytes(position),
+                    format_bytes(total)
+                )
+            }
+            total => {
+                let frame = (elapsed.as_millis() / TICK.as_millis()) as usize;
+                let mut line = format!(
+                    "{} {} {}s",
+                    status,
+                    SPINNER_FRAMES[frame % SPINNER_FRAMES.len()],
+                    elapsed.as_secs()
+                );
+                // Downloads without a known size still show what arrived
+                if total.is_some() || position > 0 {
+                    line.push_str(&format!(" {}", format_bytes(position)));
+                }
+                line
+            }
+        }
+    }
+}
+
+/// The bar currently drawn on stderr, if any
+static ACTIVE_BAR: Mutex<Option<Arc<BarState>>> = Mutex::new(None);
+
+/// Erase the line the progress bar is drawn on
+fn clear_line() {
+    eprint!("\r\x1b[2K");
+}
+
+/// Run `write` with the active progress bar hidden, then draw it again
+///
+/// Everything printed while a bar is shown must go through here, so that
+/// output lines are not mixed into the bar's line.
+///
+/// # Arguments
+///
+/// * `write` - Prints the output
+///
+/// # Returns
+///
+/// * `T` - What `write` returned
+pub fn suspend<T>(write: impl FnOnce() -> T) -> T {
+    let active = ACTIVE_BAR.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(bar) = active.as_ref() else {
+        return write();
+    };
+    clear_line();
+    let _ = io::stderr().flush();
+    let result = write();
+    eprint!("{}", bar.line());
+    let _ = io::stderr().flush();
+    result
+}
+
+/// A progress bar or spinner for a long step, drawn on stderr
+///
+/// Nothing is drawn when `progress_enabled` is false; callers announce the
+/// step with a status line either way. The bar disappears when dropped.
+#[derive(Debug)]
+pub struct ProgressBar {
+    state: Option<Arc<BarState>>,
+    ticker: Option<thread::JoinHandle<()>>,
+}
+
+impl ProgressBar {
+    /// Show a spinner with the elapsed time next to `message`
+    pub fn spinner(message: &str) -> Self {
+        Self::start(message, None)
+    }
+
+    /// Show a bar counting bytes up to `total`, or a spinner with the byte
+    /// count when the total is not known
+    pub fn bytes(message: &str, total: Option<u64>) -> Self {
+        Self::start(message, Some(total.unwrap_or(0)))
+    }
+
+    fn start(message: &str, total: Option<u64>) -> Self {
+        if !progress_enabled() {
+            return Self {
+                state: None,
+                ticker: None,
+            };
+        }
+
+        let state = Arc::new(BarState {
+            message: message.to_string(),
+            total,
+            position: AtomicU64::new(0),
+            started: Instant::now(),
+            finished: AtomicBool::new(false),
+        });
+        *ACTIVE_BAR.lock().unwrap_or_else(|e| e.into_inner()) = Some(Arc::clone(&state));
+
+        let ticking = Arc::clone(&state);
+        let ticker = thread::spawn(move || {
+            while !ticking.finished.load(Ordering::Relaxed) {
+                {
+                    let active = ACTIVE_BAR.lock().unwrap_or_else(|e| e.into_inner());
+                    if active.is_some() {
+                        clear_line();
+                        eprint!("{}", ticking.line());
+                        let _ = io::stderr().flush();
+                    }
+                }
+                thread::sleep(TICK);
+            }
+        });
+
+        Self {
+            state: Some(state),
+            ticker: Some(ticker),
+        }
+    }
+
+    /// Advance the bar by `delta` bytes
+    pub fn inc(&self, delta: u64) {
+        if let Some(state) = &self.state {
+            state.position.fetch_add(delta, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for ProgressBar {
+    fn drop(&mut self) {
+        let Some(state) = self.state.take() else {
+            return;
+        };
+        state.finished.store(true, Ordering::Relaxed);
+        if let Some(ticker) = self.ticker.take() {
+            let _ = ticker.join();
+        }
+        *ACTIVE_BAR.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        clear_line();
+        let _ = io::stderr().flush();
+    }
+}
+
+/// Format a byte count with a binary unit, e.g. `1.5 MiB`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
 }
 
 #[cfg(test)]
@@ -146,4 +1026,182 @@ mod tests {
         assert!(formatted.contains("Details: Process terminated"));
         assert!(formatted.contains("Suggestion: Check permissions"));
     }
+
+    #[test]
+    fn test_verbosity() {
+        assert_eq!(Verbosity::from_flags(0, false), Verbosity::Normal);
+        assert_eq!(Verbosity::from_flags(1, false), Verbosity::Verbose);
+        assert_eq!(Verbosity::from_flags(3, false), Verbosity::Debug);
+        assert_eq!(Verbosity::from_flags(2, true), Verbosity::Quiet);
+
+        assert!(Verbosity::Quiet.shows(Level::Warn));
+        assert!(!Verbosity::Quiet.shows(Level::Status));
+        assert!(Verbosity::Quiet.shows(Level::Output));
+        assert!(Verbosity::Normal.shows(Level::Note));
+        assert!(!Verbosity::Verbose.shows(Level::Debug));
+        assert!(Verbosity::Debug.shows(Level::Debug));
+    }
+
+    #[test]
+    fn test_color_choice_resolution() {
+        use ColorChoice::*;
+
+        // The flag wins over the environment
+        assert_eq!(ColorChoice::resolve(Some(Always), Some("1"), None), Always);
+        assert_eq!(ColorChoice::resolve(Some(Never), None, Some("1")), Never);
+        assert_eq!(ColorChoice::resolve(Some(Auto), Some("1"), None), Auto);
+
+        // NO_COLOR wins over FORCE_COLOR, and empty values are unset
+        assert_eq!(ColorChoice::resolve(None, Some("1"), Some("1")), Never);
+        assert_eq!(ColorChoice::resolve(None, Some(""), Some("1")), Always);
+        assert_eq!(ColorChoice::resolve(None, None, Some("3")), Always);
+        assert_eq!(ColorChoice::resolve(None, None, Some("0")), Never);
+        assert_eq!(ColorChoice::resolve(None, None, Some("false")), Never);
+        assert_eq!(ColorChoice::resolve(None, None, Some("")), Auto);
+        assert_eq!(ColorChoice::resolve(None, None, None), Auto);
+    }
+
+    #[test]
+    fn test_apply_color_env() {
+        // Colors are off in tests, so children are told not to use them
+        let mut command = Command::new("biome");
+        command.env("FORCE_COLOR", "1");
+        apply_color_env(&mut command);
+        let envs: Vec<_> = command.get_envs().collect();
+        assert!(envs.contains(&("NO_COLOR".as_ref(), Some("1".as_ref()))));
+        assert!(envs.contains(&("FORCE_COLOR".as_ref(), None)));
+    }
+
+    #[test]
+    fn test_with_sink() {
+        let sink = Arc::new(RecordingSink::default());
+        with_sink(sink.clone(), || -> io::Result<()> {
+            status("Compiling", "app")?;
+            note("using tsc")?;
+            // Hidden at the default verbosity
+            debug("cache key")?;
+            write_message("| a | b |")?;
+            write_status("ran 1 tool")?;
+            emit(&Event::ToolStarted {
+                tool: "build".to_string(),
+            })
+        })
+        .unwrap();
+
+        let messages: Vec<_> = sink
+            .messages()
+            .into_iter()
+            .map(|message| (message.level, message.text))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (Level::Status, "Compiling: app".to_string()),
+                (Level::Note, "note: using tsc".to_string()),
+                (Level::Output, "| a | b |".to_string()),
+                (Level::Status, "ran 1 tool".to_string()),
+            ]
+        );
+        assert_eq!(sink.events().len(), 1);
+
+        // The scoped sink is gone once the closure returns
+        with_sink(Arc::new(RecordingSink::default()), || warn("elsewhere")).unwrap();
+        assert_eq!(sink.messages().len(), 4);
+    }
+
+    #[test]
+    fn test_warn_once() {
+        let sink = Arc::new(RecordingSink::default());
+        with_sink(sink.clone(), || -> io::Result<()> {
+            warn_once("test_warn_once: first")?;
+            warn_once("test_warn_once: first")?;
+            warn_once("test_warn_once: second")
+        })
+        .unwrap();
+        assert_eq!(sink.messages().len(), 2);
+    }
+
+    #[test]
+    fn test_message_styled() {
+        let message = Message {
+            level: Level::Error,
+            label: Some("error[CN_CONFIG]"),
+            text: "Configuration error\n\nSuggestion: Fix it",
+        };
+        assert_eq!(
+            message.styled(),
+            "error[CN_CONFIG]: Configuration error\n\nSuggestion: Fix it"
+        );
+        assert_eq!(message.styled(), message.to_string());
+    }
+
+    #[test]
+    fn test_draws_progress() {
+        assert!(draws_progress(ProgressMode::Always, Verbosity::Normal));
+        assert!(!draws_progress(ProgressMode::Never, Verbosity::Verbose));
+        // --quiet wins even over --progress always
+        assert!(!draws_progress(ProgressMode::Always, Verbosity::Quiet));
+    }
+
+    #[test]
+    fn test_bar_line() {
+        let bar = BarState {
+            message: "Downloading".to_string(),
+            total: Some(2048),
+            position: AtomicU64::new(1024),
+            started: Instant::now(),
+            finished: AtomicBool::new(false),
+        };
+        assert_eq!(
+            bar.line(),
+            format!(
+                "Downloading [{}{}] 1.0 KiB/2.0 KiB",
+                "=".repeat(12),
+                " ".repeat(12)
+            )
+        );
+
+        let spinner = BarState { total: None, ..bar };
+        assert_eq!(spinner.line(), "Downloading - 0s 1.0 KiB");
+
+        // Progress is never drawn in tests, so bars are inert
+        let bar = ProgressBar::bytes("Downloading", Some(10));
+        bar.inc(5);
+        assert!(bar.state.is_none());
+        assert_eq!(format_bytes(1536 * 1024), "1.5 MiB");
+    }
+
+    #[test]
+    fn test_event_json() {
+        let event = Event::ToolFinished {
+            tool: "build".to_string(),
+            duration_ms: 1200,
+            exit_code: 0,
+            cached: true,
+            outputs: vec!["dist/index.js".to_string()],
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"tool-finished","tool":"build","duration_ms":1200,"exit_code":0,"cached":true,"outputs":["dist/index.js"]}"#
+        );
+
+        let event = Event::Output {
+            command: "info".to_string(),
+            data: serde_json::json!({"name": "left-pad"}),
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"output","command":"info","data":{"name":"left-pad"}}"#
+        );
+
+        let event = Event::Error {
+            code: Some("CN_CONFIG".to_string()),
+            message: "Configuration error".to_string(),
+            suggestion: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"error","code":"CN_CONFIG","message":"Configuration error","suggestion":null}"#
+        );
+    }
 }
@@ -0,0 +1,204 @@
+use std::{
+    fmt::Display,
+    io::{self, BufRead, IsTerminal, Write},
+};
+
+use crate::{error::Error, progress, Result};
+
+/// Whether questions can be asked: stdin and stderr are both terminals
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Asks questions on one stream and reads the answers from another
+pub struct Prompter<R, W> {
+    input: R,
+    output: W,
+}
+
+impl Prompter<io::StdinLock<'static>, io::Stderr> {
+    /// Ask on stderr and read answers from stdin, keeping stdout free
+    pub fn stdio() -> Self {
+        Self::new(io::stdin().lock(), io::stderr())
+    }
+}
+
+impl<R: BufRead, W: Write> Prompter<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self { input, output }
+    }
+
+    /// Read one answer line, without its line ending
+    fn read_answer(&mut self) -> Result<String> {
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "input ended before every question was answered",
+            )));
+        }
+        Ok(line.trim().to_string())
+    }
+
+    /// Ask for a line of text until `validate` accepts it
+    ///
+    /// An empty answer picks `default`.
+    ///
+    /// # Arguments
+    ///
+    /// * `question` - What to ask
+    /// * `default` - Answer used when the user just presses enter
+    /// * `validate` - Check run on every answer; a rejected answer is asked again
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String>` - The accepted answer
+    pub fn input(
+        &mut self,
+        question: &str,
+        default: &str,
+        validate: impl Fn(&str) -> Result<()>,
+    ) -> Result<String> {
+        loop {
+            if default.is_empty() {
+                write!(self.output, "{}: ", question)?;
+            } else {
+                write!(self.output, "{} ({}): ", question, default)?;
+            }
+            self.output.flush()?;
+
+            let answer = match self.read_answer()? {
+                answer if answer.is_empty() => default.to_string(),
+                answer => answer,
+            };
+            match validate(&answer) {
+                Ok(()) => return Ok(answer),
+                Err(e) => writeln!(self.output, "{}", progress::format_error(&e.message()))?,
+            }
+        }
+    }
+
+    /// Ask for one of `choices`, by number or by name
+    ///
+    /// An empty answer picks the choice at `default`.
+    ///
+    /// # Arguments
+    ///
+    /// * `question` - What to ask
+    /// * `choices` - Answers to pick from, listed with their numbers
+    /// * `default` - Index of the choice used when the user just presses enter
+    ///
+    /// # Returns
+    ///
+    /// * `Result<T>` - The picked choice
+    pub fn select<T: Clone + Display>(
+        &mut self,
+        question: &str,
+        choices: &[T],
+        default: usize,
+    ) -> Result<T> {
+        writeln!(self.output, "{}", question)?;
+        for (index, choice) in choices.iter().enumerate() {
+            let marker = if index == default { '>' } else { ' ' };
+            writeln!(self.output, "{} {}) {}", marker, index + 1, choice)?;
+        }
+
+        loop {
+            write!(
+                self.output,
+                "Choose 1-{} ({}): ",
+                choices.len(),
+                default + 1
+            )?;
+            self.output.flush()?;
+
+            let answer = self.read_answer()?;
+            if answer.is_empty() {
+                return Ok(choices[default].clone());
+            }
+            let picked = answer
+                .parse::<usize>()
+                .ok()
+                .and_then(|number| number.checked_sub(1))
+                .and_then(|index| choices.get(index))
+                .or_else(|| {
+                    choices
+                        .iter()
+                        .find(|choice| choice.to_string().eq_ignore_ascii_case(&answer))
+                });
+            match picked {
+                Some(choice) => return Ok(choice.clone()),
+                None => writeln!(
+                    self.output,
+                    "{}",
+                    progress::format_error(&format!("'{}' is not one of the choices", answer))
+                )?,
+            }
+        }
+    }
+}
+
+/// Quote a command line argument for a POSIX shell when it needs it
+pub fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    fn prompter(answers: &str) -> Prompter<&[u8], Vec<u8>> {
+        Prompter::new(answers.as_bytes(), Vec::new())
+    }
+
+    #[test]
+    fn test_input() -> Result<()> {
+        let mut prompter = prompter("My-App\nmy-app\n\n");
+        let name = prompter.input("Package name", "app", utils::validate_package_name)?;
+        assert_eq!(name, "my-app");
+        // The uppercase answer was rejected and asked again
+        let output = String::from_utf8(prompter.output.clone()).unwrap();
+        assert_eq!(output.matches("Package name (app): ").count(), 2);
+        assert!(output.contains("Invalid package name: My-App"));
+
+        let name = prompter.input("Package name", "app", utils::validate_package_name)?;
+        assert_eq!(name, "app");
+
+        // Running out of input is an error rather than a loop
+        assert!(prompter.input("License", "", |_| Ok(())).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_select() -> Result<()> {
+        let choices = ["npm", "pnpm", "bun"];
+        let mut prompter = prompter("\n2\nBUN\n7\nyarn\n1\n");
+        assert_eq!(prompter.select("Package manager", &choices, 0)?, "npm");
+        assert_eq!(prompter.select("Package manager", &choices, 0)?, "pnpm");
+        assert_eq!(prompter.select("Package manager", &choices, 0)?, "bun");
+        assert_eq!(prompter.select("Package manager", &choices, 2)?, "npm");
+
+        let output = String::from_utf8(prompter.output).unwrap();
+        assert!(output.contains("> 3) bun"));
+        assert!(output.contains("'7' is not one of the choices"));
+        assert!(output.contains("'yarn' is not one of the choices"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("@acme/my-app"), "@acme/my-app");
+        assert_eq!(shell_quote("Jane Doe"), "'Jane Doe'");
+        assert_eq!(shell_quote("O'Brien"), r"'O'\''Brien'");
+        assert_eq!(shell_quote(""), "''");
+    }
+}
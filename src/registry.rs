@@ -0,0 +1,1342 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::hash::BuildHasher;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::npmrc::Npmrc;
+use crate::{progress, utils, Result};
+
+/// Base URL of the public npm registry
+pub const NPM_REGISTRY_API: &str = "https://registry.npmjs.org";
+
+/// Environment variables that override the default registry, as read by npm
+const REGISTRY_ENV_VARS: [&str; 2] = ["NPM_CONFIG_REGISTRY", "npm_config_registry"];
+
+/// Timeout applied to every registry request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest `Retry-After` delay honored before giving up on a rate limit
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// How long cached registry responses are considered fresh by default
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Package document returned by the registry
+///
+/// Only `name`, `dist-tags` and `versions` are part of the abbreviated
+/// documents used for installs; the other fields are filled in from full
+/// documents when present.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistryResponse {
+    /// Name of the package
+    pub name: String,
+
+    /// Dist-tags mapping tag names (e.g. `latest`) to versions
+    #[serde(rename = "dist-tags", default)]
+    pub dist_tags: HashMap<String, String>,
+
+    /// Published versions keyed by version string
+    #[serde(default)]
+    pub versions: HashMap<String, serde_json::Value>,
+
+    /// Description of the latest version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// License of the latest version, an SPDX expression or a legacy `{ "type": ... }` object
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<serde_json::Value>,
+
+    /// Publication times keyed by version, plus `created` and `modified`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub time: HashMap<String, serde_json::Value>,
+
+    /// Last modification time, as sent in abbreviated documents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+
+    /// People allowed to publish the package
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maintainers: Vec<Person>,
+
+    /// Source repository, a URL or a `{ "type": ..., "url": ... }` object
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<serde_json::Value>,
+}
+
+/// A maintainer or author of a package
+///
+/// Registries send `{ "name": ..., "email": ... }` objects, but old packages
+/// may list people as `Name <email>` strings instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "PersonField")]
+pub struct Person {
+    /// Name, usually the npm user name
+    pub name: String,
+
+    /// Email address, if published
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+/// A person in either of the forms found in package documents
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PersonField {
+    Text(String),
+    Object {
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        email: Option<String>,
+    },
+}
+
+impl From<PersonField> for Person {
+    fn from(field: PersonField) -> Self {
+        match field {
+            PersonField::Object { name, email } => Self { name, email },
+            PersonField::Text(text) => {
+                // `Name <email> (url)`, where both parts in brackets are optional
+                let email = text
+                    .split_once('<')
+                    .and_then(|(_, rest)| rest.split_once('>'))
+                    .map(|(email, _)| email.trim().to_string());
+                let name = text
+                    .split(['<', '('])
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                Self { name, email }
+            }
+        }
+    }
+}
+
+/// Where the tarball of a published version lives and what it contains
+///
+/// Every field is optional; old packages only have `tarball` and `shasum`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dist {
+    /// URL of the tarball
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tarball: Option<String>,
+
+    /// Subresource integrity hash of the tarball, e.g. `sha512-...`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
+
+    /// Hex SHA-1 of the tarball
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shasum: Option<String>,
+
+    /// Size of the unpacked files, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unpacked_size: Option<u64>,
+
+    /// Number of files in the tarball
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_count: Option<u64>,
+}
+
+/// Document of one published version, as returned by `/{name}/{version}`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VersionMetadata {
+    /// Name of the package
+    #[serde(default)]
+    pub name: String,
+
+    /// The version
+    #[serde(default)]
+    pub version: String,
+
+    /// Description of this version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// License, an SPDX expression or a legacy `{ "type": ... }` object
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<serde_json::Value>,
+
+    /// Tarball location, hashes and size
+    #[serde(default)]
+    pub dist: Dist,
+
+    /// People allowed to publish the package when this version was published
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maintainers: Vec<Person>,
+
+    /// Source repository, a URL or a `{ "type": ..., "url": ... }` object
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<serde_json::Value>,
+}
+
+impl VersionMetadata {
+    /// License identifier, whichever form the version declares it in
+    pub fn license(&self) -> Option<&str> {
+        license_name(self.license.as_ref()?)
+    }
+
+    /// URL of the source repository, whichever form it is declared in
+    pub fn repository(&self) -> Option<&str> {
+        repository_url(self.repository.as_ref()?)
+    }
+}
+
+/// License identifier of a `license` field
+fn license_name(license: &serde_json::Value) -> Option<&str> {
+    match license {
+        serde_json::Value::String(license) => Some(license),
+        license => license.get("type")?.as_str(),
+    }
+}
+
+/// URL of a `repository` field
+fn repository_url(repository: &serde_json::Value) -> Option<&str> {
+    match repository {
+        serde_json::Value::String(url) => Some(url),
+        repository => repository.get("url")?.as_str(),
+    }
+}
+
+impl RegistryResponse {
+    /// Published versions that are valid semver, in ascending order
+    pub fn version_list(&self) -> Vec<Version> {
+        let mut versions: Vec<Version> = self
+            .versions
+            .keys()
+            .filter_map(|version| Version::parse(version).ok())
+            .collect();
+        versions.sort();
+        versions
+    }
+
+    /// License identifier, whichever form the package declares it in
+    pub fn license(&self) -> Option<&str> {
+        license_name(self.license.as_ref()?)
+    }
+
+    /// URL of the source repository, whichever form it is declared in
+    pub fn repository(&self) -> Option<&str> {
+        repository_url(self.repository.as_ref()?)
+    }
+
+    /// When `version` was published, as an ISO 8601 timestamp
+    ///
+    /// Only full documents have publication times.
+    pub fn published(&self, version: &str) -> Option<&str> {
+        self.time.get(version)?.as_str()
+    }
+
+    /// Metadata of `version`, if the document lists it
+    ///
+    /// Abbreviated documents only carry some of the fields, so the rest are
+    /// left empty.
+    pub fn version_metadata(&self, version: &str) -> Option<VersionMetadata> {
+        serde_json::from_value(self.versions.get(version)?.clone()).ok()
+    }
+
+    /// When the package was last modified, as an ISO 8601 timestamp
+    pub fn modified(&self) -> Option<&str> {
+        self.time
+            .get("modified")
+            .and_then(|modified| modified.as_str())
+            .or(self.modified.as_deref())
+    }
+}
+
+/// Source of package metadata used during dependency resolution
+pub trait RegistryClient {
+    /// Fetch the package document for `name`
+    fn get_package_info(&self, name: &str) -> Result<RegistryResponse>;
+}
+
+/// Options for the on-disk cache of registry responses
+#[derive(Debug, Clone)]
+pub struct CacheOptions {
+    /// Directory holding one JSON file per package
+    pub dir: PathBuf,
+
+    /// How long a cached response is considered fresh
+    pub ttl: Duration,
+
+    /// Serve cached responses, even stale ones, without touching the network
+    pub offline: bool,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        Self {
+            dir: utils::user_cache_dir().join("registry"),
+            ttl: DEFAULT_CACHE_TTL,
+            offline: false,
+        }
+    }
+}
+
+/// A registry response as stored in the cache
+#[derive(Serialize, Deserialize)]
+struct CachedResponse {
+    /// Seconds since the Unix epoch at which the response was fetched
+    fetched_at: u64,
+
+    /// The cached response
+    response: RegistryResponse,
+}
+
+/// Resolve the registry base URL to use
+///
+/// An explicit URL wins, then `NPM_CONFIG_REGISTRY`, then the public npm
+/// registry.
+///
+/// # Arguments
+///
+/// * `explicit` - Registry URL given on the command line, if any
+///
+/// # Returns
+///
+/// * `Result<String>` - The validated base URL, without trailing slashes
+pub fn resolve_registry_url(explicit: Option<&str>) -> Result<String> {
+    let url = explicit
+        .map(str::to_string)
+        .or_else(|| {
+            REGISTRY_ENV_VARS
+                .iter()
+                .find_map(|var| env::var(var).ok().filter(|url| !url.trim().is_empty()))
+        })
+        .unwrap_or_else(|| NPM_REGISTRY_API.to_string());
+
+    normalize_registry_url(&url)
+}
+
+/// Resolve the registry the package described by `manifest` is published to
+///
+/// This follows npm: an explicit URL wins, then `publishConfig.registry` in
+/// package.json, then the `@scope:registry` key of `.npmrc` for a scoped
+/// name, then `NPM_CONFIG_REGISTRY`, then the `registry` key of `.npmrc`,
+/// then the public npm registry.
+///
+/// # Arguments
+///
+/// * `explicit` - Registry URL given on the command line, if any
+/// * `manifest` - Content of package.json, or `null` if there is none
+/// * `npmrc` - The user's and the project's `.npmrc` settings
+///
+/// # Returns
+///
+/// * `Result<String>` - The validated base URL, without trailing slashes
+pub fn resolve_package_registry(
+    explicit: Option<&str>,
+    manifest: &serde_json::Value,
+    npmrc: &Npmrc,
+) -> Result<String> {
+    if explicit.is_some() {
+        return resolve_registry_url(explicit);
+    }
+    let publish_config = manifest
+        .pointer("/publishConfig/registry")
+        .and_then(|registry| registry.as_str());
+    let scope = manifest
+        .get("name")
+        .and_then(|name| name.as_str())
+        .filter(|name| name.starts_with('@'))
+        .and_then(|name| name.split_once('/'))
+        .and_then(|(scope, _)| npmrc.get(&format!("{}:registry", scope)));
+    match publish_config.or(scope) {
+        Some(url) => normalize_registry_url(url),
+        None if REGISTRY_ENV_VARS
+            .iter()
+            .any(|var| env::var_os(var).is_some()) =>
+        {
+            resolve_registry_url(None)
+        }
+        None => resolve_registry_url(npmrc.get("registry")),
+    }
+}
+
+/// Validate a registry base URL and strip trailing slashes
+fn normalize_registry_url(url: &str) -> Result<String> {
+    let trimmed = url.trim().trim_end_matches('/');
+    let invalid = |reason: &str| Error::Registry {
+        message: format!("Invalid registry URL '{}': {}", url, reason),
+    };
+
+    let uri: ureq::http::Uri = trimmed.parse().map_err(|_| invalid("not a valid URL"))?;
+    if !matches!(uri.scheme_str(), Some("http" | "https")) {
+        return Err(invalid("the scheme must be http or https"));
+    }
+    if uri.host().is_none_or(str::is_empty) {
+        return Err(invalid("a host is required"));
+    }
+    if uri.query().is_some() {
+        return Err(invalid("query strings are not supported"));
+    }
+
+    Ok(trimmed.to_string())
+}
+
+impl CachedResponse {
+    fn is_fresh(&self, ttl: Duration) -> bool {
+        utils::unix_timestamp().saturating_sub(self.fetched_at) <= ttl.as_secs()
+    }
+}
+
+/// How failed registry requests are retried
+///
+/// Timeouts, connection errors, rate limits (HTTP 429) and server errors
+/// (HTTP 5xx) are retried with exponential backoff plus jitter; any other
+/// response is final.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one
+    pub max_attempts: u32,
+
+    /// Delay before the first retry, doubled for each later one
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that sends every request exactly once
+    pub fn never() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retrying after `attempt` failed attempts
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - Number of attempts made so far, starting at 1
+    /// * `retry_after` - Delay requested by the server, which takes precedence
+    ///
+    /// # Returns
+    ///
+    /// * `Duration` - How long to wait before the next attempt
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(MAX_RETRY_AFTER);
+        }
+
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        // Up to 50% jitter keeps parallel CI jobs from retrying in lockstep
+        let jitter_range = backoff.as_millis() as u64 / 2;
+        let jitter = match jitter_range {
+            0 => 0,
+            range => RandomState::new().hash_one(Instant::now()) % range,
+        };
+        backoff + Duration::from_millis(jitter)
+    }
+}
+
+/// HTTP agent giving up on requests after `timeout`
+fn build_agent(timeout: Duration) -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .timeout_global(Some(timeout))
+        .http_status_as_error(false)
+        .build()
+        .into()
+}
+
+/// Whether a response with `status` is worth retrying
+fn is_retryable_status(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+/// Whether a failed request is worth retrying
+fn is_retryable_error(error: &ureq::Error) -> bool {
+    matches!(
+        error,
+        ureq::Error::Timeout(_) | ureq::Error::ConnectionFailed | ureq::Error::Io(_)
+    )
+}
+
+/// Delay requested by a `Retry-After` header in seconds
+fn retry_after(response: &ureq::http::Response<ureq::Body>) -> Option<Duration> {
+    let value = response.headers().get("Retry-After")?.to_str().ok()?;
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Kind of document requested from the registry
+#[derive(Debug, Clone, Copy)]
+enum Document<'a> {
+    /// Abbreviated package document, as used for installs
+    Abbreviated,
+    /// Full package document
+    Full,
+    /// Document of one version or dist-tag
+    Version(&'a str),
+}
+
+/// Blocking client for the npm registry
+///
+/// Cloning is cheap: clones share the agent and its pool of open
+/// connections, so a client can be created once and handed to every thread
+/// that talks to the registry.
+#[derive(Clone)]
+pub struct Client {
+    agent: ureq::Agent,
+    base_url: String,
+    cache: Option<CacheOptions>,
+    token: Option<String>,
+    retry: RetryPolicy,
+}
+
+impl Client {
+    /// Create a client for the public npm registry
+    pub fn new() -> Self {
+        Self {
+            agent: build_agent(REQUEST_TIMEOUT),
+            base_url: NPM_REGISTRY_API.to_string(),
+            cache: None,
+            token: None,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Create a client for the registry at `url`
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Base URL of the registry, e.g. `https://npm.example.com/repository/npm`
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The client, or `Error::Registry` if the URL is malformed
+    pub fn with_registry(url: &str) -> Result<Self> {
+        Ok(Self {
+            base_url: normalize_registry_url(url)?,
+            ..Self::new()
+        })
+    }
+
+    /// Cache package documents on disk
+    ///
+    /// Only `get_package_info` uses the cache; name availability checks always
+    /// go to the registry.
+    pub fn with_cache(mut self, options: CacheOptions) -> Self {
+        self.cache = Some(options);
+        self
+    }
+
+    /// Authenticate requests with a bearer token
+    ///
+    /// Needed for private packages; see `Npmrc::auth_token` for reading the
+    /// token from `.npmrc`.
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Give up on each request after `timeout` instead of the default 30 seconds
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.agent = build_agent(timeout);
+        self
+    }
+
+    /// Retry transient failures according to `policy`
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Base URL requests are sent to
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Check that the registry answers at all
+    ///
+    /// Sends a single HEAD request for the base URL without retrying; any
+    /// response below HTTP 500 counts as reachable.
+    pub fn ping(&self) -> Result<()> {
+        let response = self
+            .agent
+            .head(&format!("{}/", self.base_url))
+            .call()
+            .map_err(|e| Error::Network {
+                message: format!("Failed to reach {}: {}", self.base_url, e),
+            })?;
+        match response.status().as_u16() {
+            status if status < 500 => Ok(()),
+            status => Err(Error::Network {
+                message: format!("{} answered with HTTP {}", self.base_url, status),
+            }),
+        }
+    }
+
+    /// Check whether `name` is still unclaimed on the registry
+    pub fn is_name_available(&self, name: &str) -> Result<bool> {
+        let response = self.request(name, Document::Abbreviated)?;
+        match response.status().as_u16() {
+            404 => Ok(true),
+            200 => Ok(false),
+            status => Err(self.status_error(name, status)),
+        }
+    }
+
+    /// Fetch the full package document for `name`, bypassing the cache
+    ///
+    /// Unlike the abbreviated documents used for installs, full documents
+    /// carry descriptions, maintainers and publication times, but they can
+    /// be large for packages with many versions.
+    pub fn get_package_document(&self, name: &str) -> Result<RegistryResponse> {
+        self.fetch_document(name, Document::Full)
+    }
+
+    /// Fetch the document of one published version of `name`
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Package name, possibly scoped
+    /// * `version` - Exact version or dist-tag, e.g. `1.2.3` or `latest`
+    ///
+    /// # Returns
+    ///
+    /// * `Result<VersionMetadata>` - The version's metadata, or
+    ///   `Error::Registry` if the package or version does not exist
+    pub fn get_version_metadata(&self, name: &str, version: &str) -> Result<VersionMetadata> {
+        self.fetch_document(name, Document::Version(version))
+    }
+
+    fn package_url(&self, name: &str) -> String {
+        // Scoped names keep their `@` but the separator must be escaped
+        format!("{}/{}", self.base_url, name.replace('/', "%2f"))
+    }
+
+    /// Send a GET request for a document of `name`, retrying transient failures
+    ///
+    /// Once the attempts run out, a retryable status becomes an error as well;
+    /// any other response is returned for the caller to interpret.
+    fn request(&self, name: &str, document: Document) -> Result<ureq::http::Response<ureq::Body>> {
+        let max_attempts = self.retry.max_attempts.max(1);
+        let (url, accept) = self.document_url(name, document);
+        let mut attempt = 1;
+        loop {
+            progress::debug(&format!("GET {}", url))?;
+            let (retry_after, last_error) = match self.send(&url, accept) {
+                Ok(response) if !is_retryable_status(response.status().as_u16()) => {
+                    return Ok(response)
+                }
+                Ok(response) if max_attempts == 1 => return Ok(response),
+                Ok(response) => (
+                    retry_after(&response),
+                    format!("HTTP {}", response.status().as_u16()),
+                ),
+                Err(e) if is_retryable_error(&e) && max_attempts > 1 => (None, e.to_string()),
+                Err(e) => {
+                    return Err(Error::Network {
+                        message: format!("Failed to fetch '{}': {}", name, e),
+                    })
+                }
+            };
+
+            if attempt == max_attempts {
+                return Err(Error::Network {
+                    message: format!(
+                        "Fetching '{}' failed after {} attempts: {}",
+                        name, attempt, last_error
+                    ),
+                });
+            }
+            thread::sleep(self.retry.delay(attempt, retry_after));
+            attempt += 1;
+        }
+    }
+
+    /// URL of a document of `name` and the media type to accept for it
+    fn document_url(&self, name: &str, document: Document) -> (String, &'static str) {
+        match document {
+            Document::Abbreviated => (
+                self.package_url(name),
+                "application/vnd.npm.install-v1+json",
+            ),
+            Document::Full => (self.package_url(name), "application/json"),
+            Document::Version(version) => (
+                format!("{}/{}", self.package_url(name), version),
+                "application/json",
+            ),
+        }
+    }
+
+    fn send(
+        &self,
+        url: &str,
+        accept: &str,
+    ) -> std::result::Result<ureq::http::Response<ureq::Body>, ureq::Error> {
+        let mut request = self.agent.get(url).header("Accept", accept);
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", &format!("Bearer {}", token));
+        }
+        request.call()
+    }
+
+    fn fetch(&self, name: &str) -> Result<RegistryResponse> {
+        self.fetch_document(name, Document::Abbreviated)
+    }
+
+    fn fetch_document<T: serde::de::DeserializeOwned>(
+        &self,
+        name: &str,
+        document: Document,
+    ) -> Result<T> {
+        let mut response = self.request(name, document)?;
+        match response.status().as_u16() {
+            200 => response
+                .body_mut()
+                .read_json::<T>()
+                .map_err(|e| Error::Registry {
+                    message: format!("Invalid registry response for '{}': {}", name, e),
+                }),
+            404 => Err(Error::PackageNotFound {
+                name: name.to_string(),
+                version: match document {
+                    Document::Version(version) => Some(version.to_string()),
+                    _ => None,
+                },
+            }),
+            status => Err(self.status_error(name, status)),
+        }
+    }
+
+    fn status_error(&self, name: &str, status: u16) -> Error {
+        let message = match status {
+            401 | 403 if self.token.is_some() => format!(
+                "Access to '{}' was denied (HTTP {}); the configured auth token lacks access to this package",
+                name, status
+            ),
+            401 | 403 => format!(
+                "Access to '{}' was denied (HTTP {}); the package may be private and require an auth token. \
+                 Add `{}/:_authToken=<token>` to your .npmrc",
+                name,
+                status,
+                registry_key(&self.base_url)
+            ),
+            _ => format!(
+                "Unexpected registry response for '{}': HTTP {}",
+                name, status
+            ),
+        };
+        Error::Registry { message }
+    }
+}
+
+/// Registry URL without its scheme, as used for keys in `.npmrc`
+fn registry_key(base_url: &str) -> String {
+    let without_scheme = base_url
+        .split_once("://")
+        .map_or(base_url, |(_, rest)| rest);
+    format!("//{}", without_scheme)
+}
+
+/// Directory holding the cached responses of the registry at `base_url`
+///
+/// Each registry gets its own directory so mirrors never serve each other's
+/// documents.
+fn registry_cache_dir(options: &CacheOptions, base_url: &str) -> PathBuf {
+    let without_scheme = base_url
+        .split_once("://")
+        .map_or(base_url, |(_, rest)| rest);
+    let key: String = without_scheme
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    options.dir.join(key)
+}
+
+/// Path of the cache file for `name`
+fn cache_path(options: &CacheOptions, base_url: &str, name: &str) -> PathBuf {
+    registry_cache_dir(options, base_url).join(format!("{}.json", name.replace('/', "%2f")))
+}
+
+/// Read a cached response, treating unreadable or corrupt files as missing
+fn read_cached(options: &CacheOptions, base_url: &str, name: &str) -> Option<CachedResponse> {
+    let content = fs::read_to_string(cache_path(options, base_url, name)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_cached(
+    options: &CacheOptions,
+    base_url: &str,
+    name: &str,
+    response: &RegistryResponse,
+) -> Result<()> {
+    fs::create_dir_all(registry_cache_dir(options, base_url))?;
+    let cached = CachedResponse {
+        fetched_at: utils::unix_timestamp(),
+        response: response.clone(),
+    };
+    fs::write(
+        cache_path(options, base_url, name),
+        serde_json::to_string(&cached)?,
+    )?;
+    Ok(())
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegistryClient for Client {
+    fn get_package_info(&self, name: &str) -> Result<RegistryResponse> {
+        let Some(options) = &self.cache else {
+            return self.fetch(name);
+        };
+
+        if let Some(cached) = read_cached(options, &self.base_url, name) {
+            if options.offline || cached.is_fresh(options.ttl) {
+                return Ok(cached.response);
+            }
+        }
+
+        if options.offline {
+            return Err(Error::Network {
+                message: format!(
+                    "No cached registry data for '{}' is available offline",
+                    name
+                ),
+            });
+        }
+
+        let response = self.fetch(name)?;
+        // A cache that cannot be written only costs a refetch next time
+        let _ = write_cached(options, &self.base_url, name, &response);
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::test_utils::{serve_keep_alive, serve_once, serve_sequence};
+
+    /// Address nothing listens on, so any request fails
+    const UNREACHABLE_REGISTRY: &str = "http://127.0.0.1:9";
+
+    fn cached_client(dir: &TempDir, ttl: Duration, offline: bool) -> Client {
+        Client::with_registry(UNREACHABLE_REGISTRY)
+            .unwrap()
+            .with_retry(RetryPolicy::never())
+            .with_cache(CacheOptions {
+                dir: dir.path().to_path_buf(),
+                ttl,
+                offline,
+            })
+    }
+
+    fn plant_cache_entry(dir: &TempDir, name: &str, fetched_at: u64) {
+        let cached = CachedResponse {
+            fetched_at,
+            response: RegistryResponse {
+                name: name.to_string(),
+                dist_tags: HashMap::from([("latest".to_string(), "1.0.0".to_string())]),
+                versions: HashMap::from([("1.0.0".to_string(), serde_json::json!({}))]),
+                ..Default::default()
+            },
+        };
+        let options = CacheOptions {
+            dir: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let path = cache_path(&options, UNREACHABLE_REGISTRY, name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, serde_json::to_string(&cached).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_cache_hit_skips_network() {
+        let dir = TempDir::new().unwrap();
+        plant_cache_entry(&dir, "@scope/pkg", utils::unix_timestamp());
+
+        let client = cached_client(&dir, DEFAULT_CACHE_TTL, false);
+        let info = client.get_package_info("@scope/pkg").unwrap();
+        assert_eq!(info.dist_tags["latest"], "1.0.0");
+    }
+
+    #[test]
+    fn test_expired_cache_entry_is_refetched() {
+        let dir = TempDir::new().unwrap();
+        plant_cache_entry(&dir, "pkg", utils::unix_timestamp() - 3600);
+
+        // Online, the stale entry triggers a request which fails here
+        let client = cached_client(&dir, DEFAULT_CACHE_TTL, false);
+        assert!(matches!(
+            client.get_package_info("pkg"),
+            Err(Error::Network { .. })
+        ));
+
+        // Offline, the stale entry is served
+        let client = cached_client(&dir, DEFAULT_CACHE_TTL, true);
+        assert!(client.get_package_info("pkg").is_ok());
+    }
+
+    #[test]
+    fn test_offline_without_cache_entry() {
+        let dir = TempDir::new().unwrap();
+        let options = CacheOptions {
+            dir: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let path = cache_path(&options, UNREACHABLE_REGISTRY, "corrupt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "{ not json").unwrap();
+
+        let client = cached_client(&dir, DEFAULT_CACHE_TTL, true);
+        assert!(matches!(
+            client.get_package_info("missing"),
+            Err(Error::Network { .. })
+        ));
+        // Corrupt entries are ignored rather than failing to parse
+        assert!(matches!(
+            client.get_package_info("corrupt"),
+            Err(Error::Network { .. })
+        ));
+    }
+
+    #[test]
+    fn test_package_url() {
+        let client = Client::new();
+        assert_eq!(
+            client.package_url("express"),
+            "https://registry.npmjs.org/express"
+        );
+        assert_eq!(
+            client.package_url("@types/node"),
+            "https://registry.npmjs.org/@types%2fnode"
+        );
+    }
+
+    #[test]
+    fn test_normalize_registry_url() {
+        assert_eq!(
+            normalize_registry_url("https://npm.example.com/repository/npm///").unwrap(),
+            "https://npm.example.com/repository/npm"
+        );
+        assert_eq!(
+            normalize_registry_url(" http://localhost:4873/ ").unwrap(),
+            "http://localhost:4873"
+        );
+
+        for url in [
+            "",
+            "npm.example.com",
+            "ftp://npm.example.com",
+            "https://",
+            "https://npm.example.com/?q=1",
+        ] {
+            assert!(
+                matches!(normalize_registry_url(url), Err(Error::Registry { .. })),
+                "{url:?} should be rejected"
+            );
+        }
+
+        assert_eq!(
+            resolve_registry_url(Some("https://npm.example.com/")).unwrap(),
+            "https://npm.example.com"
+        );
+    }
+
+    #[test]
+    fn test_resolve_package_registry() {
+        let npmrc = Npmrc::parse(
+            "registry=https://npm.example.com/\n@corp:registry=https://corp.example.com/npm/\n",
+        );
+        let manifest = |json: &str| serde_json::from_str::<serde_json::Value>(json).unwrap();
+        let resolve = |json: &str, npmrc: &Npmrc| {
+            resolve_package_registry(None, &manifest(json), npmrc).unwrap()
+        };
+
+        // Scoped names use their scope's registry, others the default one
+        assert_eq!(
+            resolve(r#"{"name": "@corp/utils"}"#, &npmrc),
+            "https://corp.example.com/npm"
+        );
+        if REGISTRY_ENV_VARS
+            .iter()
+            .all(|var| env::var_os(var).is_none())
+        {
+            assert_eq!(
+                resolve(r#"{"name": "@other/utils"}"#, &npmrc),
+                "https://npm.example.com"
+            );
+            assert_eq!(
+                resolve(r#"{"name": "utils"}"#, &Npmrc::default()),
+                NPM_REGISTRY_API
+            );
+        }
+
+        // publishConfig wins over .npmrc, and an explicit URL over both
+        let published =
+            r#"{"name": "@corp/utils", "publishConfig": {"registry": "https://pub.example.com"}}"#;
+        assert_eq!(resolve(published, &npmrc), "https://pub.example.com");
+        assert_eq!(
+            resolve_package_registry(
+                Some("https://cli.example.com"),
+                &manifest(published),
+                &npmrc
+            )
+            .unwrap(),
+            "https://cli.example.com"
+        );
+    }
+
+    #[test]
+    fn test_custom_registry() {
+        let (base_url, handle) = serve_once(
+            200,
+            r#"{"name": "@corp/utils", "dist-tags": {"latest": "2.0.0"}, "versions": {"2.0.0": {}}}"#,
+        );
+
+        let client = Client::with_registry(&format!("{}/repository/npm/", base_url)).unwrap();
+        assert_eq!(client.base_url(), format!("{}/repository/npm", base_url));
+
+        let info = client.get_package_info("@corp/utils").unwrap();
+        assert_eq!(info.dist_tags["latest"], "2.0.0");
+
+        let request = handle.join().unwrap();
+        assert!(request.starts_with("GET /repository/npm/@corp%2futils HTTP/1.1"));
+    }
+
+    #[test]
+    fn test_custom_registry_name_availability() {
+        let (base_url, handle) = serve_once(404, "{}");
+
+        let client = Client::with_registry(&base_url).unwrap();
+        assert!(client.is_name_available("unclaimed").unwrap());
+        assert!(handle
+            .join()
+            .unwrap()
+            .starts_with("GET /unclaimed HTTP/1.1"));
+    }
+
+    #[test]
+    fn test_auth_token_is_sent() {
+        let (base_url, handle) = serve_once(
+            200,
+            r#"{"name": "@corp/private", "dist-tags": {"latest": "1.0.0"}, "versions": {"1.0.0": {}}}"#,
+        );
+
+        let client = Client::with_registry(&base_url)
+            .unwrap()
+            .with_token("s3cret");
+        client.get_package_info("@corp/private").unwrap();
+
+        let request = handle.join().unwrap().to_lowercase();
+        assert!(request.contains("authorization: bearer s3cret\r\n"));
+    }
+
+    #[test]
+    fn test_unauthorized_suggests_token() {
+        let (base_url, handle) = serve_once(401, "{}");
+
+        let client = Client::with_registry(&base_url).unwrap();
+        let err = client.get_package_info("@corp/private").unwrap_err();
+        handle.join().unwrap();
+
+        let Error::Registry { message } = err else {
+            panic!("expected a registry error, got {err:?}");
+        };
+        assert!(message.contains("may be private"));
+        assert!(message.contains(&format!(
+            "`//{}/:_authToken=<token>`",
+            base_url.trim_start_matches("http://")
+        )));
+    }
+
+    /// Client for `base_url` that retries without waiting
+    fn retrying_client(base_url: &str) -> Client {
+        Client::with_registry(base_url)
+            .unwrap()
+            .with_retry(RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::ZERO,
+            })
+    }
+
+    #[test]
+    fn test_clones_share_connections() {
+        let document = br#"{"name": "shared", "dist-tags": {"latest": "1.0.0"}, "versions": {}}"#;
+        let (base_url, connections) = serve_keep_alive(200, document);
+        let client = Client::with_registry(&base_url)
+            .unwrap()
+            .with_retry(RetryPolicy::never());
+
+        // Clones used from several threads at once all get an answer
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                let client = client.clone();
+                scope.spawn(move || {
+                    let info = client.get_package_info("shared").unwrap();
+                    assert_eq!(info.dist_tags["latest"], "1.0.0");
+                });
+            }
+        });
+        let opened = connections.load(Ordering::SeqCst);
+        assert!((1..=4).contains(&opened));
+
+        // Later calls reuse the pooled connections instead of opening new ones
+        for _ in 0..3 {
+            let client = client.clone();
+            thread::spawn(move || client.get_package_info("shared").unwrap())
+                .join()
+                .unwrap();
+        }
+        assert_eq!(connections.load(Ordering::SeqCst), opened);
+    }
+
+    #[test]
+    fn test_retry_transient_failures() {
+        let document = br#"{"name": "flaky", "dist-tags": {"latest": "1.0.0"}, "versions": {}}"#;
+        let (base_url, handle) = serve_sequence(vec![
+            (429, "Retry-After: 0\r\n".to_string(), Vec::new()),
+            (503, String::new(), Vec::new()),
+            (200, String::new(), document.to_vec()),
+        ]);
+
+        let info = retrying_client(&base_url)
+            .get_package_info("flaky")
+            .unwrap();
+        assert_eq!(info.dist_tags["latest"], "1.0.0");
+        assert_eq!(handle.join().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_retry_gives_up_after_max_attempts() {
+        let (base_url, handle) = serve_sequence(vec![(500, String::new(), Vec::new()); 3]);
+
+        let err = retrying_client(&base_url)
+            .get_package_info("broken")
+            .unwrap_err();
+        assert_eq!(handle.join().unwrap().len(), 3);
+        let Error::Network { message } = err else {
+            panic!("expected a network error, got {err:?}");
+        };
+        assert_eq!(
+            message,
+            "Fetching 'broken' failed after 3 attempts: HTTP 500"
+        );
+    }
+
+    #[test]
+    fn test_not_found_is_not_retried() {
+        let (base_url, handle) = serve_once(404, "{}");
+
+        let err = retrying_client(&base_url)
+            .get_package_info("missing")
+            .unwrap_err();
+        handle.join().unwrap();
+        assert!(matches!(err, Error::PackageNotFound { .. }));
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+        };
+
+        for (attempt, backoff) in [(1, 100), (2, 200), (3, 400)] {
+            let delay = policy.delay(attempt, None);
+            assert!(delay >= Duration::from_millis(backoff));
+            assert!(delay <= Duration::from_millis(backoff * 3 / 2));
+        }
+
+        // The server's Retry-After wins, within reason
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(2))),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(3600))),
+            MAX_RETRY_AFTER
+        );
+    }
+
+    #[test]
+    fn test_deserialize_registry_response() {
+        let payload = r#"{
+            "name": "left-pad",
+            "dist-tags": { "latest": "1.3.0" },
+            "modified": "2022-06-19T11:37:19.398Z",
+            "versions": {
+                "1.2.0": { "name": "left-pad", "version": "1.2.0" },
+                "1.3.0": { "name": "left-pad", "version": "1.3.0" }
+            }
+        }"#;
+
+        let response: RegistryResponse = serde_json::from_str(payload).unwrap();
+        assert_eq!(response.name, "left-pad");
+        assert_eq!(response.dist_tags["latest"], "1.3.0");
+        assert_eq!(response.versions.len(), 2);
+        assert!(response.versions.contains_key("1.2.0"));
+        assert_eq!(response.modified(), Some("2022-06-19T11:37:19.398Z"));
+    }
+
+    #[test]
+    fn test_get_version_metadata() {
+        let (base_url, handle) = serve_once(
+            200,
+            r#"{
+                "name": "@corp/utils",
+                "version": "2.0.0",
+                "license": "MIT",
+                "repository": "github:corp/utils",
+                "maintainers": [{ "name": "ci", "email": "ci@corp.example" }, "Jane Doe <jane@corp.example> (https://corp.example)"],
+                "dist": {
+                    "tarball": "https://npm.corp.example/@corp/utils/-/utils-2.0.0.tgz",
+                    "integrity": "sha512-abc",
+                    "shasum": "0123",
+                    "unpackedSize": 20480,
+                    "fileCount": 12,
+                    "signatures": [{ "keyid": "SHA256:x", "sig": "y" }]
+                }
+            }"#,
+        );
+
+        let client = Client::with_registry(&base_url).unwrap();
+        let metadata = client.get_version_metadata("@corp/utils", "2.0.0").unwrap();
+        assert_eq!(metadata.license(), Some("MIT"));
+        assert_eq!(metadata.repository(), Some("github:corp/utils"));
+        assert_eq!(metadata.dist.unpacked_size, Some(20480));
+        assert_eq!(metadata.dist.file_count, Some(12));
+        assert_eq!(metadata.dist.integrity.as_deref(), Some("sha512-abc"));
+        assert_eq!(
+            metadata.maintainers,
+            vec![
+                Person {
+                    name: "ci".to_string(),
+                    email: Some("ci@corp.example".to_string()),
+                },
+                Person {
+                    name: "Jane Doe".to_string(),
+                    email: Some("jane@corp.example".to_string()),
+                },
+            ]
+        );
+
+        let request = handle.join().unwrap();
+        assert!(request.starts_with("GET /@corp%2futils/2.0.0 HTTP/1.1"));
+        assert!(request.contains("accept: application/json"));
+
+        // Old packages lack most fields
+        let metadata: VersionMetadata =
+            serde_json::from_str(r#"{"name": "a", "version": "0.0.1"}"#).unwrap();
+        assert_eq!(metadata.dist, Dist::default());
+        assert_eq!(metadata.license(), None);
+    }
+
+    #[test]
+    fn test_deserialize_full_registry_document() {
+        // Trimmed from https://registry.npmjs.org/express
+        let payload = r#"{
+            "_id": "express",
+            "_rev": "1281-8c4d5b0e3a6b7d7e2d0b1f5a9c3e4d21",
+            "name": "express",
+            "description": "Fast, unopinionated, minimalist web framework",
+            "dist-tags": { "latest": "5.1.0", "next": "5.0.0-beta.3" },
+            "versions": {
+                "4.21.2": {
+                    "name": "express",
+                    "version": "4.21.2",
+                    "license": "MIT",
+                    "dependencies": { "accepts": "~1.3.8", "body-parser": "1.20.3" },
+                    "engines": { "node": ">= 0.10.0" },
+                    "dist": {
+                        "shasum": "cf250e48362174ead6cea4a566abef0162c1ec32",
+                        "tarball": "https://registry.npmjs.org/express/-/express-4.21.2.tgz",
+                        "integrity": "sha512-28HqgMZAmih1Czt9ny7qr6ek2qddF4FclbMzwhCREB6OFfH+rXAnuNCwo1/wFvrtbgsQDb4kSbX9de9lFbrXnA=="
+                    }
+                },
+                "5.0.0-beta.3": { "name": "express", "version": "5.0.0-beta.3" },
+                "5.1.0": {
+                    "name": "express",
+                    "version": "5.1.0",
+                    "license": "MIT",
+                    "dependencies": { "accepts": "^2.0.0" },
+                    "engines": { "node": ">= 18" }
+                },
+                "0.14.0beta": { "name": "express", "version": "0.14.0beta" }
+            },
+            "time": {
+                "created": "2010-12-29T19:38:25.450Z",
+                "modified": "2025-03-31T14:23:11.613Z",
+                "4.21.2": "2024-12-05T22:52:38.134Z",
+                "5.1.0": "2025-03-31T14:23:10.911Z"
+            },
+            "maintainers": [{ "name": "wesleytodd", "email": "wes@wesleytodd.com" }],
+            "repository": { "type": "git", "url": "git+https://github.com/expressjs/express.git" },
+            "license": "MIT",
+            "readmeFilename": "Readme.md"
+        }"#;
+
+        let response: RegistryResponse = serde_json::from_str(payload).unwrap();
+        assert_eq!(response.name, "express");
+        assert_eq!(
+            response.description.as_deref(),
+            Some("Fast, unopinionated, minimalist web framework")
+        );
+        assert_eq!(response.license(), Some("MIT"));
+        assert_eq!(response.modified(), Some("2025-03-31T14:23:11.613Z"));
+        assert_eq!(response.dist_tags["next"], "5.0.0-beta.3");
+
+        // Versions that are not valid semver are left out
+        let versions: Vec<String> = response
+            .version_list()
+            .iter()
+            .map(Version::to_string)
+            .collect();
+        assert_eq!(versions, vec!["4.21.2", "5.0.0-beta.3", "5.1.0"]);
+
+        let metadata = response.version_metadata("4.21.2").unwrap();
+        assert_eq!(
+            metadata.dist.shasum.as_deref(),
+            Some("cf250e48362174ead6cea4a566abef0162c1ec32")
+        );
+        assert_eq!(metadata.dist.unpacked_size, None);
+        assert_eq!(
+            response.published("4.21.2"),
+            response.time["4.21.2"].as_str()
+        );
+
+        // Old packages declare their license as an object
+        let legacy: RegistryResponse = serde_json::from_str(
+            r#"{"name": "old", "license": {"type": "BSD", "url": "http://example.com"}}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.license(), Some("BSD"));
+    }
+}
@@ -0,0 +1,147 @@
+/// Subcommands of the cargonode command line, for hints naming a command
+pub const KNOWN_COMMANDS: [&str; 22] = [
+    "new",
+    "init",
+    "add",
+    "remove",
+    "upgrade",
+    "info",
+    "publish",
+    "version",
+    "run",
+    "check",
+    "fmt",
+    "build",
+    "test",
+    "clean",
+    "metadata",
+    "history",
+    "doctor",
+    "cache",
+    "template",
+    "config",
+    "explain",
+    "completions",
+];
+
+/// Number of single-character edits that turn `a` into `b`
+///
+/// Swapping two neighbouring characters counts as one edit, as it is a
+/// common typo.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    distances[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+/// The candidate closest to `input`, if it is close enough to be a typo
+///
+/// A candidate is close enough when it is at most one edit away per three
+/// characters of `input`, and at least one. Ties go to the first candidate.
+///
+/// # Arguments
+///
+/// * `input` - What the user typed
+/// * `candidates` - The valid values
+///
+/// # Returns
+///
+/// * `Option<&str>` - The closest candidate, if any is close enough
+pub fn closest_match<'a>(
+    input: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(input, candidate), candidate))
+        .filter(|(distance, _)| *distance <= input.chars().count().max(3) / 3)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Hint for a tool that is not configured
+///
+/// Suggests the configured tool closest to `tool`, or running cargonode's
+/// own command of that name, or listing the configured tools.
+///
+/// # Arguments
+///
+/// * `tool` - The tool that was asked for
+/// * `configured` - Names of the configured tools
+///
+/// # Returns
+///
+/// * `String` - The hint
+pub fn unknown_tool_hint<'a>(tool: &str, configured: impl IntoIterator<Item = &'a str>) -> String {
+    let configured: Vec<&str> = configured.into_iter().collect();
+    if let Some(name) = closest_match(tool, configured.iter().copied()) {
+        return format!("Did you mean `{}`?", name);
+    }
+    if KNOWN_COMMANDS.contains(&tool) {
+        return format!(
+            "`{}` is a cargonode command rather than a tool; run `cargonode {}`.",
+            tool, tool
+        );
+    }
+    if configured.is_empty() {
+        return "No tools are configured; add one to the cargonode section of package.json, or import the npm scripts with `cargonode init --import`.".to_string();
+    }
+    format!("Configured tools: {}.", configured.join(", "))
+}
+
+/// Hint for a command run outside any project
+pub fn missing_manifest_hint() -> String {
+    "Run `cargonode init` to create a project here, or run the command from inside an existing project."
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("comand", "command"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("cahce", "cache"), 1);
+        assert_eq!(edit_distance("", "args"), 4);
+    }
+
+    #[test]
+    fn test_closest_match() {
+        let tools = ["build", "test", "lint"];
+        assert_eq!(closest_match("biuld", tools), Some("build"));
+        assert_eq!(closest_match("tset", tools), Some("test"));
+        assert_eq!(closest_match("deploy", tools), None);
+        assert_eq!(closest_match("env", ["exclude"]), None);
+        assert_eq!(closest_match("x", ["a", "b"]), Some("a"));
+    }
+
+    #[test]
+    fn test_unknown_tool_hint() {
+        let tools = ["build", "test"];
+        assert_eq!(unknown_tool_hint("biuld", tools), "Did you mean `build`?");
+        assert!(unknown_tool_hint("doctor", tools).contains("run `cargonode doctor`"));
+        assert_eq!(
+            unknown_tool_hint("deploy", tools),
+            "Configured tools: build, test."
+        );
+        assert!(unknown_tool_hint("deploy", []).contains("No tools are configured"));
+    }
+}
@@ -1,16 +1,56 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
 
-use serde::Serialize;
+use clap::ValueEnum;
+use flate2::read::GzDecoder;
+use semver::Version;
+use serde::{Deserialize, Serialize};
 
-use crate::Result;
+use crate::{error::Error, progress, utils, Result};
 
 /// Represents the type of Node.js project
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
 pub enum ProjectType {
     /// A binary project with a main entry point
+    #[default]
     Binary,
     /// A library project that can be imported
     Library,
+    /// A command-line tool published with an executable in `bin`
+    Cli,
+}
+
+impl ProjectType {
+    /// Name of the type in messages, templates and the user config
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProjectType::Binary => "binary",
+            ProjectType::Library => "library",
+            ProjectType::Cli => "cli",
+        }
+    }
+
+    /// Source file the embedded template generates, relative to the project
+    pub fn entry_file(&self) -> &'static str {
+        match self {
+            ProjectType::Binary | ProjectType::Cli => "src/main.js",
+            ProjectType::Library => "src/lib.js",
+        }
+    }
+
+    /// Content of [`ProjectType::entry_file`] in the embedded template
+    pub fn entry_content(&self) -> &'static str {
+        match self {
+            ProjectType::Binary => MAIN_JS_CONTENT,
+            ProjectType::Library => LIB_JS_CONTENT,
+            ProjectType::Cli => CLI_JS_CONTENT,
+        }
+    }
 }
 
 /// Configuration for package.json generation
@@ -18,10 +58,16 @@ pub enum ProjectType {
 pub struct PackageConfig {
     /// Name of the package
     pub name: String,
-    /// Type of the project (binary or library)
+    /// Type of the project (binary, library or cli)
     pub project_type: ProjectType,
     /// Version of the package (defaults to "0.1.0")
     pub version: Option<String>,
+    /// Author of the package
+    pub author: Option<String>,
+    /// SPDX license identifier
+    pub license: Option<String>,
+    /// Node.js versions the package supports, written to `engines.node`
+    pub node: Option<String>,
 }
 
 /// Represents a package.json file structure
@@ -34,30 +80,297 @@ pub struct PackageJson {
     main: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     bin: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    files: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    scripts: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    license: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    dependencies: BTreeMap<String, String>,
+    #[serde(rename = "devDependencies", skip_serializing_if = "BTreeMap::is_empty")]
+    dev_dependencies: BTreeMap<String, String>,
+    #[serde(flatten)]
+    inherited: serde_json::Map<String, serde_json::Value>,
 }
 
-pub fn create_package_json(config: PackageConfig) -> PackageJson {
-    let is_binary = matches!(config.project_type, ProjectType::Binary);
-    let main_file = if is_binary {
-        "src/main.js"
-    } else {
-        "src/lib.js"
+/// Sections a new workspace member copies from the root package.json
+const INHERITED_SECTIONS: [&str; 1] = ["engines"];
+
+/// Fields besides `bin` that point at a file in the package
+const PATH_FIELDS: [&str; 3] = ["main", "module", "types"];
+
+/// Whether `value` is a path inside the package root: neither absolute, on
+/// any platform, nor escaping it with `..`
+fn is_relative_path(value: &str) -> bool {
+    let path = value.strip_prefix("./").unwrap_or(value);
+    let mut chars = path.chars();
+    let has_drive = matches!(
+        (chars.next(), chars.next()),
+        (Some(drive), Some(':')) if drive.is_ascii_alphabetic()
+    );
+    !path.is_empty()
+        && !path.starts_with(['/', '\\'])
+        && !has_drive
+        && path.split(['/', '\\']).all(|part| part != "..")
+}
+
+/// Targets of the `.` entry of an `exports` map, following conditions
+fn root_export_targets(exports: &serde_json::Value) -> Vec<&str> {
+    fn targets<'a>(value: &'a serde_json::Value, found: &mut Vec<&'a str>) {
+        match value {
+            serde_json::Value::String(target) => found.push(target),
+            serde_json::Value::Array(values) => values.iter().for_each(|v| targets(v, found)),
+            serde_json::Value::Object(conditions) => {
+                conditions.values().for_each(|v| targets(v, found))
+            }
+            _ => {}
+        }
+    }
+
+    let root = match exports {
+        serde_json::Value::Object(map) if map.keys().any(|key| key.starts_with('.')) => {
+            match map.get(".") {
+                Some(root) => root,
+                None => return Vec::new(),
+            }
+        }
+        exports => exports,
     };
+    let mut found = Vec::new();
+    targets(root, &mut found);
+    found
+}
+
+/// Check every string in an `exports` value, keyed by subpath or condition
+fn validate_exports(value: &serde_json::Value, key: &str) -> Result<()> {
+    match value {
+        serde_json::Value::String(target)
+            if target.starts_with("./") && is_relative_path(target) =>
+        {
+            Ok(())
+        }
+        serde_json::Value::String(target) => Err(invalid_field(
+            &format!("exports[\"{}\"]", key),
+            &format!("'{}' is not a path starting with `./`", preview(target)),
+        )),
+        serde_json::Value::Null => Ok(()),
+        serde_json::Value::Array(values) => {
+            values.iter().try_for_each(|v| validate_exports(v, key))
+        }
+        serde_json::Value::Object(entries) => entries
+            .iter()
+            .try_for_each(|(key, v)| validate_exports(v, key)),
+        _ => Err(invalid_field(
+            &format!("exports[\"{}\"]", key),
+            "must be a path, a list or a map of conditions",
+        )),
+    }
+}
 
-    let bin = if is_binary {
-        let mut bin_map = HashMap::new();
-        bin_map.insert(config.name.clone(), main_file.to_string());
-        Some(bin_map)
+/// The start of a field value, for error messages
+fn preview(value: &str) -> String {
+    let line = value.lines().next().unwrap_or_default();
+    if line.chars().count() > 40 || line.len() < value.len() {
+        format!("{}...", line.chars().take(40).collect::<String>())
     } else {
-        None
+        line.to_string()
+    }
+}
+
+fn invalid_field(field: &str, reason: &str) -> Error {
+    Error::PackageJsonCreation(format!("field `{}` is invalid: {}", field, reason))
+}
+
+impl PackageJson {
+    /// Merge the entry point, scripts and dependencies declared by a
+    /// template and by its enabled features
+    pub fn merge_template(&mut self, template: &TemplateConfig, features: &[String]) {
+        if let Some(main) = &template.main {
+            // Executables point at the entry point too
+            for target in self.bin.iter_mut().flat_map(|bin| bin.values_mut()) {
+                if self.main.as_deref() == Some(target.trim_start_matches("./")) {
+                    *target = main.clone();
+                }
+            }
+            self.main = Some(main.clone());
+        }
+        self.scripts.extend(template.scripts.clone());
+        self.dependencies.extend(template.dependencies.clone());
+        self.dev_dependencies
+            .extend(template.dev_dependencies.clone());
+        for (_, feature) in template.enabled_features(features) {
+            self.dependencies.extend(feature.dependencies.clone());
+            self.dev_dependencies
+                .extend(feature.dev_dependencies.clone());
+        }
+    }
+
+    /// Copy what every member of a workspace shares from the root
+    /// package.json: `engines` and the shared tools
+    ///
+    /// Only `tools` is taken from the root's cargonode section; its hooks,
+    /// templates and other settings concern the workspace as a whole.
+    pub fn inherit_workspace(&mut self, root: &serde_json::Value) {
+        for section in INHERITED_SECTIONS {
+            if let Some(value) = root.get(section) {
+                self.inherited.insert(section.to_string(), value.clone());
+            }
+        }
+        if let Some(tools) = root.get("cargonode").and_then(|config| config.get("tools")) {
+            self.set_tool_config(serde_json::json!({ "tools": tools }));
+        }
+    }
+
+    /// Remove the cargonode tool configuration, e.g. to write it to
+    /// `cargonode.toml` instead
+    pub fn take_tool_config(&mut self) -> Option<serde_json::Value> {
+        self.inherited.remove("cargonode")
+    }
+
+    /// Set the cargonode tool configuration
+    pub fn set_tool_config(&mut self, config: serde_json::Value) {
+        self.inherited.insert("cargonode".to_string(), config);
+    }
+
+    /// Check the fields before the manifest is written
+    ///
+    /// `main`, `module`, `types` and `bin` must be paths inside the package,
+    /// neither absolute nor escaping it with `..`. `name` must be a valid
+    /// package name, `version` a semver version and `type` either `module`
+    /// or `commonjs`. Every `exports` target must start with `./`, and when
+    /// both are set the `.` export must include `main`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - An error naming the first offending field
+    pub fn validate(&self) -> Result<()> {
+        // Check the document that is written, with the fields a template
+        // merged in and a workspace member inherited
+        let manifest = serde_json::to_value(self)?;
+        let text = |field: &str| manifest.get(field).and_then(serde_json::Value::as_str);
+
+        let name = text("name").unwrap_or_default();
+        if utils::validate_package_name(name).is_err() {
+            return Err(invalid_field(
+                "name",
+                &format!("'{}' is not a valid package name", preview(name)),
+            ));
+        }
+        let version = text("version").unwrap_or_default();
+        if let Err(e) = semver::Version::parse(version) {
+            return Err(invalid_field(
+                "version",
+                &format!("'{}' is not a semver version: {}", preview(version), e),
+            ));
+        }
+
+        let mut paths: Vec<(String, Option<&str>)> = PATH_FIELDS
+            .iter()
+            .filter_map(|field| {
+                let value = manifest.get(*field)?;
+                Some((field.to_string(), value.as_str()))
+            })
+            .collect();
+        match manifest.get("bin") {
+            Some(serde_json::Value::Object(bins)) => paths.extend(
+                bins.iter()
+                    .map(|(name, value)| (format!("bin.{}", name), value.as_str())),
+            ),
+            Some(bin) => paths.push(("bin".to_string(), bin.as_str())),
+            None => {}
+        }
+        paths.sort_by_key(|(field, _)| field.starts_with("bin"));
+        for (field, value) in paths {
+            match value {
+                Some(value) if is_relative_path(value) => {}
+                Some(value) => {
+                    return Err(invalid_field(
+                        &field,
+                        &format!("'{}' is not a path inside the package", preview(value)),
+                    ))
+                }
+                None => return Err(invalid_field(&field, "must be a path")),
+            }
+        }
+
+        if let Some(kind) = manifest.get("type") {
+            if !matches!(kind.as_str(), Some("module" | "commonjs")) {
+                return Err(invalid_field(
+                    "type",
+                    &format!("{} is neither \"module\" nor \"commonjs\"", kind),
+                ));
+            }
+        }
+
+        if let Some(exports) = manifest.get("exports") {
+            validate_exports(exports, ".")?;
+            let targets = root_export_targets(exports);
+            if let Some(main) = text("main") {
+                let main = format!("./{}", main.strip_prefix("./").unwrap_or(main));
+                if !targets.is_empty() && !targets.contains(&main.as_str()) {
+                    return Err(invalid_field(
+                        "exports",
+                        &format!("the \".\" export does not include `main` ({})", main),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn create_package_json(config: PackageConfig) -> PackageJson {
+    let main_file = config.project_type.entry_file();
+
+    let bin = match config.project_type {
+        ProjectType::Binary => Some(HashMap::from([(
+            config.name.clone(),
+            main_file.to_string(),
+        )])),
+        // npm installs the command under the unscoped name
+        ProjectType::Cli => {
+            let command = config
+                .name
+                .rsplit_once('/')
+                .map_or(config.name.as_str(), |(_, name)| name);
+            Some(HashMap::from([(
+                command.to_string(),
+                format!("./{}", main_file),
+            )]))
+        }
+        ProjectType::Library => None,
+    };
+    // A CLI is published, so it lists what goes into the tarball
+    let files = match config.project_type {
+        ProjectType::Cli => vec!["src".to_string()],
+        _ => Vec::new(),
     };
+    let mut inherited = serde_json::Map::new();
+    if config.project_type == ProjectType::Cli {
+        // Its entry is run directly, where Node.js would warn about guessing
+        // the module type
+        inherited.insert("type".to_string(), "module".into());
+    }
+    if let Some(node) = config.node {
+        inherited.insert("engines".to_string(), serde_json::json!({ "node": node }));
+    }
 
     PackageJson {
         name: config.name,
         version: config.version.unwrap_or_else(|| "0.1.0".to_string()),
         main: Some(main_file.to_string()),
         bin,
-        private: is_binary,
+        files,
+        private: config.project_type == ProjectType::Binary,
+        scripts: BTreeMap::new(),
+        author: config.author,
+        license: config.license,
+        dependencies: BTreeMap::new(),
+        dev_dependencies: BTreeMap::new(),
+        inherited,
     }
 }
 
@@ -66,14 +379,1321 @@ pub fn serialize_package_json(package: &PackageJson) -> Result<String> {
 }
 
 pub fn write_package_json(package: &PackageJson, path: &Path) -> Result<()> {
+    package.validate()?;
     let content = serialize_package_json(package)?;
     std::fs::write(path.join("package.json"), content)?;
     Ok(())
 }
 
+/// Name under which `cargonode template` lists the template built into
+/// cargonode
+pub const BUILTIN_TEMPLATE: &str = "default";
+
+/// Template variants built into cargonode, by name
+///
+/// The build script packs each directory below `templates/` into a
+/// `.tar.gz` archive in the format of user templates.
+const TEMPLATE_VARIANTS: &[(&str, &[u8])] =
+    include!(concat!(env!("OUT_DIR"), "/template_variants.rs"));
+
+/// Names of the template variants built into cargonode, sorted
+pub fn template_variants() -> Vec<&'static str> {
+    TEMPLATE_VARIANTS.iter().map(|(name, _)| *name).collect()
+}
+
+/// Name of the manifest describing a user template
+pub const TEMPLATE_MANIFEST: &str = "template.json";
+
+/// Suffix of template file paths that are only written when a condition
+/// holds, e.g. `bin/cli.js__if_binary`
+const CONDITIONAL_PATH_MARKER: &str = "__if_";
+
+/// Variables templates can use as `{{variable}}`
+const PLACEHOLDERS: [&str; 7] = [
+    "project-name",
+    "project-type",
+    "project-description",
+    "author",
+    "license",
+    "year",
+    "node-version",
+];
+
+/// License used by generated projects unless configured otherwise
+pub const DEFAULT_LICENSE: &str = "MIT";
+
+/// Licenses a LICENSE file can be generated for, with their text
+const LICENSE_TEXTS: [(&str, &str); 3] = [
+    ("MIT", MIT_LICENSE),
+    ("Apache-2.0", APACHE_LICENSE),
+    ("ISC", ISC_LICENSE),
+];
+
+/// Spell a license the way SPDX does if it is one of the known licenses
+///
+/// `mit` becomes `MIT`; unknown identifiers are returned unchanged.
+pub fn canonical_license(license: &str) -> String {
+    let license = license.trim();
+    LICENSE_TEXTS
+        .iter()
+        .map(|(id, _)| *id)
+        .chain(["UNLICENSED"])
+        .find(|id| id.eq_ignore_ascii_case(license))
+        .unwrap_or(license)
+        .to_string()
+}
+
+/// Text of the LICENSE file for `license`, if one can be generated
+///
+/// The text contains `{{year}}` and `{{author}}` placeholders.
+pub fn license_text(license: &str) -> Option<&'static str> {
+    LICENSE_TEXTS
+        .iter()
+        .find(|(id, _)| *id == license)
+        .map(|(_, text)| *text)
+}
+
+/// Node.js version used when none is installed
+const DEFAULT_NODE_VERSION: &str = "22";
+
+/// Values substituted for `{{...}}` placeholders when rendering a template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateContext {
+    /// `{{project-name}}`: name of the package
+    pub project_name: String,
+    /// `{{project-type}}`: `binary`, `library` or `cli`
+    pub project_type: ProjectType,
+    /// `{{project-description}}`: short description of the package
+    pub description: String,
+    /// `{{author}}`: author of the package
+    pub author: String,
+    /// `{{license}}`: SPDX license identifier
+    pub license: String,
+    /// `{{year}}`: current year
+    pub year: i64,
+    /// `{{node-version}}`: Node.js version the project targets
+    pub node_version: String,
+}
+
+impl TemplateContext {
+    /// Create a context for a package, filling in the remaining values from
+    /// the environment
+    ///
+    /// The author comes from git's `user.name`, the year from the system clock
+    /// and the Node.js version from the installed `node`.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_name` - Name of the package
+    /// * `project_type` - Type of the project
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new TemplateContext instance
+    pub fn new(project_name: &str, project_type: ProjectType) -> Self {
+        Self {
+            project_name: project_name.to_string(),
+            project_type,
+            description: String::new(),
+            author: utils::git_user_name().unwrap_or_default(),
+            license: DEFAULT_LICENSE.to_string(),
+            year: utils::utc_date(utils::unix_timestamp()).0,
+            node_version: utils::node_version().unwrap_or_else(|| DEFAULT_NODE_VERSION.to_string()),
+        }
+    }
+
+    /// Files every new project gets next to its sources
+    ///
+    /// A README, an `.editorconfig`, a `.gitattributes` and, for licenses
+    /// with a known text, a LICENSE file naming the author and year.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<TemplateFile>` - The rendered files
+    pub fn project_files(&self) -> Vec<TemplateFile> {
+        let mut context = self.clone();
+        if context.author.is_empty() {
+            context.author = format!("The {} authors", self.project_name);
+        }
+        let render = |path: &str, text: &str| TemplateFile {
+            path: path.to_string(),
+            // The embedded texts only use known placeholders
+            content: Some(context.substitute(text, false).unwrap_or_default()),
+            ..Default::default()
+        };
+
+        let mut files = vec![
+            render(".editorconfig", EDITORCONFIG_CONTENT),
+            render(".gitattributes", GITATTRIBUTES_CONTENT),
+        ];
+        match license_text(&self.license) {
+            Some(text) => {
+                files.push(render("LICENSE", text));
+                files.push(render("README.md", README_CONTENT));
+            }
+            None => files.push(render("README.md", README_NO_LICENSE_FILE_CONTENT)),
+        }
+        files
+    }
+
+    /// Every placeholder with the value it is replaced with
+    ///
+    /// # Returns
+    ///
+    /// * `BTreeMap<String, String>` - Values keyed by placeholder, e.g.
+    ///   `{{project-name}}`
+    pub fn substitutions(&self) -> BTreeMap<String, String> {
+        PLACEHOLDERS
+            .iter()
+            .filter_map(|variable| {
+                let value = self.value(variable, false)?;
+                Some((format!("{{{{{}}}}}", variable), value))
+            })
+            .collect()
+    }
+
+    /// Look up the value of a placeholder
+    ///
+    /// In paths, scoped names lose their scope so that `@scope/name` does
+    /// not introduce a directory.
+    fn value(&self, variable: &str, in_path: bool) -> Option<String> {
+        let value = match variable {
+            "project-name" if in_path => self
+                .project_name
+                .rsplit_once('/')
+                .map_or(self.project_name.as_str(), |(_, name)| name)
+                .to_string(),
+            "project-name" => self.project_name.clone(),
+            "project-type" => self.project_type.name().to_string(),
+            "project-description" => self.description.clone(),
+            "author" => self.author.clone(),
+            "license" => self.license.clone(),
+            "year" => self.year.to_string(),
+            "node-version" => self.node_version.clone(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Whether a condition of an `{{#if ...}}` block holds
+    ///
+    /// `binary`, `lib`, `cli` and `license-file` (a LICENSE text can be
+    /// generated) are flags; any other variable holds when its value is not
+    /// empty.
+    fn condition(&self, variable: &str) -> Option<bool> {
+        let holds = match variable {
+            "binary" => self.project_type == ProjectType::Binary,
+            "lib" => self.project_type == ProjectType::Library,
+            "cli" => self.project_type == ProjectType::Cli,
+            "license-file" => license_text(&self.license).is_some(),
+            _ => !self.value(variable, false)?.is_empty(),
+        };
+        Some(holds)
+    }
+
+    /// Substitute the placeholders in `text`
+    ///
+    /// `{{#if variable}} ... {{else}} ... {{/if}}` blocks keep only the branch
+    /// selected by `condition`. A block tag on a line of its own is removed
+    /// with its line. `\{{` is written out as a literal `{{`.
+    ///
+    /// # Returns
+    ///
+    /// * `std::result::Result<String, String>` - The rendered text, or the
+    ///   first unknown placeholder or unbalanced block tag
+    fn substitute(&self, text: &str, in_path: bool) -> std::result::Result<String, String> {
+        /// An open `{{#if}}` block
+        struct Block {
+            tag: String,
+            /// Whether the enclosing text is rendered
+            outer: bool,
+            holds: bool,
+            in_else: bool,
+        }
+        let rendering = |blocks: &[Block]| {
+            blocks
+                .last()
+                .is_none_or(|block| block.outer && block.holds != block.in_else)
+        };
+
+        let mut rendered = String::with_capacity(text.len());
+        let mut blocks: Vec<Block> = Vec::new();
+        let mut rest = text;
+        let mut at_line_start = true;
+
+        while let Some(start) = rest.find("{{") {
+            let active = rendering(&blocks);
+            if rest[..start].ends_with('\\') {
+                if active {
+                    rendered.push_str(&rest[..start - 1]);
+                    rendered.push_str("{{");
+                }
+                at_line_start = false;
+                rest = &rest[start + 2..];
+                continue;
+            }
+
+            let Some(end) = rest[start..].find("}}") else {
+                break;
+            };
+            let end = start + end + 2;
+            let placeholder = &rest[start..end];
+            let tag = placeholder[2..placeholder.len() - 2].trim();
+            let is_block = tag.starts_with("#if ") || tag == "else" || tag == "/if";
+
+            // Block tags alone on their line take the line with them
+            let before = &rest[..start];
+            let line_before = before.rsplit('\n').next().unwrap_or_default();
+            let line_after = rest[end..].split('\n').next().unwrap_or_default();
+            let standalone = is_block
+                && (at_line_start || before.contains('\n'))
+                && line_before.trim().is_empty()
+                && line_after.trim().is_empty();
+            if active {
+                let kept = if standalone {
+                    &before[..before.len() - line_before.len()]
+                } else {
+                    before
+                };
+                rendered.push_str(kept);
+            }
+            rest = &rest[end..];
+            at_line_start = false;
+            if standalone {
+                rest = rest.split_once('\n').map_or("", |(_, after)| after);
+                at_line_start = true;
+            }
+
+            if let Some(variable) = tag.strip_prefix("#if ") {
+                let variable = variable.trim();
+                let holds = self
+                    .condition(variable)
+                    .ok_or_else(|| format!("{{{{{}}}}}", variable))?;
+                blocks.push(Block {
+                    tag: placeholder.to_string(),
+                    outer: active,
+                    holds,
+                    in_else: false,
+                });
+            } else if tag == "else" {
+                match blocks.last_mut() {
+                    Some(block) if !block.in_else => block.in_else = true,
+                    _ => return Err(placeholder.to_string()),
+                }
+            } else if tag == "/if" {
+                blocks.pop().ok_or_else(|| placeholder.to_string())?;
+            } else {
+                let value = self.value(tag, in_path);
+                let value = value.ok_or_else(|| placeholder.to_string())?;
+                if active {
+                    rendered.push_str(&value);
+                }
+            }
+        }
+
+        if let Some(block) = blocks.pop() {
+            return Err(block.tag);
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+}
+
+/// A file shipped by a user template
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct TemplateFile {
+    /// Destination path relative to the project root
+    pub path: String,
+    /// File content; read from the template next to the manifest when omitted
+    #[serde(default)]
+    pub content: Option<String>,
+    /// Content of a binary file, copied byte for byte without rendering
+    #[serde(skip)]
+    pub bytes: Option<Vec<u8>>,
+    /// Whether the file should be made executable
+    #[serde(default)]
+    pub executable: bool,
+}
+
+impl TemplateFile {
+    /// Take the content of the file from the template
+    ///
+    /// Text becomes `content` and is rendered; binary content is kept in
+    /// `bytes`. A source file with an executable bit makes the written file
+    /// executable too.
+    fn load_source(&mut self, bytes: Vec<u8>, executable: bool) {
+        self.executable |= executable;
+        if is_binary(&bytes) {
+            self.bytes = Some(bytes);
+            return;
+        }
+        match String::from_utf8(bytes) {
+            Ok(text) => self.content = Some(text),
+            Err(e) => self.bytes = Some(e.into_bytes()),
+        }
+    }
+}
+
+/// Whether file content is binary rather than text
+///
+/// As git does, content with a NUL byte in its first 8000 bytes is binary.
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8000).any(|&byte| byte == 0)
+}
+
+/// Links followed to find a file in a template archive before giving up, so
+/// a cycle of links is reported rather than looped over
+const MAX_ARCHIVE_LINKS: usize = 8;
+
+/// Variable overriding [`ArchiveLimits::max_bytes`]
+pub const ARCHIVE_MAX_BYTES_ENV: &str = "CARGONODE_TEMPLATE_MAX_BYTES";
+
+/// Variable overriding [`ArchiveLimits::max_entries`]
+pub const ARCHIVE_MAX_ENTRIES_ENV: &str = "CARGONODE_TEMPLATE_MAX_ENTRIES";
+
+/// Bounds on what a template archive may unpack to
+///
+/// Archives are unpacked in memory, so these keep a corrupted or malicious
+/// archive from exhausting it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveLimits {
+    /// Total uncompressed size in bytes of the entries
+    pub max_bytes: u64,
+
+    /// Number of entries, directories and links included
+    pub max_entries: usize,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 256 * 1024 * 1024,
+            max_entries: 10_000,
+        }
+    }
+}
+
+impl ArchiveLimits {
+    /// The default limits, with any override from [`ARCHIVE_MAX_BYTES_ENV`]
+    /// and [`ARCHIVE_MAX_ENTRIES_ENV`]
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The limits, or `Error::Config` if a variable is not
+    ///   a positive integer
+    pub fn from_env() -> Result<Self> {
+        fn var<T: std::str::FromStr + PartialOrd + Default>(name: &str) -> Result<Option<T>> {
+            let Ok(value) = std::env::var(name) else {
+                return Ok(None);
+            };
+            match value.trim().parse::<T>() {
+                Ok(limit) if limit > T::default() => Ok(Some(limit)),
+                _ => Err(Error::Config {
+                    message: format!("Invalid {}='{}'; expected a positive integer", name, value),
+                    hint: None,
+                }),
+            }
+        }
+
+        let defaults = Self::default();
+        Ok(Self {
+            max_bytes: var(ARCHIVE_MAX_BYTES_ENV)?.unwrap_or(defaults.max_bytes),
+            max_entries: var(ARCHIVE_MAX_ENTRIES_ENV)?.unwrap_or(defaults.max_entries),
+        })
+    }
+}
+
+/// Whether a file mode has any executable bit set
+fn is_executable_mode(mode: u32) -> bool {
+    mode & 0o111 != 0
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    is_executable_mode(metadata.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Read the file at `path` of the template directory `dir`
+///
+/// Symlinks, and junctions on Windows, are followed as long as they resolve
+/// to a regular file inside the template, whose content and executable bit
+/// are taken; one leading outside of it is an error rather than copying
+/// whatever it points to into the project.
+///
+/// # Returns
+///
+/// * `Result<(Vec<u8>, bool), String>` - The content and whether the file is
+///   executable, or why it cannot be read
+fn read_directory_source(dir: &Path, path: &str) -> std::result::Result<(Vec<u8>, bool), String> {
+    let source = dir.join(path);
+    let cannot_read =
+        |e: std::io::Error| format!("Cannot read template file '{}': {}", source.display(), e);
+
+    let resolved = fs::canonicalize(&source).map_err(cannot_read)?;
+    let root = fs::canonicalize(dir).map_err(cannot_read)?;
+    if !resolved.starts_with(&root) {
+        return Err(format!(
+            "Template file '{}' links to '{}', which is outside the template",
+            source.display(),
+            resolved.display()
+        ));
+    }
+    let metadata = fs::metadata(&resolved).map_err(cannot_read)?;
+    if !metadata.is_file() {
+        return Err(format!(
+            "Template file '{}' is not a regular file",
+            source.display()
+        ));
+    }
+    let bytes = fs::read(&resolved).map_err(cannot_read)?;
+    Ok((bytes, is_executable(&metadata)))
+}
+
+/// Resolve a link target inside an archive to the entry path it names
+///
+/// `base` is the directory the target is relative to: the link's own
+/// directory for symlinks, the archive root for hard links.
+///
+/// # Returns
+///
+/// * `Option<PathBuf>` - The entry path, or `None` if the target is absolute
+///   or climbs above the archive root
+fn resolve_archive_link(base: &Path, target: &Path) -> Option<PathBuf> {
+    let mut resolved = PathBuf::new();
+    for component in base.join(target).components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(resolved)
+}
+
+/// Follow the links on the way to `path` inside an archive
+///
+/// A link may name the entry itself or any directory above it, as a
+/// symlinked directory does; the outermost one is followed first. Following
+/// stops after [`MAX_ARCHIVE_LINKS`] links, leaving a path that still goes
+/// through one.
+///
+/// # Arguments
+///
+/// * `path` - Entry path inside the archive
+/// * `links` - Where each link of the archive points, as collected by
+///   `read_archive_from`
+///
+/// # Returns
+///
+/// * `Option<PathBuf>` - The entry path reached, or `None` if a link leads
+///   out of the archive
+fn follow_archive_links(path: &Path, links: &HashMap<PathBuf, Option<PathBuf>>) -> Option<PathBuf> {
+    let mut path = path.to_path_buf();
+    for _ in 0..=MAX_ARCHIVE_LINKS {
+        let Some(link) = path.ancestors().filter(|p| links.contains_key(*p)).last() else {
+            break;
+        };
+        let rest = path.strip_prefix(link).unwrap_or(Path::new(""));
+        let target = links[link].as_ref()?;
+        path = if rest.as_os_str().is_empty() {
+            target.clone()
+        } else {
+            target.join(rest)
+        };
+    }
+    Some(path)
+}
+
+/// An optional part of a user template, enabled with `--features`
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct FeatureSpec {
+    /// Short description of the feature
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Paths of template files only written when the feature is enabled
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Dependencies added when the feature is enabled
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+    /// Development dependencies added when the feature is enabled
+    #[serde(default)]
+    pub dev_dependencies: BTreeMap<String, String>,
+}
+
+fn default_project_types() -> Vec<ProjectType> {
+    vec![ProjectType::Binary, ProjectType::Library]
+}
+
+/// A user template described by a `template.json` manifest
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplateConfig {
+    /// Name of the template
+    pub name: String,
+    /// Short description of the template
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Version of the template itself
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Project types the template can scaffold
+    #[serde(default = "default_project_types")]
+    pub project_types: Vec<ProjectType>,
+    /// Files written into the new project
+    #[serde(default)]
+    pub files: Vec<TemplateFile>,
+    /// Dependencies merged into the generated package.json
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+    /// Development dependencies merged into the generated package.json
+    #[serde(default)]
+    pub dev_dependencies: BTreeMap<String, String>,
+    /// Scripts merged into the generated package.json
+    #[serde(default)]
+    pub scripts: BTreeMap<String, String>,
+    /// Entry point recorded in package.json instead of the project type's,
+    /// e.g. the compiled output of a TypeScript template
+    #[serde(default)]
+    pub main: Option<String>,
+    /// Oldest cargonode version the template works with
+    #[serde(default)]
+    pub min_cargonode_version: Option<String>,
+    /// Optional parts of the template, by name
+    #[serde(default)]
+    pub features: BTreeMap<String, FeatureSpec>,
+}
+
+/// A problem found while checking a user template
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TemplateProblem {
+    /// Path of the template file the problem is in, relative to the template;
+    /// `None` when the template as a whole cannot be read
+    pub file: Option<String>,
+    /// What is wrong
+    pub message: String,
+}
+
+impl TemplateProblem {
+    fn new(file: Option<&str>, message: String) -> Self {
+        Self {
+            file: file.map(str::to_string),
+            message,
+        }
+    }
+
+    fn into_error(self) -> Error {
+        Error::Template {
+            message: self.message,
+        }
+    }
+}
+
+/// Whether a template file path is relative and stays inside the project
+fn is_project_path(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+impl TemplateConfig {
+    /// Load a template from a directory or a `.tar.gz` archive
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Directory containing `template.json`, or a gzipped tarball
+    ///
+    /// # Returns
+    ///
+    /// * `Result<TemplateConfig>` - The validated template with all file contents loaded
+    pub fn load(path: &Path) -> Result<Self> {
+        let (config, unreadable) = Self::read(path).map_err(TemplateProblem::into_error)?;
+        match config.problems().into_iter().chain(unreadable).next() {
+            Some(problem) => Err(problem.into_error()),
+            None => Ok(config),
+        }
+    }
+
+    /// Load one of the template variants built into cargonode
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the variant, e.g. `typescript-lib`
+    ///
+    /// # Returns
+    ///
+    /// * `Result<TemplateConfig>` - The variant, or `Error::Template` listing
+    ///   the available variants when there is none of that name
+    pub fn load_variant(name: &str) -> Result<Self> {
+        let (_, archive) = TEMPLATE_VARIANTS
+            .iter()
+            .find(|(variant, _)| *variant == name)
+            .ok_or_else(|| Error::Template {
+                message: format!(
+                    "Unknown template variant '{}'. Available variants: {}",
+                    name,
+                    template_variants().join(", ")
+                ),
+            })?;
+        let (config, unreadable) =
+            Self::read_archive_from(*archive, Path::new(name), &ArchiveLimits::default())
+                .map_err(TemplateProblem::into_error)?;
+        match config.problems().into_iter().chain(unreadable).next() {
+            Some(problem) => Err(problem.into_error()),
+            None => Ok(config),
+        }
+    }
+
+    /// The template built into cargonode, as `cargonode template` describes it
+    ///
+    /// Its files are the entry point of each project type, under the
+    /// condition that picks it, and the files every project gets, unrendered.
+    pub fn builtin() -> Self {
+        let file = |path: &str, content: &str| TemplateFile {
+            path: path.to_string(),
+            content: Some(content.to_string()),
+            ..Default::default()
+        };
+        Self {
+            name: BUILTIN_TEMPLATE.to_string(),
+            description: Some("The project scaffold built into cargonode".to_string()),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            project_types: ProjectType::value_variants().to_vec(),
+            files: vec![
+                file("src/main.js__if_binary", MAIN_JS_CONTENT),
+                file("src/main.js__if_cli", CLI_JS_CONTENT),
+                file("src/lib.js__if_lib", LIB_JS_CONTENT),
+                file(".editorconfig", EDITORCONFIG_CONTENT),
+                file(".gitattributes", GITATTRIBUTES_CONTENT),
+                file("README.md", README_CONTENT),
+            ],
+            dependencies: BTreeMap::new(),
+            dev_dependencies: BTreeMap::new(),
+            scripts: BTreeMap::new(),
+            main: None,
+            min_cargonode_version: None,
+            features: BTreeMap::new(),
+        }
+    }
+
+    /// Check a template without stopping at the first problem
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Directory containing `template.json`, or a gzipped tarball
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<TemplateProblem>` - Every problem found, manifest problems
+    ///   first; empty when `load` would succeed
+    pub fn check(path: &Path) -> Vec<TemplateProblem> {
+        match Self::read(path) {
+            Ok((config, unreadable)) => config.problems().into_iter().chain(unreadable).collect(),
+            Err(problem) => vec![problem],
+        }
+    }
+
+    /// Read the manifest and the contents of its files without validating them
+    ///
+    /// Files that cannot be read are returned as problems next to the
+    /// template; only a missing or unparsable manifest is an error.
+    fn read(path: &Path) -> std::result::Result<(Self, Vec<TemplateProblem>), TemplateProblem> {
+        if path.is_dir() {
+            Self::read_directory(path)
+        } else if path.is_file() {
+            Self::read_archive(path)
+        } else {
+            Err(TemplateProblem::new(
+                None,
+                format!("Template '{}' does not exist", path.display()),
+            ))
+        }
+    }
+
+    fn parse(manifest: &str, origin: &Path) -> std::result::Result<Self, TemplateProblem> {
+        serde_json::from_str(manifest).map_err(|e| {
+            TemplateProblem::new(
+                Some(TEMPLATE_MANIFEST),
+                format!(
+                    "Invalid {} in '{}': {}",
+                    TEMPLATE_MANIFEST,
+                    origin.display(),
+                    e
+                ),
+            )
+        })
+    }
+
+    /// Files whose content is read from the template rather than the manifest
+    ///
+    /// Paths that would leave the template are never read; `problems`
+    /// reports them.
+    fn external_files(&mut self) -> impl Iterator<Item = &mut TemplateFile> {
+        self.files
+            .iter_mut()
+            .filter(|file| file.content.is_none() && is_project_path(&file.path))
+    }
+
+    fn read_directory(
+        dir: &Path,
+    ) -> std::result::Result<(Self, Vec<TemplateProblem>), TemplateProblem> {
+        let manifest_path = dir.join(TEMPLATE_MANIFEST);
+        let manifest = fs::read_to_string(&manifest_path).map_err(|e| {
+            TemplateProblem::new(
+                Some(TEMPLATE_MANIFEST),
+                format!("Cannot read '{}': {}", manifest_path.display(), e),
+            )
+        })?;
+
+        let mut config = Self::parse(&manifest, dir)?;
+        let mut unreadable = Vec::new();
+        for file in config.external_files() {
+            match read_directory_source(dir, &file.path) {
+                Ok((bytes, executable)) => file.load_source(bytes, executable),
+                Err(message) => unreadable.push(TemplateProblem::new(Some(&file.path), message)),
+            }
+        }
+
+        Ok((config, unreadable))
+    }
+
+    fn read_archive(
+        path: &Path,
+    ) -> std::result::Result<(Self, Vec<TemplateProblem>), TemplateProblem> {
+        let file = fs::File::open(path).map_err(|e| {
+            TemplateProblem::new(
+                None,
+                format!("Cannot read template archive '{}': {}", path.display(), e),
+            )
+        })?;
+        let limits =
+            ArchiveLimits::from_env().map_err(|e| TemplateProblem::new(None, e.to_string()))?;
+        Self::read_archive_from(file, path, &limits)
+    }
+
+    /// Read a gzipped tarball named `path` in messages from `reader`
+    ///
+    /// The whole archive is rejected when it exceeds `limits`, has an entry
+    /// whose path leads outside of it, or holds a device or FIFO.
+    fn read_archive_from(
+        reader: impl Read,
+        path: &Path,
+        limits: &ArchiveLimits,
+    ) -> std::result::Result<(Self, Vec<TemplateProblem>), TemplateProblem> {
+        let archive_error = |e: std::io::Error| {
+            TemplateProblem::new(
+                None,
+                format!("Cannot read template archive '{}': {}", path.display(), e),
+            )
+        };
+        let rejected = |message: String| {
+            TemplateProblem::new(
+                None,
+                format!("Template archive '{}' {}", path.display(), message),
+            )
+        };
+
+        // Collect every regular file of the archive and its mode, keyed by its
+        // path, and where its symlinks and hard links point
+        let mut entries: HashMap<PathBuf, (Vec<u8>, u32)> = HashMap::new();
+        let mut links: HashMap<PathBuf, Option<PathBuf>> = HashMap::new();
+        let mut archive = tar::Archive::new(GzDecoder::new(reader));
+        let mut total_bytes: u64 = 0;
+        for (count, entry) in archive.entries().map_err(archive_error)?.enumerate() {
+            if count == limits.max_entries {
+                return Err(rejected(format!(
+                    "has more than {} entries; raise {} to allow it",
+                    limits.max_entries, ARCHIVE_MAX_ENTRIES_ENV
+                )));
+            }
+            let mut entry = entry.map_err(archive_error)?;
+            let entry_type = entry.header().entry_type();
+            let entry_path = entry.path().map_err(archive_error)?.into_owned();
+            if resolve_archive_link(Path::new(""), &entry_path).is_none() {
+                return Err(rejected(format!(
+                    "contains '{}', which is outside the archive",
+                    entry_path.display()
+                )));
+            }
+            if entry_type.is_block_special()
+                || entry_type.is_character_special()
+                || entry_type.is_fifo()
+            {
+                return Err(rejected(format!(
+                    "contains '{}', a device or FIFO, which a template cannot hold",
+                    entry_path.display()
+                )));
+            }
+            total_bytes = total_bytes.saturating_add(entry.size());
+            if total_bytes > limits.max_bytes {
+                return Err(rejected(format!(
+                    "unpacks to more than {} (at '{}'); raise {} to allow it",
+                    progress::format_bytes(limits.max_bytes),
+                    entry_path.display(),
+                    ARCHIVE_MAX_BYTES_ENV
+                )));
+            }
+            if entry_type.is_symlink() || entry_type.is_hard_link() {
+                let target = entry
+                    .link_name()
+                    .map_err(archive_error)?
+                    .unwrap_or_default();
+                let base = if entry_type.is_symlink() {
+                    entry_path.parent().unwrap_or(Path::new(""))
+                } else {
+                    Path::new("")
+                };
+                let resolved = resolve_archive_link(base, &target);
+                links.insert(entry_path, resolved);
+                continue;
+            }
+            if !entry_type.is_file() {
+                continue;
+            }
+            let mode = entry.header().mode().unwrap_or(0o644);
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).map_err(archive_error)?;
+            entries.insert(entry_path, (content, mode));
+        }
+
+        // The manifest may sit at the root or inside a single top-level directory
+        let manifest_path = entries
+            .keys()
+            .filter(|p| p.file_name() == Some(TEMPLATE_MANIFEST.as_ref()))
+            .min_by_key(|p| p.components().count())
+            .cloned()
+            .ok_or_else(|| {
+                TemplateProblem::new(
+                    None,
+                    format!(
+                        "Template archive '{}' does not contain a {}",
+                        path.display(),
+                        TEMPLATE_MANIFEST
+                    ),
+                )
+            })?;
+        let root = manifest_path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .to_path_buf();
+
+        let manifest = String::from_utf8(entries[&manifest_path].0.clone()).map_err(|_| {
+            TemplateProblem::new(
+                Some(TEMPLATE_MANIFEST),
+                format!(
+                    "Template file '{}' is not valid UTF-8",
+                    manifest_path.display()
+                ),
+            )
+        })?;
+        let mut config = Self::parse(&manifest, path)?;
+        let mut unreadable = Vec::new();
+        for file in config.external_files() {
+            // Links are materialized as a copy of the file they point to, as
+            // long as it is inside the template
+            let entry_path = follow_archive_links(&root.join(&file.path), &links);
+            let Some(entry_path) = entry_path.filter(|p| p.starts_with(&root)) else {
+                unreadable.push(TemplateProblem::new(
+                    Some(&file.path),
+                    format!(
+                        "Template file '{}' in '{}' links to a file outside the template",
+                        file.path,
+                        path.display()
+                    ),
+                ));
+                continue;
+            };
+            if entry_path.ancestors().any(|p| links.contains_key(p)) {
+                unreadable.push(TemplateProblem::new(
+                    Some(&file.path),
+                    format!(
+                        "Template file '{}' in '{}' goes through more than {} links",
+                        file.path,
+                        path.display(),
+                        MAX_ARCHIVE_LINKS
+                    ),
+                ));
+                continue;
+            }
+            match entries.get(&entry_path) {
+                Some((bytes, mode)) => file.load_source(bytes.clone(), is_executable_mode(*mode)),
+                None => unreadable.push(TemplateProblem::new(
+                    Some(&file.path),
+                    format!(
+                        "Template file '{}' is missing from '{}'",
+                        file.path,
+                        path.display()
+                    ),
+                )),
+            }
+        }
+
+        Ok((config, unreadable))
+    }
+
+    /// Validate the manifest
+    ///
+    /// File paths must be relative and stay inside the project directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - `Error::Template` with the first of `problems`
+    pub fn validate(&self) -> Result<()> {
+        match self.problems().into_iter().next() {
+            Some(problem) => Err(problem.into_error()),
+            None => Ok(()),
+        }
+    }
+
+    /// Every problem of the manifest, in the order `validate` checks them
+    pub fn problems(&self) -> Vec<TemplateProblem> {
+        let manifest = |message: String| TemplateProblem::new(Some(TEMPLATE_MANIFEST), message);
+        let mut problems = Vec::new();
+
+        if self.name.trim().is_empty() {
+            problems.push(manifest("Template name cannot be empty".to_string()));
+        }
+        if self.project_types.is_empty() {
+            problems.push(manifest(format!(
+                "Template '{}' does not support any project type",
+                self.name
+            )));
+        }
+        if let Some(version) = &self.version {
+            if let Err(e) = Version::parse(version) {
+                problems.push(manifest(format!(
+                    "Template '{}' has an invalid version '{}': {}",
+                    self.name, version, e
+                )));
+            }
+        }
+
+        let mut seen = Vec::new();
+        for file in &self.files {
+            let path = Path::new(&file.path);
+            let in_file = |message: String| TemplateProblem::new(Some(&file.path), message);
+            if !is_project_path(&file.path) {
+                problems.push(in_file(format!(
+                    "Template file path '{}' must be relative and stay inside the project",
+                    file.path
+                )));
+            } else if path == Path::new("package.json") {
+                problems.push(in_file(
+                    "Templates cannot ship package.json; declare dependencies and scripts in the manifest instead"
+                        .to_string(),
+                ));
+            } else if seen.contains(&path) {
+                problems.push(in_file(format!(
+                    "Template file '{}' is listed twice",
+                    file.path
+                )));
+            }
+            seen.push(path);
+        }
+
+        if let Some(required) = &self.min_cargonode_version {
+            match Version::parse(required) {
+                Err(e) => problems.push(manifest(format!(
+                    "Template '{}' has an invalid min_cargonode_version '{}': {}",
+                    self.name, required, e
+                ))),
+                Ok(version) => {
+                    let running =
+                        Version::parse(env!("CARGO_PKG_VERSION")).unwrap_or(version.clone());
+                    if running < version {
+                        problems.push(manifest(format!(
+                            "Template '{}' requires cargonode >= {}, but this is cargonode {}",
+                            self.name, version, running
+                        )));
+                    }
+                }
+            }
+        }
+
+        for (name, feature) in &self.features {
+            if name.trim().is_empty() || name.contains(',') {
+                problems.push(manifest(format!(
+                    "Template '{}' has an invalid feature name '{}'",
+                    self.name, name
+                )));
+            }
+            for missing in feature
+                .files
+                .iter()
+                .filter(|path| !self.files.iter().any(|file| file.path == **path))
+            {
+                problems.push(manifest(format!(
+                    "Feature '{}' of template '{}' lists '{}', which is not one of the template files",
+                    name, self.name, missing
+                )));
+            }
+        }
+
+        problems
+    }
+
+    /// Check that every selected feature exists
+    ///
+    /// # Arguments
+    ///
+    /// * `features` - Names of the features to enable
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - `Error::Template` naming the first unknown feature
+    pub fn check_features(&self, features: &[String]) -> Result<()> {
+        let Some(unknown) = features
+            .iter()
+            .find(|name| !self.features.contains_key(*name))
+        else {
+            return Ok(());
+        };
+        let available = if self.features.is_empty() {
+            "it has no features".to_string()
+        } else {
+            format!(
+                "available features: {}",
+                self.features.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        };
+        Err(Error::Template {
+            message: format!(
+                "Template '{}' has no feature '{}'; {}",
+                self.name, unknown, available
+            ),
+        })
+    }
+
+    /// The features among `selected`, by name
+    fn enabled_features<'a>(
+        &'a self,
+        selected: &'a [String],
+    ) -> impl Iterator<Item = (&'a String, &'a FeatureSpec)> {
+        self.features
+            .iter()
+            .filter(move |(name, _)| selected.contains(name))
+    }
+
+    /// Whether a template file is written with the selected features
+    ///
+    /// Files that no feature lists are always written; the others only
+    /// when one of the features listing them is enabled.
+    fn includes_file(&self, path: &str, selected: &[String]) -> bool {
+        let mut owners = self
+            .features
+            .iter()
+            .filter(|(_, feature)| feature.files.iter().any(|file| file == path))
+            .peekable();
+        owners.peek().is_none() || owners.any(|(name, _)| selected.contains(name))
+    }
+
+    /// Whether the template can scaffold projects of the given type
+    pub fn supports(&self, project_type: ProjectType) -> bool {
+        self.project_types.contains(&project_type)
+    }
+
+    /// Render the template files for a package
+    ///
+    /// Placeholders such as `{{project-name}}` or `{{year}}` in file paths and
+    /// contents are replaced with the values from `context`, and
+    /// `{{#if ...}}` blocks in contents are resolved. Files named
+    /// `name__if_variable` are only written, as `name`, when the condition
+    /// holds, and files left empty by their blocks are not written. Files of
+    /// features that are not selected are skipped. Binary files are copied
+    /// unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - Values for the placeholders
+    /// * `features` - Features whose files are written
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<TemplateFile>>` - The rendered files, or `Error::Template`
+    ///   naming an unknown feature, or the first unknown placeholder or
+    ///   unbalanced block and the file it appears in
+    pub fn render(
+        &self,
+        context: &TemplateContext,
+        features: &[String],
+    ) -> Result<Vec<TemplateFile>> {
+        self.check_features(features)?;
+        let invalid = |file: &TemplateFile, tag: String| {
+            let message = if ["{{#if", "{{else", "{{/if"]
+                .iter()
+                .any(|block| tag.starts_with(block))
+            {
+                format!(
+                    "Unbalanced template block '{}' in {}. Every {{{{#if ...}}}} needs one {{{{/if}}}}",
+                    tag, file.path
+                )
+            } else {
+                format!(
+                    "Unknown template variable '{}' in {}. Escape it as \\{} to keep it literally",
+                    tag, file.path, tag
+                )
+            };
+            Error::Template { message }
+        };
+
+        let mut files = Vec::new();
+        for file in &self.files {
+            if !self.includes_file(&file.path, features) {
+                continue;
+            }
+
+            // `name__if_variable` is only written when the condition holds
+            let mut path = file.path.as_str();
+            if let Some((stripped, variable)) = path.rsplit_once(CONDITIONAL_PATH_MARKER) {
+                let holds = context
+                    .condition(variable)
+                    .ok_or_else(|| invalid(file, format!("{{{{{}}}}}", variable)))?;
+                if !holds {
+                    continue;
+                }
+                path = stripped;
+            }
+
+            let content = file
+                .content
+                .as_ref()
+                .map(|content| context.substitute(content, false))
+                .transpose()
+                .map_err(|tag| invalid(file, tag))?;
+            // A file whose blocks were all left out is not written at all
+            let emptied = file.content.as_ref().is_some_and(|c| !c.trim().is_empty())
+                && content.as_ref().is_some_and(|c| c.trim().is_empty());
+            if emptied {
+                continue;
+            }
+
+            files.push(TemplateFile {
+                path: context
+                    .substitute(path, true)
+                    .map_err(|tag| invalid(file, tag))?,
+                content,
+                bytes: file.bytes.clone(),
+                executable: file.executable,
+            });
+        }
+        Ok(files)
+    }
+}
+
 /// Template content for .gitignore file
 pub const GITIGNORE_CONTENT: &str = r#"node_modules/
 .env.*
+.cargonode/
+"#;
+
+/// Template content for .gitattributes file
+///
+/// Generated files use LF line endings on every platform, so Git is told to
+/// keep them that way rather than converting them on checkout.
+pub const GITATTRIBUTES_CONTENT: &str = "* text=auto eol=lf\n";
+
+/// Template content for .editorconfig file
+pub const EDITORCONFIG_CONTENT: &str = r#"root = true
+
+[*]
+charset = utf-8
+end_of_line = lf
+indent_style = space
+indent_size = 4
+insert_final_newline = true
+trim_trailing_whitespace = true
+
+[*.{json,yml,yaml}]
+indent_size = 2
+
+[*.md]
+trim_trailing_whitespace = false
+"#;
+
+/// Template content for README.md file
+const README_CONTENT: &str = r#"# {{project-name}}
+
+## License
+
+Licensed under the {{license}} license. See [LICENSE](LICENSE) for details.
+"#;
+
+/// Template content for README.md file when no LICENSE file is generated
+const README_NO_LICENSE_FILE_CONTENT: &str = r#"# {{project-name}}
+
+## License
+
+{{license}}
+"#;
+
+/// Text of the MIT license
+const MIT_LICENSE: &str = r#"MIT License
+
+Copyright (c) {{year}} {{author}}
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+"#;
+
+/// Text of the ISC license
+const ISC_LICENSE: &str = r#"ISC License
+
+Copyright (c) {{year}} {{author}}
+
+Permission to use, copy, modify, and/or distribute this software for any
+purpose with or without fee is hereby granted, provided that the above
+copyright notice and this permission notice appear in all copies.
+
+THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+"#;
+
+/// Apache-2.0 license notice, as recommended by the license's appendix
+const APACHE_LICENSE: &str = r#"Copyright {{year}} {{author}}
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
 "#;
 
 /// Template content for main.js file
@@ -86,6 +1706,42 @@ if (import.meta.url === new URL(import.meta.resolve(), import.meta.url).href) {
 }
 "#;
 
+/// Template content for the main.js file of a CLI
+pub const CLI_JS_CONTENT: &str = r#"#!/usr/bin/env node
+import { basename } from "node:path";
+import { parseArgs } from "node:util";
+
+function usage() {
+    return `Usage: ${basename(process.argv[1])} [--help] [name]`;
+}
+
+function main(argv) {
+    const { values, positionals } = parseArgs({
+        args: argv,
+        options: {
+            help: { type: "boolean", short: "h" },
+        },
+        allowPositionals: true,
+    });
+
+    if (values.help) {
+        console.log(usage());
+        return 0;
+    }
+
+    const name = positionals[0] ?? "world";
+    console.log(`Hello, ${name}!`);
+    return 0;
+}
+
+try {
+    process.exitCode = main(process.argv.slice(2));
+} catch (error) {
+    console.error(`${error.message}\n${usage()}`);
+    process.exitCode = 2;
+}
+"#;
+
 /// Template content for lib.js file
 pub const LIB_JS_CONTENT: &str = r#"export function add(left, right) {
     return left + right;
@@ -107,14 +1763,859 @@ if (import.meta.url === new URL(import.meta.resolve(), import.meta.url).href) {
 
 #[cfg(test)]
 mod tests {
+    use flate2::{write::GzEncoder, Compression};
+    use tempfile::TempDir;
+
     use super::*;
 
+    const MANIFEST: &str = r##"{
+        "name": "web",
+        "project_types": ["binary"],
+        "files": [
+            { "path": "src/main.js" },
+            { "path": "bin/{{project-name}}", "content": "#!/bin/sh\necho {{project-name}}\n", "executable": true }
+        ],
+        "dependencies": { "express": "^4.21.2" },
+        "scripts": { "start": "node src/main.js" }
+    }"##;
+
+    fn library_package_json() -> PackageJson {
+        create_package_json(PackageConfig {
+            name: "my-lib".to_string(),
+            project_type: ProjectType::Library,
+            version: None,
+            author: None,
+            license: None,
+            node: None,
+        })
+    }
+
+    fn invalid_field_of(package: &PackageJson) -> String {
+        match package.validate() {
+            Err(Error::PackageJsonCreation(message)) => message,
+            other => panic!("expected an invalid field, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validate_package_json() {
+        let mut package = library_package_json();
+        assert!(package.validate().is_ok());
+
+        // Long values are shortened in the message
+        package.main = Some("/**\n * My library\n */\nexport function hello() {}\n".to_string());
+        let message = invalid_field_of(&package);
+        assert!(message.starts_with("field `main` is invalid"));
+        assert!(message.contains("'/**...'"));
+
+        for main in [
+            "/src/lib.js",
+            "../lib.js",
+            "src/../../lib.js",
+            "C:\\lib.js",
+            "c:/lib.js",
+            "\\\\server\\lib.js",
+            "",
+        ] {
+            package.main = Some(main.to_string());
+            assert!(invalid_field_of(&package).contains("`main`"), "{}", main);
+        }
+        // Anything else is a path a package can contain
+        for main in [
+            "./src/lib.js",
+            "src/(app)/page.js",
+            "lib/#internal.js",
+            "src/it's.js",
+            "src/..hidden/lib.js",
+        ] {
+            package.main = Some(main.to_string());
+            assert!(package.validate().is_ok(), "{}", main);
+        }
+        package.main = Some("./src/lib.js".to_string());
+
+        package.bin = Some(HashMap::from([(
+            "cli".to_string(),
+            "/usr/bin/env node".to_string(),
+        )]));
+        assert!(invalid_field_of(&package).contains("`bin.cli`"));
+        package.bin = None;
+
+        // Fields merged into the document are checked too
+        package
+            .inherited
+            .insert("bin".to_string(), "../cli.js".into());
+        assert!(invalid_field_of(&package).contains("`bin`"));
+        package.inherited.remove("bin");
+        package.inherited.insert("module".to_string(), 1.into());
+        assert!(invalid_field_of(&package).contains("`module` is invalid: must be a path"));
+        package.inherited.remove("module");
+
+        package.version = "1.0".to_string();
+        assert!(invalid_field_of(&package).contains("`version`"));
+        package.version = "1.0.0-rc.1".to_string();
+
+        package.name = "My Lib".to_string();
+        assert!(invalid_field_of(&package).contains("`name`"));
+        package.name = "@acme/my-lib".to_string();
+
+        package.inherited.insert("type".to_string(), "esm".into());
+        assert!(invalid_field_of(&package).contains("`type`"));
+        package
+            .inherited
+            .insert("type".to_string(), "module".into());
+        package
+            .inherited
+            .insert("types".to_string(), "src/lib.d.ts".into());
+        assert!(package.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_package_json_exports() {
+        let mut package = library_package_json();
+        let mut check = |exports: serde_json::Value| {
+            package.inherited.insert("exports".to_string(), exports);
+            package.validate().map_err(|e| e.to_string())
+        };
+
+        assert!(check(serde_json::json!("./src/lib.js")).is_ok());
+        assert!(check(serde_json::json!({
+            ".": {"import": "./src/lib.mjs", "require": "./src/lib.js"},
+            "./package.json": "./package.json",
+            "./features/*": "./src/features/*.js"
+        }))
+        .is_ok());
+        assert!(check(serde_json::json!({"import": "./src/lib.js"})).is_ok());
+
+        // `main` and the `.` export disagree
+        let error = check(serde_json::json!({".": "./dist/index.js"})).unwrap_err();
+        assert!(error.contains("field `exports` is invalid"));
+
+        let error =
+            check(serde_json::json!({".": "./src/lib.js", "./utils": "src/utils.js"})).unwrap_err();
+        assert!(error.contains(r#"field `exports["./utils"]` is invalid"#));
+
+        let error = check(serde_json::json!({".": 1})).unwrap_err();
+        assert!(error.contains(r#"field `exports["."]` is invalid"#));
+    }
+
+    fn create_template_dir(dir: &Path) {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join(TEMPLATE_MANIFEST), MANIFEST).unwrap();
+        fs::write(
+            dir.join("src/main.js"),
+            "console.log('{{project-name}}');\n",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_load_template_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        create_template_dir(temp_dir.path());
+
+        let template = TemplateConfig::load(temp_dir.path()).unwrap();
+        assert_eq!(template.name, "web");
+        assert!(template.supports(ProjectType::Binary));
+        assert!(!template.supports(ProjectType::Library));
+
+        let files = template
+            .render(&TemplateContext::new("my-app", ProjectType::Binary), &[])
+            .unwrap();
+        assert_eq!(
+            files[0].content.as_deref(),
+            Some("console.log('my-app');\n")
+        );
+        assert_eq!(files[1].path, "bin/my-app");
+        assert!(files[1].executable);
+
+        // Scoped names keep their scope in contents but not in paths
+        let files = template
+            .render(
+                &TemplateContext::new("@acme/my-app", ProjectType::Binary),
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            files[0].content.as_deref(),
+            Some("console.log('@acme/my-app');\n")
+        );
+        assert_eq!(files[1].path, "bin/my-app");
+    }
+
+    fn context() -> TemplateContext {
+        TemplateContext {
+            project_name: "@acme/app".to_string(),
+            project_type: ProjectType::Library,
+            description: "An app".to_string(),
+            author: "Jane Doe".to_string(),
+            license: "MIT".to_string(),
+            year: 2025,
+            node_version: "22.14.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_substitute() {
+        let context = context();
+        assert_eq!(
+            context
+                .substitute(
+                    "{{project-name}} ({{ project-type }}): {{project-description}}\n\
+                     (c) {{year}} {{author}}, {{license}}, node {{node-version}}",
+                    false
+                )
+                .unwrap(),
+            "@acme/app (library): An app\n(c) 2025 Jane Doe, MIT, node 22.14.0"
+        );
+        assert_eq!(
+            context
+                .substitute("docs/{{project-name}}.md", true)
+                .unwrap(),
+            "docs/app.md"
+        );
+
+        // Escaped and unterminated braces are kept
+        assert_eq!(
+            context
+                .substitute(r"\{{literal}} and {{project-name}} {{", false)
+                .unwrap(),
+            "{{literal}} and @acme/app {{"
+        );
+
+        assert_eq!(
+            context.substitute("hello {{name}}", false),
+            Err("{{name}}".to_string())
+        );
+
+        let substitutions = context.substitutions();
+        assert_eq!(substitutions.len(), PLACEHOLDERS.len());
+        assert_eq!(substitutions["{{project-name}}"], "@acme/app");
+        assert_eq!(substitutions["{{year}}"], "2025");
+    }
+
+    #[test]
+    fn test_substitute_conditionals() {
+        let mut context = context();
+        let text = "import x from 'x';\n\
+                    {{#if lib}}\n\
+                    export default {{project-name}};\n\
+                    {{else}}\n\
+                    x();\n\
+                    {{/if}}\n\
+                    // {{#if author}}by {{author}}{{/if}}{{#if binary}}!{{/if}}\n";
+        assert_eq!(
+            context.substitute(text, false).unwrap(),
+            "import x from 'x';\nexport default @acme/app;\n// by Jane Doe\n"
+        );
+
+        context.project_type = ProjectType::Binary;
+        context.author = String::new();
+        assert_eq!(
+            context.substitute(text, false).unwrap(),
+            "import x from 'x';\nx();\n// !\n"
+        );
+
+        // Blocks nest, and the skipped branch is still checked
+        assert_eq!(
+            context
+                .substitute("{{#if lib}}{{#if binary}}a{{/if}}{{else}}b{{/if}}", false)
+                .unwrap(),
+            "b"
+        );
+        assert_eq!(
+            context.substitute("{{#if lib}}{{name}}{{/if}}", false),
+            Err("{{name}}".to_string())
+        );
+        assert_eq!(
+            context.substitute("{{#if typescript}}{{/if}}", false),
+            Err("{{typescript}}".to_string())
+        );
+        assert_eq!(
+            context.substitute("{{#if lib}}open", false),
+            Err("{{#if lib}}".to_string())
+        );
+        assert_eq!(
+            context.substitute("{{else}}", false),
+            Err("{{else}}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_render_conditional_files() {
+        let template: TemplateConfig = serde_json::from_str(
+            r##"{"name": "t", "files": [
+                {"path": "bin/{{project-name}}.js__if_binary", "content": "#!/usr/bin/env node"},
+                {"path": "src/index.d.ts__if_lib", "content": "export {};"},
+                {"path": "CLI.md", "content": "{{#if binary}}\n# CLI\n{{/if}}\n"},
+                {"path": ".keep", "content": ""},
+                {"path": "broken.js", "content": "{{#if lib}}"}
+            ]}"##,
+        )
+        .unwrap();
+
+        let err = template.render(&context(), &[]).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unbalanced template block '{{#if lib}}' in broken.js"));
+
+        let mut template = template;
+        template.files.pop();
+        let paths = |context: &TemplateContext| -> Vec<String> {
+            template
+                .render(context, &[])
+                .unwrap()
+                .into_iter()
+                .map(|file| file.path)
+                .collect()
+        };
+        assert_eq!(paths(&context()), vec!["src/index.d.ts", ".keep"]);
+
+        let mut binary = context();
+        binary.project_type = ProjectType::Binary;
+        assert_eq!(paths(&binary), vec!["bin/app.js", "CLI.md", ".keep"]);
+    }
+
+    #[test]
+    fn test_render_unknown_variable() {
+        let template: TemplateConfig = serde_json::from_str(
+            r##"{"name": "t", "files": [{"path": "README.md", "content": "# {{title}}"}]}"##,
+        )
+        .unwrap();
+
+        let err = template.render(&context(), &[]).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("'{{title}}' in README.md"));
+    }
+
+    #[test]
+    fn test_load_template_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("template.tar.gz");
+        let manifest = r##"{
+            "name": "web",
+            "dependencies": { "express": "^4.21.2" },
+            "files": [
+                { "path": "src/main.js" },
+                { "path": "public/favicon.png" },
+                { "path": "bin/{{project-name}}.sh" }
+            ]
+        }"##;
+        let favicon: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR{{project-name}}";
+
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            fs::File::create(&archive_path).unwrap(),
+            Compression::default(),
+        ));
+        for (path, content, mode) in [
+            ("web/template.json", manifest.as_bytes(), 0o644),
+            ("web/src/main.js", b"main();\n".as_slice(), 0o644),
+            ("web/public/favicon.png", favicon, 0o644),
+            (
+                "web/bin/{{project-name}}.sh",
+                b"#!/bin/sh\n".as_slice(),
+                0o755,
+            ),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(mode);
+            header.set_cksum();
+            builder.append_data(&mut header, path, content).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let template = TemplateConfig::load(&archive_path).unwrap();
+        assert_eq!(template.files[0].content.as_deref(), Some("main();\n"));
+        assert!(!template.files[0].executable);
+        assert_eq!(template.dependencies["express"], "^4.21.2");
+
+        // Binary files skip rendering; executable entries stay executable
+        let files = template.render(&context(), &[]).unwrap();
+        assert_eq!(files[1].content, None);
+        assert_eq!(files[1].bytes.as_deref(), Some(favicon));
+        assert_eq!(files[2].path, "bin/app.sh");
+        assert!(files[2].executable);
+
+        let root = temp_dir.path().join("project");
+        utils::write_template_files(&root, &files).unwrap();
+        assert_eq!(fs::read(root.join("public/favicon.png")).unwrap(), favicon);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_load_template_directory_modes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::write(
+            dir.join(TEMPLATE_MANIFEST),
+            r#"{"name": "t", "files": [{ "path": "run.sh" }, { "path": "font.woff2" }]}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(dir.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.join("font.woff2"), [0x77, 0x4f, 0x46, 0x32, 0xff, 0x00]).unwrap();
+
+        let template = TemplateConfig::load(dir).unwrap();
+        assert!(template.files[0].executable);
+        assert_eq!(template.files[0].content.as_deref(), Some("#!/bin/sh\n"));
+        assert!(!template.files[1].executable);
+        assert_eq!(
+            template.files[1].bytes.as_deref(),
+            Some([0x77, 0x4f, 0x46, 0x32, 0xff, 0x00].as_slice())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_load_template_directory_symlinks() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("template");
+        fs::create_dir_all(dir.join("scripts")).unwrap();
+        fs::write(
+            dir.join(TEMPLATE_MANIFEST),
+            r#"{"name": "t", "files": [{ "path": "bin/run.sh" }]}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("scripts/run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(
+            dir.join("scripts/run.sh"),
+            fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+
+        // A link inside the template is copied as the file it points to,
+        // executable bit included
+        symlink("scripts", dir.join("bin")).unwrap();
+        let template = TemplateConfig::load(&dir).unwrap();
+        assert_eq!(template.files[0].content.as_deref(), Some("#!/bin/sh\n"));
+        assert!(template.files[0].executable);
+
+        // A link leading out of the template is refused
+        fs::remove_file(dir.join("bin")).unwrap();
+        fs::create_dir(dir.join("bin")).unwrap();
+        fs::write(temp_dir.path().join("secret"), "token").unwrap();
+        symlink("../../secret", dir.join("bin/run.sh")).unwrap();
+        let err = TemplateConfig::load(&dir).unwrap_err().to_string();
+        assert!(err.contains("outside the template"), "{}", err);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_load_template_directory_junction() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("template");
+        fs::create_dir_all(dir.join("scripts")).unwrap();
+        fs::write(
+            dir.join(TEMPLATE_MANIFEST),
+            r#"{"name": "t", "files": [{ "path": "bin/run.cmd" }]}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("scripts/run.cmd"), "@echo off\r\n").unwrap();
+        // Junctions need no privilege, unlike symlinks
+        let junction = |link: &Path, target: &Path| {
+            let status = std::process::Command::new("cmd")
+                .args(["/C", "mklink", "/J"])
+                .arg(link)
+                .arg(target)
+                .stdout(std::process::Stdio::null())
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+
+        // A junction inside the template is followed like a symlink
+        junction(&dir.join("bin"), &dir.join("scripts"));
+        let template = TemplateConfig::load(&dir).unwrap();
+        assert_eq!(template.files[0].content.as_deref(), Some("@echo off\r\n"));
+
+        // A junction leading out of the template is refused
+        fs::remove_dir(dir.join("bin")).unwrap();
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir(&outside).unwrap();
+        fs::write(outside.join("run.cmd"), "secret").unwrap();
+        junction(&dir.join("bin"), &outside);
+        let err = TemplateConfig::load(&dir).unwrap_err().to_string();
+        assert!(err.contains("outside the template"), "{}", err);
+    }
+
+    #[test]
+    fn test_load_template_archive_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("template.tar.gz");
+        let write_archive = |links: &[(&str, &str)]| {
+            let manifest = r#"{"name": "t", "files": [{ "path": "bin/run.sh" }]}"#;
+            let mut builder = tar::Builder::new(GzEncoder::new(
+                fs::File::create(&archive_path).unwrap(),
+                Compression::default(),
+            ));
+            for (path, content, mode) in [
+                ("t/template.json", manifest.as_bytes(), 0o644),
+                ("t/scripts/run.sh", b"#!/bin/sh\n".as_slice(), 0o755),
+            ] {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(content.len() as u64);
+                header.set_mode(mode);
+                header.set_cksum();
+                builder.append_data(&mut header, path, content).unwrap();
+            }
+            for (path, target) in links {
+                let mut header = tar::Header::new_gnu();
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_size(0);
+                builder.append_link(&mut header, path, target).unwrap();
+            }
+            builder.into_inner().unwrap().finish().unwrap();
+        };
+
+        // A link to the file, or to a directory above it
+        for links in [
+            [("t/bin/run.sh", "../scripts/run.sh")],
+            [("t/bin", "scripts")],
+        ] {
+            write_archive(&links);
+            let template = TemplateConfig::load(&archive_path).unwrap();
+            assert_eq!(template.files[0].content.as_deref(), Some("#!/bin/sh\n"));
+            assert!(template.files[0].executable);
+        }
+
+        for links in [
+            [("t/bin/run.sh", "../../../etc/passwd")],
+            [("t/bin/run.sh", "/etc/passwd")],
+            [("t/bin", "../..")],
+            [("t/bin", "..")],
+        ] {
+            write_archive(&links);
+            let err = TemplateConfig::load(&archive_path).unwrap_err().to_string();
+            assert!(err.contains("outside the template"), "{}", err);
+        }
+
+        for links in [[("t/bin/run.sh", "run.sh")], [("t/bin", "bin")]] {
+            write_archive(&links);
+            let err = TemplateConfig::load(&archive_path).unwrap_err().to_string();
+            assert!(err.contains("more than"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_archive_limits() {
+        fn header(path: &str, entry_type: tar::EntryType, size: u64) -> tar::Header {
+            // Set the name directly, as `set_path` refuses `..` and absolute paths
+            let mut header = tar::Header::new_gnu();
+            header.as_gnu_mut().unwrap().name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_entry_type(entry_type);
+            header.set_size(size);
+            header.set_mode(0o644);
+            header.set_cksum();
+            header
+        }
+        fn read(entries: &[tar::Header], limits: &ArchiveLimits) -> String {
+            let manifest = br#"{"name": "t"}"#;
+            let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+            let mut manifest_header = header(TEMPLATE_MANIFEST, tar::EntryType::Regular, 0);
+            manifest_header.set_size(manifest.len() as u64);
+            manifest_header.set_cksum();
+            builder
+                .append(&manifest_header, manifest.as_slice())
+                .unwrap();
+            for entry in entries {
+                builder.append(entry, std::io::empty()).unwrap();
+            }
+            let archive = builder.into_inner().unwrap().finish().unwrap();
+            match TemplateConfig::read_archive_from(
+                archive.as_slice(),
+                Path::new("t.tar.gz"),
+                limits,
+            ) {
+                Ok(_) => String::new(),
+                Err(problem) => problem.message,
+            }
+        }
+        let limits = ArchiveLimits::default();
+
+        assert_eq!(read(&[], &limits), "");
+        for path in ["../evil.sh", "/etc/cron.d/evil", "src/../../evil.sh"] {
+            let message = read(&[header(path, tar::EntryType::Regular, 0)], &limits);
+            assert!(message.contains("outside the archive"), "{}", message);
+        }
+        for entry_type in [
+            tar::EntryType::Fifo,
+            tar::EntryType::Char,
+            tar::EntryType::Block,
+        ] {
+            let message = read(&[header("dev", entry_type, 0)], &limits);
+            assert!(message.contains("a device or FIFO"), "{}", message);
+        }
+
+        // A declared size over the budget is refused before reading the entry
+        let message = read(
+            &[header("big.bin", tar::EntryType::Regular, 1 << 40)],
+            &limits,
+        );
+        assert!(
+            message.contains("unpacks to more than 256.0 MiB"),
+            "{}",
+            message
+        );
+        assert!(message.contains(ARCHIVE_MAX_BYTES_ENV), "{}", message);
+
+        let few = ArchiveLimits {
+            max_entries: 2,
+            ..limits
+        };
+        let dirs = [
+            header("a/", tar::EntryType::Directory, 0),
+            header("b/", tar::EntryType::Directory, 0),
+        ];
+        let message = read(&dirs, &few);
+        assert!(message.contains("more than 2 entries"), "{}", message);
+        assert_eq!(read(&dirs[..1], &few), "");
+    }
+
+    #[test]
+    fn test_validate_template_paths() {
+        for path in [
+            "../outside.js",
+            "/etc/passwd",
+            "src/../../x.js",
+            "",
+            "package.json",
+        ] {
+            let manifest = format!(
+                r#"{{"name": "bad", "files": [{{"path": "{}", "content": ""}}]}}"#,
+                path
+            );
+            let temp_dir = TempDir::new().unwrap();
+            fs::write(temp_dir.path().join(TEMPLATE_MANIFEST), manifest).unwrap();
+            assert!(
+                matches!(
+                    TemplateConfig::load(temp_dir.path()),
+                    Err(Error::Template { .. })
+                ),
+                "Should reject {}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_template_features() {
+        let template: TemplateConfig = serde_json::from_str(
+            r#"{
+                "name": "web",
+                "files": [
+                    {"path": "src/main.js", "content": "main();"},
+                    {"path": ".eslintrc.json", "content": "{}"},
+                    {"path": "eslint.config.js", "content": "export default [];"}
+                ],
+                "features": {
+                    "eslint": {
+                        "files": [".eslintrc.json", "eslint.config.js"],
+                        "dev_dependencies": {"eslint": "^9.0.0"}
+                    },
+                    "flat": {"files": ["eslint.config.js"]}
+                }
+            }"#,
+        )
+        .unwrap();
+        template.validate().unwrap();
+
+        let paths = |features: &[&str]| -> Vec<String> {
+            let features: Vec<String> = features.iter().map(|f| f.to_string()).collect();
+            template
+                .render(&context(), &features)
+                .unwrap()
+                .into_iter()
+                .map(|file| file.path)
+                .collect()
+        };
+        assert_eq!(paths(&[]), vec!["src/main.js"]);
+        assert_eq!(paths(&["flat"]), vec!["src/main.js", "eslint.config.js"]);
+        assert_eq!(paths(&["eslint"]).len(), 3);
+
+        let err = template
+            .render(&context(), &["prettier".to_string()])
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("has no feature 'prettier'; available features: eslint, flat"));
+
+        let mut package = create_package_json(PackageConfig {
+            name: "my-app".to_string(),
+            project_type: ProjectType::Binary,
+            version: None,
+            author: None,
+            license: None,
+            node: None,
+        });
+        package.merge_template(&template, &["eslint".to_string()]);
+        assert_eq!(package.dev_dependencies["eslint"], "^9.0.0");
+
+        // A feature can only list files the template ships
+        let mut broken = template.clone();
+        broken.features.get_mut("flat").unwrap().files = vec![".prettierrc".to_string()];
+        let err = broken.validate().unwrap_err();
+        assert!(err.to_string().contains(
+            "Feature 'flat' of template 'web' lists '.prettierrc', which is not one of the template files"
+        ));
+    }
+
+    #[test]
+    fn test_template_min_cargonode_version() {
+        let mut template: TemplateConfig =
+            serde_json::from_str(r#"{"name": "web", "min_cargonode_version": "0.1.0"}"#).unwrap();
+        assert!(template.validate().is_ok());
+
+        template.min_cargonode_version = Some("999.0.0".to_string());
+        let err = template.validate().unwrap_err();
+        assert!(err.to_string().contains(&format!(
+            "Template 'web' requires cargonode >= 999.0.0, but this is cargonode {}",
+            env!("CARGO_PKG_VERSION")
+        )));
+
+        template.min_cargonode_version = Some("two".to_string());
+        let err = template.validate().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("invalid min_cargonode_version 'two'"));
+    }
+
+    #[test]
+    fn test_merge_template() {
+        let temp_dir = TempDir::new().unwrap();
+        create_template_dir(temp_dir.path());
+        let template = TemplateConfig::load(temp_dir.path()).unwrap();
+
+        let mut package = create_package_json(PackageConfig {
+            name: "my-app".to_string(),
+            project_type: ProjectType::Binary,
+            version: None,
+            author: None,
+            license: None,
+            node: None,
+        });
+        package.merge_template(&template, &[]);
+
+        let json = serialize_package_json(&package).unwrap();
+        assert!(json.contains(r#""express": "^4.21.2""#));
+        assert!(json.contains(r#""start": "node src/main.js""#));
+        assert!(!json.contains("devDependencies"));
+    }
+
+    #[test]
+    fn test_template_variants() {
+        assert_eq!(
+            template_variants(),
+            vec![
+                "javascript-app",
+                "javascript-lib",
+                "typescript-app",
+                "typescript-lib"
+            ]
+        );
+        for name in template_variants() {
+            let template = TemplateConfig::load_variant(name).unwrap();
+            assert_eq!(template.name, name);
+            let files = template.render(&context(), &[]).unwrap();
+            let content = |path: &str| {
+                files
+                    .iter()
+                    .find(|file| file.path == path)
+                    .and_then(|file| file.content.clone())
+                    .unwrap_or_else(|| panic!("{} has no {}", name, path))
+            };
+
+            // Each variant is a runnable scaffold: an entry, a test of it and,
+            // for TypeScript, the scripts that compile both
+            let (stem, extension) = match (name.ends_with("-app"), name.starts_with("typescript")) {
+                (true, true) => ("main", "ts"),
+                (true, false) => ("main", "js"),
+                (false, true) => ("lib", "ts"),
+                (false, false) => ("lib", "js"),
+            };
+            let entry = content(&format!("src/{}.{}", stem, extension));
+            let test = if extension == "ts" {
+                content(&format!("src/{}.test.ts", stem))
+            } else {
+                content(&format!("test/{}.test.js", stem))
+            };
+            assert!(test.contains("node:test"), "{}", name);
+            if stem == "main" {
+                assert!(entry.starts_with("#!/usr/bin/env node\n"), "{}", name);
+                assert!(!entry.contains("import.meta.resolve()"), "{}", name);
+            }
+            if extension == "ts" {
+                content("tsconfig.json");
+                let main = template.main.as_deref().unwrap();
+                assert_eq!(main, format!("dist/{}.js", stem));
+                assert_eq!(template.scripts["build"], "tsc");
+                assert!(template.scripts["test"].starts_with("tsc && "), "{}", name);
+                assert!(template.dev_dependencies.contains_key("@types/node"));
+                if let Some(start) = template.scripts.get("start") {
+                    assert_eq!(start, &format!("node {}", main));
+                }
+            } else {
+                assert!(template.main.is_none(), "{}", name);
+                assert!(template.scripts.is_empty(), "{}", name);
+            }
+        }
+
+        // Shared files are packed into the variants that list them
+        let typescript = TemplateConfig::load_variant("typescript-lib").unwrap();
+        let tsconfig = typescript
+            .files
+            .iter()
+            .find(|file| file.path == "tsconfig.json")
+            .unwrap();
+        assert!(tsconfig
+            .content
+            .as_deref()
+            .unwrap()
+            .contains("\"outDir\": \"dist\""));
+
+        let Err(Error::Template { message }) = TemplateConfig::load_variant("rust-app") else {
+            panic!("expected an unknown variant to be rejected");
+        };
+        assert!(message.contains("'rust-app'"), "{}", message);
+        assert!(message.contains("javascript-app, javascript-lib, typescript-app, typescript-lib"));
+    }
+
+    #[test]
+    fn test_merge_template_main() {
+        let template = TemplateConfig::load_variant("typescript-app").unwrap();
+        let mut package = create_package_json(PackageConfig {
+            name: "my-app".to_string(),
+            project_type: ProjectType::Binary,
+            version: None,
+            author: None,
+            license: None,
+            node: None,
+        });
+        package.merge_template(&template, &[]);
+
+        assert_eq!(package.main.as_deref(), Some("dist/main.js"));
+        assert_eq!(
+            package.bin,
+            Some(HashMap::from([(
+                "my-app".to_string(),
+                "dist/main.js".to_string()
+            )]))
+        );
+        assert!(package.dev_dependencies.contains_key("typescript"));
+    }
+
     #[test]
     fn test_create_package_json_binary() {
         let config = PackageConfig {
             name: "test-bin".to_string(),
             project_type: ProjectType::Binary,
             version: None,
+            author: None,
+            license: None,
+            node: None,
         };
 
         let package = create_package_json(config);
@@ -134,6 +2635,9 @@ mod tests {
             name: "test-lib".to_string(),
             project_type: ProjectType::Library,
             version: Some("1.0.0".to_string()),
+            author: None,
+            license: None,
+            node: None,
         };
 
         let package = create_package_json(config);
@@ -151,14 +2655,75 @@ mod tests {
             name: "test-pkg".to_string(),
             project_type: ProjectType::Library,
             version: None,
+            author: None,
+            license: Some("MIT".to_string()),
+            node: Some(">=20".to_string()),
         };
 
         let package = create_package_json(config);
         let json = serialize_package_json(&package).expect("Should serialize successfully");
+        let manifest: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(manifest["engines"]["node"], ">=20");
 
         assert!(json.contains(r#""name": "test-pkg""#));
         assert!(json.contains(r#""version": "0.1.0""#));
         assert!(json.contains(r#""main": "src/lib.js""#));
+        assert!(json.contains(r#""license": "MIT""#));
         assert!(!json.contains(r#""bin""#));
+        assert!(!json.contains(r#""author""#));
+    }
+
+    #[test]
+    fn test_canonical_license() {
+        assert_eq!(canonical_license("mit"), "MIT");
+        assert_eq!(canonical_license(" apache-2.0 "), "Apache-2.0");
+        assert_eq!(canonical_license("unlicensed"), "UNLICENSED");
+        assert_eq!(canonical_license("GPL-3.0-only"), "GPL-3.0-only");
+    }
+
+    #[test]
+    fn test_project_files() {
+        let file = |files: &[TemplateFile], path: &str| {
+            files
+                .iter()
+                .find(|file| file.path == path)
+                .and_then(|file| file.content.clone())
+        };
+
+        for license in ["MIT", "Apache-2.0", "ISC"] {
+            let metadata = TemplateContext {
+                license: license.to_string(),
+                ..context()
+            };
+            let files = metadata.project_files();
+            let text = file(&files, "LICENSE").unwrap();
+            assert!(text.contains("2025 Jane Doe"), "{license}: {text}");
+            assert!(file(&files, "README.md")
+                .unwrap()
+                .contains(&format!("the {} license", license)));
+            assert!(file(&files, ".editorconfig").is_some());
+            assert_eq!(
+                file(&files, ".gitattributes"),
+                Some(GITATTRIBUTES_CONTENT.to_string())
+            );
+        }
+
+        // Without a known text only the README names the license
+        let metadata = TemplateContext {
+            license: "GPL-3.0-only".to_string(),
+            author: String::new(),
+            ..context()
+        };
+        let files = metadata.project_files();
+        assert!(file(&files, "LICENSE").is_none());
+        assert!(file(&files, "README.md").unwrap().contains("GPL-3.0-only"));
+
+        let metadata = TemplateContext {
+            author: String::new(),
+            ..context()
+        };
+        assert!(file(&metadata.project_files(), "LICENSE")
+            .unwrap()
+            .contains("Copyright (c) 2025 The @acme/app authors"));
     }
 }
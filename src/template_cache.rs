@@ -0,0 +1,738 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use crate::template::TEMPLATE_MANIFEST;
+use crate::{process, progress, prompt, utils, Result};
+
+/// Timeout applied to template downloads
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Largest template archive that will be downloaded
+const MAX_ARCHIVE_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Whether a `--template` argument refers to a remote archive
+pub fn is_remote(template: &str) -> bool {
+    template.starts_with("https://") || template.starts_with("http://")
+}
+
+/// URL schemes a git template repository can be cloned from
+const GIT_SCHEMES: [&str; 6] = ["https://", "http://", "ssh://", "git://", "file://", "git@"];
+
+/// A template kept in a git repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitTemplate {
+    /// URL the repository is cloned from
+    pub url: String,
+    /// Branch or tag to check out, or the default branch
+    pub reference: Option<String>,
+}
+
+impl GitTemplate {
+    /// Parse a `--template` argument naming a git repository
+    ///
+    /// Accepts `gh:user/repo` for GitHub and URLs ending in `.git`, either
+    /// optionally followed by `#branch-or-tag`.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The `--template` argument
+    ///
+    /// # Returns
+    ///
+    /// * `Option<GitTemplate>` - The repository, or `None` for other templates
+    pub fn parse(template: &str) -> Option<Self> {
+        let (location, reference) = match template.split_once('#') {
+            Some((location, reference)) if !reference.is_empty() => {
+                (location, Some(reference.to_string()))
+            }
+            Some((location, _)) => (location, None),
+            None => (template, None),
+        };
+
+        let url = if let Some(repository) = location.strip_prefix("gh:") {
+            let (user, repo) = repository.split_once('/')?;
+            if user.is_empty() || repo.is_empty() || repo.contains('/') {
+                return None;
+            }
+            format!(
+                "https://github.com/{}/{}.git",
+                user,
+                repo.trim_end_matches(".git")
+            )
+        } else if GIT_SCHEMES
+            .iter()
+            .any(|scheme| location.starts_with(scheme))
+            && location.trim_end_matches('/').ends_with(".git")
+        {
+            location.to_string()
+        } else {
+            return None;
+        };
+        Some(Self { url, reference })
+    }
+
+    /// The repository and reference, as used to key the cache
+    fn source(&self) -> String {
+        match &self.reference {
+            Some(reference) => format!("{}#{}", self.url, reference),
+            None => self.url.clone(),
+        }
+    }
+}
+
+/// Hex SHA-256 digest of `content`
+fn sha256_hex(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Validate a pinned `--sha256` digest and bring it to lowercase
+fn normalize_digest(digest: &str) -> Result<String> {
+    let digest = digest.trim().to_ascii_lowercase();
+    if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::Template {
+            message: format!(
+                "'{}' is not a SHA-256 digest; expected 64 hexadecimal characters",
+                digest
+            ),
+        });
+    }
+    Ok(digest)
+}
+
+/// Resolve a `--template` argument to a local path
+///
+/// Local paths are returned as-is; git repositories and archive URLs are
+/// fetched through the default template cache.
+///
+/// # Arguments
+///
+/// * `template` - Path or URL of the template
+/// * `offline` - Use only a cached copy of remote templates
+/// * `sha256` - Digest the archive must have; only archive URLs can be pinned
+///
+/// # Returns
+///
+/// * `Result<PathBuf>` - Path of the template directory or archive
+pub fn resolve_template(template: &Path, offline: bool, sha256: Option<&str>) -> Result<PathBuf> {
+    let argument = template.to_str().unwrap_or_default();
+    let remote_archive = GitTemplate::parse(argument).is_none() && is_remote(argument);
+    if sha256.is_some() && !remote_archive {
+        return Err(Error::Template {
+            message: format!(
+                "`--sha256` only applies to template archive URLs, not '{}'",
+                template.display()
+            ),
+        });
+    }
+
+    if let Some(git) = GitTemplate::parse(argument) {
+        return TemplateCache::default().fetch_git(&git, offline);
+    }
+    match remote_archive {
+        true => {
+            let expected = sha256.map(normalize_digest).transpose()?;
+            TemplateCache::default().fetch(argument, offline, expected.as_deref())
+        }
+        false => Ok(template.to_path_buf()),
+    }
+}
+
+/// Fail unless `actual` is the digest `url` was pinned to, if it was pinned
+fn verify_digest(url: &str, expected: Option<&str>, actual: &str) -> Result<()> {
+    match expected {
+        Some(expected) if expected != actual => Err(Error::ChecksumMismatch {
+            url: url.to_string(),
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// On-disk cache of downloaded template archives
+///
+/// Each archive is stored as `<key>.tar.gz` next to a `<key>.sha256` file
+/// holding its checksum, where the key is derived from the template URL.
+pub struct TemplateCache {
+    /// Directory holding the cached archives
+    dir: PathBuf,
+}
+
+impl Default for TemplateCache {
+    fn default() -> Self {
+        Self::new(&utils::user_cache_dir().join("templates"))
+    }
+}
+
+impl TemplateCache {
+    /// Create a cache stored in `dir`
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory holding the cached archives
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A new TemplateCache instance
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    fn key(url: &str) -> String {
+        format!("{:x}", Sha256::digest(url.as_bytes()))[..16].to_string()
+    }
+
+    fn archive_path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.tar.gz", Self::key(url)))
+    }
+
+    fn checksum_path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.sha256", Self::key(url)))
+    }
+
+    /// Get the cached archive for `url` if it exists and matches its checksum
+    pub fn cached(&self, url: &str) -> Option<PathBuf> {
+        self.cached_with_digest(url).map(|(path, _)| path)
+    }
+
+    /// The cached archive for `url` and its digest, re-hashed on every read
+    fn cached_with_digest(&self, url: &str) -> Option<(PathBuf, String)> {
+        let path = self.archive_path(url);
+        let expected = fs::read_to_string(self.checksum_path(url)).ok()?;
+        let digest = sha256_hex(&fs::read(&path).ok()?);
+
+        (digest == expected.trim()).then_some((path, digest))
+    }
+
+    /// Get the archive for `url`, downloading it unless offline
+    ///
+    /// When the download fails because the network is unreachable, a cached
+    /// copy is used instead, with a warning. Without a pinned digest, the
+    /// digest of a fresh download is printed so that it can be pinned.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - URL of a `.tar.gz` template archive
+    /// * `offline` - Skip the download and use only the cache
+    /// * `sha256` - Lowercase hex digest the archive must have, if pinned
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PathBuf>` - Path of the archive in the cache, or
+    ///   `Error::ChecksumMismatch` if the archive is not the pinned one
+    pub fn fetch(&self, url: &str, offline: bool, sha256: Option<&str>) -> Result<PathBuf> {
+        let use_cached = |(path, digest): (PathBuf, String)| {
+            verify_digest(url, sha256, &digest)?;
+            Ok(path)
+        };
+        if offline {
+            let cached = self.cached_with_digest(url).ok_or_else(|| Error::Network {
+                message: format!("No cached copy of template '{}' is available offline", url),
+            })?;
+            return use_cached(cached);
+        }
+
+        match download(url) {
+            Ok(content) => {
+                let digest = sha256_hex(&content);
+                verify_digest(url, sha256, &digest)?;
+                if sha256.is_none() {
+                    progress::note(&format!(
+                        "template archive has sha256 {}; pass `--sha256 {}` to pin it",
+                        digest, digest
+                    ))?;
+                }
+                self.store(url, &content)
+            }
+            Err(Error::Network { message }) => match self.cached_with_digest(url) {
+                Some(cached) => {
+                    progress::warn(&format!(
+                        "{}; using the cached copy of the template",
+                        message
+                    ))?;
+                    use_cached(cached)
+                }
+                None => Err(Error::Network { message }),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Directory holding the checkout of a git template
+    fn checkout_path(&self, git: &GitTemplate) -> PathBuf {
+        self.dir.join("git").join(Self::key(&git.source()))
+    }
+
+    /// Get a checkout of a git template, cloning it unless offline
+    ///
+    /// The clone is shallow and uses the system git, so private repositories
+    /// work with whatever credentials git is configured with. A cached clone
+    /// is updated with a fetch rather than cloned again. When the remote
+    /// cannot be reached, a cached checkout is used instead, with a warning.
+    ///
+    /// # Arguments
+    ///
+    /// * `git` - Repository and reference of the template
+    /// * `offline` - Skip the clone and use only the cache
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PathBuf>` - Directory of the checkout, containing `template.json`
+    pub fn fetch_git(&self, git: &GitTemplate, offline: bool) -> Result<PathBuf> {
+        let checkout = self.checkout_path(git);
+        let cached = checkout.join(TEMPLATE_MANIFEST).is_file();
+        if offline {
+            return cached.then_some(checkout).ok_or_else(|| Error::Network {
+                message: format!(
+                    "No cached copy of template '{}' is available offline",
+                    git.source()
+                ),
+            });
+        }
+
+        let result = match checkout.join(".git").is_dir() {
+            true => self.fetch_into(git, &checkout).or_else(|e| match e {
+                Error::TemplateUnreachable { .. } => Err(e),
+                _ => self.clone_into(git, &checkout),
+            }),
+            false => self.clone_into(git, &checkout),
+        };
+        match result {
+            Ok(()) => {}
+            Err(Error::TemplateUnreachable { url, details }) if cached => {
+                progress::warn(&format!(
+                    "cannot reach {} ({}); using the cached copy of the template",
+                    url, details
+                ))?;
+                return Ok(checkout);
+            }
+            Err(e) => return Err(e),
+        }
+
+        if !checkout.join(TEMPLATE_MANIFEST).is_file() {
+            fs::remove_dir_all(&checkout)?;
+            return Err(Error::Template {
+                message: format!(
+                    "Template repository '{}' has no {} at its root",
+                    git.source(),
+                    TEMPLATE_MANIFEST
+                ),
+            });
+        }
+        Ok(checkout)
+    }
+
+    /// Clone `git` next to `checkout`, then replace `checkout` with it
+    fn clone_into(&self, git: &GitTemplate, checkout: &Path) -> Result<()> {
+        let parent = checkout.parent().unwrap_or(&self.dir);
+        fs::create_dir_all(parent)?;
+        let staging = checkout.with_extension("tmp");
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        let _cleanup = process::remove_on_interrupt(&staging);
+
+        let mut command = Command::new("git");
+        command.args(["clone", "--quiet", "--depth", "1"]);
+        if let Some(reference) = &git.reference {
+            command.args(["--branch", reference]);
+        }
+        command.arg(&git.url).arg(&staging);
+        if let Err(e) = run_git(git, command, "Cloning") {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+
+        if checkout.exists() {
+            fs::remove_dir_all(checkout)?;
+        }
+        fs::rename(&staging, checkout)?;
+        Ok(())
+    }
+
+    /// Fetch the latest commit of `git` into the clone at `checkout`
+    ///
+    /// Only the new objects are transferred, and the working tree is reset
+    /// to the fetched commit.
+    fn fetch_into(&self, git: &GitTemplate, checkout: &Path) -> Result<()> {
+        let reference = git.reference.as_deref().unwrap_or("HEAD");
+        let mut fetch = Command::new("git");
+        fetch
+            .arg("-C")
+            .arg(checkout)
+            .args(["fetch", "--quiet", "--depth", "1", "origin", reference]);
+        run_git(git, fetch, "Fetching")?;
+
+        for args in [
+            &["reset", "--quiet", "--hard", "FETCH_HEAD"][..],
+            &["clean", "--quiet", "-fdx"],
+        ] {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(checkout)
+                .args(args)
+                .output()?;
+            if !output.status.success() {
+                return Err(Error::Git {
+                    message: format!("Failed to update the cached clone of '{}'", git.source()),
+                    details: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn store(&self, url: &str, content: &[u8]) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.archive_path(url);
+        fs::write(&path, content)?;
+        fs::write(self.checksum_path(url), sha256_hex(content))?;
+        Ok(path)
+    }
+}
+
+/// Run a git command that talks to the remote of `git`
+///
+/// # Arguments
+///
+/// * `git` - Repository the command reaches
+/// * `command` - The git command
+/// * `label` - Label of the spinner shown while it runs
+///
+/// # Returns
+///
+/// * `Result<()>` - `Error::TemplateUnreachable` when git fails
+fn run_git(git: &GitTemplate, mut command: Command, label: &str) -> Result<()> {
+    if !prompt::is_interactive() {
+        command.env("GIT_TERMINAL_PROMPT", "0");
+    }
+
+    let spinner = progress::ProgressBar::spinner(label);
+    let output = command.output();
+    drop(spinner);
+    let output = output.map_err(|e| Error::Git {
+        message: "Git is required to use template repositories".to_string(),
+        details: e.to_string(),
+    })?;
+    if !output.status.success() {
+        return Err(Error::TemplateUnreachable {
+            url: git.source(),
+            details: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Download a template archive
+fn download(url: &str) -> Result<Vec<u8>> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(DOWNLOAD_TIMEOUT))
+        .http_status_as_error(false)
+        .build()
+        .into();
+
+    let mut response = agent.get(url).call().map_err(|e| Error::Network {
+        message: format!("Failed to download template '{}': {}", url, e),
+    })?;
+
+    let failed = |e: std::io::Error| Error::Network {
+        message: format!("Failed to download template '{}': {}", url, e),
+    };
+    match response.status().as_u16() {
+        200 => {
+            let bar = progress::ProgressBar::bytes("Downloading", response.body().content_length());
+            let mut reader = response
+                .body_mut()
+                .with_config()
+                .limit(MAX_ARCHIVE_SIZE)
+                .reader();
+            let mut content = Vec::new();
+            let mut chunk = [0; 16 * 1024];
+            loop {
+                let read = reader.read(&mut chunk).map_err(failed)?;
+                if read == 0 {
+                    break;
+                }
+                content.extend_from_slice(&chunk[..read]);
+                bar.inc(read as u64);
+            }
+            Ok(content)
+        }
+        status => Err(Error::Template {
+            message: format!("Failed to download template '{}': HTTP {}", url, status),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::test_utils::serve_once;
+
+    /// Address nothing listens on, so any request fails
+    const UNREACHABLE_TEMPLATE: &str = "http://127.0.0.1:9/template.tar.gz";
+
+    #[test]
+    fn test_is_remote() {
+        assert!(is_remote("https://example.com/t.tar.gz"));
+        assert!(is_remote("http://example.com/t.tar.gz"));
+        assert!(!is_remote("./templates/web"));
+        assert!(!is_remote("/tmp/web.tar.gz"));
+    }
+
+    #[test]
+    fn test_parse_git_template() {
+        assert_eq!(
+            GitTemplate::parse("gh:acme/web-template#v2"),
+            Some(GitTemplate {
+                url: "https://github.com/acme/web-template.git".to_string(),
+                reference: Some("v2".to_string()),
+            })
+        );
+        assert_eq!(
+            GitTemplate::parse("https://git.example.com/acme/web.git"),
+            Some(GitTemplate {
+                url: "https://git.example.com/acme/web.git".to_string(),
+                reference: None,
+            })
+        );
+        assert!(GitTemplate::parse("git@github.com:acme/web.git#main").is_some());
+        assert!(GitTemplate::parse("gh:acme").is_none());
+        assert!(GitTemplate::parse("https://example.com/t.tar.gz").is_none());
+        assert!(GitTemplate::parse("./templates/web.git").is_none());
+    }
+
+    /// Create a git repository in `dir` holding `files`, or `None` without git
+    fn create_repository(dir: &Path, files: &[(&str, &str)]) -> Option<String> {
+        utils::git_version()?;
+        fs::create_dir_all(dir).unwrap();
+        for (path, content) in files {
+            fs::write(dir.join(path), content).unwrap();
+        }
+        for args in [
+            &["init", "--quiet", "--initial-branch", "main"][..],
+            &["add", "."],
+            &[
+                "-c",
+                "user.name=Test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "--quiet",
+                "-m",
+                "Template",
+            ],
+            &["tag", "v1"],
+        ] {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(dir)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+        Some(format!("file://{}", dir.display()))
+    }
+
+    #[test]
+    fn test_fetch_git_template() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = TemplateCache::new(&temp_dir.path().join("cache"));
+        let manifest = r#"{"name": "web", "project_types": ["binary"]}"#;
+        let Some(url) = create_repository(
+            &temp_dir.path().join("web.git"),
+            &[(TEMPLATE_MANIFEST, manifest)],
+        ) else {
+            return;
+        };
+
+        let git = GitTemplate::parse(&format!("{}#v1", url)).unwrap();
+        let checkout = cache.fetch_git(&git, false).unwrap();
+        assert_eq!(
+            fs::read_to_string(checkout.join(TEMPLATE_MANIFEST)).unwrap(),
+            manifest
+        );
+
+        // Once cloned, the checkout is used offline and when unreachable
+        fs::remove_dir_all(temp_dir.path().join("web.git")).unwrap();
+        assert_eq!(cache.fetch_git(&git, true).unwrap(), checkout);
+        assert_eq!(cache.fetch_git(&git, false).unwrap(), checkout);
+
+        let other = GitTemplate::parse(&format!("{}#v2", url)).unwrap();
+        assert!(matches!(
+            cache.fetch_git(&other, false),
+            Err(Error::TemplateUnreachable { .. })
+        ));
+        assert!(matches!(
+            cache.fetch_git(&other, true),
+            Err(Error::Network { .. })
+        ));
+    }
+
+    #[test]
+    fn test_fetch_git_updates_cached_clone() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = TemplateCache::new(&temp_dir.path().join("cache"));
+        let repository = temp_dir.path().join("web.git");
+        let Some(url) =
+            create_repository(&repository, &[(TEMPLATE_MANIFEST, r#"{"name": "web"}"#)])
+        else {
+            return;
+        };
+
+        let git = GitTemplate::parse(&url).unwrap();
+        let checkout = cache.fetch_git(&git, false).unwrap();
+        // A marker inside the clone's git directory shows it is not cloned again
+        fs::write(checkout.join(".git").join("marker"), "").unwrap();
+
+        let manifest = r#"{"name": "web", "version": "2.0.0"}"#;
+        fs::write(repository.join(TEMPLATE_MANIFEST), manifest).unwrap();
+        let status = Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(["commit", "--quiet", "--all", "-m", "Update"])
+            .current_dir(&repository)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        assert_eq!(cache.fetch_git(&git, false).unwrap(), checkout);
+        assert_eq!(
+            fs::read_to_string(checkout.join(TEMPLATE_MANIFEST)).unwrap(),
+            manifest
+        );
+        assert!(checkout.join(".git").join("marker").exists());
+    }
+
+    #[test]
+    fn test_fetch_git_template_without_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = TemplateCache::new(&temp_dir.path().join("cache"));
+        let Some(url) = create_repository(
+            &temp_dir.path().join("empty.git"),
+            &[("README.md", "# Not a template")],
+        ) else {
+            return;
+        };
+
+        let git = GitTemplate::parse(&url).unwrap();
+        let Err(Error::Template { message }) = cache.fetch_git(&git, false) else {
+            panic!("expected a repository without template.json to be rejected");
+        };
+        assert!(message.ends_with("has no template.json at its root"));
+        assert!(!cache.checkout_path(&git).exists());
+    }
+
+    #[test]
+    fn test_fetch_stores_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = TemplateCache::new(temp_dir.path());
+        let (base_url, handle) = serve_once(200, b"archive bytes");
+        let url = format!("{}/web.tar.gz", base_url);
+
+        let path = cache.fetch(&url, false, None).unwrap();
+        assert!(handle.join().unwrap().starts_with("GET /web.tar.gz "));
+        assert_eq!(fs::read(&path).unwrap(), b"archive bytes");
+
+        // The stored copy is served offline without a request
+        assert_eq!(cache.fetch(&url, true, None).unwrap(), path);
+    }
+
+    #[test]
+    fn test_fetch_verifies_pinned_digest() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = TemplateCache::new(temp_dir.path());
+        let digest = sha256_hex(b"archive bytes");
+        let other = sha256_hex(b"other bytes");
+
+        let (base_url, handle) = serve_once(200, b"archive bytes");
+        let url = format!("{}/web.tar.gz", base_url);
+        let Err(Error::ChecksumMismatch {
+            expected, actual, ..
+        }) = cache.fetch(&url, false, Some(&other))
+        else {
+            panic!("expected the download to be rejected");
+        };
+        handle.join().unwrap();
+        assert_eq!((expected, actual), (other.clone(), digest.clone()));
+        // A rejected archive is never cached
+        assert!(cache.cached(&url).is_none());
+
+        let (base_url, handle) = serve_once(200, b"archive bytes");
+        let url = format!("{}/web.tar.gz", base_url);
+        let path = cache.fetch(&url, false, Some(&digest)).unwrap();
+        handle.join().unwrap();
+
+        // Cached archives are checked against the pin as well
+        assert_eq!(cache.fetch(&url, true, Some(&digest)).unwrap(), path);
+        assert!(matches!(
+            cache.fetch(&url, true, Some(&other)),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_template_digest() {
+        assert_eq!(
+            normalize_digest(&format!(" {} ", "AB".repeat(32))).unwrap(),
+            "ab".repeat(32)
+        );
+        assert!(matches!(
+            normalize_digest("abc"),
+            Err(Error::Template { .. })
+        ));
+        assert!(matches!(
+            resolve_template(Path::new("./web"), true, Some(&"ab".repeat(32))),
+            Err(Error::Template { .. })
+        ));
+        assert!(matches!(
+            resolve_template(Path::new("gh:acme/web"), true, Some(&"ab".repeat(32))),
+            Err(Error::Template { .. })
+        ));
+    }
+
+    #[test]
+    fn test_fetch_falls_back_to_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = TemplateCache::new(temp_dir.path());
+
+        assert!(matches!(
+            cache.fetch(UNREACHABLE_TEMPLATE, false, None),
+            Err(Error::Network { .. })
+        ));
+        assert!(matches!(
+            cache.fetch(UNREACHABLE_TEMPLATE, true, None),
+            Err(Error::Network { .. })
+        ));
+
+        let path = cache.store(UNREACHABLE_TEMPLATE, b"cached").unwrap();
+        assert_eq!(
+            cache.fetch(UNREACHABLE_TEMPLATE, false, None).unwrap(),
+            path
+        );
+
+        // A copy that no longer matches its checksum is not used
+        fs::write(&path, b"corrupted").unwrap();
+        assert!(cache.cached(UNREACHABLE_TEMPLATE).is_none());
+    }
+
+    #[test]
+    fn test_fetch_http_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = TemplateCache::new(temp_dir.path());
+        let (base_url, handle) = serve_once(404, "");
+
+        let result = cache.fetch(&format!("{}/missing.tar.gz", base_url), false, None);
+        handle.join().unwrap();
+        assert!(matches!(result, Err(Error::Template { .. })));
+    }
+}
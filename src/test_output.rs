@@ -0,0 +1,470 @@
+use std::time::Duration;
+
+/// Failed tests named in a summary; the rest are only counted
+const MAX_LISTED_FAILURES: usize = 20;
+
+/// Output format a test summary was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestFormat {
+    /// TAP, which `node --test` prints when its output is not a terminal
+    Tap,
+    /// Vitest's default reporter
+    Vitest,
+}
+
+/// What a test runner reported
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestSummary {
+    /// Format the summary was read from
+    pub format: TestFormat,
+    /// Number of tests that passed, if the runner printed it
+    pub passed: Option<u64>,
+    /// Number of tests that failed
+    pub failed: u64,
+    /// Number of tests that were skipped, if the runner printed it
+    pub skipped: Option<u64>,
+    /// Time the tests took, if the runner printed it
+    pub duration: Option<Duration>,
+    /// Names of the failed tests, at most [`MAX_LISTED_FAILURES`] of them
+    pub failures: Vec<String>,
+}
+
+impl TestSummary {
+    /// One line with the counts, e.g. `2 failed, 10 passed, 1 skipped in 0.31s`
+    pub fn counts(&self) -> String {
+        let mut counts = vec![format!("{} failed", self.failed)];
+        counts.extend(self.passed.map(|passed| format!("{} passed", passed)));
+        counts.extend(
+            self.skipped
+                .filter(|skipped| *skipped > 0)
+                .map(|skipped| format!("{} skipped", skipped)),
+        );
+        let mut line = counts.join(", ");
+        if let Some(duration) = self.duration {
+            line.push_str(&format!(" in {:.2}s", duration.as_secs_f64()));
+        }
+        line
+    }
+
+    /// The counts followed by the failed tests, one per line
+    pub fn render(&self) -> String {
+        let mut text = self.counts();
+        for name in &self.failures {
+            text.push_str(&format!("\n  ✖ {}", name));
+        }
+        let unlisted = self.failed.saturating_sub(self.failures.len() as u64);
+        if unlisted > 0 && !self.failures.is_empty() {
+            text.push_str(&format!("\n  … and {} more", unlisted));
+        }
+        text
+    }
+}
+
+/// Reads a test runner's output line by line and extracts its summary
+///
+/// Lines are fed as they arrive, so output of any length is summarized
+/// without being kept. Output in a format that is not recognized produces no
+/// summary.
+#[derive(Debug, Default)]
+pub struct TestOutputParser {
+    format: Option<TestFormat>,
+    passed: Option<u64>,
+    failed: Option<u64>,
+    skipped: Option<u64>,
+    duration: Option<Duration>,
+    failures: Vec<String>,
+    /// Indentation of the TAP failures not yet attributed to a parent test
+    nested_failures: Vec<usize>,
+}
+
+impl TestOutputParser {
+    /// Read one line of output, without its line ending
+    pub fn feed(&mut self, line: &str) {
+        let line = strip_ansi(line);
+        let trimmed = line.trim();
+        let indent = line.len() - line.trim_start().len();
+
+        if trimmed.starts_with("TAP version") {
+            self.format = Some(TestFormat::Tap);
+        } else if let Some(rest) = trimmed.strip_prefix("not ok ") {
+            self.tap_point(indent, Some(rest));
+        } else if trimmed.starts_with("ok ") {
+            self.tap_point(indent, None);
+        } else if let Some(comment) = trimmed.strip_prefix("# ").filter(|_| indent == 0) {
+            self.tap_comment(comment);
+        } else if let Some(name) = trimmed.strip_prefix("FAIL ") {
+            self.add_failure(name.trim());
+        } else if let Some(counts) = trimmed.strip_prefix("Tests ") {
+            self.vitest_counts(counts);
+        } else if let Some(duration) = trimmed
+            .strip_prefix("Duration ")
+            .filter(|_| self.format == Some(TestFormat::Vitest))
+        {
+            self.duration = duration.split_whitespace().next().and_then(parse_duration);
+        }
+    }
+
+    /// The summary of everything fed so far, if the output was recognized
+    pub fn finish(self) -> Option<TestSummary> {
+        let format = self.format?;
+        if self.failed.is_none() && self.failures.is_empty() {
+            return None;
+        }
+        Some(TestSummary {
+            format,
+            passed: self.passed,
+            failed: self.failed.unwrap_or(self.failures.len() as u64),
+            skipped: self.skipped,
+            duration: self.duration,
+            failures: self.failures,
+        })
+    }
+
+    fn add_failure(&mut self, name: &str) {
+        if !name.is_empty()
+            && self.failures.len() < MAX_LISTED_FAILURES
+            && !self.failures.iter().any(|failure| failure == name)
+        {
+            self.failures.push(name.to_string());
+        }
+    }
+
+    /// A TAP test point; `failure` is what follows `not ok` of a failed one
+    ///
+    /// Subtests are reported before their parent, more deeply indented, and
+    /// a parent fails with them. Only the subtests are listed.
+    fn tap_point(&mut self, indent: usize, failure: Option<&str>) {
+        let nested = self.nested_failures.iter().any(|&depth| depth > indent);
+        self.nested_failures.retain(|&depth| depth <= indent);
+        let Some(rest) = failure else {
+            return;
+        };
+        self.format.get_or_insert(TestFormat::Tap);
+        self.nested_failures.push(indent);
+
+        // `not ok 3 - name # TODO reason`
+        let (description, directive) = rest.split_once(" # ").unwrap_or((rest, ""));
+        let directive = directive.trim_start().to_ascii_uppercase();
+        if nested || directive.starts_with("TODO") || directive.starts_with("SKIP") {
+            return;
+        }
+        let name = description
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .trim_start()
+            .trim_start_matches('-')
+            .trim();
+        self.add_failure(name);
+    }
+
+    /// A top-level TAP comment; `node --test` ends with counts like `# fail 2`
+    fn tap_comment(&mut self, comment: &str) {
+        if self.format != Some(TestFormat::Tap) {
+            return;
+        }
+        let Some((key, value)) = comment.split_once(' ') else {
+            return;
+        };
+        let value = value.trim();
+        match key {
+            "pass" => self.passed = value.parse().ok(),
+            "fail" => self.failed = value.parse().ok(),
+            "skipped" => self.skipped = value.parse().ok(),
+            "duration_ms" => {
+                self.duration = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|ms| ms.is_finite() && *ms >= 0.0)
+                    .map(|ms| Duration::from_secs_f64(ms / 1000.0));
+            }
+            _ => {}
+        }
+    }
+
+    /// Vitest's `Tests  2 failed | 10 passed | 1 skipped (13)`
+    fn vitest_counts(&mut self, counts: &str) {
+        let counts = counts.split(" (").next().unwrap_or(counts);
+        let mut found = false;
+        for part in counts.split('|') {
+            let mut words = part.split_whitespace();
+            let (Some(count), Some(kind)) = (words.next(), words.next()) else {
+                continue;
+            };
+            let Ok(count) = count.parse::<u64>() else {
+                continue;
+            };
+            found = true;
+            match kind {
+                "passed" => self.passed = Some(count),
+                "failed" => self.failed = Some(count),
+                "skipped" => self.skipped = Some(count),
+                _ => {}
+            }
+        }
+        if found {
+            self.format = Some(TestFormat::Vitest);
+            self.failed.get_or_insert(0);
+        }
+    }
+}
+
+/// Summarize a test runner's complete output
+///
+/// # Arguments
+///
+/// * `output` - Everything the runner printed
+///
+/// # Returns
+///
+/// * `Option<TestSummary>` - The summary, or `None` when the format is not
+///   recognized
+pub fn parse_test_output(output: &str) -> Option<TestSummary> {
+    let mut parser = TestOutputParser::default();
+    for line in output.lines() {
+        parser.feed(line);
+    }
+    parser.finish()
+}
+
+/// A duration like vitest prints it, e.g. `312ms` or `1.23s`
+fn parse_duration(text: &str) -> Option<Duration> {
+    let (number, scale) = if let Some(ms) = text.strip_suffix("ms") {
+        (ms, 1000.0)
+    } else {
+        (text.strip_suffix('s')?, 1.0)
+    };
+    number
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .map(|value| Duration::from_secs_f64(value / scale))
+}
+
+/// Remove terminal escape sequences such as colors
+fn strip_ansi(line: &str) -> String {
+    let mut plain = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            plain.push(c);
+            continue;
+        }
+        // CSI sequences end with a letter; others are a single character
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        }
+    }
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NODE_TAP_FAILED: &str = r#"TAP version 13
+# Subtest: adds numbers
+ok 1 - adds numbers
+  ---
+  duration_ms: 0.512
+  ...
+# Subtest: math
+    # Subtest: divides by zero
+    not ok 1 - divides by zero
+      ---
+      duration_ms: 1.204
+      location: '/app/test/math.test.js:8:3'
+      failureType: 'testCodeFailure'
+      error: |-
+        Expected values to be strictly equal:
+
+        Infinity !== 0
+
+      code: 'ERR_ASSERTION'
+      ...
+    # Subtest: multiplies
+    ok 2 - multiplies
+      ---
+      duration_ms: 0.1
+      ...
+    1..2
+not ok 2 - math
+  ---
+  duration_ms: 2.001
+  type: 'suite'
+  failureType: 'subtestsFailed'
+  error: '1 subtest failed'
+  ...
+# Subtest: parses input
+not ok 3 - parses input
+  ---
+  duration_ms: 0.3
+  failureType: 'testCodeFailure'
+  ...
+# Subtest: later
+not ok 4 - later # TODO not written yet
+  ---
+  duration_ms: 0.1
+  ...
+1..4
+# tests 5
+# suites 1
+# pass 2
+# fail 2
+# cancelled 0
+# skipped 0
+# todo 1
+# duration_ms 45.678
+"#;
+
+    const NODE_TAP_PASSED: &str = "TAP version 13
+# Subtest: adds numbers
+ok 1 - adds numbers
+  ---
+  duration_ms: 0.5
+  ...
+# Subtest: skipped
+ok 2 - skipped # SKIP
+  ---
+  duration_ms: 0.1
+  ...
+1..2
+# tests 2
+# suites 0
+# pass 1
+# fail 0
+# cancelled 0
+# skipped 1
+# todo 0
+# duration_ms 12.5
+";
+
+    const VITEST_FAILED: &str = " RUN  v1.6.0 /app
+
+ ❯ src/math.test.ts  (3 tests | 1 failed) 5ms
+   ❯ src/math.test.ts > math > adds numbers
+     → expected 3 to be 4
+ ✓ src/parse.test.ts  (10 tests | 1 skipped) 12ms
+
+⎯⎯⎯⎯⎯⎯⎯ Failed Tests 2 ⎯⎯⎯⎯⎯⎯⎯
+
+ FAIL  src/math.test.ts > math > adds numbers
+AssertionError: expected 3 to be 4
+
+- Expected
++ Received
+
+- 4
++ 3
+
+ ❯ src/math.test.ts:5:17
+      3|   it('adds numbers', () => {
+      4|     expect(1 + 2).toBe(4)
+       |                   ^
+      5|   })
+
+⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯⎯[1/2]⎯
+
+ FAIL  src/math.test.ts > math > subtracts
+AssertionError: expected 1 to be 0
+
+ Test Files  1 failed | 1 passed (2)
+      Tests  2 failed | 10 passed | 1 skipped (13)
+   Start at  12:00:00
+   Duration  312ms (transform 20ms, setup 0ms, collect 30ms, tests 17ms)
+";
+
+    #[test]
+    fn test_parse_node_tap() {
+        let summary = parse_test_output(NODE_TAP_FAILED).unwrap();
+        assert_eq!(
+            summary,
+            TestSummary {
+                format: TestFormat::Tap,
+                passed: Some(2),
+                failed: 2,
+                skipped: Some(0),
+                duration: Some(Duration::from_secs_f64(0.045678)),
+                failures: vec!["divides by zero".to_string(), "parses input".to_string()],
+            }
+        );
+        assert_eq!(summary.counts(), "2 failed, 2 passed in 0.05s");
+        assert_eq!(
+            summary.render(),
+            "2 failed, 2 passed in 0.05s\n  ✖ divides by zero\n  ✖ parses input"
+        );
+
+        let summary = parse_test_output(NODE_TAP_PASSED).unwrap();
+        assert_eq!(summary.failed, 0);
+        assert_eq!(summary.passed, Some(1));
+        assert_eq!(summary.counts(), "0 failed, 1 passed, 1 skipped in 0.01s");
+        assert!(summary.failures.is_empty());
+    }
+
+    #[test]
+    fn test_parse_tap_without_counts() {
+        // Plain TAP from other producers has no trailing counts
+        let summary = parse_test_output("1..3\nok 1 - a\nnot ok 2 - b\nnot ok 3 c\n").unwrap();
+        assert_eq!(summary.failed, 2);
+        assert_eq!(summary.passed, None);
+        assert_eq!(summary.failures, ["b", "c"]);
+        assert_eq!(summary.duration, None);
+    }
+
+    #[test]
+    fn test_parse_vitest() {
+        let summary = parse_test_output(VITEST_FAILED).unwrap();
+        assert_eq!(
+            summary,
+            TestSummary {
+                format: TestFormat::Vitest,
+                passed: Some(10),
+                failed: 2,
+                skipped: Some(1),
+                duration: Some(Duration::from_millis(312)),
+                failures: vec![
+                    "src/math.test.ts > math > adds numbers".to_string(),
+                    "src/math.test.ts > math > subtracts".to_string(),
+                ],
+            }
+        );
+        assert_eq!(
+            summary.render(),
+            "2 failed, 10 passed, 1 skipped in 0.31s\n  ✖ src/math.test.ts > math > adds numbers\n  ✖ src/math.test.ts > math > subtracts"
+        );
+
+        // Colors are ignored
+        let colored = "\x1b[31m FAIL \x1b[39m src/a.test.ts > fails\n\x1b[2m      Tests \x1b[22m \x1b[1m\x1b[31m1 failed\x1b[39m\x1b[22m\x1b[2m (1)\x1b[22m\n\x1b[2m   Duration \x1b[22m 1.50s\n";
+        let summary = parse_test_output(colored).unwrap();
+        assert_eq!(summary.failures, ["src/a.test.ts > fails"]);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.duration, Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn test_parse_many_failures() {
+        let mut output = String::from("TAP version 13\n");
+        for i in 1..=25 {
+            output.push_str(&format!("not ok {} - case {}\n", i, i));
+        }
+        output.push_str("# pass 0\n# fail 25\n");
+        let summary = parse_test_output(&output).unwrap();
+        assert_eq!(summary.failures.len(), MAX_LISTED_FAILURES);
+        assert!(summary.render().ends_with("\n  ✖ case 20\n  … and 5 more"));
+    }
+
+    #[test]
+    fn test_parse_unknown_output() {
+        assert_eq!(parse_test_output(""), None);
+        assert_eq!(
+            parse_test_output("PASS src/a.test.js\nTests: 1 failed, 2 passed\nok then\n"),
+            None
+        );
+        assert_eq!(
+            parse_test_output("Running 3 tests\nAll good\n# pass 3\n"),
+            None
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! Helpers shared by the unit tests
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+/// Serve a single HTTP request with `status` and `body`
+///
+/// # Returns
+///
+/// * `(String, JoinHandle<String>)` - Base URL of the server and a handle
+///   yielding the raw request it received
+pub fn serve_once(status: u16, body: impl AsRef<[u8]>) -> (String, thread::JoinHandle<String>) {
+    let (base_url, handle) = serve_sequence(vec![(status, String::new(), body.as_ref().to_vec())]);
+    let handle = thread::spawn(move || handle.join().unwrap().remove(0));
+    (base_url, handle)
+}
+
+/// Serve one HTTP request per response, in order
+///
+/// # Arguments
+///
+/// * `responses` - Status, extra header lines (each ending in `\r\n`) and body
+///   of every response
+///
+/// # Returns
+///
+/// * `(String, JoinHandle<Vec<String>>)` - Base URL of the server and a handle
+///   yielding the raw requests it received
+pub fn serve_sequence(
+    responses: Vec<(u16, String, Vec<u8>)>,
+) -> (String, thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let mut requests = Vec::new();
+        for (status, headers, body) in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                    break;
+                }
+                request.push_str(&line);
+            }
+
+            write!(
+                stream,
+                "HTTP/1.1 {} Status\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+                status,
+                body.len(),
+                headers
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+            requests.push(request);
+        }
+        requests
+    });
+
+    (base_url, handle)
+}
+
+/// Serve every request with `status` and `body`, keeping connections open
+///
+/// # Returns
+///
+/// * `(String, Arc<AtomicUsize>)` - Base URL of the server and the number of
+///   connections it has accepted so far
+pub fn serve_keep_alive(status: u16, body: impl AsRef<[u8]>) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+    let body = body.as_ref().to_vec();
+
+    let accepted = Arc::clone(&connections);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { break };
+            accepted.fetch_add(1, Ordering::SeqCst);
+            let body = body.clone();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    // Read one request, then answer it on the same connection
+                    let mut line = String::new();
+                    loop {
+                        line.clear();
+                        match reader.read_line(&mut line) {
+                            Ok(0) | Err(_) => return,
+                            Ok(_) if line == "\r\n" => break,
+                            Ok(_) => {}
+                        }
+                    }
+                    let head = format!(
+                        "HTTP/1.1 {} Status\r\nContent-Length: {}\r\n\r\n",
+                        status,
+                        body.len()
+                    );
+                    if stream.write_all(head.as_bytes()).is_err()
+                        || stream.write_all(&body).is_err()
+                    {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+
+    (base_url, connections)
+}
@@ -1,7 +1,12 @@
-use regex::Regex;
-use std::{fs, path::Path, process::Command, sync::OnceLock};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use crate::{Error, Result};
+use crate::template::ProjectType;
+use crate::{process, progress, Error, Result};
 
 /// Represents the type of version control system to use
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
@@ -9,10 +14,47 @@ pub enum Vcs {
     /// Git version control (default)
     #[default]
     Git,
+    /// Mercurial
+    Hg,
+    /// Jujutsu, backed by a git repository
+    #[value(alias = "jj")]
+    Jujutsu,
     /// No version control
     None,
 }
 
+impl Vcs {
+    /// Name of the executable driving this VCS
+    pub fn binary(&self) -> Option<&'static str> {
+        match self {
+            Vcs::Git => Some("git"),
+            Vcs::Hg => Some("hg"),
+            Vcs::Jujutsu => Some("jj"),
+            Vcs::None => None,
+        }
+    }
+
+    /// Directory marking the root of a repository
+    pub fn marker(&self) -> Option<&'static str> {
+        match self {
+            Vcs::Git => Some(".git"),
+            Vcs::Hg => Some(".hg"),
+            Vcs::Jujutsu => Some(".jj"),
+            Vcs::None => None,
+        }
+    }
+
+    /// Arguments that create a repository in the current directory
+    fn init_args(&self) -> &'static [&'static str] {
+        match self {
+            Vcs::Git => &["init", "--quiet"],
+            Vcs::Hg => &["init", "--quiet"],
+            Vcs::Jujutsu => &["git", "init", "--quiet"],
+            Vcs::None => &[],
+        }
+    }
+}
+
 /// Configuration for package name validation
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PackageNameConfig<'a> {
@@ -22,54 +64,270 @@ pub struct PackageNameConfig<'a> {
     pub is_scoped: bool,
 }
 
-/// NPM package name regex pattern
-/// Follows the official npm specification:
-/// - Can be scoped (@org/name) or unscoped (name)
-/// - Must contain only lowercase letters, numbers, and special characters: -._
-/// - Must start with @ (for scoped) or letter/number
-/// - Cannot have consecutive dots, hyphens, or underscores
-/// - Cannot end with a dot, hyphen, or underscore
-static NPM_PACKAGE_REGEX: OnceLock<Regex> = OnceLock::new();
+/// Longest package name npm accepts for new packages
+pub const MAX_PACKAGE_NAME_LENGTH: usize = 214;
 
-pub fn validate_package_name(name: &str) -> Result<()> {
-    let regex = NPM_PACKAGE_REGEX.get_or_init(|| {
-        Regex::new(r"^(?:@[a-z][a-z0-9-]*\/)?[a-z][a-z0-9-._]*[a-z0-9]$")
-            .expect("Invalid package name regex pattern")
-    });
+/// Names npm never accepts, whatever their case
+const RESERVED_PACKAGE_NAMES: [&str; 2] = ["node_modules", "favicon.ico"];
+
+/// Device names Windows reserves in every directory, with or without an
+/// extension
+const WINDOWS_DEVICE_NAMES: [&str; 22] = [
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Modules built into Node.js that `require` resolves before any package
+const NODE_CORE_MODULES: [&str; 42] = [
+    "assert",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "domain",
+    "events",
+    "fs",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "module",
+    "net",
+    "os",
+    "path",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "repl",
+    "stream",
+    "string_decoder",
+    "sys",
+    "timers",
+    "tls",
+    "trace_events",
+    "tty",
+    "url",
+    "util",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+    "zlib",
+];
+
+/// Widely used packages whose names are easily imitated
+const POPULAR_PACKAGES: [&str; 30] = [
+    "axios",
+    "body-parser",
+    "chalk",
+    "commander",
+    "cross-env",
+    "debug",
+    "dotenv",
+    "eslint",
+    "express",
+    "jest",
+    "jquery",
+    "lodash",
+    "mocha",
+    "moment",
+    "next",
+    "prettier",
+    "react",
+    "react-dom",
+    "request",
+    "rxjs",
+    "socket.io",
+    "tslib",
+    "typescript",
+    "underscore",
+    "uuid",
+    "vite",
+    "vitest",
+    "vue",
+    "webpack",
+    "yargs",
+];
+
+/// Characters npm only accepts in the names of packages published before
+/// its current rules
+const LEGACY_NAME_CHARACTERS: &str = "~'!()*";
+
+/// Whether `c` survives `encodeURIComponent` unchanged
+fn is_url_safe(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-_.".contains(c) || LEGACY_NAME_CHARACTERS.contains(c)
+}
 
+/// Check a package name against npm's naming rules
+///
+/// With `new`, the rules for new packages apply; otherwise only those every
+/// published package obeys, as in `validate-npm-package-name`.
+fn check_package_name(name: &str, new: bool) -> std::result::Result<(), String> {
     if name.is_empty() {
-        return Err(Error::InvalidPackageName {
-            name: name.to_string(),
-            reason: "Package name cannot be empty".to_string(),
-        });
+        return Err("Package name cannot be empty".to_string());
     }
-
-    // Check for consecutive special characters
-    if name.contains("..") || name.contains("--") || name.contains("__") {
-        return Err(Error::InvalidPackageName {
-            name: name.to_string(),
-            reason: "Package name cannot contain consecutive dots, hyphens, or underscores"
-                .to_string(),
-        });
+    if name.trim() != name {
+        return Err("Package name cannot start or end with whitespace".to_string());
+    }
+    if RESERVED_PACKAGE_NAMES.contains(&name.to_lowercase().as_str()) {
+        return Err(format!("'{}' is a reserved name", name));
     }
 
-    if !regex.is_match(name) {
-        return Err(Error::InvalidPackageName {
-            name: name.to_string(),
-            reason: format!(
-                "Invalid package name format. Package names must:\n\
-                 - Start with a letter (or @ for scoped packages)\n\
-                 - Contain only lowercase letters, numbers, and special characters: -._\n\
-                 - Not end with a dot, hyphen, or underscore\n\
-                 - Follow the pattern: {} or @scope/{}",
-                "[a-z][a-z0-9-._]*[a-z0-9]", "[a-z][a-z0-9-._]*[a-z0-9]"
-            ),
-        });
+    let (scope, package) = match name.strip_prefix('@') {
+        Some(scoped) => match scoped.split_once('/') {
+            Some((scope, package))
+                if !scope.is_empty() && !package.is_empty() && !package.contains('/') =>
+            {
+                (Some(scope), package)
+            }
+            _ => return Err("Scoped package names must look like @scope/name".to_string()),
+        },
+        None if name.contains('/') => {
+            return Err("Only scoped package names (@scope/name) may contain '/'".to_string())
+        }
+        None => (None, name),
+    };
+    for part in scope.into_iter().chain([package]) {
+        if part.starts_with('.') || part.starts_with('_') {
+            return Err("Package name cannot start with a dot or an underscore".to_string());
+        }
+        if let Some(c) = part.chars().find(|c| !is_url_safe(*c)) {
+            return Err(format!("Package name cannot contain '{}'", c));
+        }
     }
 
+    if new {
+        if name.len() > MAX_PACKAGE_NAME_LENGTH {
+            return Err(format!(
+                "Package name cannot be longer than {} characters",
+                MAX_PACKAGE_NAME_LENGTH
+            ));
+        }
+        if name.chars().any(|c| c.is_ascii_uppercase()) {
+            return Err("Package name must be lowercase".to_string());
+        }
+        if let Some(c) = name.chars().find(|c| LEGACY_NAME_CHARACTERS.contains(*c)) {
+            return Err(format!("Package name cannot contain '{}'", c));
+        }
+        let stem = package.split('.').next().unwrap_or_default();
+        if WINDOWS_DEVICE_NAMES.contains(&stem) {
+            return Err(format!(
+                "'{}' is a Windows device name, so the package cannot be checked out on Windows",
+                stem
+            ));
+        }
+    }
     Ok(())
 }
 
+/// Why a valid package name is easily mistaken for another module, if it is
+///
+/// Unscoped names are confusing when they shadow a Node.js core module, or
+/// when they differ from a popular package only by `-`, `.` and `_`.
+///
+/// # Arguments
+///
+/// * `name` - The package name
+///
+/// # Returns
+///
+/// * `Option<String>` - The rule the name breaks, if any
+pub fn confusing_package_name(name: &str) -> Option<String> {
+    if name.starts_with('@') {
+        return None;
+    }
+    if NODE_CORE_MODULES.contains(&name) {
+        return Some(format!(
+            "'{}' is the name of a Node.js core module, which `require` loads instead",
+            name
+        ));
+    }
+    let normalize = |name: &str| name.replace(['-', '.', '_'], "");
+    let normalized = normalize(name);
+    POPULAR_PACKAGES
+        .iter()
+        .find(|popular| **popular != name && normalize(popular) == normalized)
+        .map(|popular| {
+            format!(
+                "'{}' differs from the popular package '{}' only by punctuation",
+                name, popular
+            )
+        })
+}
+
+/// Refuse a confusing package name, see [`confusing_package_name`], unless
+/// `allow` is set, in which case it is only a warning
+///
+/// # Arguments
+///
+/// * `name` - The package name
+/// * `allow` - Whether a confusing name is accepted (`--allow-confusing-name`)
+///
+/// # Returns
+///
+/// * `Result<()>` - `Error::InvalidPackageName` with the rule that is broken
+pub fn check_confusing_package_name(name: &str, allow: bool) -> Result<()> {
+    let Some(reason) = confusing_package_name(name) else {
+        return Ok(());
+    };
+    if allow {
+        progress::warn(&reason)?;
+        return Ok(());
+    }
+    Err(Error::InvalidPackageName {
+        name: name.to_string(),
+        reason: format!("{}; pass --allow-confusing-name to use it anyway", reason),
+    })
+}
+
+/// Validate the name of a package that cargonode creates or publishes
+///
+/// Follows npm's rules for new packages: at most 214 characters, lowercase
+/// and URL-safe (letters, digits, `-`, `.` and `_`), not starting with `.` or
+/// `_`, and optionally scoped as `@scope/name`.
+///
+/// # Arguments
+///
+/// * `name` - The package name
+///
+/// # Returns
+///
+/// * `Result<()>` - `Error::InvalidPackageName` with the rule that is broken
+pub fn validate_package_name(name: &str) -> Result<()> {
+    check_package_name(name, true).map_err(|reason| Error::InvalidPackageName {
+        name: name.to_string(),
+        reason,
+    })
+}
+
+/// Validate the name of a package that may already be published
+///
+/// More lenient than `validate_package_name`: registry packages published
+/// before npm's current rules may be long, use uppercase letters or contain
+/// `~'!()*`.
+///
+/// # Arguments
+///
+/// * `name` - The package name
+///
+/// # Returns
+///
+/// * `Result<()>` - `Error::InvalidPackageName` with the rule that is broken
+pub fn validate_existing_package_name(name: &str) -> Result<()> {
+    check_package_name(name, false).map_err(|reason| Error::InvalidPackageName {
+        name: name.to_string(),
+        reason,
+    })
+}
+
 /// Configuration for version control initialization
 #[derive(Debug, Clone)]
 pub struct VcsConfig {
@@ -88,65 +346,185 @@ impl Default for VcsConfig {
     }
 }
 
-fn check_git_available() -> bool {
-    Command::new("git")
+fn is_vcs_available(binary: &str) -> bool {
+    Command::new(binary)
         .arg("--version")
         .output()
         .map(|output| output.status.success())
         .unwrap_or(false)
 }
 
-fn is_git_repo(path: &Path) -> bool {
-    path.join(".git").exists()
+/// Get the user name configured in git, if any
+pub fn git_user_name() -> Option<String> {
+    git_config("user.name")
 }
 
-fn init_git_repo(path: &Path) -> Result<()> {
-    if !check_git_available() {
-        return Err(Error::Git {
-            message: "Git is not installed".to_string(),
-            details: "Please install git to continue".to_string(),
-        });
+/// Get a value from the git configuration, if set
+pub fn git_config(key: &str) -> Option<String> {
+    command_output("git", &["config", key])
+}
+
+/// Get the version reported by the installed git, if any
+pub fn git_version() -> Option<String> {
+    command_output("git", &["--version"])
+}
+
+/// Get the version of the installed Node.js, without the leading `v`
+pub fn node_version() -> Option<String> {
+    command_output("node", &["--version"])
+        .map(|version| version.trim_start_matches('v').to_string())
+}
+
+/// Run a command and return its trimmed standard output if it succeeded
+/// with a non-empty result
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!stdout.is_empty()).then_some(stdout)
+}
+
+/// Find the root of the `vcs` repository containing `path`, if any
+///
+/// Walks up from `path` the same way `find_workspace_root` does, so a project
+/// created inside an existing checkout is recognized as part of it.
+///
+/// # Arguments
+///
+/// * `path` - Directory to start searching from
+/// * `vcs` - Version control system to look for
+///
+/// # Returns
+///
+/// * `Option<PathBuf>` - The repository root, or `None` outside a repository
+pub fn find_repository_root(path: &Path, vcs: Vcs) -> Option<PathBuf> {
+    let marker = vcs.marker()?;
+    path.ancestors()
+        .find(|dir| dir.join(marker).exists())
+        .map(Path::to_path_buf)
+}
+
+fn init_repo(path: &Path, vcs: Vcs) -> Result<()> {
+    let Some(binary) = vcs.binary() else {
+        return Ok(());
+    };
+    let error = |message: String, details: String| match vcs {
+        Vcs::Git => Error::Git { message, details },
+        _ => Error::Vcs {
+            binary: binary.to_string(),
+            message,
+            details,
+        },
+    };
+
+    if !is_vcs_available(binary) {
+        return Err(error(
+            format!("{} is not installed", binary),
+            format!("Please install {} to continue", binary),
+        ));
     }
 
-    let output = Command::new("git")
-        .args(["init", "--quiet"])
+    let spinner = crate::progress::ProgressBar::spinner("Initializing");
+    let output = Command::new(binary)
+        .args(vcs.init_args())
         .current_dir(path)
         .output()?;
+    drop(spinner);
 
     if !output.status.success() {
-        return Err(Error::Git {
-            message: "Failed to initialize git repository".to_string(),
-            details: String::from_utf8_lossy(&output.stderr).to_string(),
-        });
+        return Err(error(
+            format!("Failed to initialize {} repository", binary),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
     }
 
     Ok(())
 }
 
-fn write_ignore_file(path: &Path, content: &str) -> Result<()> {
-    let gitignore = path.join(".gitignore");
-    let should_write = if !gitignore.exists() {
-        true
-    } else {
-        fs::read_to_string(&gitignore)?.is_empty()
+/// Add the entries of `content` to the ignore file at `ignore_file`
+///
+/// A missing or empty file gets `content` verbatim; an existing one keeps its
+/// lines and only gains the entries it does not list yet.
+fn write_ignore_file(ignore_file: &Path, content: &str) -> Result<()> {
+    let existing = match fs::read_to_string(ignore_file) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
     };
-    if should_write {
-        fs::write(&gitignore, content)?;
+    if existing.trim().is_empty() {
+        fs::write(ignore_file, content)?;
+        return Ok(());
     }
+
+    let missing: Vec<&str> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter(|line| {
+            !existing
+                .lines()
+                .any(|existing| existing.trim() == line.trim())
+        })
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let mut updated = existing;
+    if !updated.ends_with('\n') {
+        updated.push('\n');
+    }
+    for line in missing {
+        updated.push_str(line);
+        updated.push('\n');
+    }
+    fs::write(ignore_file, updated)?;
     Ok(())
 }
 
+/// Convert gitignore entries to an `.hgignore` using glob syntax
+///
+/// Mercurial only reads the `.hgignore` at the repository root, where glob
+/// patterns match at any depth, just like unanchored gitignore entries.
+pub(crate) fn hgignore_content(content: &str) -> String {
+    let mut hgignore = String::from("syntax: glob\n");
+    for line in content.lines() {
+        let entry = line.trim().trim_end_matches('/');
+        if !entry.is_empty() {
+            hgignore.push_str(entry);
+            hgignore.push('\n');
+        }
+    }
+    hgignore
+}
+
+/// Set up version control for the project at `path`
+///
+/// A new repository is only created when `path` is not already inside one;
+/// either way the ignore entries from `config` are added to the ignore file.
 pub fn init_vcs(path: &Path, config: &VcsConfig) -> Result<()> {
-    match config.vcs {
-        Vcs::Git => {
-            if !is_git_repo(path) {
-                init_git_repo(path)?;
-            }
-            write_ignore_file(path, &config.ignore_content)?;
+    if config.vcs == Vcs::None {
+        return Ok(());
+    }
+
+    let root = match find_repository_root(path, config.vcs) {
+        Some(root) => root,
+        None => {
+            init_repo(path, config.vcs)?;
+            path.to_path_buf()
         }
-        Vcs::None => (),
+    };
+
+    match config.vcs {
+        Vcs::Hg => write_ignore_file(
+            &root.join(".hgignore"),
+            &hgignore_content(&config.ignore_content),
+        ),
+        // Git and Jujutsu both honor nested .gitignore files
+        _ => write_ignore_file(&path.join(".gitignore"), &config.ignore_content),
     }
-    Ok(())
 }
 
 pub fn is_directory_empty(path: &Path) -> Result<bool> {
@@ -176,44 +554,131 @@ pub fn ensure_directory_empty(path: &Path) -> Result<()> {
 pub struct ProjectStructure {
     /// Root path of the project
     pub path: std::path::PathBuf,
-    /// Whether this is a binary project
-    pub is_binary: bool,
+    /// Type of the project
+    pub project_type: ProjectType,
     /// Source file content
     pub source_content: String,
 }
 
-pub fn create_project_config(path: &Path, is_binary: bool) -> ProjectStructure {
-    let source_content = if is_binary {
-        crate::template::MAIN_JS_CONTENT.to_string()
-    } else {
-        crate::template::LIB_JS_CONTENT.to_string()
-    };
-
+pub fn create_project_config(path: &Path, project_type: ProjectType) -> ProjectStructure {
     ProjectStructure {
         path: path.to_path_buf(),
-        is_binary,
-        source_content,
+        project_type,
+        source_content: project_type.entry_content().to_string(),
     }
 }
 
 pub fn create_project_structure(config: &ProjectStructure) -> Result<()> {
     fs::create_dir_all(&config.path)?;
-    let src_path = config.path.join("src");
-    fs::create_dir_all(&src_path)?;
-    let source_file = if config.is_binary {
-        "main.js"
-    } else {
-        "lib.js"
-    };
-    let file_path = src_path.join(source_file);
+    let file_path = config.path.join(config.project_type.entry_file());
+    fs::create_dir_all(file_path.parent().unwrap_or(&config.path))?;
     if file_path.exists() {
+        // An existing entry is kept, but `bin` points at it all the same
+        if config.project_type == ProjectType::Cli {
+            return prepare_existing_cli_entry(&file_path, config.project_type.entry_file());
+        }
         return Ok(());
     }
-    fs::write(file_path, &config.source_content)?;
+    fs::write(&file_path, &config.source_content)?;
+    // The entry of a CLI is run directly through its shebang
+    if config.project_type == ProjectType::Cli {
+        set_executable(&file_path)?;
+    }
 
     Ok(())
 }
 
+/// Make an existing CLI entry runnable through `bin`
+///
+/// An entry with a shebang is made executable; one without is left alone
+/// with a warning, as npm would link a command that cannot run.
+///
+/// # Arguments
+///
+/// * `path` - The existing entry file
+/// * `entry` - Its path in the project, for the warning
+///
+/// # Returns
+///
+/// * `Result<()>` - An IO error if the file cannot be read or changed
+fn prepare_existing_cli_entry(path: &Path, entry: &str) -> Result<()> {
+    if fs::read(path)?.starts_with(b"#!") {
+        return set_executable(path);
+    }
+    progress::warn(&format!(
+        "{} has no shebang, so the `bin` command will not run; start it with `#!/usr/bin/env node`",
+        entry
+    ))?;
+    Ok(())
+}
+
+/// Write rendered template files below `root`
+///
+/// Files that already exist are left untouched, matching how the embedded
+/// template behaves when initializing an existing directory.
+pub fn write_template_files(root: &Path, files: &[crate::template::TemplateFile]) -> Result<()> {
+    fs::create_dir_all(root)?;
+
+    for file in files {
+        let file_path = root.join(&file.path);
+        if file_path.exists() {
+            continue;
+        }
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match &file.bytes {
+            Some(bytes) => fs::write(&file_path, bytes)?,
+            None => fs::write(&file_path, file.content.as_deref().unwrap_or_default())?,
+        }
+        if file.executable {
+            set_executable(&file_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o111);
+    fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Pick the name of a package created in `path`
+///
+/// An explicit name wins, whatever the directory is called, so a scoped
+/// package doesn't need to live below a directory named after its scope.
+/// Otherwise the name comes from the directory, as in
+/// [`extract_package_name`].
+///
+/// # Arguments
+///
+/// * `path` - Directory of the package
+/// * `name` - The name passed with `--name`, if any
+///
+/// # Returns
+///
+/// * `Result<String>` - The validated package name
+pub fn resolve_package_name(path: &Path, name: Option<&str>) -> Result<String> {
+    match name {
+        Some(name) => {
+            validate_package_name(name)?;
+            Ok(name.to_string())
+        }
+        None => extract_package_name(path),
+    }
+}
+
 pub fn extract_package_name(path: &Path) -> Result<String> {
     // Get the base name
     let name = path.file_name().and_then(|n| n.to_str()).ok_or_else(|| {
@@ -257,13 +722,248 @@ pub fn extract_package_name(path: &Path) -> Result<String> {
     Ok(package_name)
 }
 
+/// Get the current time as seconds since the Unix epoch
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Write a file so that readers see either its old or its new content
+///
+/// The content is written to a temporary file next to `path`, which is then
+/// renamed over it. The temporary name includes the process id, so
+/// concurrent writers never interleave their content.
+///
+/// # Arguments
+///
+/// * `path` - File to write
+/// * `content` - New content of the file
+///
+/// # Returns
+///
+/// * `Result<()>` - Whether the file was replaced
+pub fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> Result<()> {
+    let file_name = path.file_name().ok_or_else(|| Error::Config {
+        message: format!("Cannot write to '{}'", path.display()),
+        hint: None,
+    })?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    let _cleanup = process::remove_on_interrupt(&temp_path);
+    fs::write(&temp_path, content)?;
+    if let Err(e) = fs::rename(&temp_path, path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Get the UTC calendar date of a Unix timestamp
+///
+/// # Returns
+///
+/// * `(i64, u32, u32)` - Year, month (1-12) and day (1-31)
+pub fn utc_date(timestamp: u64) -> (i64, u32, u32) {
+    let days = (timestamp / 86_400) as i64;
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// Environment variable that relocates the project-local `.cargonode` directory
+pub const HOME_ENV: &str = "CARGONODE_HOME";
+
+/// Get the directory holding the cache and journal of the project at `project_dir`
+///
+/// This is `.cargonode` in the project unless `CARGONODE_HOME` is set; a
+/// relative `CARGONODE_HOME` is resolved against the project directory.
+pub fn state_dir(project_dir: &Path) -> PathBuf {
+    state_dir_with(project_dir, env::var_os(HOME_ENV))
+}
+
+pub(crate) fn state_dir_with(project_dir: &Path, home: Option<std::ffi::OsString>) -> PathBuf {
+    match home.filter(|home| !home.is_empty()) {
+        Some(home) => project_dir.join(home),
+        None => project_dir.join(".cargonode"),
+    }
+}
+
+/// Find an executable installed in `node_modules/.bin` of `project_dir` or
+/// one of its parents
+pub fn find_local_bin(project_dir: &Path, name: &str) -> Option<PathBuf> {
+    project_dir
+        .ancestors()
+        .map(|dir| local_bin_path(dir, name))
+        .find(|path| path.is_file())
+}
+
+/// Find the nearest `node_modules/.bin`, in `project_dir` or one of its
+/// parents, that has any of `names` installed
+///
+/// # Arguments
+///
+/// * `project_dir` - Directory the search starts in
+/// * `names` - Executables to look for
+///
+/// # Returns
+///
+/// * `Vec<&str>` - Those of `names` installed there, in the order given;
+///   empty if none is installed anywhere
+pub fn find_nearest_local_bins<'a>(project_dir: &Path, names: &[&'a str]) -> Vec<&'a str> {
+    project_dir
+        .ancestors()
+        .map(|dir| {
+            names
+                .iter()
+                .copied()
+                .filter(|name| local_bin_path(dir, name).is_file())
+                .collect::<Vec<_>>()
+        })
+        .find(|installed| !installed.is_empty())
+        .unwrap_or_default()
+}
+
+/// Path of the executable `name` in `node_modules/.bin` of `dir`
+fn local_bin_path(dir: &Path, name: &str) -> PathBuf {
+    let file_name = if cfg!(windows) {
+        format!("{}.cmd", name)
+    } else {
+        name.to_string()
+    };
+    dir.join("node_modules").join(".bin").join(file_name)
+}
+
+/// Directory holding the user's cargonode configuration
+///
+/// `$XDG_CONFIG_HOME/cargonode` when set, otherwise the platform's config
+/// directory: `%APPDATA%` on Windows, `~/Library/Application Support` on
+/// macOS and `~/.config` elsewhere.
+///
+/// # Returns
+///
+/// * `Result<PathBuf>` - The directory, or `Error::Config` when none of
+///   these variables is set, rather than a directory shared with other users
+pub fn user_config_dir() -> Result<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| {
+            if cfg!(windows) {
+                env::var_os("APPDATA").map(PathBuf::from)
+            } else if cfg!(target_os = "macos") {
+                home.map(|home| home.join("Library").join("Application Support"))
+            } else {
+                home.map(|home| home.join(".config"))
+            }
+        })
+        .ok_or_else(|| Error::Config {
+            message: "Cannot find the user configuration directory".to_string(),
+            hint: Some(if cfg!(windows) {
+                "Set APPDATA or XDG_CONFIG_HOME to the directory holding cargonode's config.toml."
+                    .to_string()
+            } else {
+                "Set HOME, or XDG_CONFIG_HOME to the directory holding cargonode's config.toml."
+                    .to_string()
+            }),
+        })?;
+
+    Ok(base.join("cargonode"))
+}
+
+/// Get the per-user cache directory for cargonode
+///
+/// Honors `XDG_CACHE_HOME` and otherwise uses the platform's conventional
+/// cache location, falling back to the system temporary directory.
+pub fn user_cache_dir() -> PathBuf {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let base = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| {
+            if cfg!(windows) {
+                env::var_os("LOCALAPPDATA").map(PathBuf::from)
+            } else if cfg!(target_os = "macos") {
+                home.map(|home| home.join("Library").join("Caches"))
+            } else {
+                home.map(|home| home.join(".cache"))
+            }
+        })
+        .unwrap_or_else(env::temp_dir);
+
+    base.join("cargonode")
+}
+
+/// Get the signal that terminated a process, if it was killed by one
+#[cfg(unix)]
+pub fn termination_signal(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+
+    status.signal()
+}
+
+/// Get the signal that terminated a process, if it was killed by one
+#[cfg(not(unix))]
+pub fn termination_signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Get the exit code to report for a finished process
+///
+/// Processes killed by a signal have no exit code; like shells, report
+/// `128 + signal` for them instead.
+pub fn exit_code(status: &ExitStatus) -> i32 {
+    match termination_signal(status) {
+        Some(signal) => 128 + signal,
+        None => status.code().unwrap_or(1),
+    }
+}
+
+/// Describe a signal by number and, for common signals, by name
+pub fn describe_signal(signal: i32) -> String {
+    // Only signals whose numbers agree across Unix platforms are named
+    let name = match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        6 => "SIGABRT",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        11 => "SIGSEGV",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        _ => return format!("signal {}", signal),
+    };
+    format!("signal {} ({})", signal, name)
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
+    use std::sync::Arc;
 
     use tempfile::TempDir;
 
     use super::*;
+    use crate::progress::RecordingSink;
 
     #[test]
     fn test_validate_package_name_valid() {
@@ -280,6 +980,21 @@ mod tests {
             "@scope/my_package",
             "ab1",
             "@abc/def2",
+            "a",                  // single character
+            "1package",           // starts with a digit
+            "@scope/1package",    // package starts with a digit
+            "@123/package",       // scope starts with a digit
+            "end.",               // ends with a dot
+            "end_",               // ends with an underscore
+            "double..dot",        // consecutive dots
+            "double__underscore", // consecutive underscores
+            "double--dash",       // consecutive dashes
+            "some-package",
+            "example.com",
+            "under_score",
+            "period.js",
+            "123numeric",
+            "crazy-e2e-test",
         ];
 
         for name in valid_names {
@@ -289,6 +1004,7 @@ mod tests {
                 name
             );
         }
+        assert!(validate_package_name(&"a".repeat(MAX_PACKAGE_NAME_LENGTH)).is_ok());
     }
 
     #[test]
@@ -296,30 +1012,34 @@ mod tests {
         let invalid_names = [
             "",                     // empty
             "UPPERCASE",            // uppercase letters
+            "camelCase",            // uppercase letters
             ".start-dot",           // starts with dot
             "_start-underscore",    // starts with underscore
-            "end.",                 // ends with dot
-            "end_",                 // ends with underscore
-            "double..dot",          // consecutive dots
-            "double__underscore",   // consecutive underscores
-            "double--dash",         // consecutive dashes
             "@/package",            // empty scope
             "@scope/",              // empty package name
             "/package",             // no scope but starts with slash
             "@scope//pkg",          // double slash
             "pkg/",                 // ends with slash
-            "@123/package",         // scope starts with number
             "~package",             // starts with tilde
             "package~",             // ends with tilde
             "@.org/package",        // scope starts with dot
             "@org/.package",        // package starts with dot
+            "@org/_package",        // package starts with underscore
             "package name",         // contains space
+            " leading-space",       // leading whitespace
+            "trailing-space ",      // trailing whitespace
             "@scope/package/extra", // extra segments
-            "a",                    // single character
             "@a/",                  // scope with no package
             "@/a",                  // no scope name
-            "1package",             // starts with number
-            "@scope/1package",      // package starts with number
+            "s/l/a/s/h/e/s",        // slashes without a scope
+            "node_modules",         // reserved
+            "favicon.ico",          // reserved
+            "crazy!",               // legacy special character
+            "it's",                 // legacy special character
+            "ifyouwant-get-the-(thing)",
+            "caf\u{e9}",   // not URL-safe
+            "hello:world", // not URL-safe
+            "@npm-zors/money!time.js",
         ];
 
         for name in invalid_names {
@@ -329,6 +1049,87 @@ mod tests {
                 name
             );
         }
+        assert!(validate_package_name(&"a".repeat(MAX_PACKAGE_NAME_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_validate_existing_package_name() {
+        // Packages published before the current rules
+        for name in ["CAPITAL-LETTERS", "crazy!", "JSONStream", "@Scope/Name"] {
+            assert!(validate_existing_package_name(name).is_ok(), "{}", name);
+            assert!(validate_package_name(name).is_err(), "{}", name);
+        }
+        assert!(validate_existing_package_name(&"a".repeat(300)).is_ok());
+
+        for name in ["", "pkg name", "@scope", "a/b", ".hidden", "node_modules"] {
+            assert!(validate_existing_package_name(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_reserved_package_names() {
+        let cases = [
+            ("node_modules", "reserved name"),
+            ("favicon.ico", "reserved name"),
+            ("con", "Windows device name"),
+            ("nul", "Windows device name"),
+            ("aux.js", "Windows device name"),
+            ("@scope/com1", "Windows device name"),
+            ("lpt9", "Windows device name"),
+        ];
+        for (name, rule) in cases {
+            let Err(Error::InvalidPackageName { reason, .. }) = validate_package_name(name) else {
+                panic!("Should reject {}", name);
+            };
+            assert!(reason.contains(rule), "{}: {}", name, reason);
+        }
+
+        // Only whole names are devices
+        for name in ["console", "auxiliary", "com10", "@con/app"] {
+            assert!(
+                validate_package_name(name).is_ok(),
+                "Should accept {}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_confusing_package_names() {
+        let cases = [
+            ("fs", Some("Node.js core module")),
+            ("http", Some("Node.js core module")),
+            ("child_process", Some("Node.js core module")),
+            ("reactdom", Some("popular package 'react-dom'")),
+            ("react_dom", Some("popular package 'react-dom'")),
+            ("type-script", Some("popular package 'typescript'")),
+            ("socketio", Some("popular package 'socket.io'")),
+            ("react-dom", None),
+            ("fs-extra", None),
+            ("@acme/fs", None),
+            ("@acme/reactdom", None),
+            ("my-app", None),
+        ];
+        for (name, rule) in cases {
+            let reason = confusing_package_name(name);
+            match rule {
+                Some(rule) => {
+                    let reason = reason.unwrap_or_else(|| panic!("Should flag {}", name));
+                    assert!(reason.contains(rule), "{}: {}", name, reason);
+                }
+                None => assert_eq!(reason, None, "{}", name),
+            }
+        }
+
+        // Refused unless explicitly allowed
+        let Err(Error::InvalidPackageName { reason, .. }) =
+            check_confusing_package_name("path", false)
+        else {
+            panic!("Should refuse path");
+        };
+        assert!(reason.contains("--allow-confusing-name"));
+        assert!(check_confusing_package_name("path", true).is_ok());
+        assert!(check_confusing_package_name("my-app", false).is_ok());
     }
 
     #[test]
@@ -343,14 +1144,118 @@ mod tests {
     #[test]
     fn test_create_project_config() {
         let path = PathBuf::from("/test/path");
-        let config = create_project_config(&path, true);
-        assert!(config.is_binary);
+        let config = create_project_config(&path, ProjectType::Binary);
+        assert_eq!(config.project_type, ProjectType::Binary);
         assert_eq!(config.path, path);
         assert_eq!(config.source_content, crate::template::MAIN_JS_CONTENT);
 
-        let config = create_project_config(&path, false);
-        assert!(!config.is_binary);
+        let config = create_project_config(&path, ProjectType::Library);
+        assert_eq!(config.project_type, ProjectType::Library);
         assert_eq!(config.source_content, crate::template::LIB_JS_CONTENT);
+
+        let config = create_project_config(&path, ProjectType::Cli);
+        assert!(config.source_content.starts_with("#!/usr/bin/env node\n"));
+    }
+
+    /// Whether `vcs` can be exercised; prints why a test is skipped otherwise
+    fn vcs_installed(vcs: Vcs) -> bool {
+        let installed = is_vcs_available(vcs.binary().unwrap());
+        if !installed {
+            eprintln!("skipping: {} is not installed", vcs.binary().unwrap());
+        }
+        installed
+    }
+
+    #[test]
+    fn test_find_repository_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let nested = root.join("packages/app");
+        fs::create_dir_all(&nested).unwrap();
+
+        for (vcs, marker) in [(Vcs::Git, ".git"), (Vcs::Hg, ".hg"), (Vcs::Jujutsu, ".jj")] {
+            assert_eq!(find_repository_root(&nested, vcs), None);
+            fs::create_dir(root.join(marker)).unwrap();
+            assert_eq!(find_repository_root(&nested, vcs), Some(root.to_path_buf()));
+        }
+        assert_eq!(find_repository_root(&nested, Vcs::None), None);
+
+        // Worktrees and submodules have a .git file instead of a directory
+        let worktree = root.join("packages/worktree");
+        fs::create_dir_all(&worktree).unwrap();
+        fs::write(worktree.join(".git"), "gitdir: ../../.git/worktrees/w").unwrap();
+        assert_eq!(find_repository_root(&worktree, Vcs::Git), Some(worktree));
+    }
+
+    #[test]
+    fn test_write_ignore_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let ignore_file = temp_dir.path().join(".gitignore");
+
+        write_ignore_file(&ignore_file, "node_modules/\n.env.*\n")?;
+        assert_eq!(fs::read_to_string(&ignore_file)?, "node_modules/\n.env.*\n");
+
+        // Existing entries are kept and only missing ones are added
+        fs::write(&ignore_file, "target\nnode_modules/")?;
+        write_ignore_file(&ignore_file, "node_modules/\n.env.*\n")?;
+        assert_eq!(
+            fs::read_to_string(&ignore_file)?,
+            "target\nnode_modules/\n.env.*\n"
+        );
+
+        assert_eq!(
+            hgignore_content("node_modules/\n.env.*\n"),
+            "syntax: glob\nnode_modules\n.env.*\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_vcs_inside_existing_repository() -> Result<()> {
+        if !vcs_installed(Vcs::Git) {
+            return Ok(());
+        }
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        init_repo(root, Vcs::Git)?;
+        let project = root.join("packages/app");
+        fs::create_dir_all(&project)?;
+
+        init_vcs(&project, &VcsConfig::default())?;
+        assert!(!project.join(".git").exists());
+        assert!(fs::read_to_string(project.join(".gitignore"))?.contains("node_modules/"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_vcs() -> Result<()> {
+        for (vcs, marker, ignore_file) in [
+            (Vcs::Git, ".git", ".gitignore"),
+            (Vcs::Hg, ".hg", ".hgignore"),
+            (Vcs::Jujutsu, ".jj", ".gitignore"),
+        ] {
+            if !vcs_installed(vcs) {
+                continue;
+            }
+            let temp_dir = TempDir::new()?;
+            let config = VcsConfig {
+                vcs,
+                ..Default::default()
+            };
+            init_vcs(temp_dir.path(), &config)?;
+            assert!(temp_dir.path().join(marker).exists());
+            assert!(fs::read_to_string(temp_dir.path().join(ignore_file))?.contains("node_modules"));
+        }
+
+        // `--vcs none` leaves the directory alone
+        let temp_dir = TempDir::new()?;
+        let config = VcsConfig {
+            vcs: Vcs::None,
+            ..Default::default()
+        };
+        init_vcs(temp_dir.path(), &config)?;
+        assert!(is_directory_empty(temp_dir.path())?);
+        Ok(())
     }
 
     #[test]
@@ -365,7 +1270,7 @@ mod tests {
         let temp_dir = tempfile::TempDir::new()?;
         let config = ProjectStructure {
             path: temp_dir.path().to_path_buf(),
-            is_binary: true,
+            project_type: ProjectType::Binary,
             source_content: "test content".to_string(),
         };
 
@@ -386,7 +1291,7 @@ mod tests {
 
         let lib_config = ProjectStructure {
             path: temp_dir.path().to_path_buf(),
-            is_binary: false,
+            project_type: ProjectType::Library,
             source_content: "lib content".to_string(),
         };
         create_project_structure(&lib_config)?;
@@ -396,6 +1301,74 @@ mod tests {
         assert!(lib_file.is_file());
         assert_eq!(fs::read_to_string(lib_file)?, "lib content");
 
+        // The entry of a CLI is executable
+        let cli_dir = temp_dir.path().join("cli");
+        create_project_structure(&create_project_config(&cli_dir, ProjectType::Cli))?;
+        let cli_file = cli_dir.join("src/main.js");
+        assert!(fs::read_to_string(&cli_file)?.starts_with("#!/usr/bin/env node\n"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_ne!(fs::metadata(&cli_file)?.permissions().mode() & 0o111, 0);
+        }
+
+        // An existing entry is made executable, or warned about without a shebang
+        let kept_dir = temp_dir.path().join("kept");
+        fs::create_dir_all(kept_dir.join("src"))?;
+        let kept_file = kept_dir.join("src/main.js");
+        let cli_config = create_project_config(&kept_dir, ProjectType::Cli);
+        fs::write(&kept_file, "console.log('hi');\n")?;
+        let sink = Arc::new(RecordingSink::default());
+        progress::with_sink(sink.clone(), || create_project_structure(&cli_config))?;
+        assert_eq!(fs::read_to_string(&kept_file)?, "console.log('hi');\n");
+        let messages = sink.messages();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].text.contains("src/main.js has no shebang"));
+
+        fs::write(&kept_file, "#!/usr/bin/env node\nconsole.log('hi');\n")?;
+        let sink = Arc::new(RecordingSink::default());
+        progress::with_sink(sink.clone(), || create_project_structure(&cli_config))?;
+        assert!(sink.messages().is_empty());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_ne!(fs::metadata(&kept_file)?.permissions().mode() & 0o111, 0);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_template_files() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let root = temp_dir.path().join("app");
+        fs::create_dir_all(root.join("src"))?;
+        fs::write(root.join("src/keep.js"), "existing")?;
+
+        let file = |path: &str, executable: bool| crate::template::TemplateFile {
+            path: path.to_string(),
+            content: Some(format!("// {}", path)),
+            executable,
+            ..Default::default()
+        };
+        write_template_files(
+            &root,
+            &[file("src/keep.js", false), file("bin/run.sh", true)],
+        )?;
+
+        assert_eq!(fs::read_to_string(root.join("src/keep.js"))?, "existing");
+        assert_eq!(
+            fs::read_to_string(root.join("bin/run.sh"))?,
+            "// bin/run.sh"
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(root.join("bin/run.sh"))?.permissions().mode();
+            assert_eq!(mode & 0o111, 0o111);
+        }
+
         Ok(())
     }
 
@@ -422,4 +1395,77 @@ mod tests {
 
         assert!(extract_package_name(&path).is_err());
     }
+
+    #[test]
+    fn test_resolve_package_name() {
+        // An explicit name ignores the directory, even one without a name
+        let path = Path::new("/projects/@one/@two/app");
+        assert!(resolve_package_name(path, None).is_err());
+        assert_eq!(
+            resolve_package_name(path, Some("@acme/tools")).unwrap(),
+            "@acme/tools"
+        );
+        assert_eq!(
+            resolve_package_name(Path::new("/"), Some("app")).unwrap(),
+            "app"
+        );
+        assert!(matches!(
+            resolve_package_name(Path::new("/projects/app"), Some("@acme")),
+            Err(Error::InvalidPackageName { .. })
+        ));
+
+        assert_eq!(
+            resolve_package_name(Path::new("/projects/@acme/ui"), None).unwrap(),
+            "@acme/ui"
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_extract_package_name_windows() {
+        // Drive letters and extended-length prefixes are not part of the name
+        let name = extract_package_name(Path::new(r"C:\projects\my-app")).unwrap();
+        assert_eq!(name, "my-app");
+        let name = extract_package_name(Path::new(r"\\?\C:\projects\@acme\ui")).unwrap();
+        assert_eq!(name, "@acme/ui");
+    }
+
+    #[test]
+    fn test_describe_signal() {
+        assert_eq!(describe_signal(9), "signal 9 (SIGKILL)");
+        assert_eq!(describe_signal(15), "signal 15 (SIGTERM)");
+        assert_eq!(describe_signal(42), "signal 42");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exit_code() {
+        let status = |script: &str| Command::new("sh").args(["-c", script]).status().unwrap();
+
+        let exited = status("exit 3");
+        assert_eq!(termination_signal(&exited), None);
+        assert_eq!(exit_code(&exited), 3);
+
+        let killed = status("kill -15 $$");
+        assert_eq!(termination_signal(&killed), Some(15));
+        assert_eq!(exit_code(&killed), 143);
+    }
+
+    #[test]
+    fn test_state_dir() {
+        let project = Path::new("/work/app");
+        assert_eq!(state_dir_with(project, None), project.join(".cargonode"));
+        assert_eq!(
+            state_dir_with(project, Some("".into())),
+            project.join(".cargonode")
+        );
+        assert_eq!(
+            state_dir_with(project, Some("/var/cargonode".into())),
+            Path::new("/var/cargonode")
+        );
+        assert_eq!(
+            state_dir_with(project, Some("build/state".into())),
+            project.join("build/state")
+        );
+    }
 }
@@ -0,0 +1,8 @@
+import assert from "node:assert/strict";
+import { test } from "node:test";
+
+import { add } from "../src/lib.js";
+
+test("add", () => {
+    assert.strictEqual(add(2, 2), 4);
+});
@@ -0,0 +1,8 @@
+import assert from "node:assert/strict";
+import { test } from "node:test";
+
+import { greet } from "./main";
+
+test("greet", () => {
+    assert.strictEqual(greet("world"), "Hello, world!");
+});
@@ -0,0 +1,8 @@
+#!/usr/bin/env node
+export function greet(name: string): string {
+    return `Hello, ${name}!`;
+}
+
+if (require.main === module) {
+    console.log(greet("world"));
+}